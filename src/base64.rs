@@ -0,0 +1,108 @@
+//! Minimal base64 codec (RFC 4648, standard alphabet) used by the crate internals.
+
+use alloc::{string::String, vec::Vec};
+use core::fmt;
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Encodes `input` as padded base64.
+pub(crate) fn encode(input: impl AsRef<[u8]>) -> String {
+    let input = input.as_ref();
+    let mut output = String::with_capacity(input.len().div_ceil(3) * 4);
+    for chunk in input.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = (u32::from(b[0]) << 16) | (u32::from(b[1]) << 8) | u32::from(b[2]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                output.push(ALPHABET[(n >> (18 - 6 * i)) as usize & 0x3f] as char);
+            } else {
+                output.push('=');
+            }
+        }
+    }
+    output
+}
+
+/// Error returned when decoding malformed base64.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct DecodeError;
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid base64 data")
+    }
+}
+
+impl core::error::Error for DecodeError {}
+
+fn value(byte: u8) -> Result<u32, DecodeError> {
+    let value = match byte {
+        b'A'..=b'Z' => byte - b'A',
+        b'a'..=b'z' => byte - b'a' + 26,
+        b'0'..=b'9' => byte - b'0' + 52,
+        b'+' => 62,
+        b'/' => 63,
+        _ => return Err(DecodeError),
+    };
+    Ok(u32::from(value))
+}
+
+/// Decodes base64 with or without trailing padding.
+pub(crate) fn decode(input: impl AsRef<[u8]>) -> Result<Vec<u8>, DecodeError> {
+    let mut input = input.as_ref();
+    if input.len() % 4 == 0 {
+        input = input.strip_suffix(b"==").unwrap_or(input);
+        input = input.strip_suffix(b"=").unwrap_or(input);
+    }
+    if input.len() % 4 == 1 {
+        return Err(DecodeError);
+    }
+
+    let mut output = Vec::with_capacity(input.len() / 4 * 3 + 2);
+    for chunk in input.chunks(4) {
+        let mut n = 0;
+        for (i, byte) in chunk.iter().enumerate() {
+            n |= value(*byte)? << (18 - 6 * i);
+        }
+        let bytes = n.to_be_bytes();
+        output.extend_from_slice(&bytes[1..chunk.len()]);
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rfc4648_vectors() {
+        let vectors = [
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="),
+            ("fooba", "Zm9vYmE="),
+            ("foobar", "Zm9vYmFy"),
+        ];
+        for (plain, encoded) in vectors {
+            assert_eq!(encode(plain), encoded);
+            assert_eq!(decode(encoded).unwrap(), plain.as_bytes());
+            assert_eq!(
+                decode(encoded.trim_end_matches('=')).unwrap(),
+                plain.as_bytes()
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_input() {
+        assert_eq!(decode("Zm9v!"), Err(DecodeError));
+        assert_eq!(decode("Z"), Err(DecodeError));
+        assert_eq!(decode("Zm=v"), Err(DecodeError));
+    }
+}
@@ -0,0 +1,736 @@
+//! Batch responses carried as `multipart/mixed`.
+//!
+//! Batch APIs return several responses in a single `multipart/mixed` message, each part
+//! holding one serialized HTTP response (`application/http`). [`BatchResponseBuilder`]
+//! produces such a message and [`parse_batch`] splits it back into responses.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use futures_lite::StreamExt;
+//! use http_kit::batch::{parse_batch, BatchResponseBuilder};
+//! use http_kit::{Body, Response, StatusCode};
+//!
+//! # async fn example() -> Result<(), http_kit::batch::BatchError> {
+//! let mut created = Response::new(Body::from_bytes("{\"id\":1}"));
+//! *created.status_mut() = StatusCode::CREATED;
+//!
+//! let batch = BatchResponseBuilder::new()
+//!     .part(created)
+//!     .part(Response::new(Body::empty()))
+//!     .build()
+//!     .await?;
+//!
+//! // Part bodies are streamed, so each is read before the next part.
+//! let mut parts = core::pin::pin!(parse_batch(batch)?);
+//! let created = parts.next().await.unwrap()?;
+//! assert_eq!(created.status(), StatusCode::CREATED);
+//! assert_eq!(created.into_body().into_bytes().await?, "{\"id\":1}");
+//! assert_eq!(parts.next().await.unwrap()?.status(), StatusCode::OK);
+//! assert!(parts.next().await.is_none());
+//! # Ok(())
+//! # }
+//! ```
+
+use alloc::{boxed::Box, format, string::String, vec::Vec};
+use core::{
+    fmt,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use bytes::{Bytes, BytesMut};
+use futures_lite::{stream, Stream, StreamExt};
+use http::{header, HeaderName, HeaderValue, StatusCode};
+
+use crate::{Body, BodyError, HttpError, Response};
+
+const DEFAULT_MAX_PART_SIZE: usize = 1 << 20;
+const DEFAULT_MAX_HEAD_SIZE: usize = 64 << 10;
+const CRLF: &[u8] = b"\r\n";
+
+/// Error raised while building or parsing a batch.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum BatchError {
+    /// The batch is not `multipart/mixed` or lacks a boundary.
+    MissingBoundary,
+    /// A part is not a well-formed serialized response.
+    Malformed(&'static str),
+    /// A part body is larger than the configured maximum, or so is the head of a part
+    /// being parsed.
+    PartTooLarge {
+        /// The configured maximum in bytes.
+        max: usize,
+    },
+    /// The next part was requested before the body of the previous one was read to its
+    /// end or dropped.
+    PartInUse,
+    /// Reading a body failed.
+    Body(BodyError),
+}
+
+impl fmt::Display for BatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingBoundary => f.write_str("batch is not multipart/mixed with a boundary"),
+            Self::Malformed(reason) => write!(f, "malformed batch part: {reason}"),
+            Self::PartTooLarge { max } => write!(f, "batch part exceeds {max} bytes"),
+            Self::PartInUse => f.write_str("the body of the previous batch part is still in use"),
+            Self::Body(error) => write!(f, "failed to read batch body: {error}"),
+        }
+    }
+}
+
+impl core::error::Error for BatchError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            Self::Body(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl HttpError for BatchError {
+    fn status(&self) -> StatusCode {
+        match self {
+            // Parsed batches come from upstream services.
+            Self::MissingBoundary | Self::Malformed(_) => StatusCode::BAD_GATEWAY,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl From<BodyError> for BatchError {
+    fn from(error: BodyError) -> Self {
+        Self::Body(error)
+    }
+}
+
+/// Builder of a `multipart/mixed` response holding several responses.
+///
+/// Each response becomes an `application/http` part with its status line, headers and
+/// body. Part bodies are buffered, at most [`max_part_size`](Self::max_part_size) bytes
+/// each, so that every part carries a `Content-Length`.
+#[derive(Debug, Default)]
+pub struct BatchResponseBuilder {
+    parts: Vec<Response>,
+    max_part_size: Option<usize>,
+}
+
+impl BatchResponseBuilder {
+    /// Creates an empty builder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a response.
+    #[must_use]
+    pub fn part(mut self, response: Response) -> Self {
+        self.parts.push(response);
+        self
+    }
+
+    /// Appends several responses.
+    #[must_use]
+    pub fn parts(mut self, responses: impl IntoIterator<Item = Response>) -> Self {
+        self.parts.extend(responses);
+        self
+    }
+
+    /// Sets the maximum size of a single part body, 1 MiB by default.
+    #[must_use]
+    pub fn max_part_size(mut self, max_part_size: usize) -> Self {
+        self.max_part_size = Some(max_part_size);
+        self
+    }
+
+    /// Buffers the part bodies and serializes the batch into a `200 OK` response.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a part body cannot be read or exceeds the maximum size.
+    pub async fn build(self) -> Result<Response, BatchError> {
+        let max = self.max_part_size.unwrap_or(DEFAULT_MAX_PART_SIZE);
+        let mut parts = Vec::with_capacity(self.parts.len());
+        for response in self.parts {
+            let (head, mut body) = response.into_parts();
+            let mime = body.mime().cloned();
+            if body.len().is_some_and(|len| len > max) {
+                return Err(BatchError::PartTooLarge { max });
+            }
+            if body.peek(max.saturating_add(1)).await?.len() > max {
+                return Err(BatchError::PartTooLarge { max });
+            }
+            let data = body.into_bytes().await?;
+            parts.push((head, mime, data));
+        }
+
+        let boundary = loop {
+            let boundary = next_boundary();
+            let delimiter = format!("--{boundary}");
+            if !parts
+                .iter()
+                .any(|(_, _, data)| contains(data, delimiter.as_bytes()))
+            {
+                break boundary;
+            }
+        };
+
+        let mut out = BytesMut::new();
+        for (head, mime, data) in parts {
+            out.extend_from_slice(format!("--{boundary}\r\n").as_bytes());
+            out.extend_from_slice(b"content-type: application/http\r\n");
+            out.extend_from_slice(b"content-transfer-encoding: binary\r\n\r\n");
+            out.extend_from_slice(
+                format!(
+                    "HTTP/1.1 {} {}\r\n",
+                    head.status.as_u16(),
+                    head.status.canonical_reason().unwrap_or("")
+                )
+                .as_bytes(),
+            );
+            let mut headers = head.headers;
+            if let Some(mime) = mime.filter(|_| !headers.contains_key(header::CONTENT_TYPE)) {
+                if let Ok(value) = HeaderValue::from_str(mime.as_ref()) {
+                    headers.insert(header::CONTENT_TYPE, value);
+                }
+            }
+            headers.insert(header::CONTENT_LENGTH, data.len().into());
+            for (name, value) in &headers {
+                out.extend_from_slice(name.as_str().as_bytes());
+                out.extend_from_slice(b": ");
+                out.extend_from_slice(value.as_bytes());
+                out.extend_from_slice(CRLF);
+            }
+            out.extend_from_slice(CRLF);
+            out.extend_from_slice(&data);
+            out.extend_from_slice(CRLF);
+        }
+        out.extend_from_slice(format!("--{boundary}--\r\n").as_bytes());
+
+        let mut response = Response::new(Body::from_bytes(out.freeze()));
+        response.headers_mut().insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_str(&format!("multipart/mixed; boundary={boundary}"))
+                .expect("boundaries are valid header values"),
+        );
+        Ok(response)
+    }
+}
+
+// Boundaries only need to be absent from the parts, which `build` checks, so a counter
+// scrambled with a multiplicative hash is enough.
+fn next_boundary() -> String {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let n = COUNTER.fetch_add(1, Ordering::Relaxed) as u64;
+    let scrambled = (n ^ 0x5DEE_CE66_D1CE_4E5B).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    format!("batch_{scrambled:016x}")
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    find(haystack, needle).is_some()
+}
+
+fn boundary_of(response: &Response) -> Option<String> {
+    let content_type: mime::Mime = response
+        .headers()
+        .get(header::CONTENT_TYPE)?
+        .to_str()
+        .ok()?
+        .parse()
+        .ok()?;
+    if content_type.essence_str() != "multipart/mixed" {
+        return None;
+    }
+    content_type
+        .get_param(mime::BOUNDARY)
+        .map(|boundary| String::from(boundary.as_str()))
+}
+
+fn parse_head(head: &[u8]) -> Result<(Response, Option<usize>), BatchError> {
+    let malformed = BatchError::Malformed;
+    let head = core::str::from_utf8(head).map_err(|_| malformed("response head is not UTF-8"))?;
+    let mut lines = head.split("\r\n");
+    let status = lines
+        .next()
+        .filter(|line| line.starts_with("HTTP/"))
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|code| StatusCode::from_bytes(code.as_bytes()).ok())
+        .ok_or(malformed("invalid status line"))?;
+
+    let mut response = Response::new(Body::empty());
+    *response.status_mut() = status;
+    for line in lines {
+        let (name, value) = line.split_once(':').ok_or(malformed("invalid header"))?;
+        let name = HeaderName::from_bytes(name.trim().as_bytes())
+            .map_err(|_| malformed("invalid header"))?;
+        let value = HeaderValue::from_str(value.trim()).map_err(|_| malformed("invalid header"))?;
+        response.headers_mut().append(name, value);
+    }
+
+    let length = match response.headers().get(header::CONTENT_LENGTH) {
+        Some(length) => Some(
+            length
+                .to_str()
+                .ok()
+                .and_then(|length| length.parse().ok())
+                .ok_or(malformed("invalid content-length"))?,
+        ),
+        None => None,
+    };
+    Ok((response, length))
+}
+
+enum Stage {
+    // Discarding data up to the next delimiter: the preamble, or what follows a part
+    // body sized by its `Content-Length`.
+    Skip,
+    // At a delimiter whose line is not consumed yet.
+    Delimiter,
+    // At the head of a part.
+    Head,
+    // In a part body, with the number of bytes left when the part has a length.
+    Body(Option<usize>),
+    Done,
+}
+
+struct Parser {
+    body: Body,
+    // Data received and not consumed yet. It starts with a CRLF, so that the first
+    // delimiter is found like the others.
+    buffer: BytesMut,
+    // CRLF, `--` and the boundary.
+    delimiter: Vec<u8>,
+    // No delimiter starts before this offset of the buffer, so searches resume there.
+    searched: usize,
+    max_head_size: usize,
+    stage: Stage,
+}
+
+impl Parser {
+    // Reads another chunk, failing if the batch ends before its closing delimiter.
+    async fn fill(&mut self) -> Result<(), BatchError> {
+        match self.body.next().await {
+            Some(chunk) => {
+                self.buffer.extend_from_slice(&chunk?);
+                Ok(())
+            }
+            None => Err(BatchError::Malformed(
+                "batch ended before its closing delimiter",
+            )),
+        }
+    }
+
+    fn advance(&mut self, n: usize) -> BytesMut {
+        self.searched = self.searched.saturating_sub(n);
+        self.buffer.split_to(n)
+    }
+
+    fn find_delimiter(&mut self) -> Option<usize> {
+        let found = find(&self.buffer[self.searched..], &self.delimiter);
+        match found {
+            Some(at) => Some(self.searched + at),
+            None => {
+                self.searched = (self.buffer.len() + 1).saturating_sub(self.delimiter.len());
+                None
+            }
+        }
+    }
+
+    // Finds `needle` in the head at the start of the buffer, failing once the head is
+    // longer than allowed.
+    async fn find_in_head(&mut self, needle: &[u8]) -> Result<usize, BatchError> {
+        let mut searched = 0;
+        loop {
+            if let Some(at) = find(&self.buffer[searched..], needle) {
+                return Ok(searched + at);
+            }
+            if self.buffer.len() > self.max_head_size {
+                return Err(BatchError::PartTooLarge {
+                    max: self.max_head_size,
+                });
+            }
+            searched = (self.buffer.len() + 1).saturating_sub(needle.len());
+            self.fill().await?;
+        }
+    }
+
+    async fn skip(&mut self) -> Result<(), BatchError> {
+        loop {
+            if let Some(at) = self.find_delimiter() {
+                self.advance(at);
+                self.stage = Stage::Delimiter;
+                return Ok(());
+            }
+            self.advance(self.searched);
+            self.fill().await?;
+        }
+    }
+
+    // Consumes the delimiter line at the start of the buffer.
+    async fn finish_delimiter(&mut self) -> Result<(), BatchError> {
+        let after = self.delimiter.len();
+        while self.buffer.len() < after + 2 {
+            self.fill().await?;
+        }
+        if &self.buffer[after..after + 2] == b"--" {
+            self.stage = Stage::Done;
+            return Ok(());
+        }
+        self.advance(after);
+        // The CRLF ending the line is kept, so that part headers, even none, end with
+        // a blank line.
+        let end = self.find_in_head(CRLF).await?;
+        self.advance(end);
+        self.stage = Stage::Head;
+        Ok(())
+    }
+
+    async fn read_head(&mut self) -> Result<Response, BatchError> {
+        let malformed = BatchError::Malformed;
+        // The MIME headers of the part (`Content-Type: application/http`) are not needed.
+        let split = self
+            .find_in_head(b"\r\n\r\n")
+            .await
+            .map_err(|error| match error {
+                BatchError::Malformed(_) => malformed("missing part headers"),
+                error => error,
+            })?;
+        self.advance(split + 4);
+        let end = self
+            .find_in_head(b"\r\n\r\n")
+            .await
+            .map_err(|error| match error {
+                BatchError::Malformed(_) => malformed("missing response head"),
+                error => error,
+            })?;
+        let head = self.advance(end + 4);
+        let (response, length) = parse_head(&head[..end])?;
+        self.stage = Stage::Body(length);
+        Ok(response)
+    }
+
+    // Reads the next chunk of the current part body. Data that may start a delimiter is
+    // held back until the next read tells.
+    async fn body_chunk(&mut self) -> Result<Option<Bytes>, BatchError> {
+        loop {
+            let Stage::Body(left) = self.stage else {
+                return Ok(None);
+            };
+            let found = self.find_delimiter();
+            if found.zip(left).is_some_and(|(at, left)| at < left) {
+                return Err(BatchError::Malformed("truncated part body"));
+            }
+            let end = found.unwrap_or(self.searched);
+            let n = left.map_or(end, |left| left.min(end));
+            if n > 0 {
+                self.stage = Stage::Body(left.map(|left| left - n));
+                return Ok(Some(self.advance(n).freeze()));
+            }
+            if found.is_some() || left == Some(0) {
+                self.stage = Stage::Skip;
+                return Ok(None);
+            }
+            self.fill().await?;
+        }
+    }
+
+    async fn next_part(&mut self) -> Result<Option<Response>, BatchError> {
+        loop {
+            match self.stage {
+                Stage::Done => return Ok(None),
+                // The body of the previous part was dropped before its end.
+                Stage::Body(_) => while self.body_chunk().await?.is_some() {},
+                Stage::Skip => self.skip().await?,
+                Stage::Delimiter => self.finish_delimiter().await?,
+                Stage::Head => return self.read_head().await.map(Some),
+            }
+        }
+    }
+}
+
+// Parser lent to the body of a part. It goes back to the stream of parts once the body
+// ends or is dropped.
+struct Lease {
+    parser: Option<Parser>,
+    home: async_channel::Sender<Parser>,
+}
+
+impl Drop for Lease {
+    fn drop(&mut self) {
+        if let Some(parser) = self.parser.take() {
+            // The channel has room for the only parser ever sent on it.
+            let _ = self.home.try_send(parser);
+        }
+    }
+}
+
+fn body_error(error: BatchError) -> BodyError {
+    match error {
+        BatchError::Body(error) => error,
+        error => BodyError::Other(Box::new(error)),
+    }
+}
+
+fn part_body(lease: Lease) -> Body {
+    Body::from_stream_unsync(stream::unfold(lease, |mut lease| async move {
+        let parser = lease.parser.as_mut()?;
+        match parser.body_chunk().await {
+            Ok(Some(chunk)) => Some((Ok(chunk), lease)),
+            Ok(None) => None,
+            Err(error) => {
+                parser.stage = Stage::Done;
+                Some((Err(body_error(error)), lease))
+            }
+        }
+    }))
+}
+
+/// Splits a `multipart/mixed` batch response into its responses.
+///
+/// Parts are yielded as soon as their head has been received, and their bodies are
+/// streamed from the batch, so neither the batch nor a part is buffered as a whole. The
+/// body of a part must therefore be read to its end or dropped before the next part is
+/// requested, or the stream yields [`BatchError::PartInUse`]. Part bodies honor their
+/// `Content-Length` when present.
+///
+/// The head of each part may hold up to 64 KiB; see [`parse_batch_with_max_head_size`].
+///
+/// # Errors
+///
+/// Returns [`BatchError::MissingBoundary`] if the response is not `multipart/mixed`. The
+/// stream yields an error and ends when a part is malformed or the body fails.
+pub fn parse_batch(
+    response: Response,
+) -> Result<impl Stream<Item = Result<Response, BatchError>> + Send, BatchError> {
+    parse_batch_with_max_head_size(response, DEFAULT_MAX_HEAD_SIZE)
+}
+
+/// Splits a batch like [`parse_batch`], allowing up to `max_head_size` bytes for the
+/// head of each part, its MIME headers and response head together.
+///
+/// # Errors
+///
+/// Like [`parse_batch`], and the stream yields [`BatchError::PartTooLarge`] when the
+/// head of a part is longer.
+pub fn parse_batch_with_max_head_size(
+    mut response: Response,
+    max_head_size: usize,
+) -> Result<impl Stream<Item = Result<Response, BatchError>> + Send, BatchError> {
+    let boundary = boundary_of(&response).ok_or(BatchError::MissingBoundary)?;
+    let body = response
+        .body_mut()
+        .take()
+        .map_err(|error| BatchError::Body(error.into()))?;
+    let parser = Parser {
+        body,
+        buffer: BytesMut::from(CRLF),
+        delimiter: format!("\r\n--{boundary}").into_bytes(),
+        searched: 0,
+        max_head_size,
+        stage: Stage::Skip,
+    };
+    // The stream of parts gets the parser back from the body of each part, starting
+    // with a first handoff of its own.
+    let (home, returned) = async_channel::bounded(1);
+    let _ = home.try_send(parser);
+    Ok(stream::unfold(Some(returned), |returned| async move {
+        let returned = returned?;
+        let mut parser = match returned.try_recv() {
+            Ok(parser) => parser,
+            Err(async_channel::TryRecvError::Empty) => {
+                return Some((Err(BatchError::PartInUse), Some(returned)));
+            }
+            Err(_) => return None,
+        };
+        match parser.next_part().await {
+            Ok(Some(mut response)) => {
+                let (home, returned) = async_channel::bounded(1);
+                *response.body_mut() = part_body(Lease {
+                    parser: Some(parser),
+                    home,
+                });
+                Some((Ok(response), Some(returned)))
+            }
+            Ok(None) => None,
+            Err(error) => Some((Err(error), None)),
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    use core::convert::Infallible;
+
+    fn responses() -> Vec<Response> {
+        let mut json =
+            Response::new(Body::from_bytes("{\"id\":7}").with_mime(mime::APPLICATION_JSON));
+        *json.status_mut() = StatusCode::CREATED;
+        json.headers_mut()
+            .insert(header::LOCATION, HeaderValue::from_static("/items/7"));
+
+        let mut empty = Response::new(Body::empty());
+        *empty.status_mut() = StatusCode::NO_CONTENT;
+
+        // Streaming binary body that contains CRLFs and dashes.
+        let chunks = vec![
+            Ok::<_, Infallible>(Bytes::from_static(b"\x00\x01\r\n--")),
+            Ok(Bytes::from_static(b"\xFF\xFE\r\n\r\n")),
+        ];
+        let mut binary = Response::new(Body::from_stream(stream::iter(chunks)));
+        binary.headers_mut().insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/octet-stream"),
+        );
+        vec![json, empty, binary]
+    }
+
+    #[tokio::test]
+    async fn round_trips_heterogeneous_responses() {
+        let batch = BatchResponseBuilder::new()
+            .parts(responses())
+            .build()
+            .await
+            .unwrap();
+        assert!(batch.headers()[header::CONTENT_TYPE]
+            .to_str()
+            .unwrap()
+            .starts_with("multipart/mixed; boundary="));
+
+        // Deliver the batch in small chunks to exercise incremental parsing.
+        let (head, body) = batch.into_parts();
+        let data = body.into_bytes().await.unwrap();
+        let chunks: Vec<_> = data
+            .chunks(7)
+            .map(|chunk| Ok::<_, Infallible>(Bytes::copy_from_slice(chunk)))
+            .collect();
+        let batch = Response::from_parts(head, Body::from_stream(stream::iter(chunks)));
+
+        let mut parts = parse_batch(batch).unwrap().boxed();
+
+        let json = parts.next().await.unwrap().unwrap();
+        assert_eq!(json.status(), StatusCode::CREATED);
+        assert_eq!(json.headers()[header::LOCATION], "/items/7");
+        assert_eq!(json.headers()[header::CONTENT_TYPE], "application/json");
+        assert_eq!(json.into_body().into_bytes().await.unwrap(), "{\"id\":7}");
+
+        let empty = parts.next().await.unwrap().unwrap();
+        assert_eq!(empty.status(), StatusCode::NO_CONTENT);
+        assert!(empty.into_body().into_bytes().await.unwrap().is_empty());
+
+        let binary = parts.next().await.unwrap().unwrap();
+        assert_eq!(binary.status(), StatusCode::OK);
+        assert_eq!(binary.headers()[header::CONTENT_LENGTH], "12");
+        assert_eq!(
+            binary.into_body().into_bytes().await.unwrap(),
+            &b"\x00\x01\r\n--\xFF\xFE\r\n\r\n"[..]
+        );
+        assert!(parts.next().await.is_none());
+    }
+
+    fn batch(chunks: &[&'static str]) -> Response {
+        let chunks: Vec<_> = chunks
+            .iter()
+            .map(|chunk| Ok::<_, Infallible>(*chunk))
+            .collect();
+        let mut batch = Response::new(Body::from_stream(stream::iter(chunks)));
+        batch.headers_mut().insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("multipart/mixed; boundary=b"),
+        );
+        batch
+    }
+
+    #[tokio::test]
+    async fn part_bodies_are_streamed() {
+        let head =
+            "preamble\r\n--b\r\ncontent-type: application/http\r\n\r\nHTTP/1.1 200 OK\r\n\r\n";
+        let mut parts = parse_batch(batch(&[
+            head,
+            "first chunk, ",
+            "second chunk\r\n--b\r\n\r\nHTTP/1.1 204 No Content\r\n\r\n\r\n--b--\r\n",
+        ]))
+        .unwrap()
+        .boxed();
+
+        let first = parts.next().await.unwrap().unwrap();
+        let mut body = first.into_body();
+        // Data that may start a delimiter is held back until the next chunk arrives.
+        let start = body.next().await.unwrap().unwrap();
+        assert_eq!(start, "first chu");
+        // The next part waits for the body of this one.
+        assert!(matches!(
+            parts.next().await,
+            Some(Err(BatchError::PartInUse))
+        ));
+        let rest = body.into_bytes().await.unwrap();
+        assert_eq!([start, rest].concat(), b"first chunk, second chunk");
+
+        let second = parts.next().await.unwrap().unwrap();
+        assert_eq!(second.status(), StatusCode::NO_CONTENT);
+        // Dropping a body unread skips the rest of its part.
+        drop(second);
+        assert!(parts.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn long_part_heads_are_rejected() {
+        let padding = "x-padding: 0123456789\r\n";
+        let parts: Vec<_> = parse_batch_with_max_head_size(
+            batch(&["--b\r\n\r\nHTTP/1.1 200 OK\r\n", padding, padding, padding]),
+            40,
+        )
+        .unwrap()
+        .collect()
+        .await;
+        assert_eq!(parts.len(), 1);
+        assert!(matches!(
+            parts[0],
+            Err(BatchError::PartTooLarge { max: 40 })
+        ));
+    }
+
+    #[tokio::test]
+    async fn oversized_parts_are_rejected() {
+        let error = BatchResponseBuilder::new()
+            .parts(responses())
+            .max_part_size(4)
+            .build()
+            .await
+            .unwrap_err();
+        assert!(matches!(error, BatchError::PartTooLarge { max: 4 }));
+    }
+
+    #[tokio::test]
+    async fn malformed_batches_fail() {
+        let response = Response::new(Body::from_bytes("not a batch"));
+        assert!(matches!(
+            parse_batch(response).err(),
+            Some(BatchError::MissingBoundary)
+        ));
+
+        let mut truncated = Response::new(Body::from_bytes(
+            "--b\r\ncontent-type: application/http\r\n\r\nHTTP/1.1 200 OK\r\n\r\nhi",
+        ));
+        truncated.headers_mut().insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("multipart/mixed; boundary=b"),
+        );
+        let mut parts = parse_batch(truncated).unwrap().boxed();
+        let part = parts.next().await.unwrap().unwrap();
+        let error = part.into_body().into_bytes().await.unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<BatchError>(),
+            Some(BatchError::Malformed(_))
+        ));
+        assert!(parts.next().await.is_none());
+    }
+}
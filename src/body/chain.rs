@@ -0,0 +1,177 @@
+use alloc::collections::VecDeque;
+use core::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use bytes::Bytes;
+use futures_lite::ready;
+use http::HeaderMap;
+use http_body::{Frame, SizeHint};
+
+use super::{Body, Error};
+
+// Body sending the data of each of `bodies` in turn. Trailers sent along the way are
+// merged, later bodies taking precedence, and sent once the last body ends.
+pub(super) struct Chain {
+    bodies: VecDeque<Body>,
+    trailers: Option<HeaderMap>,
+}
+
+impl Chain {
+    pub(super) fn new(bodies: VecDeque<Body>) -> Self {
+        Self {
+            bodies,
+            trailers: None,
+        }
+    }
+}
+
+impl http_body::Body for Chain {
+    type Data = Bytes;
+    type Error = Error;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let this = &mut *self;
+        // Errors are passed on without moving to the next body, so a frozen body keeps
+        // failing like it would on its own.
+        while let Some(body) = this.bodies.front_mut() {
+            match ready!(Pin::new(body).poll_nested(cx)) {
+                Some(Ok(frame)) => match frame.into_trailers() {
+                    Ok(trailers) => this
+                        .trailers
+                        .get_or_insert_with(HeaderMap::new)
+                        .extend(trailers),
+                    Err(frame) => return Poll::Ready(Some(Ok(frame))),
+                },
+                Some(Err(error)) => return Poll::Ready(Some(Err(error))),
+                None => {
+                    this.bodies.pop_front();
+                }
+            }
+        }
+        Poll::Ready(
+            this.trailers
+                .take()
+                .map(|trailers| Ok(Frame::trailers(trailers))),
+        )
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.trailers.is_none() && self.bodies.iter().all(http_body::Body::is_end_stream)
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        let mut lower = 0u64;
+        let mut upper = Some(0u64);
+        for body in &self.bodies {
+            let hint = http_body::Body::size_hint(body);
+            lower = lower.saturating_add(hint.lower());
+            upper = upper
+                .zip(hint.upper())
+                .and_then(|(upper, more)| upper.checked_add(more));
+        }
+        let mut hint = SizeHint::new();
+        hint.set_lower(lower);
+        if let Some(upper) = upper {
+            hint.set_upper(upper);
+        }
+        hint
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::BodyInner;
+    use super::*;
+    use alloc::vec::Vec;
+    use futures_lite::{io::Cursor, stream, StreamExt};
+    use http_body_util::BodyExt;
+
+    fn streamed(chunks: &[&'static str]) -> Body {
+        let chunks: Vec<_> = chunks.iter().map(|chunk| Ok::<_, Error>(*chunk)).collect();
+        Body::from_stream(stream::iter(chunks))
+    }
+
+    async fn chunks(body: Body) -> Vec<Bytes> {
+        body.into_data_stream().map(Result::unwrap).collect().await
+    }
+
+    #[tokio::test]
+    async fn chunks_follow_the_order_of_the_bodies() {
+        let reader = Body::from_reader(Cursor::new(b"reader".to_vec()), 6);
+        let body = Body::from_bytes("once ")
+            .chain(streamed(&["first ", "second "]))
+            .chain(reader);
+        assert_eq!(body.len(), None);
+        assert_eq!(chunks(body).await, ["once ", "first ", "second ", "reader"]);
+
+        let body = Body::concat([
+            streamed(&["a", "b"]),
+            Body::empty(),
+            Body::from_text("c"),
+            Body::from_reader(Cursor::new(b"d".to_vec()), None),
+        ]);
+        assert_eq!(body.len(), None);
+        assert_eq!(chunks(body).await, ["a", "b", "c", "d"]);
+    }
+
+    #[tokio::test]
+    async fn lengths_add_up_when_all_are_known() {
+        let joined = Body::from_bytes("Hello, ").chain(Body::from_text("world!"));
+        assert!(matches!(joined.inner, BodyInner::Once(_)));
+        assert_eq!(joined.len(), Some(13));
+        assert_eq!(joined.into_bytes().await.unwrap(), "Hello, world!");
+
+        let reader = Body::from_reader(Cursor::new(b"reader".to_vec()), 6);
+        let mut body = Body::from_bytes("once ").chain(reader);
+        assert_eq!(body.len(), Some(11));
+        assert_eq!(http_body::Body::size_hint(&body).exact(), Some(11));
+        // Both the length and the size hint count what is left to read.
+        assert_eq!(body.next().await.unwrap().unwrap(), "once ");
+        assert_eq!(body.len(), Some(6));
+        assert_eq!(http_body::Body::size_hint(&body).exact(), Some(6));
+        let frame = body.frame().await.unwrap().unwrap();
+        assert_eq!(frame.into_data().unwrap(), "reader");
+        assert_eq!(body.len(), Some(0));
+        assert!(body.frame().await.is_none());
+
+        let body = Body::from_bytes("once ").chain(streamed(&["stream"]));
+        assert_eq!(body.len(), None);
+        assert_eq!(Body::concat([]).len(), Some(0));
+    }
+
+    #[tokio::test]
+    async fn keeps_the_first_mime_type_and_merges_trailers() {
+        let mut first = HeaderMap::new();
+        first.insert("checksum", "first".parse().unwrap());
+        first.insert("first", "1".parse().unwrap());
+        let mut second = HeaderMap::new();
+        second.insert("checksum", "second".parse().unwrap());
+
+        let body = Body::from_text("text").with_trailers(first).chain(
+            Body::from_bytes("[1]")
+                .with_mime(mime::APPLICATION_JSON)
+                .with_trailers(second),
+        );
+        assert_eq!(body.mime(), Some(&mime::TEXT_PLAIN_UTF_8));
+        let (data, trailers) = body.into_bytes_with_trailers().await.unwrap();
+        assert_eq!(data, "text[1]");
+        let trailers = trailers.unwrap();
+        assert_eq!(trailers["checksum"], "second");
+        assert_eq!(trailers["first"], "1");
+    }
+
+    #[tokio::test]
+    async fn frozen_bodies_fail_when_reached() {
+        let mut body = Body::from_bytes("before").chain(Body::frozen());
+        assert_eq!(body.next().await.unwrap().unwrap(), "before");
+        assert!(matches!(body.next().await, Some(Err(Error::BodyFrozen))));
+
+        let mut body = Body::frozen().chain(Body::from_bytes("after"));
+        assert!(matches!(body.frame().await, Some(Err(Error::BodyFrozen))));
+    }
+}
@@ -0,0 +1,150 @@
+use alloc::borrow::Cow;
+use bytes::Bytes;
+use bytestr::ByteStr;
+use encoding_rs::{Encoding, REPLACEMENT, UTF_8};
+
+use super::{Body, Error};
+
+// Looks up a charset label as browsers do. The `replacement` encoding, which labels of
+// unsafe charsets map to, decodes nothing and counts as unknown.
+fn lookup(label: &str) -> Option<&'static Encoding> {
+    Encoding::for_label(label.trim().as_bytes()).filter(|&encoding| encoding != REPLACEMENT)
+}
+
+// Decodes `bytes` in `encoding`, or in the encoding of their byte order mark, which
+// takes precedence and is stripped.
+fn decode(bytes: Bytes, encoding: &'static Encoding) -> Result<ByteStr, Error> {
+    let (encoding, bom) = Encoding::for_bom(&bytes).unwrap_or((encoding, 0));
+    let bytes = bytes.slice(bom..);
+    if encoding == UTF_8 {
+        return Ok(ByteStr::from_utf8(bytes)?);
+    }
+    let decoded = encoding
+        .decode_without_bom_handling_and_without_replacement(&bytes)
+        .ok_or(Error::MalformedText {
+            charset: encoding.name(),
+        })?;
+    match decoded {
+        Cow::Owned(text) => Ok(ByteStr::from(text)),
+        // The bytes read the same in UTF-8, so they are kept as they are.
+        Cow::Borrowed(_) => Ok(ByteStr::from_utf8(bytes)?),
+    }
+}
+
+// Decodes `bytes` in the charset `label`, replacing malformed sequences. Unknown or
+// missing labels fall back to UTF-8.
+pub(crate) fn decode_lossy(bytes: Bytes, label: Option<&str>) -> ByteStr {
+    let encoding = label.and_then(lookup).unwrap_or(UTF_8);
+    let (encoding, bom) = Encoding::for_bom(&bytes).unwrap_or((encoding, 0));
+    let (text, _) = encoding.decode_without_bom_handling(&bytes[bom..]);
+    ByteStr::from(text.into_owned())
+}
+
+impl Body {
+    /// Consumes the body and decodes it as text in `charset`, such as `iso-8859-1` or
+    /// `windows-1252`.
+    ///
+    /// Labels are matched like browsers do, so `latin1` and `ISO-8859-1` are the same
+    /// charset, and `iso-8859-1` is decoded as `windows-1252`, its superset. A byte order
+    /// mark at the start of the body takes precedence over `charset` and is stripped.
+    /// UTF-8 bodies are not copied.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BodyError::UnknownCharset`](crate::BodyError::UnknownCharset) if
+    /// `charset` is not a known label, before reading the body.
+    /// [`BodyError::MalformedText`](crate::BodyError::MalformedText) or
+    /// [`BodyError::Utf8`](crate::BodyError::Utf8) is returned if the bytes are not
+    /// valid in the charset, and the errors of [`Body::into_bytes`] are forwarded.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use http_kit::Body;
+    ///
+    /// # async fn example() -> Result<(), http_kit::BodyError> {
+    /// let body = Body::from_bytes(&b"caf\xe9"[..]);
+    /// assert_eq!(body.into_string_with_charset("latin1").await?, "caf\u{e9}");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn into_string_with_charset(self, charset: &str) -> Result<ByteStr, Error> {
+        let encoding = lookup(charset).ok_or_else(|| Error::UnknownCharset(charset.into()))?;
+        decode(self.into_bytes().await?, encoding)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::{string::ToString, vec::Vec};
+
+    #[tokio::test]
+    async fn legacy_charsets() {
+        let latin1 = Body::from_bytes(&b"na\xefve \xa3"[..]);
+        let text = latin1.into_string_with_charset("ISO-8859-1").await.unwrap();
+        assert_eq!(text, "na\u{ef}ve \u{a3}");
+
+        let windows = Body::from_bytes(&b"\x93quoted\x94"[..]);
+        let text = windows
+            .into_string_with_charset("windows-1252")
+            .await
+            .unwrap();
+        assert_eq!(text, "\u{201c}quoted\u{201d}");
+
+        let sjis = Body::from_bytes(&b"\x82\xa0"[..]);
+        let text = sjis.into_string_with_charset("shift_jis").await.unwrap();
+        assert_eq!(text, "\u{3042}");
+        let broken = Body::from_bytes(&b"\x82"[..]);
+        let error = broken
+            .into_string_with_charset("shift_jis")
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            Error::MalformedText {
+                charset: "Shift_JIS"
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn byte_order_marks_are_stripped() {
+        let body = Body::from_bytes(&b"\xef\xbb\xbfhello"[..]);
+        assert_eq!(
+            body.into_string_with_charset("utf-8").await.unwrap(),
+            "hello"
+        );
+
+        // The mark wins over the label.
+        let body = Body::from_bytes(&b"\xef\xbb\xbfcaf\xc3\xa9"[..]);
+        let text = body.into_string_with_charset("latin1").await.unwrap();
+        assert_eq!(text, "caf\u{e9}");
+
+        let utf16: Vec<u8> = [0xff, 0xfe, b'h', 0, b'i', 0].into();
+        let body = Body::from_bytes(utf16);
+        assert_eq!(body.into_string_with_charset("utf-8").await.unwrap(), "hi");
+    }
+
+    #[tokio::test]
+    async fn unknown_charsets() {
+        for label in ["klingon", "iso-2022-kr", ""] {
+            let error = Body::from_bytes("text")
+                .into_string_with_charset(label)
+                .await
+                .unwrap_err();
+            assert!(matches!(&error, Error::UnknownCharset(found) if found == label));
+            assert_eq!(
+                crate::HttpError::status(&error),
+                http::StatusCode::UNSUPPORTED_MEDIA_TYPE
+            );
+        }
+        assert_eq!(
+            Error::UnknownCharset("klingon".into()).to_string(),
+            "unknown charset `klingon`"
+        );
+
+        let text = decode_lossy(Bytes::from_static(b"ok \xff"), Some("klingon"));
+        assert_eq!(text, "ok \u{fffd}");
+    }
+}
@@ -1,10 +1,11 @@
-use alloc::{borrow::Cow, boxed::Box, string::String, vec::Vec};
-use bytes::Bytes;
+use alloc::{borrow::Cow, boxed::Box, collections::VecDeque, string::String, sync::Arc, vec::Vec};
+use bytes::{Bytes, BytesMut};
 use bytestr::ByteStr;
 use core::pin::Pin;
 use futures_lite::AsyncBufRead;
+use http_body_util::Collected;
 
-use super::{Body, BodyInner};
+use super::{Body, BodyInner, DEFAULT_READER_CAPACITY};
 
 macro_rules! from_bytes {
     ($($ty:ty),*) => {
@@ -30,6 +31,8 @@ macro_rules! from_str {
     };
 }
 
+// Text is typed `text/plain; charset=utf-8` and bytes `application/octet-stream`, which
+// `IntoResponse` and `Response::try_new` copy to the `Content-Type` header.
 from_str!(ByteStr, String, Box<str>, &str, Cow<'_, str>);
 
 from_bytes!(Bytes, Vec<u8>, Box<[u8]>);
@@ -46,6 +49,53 @@ impl From<&[u8]> for Body {
     }
 }
 
+/// Zero-copy: the body keeps the `Arc` alive instead of copying the text.
+impl From<Arc<str>> for Body {
+    fn from(data: Arc<str>) -> Self {
+        struct ArcStr(Arc<str>);
+
+        impl AsRef<[u8]> for ArcStr {
+            fn as_ref(&self) -> &[u8] {
+                self.0.as_bytes()
+            }
+        }
+
+        Self {
+            mime: Some(mime::TEXT_PLAIN_UTF_8),
+            inner: BodyInner::Once(Bytes::from_owner(ArcStr(data))),
+            trailers: None,
+        }
+    }
+}
+
+/// Zero-copy: the body keeps the `Arc` alive instead of copying the data.
+impl From<Arc<[u8]>> for Body {
+    fn from(data: Arc<[u8]>) -> Self {
+        Body::from_bytes(Bytes::from_owner(data))
+    }
+}
+
+/// Reuses the buffer of the deque, moving its elements in place if they wrap around.
+impl From<VecDeque<u8>> for Body {
+    fn from(data: VecDeque<u8>) -> Self {
+        Body::from_bytes(Vec::from(data))
+    }
+}
+
+impl FromIterator<u8> for Body {
+    fn from_iter<I: IntoIterator<Item = u8>>(iter: I) -> Self {
+        Body::from_bytes(iter.into_iter().collect::<Vec<u8>>())
+    }
+}
+
+/// Serializes the value, with the MIME type set to `application/json`.
+#[cfg(feature = "json")]
+impl From<serde_json::Value> for Body {
+    fn from(value: serde_json::Value) -> Self {
+        Self::from_json_value(&value)
+    }
+}
+
 impl From<Box<dyn AsyncBufRead + Send + Sync + 'static>> for Body {
     fn from(reader: Box<dyn AsyncBufRead + Send + Sync + 'static>) -> Self {
         Pin::from(reader).into()
@@ -59,7 +109,136 @@ impl From<Pin<Box<dyn AsyncBufRead + Send + Sync + 'static>>> for Body {
             inner: BodyInner::Reader {
                 reader,
                 length: None,
+                buf: BytesMut::new(),
+                capacity: DEFAULT_READER_CAPACITY,
             },
+            trailers: None,
+        }
+    }
+}
+
+// The trailers are sent again after the data, and `Collected` of other data types can
+// be converted with `to_bytes` first.
+impl From<Collected<Bytes>> for Body {
+    fn from(collected: Collected<Bytes>) -> Self {
+        let trailers = collected.trailers().cloned();
+        let body = Body::from_bytes(collected.to_bytes());
+        match trailers {
+            Some(trailers) => body.with_trailers(trailers),
+            None => body,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Buffers the body, so its address can be compared with the source data.
+    async fn data(body: Body) -> Bytes {
+        body.into_bytes().await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn shared_buffers_are_not_copied() {
+        let text: Arc<str> = Arc::from("shared text");
+        let body = Body::from(text.clone());
+        assert_eq!(body.mime(), Some(&mime::TEXT_PLAIN_UTF_8));
+        let bytes = data(body).await;
+        assert_eq!(bytes, "shared text");
+        assert_eq!(bytes.as_ptr(), text.as_ptr());
+
+        let blob: Arc<[u8]> = Arc::from(&b"\x00\x01\x02"[..]);
+        let body = Body::from(blob.clone());
+        assert_eq!(body.mime(), Some(&mime::APPLICATION_OCTET_STREAM));
+        let bytes = data(body).await;
+        assert_eq!(&bytes[..], &blob[..]);
+        assert_eq!(bytes.as_ptr(), blob.as_ptr());
+        drop(bytes);
+        assert_eq!(Arc::strong_count(&blob), 1);
+
+        let boxed: Box<[u8]> = Box::from(&b"boxed"[..]);
+        let address = boxed.as_ptr();
+        assert_eq!(data(Body::from(boxed)).await.as_ptr(), address);
+    }
+
+    #[tokio::test]
+    async fn deques_keep_their_buffer() {
+        let mut deque = VecDeque::with_capacity(8);
+        deque.extend(*b"cdef");
+        deque.push_front(b'b');
+        deque.push_front(b'a');
+        assert!(!deque.as_slices().0.is_empty() && !deque.as_slices().1.is_empty());
+        let address = deque.as_slices().1.as_ptr() as usize;
+        let bytes = data(Body::from(deque)).await;
+        assert_eq!(bytes, "abcdef");
+        // The wrapped-around elements were rotated within the same allocation.
+        let start = bytes.as_ptr() as usize;
+        assert!((start..start + 8).contains(&address));
+    }
+
+    #[test]
+    fn text_and_bytes_mime_types() {
+        let text = [
+            Body::from("text"),
+            Body::from(String::from("text")),
+            Body::from(ByteStr::from_static("text")),
+            Body::from(Box::<str>::from("text")),
+        ];
+        for body in text {
+            assert_eq!(body.mime(), Some(&mime::TEXT_PLAIN_UTF_8));
+        }
+        let bytes = [
+            Body::from(&b"bytes"[..]),
+            Body::from(b"bytes".to_vec()),
+            Body::from(Bytes::from_static(b"bytes")),
+            Body::from(Box::<[u8]>::from(&b"bytes"[..])),
+        ];
+        for body in bytes {
+            assert_eq!(body.mime(), Some(&mime::APPLICATION_OCTET_STREAM));
+        }
+    }
+
+    #[tokio::test]
+    async fn iterators_and_cows() {
+        let body: Body = (b'a'..=b'e').collect();
+        assert_eq!(data(body).await, "abcde");
+
+        let body = Body::from(Cow::<'static, str>::Borrowed("borrowed"));
+        assert_eq!(body.mime(), Some(&mime::TEXT_PLAIN_UTF_8));
+        assert_eq!(data(body).await, "borrowed");
+
+        let body = Body::from(Cow::<[u8]>::Owned(alloc::vec![1, 2]));
+        assert_eq!(data(body).await, &[1u8, 2][..]);
+    }
+
+    #[cfg(feature = "json")]
+    #[tokio::test]
+    async fn json_values_are_serialized() {
+        let body = Body::from(serde_json::json!({ "id": 7, "tags": ["a"] }));
+        assert_eq!(body.mime(), Some(&mime::APPLICATION_JSON));
+        assert_eq!(data(body).await, r#"{"id":7,"tags":["a"]}"#);
+    }
+
+    #[tokio::test]
+    async fn collected_bodies_keep_their_trailers() {
+        let mut trailers = http::HeaderMap::new();
+        trailers.insert("checksum", "abc".parse().unwrap());
+        let chunks = futures_lite::stream::iter([Ok::<_, crate::BodyError>("col"), Ok("lected")]);
+        let body = Body::from_stream(chunks).with_trailers(trailers);
+
+        let collected = body.collect().await.unwrap();
+        assert_eq!(collected.trailers().unwrap()["checksum"], "abc");
+        let (data, trailers) = Body::from(collected)
+            .into_bytes_with_trailers()
+            .await
+            .unwrap();
+        assert_eq!(data, "collected");
+        assert_eq!(trailers.unwrap()["checksum"], "abc");
+
+        let collected = Body::from_bytes("plain").collect().await.unwrap();
+        assert!(collected.trailers().is_none());
+        let body = Body::from(collected);
+        assert_eq!(body.len(), Some(5));
+    }
+}
@@ -0,0 +1,340 @@
+use alloc::{vec, vec::Vec};
+use bytes::Bytes;
+use core::fmt;
+use core::marker::PhantomData;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+use csv_core::ReadRecordResult;
+use futures_lite::{Stream, StreamExt};
+use mime::Mime;
+use serde::{de::DeserializeOwned, Serialize};
+
+use super::{Body, Error};
+
+fn csv_mime() -> Mime {
+    "text/csv; charset=utf-8"
+        .parse()
+        .expect("text/csv; charset=utf-8 is a valid MIME type")
+}
+
+/// A stream of the records of a CSV body, created by [`Body::into_csv`].
+///
+/// The first record is the header row, whose names match the fields of `T`. Records are
+/// parsed as data arrives, following RFC 4180: fields may be quoted, quoted fields may
+/// hold commas, quotes (doubled) and line breaks, and records end with CRLF or LF. Only
+/// the bytes of the current record are buffered.
+///
+/// A record that doesn't match `T` yields
+/// [`BodyError::CsvError`](crate::BodyError::CsvError), whose position gives its record
+/// and line numbers, and the stream moves on to the next record. Errors of the
+/// underlying body end the stream.
+pub struct CsvStream<T> {
+    body: Body,
+    reader: csv_core::Reader,
+    buf: Vec<u8>,
+    // Start of the input not parsed yet.
+    pos: usize,
+    // Fields of the record being parsed, and the end offset of each field, with the
+    // length of each written so far.
+    fields: Vec<u8>,
+    ends: Vec<usize>,
+    outlen: usize,
+    endlen: usize,
+    headers: Option<::csv::ByteRecord>,
+    // Position of the record being parsed.
+    start: ::csv::Position,
+    consumed: u64,
+    eof: bool,
+    done: bool,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Unpin for CsvStream<T> {}
+
+impl<T> fmt::Debug for CsvStream<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CsvStream")
+            .field("headers", &self.headers)
+            .field("position", &self.start)
+            .field("done", &self.done)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T> CsvStream<T> {
+    // Parses the buffered input, returning the next complete record, if any.
+    fn next_record(&mut self) -> Option<::csv::ByteRecord> {
+        loop {
+            // Empty input tells the reader that the body has ended.
+            if self.pos == self.buf.len() && !self.eof {
+                return None;
+            }
+            let (result, nin, nout, nend) = self.reader.read_record(
+                &self.buf[self.pos..],
+                &mut self.fields[self.outlen..],
+                &mut self.ends[self.endlen..],
+            );
+            self.pos += nin;
+            self.consumed += nin as u64;
+            self.outlen += nout;
+            self.endlen += nend;
+            match result {
+                ReadRecordResult::InputEmpty => {}
+                ReadRecordResult::OutputFull => {
+                    let len = self.fields.len().max(64);
+                    self.fields.resize(len * 2, 0);
+                }
+                ReadRecordResult::OutputEndsFull => {
+                    let len = self.ends.len().max(8);
+                    self.ends.resize(len * 2, 0);
+                }
+                ReadRecordResult::Record => {
+                    let endlen = core::mem::take(&mut self.endlen);
+                    self.outlen = 0;
+                    let mut record: ::csv::ByteRecord = self.ends[..endlen]
+                        .iter()
+                        .scan(0, |from, &end| {
+                            Some(&self.fields[core::mem::replace(from, end)..end])
+                        })
+                        .collect();
+                    record.set_position(Some(self.start.clone()));
+                    let mut next = ::csv::Position::new();
+                    next.set_byte(self.consumed)
+                        .set_line(self.reader.line())
+                        .set_record(self.start.record() + 1);
+                    self.start = next;
+                    return Some(record);
+                }
+                ReadRecordResult::End => {
+                    self.done = true;
+                    return None;
+                }
+            }
+        }
+    }
+}
+
+impl<T: DeserializeOwned> Stream for CsvStream<T> {
+    type Item = Result<T, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            while let Some(record) = self.next_record() {
+                match &self.headers {
+                    None => self.headers = Some(record),
+                    Some(headers) => {
+                        let result = record.deserialize(Some(headers)).map_err(Error::from);
+                        return Poll::Ready(Some(result));
+                    }
+                }
+            }
+            if self.done {
+                return Poll::Ready(None);
+            }
+
+            match Pin::new(&mut self.body).poll_next(cx) {
+                Poll::Ready(Some(Ok(chunk))) => {
+                    let pos = core::mem::take(&mut self.pos);
+                    self.buf.drain(..pos);
+                    self.buf.extend_from_slice(&chunk);
+                }
+                Poll::Ready(Some(Err(error))) => {
+                    self.done = true;
+                    return Poll::Ready(Some(Err(error)));
+                }
+                Poll::Ready(None) => self.eof = true,
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+impl Body {
+    /// Creates a CSV body from a stream of records.
+    ///
+    /// Records are serialized as they are produced, each on its own CRLF-terminated
+    /// line. When the records are structs, the names of their fields are written as a
+    /// header row first. The MIME type is set to `text/csv; charset=utf-8`.
+    ///
+    /// # Errors
+    ///
+    /// The body fails with [`BodyError::CsvError`](crate::BodyError::CsvError) if a
+    /// record cannot be serialized, and forwards the errors of the stream.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use futures_lite::stream;
+    /// use http_kit::Body;
+    /// use serde::Serialize;
+    ///
+    /// #[derive(Serialize)]
+    /// struct Row {
+    ///     city: &'static str,
+    ///     population: u32,
+    /// }
+    ///
+    /// # async fn example() -> Result<(), http_kit::BodyError> {
+    /// let rows = stream::iter([Ok::<_, core::convert::Infallible>(Row {
+    ///     city: "Boston, MA",
+    ///     population: 650_000,
+    /// })]);
+    /// let body = Body::from_csv(rows);
+    /// assert_eq!(
+    ///     body.into_string().await?,
+    ///     "city,population\r\n\"Boston, MA\",650000\r\n"
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_csv<S, T, E>(records: S) -> Self
+    where
+        S: Stream<Item = Result<T, E>> + Send + Sync + 'static,
+        T: Serialize,
+        E: Into<Error>,
+    {
+        let mut first = true;
+        let rows = records.map(move |record| {
+            let record = record.map_err(Into::into)?;
+            let mut writer = ::csv::WriterBuilder::new()
+                .has_headers(core::mem::take(&mut first))
+                .terminator(::csv::Terminator::CRLF)
+                .from_writer(Vec::new());
+            writer.serialize(record)?;
+            let row = writer.into_inner().map_err(|error| error.into_error())?;
+            Ok::<_, Error>(Bytes::from(row))
+        });
+        Body::from_stream(rows).with_mime(csv_mime())
+    }
+
+    /// Converts a CSV body with a header row into a stream of records, see
+    /// [`CsvStream`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use futures_lite::StreamExt;
+    /// use http_kit::Body;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Row {
+    ///     city: String,
+    ///     population: u32,
+    /// }
+    ///
+    /// # async fn example() -> Result<(), http_kit::BodyError> {
+    /// let body = Body::from("city,population\r\n\"Boston, MA\",650000\r\n");
+    /// let rows: Vec<Row> = body.into_csv().try_collect().await?;
+    /// assert_eq!(rows[0].city, "Boston, MA");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn into_csv<T: DeserializeOwned>(self) -> CsvStream<T> {
+        CsvStream {
+            body: self,
+            reader: csv_core::Reader::new(),
+            buf: Vec::new(),
+            pos: 0,
+            fields: vec![0; 1024],
+            ends: vec![0; 16],
+            outlen: 0,
+            endlen: 0,
+            headers: None,
+            start: ::csv::Position::new(),
+            consumed: 0,
+            eof: false,
+            done: false,
+            _marker: PhantomData,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::{String, ToString};
+    use futures_lite::stream;
+    use serde::Deserialize;
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct Row {
+        name: String,
+        note: String,
+        count: u32,
+    }
+
+    fn chunked(chunks: Vec<&'static str>) -> Body {
+        Body::from_stream(stream::iter(
+            chunks.into_iter().map(Ok::<_, core::convert::Infallible>),
+        ))
+    }
+
+    #[tokio::test]
+    async fn round_trip_with_quoted_fields() {
+        let rows = vec![
+            Row {
+                name: "plain".into(),
+                note: "".into(),
+                count: 1,
+            },
+            Row {
+                name: "comma, inside".into(),
+                note: "say \"hi\"\r\nnext line".into(),
+                count: 2,
+            },
+        ];
+        let body = Body::from_csv(stream::iter(rows.clone().into_iter().map(Ok::<_, Error>)));
+        assert_eq!(body.mime(), Some(&csv_mime()));
+        let text = body.into_string().await.unwrap();
+        assert_eq!(
+            text,
+            "name,note,count\r\nplain,,1\r\n\"comma, inside\",\"say \"\"hi\"\"\r\nnext line\",2\r\n"
+        );
+
+        // Split the encoded text at every few bytes, so that records, quoted fields and
+        // CRLF terminators straddle chunks.
+        let text: &'static str = String::from(text.as_str()).leak();
+        let chunks: Vec<&'static str> = text
+            .as_bytes()
+            .chunks(3)
+            .map(|chunk| core::str::from_utf8(chunk).unwrap())
+            .collect();
+        let decoded: Vec<Row> = chunked(chunks).into_csv().try_collect().await.unwrap();
+        assert_eq!(decoded, rows);
+    }
+
+    #[tokio::test]
+    async fn bad_record_reports_its_row() {
+        let body = chunked(vec![
+            "name,note,count\n",
+            "a,,1\nb,,many\n",
+            "c,\"multi\nline\",3",
+        ]);
+        let results: Vec<Result<Row, Error>> = body.into_csv().collect().await;
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].as_ref().unwrap().name, "a");
+        match &results[1] {
+            Err(Error::CsvError(error)) => {
+                let position = error.position().unwrap();
+                assert_eq!(position.record(), 2);
+                assert_eq!(position.line(), 3);
+                assert!(error.to_string().contains("record 2"));
+            }
+            other => panic!("unexpected result: {other:?}"),
+        }
+        assert_eq!(results[2].as_ref().unwrap().note, "multi\nline");
+    }
+
+    #[tokio::test]
+    async fn empty_body_has_no_records() {
+        let rows: Vec<Row> = Body::empty().into_csv().try_collect().await.unwrap();
+        assert!(rows.is_empty());
+        let rows: Vec<Row> = Body::from("name,note,count\r\n")
+            .into_csv()
+            .try_collect()
+            .await
+            .unwrap();
+        assert!(rows.is_empty());
+    }
+}
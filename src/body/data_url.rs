@@ -0,0 +1,251 @@
+use alloc::{string::String, vec::Vec};
+use bytestr::ByteStr;
+use core::fmt;
+use mime::Mime;
+
+use super::{Body, Error};
+use crate::{base64, percent};
+
+/// Default maximum length of a `data:` URL accepted by [`Body::from_data_url`].
+pub const DEFAULT_DATA_URL_LIMIT: usize = 1 << 20;
+
+/// Error type for `data:` URL parsing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DataUrlError {
+    /// The URL does not start with the `data:` scheme.
+    NotDataUrl,
+    /// The URL has no `,` separating the media type from the data.
+    MissingComma,
+    /// The media type could not be parsed.
+    InvalidMediaType,
+    /// The data is marked `;base64` but is not valid base64.
+    InvalidBase64,
+    /// The data contains a malformed percent-encoded sequence.
+    InvalidPercentEncoding,
+    /// The URL is longer than the configured limit.
+    TooLarge {
+        /// The configured limit in bytes.
+        limit: usize,
+    },
+}
+
+impl fmt::Display for DataUrlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotDataUrl => f.write_str("URL does not use the `data:` scheme"),
+            Self::MissingComma => f.write_str("data URL is missing the `,` separator"),
+            Self::InvalidMediaType => f.write_str("data URL has an invalid media type"),
+            Self::InvalidBase64 => f.write_str("data URL contains invalid base64 data"),
+            Self::InvalidPercentEncoding => {
+                f.write_str("data URL contains invalid percent-encoding")
+            }
+            Self::TooLarge { limit } => write!(f, "data URL exceeds the limit of {limit} bytes"),
+        }
+    }
+}
+
+impl core::error::Error for DataUrlError {}
+
+fn percent_decode(input: &[u8]) -> Result<Vec<u8>, DataUrlError> {
+    percent::decode(input, false).ok_or(DataUrlError::InvalidPercentEncoding)
+}
+
+// Characters that can appear verbatim in the data part of a data URL.
+fn is_url_safe(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || b"-._~!$&'()*+,;=:@/?".contains(&byte)
+}
+
+fn is_textual(mime: Option<&Mime>) -> bool {
+    mime.is_some_and(|mime| {
+        mime.type_() == mime::TEXT
+            || mime.subtype() == mime::JSON
+            || mime.suffix() == Some(mime::JSON)
+            || mime.subtype() == mime::XML
+    })
+}
+
+impl Body {
+    /// Creates a body from an RFC 2397 `data:` URL.
+    ///
+    /// Both the base64 form (`data:image/png;base64,...`) and the percent-encoded
+    /// form (`data:text/plain,Hello%20world`) are supported. The body MIME type is set
+    /// from the media type of the URL, defaulting to `text/plain;charset=US-ASCII`.
+    ///
+    /// URLs longer than [`DEFAULT_DATA_URL_LIMIT`](crate::DEFAULT_DATA_URL_LIMIT) are
+    /// rejected; use [`Body::from_data_url_with_limit`] to configure the limit.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use http_kit::Body;
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let body = Body::from_data_url("data:text/plain,Hello%2C%20world")?;
+    /// assert_eq!(body.mime().unwrap().essence_str(), "text/plain");
+    /// assert_eq!(body.into_string().await?, "Hello, world");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_data_url(url: &str) -> Result<Self, DataUrlError> {
+        Self::from_data_url_with_limit(url, DEFAULT_DATA_URL_LIMIT)
+    }
+
+    /// Creates a body from an RFC 2397 `data:` URL, rejecting URLs longer than `limit` bytes.
+    pub fn from_data_url_with_limit(url: &str, limit: usize) -> Result<Self, DataUrlError> {
+        if url.len() > limit {
+            return Err(DataUrlError::TooLarge { limit });
+        }
+        let scheme = url.get(..5).ok_or(DataUrlError::NotDataUrl)?;
+        if !scheme.eq_ignore_ascii_case("data:") {
+            return Err(DataUrlError::NotDataUrl);
+        }
+        let (header, data) = url[5..].split_once(',').ok_or(DataUrlError::MissingComma)?;
+
+        let (media_type, base64) = match header.rsplit_once(';') {
+            Some((media_type, flag)) if flag.trim().eq_ignore_ascii_case("base64") => {
+                (media_type, true)
+            }
+            _ => (header, false),
+        };
+
+        let mime = if media_type.trim().is_empty() {
+            "text/plain;charset=US-ASCII".parse().ok()
+        } else if media_type.starts_with(';') {
+            let mut full = String::from("text/plain");
+            full.push_str(media_type);
+            Some(full.parse().map_err(|_| DataUrlError::InvalidMediaType)?)
+        } else {
+            Some(
+                media_type
+                    .parse()
+                    .map_err(|_| DataUrlError::InvalidMediaType)?,
+            )
+        };
+
+        let data = if base64 {
+            let encoded = percent_decode(data.as_bytes())?;
+            base64::decode(encoded).map_err(|_| DataUrlError::InvalidBase64)?
+        } else {
+            percent_decode(data.as_bytes())?
+        };
+
+        Ok(Self {
+            mime,
+            ..Self::from_bytes(data)
+        })
+    }
+
+    /// Buffers the body and renders it as an RFC 2397 `data:` URL.
+    ///
+    /// Textual bodies (`text/*`, JSON and XML) holding valid UTF-8 are percent-encoded,
+    /// everything else is base64-encoded. The body MIME type is included when known.
+    /// The body remains usable afterwards.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the body is frozen or reading it fails.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use http_kit::Body;
+    ///
+    /// # async fn example() -> Result<(), http_kit::BodyError> {
+    /// let mut body = Body::from_bytes(vec![0u8, 1, 2]);
+    /// assert_eq!(body.to_data_url().await?, "data:application/octet-stream;base64,AAEC");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn to_data_url(&mut self) -> Result<ByteStr, Error> {
+        let mime = self.mime.clone();
+        let data = self.as_bytes().await?;
+
+        let mut url = String::from("data:");
+        if let Some(mime) = &mime {
+            url.push_str(mime.as_ref());
+        }
+
+        if is_textual(mime.as_ref()) && core::str::from_utf8(data).is_ok() {
+            url.push(',');
+            for &byte in data {
+                if is_url_safe(byte) && byte != b',' {
+                    url.push(byte as char);
+                } else {
+                    url.push('%');
+                    url.push(char::from(b"0123456789ABCDEF"[usize::from(byte >> 4)]));
+                    url.push(char::from(b"0123456789ABCDEF"[usize::from(byte & 0xf)]));
+                }
+            }
+        } else {
+            url.push_str(";base64,");
+            url.push_str(&base64::encode(data));
+        }
+        Ok(url.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PNG: &str = "data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNk+M9QDwADhgGAWjR9awAAAABJRU5ErkJggg==";
+
+    #[tokio::test]
+    async fn parses_base64_png() {
+        let body = Body::from_data_url(PNG).unwrap();
+        assert_eq!(body.mime().unwrap().essence_str(), "image/png");
+        let bytes = body.into_bytes().await.unwrap();
+        assert_eq!(&bytes[..8], b"\x89PNG\r\n\x1a\n");
+        assert_eq!(bytes.len(), 70);
+    }
+
+    #[tokio::test]
+    async fn parses_percent_encoded_text() {
+        let body =
+            Body::from_data_url("data:text/plain;charset=utf-8,caf%C3%A9%20au%20lait").unwrap();
+        assert_eq!(body.mime().unwrap().get_param("charset").unwrap(), "utf-8");
+        assert_eq!(body.into_string().await.unwrap(), "café au lait");
+
+        let body = Body::from_data_url("data:,A%20brief%20note").unwrap();
+        assert_eq!(body.mime().unwrap().essence_str(), "text/plain");
+        assert_eq!(body.into_string().await.unwrap(), "A brief note");
+    }
+
+    #[test]
+    fn rejects_malformed_urls() {
+        assert_eq!(
+            Body::from_data_url("data:text/plain").unwrap_err(),
+            DataUrlError::MissingComma
+        );
+        assert_eq!(
+            Body::from_data_url("http://example.com").unwrap_err(),
+            DataUrlError::NotDataUrl
+        );
+        assert_eq!(
+            Body::from_data_url("data:;base64,@@@").unwrap_err(),
+            DataUrlError::InvalidBase64
+        );
+        assert_eq!(
+            Body::from_data_url("data:,%zz").unwrap_err(),
+            DataUrlError::InvalidPercentEncoding
+        );
+        assert_eq!(
+            Body::from_data_url_with_limit(PNG, 16).unwrap_err(),
+            DataUrlError::TooLarge { limit: 16 }
+        );
+    }
+
+    #[tokio::test]
+    async fn round_trips() {
+        let mut png = Body::from_data_url(PNG).unwrap();
+        assert_eq!(png.to_data_url().await.unwrap(), PNG);
+
+        let mut text = Body::from_text("a,b & c?");
+        let url = text.to_data_url().await.unwrap();
+        assert_eq!(url, "data:text/plain; charset=utf-8,a%2Cb%20&%20c?");
+        let mut parsed = Body::from_data_url(&url).unwrap();
+        assert_eq!(parsed.as_str().await.unwrap(), "a,b & c?");
+        assert_eq!(parsed.mime(), text.mime());
+    }
+}
@@ -2,9 +2,11 @@
 extern crate std;
 
 use super::BodyFrozen;
+use crate::HttpError;
 use alloc::boxed::Box;
 use core::fmt::Display;
 use core::str::Utf8Error;
+use http::StatusCode;
 
 /// Error type for body operations.
 ///
@@ -39,6 +41,30 @@ pub enum Error {
     /// This error occurs when trying to interpret body bytes as UTF-8 text
     /// but the bytes don't form valid UTF-8 sequences.
     Utf8(Utf8Error),
+    /// Invalid UTF-8 data was encountered while decoding a streaming text body.
+    ///
+    /// Unlike [`Error::Utf8`], the offset is counted from the start of the whole body
+    /// rather than from the start of a single chunk.
+    InvalidUtf8 {
+        /// Byte offset of the first invalid byte.
+        offset: usize,
+    },
+    /// The charset of a text body is not one that can be decoded.
+    ///
+    /// Carries the charset label.
+    #[cfg(feature = "encoding")]
+    UnknownCharset(alloc::string::String),
+    /// A text body holds bytes that are not valid in its charset.
+    #[cfg(feature = "encoding")]
+    MalformedText {
+        /// Canonical name of the charset.
+        charset: &'static str,
+    },
+    /// The body is larger than the limit set with [`Body::limit`](crate::Body::limit),
+    /// or a line of an NDJSON body is longer than its limit.
+    ///
+    /// Carries the limit in bytes.
+    LimitExceeded(usize),
     /// The body has been consumed and cannot provide data anymore.
     ///
     /// This is distinct from a normal empty body - it indicates that the body
@@ -63,6 +89,11 @@ pub enum Error {
     /// to a Rust type using the `into_form()` method.
     #[cfg(feature = "form")]
     DeserializeForm(serde_urlencoded::de::Error),
+    /// CSV serialization or deserialization failed.
+    ///
+    /// The error of a record that doesn't deserialize carries its position.
+    #[cfg(feature = "csv")]
+    CsvError(csv::Error),
     /// Other error types not covered by specific variants.
     ///
     /// This is a catch-all for any other error that can occur during body operations,
@@ -88,7 +119,20 @@ macro_rules! impl_body_error {
                         $(#[cfg(feature = $feature)])*
                         Self::$field(error) => error.fmt(f),
                     )*
+                    Self::InvalidUtf8 { offset } => {
+                        write!(f, "invalid UTF-8 sequence at byte offset {offset}")
+                    }
+                    Self::LimitExceeded(limit) => {
+                        write!(f, "body exceeds the limit of {limit} bytes")
+                    }
+                    #[cfg(feature = "encoding")]
+                    Self::UnknownCharset(label) => write!(f, "unknown charset `{label}`"),
+                    #[cfg(feature = "encoding")]
+                    Self::MalformedText { charset } => {
+                        write!(f, "body is not valid {charset} text")
+                    }
                     Self::BodyFrozen => BodyFrozen::new().fmt(f),
+                    Self::Other(error) => error.fmt(f),
                 }
             }
         }
@@ -98,9 +142,12 @@ macro_rules! impl_body_error {
                 match self {
                     $(
                         $(#[cfg(feature = $feature)])*
-                        Self::$field(error) => error.source(),
+                        Self::$field(error) => Some(error),
                     )*
-                    Error::BodyFrozen => None,
+                    Self::Other(error) => Some(&**error),
+                    Self::InvalidUtf8 { .. } | Self::LimitExceeded(_) | Self::BodyFrozen => None,
+                    #[cfg(feature = "encoding")]
+                    Self::UnknownCharset(_) | Self::MalformedText { .. } => None,
                 }
             }
         }
@@ -112,21 +159,115 @@ macro_rules! impl_body_error {
 impl_body_error![
     (Io, std::io::Error),
     (Utf8, Utf8Error),
-    (Other, Box<dyn core::error::Error + Send + Sync + 'static>),
     (JsonError, serde_json::Error, "json"),
     (SerializeForm, serde_urlencoded::ser::Error, "form"),
-    (DeserializeForm, serde_urlencoded::de::Error, "form")
+    (DeserializeForm, serde_urlencoded::de::Error, "form"),
+    (CsvError, csv::Error, "csv")
 ];
 
 #[cfg(not(feature = "std"))]
 impl_body_error![
     (Utf8, Utf8Error),
-    (Other, Box<dyn core::error::Error + Send + Sync + 'static>),
     (JsonError, serde_json::Error, "json"),
     (SerializeForm, serde_urlencoded::ser::Error, "form"),
     (DeserializeForm, serde_urlencoded::de::Error, "form")
 ];
 
+impl From<Box<dyn core::error::Error + Send + Sync + 'static>> for Error {
+    fn from(error: Box<dyn core::error::Error + Send + Sync + 'static>) -> Self {
+        Self::Other(error)
+    }
+}
+
+impl Error {
+    /// Returns the error of type `E` this error was created from, if any.
+    ///
+    /// Errors wrapped in [`Error::Other`] are found, and so are errors wrapped in an
+    /// [`std::io::Error`] with the `std` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use futures_lite::stream;
+    /// use http_kit::{Body, BodyError};
+    ///
+    /// #[derive(Debug)]
+    /// struct Disconnected;
+    ///
+    /// impl std::fmt::Display for Disconnected {
+    ///     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    ///         f.write_str("peer disconnected")
+    ///     }
+    /// }
+    ///
+    /// impl std::error::Error for Disconnected {}
+    ///
+    /// # async fn example() {
+    /// let chunks = stream::iter([Ok("partial"), Err(BodyError::Other(Box::new(Disconnected)))]);
+    /// let error = Body::from_stream(chunks).into_bytes().await.unwrap_err();
+    /// assert!(error.downcast_ref::<Disconnected>().is_some());
+    /// # }
+    /// ```
+    pub fn downcast_ref<E: core::error::Error + 'static>(&self) -> Option<&E> {
+        match self {
+            Self::Other(error) => error.downcast_ref(),
+            #[cfg(feature = "std")]
+            Self::Io(error) => error.get_ref()?.downcast_ref(),
+            _ => None,
+        }
+    }
+
+    /// Extracts the error of type `E` this error was created from, or returns `self`
+    /// if there is none; see [`Error::downcast_ref`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `self` unchanged if it does not wrap an `E`.
+    pub fn downcast<E: core::error::Error + 'static>(self) -> Result<E, Self> {
+        if self.downcast_ref::<E>().is_none() {
+            return Err(self);
+        }
+        let boxed = match self {
+            Self::Other(error) => error,
+            #[cfg(feature = "std")]
+            Self::Io(error) => error.into_inner().expect("checked above"),
+            _ => unreachable!("checked above"),
+        };
+        Ok(*boxed.downcast().expect("checked above"))
+    }
+}
+
+impl HttpError for Error {
+    /// `413 Payload Too Large` for [`Error::LimitExceeded`], `415 Unsupported Media
+    /// Type` for an unknown charset, `400 Bad Request` for bodies that cannot be
+    /// decoded, and `500 Internal Server Error` otherwise.
+    fn status(&self) -> StatusCode {
+        match self {
+            Self::LimitExceeded(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            Self::Utf8(_) | Self::InvalidUtf8 { .. } => StatusCode::BAD_REQUEST,
+            #[cfg(feature = "encoding")]
+            Self::UnknownCharset(_) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            #[cfg(feature = "encoding")]
+            Self::MalformedText { .. } => StatusCode::BAD_REQUEST,
+            #[cfg(feature = "json")]
+            Self::JsonError(error) if !error.is_io() => StatusCode::BAD_REQUEST,
+            #[cfg(feature = "form")]
+            Self::DeserializeForm(_) => StatusCode::BAD_REQUEST,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl Error {
+    // Converts into a `crate::Error` with the same status, for the decoding helpers of
+    // requests and responses.
+    #[cfg(any(feature = "json", feature = "form"))]
+    pub(crate) fn into_http_error(self) -> crate::Error {
+        let status = self.status();
+        crate::Error::new(self).set_status(status)
+    }
+}
+
 impl From<BodyFrozen> for Error {
     fn from(_error: BodyFrozen) -> Self {
         Self::BodyFrozen
@@ -145,3 +286,90 @@ impl From<core::convert::Infallible> for Error {
         unreachable!()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Body;
+    use alloc::string::ToString;
+    use core::error::Error as _;
+    use futures_lite::stream;
+
+    #[derive(Debug, PartialEq)]
+    struct Reset(u32);
+
+    impl Display for Reset {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            write!(f, "stream reset with code {}", self.0)
+        }
+    }
+
+    impl core::error::Error for Reset {}
+
+    async fn failing_body(error: Error) -> Error {
+        let chunks = stream::iter([Ok("partial"), Err(error)]);
+        Body::from_stream(chunks).into_bytes().await.unwrap_err()
+    }
+
+    #[tokio::test]
+    async fn stream_errors_can_be_downcast() {
+        let error = failing_body(Error::Other(Box::new(Reset(8)))).await;
+        assert_eq!(error.to_string(), "stream reset with code 8");
+        assert_eq!(error.downcast_ref::<Reset>(), Some(&Reset(8)));
+        assert!(error.source().unwrap().is::<Reset>());
+        assert!(error.downcast_ref::<Utf8Error>().is_none());
+
+        let error = error.downcast::<Utf8Error>().unwrap_err();
+        assert_eq!(error.downcast::<Reset>().unwrap(), Reset(8));
+    }
+
+    #[cfg(feature = "std")]
+    #[tokio::test]
+    async fn io_errors_keep_their_payload() {
+        let io = std::io::Error::new(std::io::ErrorKind::ConnectionReset, Reset(2));
+        let error = failing_body(Error::Io(io)).await;
+        assert_eq!(error.downcast_ref::<Reset>(), Some(&Reset(2)));
+        assert_eq!(error.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(error.downcast::<Reset>().unwrap(), Reset(2));
+
+        let plain = Error::Io(std::io::ErrorKind::TimedOut.into());
+        assert!(plain.downcast_ref::<Reset>().is_none());
+        assert!(matches!(plain.downcast::<Reset>(), Err(Error::Io(_))));
+    }
+
+    #[tokio::test]
+    async fn statuses() {
+        assert_eq!(
+            Error::LimitExceeded(1).status(),
+            StatusCode::PAYLOAD_TOO_LARGE
+        );
+        assert_eq!(
+            Error::InvalidUtf8 { offset: 3 }.status(),
+            StatusCode::BAD_REQUEST
+        );
+        let invalid = Body::from_bytes(&b"\xff"[..])
+            .into_string()
+            .await
+            .unwrap_err();
+        assert_eq!(invalid.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            Error::BodyFrozen.status(),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+        assert_eq!(
+            Error::Other(Box::new(Reset(1))).status(),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+    }
+
+    #[cfg(feature = "json")]
+    #[tokio::test]
+    async fn malformed_json_is_a_client_error() {
+        let error = Body::from_bytes("{")
+            .into_json::<serde_json::Value>()
+            .await
+            .unwrap_err();
+        assert_eq!(error.status(), StatusCode::BAD_REQUEST);
+        assert!(error.source().unwrap().is::<serde_json::Error>());
+    }
+}
@@ -0,0 +1,169 @@
+//! Windowed file bodies.
+
+extern crate std;
+
+use std::{io, path::Path};
+
+use futures_lite::io::{AsyncReadExt, AsyncSeekExt, BufReader, SeekFrom};
+use mime::Mime;
+
+use super::Body;
+
+const DEFAULT_BUFFER_SIZE: usize = 64 << 10;
+
+/// Options of [`Body::from_file_with`].
+///
+/// The default streams the whole file in chunks of up to 64 KiB.
+///
+/// # Examples
+///
+/// ```rust
+/// use http_kit::FileOptions;
+///
+/// // The second KiB of the file, in chunks of up to 256 bytes.
+/// let options = FileOptions {
+///     offset: 1024,
+///     length: Some(1024),
+///     buffer_size: 256,
+/// };
+/// assert_eq!(FileOptions::default().buffer_size, 64 * 1024);
+/// # let _ = options;
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FileOptions {
+    /// Position of the first byte to stream.
+    pub offset: u64,
+
+    /// Maximum number of bytes to stream from `offset`; `None` streams to the end of
+    /// the file.
+    pub length: Option<u64>,
+
+    /// Capacity of the read buffer, which bounds the size of each chunk.
+    pub buffer_size: usize,
+}
+
+impl Default for FileOptions {
+    fn default() -> Self {
+        Self {
+            offset: 0,
+            length: None,
+            buffer_size: DEFAULT_BUFFER_SIZE,
+        }
+    }
+}
+
+// Guesses the MIME type from the extension of `path`, in any case, so that `REPORT.PDF`
+// from a Windows share is typed like `report.pdf`.
+fn guess(path: &Path) -> Option<Mime> {
+    let extension = path.extension()?.to_str()?;
+    mime_guess::from_ext(extension).first_raw()?.parse().ok()
+}
+
+pub(super) async fn open(path: &Path, options: FileOptions) -> io::Result<Body> {
+    let mut file = async_fs::File::open(path).await?;
+    let size = file.metadata().await?.len();
+    let available = size.saturating_sub(options.offset);
+    let window = options
+        .length
+        .map_or(available, |length| length.min(available));
+    if window > 0 {
+        file.seek(SeekFrom::Start(options.offset)).await?;
+    }
+    let reader = BufReader::with_capacity(options.buffer_size.max(1), file.take(window));
+    Ok(Body {
+        mime: guess(path),
+        ..Body::from_reader_with_capacity(
+            reader,
+            usize::try_from(window).ok(),
+            options.buffer_size.max(1),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+    use futures_lite::StreamExt;
+
+    struct TempFile(std::path::PathBuf);
+
+    impl TempFile {
+        fn new(name: &str, contents: &[u8]) -> Self {
+            let path = std::env::temp_dir().join(name);
+            std::fs::write(&path, contents).unwrap();
+            Self(path)
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    #[tokio::test]
+    async fn chunks_stay_within_the_window() {
+        let contents: Vec<u8> = (0..100_000u32).map(|i| (i % 251) as u8).collect();
+        let file = TempFile::new("http_kit_window.bin", &contents);
+
+        let options = FileOptions {
+            offset: 1000,
+            length: Some(70_000),
+            buffer_size: 16 << 10,
+        };
+        let mut body = Body::from_file_with(&file.0, options).await.unwrap();
+        assert_eq!(body.len(), Some(70_000));
+
+        let mut streamed = Vec::new();
+        let mut chunks = 0;
+        while let Some(chunk) = body.next().await {
+            let chunk = chunk.unwrap();
+            assert!(!chunk.is_empty() && chunk.len() <= 16 << 10);
+            streamed.extend_from_slice(&chunk);
+            chunks += 1;
+        }
+        assert!(chunks >= 5);
+        assert_eq!(streamed, contents[1000..71_000]);
+    }
+
+    #[test]
+    fn extensions_are_matched_in_any_case() {
+        for name in ["report.pdf", "REPORT.PDF", "archive.tar.Pdf"] {
+            assert_eq!(
+                guess(Path::new(name)).as_ref().map(AsRef::as_ref),
+                Some("application/pdf"),
+                "{name}"
+            );
+        }
+        assert_eq!(guess(Path::new("photo.JPG")), Some(mime::IMAGE_JPEG));
+        assert_eq!(guess(Path::new("README")), None);
+        assert_eq!(guess(Path::new("data.unknown-extension")), None);
+    }
+
+    #[tokio::test]
+    async fn windows_are_clamped_to_the_file() {
+        let file = TempFile::new("http_kit_clamp.txt", b"0123456789");
+        let path = &file.0;
+        let read = |offset, length| async move {
+            let options = FileOptions {
+                offset,
+                length,
+                ..FileOptions::default()
+            };
+            let body = Body::from_file_with(path, options).await.unwrap();
+            (body.len(), body.into_bytes().await.unwrap())
+        };
+
+        assert_eq!(read(4, None).await, (Some(6), "456789".into()));
+        assert_eq!(read(4, Some(100)).await, (Some(6), "456789".into()));
+        assert_eq!(read(10, None).await, (Some(0), "".into()));
+        assert_eq!(read(1 << 40, Some(5)).await, (Some(0), "".into()));
+        assert_eq!(read(0, Some(0)).await, (Some(0), "".into()));
+
+        let body = Body::from_file_with(&file.0, FileOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(body.mime(), Some(&mime::TEXT_PLAIN));
+    }
+}
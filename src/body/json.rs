@@ -0,0 +1,253 @@
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+use futures_lite::Stream;
+use serde::de::DeserializeOwned;
+
+use super::{Body, Error};
+
+// Incrementally decodes a sequence of JSON values, keeping only the bytes of the
+// value currently being parsed.
+struct JsonStream<T> {
+    body: Body,
+    buf: Vec<u8>,
+    // Buffer length at the last attempt that ran out of input. Parsing is retried
+    // once the buffer has doubled, the body is pending or the body has ended, so a
+    // large value is not re-parsed for every small chunk.
+    attempted: usize,
+    eof: bool,
+    done: bool,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Unpin for JsonStream<T> {}
+
+enum Attempt<T> {
+    Value(T),
+    Incomplete,
+    Failed(serde_json::Error),
+}
+
+impl<T: DeserializeOwned> JsonStream<T> {
+    fn attempt(&mut self) -> Attempt<T> {
+        let mut values = serde_json::Deserializer::from_slice(&self.buf).into_iter::<T>();
+        match values.next() {
+            Some(Ok(_)) if self.ends_with_number(values.byte_offset()) => {
+                self.attempted = self.buf.len();
+                Attempt::Incomplete
+            }
+            Some(Ok(value)) => {
+                let consumed = values.byte_offset();
+                self.buf.drain(..consumed);
+                self.attempted = 0;
+                Attempt::Value(value)
+            }
+            Some(Err(error)) if error.is_eof() && !self.eof => {
+                self.attempted = self.buf.len();
+                Attempt::Incomplete
+            }
+            Some(Err(error)) => Attempt::Failed(error),
+            None => {
+                // Only whitespace is left.
+                self.buf.clear();
+                self.attempted = 0;
+                Attempt::Incomplete
+            }
+        }
+    }
+
+    // A number running up to the end of the buffer may continue in the next chunk.
+    fn ends_with_number(&self, consumed: usize) -> bool {
+        !self.eof && consumed == self.buf.len() && self.buf[consumed - 1].is_ascii_digit()
+    }
+
+    fn finish(&mut self, error: Error) -> Poll<Option<Result<T, Error>>> {
+        self.done = true;
+        self.buf = Vec::new();
+        Poll::Ready(Some(Err(error)))
+    }
+}
+
+impl<T: DeserializeOwned> Stream for JsonStream<T> {
+    type Item = Result<T, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut due = false;
+        loop {
+            if self.done {
+                return Poll::Ready(None);
+            }
+
+            if !self.buf.is_empty() && (due || self.eof || self.buf.len() >= self.attempted * 2) {
+                match self.attempt() {
+                    Attempt::Value(value) => return Poll::Ready(Some(Ok(value))),
+                    Attempt::Failed(error) => return self.finish(error.into()),
+                    Attempt::Incomplete => {}
+                }
+            }
+            due = false;
+
+            if self.eof {
+                self.done = true;
+                return Poll::Ready(None);
+            }
+
+            match Pin::new(&mut self.body).poll_next(cx) {
+                Poll::Ready(Some(Ok(chunk))) => self.buf.extend_from_slice(&chunk),
+                Poll::Ready(Some(Err(error))) => return self.finish(error),
+                Poll::Ready(None) => self.eof = true,
+                Poll::Pending => {
+                    // Nothing more arrives for now: whatever was buffered since the
+                    // last attempt may already complete a value.
+                    if self.buf.len() > self.attempted {
+                        due = true;
+                        continue;
+                    }
+                    return Poll::Pending;
+                }
+            }
+        }
+    }
+}
+
+impl Body {
+    /// Converts the body into a stream of JSON values, deserialized as data arrives.
+    ///
+    /// The body may contain a single document or a sequence of documents separated by
+    /// whitespace, such as newline-delimited JSON. Only the bytes of the value currently
+    /// being decoded are buffered, so a long sequence is processed in bounded memory.
+    /// Whitespace before, between and after the documents is ignored.
+    ///
+    /// Use [`Body::into_json`] to deserialize a single document that borrows from the
+    /// buffered body.
+    ///
+    /// # Errors
+    ///
+    /// The stream yields [`BodyError::JsonError`](crate::BodyError::JsonError) if a
+    /// document is malformed, doesn't match `T` or is truncated by the end of the body.
+    /// Errors of the underlying body, such as an I/O error in the middle of a document,
+    /// are forwarded as is rather than reported as a JSON syntax error. The stream
+    /// terminates after the first error.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(feature = "json")]
+    /// # {
+    /// use http_kit::Body;
+    /// use futures_lite::{stream, StreamExt};
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Deserialize, PartialEq, Debug)]
+    /// struct Event {
+    ///     id: u32,
+    /// }
+    ///
+    /// # async fn example() -> Result<(), http_kit::BodyError> {
+    /// let chunks = stream::iter(vec![
+    ///     Ok::<_, std::io::Error>("{\"id\": 1}\n{\"i"),
+    ///     Ok("d\": 2}\n"),
+    /// ]);
+    /// let mut events = Body::from_stream(chunks).into_json_stream::<Event>();
+    ///
+    /// assert_eq!(events.next().await.unwrap()?, Event { id: 1 });
+    /// assert_eq!(events.next().await.unwrap()?, Event { id: 2 });
+    /// assert!(events.next().await.is_none());
+    /// # Ok(())
+    /// # }
+    /// # }
+    /// ```
+    pub fn into_json_stream<T>(self) -> impl Stream<Item = Result<T, Error>> + Send
+    where
+        T: DeserializeOwned,
+    {
+        JsonStream {
+            body: self,
+            buf: Vec::new(),
+            attempted: 0,
+            eof: false,
+            done: false,
+            _marker: PhantomData,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::{string::String, vec};
+    use core::task::Waker;
+    use futures_lite::{stream, StreamExt};
+    use serde::Deserialize;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Item {
+        name: String,
+    }
+
+    fn chunked(chunks: Vec<&'static str>) -> Body {
+        Body::from_stream(stream::iter(
+            chunks.into_iter().map(Ok::<_, core::convert::Infallible>),
+        ))
+    }
+
+    #[tokio::test]
+    async fn decodes_values_split_across_chunks() {
+        let body = chunked(vec![" [1, ", "2", "]\n", "[3]  \n\n"]);
+        let values: Vec<Vec<u32>> = body.into_json_stream().try_collect().await.unwrap();
+        assert_eq!(values, vec![vec![1, 2], vec![3]]);
+    }
+
+    #[tokio::test]
+    async fn single_document_with_trailing_whitespace() {
+        let body = chunked(vec!["{\"name\":", "\"alice\"}", " \r\n\t"]);
+        let mut items = body.into_json_stream::<Item>();
+        assert_eq!(items.next().await.unwrap().unwrap().name, "alice");
+        assert!(items.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn truncated_document_is_a_json_error() {
+        let body = chunked(vec!["{\"name\": \"al"]);
+        let mut items = body.into_json_stream::<Item>();
+        match items.next().await.unwrap() {
+            Err(Error::JsonError(error)) => assert!(error.is_eof()),
+            other => panic!("unexpected item: {other:?}"),
+        }
+        assert!(items.next().await.is_none());
+    }
+
+    #[cfg(feature = "std")]
+    #[tokio::test]
+    async fn io_error_mid_document_is_forwarded() {
+        extern crate std;
+        use std::io;
+
+        let chunks = stream::iter(vec![
+            Ok("{\"name\": \"al"),
+            Err(io::Error::new(io::ErrorKind::ConnectionReset, "reset")),
+        ]);
+        let mut items = Body::from_stream(chunks).into_json_stream::<Item>();
+        match items.next().await.unwrap() {
+            Err(Error::Io(error)) => assert_eq!(error.kind(), io::ErrorKind::ConnectionReset),
+            other => panic!("unexpected item: {other:?}"),
+        }
+        assert!(items.next().await.is_none());
+    }
+
+    #[test]
+    fn yields_complete_value_while_body_is_pending() {
+        let (sender, receiver) = async_channel::unbounded::<Result<&'static str, Error>>();
+        let mut items = Body::from_stream(receiver).into_json_stream::<u32>();
+        let mut cx = Context::from_waker(Waker::noop());
+
+        sender.try_send(Ok("4")).unwrap();
+        sender.try_send(Ok("2 ")).unwrap();
+        assert!(matches!(
+            Pin::new(&mut items).poll_next(&mut cx),
+            Poll::Ready(Some(Ok(42)))
+        ));
+        assert!(Pin::new(&mut items).poll_next(&mut cx).is_pending());
+    }
+}
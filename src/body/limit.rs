@@ -0,0 +1,85 @@
+use core::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use bytes::Bytes;
+use futures_lite::Stream;
+use http_body::{Frame, SizeHint};
+
+use super::{Body, Error};
+
+// Body wrapper failing with `Error::LimitExceeded` once more than `max` bytes are read.
+pub(super) struct Limited {
+    body: Body,
+    remaining: usize,
+    max: usize,
+    // Set when the known size exceeds the limit, failing before anything is read.
+    exceeded: bool,
+    done: bool,
+}
+
+impl Limited {
+    pub(super) fn new(body: Body, max: usize, exceeded: bool) -> Self {
+        Self {
+            body,
+            remaining: max,
+            max,
+            exceeded,
+            done: false,
+        }
+    }
+}
+
+impl http_body::Body for Limited {
+    type Data = Bytes;
+    type Error = Error;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        if self.done {
+            return Poll::Ready(None);
+        }
+        if self.exceeded {
+            self.done = true;
+            return Poll::Ready(Some(Err(Error::LimitExceeded(self.max))));
+        }
+        let frame = match Pin::new(&mut self.body).poll_nested(cx) {
+            Poll::Ready(Some(Ok(frame))) => frame,
+            Poll::Ready(Some(Err(error))) => {
+                self.done = true;
+                return Poll::Ready(Some(Err(error)));
+            }
+            Poll::Ready(None) => {
+                self.done = true;
+                return Poll::Ready(None);
+            }
+            Poll::Pending => return Poll::Pending,
+        };
+        // Trailers pass through and do not count against the limit.
+        if let Some(chunk) = frame.data_ref() {
+            if chunk.len() > self.remaining {
+                self.done = true;
+                return Poll::Ready(Some(Err(Error::LimitExceeded(self.max))));
+            }
+            self.remaining -= chunk.len();
+        }
+        Poll::Ready(Some(Ok(frame)))
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.done
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        // Never advertise more than can be read, so buffering does not over-allocate.
+        let (lower, upper) = Stream::size_hint(&self.body);
+        let cap = self.remaining as u64;
+        let mut hint = SizeHint::new();
+        hint.set_upper(upper.map_or(cap, |upper| (upper as u64).min(cap)));
+        hint.set_lower((lower as u64).min(cap));
+        hint
+    }
+}
@@ -74,32 +74,70 @@
 // # }
 // # Ok::<(), std::io::Error>(())
 // ```
+mod chain;
 mod convert;
+#[cfg(feature = "csv")]
+mod csv;
+mod data_url;
 mod error_type;
+#[cfg(all(feature = "fs", feature = "std"))]
+mod file;
+#[cfg(feature = "json")]
+mod json;
+mod limit;
+#[cfg(feature = "json")]
+mod ndjson;
+mod reader;
+mod stream;
 #[cfg(feature = "std")]
-mod utils;
+mod tee;
+mod text;
+mod trailers;
+mod unsync;
+
+#[cfg(feature = "encoding")]
+mod charset;
+
+#[cfg(feature = "csv")]
+pub use self::csv::CsvStream;
 use crate::sse::{Event, SseStream};
+#[cfg(feature = "encoding")]
+pub(crate) use charset::decode_lossy;
+pub use data_url::{DataUrlError, DEFAULT_DATA_URL_LIMIT};
 pub use error_type::Error;
+#[cfg(all(feature = "fs", feature = "std"))]
+pub use file::FileOptions;
+#[cfg(feature = "json")]
+pub use ndjson::NdjsonStream;
+pub use reader::BodyReader;
+pub use stream::BodyDataStream;
 #[cfg(feature = "std")]
 extern crate std;
 use futures_lite::{ready, Stream, StreamExt};
+use http::HeaderMap;
 use http_body::Frame;
-use http_body_util::{BodyExt, StreamBody};
+use http_body_util::{BodyExt, Collected, StreamBody};
 use mime::Mime;
 
-#[cfg(feature = "std")]
-use self::utils::IntoAsyncRead;
 use bytestr::ByteStr;
 
-use bytes::Bytes;
+use bytes::{Bytes, BytesMut};
 use futures_lite::{AsyncBufRead, AsyncBufReadExt};
 
-use alloc::{boxed::Box, vec::Vec};
+use alloc::{boxed::Box, collections::VecDeque, vec::Vec};
 use core::fmt::Debug;
+use core::future::Future;
 use core::mem::{replace, swap, take};
 use core::pin::Pin;
 use core::task::{Context, Poll};
 
+// Bytes a tee branch may buffer ahead of the slower one.
+#[cfg(feature = "std")]
+const DEFAULT_TEE_HIGH_WATER_MARK: usize = 64 << 10;
+
+// Default size of the chunks read from reader bodies.
+const DEFAULT_READER_CAPACITY: usize = 64 << 10;
+
 // A boxed bufreader object.
 type BoxBufReader = Pin<Box<dyn AsyncBufRead + Send + Sync + 'static>>;
 
@@ -139,26 +177,67 @@ pub use http_body::Body as HttpBody;
 pub struct Body {
     mime: Option<Mime>,
     inner: BodyInner,
+    // Trailers received while the body was read as a stream of data, boxed to keep
+    // bodies small.
+    trailers: Option<Box<HeaderMap>>,
 }
 
+// Prints the representation, the length when known and the MIME type, such as
+// `Body::Once { len: 13, mime: "application/json" }`, never the data.
 impl Debug for Body {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-        f.write_str("Body")
+        let (variant, len) = match &self.inner {
+            BodyInner::Once(bytes) => ("Body::Once", Some(bytes.len())),
+            BodyInner::Text(text) => ("Body::Once", Some(text.len())),
+            BodyInner::Reader { length, .. } => ("Body::Reader", *length),
+            BodyInner::HttpBody(body) => {
+                let len = body.size_hint().exact();
+                (
+                    "Body::HttpBody",
+                    len.and_then(|len| usize::try_from(len).ok()),
+                )
+            }
+            BodyInner::Chain { length, .. } => ("Body::Chain", *length),
+            BodyInner::Freeze => ("Body::Freeze", None),
+        };
+        let mut debug = f.debug_struct(variant);
+        if let Some(len) = len {
+            debug.field("len", &len);
+        }
+        if let Some(mime) = &self.mime {
+            debug.field("mime", &mime.as_ref());
+        }
+        debug.finish()
     }
 }
 
-impl_error!(
-    BodyFrozen,
+http_error!(
+    /// The error type of `BodyFrozen`.
+    pub BodyFrozen,
+    http::StatusCode::INTERNAL_SERVER_ERROR,
     "Body was frozen,it may have been consumed by `take()`"
 );
 
 enum BodyInner {
     Once(Bytes),
+    // Data in memory known to be valid UTF-8, so that it is validated only once.
+    Text(ByteStr),
     Reader {
         reader: BoxBufReader,
         length: Option<usize>,
+        // Chunks are read straight into this buffer and split off, so that they are
+        // never copied. Its length is the part of the room left that is already zeroed.
+        buf: BytesMut,
+        capacity: usize,
     },
     HttpBody(BoxHttpBody),
+    // Bodies joined by `Body::concat` or wrapped with a known length, with the length
+    // left to read if it is known, which a boxed body could not report to
+    // `Body::len`.
+    Chain {
+        body: BoxHttpBody,
+        length: Option<usize>,
+    },
     Freeze,
 }
 
@@ -186,6 +265,7 @@ impl Body {
         Self {
             mime: None,
             inner: BodyInner::Once(Bytes::new()),
+            trailers: None,
         }
     }
 
@@ -217,10 +297,8 @@ impl Body {
     {
         Self {
             mime: None,
-            inner: BodyInner::HttpBody(Box::pin(
-                body.map_frame(|result| result.map_data(|data| data.into()))
-                    .map_err(|e| e.into()),
-            )),
+            inner: BodyInner::HttpBody(Box::pin(Converted { body })),
+            trailers: None,
         }
     }
 
@@ -244,6 +322,7 @@ impl Body {
         Self {
             mime: None,
             inner: BodyInner::Freeze,
+            trailers: None,
         }
     }
 
@@ -254,6 +333,10 @@ impl Body {
     /// The optional length hint can improve performance for operations that
     /// benefit from knowing the total size.
     ///
+    /// When streamed, the body yields chunks of up to 64 KiB, read directly into the
+    /// buffers handed out. Use [`Body::from_reader_with_capacity`] to pick another
+    /// size.
+    ///
     /// You are responsible for setting the MIME type of the body.
     ///
     /// # Arguments
@@ -283,13 +366,55 @@ impl Body {
     pub fn from_reader(
         reader: impl AsyncBufRead + Send + Sync + 'static,
         length: impl Into<Option<usize>>,
+    ) -> Self {
+        Self::from_reader_with_capacity(reader, length, DEFAULT_READER_CAPACITY)
+    }
+
+    /// Creates a body from an async buffered reader that is `Send` but not `Sync`, like
+    /// [`Body::from_reader`].
+    ///
+    /// See [`Body::from_stream_unsync`] for why the reader is wrapped.
+    pub fn from_reader_unsync(
+        reader: impl AsyncBufRead + Send + 'static,
+        length: impl Into<Option<usize>>,
+    ) -> Self {
+        Self::from_reader(unsync::Unsync::new(reader), length)
+    }
+
+    /// Creates a body from an async buffered reader, like [`Body::from_reader`],
+    /// streamed in chunks of up to `capacity` bytes.
+    ///
+    /// Chunks are smaller when the reader returns less data at a time, or when
+    /// `length` says that less is left. A `capacity` of at least the size of the
+    /// internal buffer of the reader lets reads bypass that buffer.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use futures_lite::{io::{BufReader, Cursor}, StreamExt};
+    /// use http_kit::Body;
+    ///
+    /// # async fn example() -> Result<(), http_kit::BodyError> {
+    /// let reader = BufReader::new(Cursor::new(vec![7u8; 10_000]));
+    /// let mut body = Body::from_reader_with_capacity(reader, 10_000, 4096);
+    /// assert_eq!(body.next().await.unwrap()?.len(), 4096);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_reader_with_capacity(
+        reader: impl AsyncBufRead + Send + Sync + 'static,
+        length: impl Into<Option<usize>>,
+        capacity: usize,
     ) -> Self {
         Self {
             mime: None,
             inner: BodyInner::Reader {
                 reader: Box::pin(reader),
                 length: length.into(),
+                buf: BytesMut::new(),
+                capacity: capacity.max(1),
             },
+            trailers: None,
         }
     }
 
@@ -322,6 +447,21 @@ impl Body {
     /// let body = Body::from_stream(data_stream);
     /// # }
     /// ```
+    ///
+    /// Streams that are not `Sync` are rejected, see [`Body::from_stream_unsync`]:
+    ///
+    /// ```rust,compile_fail
+    /// use core::cell::Cell;
+    /// use futures_lite::stream;
+    /// use http_kit::Body;
+    ///
+    /// let polls = Cell::new(0);
+    /// let stream = stream::poll_fn(move |_| {
+    ///     polls.set(polls.get() + 1);
+    ///     core::task::Poll::Ready(None::<Result<&'static [u8], std::io::Error>>)
+    /// });
+    /// let body = Body::from_stream(stream);
+    /// ```
     pub fn from_stream<T, E, S>(stream: S) -> Self
     where
         T: Into<Bytes> + Send + 'static,
@@ -335,8 +475,81 @@ impl Body {
                     .map(|data| Frame::data(data.into()))
                     .map_err(|error| error.into())
             })))),
+            trailers: None,
         }
     }
+
+    /// Creates a body from a stream that is `Send` but not `Sync`, such as one holding
+    /// a `Cell` or a connection half, like [`Body::from_stream`].
+    ///
+    /// `Body` is `Sync`, so that a request can be borrowed across an `.await` in a
+    /// `Send` future. The stream is only ever polled through `Pin<&mut>`, so it is
+    /// wrapped instead of requiring `Sync`, at no cost.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use core::cell::Cell;
+    /// use futures_lite::stream;
+    /// use http_kit::Body;
+    ///
+    /// # async fn example() -> Result<(), http_kit::BodyError> {
+    /// let chunks = Cell::new(3u32);
+    /// let stream = stream::poll_fn(move |_| {
+    ///     let left = chunks.get();
+    ///     chunks.set(left.saturating_sub(1));
+    ///     core::task::Poll::Ready((left > 0).then(|| Ok::<_, std::io::Error>("ab")))
+    /// });
+    /// let body = Body::from_stream_unsync(stream);
+    /// assert_eq!(body.into_string().await?, "ababab");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_stream_unsync<T, E, S>(stream: S) -> Self
+    where
+        T: Into<Bytes> + Send + 'static,
+        E: Into<Error>,
+        S: Stream<Item = Result<T, E>> + Send + 'static,
+    {
+        Self::from_stream(unsync::Unsync::new(stream))
+    }
+    /// Creates a streaming body followed by trailers.
+    ///
+    /// `trailers` is polled once the stream is exhausted, so its value can depend on the
+    /// data, such as a checksum or a final status. The trailers are sent as the last
+    /// frame of the body, after all data.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use futures_lite::stream;
+    /// use http::HeaderMap;
+    /// use http_kit::Body;
+    ///
+    /// # async fn example() -> Result<(), http_kit::BodyError> {
+    /// let chunks = stream::iter(vec![Ok::<_, std::io::Error>("hello")]);
+    /// let body = Body::from_stream_with_trailers(chunks, async {
+    ///     let mut trailers = HeaderMap::new();
+    ///     trailers.insert("grpc-status", "0".parse().unwrap());
+    ///     trailers
+    /// });
+    ///
+    /// let (data, trailers) = body.into_bytes_with_trailers().await?;
+    /// assert_eq!(data, "hello");
+    /// assert_eq!(trailers.unwrap()["grpc-status"], "0");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_stream_with_trailers<T, E, S, F>(stream: S, trailers: F) -> Self
+    where
+        T: Into<Bytes> + Send + 'static,
+        E: Into<Error>,
+        S: Stream<Item = Result<T, E>> + Send + Sync + 'static,
+        F: Future<Output = HeaderMap> + Send + Sync + 'static,
+    {
+        Self::from_stream(stream).attach_trailers(Box::pin(trailers))
+    }
+
     /// Creates a body from bytes or byte-like data.
     ///
     /// This method accepts any type that can be converted to `Bytes`,
@@ -363,6 +576,7 @@ impl Body {
         Self {
             mime: Some(mime::APPLICATION_OCTET_STREAM),
             inner: BodyInner::Once(data.into()),
+            trailers: None,
         }
     }
 
@@ -390,15 +604,18 @@ impl Body {
     pub fn from_text(str: impl Into<ByteStr>) -> Self {
         Self {
             mime: Some(mime::TEXT_PLAIN_UTF_8),
-            inner: BodyInner::Once(str.into().into()),
+            inner: BodyInner::Text(str.into()),
+            trailers: None,
         }
     }
 
     /// Creates a body by streaming the contents of a file.
     ///
     /// This method opens a file and creates a streaming body that reads
-    /// the file contents on demand. The file size is determined automatically
-    /// and used as a length hint for optimization.
+    /// the file contents on demand, in chunks of up to 64 KiB. The file size is
+    /// determined automatically and used as a length hint for optimization.
+    ///
+    /// Use [`Body::from_file_with`] to stream part of the file or tune the chunk size.
     ///
     /// # Arguments
     ///
@@ -425,22 +642,46 @@ impl Body {
     /// ```
     #[cfg(all(feature = "fs", feature = "std"))]
     pub async fn from_file(path: impl AsRef<std::path::Path>) -> Result<Self, std::io::Error> {
-        let path = path.as_ref();
-        let file = async_fs::File::open(path).await?;
-        let len = file.metadata().await?.len() as usize;
-        let mime = if let Some(ext) = path.extension() {
-            if let Some(ext_str) = ext.to_str() {
-                Self::guess(ext_str.as_bytes()).and_then(|m| m.parse().ok())
-            } else {
-                None
-            }
-        } else {
-            None
-        };
-        Ok(Self {
-            mime,
-            ..Self::from_reader(futures_lite::io::BufReader::new(file), len)
-        })
+        file::open(path.as_ref(), FileOptions::default()).await
+    }
+
+    /// Creates a body by streaming a window of a file.
+    ///
+    /// Streaming starts at `options.offset` and stops after `options.length` bytes or
+    /// at the end of the file, whichever comes first; the body length is the length of
+    /// that window. An offset past the end of the file yields an empty body. Chunks are
+    /// at most `options.buffer_size` bytes long.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `std::io::Error` if the file cannot be opened, its metadata cannot be
+    /// read or it cannot be seeked to the offset.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # #[cfg(feature = "fs")]
+    /// # {
+    /// use http_kit::{Body, FileOptions};
+    ///
+    /// # async fn example() -> Result<(), std::io::Error> {
+    /// let options = FileOptions {
+    ///     offset: 1 << 20,
+    ///     length: Some(1 << 20),
+    ///     ..FileOptions::default()
+    /// };
+    /// let body = Body::from_file_with("movie.mp4", options).await?;
+    /// assert!(body.len().unwrap() <= 1 << 20);
+    /// # Ok(())
+    /// # }
+    /// # }
+    /// ```
+    #[cfg(all(feature = "fs", feature = "std"))]
+    pub async fn from_file_with(
+        path: impl AsRef<std::path::Path>,
+        options: FileOptions,
+    ) -> Result<Self, std::io::Error> {
+        file::open(path.as_ref(), options).await
     }
 
     /// Creates a body by serializing an object to JSON.
@@ -486,14 +727,51 @@ impl Body {
     pub fn from_json<T: serde::Serialize>(value: T) -> Result<Self, serde_json::Error> {
         Ok(Self {
             mime: Some(mime::APPLICATION_JSON),
-            ..Self::from_bytes(serde_json::to_string(&value)?)
+            ..Self::from_bytes(serde_json::to_vec(&value)?)
+        })
+    }
+
+    /// Creates a body by serializing an object to indented, human-readable JSON.
+    ///
+    /// Like [`Body::from_json`], the MIME type is set to `application/json`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `serde_json::Error` if serialization fails.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(feature = "json")]
+    /// # {
+    /// use http_kit::Body;
+    ///
+    /// let body = Body::from_json_pretty(serde_json::json!({ "id": 7 }))?;
+    /// assert_eq!(body.mime(), Some(&mime::APPLICATION_JSON));
+    /// # }
+    /// # Ok::<(), serde_json::Error>(())
+    /// ```
+    #[cfg(feature = "json")]
+    pub fn from_json_pretty<T: serde::Serialize>(value: T) -> Result<Self, serde_json::Error> {
+        Ok(Self {
+            mime: Some(mime::APPLICATION_JSON),
+            ..Self::from_bytes(serde_json::to_vec_pretty(&value)?)
         })
     }
 
-    #[cfg(feature = "fs")]
-    fn guess(extension: &[u8]) -> Option<&'static str> {
-        let s = core::str::from_utf8(extension).ok()?;
-        mime_guess::from_ext(s).first_raw()
+    /// Creates a body by serializing a dynamic JSON value.
+    ///
+    /// The MIME type is set to `application/json`. Unlike [`Body::from_json`], this
+    /// cannot fail, as the map keys of a `Value` are always strings.
+    #[cfg(feature = "json")]
+    pub fn from_json_value(value: &serde_json::Value) -> Self {
+        // Serializing a `Value` into memory cannot fail.
+        let json = serde_json::to_vec(value).unwrap_or_default();
+        Self {
+            mime: Some(mime::APPLICATION_JSON),
+            inner: BodyInner::Once(json.into()),
+            trailers: None,
+        }
     }
 
     /// Creates a body by serializing an object to URL-encoded form data.
@@ -548,6 +826,9 @@ impl Body {
     /// for HTTP responses. The events are formatted according to the SSE specification
     /// and can be consumed by EventSource clients.
     ///
+    /// Each event is sent as one frame. Use [`SseBody`](crate::sse::SseBody) directly to
+    /// send periodic keep-alive comments.
+    ///
     /// # Type Parameters
     ///
     /// * `S` - Stream type yielding `Result<Event, E>`
@@ -573,14 +854,7 @@ impl Body {
         S: Stream<Item = Result<Event, E>> + Send + Sync + 'static,
         E: Into<Error> + Send + Sync + 'static,
     {
-        Self {
-            mime: Some(mime::TEXT_EVENT_STREAM),
-            inner: BodyInner::HttpBody(Box::pin(
-                crate::sse::into_body(s)
-                    .map_frame(|result| result.map_data(|data| data))
-                    .map_err(|e| e.into()),
-            )),
-        }
+        crate::sse::SseBody::new(s).into()
     }
 
     /// Returns the MIME type of the body, if known.
@@ -594,6 +868,96 @@ impl Body {
         self
     }
 
+    /// Sends `trailers` after the data of the body.
+    ///
+    /// Trailers already sent by the body are kept, except those replaced by `trailers`.
+    /// They are sent through the `http_body::Body` implementation and returned by
+    /// [`Body::into_bytes_with_trailers`]; streaming the body as bytes skips them, and
+    /// [`Body::take_trailers`] returns them afterwards.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use http::HeaderMap;
+    /// use http_kit::Body;
+    ///
+    /// let mut trailers = HeaderMap::new();
+    /// trailers.insert("grpc-status", "0".parse().unwrap());
+    /// let body = Body::from_bytes("payload").with_trailers(trailers);
+    /// ```
+    pub fn with_trailers(self, trailers: HeaderMap) -> Self {
+        self.attach_trailers(Box::pin(core::future::ready(trailers)))
+    }
+
+    fn attach_trailers(self, trailers: trailers::BoxTrailers) -> Self {
+        Self {
+            mime: self.mime.clone(),
+            inner: BodyInner::HttpBody(Box::pin(trailers::Trailed::new(self, trailers))),
+            trailers: None,
+        }
+    }
+
+    /// Caps the number of bytes that can be read from the body.
+    ///
+    /// Reading more than `max_bytes`, whether through [`Body::into_bytes`] and the
+    /// conversions built on it or through the `Stream` and `http_body::Body`
+    /// implementations, fails with [`Error::LimitExceeded`]. A body whose known length
+    /// already exceeds the limit fails before anything is read.
+    ///
+    /// Streaming bodies report an unknown [`len`](Body::len) once limited.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use http_kit::{Body, BodyError};
+    ///
+    /// # async fn example() {
+    /// let body = Body::from_bytes("0123456789").limit(4);
+    /// assert!(matches!(body.into_bytes().await, Err(BodyError::LimitExceeded(4))));
+    /// # }
+    /// ```
+    pub fn limit(self, max_bytes: usize) -> Self {
+        let exceeded = match &self.inner {
+            BodyInner::Freeze => return self,
+            BodyInner::Once(bytes) if bytes.len() <= max_bytes => return self,
+            BodyInner::Text(text) if text.len() <= max_bytes => return self,
+            _ => {
+                self.len().is_some_and(|len| len > max_bytes)
+                    || Stream::size_hint(&self).0 > max_bytes
+            }
+        };
+        let mime = self.mime.clone();
+        Self {
+            mime,
+            inner: BodyInner::HttpBody(Box::pin(limit::Limited::new(self, max_bytes, exceeded))),
+            trailers: None,
+        }
+    }
+
+    /// Caps the number of bytes that can be read from the body, like
+    /// [`http_body_util::Limited`], for limits given in `u64` like those of
+    /// [`http_body::SizeHint`].
+    ///
+    /// This is [`Body::limit`] with a `u64` limit, and limits beyond `usize::MAX` are
+    /// capped to it. It goes through `limit` rather than wrapping `Limited`, so that
+    /// bodies whose known length is over the limit fail before anything is read, and
+    /// reading past `max_bytes` fails with [`Error::LimitExceeded`] rather than with the
+    /// boxed [`LengthLimitError`](http_body_util::LengthLimitError) of `Limited`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use http_kit::{Body, BodyError};
+    ///
+    /// # async fn example() {
+    /// let body = Body::from_bytes("0123456789").limited(4);
+    /// assert!(matches!(body.into_bytes().await, Err(BodyError::LimitExceeded(4))));
+    /// # }
+    /// ```
+    pub fn limited(self, max_bytes: u64) -> Self {
+        self.limit(usize::try_from(max_bytes).unwrap_or(usize::MAX))
+    }
+
     /// Returns the length of the body in bytes, if known.
     ///
     /// This method returns `Some(length)` for in-memory bodies where the size
@@ -619,7 +983,8 @@ impl Body {
     pub const fn len(&self) -> Option<usize> {
         match &self.inner {
             BodyInner::Once(bytes) => Some(bytes.len()),
-            BodyInner::Reader { length, .. } => *length,
+            BodyInner::Text(text) => Some(text.len()),
+            BodyInner::Reader { length, .. } | BodyInner::Chain { length, .. } => *length,
             _ => None,
         }
     }
@@ -679,9 +1044,13 @@ impl Body {
     /// # }
     /// ```
     pub async fn into_bytes(self) -> Result<Bytes, Error> {
+        stat!(bodies_buffered);
         match self.inner {
             BodyInner::Once(bytes) => Ok(bytes),
-            BodyInner::Reader { mut reader, length } => {
+            BodyInner::Text(text) => Ok(text.into_bytes()),
+            BodyInner::Reader {
+                mut reader, length, ..
+            } => {
                 let mut vec = Vec::with_capacity(length.unwrap_or_default());
                 loop {
                     let data = reader.fill_buf().await?;
@@ -696,32 +1065,86 @@ impl Body {
                 Ok(vec.into())
             }
 
-            BodyInner::HttpBody(body) => {
+            BodyInner::HttpBody(body) | BodyInner::Chain { body, .. } => {
                 let mut body = body.into_data_stream();
-
-                let first = body.try_next().await?.unwrap_or_default();
-                let second = body.try_next().await?;
-                if let Some(second) = second {
-                    let remain_size_hint = body.size_hint();
-                    let mut vec = Vec::with_capacity(
-                        first.len()
-                            + second.len()
-                            + remain_size_hint.1.unwrap_or(remain_size_hint.0),
-                    );
-                    vec.extend_from_slice(&first);
-                    vec.extend_from_slice(&second);
-                    while let Some(data) = body.try_next().await? {
-                        vec.extend_from_slice(&data);
+                let mut chunks = Vec::new();
+                while let Some(data) = body.try_next().await? {
+                    if !data.is_empty() {
+                        chunks.push(data);
                     }
-                    Ok(vec.into())
-                } else {
-                    Ok(first)
                 }
+                Ok(concat(chunks))
             }
             BodyInner::Freeze => Err(Error::BodyFrozen),
         }
     }
 
+    /// Consumes the body and returns its data and trailers as an
+    /// [`http_body_util::Collected`], which can be turned back into a body with its
+    /// trailers.
+    ///
+    /// Trailers already received while the body was read as a stream are included.
+    /// This method takes precedence over `StreamExt::collect` and `BodyExt::collect`,
+    /// which stay available through their traits, as in `StreamExt::collect(body)`.
+    ///
+    /// # Errors
+    ///
+    /// Fails like [`Body::into_bytes`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use http::HeaderMap;
+    /// use http_kit::Body;
+    ///
+    /// # async fn example() -> Result<(), http_kit::BodyError> {
+    /// let mut trailers = HeaderMap::new();
+    /// trailers.insert("grpc-status", "0".parse().unwrap());
+    /// let body = Body::from_bytes("payload").with_trailers(trailers);
+    /// let collected = body.collect().await?;
+    /// assert_eq!(collected.trailers().unwrap()["grpc-status"], "0");
+    ///
+    /// let mut body = Body::from(collected);
+    /// assert_eq!(body.trailers().await?.unwrap()["grpc-status"], "0");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn collect(self) -> Result<Collected<Bytes>, Error> {
+        BodyExt::collect(self).await
+    }
+
+    /// Consumes the body and returns all its data along with its trailers, if it sent
+    /// any.
+    ///
+    /// Repeated trailer frames are merged into one map, along with the trailers already
+    /// received while the body was read as a stream.
+    ///
+    /// # Errors
+    ///
+    /// Fails like [`Body::into_bytes`].
+    pub async fn into_bytes_with_trailers(mut self) -> Result<(Bytes, Option<HeaderMap>), Error> {
+        let mut trailers = self.take_trailers();
+        // The wrapped body is read directly, so that the body is not counted as streamed.
+        let mut body = match self.inner {
+            BodyInner::HttpBody(body) | BodyInner::Chain { body, .. } => body,
+            inner => return Ok((Self { inner, ..self }.into_bytes().await?, trailers)),
+        };
+        stat!(bodies_buffered);
+        let mut data = Vec::new();
+        while let Some(frame) = BodyExt::frame(&mut body).await {
+            match frame?.into_data() {
+                Ok(chunk) if chunk.is_empty() => {}
+                Ok(chunk) => data.push(chunk),
+                Err(frame) => {
+                    if let Ok(received) = frame.into_trailers() {
+                        trailers.get_or_insert_with(HeaderMap::new).extend(received);
+                    }
+                }
+            }
+        }
+        Ok((concat(data), trailers))
+    }
+
     /// Consumes the body and returns its data as a UTF-8 string.
     ///
     /// This method reads the entire body into memory and converts it to a
@@ -748,14 +1171,17 @@ impl Body {
     /// # }
     /// ```
     pub async fn into_string(self) -> Result<ByteStr, Error> {
+        if let BodyInner::Text(text) = self.inner {
+            return Ok(text);
+        }
         Ok(ByteStr::from_utf8(self.into_bytes().await?)?)
     }
 
     /// Converts the body into an async buffered reader.
     ///
-    /// This method wraps the body in a type that implements `AsyncBufRead`,
-    /// allowing it to be used anywhere that expects an async reader. This is
-    /// useful for streaming the body data to other async I/O operations.
+    /// The returned [`BodyReader`] implements `AsyncRead` and `AsyncBufRead`, so the
+    /// body can be passed to anything expecting an async reader. In-memory and
+    /// streamed bodies are read without copying their chunks.
     ///
     /// # Examples
     ///
@@ -772,92 +1198,584 @@ impl Body {
     /// # Ok(())
     /// # }
     /// ```
-    #[cfg(feature = "std")]
-    pub fn into_reader(self) -> impl AsyncBufRead + Send {
-        IntoAsyncRead::new(self)
+    pub fn into_reader(self) -> BodyReader {
+        BodyReader::new(self)
     }
 
-    /// Converts the body into a Server-Sent Events (SSE) stream.
+    /// Converts the body into a stream of its data chunks, skipping trailers.
     ///
-    /// This method transforms the body into a stream of SSE events, which can be used
-    /// to handle eventsource responses in HTTP servers or clients.
-    pub fn into_sse(self) -> SseStream {
-        SseStream::new(self)
+    /// See [`BodyDataStream`].
+    pub fn into_data_stream(self) -> BodyDataStream {
+        BodyDataStream::new(self)
     }
 
-    /// Returns a reference to the body data as bytes.
-    ///
-    /// This method ensures the body data is available as a byte slice and returns
-    /// a reference to it. For streaming bodies, this will consume and buffer all
-    /// data in memory. The body is modified to store the buffered data internally.
-    ///
-    /// # Errors
+    /// Applies `f` to every data chunk of the body, as it is read.
     ///
-    /// Returns an error if:
-    /// - The body is frozen (already consumed)
-    /// - An I/O error occurs while reading streaming data
+    /// Nothing is buffered: streaming bodies stay streaming, and bodies in memory are
+    /// passed to `f` as a single chunk. Since `f` may change the size of the chunks,
+    /// the length of the new body is unknown. The MIME type and trailers are kept.
     ///
     /// # Examples
     ///
     /// ```rust
+    /// use bytes::Bytes;
     /// use http_kit::Body;
     ///
     /// # async fn example() -> Result<(), http_kit::BodyError> {
-    /// let mut body = Body::from_bytes("Hello, world!");
-    /// let bytes = body.as_bytes().await?;
-    /// assert_eq!(bytes, b"Hello, world!");
+    /// let body = Body::from_text("token=s3cr3t").map_chunks(|chunk| {
+    ///     Bytes::from(String::from_utf8_lossy(&chunk).replace("s3cr3t", "***"))
+    /// });
+    /// assert_eq!(body.len(), None);
+    /// assert_eq!(body.into_string().await?, "token=***");
     /// # Ok(())
     /// # }
     /// ```
-    pub async fn as_bytes(&mut self) -> Result<&[u8], Error> {
-        self.inner = BodyInner::Once(self.take()?.into_bytes().await?);
-        match self.inner {
-            BodyInner::Once(ref bytes) => Ok(bytes),
-            _ => unreachable!(),
+    pub fn map_chunks<F>(self, f: F) -> Self
+    where
+        F: FnMut(Bytes) -> Bytes + Send + Sync + 'static,
+    {
+        Self {
+            mime: self.mime.clone(),
+            ..Self::new(stream::MapChunks::new(self, f))
         }
     }
 
-    /// Returns a reference to the body data as a UTF-8 string slice.
-    ///
-    /// This method ensures the body data is available as a string slice and returns
-    /// a reference to it. For streaming bodies, this will consume and buffer all
-    /// data in memory first. The body is modified to store the buffered data internally.
-    ///
-    /// # Errors
+    /// Calls `callback` with the number of bytes read so far each time a data chunk of
+    /// the body is read, whichever way the body is consumed.
     ///
-    /// Returns an error if:
-    /// - The body is frozen (already consumed)
-    /// - An I/O error occurs while reading streaming data
-    /// - The body contains invalid UTF-8 sequences
+    /// Bodies in memory are read as a single chunk. The MIME type and the length are
+    /// kept, but the body is streamed, so [`Body::try_clone`] no longer sees its data.
     ///
     /// # Examples
     ///
     /// ```rust
+    /// use std::sync::atomic::{AtomicU64, Ordering};
+    /// use std::sync::Arc;
     /// use http_kit::Body;
     ///
     /// # async fn example() -> Result<(), http_kit::BodyError> {
-    /// let mut body = Body::from_bytes("Hello, world!");
-    /// let text = body.as_str().await?;
-    /// assert_eq!(text, "Hello, world!");
+    /// let read = Arc::new(AtomicU64::new(0));
+    /// let counter = read.clone();
+    /// let body = Body::from_bytes("payload")
+    ///     .with_progress(move |bytes| counter.store(bytes, Ordering::Relaxed));
+    /// body.into_bytes().await?;
+    /// assert_eq!(read.load(Ordering::Relaxed), 7);
     /// # Ok(())
     /// # }
     /// ```
-    pub async fn as_str(&mut self) -> Result<&str, Error> {
-        let data = self.as_bytes().await?;
-        Ok(core::str::from_utf8(data)?)
+    pub fn with_progress<F>(self, callback: F) -> Self
+    where
+        F: Fn(u64) + Send + Sync + 'static,
+    {
+        let length = self.len();
+        Self {
+            mime: self.mime.clone(),
+            ..Self::with_length(stream::Progress::new(self, callback), length)
+        }
     }
 
-    /// Deserializes the body data as JSON into the specified type.
+    // Boxes `body`, whose length is known to be `length` if it is some, so that
+    // `Body::len` still reports it.
+    pub(crate) fn with_length(
+        body: impl http_body::Body<Data = Bytes, Error = Error> + Send + Sync + 'static,
+        length: Option<usize>,
+    ) -> Self {
+        Self {
+            mime: None,
+            inner: BodyInner::Chain {
+                body: Box::pin(body),
+                length,
+            },
+            trailers: None,
+        }
+    }
+
+    /// Sends the data of `next` after the data of this body.
+    ///
+    /// See [`Body::concat`], of which this is the two-body case. This method takes
+    /// precedence over [`StreamExt::chain`](futures_lite::StreamExt::chain), which
+    /// stays available as `StreamExt::chain(body, other)`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use http_kit::Body;
+    ///
+    /// # async fn example() -> Result<(), http_kit::BodyError> {
+    /// let body = Body::from_text("Hello, ").chain(Body::from_bytes("world!"));
+    /// assert_eq!(body.len(), Some(13));
+    /// assert_eq!(body.into_string().await?, "Hello, world!");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn chain(self, next: Body) -> Self {
+        Self::concat([self, next])
+    }
+
+    /// Joins `bodies` into one body sending the data of each of them in turn.
+    ///
+    /// The new body has the MIME type of the first body, and its length is the sum of
+    /// their lengths if all of them are known. Bodies in memory are joined right away,
+    /// others are read one after the other, their trailers merged and sent at the end.
+    /// A frozen body fails once it is reached, after the data of the bodies before it.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use futures_lite::stream;
+    /// use http_kit::{Body, BodyError};
+    ///
+    /// # async fn example() -> Result<(), BodyError> {
+    /// let rows = stream::iter([Ok::<_, BodyError>("1,2\n"), Ok("3,4\n")]);
+    /// let csv = Body::concat([
+    ///     Body::from_text("a,b\n"),
+    ///     Body::from_stream(rows),
+    ///     Body::from_text("5,6\n"),
+    /// ]);
+    /// assert_eq!(csv.len(), None);
+    /// assert_eq!(csv.into_string().await?, "a,b\n1,2\n3,4\n5,6\n");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn concat(bodies: impl IntoIterator<Item = Body>) -> Self {
+        let mut bodies: VecDeque<Body> = bodies.into_iter().collect();
+        let Some(first) = bodies.front() else {
+            return Self::empty();
+        };
+        let mime = first.mime.clone();
+        if bodies.len() == 1 {
+            return bodies.pop_front().unwrap_or_default();
+        }
+        let in_memory = bodies.iter().all(|body| {
+            body.trailers.is_none() && matches!(body.inner, BodyInner::Once(_) | BodyInner::Text(_))
+        });
+        if in_memory {
+            let chunks = bodies.into_iter().map(|body| match body.inner {
+                BodyInner::Once(bytes) => bytes,
+                BodyInner::Text(text) => text.into_bytes(),
+                _ => unreachable!("bodies in memory are checked above"),
+            });
+            return Self {
+                mime,
+                inner: BodyInner::Once(concat(chunks.filter(|chunk| !chunk.is_empty()).collect())),
+                trailers: None,
+            };
+        }
+        let length = bodies
+            .iter()
+            .try_fold(0usize, |total, body| total.checked_add(body.len()?));
+        Self {
+            mime,
+            inner: BodyInner::Chain {
+                body: Box::pin(chain::Chain::new(bodies)),
+                length,
+            },
+            trailers: None,
+        }
+    }
+
+    /// Converts the body into a Server-Sent Events (SSE) stream.
+    ///
+    /// This method transforms the body into a stream of SSE events, which can be used
+    /// to handle eventsource responses in HTTP servers or clients.
+    pub fn into_sse(self) -> SseStream {
+        SseStream::new(self)
+    }
+
+    /// Returns a reference to the body data as bytes.
+    ///
+    /// This method ensures the body data is available as a byte slice and returns
+    /// a reference to it. For streaming bodies, this will consume and buffer all
+    /// data in memory. The body is modified to store the buffered data internally.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The body is frozen (already consumed)
+    /// - An I/O error occurs while reading streaming data
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use http_kit::Body;
+    ///
+    /// # async fn example() -> Result<(), http_kit::BodyError> {
+    /// let mut body = Body::from_bytes("Hello, world!");
+    /// let bytes = body.as_bytes().await?;
+    /// assert_eq!(bytes, b"Hello, world!");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn as_bytes(&mut self) -> Result<&[u8], Error> {
+        // Data in memory is kept as it is, so text stays validated.
+        if !matches!(self.inner, BodyInner::Once(_) | BodyInner::Text(_)) {
+            let body = self.take()?;
+            let mime = body.mime.clone();
+            let (data, trailers) = body.into_bytes_with_trailers().await?;
+            self.mime = mime;
+            self.inner = BodyInner::Once(data);
+            self.trailers = trailers.map(Box::new);
+        }
+        match &self.inner {
+            BodyInner::Once(bytes) => Ok(bytes),
+            BodyInner::Text(text) => Ok(text.as_bytes()),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Takes the trailers received so far while the body was read as a [`Stream`] of
+    /// data, or buffered by [`Body::as_bytes`].
+    ///
+    /// Reading a body as a stream skips its trailer frames, and repeated ones are merged
+    /// into one map, so this returns them once the stream has ended. It returns `None`
+    /// if the body sent no trailers, or if they were already taken. Trailers that are
+    /// not taken are sent after the data by the `http_body::Body` implementation.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use futures_lite::{stream, StreamExt};
+    /// use http::HeaderMap;
+    /// use http_kit::Body;
+    ///
+    /// # async fn example() -> Result<(), http_kit::BodyError> {
+    /// let chunks = stream::iter([Ok::<_, std::io::Error>("data")]);
+    /// let mut body = Body::from_stream_with_trailers(chunks, async {
+    ///     let mut trailers = HeaderMap::new();
+    ///     trailers.insert("grpc-status", "0".parse().unwrap());
+    ///     trailers
+    /// });
+    /// while let Some(chunk) = body.try_next().await? {
+    ///     assert_eq!(chunk, "data");
+    /// }
+    /// assert_eq!(body.take_trailers().unwrap()["grpc-status"], "0");
+    /// assert!(body.take_trailers().is_none());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn take_trailers(&mut self) -> Option<HeaderMap> {
+        self.trailers.take().map(|trailers| *trailers)
+    }
+
+    /// Buffers the body and returns a copy of its trailers, if it sent any.
+    ///
+    /// The data stays readable, with the trailers attached again, so the body can still
+    /// be sent or read afterwards.
+    ///
+    /// # Errors
+    ///
+    /// Fails like [`Body::into_bytes`].
+    pub async fn trailers(&mut self) -> Result<Option<HeaderMap>, Error> {
+        let mime = self.mime.clone();
+        let (data, trailers) = self.take()?.into_bytes_with_trailers().await?;
+        let mut body = Self {
+            mime,
+            inner: BodyInner::Once(data),
+            trailers: None,
+        };
+        if let Some(trailers) = &trailers {
+            body = body.with_trailers(trailers.clone());
+        }
+        *self = body;
+        Ok(trailers)
+    }
+
+    /// Returns a copy of the body if that is cheap, that is, if its data is already in
+    /// memory. Streaming and frozen bodies return `None`.
+    ///
+    /// Buffer the body first, for instance with [`Body::as_bytes`], or split it with
+    /// [`Body::tee`] to copy a streaming body.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use http_kit::Body;
+    ///
+    /// let body = Body::from_text("hello");
+    /// let copy = body.try_clone().unwrap();
+    /// assert_eq!(copy.mime(), body.mime());
+    ///
+    /// let stream = Body::from_stream(futures_lite::stream::iter([Ok::<_, std::io::Error>("a")]));
+    /// assert!(stream.try_clone().is_none());
+    /// ```
+    pub fn try_clone(&self) -> Option<Self> {
+        match &self.inner {
+            BodyInner::Once(bytes) => Some(Self {
+                mime: self.mime.clone(),
+                inner: BodyInner::Once(bytes.clone()),
+                trailers: self.trailers.clone(),
+            }),
+            BodyInner::Text(text) => Some(Self {
+                mime: self.mime.clone(),
+                inner: BodyInner::Text(text.clone()),
+                trailers: self.trailers.clone(),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Splits the body into two bodies yielding the same data and trailers.
+    ///
+    /// Equivalent to [`Body::tee_with`] with a high-water mark of 64 KiB.
+    #[cfg(feature = "std")]
+    pub fn tee(self) -> (Self, Self) {
+        self.tee_with(DEFAULT_TEE_HIGH_WATER_MARK)
+    }
+
+    /// Splits the body into two bodies yielding the same data and trailers, for
+    /// instance to log a request body while the endpoint reads it.
+    ///
+    /// Chunks read by one half are kept until the other half reads them. Once the slower
+    /// half is `high_water_mark` bytes behind, the faster one waits for it, so at most
+    /// about that much data is buffered. Dropping one half lets the other read at its
+    /// own pace. A source error is returned to both halves.
+    ///
+    /// Bodies already in memory are simply cloned.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use futures_lite::stream;
+    /// use http_kit::Body;
+    ///
+    /// # async fn example() -> Result<(), http_kit::BodyError> {
+    /// let chunks = stream::iter([Ok::<_, std::io::Error>("hello, "), Ok("world")]);
+    /// let (left, right) = Body::from_stream(chunks).tee_with(1024);
+    /// let (left, right) = futures_lite::future::zip(left.into_bytes(), right.into_bytes()).await;
+    /// assert_eq!(left?, "hello, world");
+    /// assert_eq!(right?, "hello, world");
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn tee_with(self, high_water_mark: usize) -> (Self, Self) {
+        if let Some(copy) = self.try_clone() {
+            return (self, copy);
+        }
+        let mime = self.mime.clone();
+        let (left, right) = tee::tee(self, high_water_mark);
+        (
+            Self {
+                mime: mime.clone(),
+                ..Self::new(left)
+            },
+            Self {
+                mime,
+                ..Self::new(right)
+            },
+        )
+    }
+
+    /// Returns a reference to the body data as a UTF-8 string slice.
+    ///
+    /// This method ensures the body data is available as a string slice and returns
+    /// a reference to it. For streaming bodies, this will consume and buffer all
+    /// data in memory first. The body is modified to store the buffered data internally.
+    ///
+    /// The data is validated once: later calls, and [`Body::into_string`], reuse the
+    /// result, and bodies created from text start validated.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The body is frozen (already consumed)
+    /// - An I/O error occurs while reading streaming data
+    /// - The body contains invalid UTF-8 sequences
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use http_kit::Body;
+    ///
+    /// # async fn example() -> Result<(), http_kit::BodyError> {
+    /// let mut body = Body::from_bytes("Hello, world!");
+    /// let text = body.as_str().await?;
+    /// assert_eq!(text, "Hello, world!");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn as_str(&mut self) -> Result<&str, Error> {
+        self.as_bytes().await?;
+        if let BodyInner::Once(bytes) = &self.inner {
+            self.inner = BodyInner::Text(ByteStr::from_utf8(bytes.clone())?);
+        }
+        match &self.inner {
+            BodyInner::Text(text) => Ok(text.as_str()),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Returns up to `len` bytes from the start of the body without consuming it.
+    ///
+    /// Streaming bodies are read until at least `len` bytes are buffered or the stream
+    /// ends; the buffered chunks are then put back in front of the unread remainder, so
+    /// later consumers still observe the complete body. The returned prefix is shorter
+    /// than `len` only if the body is.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The body is frozen (already consumed)
+    /// - An I/O error occurs while reading streaming data
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use http_kit::Body;
+    ///
+    /// # async fn example() -> Result<(), http_kit::BodyError> {
+    /// let mut body = Body::from_bytes("Hello, world!");
+    /// assert_eq!(body.peek(5).await?, "Hello");
+    /// assert_eq!(body.into_bytes().await?, "Hello, world!");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn peek(&mut self, len: usize) -> Result<Bytes, Error> {
+        match &self.inner {
+            BodyInner::Once(bytes) => return Ok(bytes.slice(..len.min(bytes.len()))),
+            BodyInner::Text(text) => {
+                let bytes = text.as_bytes();
+                return Ok(bytes.slice(..len.min(bytes.len())));
+            }
+            _ => {}
+        }
+
+        let mut rest = self.take()?;
+        let mime = rest.mime.take();
+        let mut chunks = Vec::new();
+        let mut read = 0;
+        let mut result = Ok(());
+        let mut finished = false;
+        while read < len {
+            match rest.next().await {
+                Some(Ok(chunk)) => {
+                    read += chunk.len();
+                    chunks.push(chunk);
+                }
+                Some(Err(error)) => {
+                    result = Err(error);
+                    break;
+                }
+                None => {
+                    finished = true;
+                    break;
+                }
+            }
+        }
+
+        let buffered = if let [chunk] = chunks.as_slice() {
+            chunk.clone()
+        } else {
+            chunks.concat().into()
+        };
+        let prefix = buffered.slice(..len.min(buffered.len()));
+
+        // The rest keeps its remaining length and its trailers, either received while
+        // reading or still to come.
+        *self = if finished {
+            Self {
+                mime: None,
+                inner: BodyInner::Once(buffered),
+                trailers: rest.trailers.take(),
+            }
+        } else {
+            Self::concat([Self::from_bytes(buffered), rest])
+        };
+        self.mime = mime;
+        result.map(|()| prefix)
+    }
+
+    /// Reads and discards the rest of the body, returning the number of bytes
+    /// discarded.
+    ///
+    /// A peer can keep the caller busy with an endless body: servers draining a
+    /// request body to reuse the connection should use [`Body::drain_up_to`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The body is frozen (already consumed)
+    /// - An I/O error occurs while reading streaming data
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use http_kit::Body;
+    ///
+    /// # async fn example() -> Result<(), http_kit::BodyError> {
+    /// let body = Body::from_bytes("unwanted");
+    /// assert_eq!(body.drain().await?, 8);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn drain(self) -> Result<u64, Error> {
+        self.drain_capped(None).await
+    }
+
+    /// Reads and discards the rest of the body, like [`Body::drain`], but reads at
+    /// most `max_bytes`.
+    ///
+    /// Bodies of a known length over `max_bytes` fail without being read, so that an
+    /// HTTP/1.1 server can close the connection at once instead of reusing it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The body is frozen (already consumed)
+    /// - The body is longer than `max_bytes`, as [`Error::LimitExceeded`]
+    /// - An I/O error occurs while reading streaming data
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use http_kit::{Body, BodyError};
+    ///
+    /// # async fn example() -> Result<(), http_kit::BodyError> {
+    /// assert_eq!(Body::from_bytes("unwanted").drain_up_to(1024).await?, 8);
+    /// assert!(matches!(
+    ///     Body::from_bytes("unwanted").drain_up_to(4).await,
+    ///     Err(BodyError::LimitExceeded(4))
+    /// ));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn drain_up_to(self, max_bytes: usize) -> Result<u64, Error> {
+        self.drain_capped(Some(max_bytes)).await
+    }
+
+    async fn drain_capped(mut self, max_bytes: Option<usize>) -> Result<u64, Error> {
+        if self.is_frozen() {
+            return Err(Error::BodyFrozen);
+        }
+        let over = |len: u64| max_bytes.is_some_and(|max| len > max as u64);
+        if self.len().is_some_and(|len| over(len as u64)) {
+            return Err(Error::LimitExceeded(max_bytes.unwrap_or_default()));
+        }
+        let mut drained = 0u64;
+        while let Some(chunk) = self.next().await {
+            drained += chunk?.len() as u64;
+            if over(drained) {
+                return Err(Error::LimitExceeded(max_bytes.unwrap_or_default()));
+            }
+        }
+        Ok(drained)
+    }
+
+    /// Deserializes the body data as JSON into the specified type.
     ///
     /// This method reads the body data and attempts to deserialize it as JSON.
     /// The deserialization is performed with zero-copy when possible by working
     /// directly with the buffered byte data.
     ///
+    /// Streaming bodies are buffered in full first. To deserialize owned values
+    /// while the data arrives, use [`Body::into_json_stream`] instead.
+    ///
     /// # Warning
     ///
     /// This method does not validate the `Content-Type` header. If you need
-    /// MIME type validation, use `Request::into_json()` or `Response::into_json()`
-    /// instead, which check for the `application/json` content type.
+    /// MIME type validation, use [`RequestExt::into_json`](crate::RequestExt::into_json)
+    /// or [`ResponseExt::into_json`](crate::ResponseExt::into_json) instead, which
+    /// accept `application/json` and the other JSON media types.
     ///
     /// # Errors
     ///
@@ -897,6 +1815,19 @@ impl Body {
         Ok(serde_json::from_slice(self.as_bytes().await?)?)
     }
 
+    /// Deserializes the body data as a dynamic JSON value.
+    ///
+    /// Useful for payloads whose shape is not known in advance. Like
+    /// [`Body::into_json`], the `Content-Type` is not checked.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the body is frozen, cannot be read, or is not valid JSON.
+    #[cfg(feature = "json")]
+    pub async fn into_json_value(&mut self) -> Result<serde_json::Value, Error> {
+        self.into_json().await
+    }
+
     /// Deserializes the body data as URL-encoded form data into the specified type.
     ///
     /// This method reads the body data and attempts to deserialize it as
@@ -906,8 +1837,9 @@ impl Body {
     /// # Warning
     ///
     /// This method does not validate the `Content-Type` header. If you need
-    /// MIME type validation, use `Request::into_form()` or `Response::into_form()`
-    /// instead, which check for the `application/x-www-form-urlencoded` content type.
+    /// MIME type validation, use [`RequestExt::into_form`](crate::RequestExt::into_form)
+    /// or [`ResponseExt::into_form`](crate::ResponseExt::into_form) instead, which
+    /// check for the `application/x-www-form-urlencoded` content type.
     ///
     /// # Errors
     ///
@@ -947,12 +1879,38 @@ impl Body {
         Ok(serde_urlencoded::from_bytes(self.as_bytes().await?)?)
     }
 
+    /// Deserializes the body data as URL-encoded form data, collecting repeated keys
+    /// into sequences.
+    ///
+    /// Unlike [`Body::into_form`], `tags=a&tags=b` deserializes into a `Vec` field; see
+    /// the [`form`](crate::form) module for the rules. Forms with more than
+    /// [`form::DEFAULT_MAX_PAIRS`](crate::form::DEFAULT_MAX_PAIRS) pairs are rejected;
+    /// use [`form::from_bytes_with_limit`](crate::form::from_bytes_with_limit) on the
+    /// buffered body for another limit. Like `into_form`, the `Content-Type` is not
+    /// checked.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the body is frozen or cannot be read, or if the form has too
+    /// many pairs or doesn't match the target type.
+    #[cfg(feature = "form")]
+    pub async fn into_form_extended<T>(&mut self) -> Result<T, Error>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        Ok(crate::form::from_bytes(self.as_bytes().await?)?)
+    }
+
     /// Replaces this body with a new body and returns the old body.
     ///
     /// This method swaps the current body with the provided body, returning
     /// the original body value. This can be useful for chaining operations
     /// or temporarily substituting body content.
     ///
+    /// Like [`core::mem::replace`], this ignores whether the body is frozen, so it can
+    /// bring back a body that was consumed. Use [`Body::try_replace`] to respect the
+    /// frozen state, or [`Body::unfreeze`] to make the intent explicit.
+    ///
     /// # Examples
     ///
     /// ```rust
@@ -968,6 +1926,60 @@ impl Body {
         replace(self, body)
     }
 
+    /// Replaces this body with a new body and returns the old body, unless this body
+    /// is frozen.
+    ///
+    /// A frozen body marks data that was already consumed, for instance by a
+    /// middleware that took the request body. Refusing to replace it keeps later
+    /// layers from handing out a body that was never received.
+    ///
+    /// # Errors
+    ///
+    /// Returns `BodyFrozen`, leaving `body` unused, if this body is frozen.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use http_kit::Body;
+    ///
+    /// let mut body = Body::from_bytes("original");
+    /// let old = body.try_replace(Body::from_bytes("replacement"))?;
+    ///
+    /// body.freeze();
+    /// assert!(body.try_replace(old).is_err());
+    /// # Ok::<(), http_kit::BodyError>(())
+    /// ```
+    pub fn try_replace(&mut self, body: Body) -> Result<Body, BodyFrozen> {
+        if self.is_frozen() {
+            Err(BodyFrozen::new())
+        } else {
+            Ok(replace(self, body))
+        }
+    }
+
+    /// Installs `body` in place of a frozen body.
+    ///
+    /// This is the intended way to give a consumed body a new content, such as the
+    /// buffered copy of a body that was taken to be inspected. A body that is not
+    /// frozen is replaced as well, and its content dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use http_kit::Body;
+    ///
+    /// let mut body = Body::from_bytes("data");
+    /// let taken = body.take()?;
+    /// assert!(body.is_frozen());
+    ///
+    /// body.unfreeze(taken);
+    /// assert!(!body.is_frozen());
+    /// # Ok::<(), http_kit::BodyError>(())
+    /// ```
+    pub fn unfreeze(&mut self, body: Body) {
+        *self = body;
+    }
+
     /// Swaps the contents of this body with another body.
     ///
     /// This method exchanges the contents of two bodies, provided that this
@@ -1006,6 +2018,10 @@ impl Body {
     /// (unusable) body. This is useful when you need to move the body to
     /// another location while ensuring the original cannot be used again.
     ///
+    /// The frozen body stays in place until it is explicitly replaced with
+    /// [`Body::unfreeze`]; [`Body::take`], [`Body::swap`] and [`Body::try_replace`]
+    /// refuse to operate on it.
+    ///
     /// # Errors
     ///
     /// Returns `BodyFrozen` if the body is already frozen.
@@ -1027,7 +2043,7 @@ impl Body {
         if self.is_frozen() {
             Err(BodyFrozen::new())
         } else {
-            Ok(self.replace(Self::frozen()))
+            Ok(replace(self, Self::frozen()))
         }
     }
 
@@ -1061,6 +2077,10 @@ impl Body {
     /// it contained. After freezing, the body cannot be used for any operations
     /// and will return errors if accessed.
     ///
+    /// [`Body::take`], [`Body::swap`] and [`Body::try_replace`] refuse to operate on a
+    /// frozen body, so that consumed data cannot be mistaken for data still to read.
+    /// Only [`Body::unfreeze`] and [`Body::replace`] install a new body in its place.
+    ///
     /// # Examples
     ///
     /// ```rust
@@ -1073,7 +2093,7 @@ impl Body {
     /// // Any further operations on `body` will fail
     /// ```
     pub fn freeze(&mut self) {
-        self.replace(Self::frozen());
+        *self = Self::frozen();
     }
 }
 
@@ -1083,11 +2103,103 @@ impl Default for Body {
     }
 }
 
-impl Stream for Body {
-    type Item = Result<Bytes, Error>;
+// Joins the chunks of a body with a single copy, or none if there is only one.
+fn concat(mut chunks: Vec<Bytes>) -> Bytes {
+    if chunks.len() <= 1 {
+        return chunks.pop().unwrap_or_default();
+    }
+    let mut data = Vec::with_capacity(chunks.iter().map(Bytes::len).sum());
+    for chunk in &chunks {
+        data.extend_from_slice(chunk);
+    }
+    data.into()
+}
 
-    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        match &mut self.inner {
+// Polls the next data of a wrapped body. Empty chunks would read as the end of the data,
+// so they are skipped, and trailers are kept for `take_trailers`.
+fn poll_data(
+    body: &mut BoxHttpBody,
+    trailers: &mut Option<Box<HeaderMap>>,
+    cx: &mut Context<'_>,
+    counted: bool,
+) -> Poll<Option<Result<Bytes, Error>>> {
+    loop {
+        match ready!(body.as_mut().poll_frame(cx)) {
+            Some(Ok(frame)) => match frame.into_data() {
+                Ok(data) if data.is_empty() => {}
+                Ok(data) => return Poll::Ready(Some(Ok(data))),
+                Err(frame) => {
+                    if let Ok(received) = frame.into_trailers() {
+                        trailers.get_or_insert_with(Box::default).extend(received);
+                    }
+                }
+            },
+            Some(Err(error)) => return Poll::Ready(Some(Err(error))),
+            None => {
+                stat!(bodies_streamed if counted);
+                return Poll::Ready(None);
+            }
+        }
+    }
+}
+
+impl Body {
+    // Polls a body wrapped by another one. Only the outermost body of a stack of wrappers
+    // updates the `stats` counters, so that data read through wrappers counts once.
+    pub(super) fn poll_nested(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Bytes>, Error>>> {
+        self.poll_frame_counted(cx, false)
+    }
+
+    fn poll_frame_counted(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        counted: bool,
+    ) -> Poll<Option<Result<Frame<Bytes>, Error>>> {
+        // Forward frames of wrapped bodies as they are, so trailers are not lost.
+        let frame =
+            if let BodyInner::HttpBody(body) | BodyInner::Chain { body, .. } = &mut self.inner {
+                let frame = ready!(body.as_mut().poll_frame(cx));
+                if frame.is_none() {
+                    stat!(bodies_streamed if counted);
+                }
+                frame
+            } else {
+                ready!(self.as_mut().poll_next_counted(cx, counted))
+                    .map(|result| result.map(Frame::data))
+            };
+        if let (
+            BodyInner::Chain {
+                length: Some(left), ..
+            },
+            Some(Ok(frame)),
+        ) = (&mut self.inner, &frame)
+        {
+            if let Some(data) = frame.data_ref() {
+                *left = left.saturating_sub(data.len());
+            }
+        }
+        // Trailers buffered by `as_bytes` or received while streaming, and not taken,
+        // follow the data.
+        if frame.is_none() {
+            if let Some(trailers) = self.trailers.take() {
+                return Poll::Ready(Some(Ok(http_body::Frame::trailers(*trailers))));
+            }
+        }
+        Poll::Ready(frame)
+    }
+
+    fn poll_next_counted(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        counted: bool,
+    ) -> Poll<Option<Result<Bytes, Error>>> {
+        let Self {
+            inner, trailers, ..
+        } = self.get_mut();
+        match inner {
             BodyInner::Once(bytes) => {
                 if bytes.is_empty() {
                     Poll::Ready(None)
@@ -1095,31 +2207,77 @@ impl Stream for Body {
                     Poll::Ready(Some(Ok(take(bytes))))
                 }
             }
-            BodyInner::Reader { reader, length } => {
-                let data = ready!(reader.as_mut().poll_fill_buf(cx))?;
-                if data.is_empty() {
+            BodyInner::Text(text) => {
+                if text.is_empty() {
+                    Poll::Ready(None)
+                } else {
+                    Poll::Ready(Some(Ok(take(text).into_bytes())))
+                }
+            }
+            BodyInner::Reader {
+                reader,
+                length,
+                buf,
+                capacity,
+            } => {
+                // Once the announced length has been read, check for the end without
+                // allocating a buffer.
+                if *length == Some(0) && ready!(reader.as_mut().poll_fill_buf(cx))?.is_empty() {
+                    stat!(bodies_streamed if counted);
+                    return Poll::Ready(None);
+                }
+                let want = match *length {
+                    Some(left) if left > 0 => left.min(*capacity),
+                    _ => *capacity,
+                };
+                // Reads go to the rest of the current allocation while it has room for a
+                // fair share of a full read, so that short reads share one allocation and
+                // each of its bytes is zeroed once.
+                if buf.capacity() < want.div_ceil(4) {
+                    // The rest of the buffer holds no data, so it is not carried over.
+                    buf.clear();
+                    buf.reserve(*capacity);
+                }
+                let room = want.min(buf.capacity());
+                if buf.len() < room {
+                    buf.resize(room, 0);
+                }
+                let read = ready!(reader.as_mut().poll_read(cx, &mut buf[..room]))?;
+                if read == 0 {
+                    stat!(bodies_streamed if counted);
                     return Poll::Ready(None);
                 }
-                let data = Bytes::copy_from_slice(data);
-                reader.as_mut().consume(data.len());
                 if let Some(known_length) = length {
-                    *known_length = known_length.saturating_sub(data.len());
+                    *known_length = known_length.saturating_sub(read);
+                }
+                Poll::Ready(Some(Ok(buf.split_to(read).freeze())))
+            }
+            BodyInner::HttpBody(body) => poll_data(body, trailers, cx, counted),
+            BodyInner::Chain { body, length } => {
+                let data = ready!(poll_data(body, trailers, cx, counted));
+                if let (Some(Ok(data)), Some(left)) = (&data, length) {
+                    *left = left.saturating_sub(data.len());
                 }
-                Poll::Ready(Some(Ok(data)))
+                Poll::Ready(data)
             }
-            BodyInner::HttpBody(stream) => stream
-                .as_mut()
-                .poll_frame(cx)
-                .map_ok(|frame| frame.into_data().unwrap_or_default()),
             BodyInner::Freeze => Poll::Ready(Some(Err(Error::BodyFrozen))),
         }
     }
+}
+
+impl Stream for Body {
+    type Item = Result<Bytes, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.poll_next_counted(cx, true)
+    }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
         match &self.inner {
             BodyInner::Once(bytes) => (bytes.len(), Some(bytes.len())),
+            BodyInner::Text(text) => (text.len(), Some(text.len())),
             BodyInner::Reader { length, .. } => (0, *length),
-            BodyInner::HttpBody(body) => {
+            BodyInner::HttpBody(body) | BodyInner::Chain { body, .. } => {
                 let hint = body.size_hint();
                 (hint.lower() as usize, hint.upper().map(|u| u as usize))
             }
@@ -1128,6 +2286,45 @@ impl Stream for Body {
     }
 }
 
+pin_project_lite::pin_project! {
+    // Adapts the data and error types of a wrapped body, keeping its size hint and end of
+    // stream, which `BodyExt::map_frame` drops.
+    struct Converted<B> {
+        #[pin]
+        body: B,
+    }
+}
+
+impl<B> http_body::Body for Converted<B>
+where
+    B: http_body::Body,
+    B::Data: Into<Bytes>,
+    B::Error: Into<Error>,
+{
+    type Data = Bytes;
+    type Error = Error;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let frame = ready!(self.project().body.poll_frame(cx));
+        Poll::Ready(frame.map(|result| {
+            result
+                .map(|frame| frame.map_data(Into::into))
+                .map_err(Into::into)
+        }))
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.body.is_end_stream()
+    }
+
+    fn size_hint(&self) -> http_body::SizeHint {
+        self.body.size_hint()
+    }
+}
+
 impl http_body::Body for Body {
     type Data = Bytes;
 
@@ -1137,17 +2334,48 @@ impl http_body::Body for Body {
         self: Pin<&mut Self>,
         cx: &mut Context<'_>,
     ) -> Poll<Option<Result<http_body::Frame<Self::Data>, Self::Error>>> {
-        self.poll_next(cx)
-            .map(|opt| opt.map(|result| result.map(http_body::Frame::data)))
-            .map_err(Error::from)
+        self.poll_frame_counted(cx, true)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        if self.trailers.is_some() {
+            return false;
+        }
+        match &self.inner {
+            BodyInner::Once(bytes) => bytes.is_empty(),
+            BodyInner::Text(text) => text.is_empty(),
+            // The length of a reader is a hint, so only reading tells whether it has ended.
+            BodyInner::Reader { .. } => false,
+            BodyInner::HttpBody(body) | BodyInner::Chain { body, .. } => body.is_end_stream(),
+            BodyInner::Freeze => true,
+        }
+    }
+
+    fn size_hint(&self) -> http_body::SizeHint {
+        match &self.inner {
+            BodyInner::Chain {
+                length: Some(length),
+                ..
+            } => http_body::SizeHint::with_exact(*length as u64),
+            BodyInner::HttpBody(body) | BodyInner::Chain { body, .. } => body.size_hint(),
+            _ => {
+                let (lower, upper) = Stream::size_hint(self);
+                let mut hint = http_body::SizeHint::new();
+                hint.set_lower(lower as u64);
+                if let Some(upper) = upper {
+                    hint.set_upper(upper as u64);
+                }
+                hint
+            }
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use alloc::string::ToString;
     use alloc::vec;
+    use alloc::{format, string::ToString};
     use futures_lite::{stream, StreamExt};
 
     #[tokio::test]
@@ -1246,6 +2474,25 @@ mod tests {
         assert_eq!(parsed, data);
     }
 
+    #[cfg(feature = "json")]
+    #[tokio::test]
+    async fn json_pretty_and_values() {
+        let value = serde_json::json!({ "name": "Alice", "tags": ["a", "b"] });
+
+        let body = Body::from_json_pretty(&value).unwrap();
+        assert_eq!(body.mime(), Some(&mime::APPLICATION_JSON));
+        let text = body.into_string().await.unwrap();
+        assert!(text.contains("{\n  \"name\": \"Alice\""));
+        assert!(text.contains("\n    \"a\",\n"));
+
+        let mut body = Body::from_json_value(&value);
+        assert_eq!(body.mime(), Some(&mime::APPLICATION_JSON));
+        assert_eq!(body.into_json_value().await.unwrap(), value);
+
+        let mut body = Body::from_json_pretty(&value).unwrap();
+        assert_eq!(body.into_json_value().await.unwrap(), value);
+    }
+
     #[cfg(feature = "form")]
     #[tokio::test]
     async fn form_roundtrip() {
@@ -1287,6 +2534,93 @@ mod tests {
         assert_eq!(result.as_ref(), data.as_bytes());
     }
 
+    #[tokio::test]
+    async fn streams_are_joined_once() {
+        let chunks: Vec<Bytes> = (0..1000u32)
+            .map(|i| Bytes::from(alloc::format!("{i:04},")))
+            .collect();
+        let expected = chunks.concat();
+        let body = Body::from_stream(stream::iter(
+            chunks.into_iter().map(Ok::<_, core::convert::Infallible>),
+        ));
+        assert_eq!(body.into_bytes().await.unwrap(), expected);
+
+        // A single chunk, even among empty ones, is returned as is.
+        let chunk = Bytes::from(vec![1u8; 64]);
+        let body = Body::from_stream(stream::iter(
+            [Bytes::new(), chunk.clone(), Bytes::new()].map(Ok::<_, core::convert::Infallible>),
+        ));
+        let data = body.into_bytes().await.unwrap();
+        assert_eq!(data.as_ptr(), chunk.as_ptr());
+
+        let mut body = Body::from_stream(stream::iter(
+            ["ab", "cd"].map(Ok::<_, core::convert::Infallible>),
+        ));
+        assert_eq!(body.as_bytes().await.unwrap(), b"abcd");
+        assert_eq!(body.as_bytes().await.unwrap(), b"abcd");
+    }
+
+    #[tokio::test]
+    async fn reader_chunks_follow_the_capacity() {
+        use futures_lite::io::{AsyncBufRead, AsyncRead, BufReader, Cursor};
+
+        let data: Vec<u8> = (0..1_000_000u32).map(|i| (i % 251) as u8).collect();
+        for length in [Some(data.len()), None] {
+            let reader = BufReader::new(Cursor::new(data.clone()));
+            let body = Body::from_reader_with_capacity(reader, length, 4096);
+            let chunks: Vec<Bytes> = body.try_collect().await.unwrap();
+            let (last, full) = chunks.split_last().unwrap();
+            assert!(full.iter().all(|chunk| chunk.len() == 4096));
+            assert_eq!(last.len(), data.len() % 4096);
+            assert_eq!(chunks.concat(), data);
+        }
+
+        // A wrong length hint only bounds the size of the first chunk, and the next one
+        // fills the rest of its buffer.
+        let reader = BufReader::new(Cursor::new(data.clone()));
+        let body = Body::from_reader_with_capacity(reader, 10, 4096);
+        let chunks: Vec<Bytes> = body.try_collect().await.unwrap();
+        assert_eq!(chunks[0].len(), 10);
+        assert_eq!(chunks[1].len(), 4086);
+        assert_eq!(chunks[2].len(), 4096);
+        assert_eq!(chunks.concat(), data);
+
+        // Short reads share an allocation instead of getting a buffer each.
+        struct Trickle(Cursor<Vec<u8>>);
+        impl AsyncRead for Trickle {
+            fn poll_read(
+                mut self: Pin<&mut Self>,
+                cx: &mut Context<'_>,
+                buf: &mut [u8],
+            ) -> Poll<futures_lite::io::Result<usize>> {
+                let len = buf.len().min(100);
+                Pin::new(&mut self.0).poll_read(cx, &mut buf[..len])
+            }
+        }
+        impl AsyncBufRead for Trickle {
+            fn poll_fill_buf(
+                self: Pin<&mut Self>,
+                cx: &mut Context<'_>,
+            ) -> Poll<futures_lite::io::Result<&[u8]>> {
+                Pin::new(&mut self.get_mut().0).poll_fill_buf(cx)
+            }
+
+            fn consume(mut self: Pin<&mut Self>, amt: usize) {
+                Pin::new(&mut self.0).consume(amt);
+            }
+        }
+        let trickle = Trickle(Cursor::new(data.clone()));
+        let mut body = Body::from_reader_with_capacity(trickle, None, 4096);
+        let first = body.next().await.unwrap().unwrap();
+        let second = body.next().await.unwrap().unwrap();
+        assert_eq!(first.len(), 100);
+        assert_eq!(second.as_ptr(), first.as_ptr().wrapping_add(100));
+
+        let reader = BufReader::new(Cursor::new(Vec::new()));
+        let mut body = Body::from_reader_with_capacity(reader, 0, 0);
+        assert!(body.next().await.is_none());
+    }
+
     #[tokio::test]
     async fn sse_body_creation_sets_mime() {
         let events = stream::iter(vec![
@@ -1393,6 +2727,357 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn limit_fails_streaming_body_mid_read() {
+        let chunks = vec![Ok::<_, Error>("0123"), Ok("4567"), Ok("89")];
+        let mut body = Body::from_stream(stream::iter(chunks)).limit(6);
+        assert_eq!(body.next().await.unwrap().unwrap(), "0123");
+        assert!(matches!(
+            body.next().await,
+            Some(Err(Error::LimitExceeded(6)))
+        ));
+        assert!(body.next().await.is_none());
+
+        let chunks = vec![Ok::<_, Error>("0123"), Ok("4567")];
+        let body = Body::from_stream(stream::iter(chunks)).limit(6);
+        assert!(matches!(
+            body.into_string().await,
+            Err(Error::LimitExceeded(6))
+        ));
+
+        let chunks = vec![Ok::<_, Error>("0123"), Ok("45")];
+        let body = Body::from_stream(stream::iter(chunks)).limit(6);
+        assert_eq!(body.into_bytes().await.unwrap(), "012345");
+    }
+
+    #[tokio::test]
+    async fn limit_fails_fast_on_known_length() {
+        // The reader is empty: only the declared length can trigger the error.
+        let reader = futures_lite::io::BufReader::new(futures_lite::io::empty());
+        let mut body = Body::from_reader(reader, 100).limit(10);
+        assert!(matches!(
+            body.as_bytes().await,
+            Err(Error::LimitExceeded(10))
+        ));
+
+        let body = Body::from_bytes("small")
+            .with_mime(mime::TEXT_PLAIN)
+            .limit(10);
+        assert_eq!(body.len(), Some(5));
+        assert_eq!(body.mime(), Some(&mime::TEXT_PLAIN));
+        assert!(Body::from_bytes("too large").limit(3).len().is_none());
+    }
+
+    #[tokio::test]
+    async fn limited_maps_errors_to_body_errors() {
+        let chunks = vec![Ok::<_, Error>("0123"), Ok("4567")];
+        let mut body = Body::from_stream(stream::iter(chunks))
+            .with_mime(mime::TEXT_PLAIN)
+            .limited(6);
+        assert_eq!(body.mime(), Some(&mime::TEXT_PLAIN));
+        assert_eq!(body.next().await.unwrap().unwrap(), "0123");
+        let error = body.next().await.unwrap().unwrap_err();
+        assert!(matches!(error, Error::LimitExceeded(6)));
+        assert_eq!(
+            crate::HttpError::status(&error),
+            http::StatusCode::PAYLOAD_TOO_LARGE
+        );
+
+        let mut frozen = Body::frozen().limited(6);
+        assert!(matches!(frozen.next().await, Some(Err(Error::BodyFrozen))));
+
+        let body = Body::from_bytes("012345").limited(6);
+        assert_eq!(body.into_bytes().await.unwrap(), "012345");
+    }
+
+    #[tokio::test]
+    async fn peek_restores_streaming_body() {
+        let chunks = vec!["ab", "cd", "ef"];
+        let mut body =
+            Body::from_stream(stream::iter(chunks).map(Ok::<_, core::convert::Infallible>))
+                .with_mime(mime::TEXT_PLAIN);
+
+        assert_eq!(body.peek(3).await.unwrap(), "abc");
+        assert_eq!(body.peek(1).await.unwrap(), "a");
+        assert_eq!(body.mime(), Some(&mime::TEXT_PLAIN));
+        assert_eq!(body.peek(64).await.unwrap(), "abcdef");
+        assert_eq!(body.len(), Some(6));
+        assert_eq!(body.into_bytes().await.unwrap(), "abcdef");
+    }
+
+    #[tokio::test]
+    async fn peek_keeps_length_and_trailers() {
+        let reader = futures_lite::io::Cursor::new(b"0123456789".to_vec());
+        let mut body = Body::from_reader_with_capacity(reader, 10, 4);
+        assert_eq!(body.peek(2).await.unwrap(), "01");
+        assert_eq!(body.len(), Some(10));
+        assert_eq!(body.into_bytes().await.unwrap(), "0123456789");
+
+        let trailed = || {
+            let chunks = stream::iter(vec![Ok::<_, Error>("ab"), Ok("cd")]);
+            Body::from_stream(chunks).with_trailers(grpc_status("0"))
+        };
+        for len in [1, 64] {
+            let mut body = trailed();
+            assert_eq!(body.peek(len).await.unwrap(), &b"abcd"[..len.min(4)]);
+            let (data, trailers) = body.into_bytes_with_trailers().await.unwrap();
+            assert_eq!(data, "abcd");
+            assert_eq!(trailers, Some(grpc_status("0")));
+        }
+    }
+
+    #[test]
+    fn http_body_hints() {
+        use http_body::Body as _;
+        let hint = |body: &Body| http_body::Body::size_hint(body).exact();
+
+        let body = Body::from_bytes("hello");
+        assert_eq!(hint(&body), Some(5));
+        assert!(!body.is_end_stream());
+
+        let empty = Body::empty();
+        assert_eq!(hint(&empty), Some(0));
+        assert!(empty.is_end_stream());
+        assert!(Body::frozen().is_end_stream());
+
+        let full = Body::new(http_body_util::Full::new(Bytes::from_static(b"abc")));
+        assert_eq!(hint(&full), Some(3));
+        assert!(!full.is_end_stream());
+        let drained = Body::new(http_body_util::Empty::<Bytes>::new());
+        assert!(drained.is_end_stream());
+    }
+
+    #[tokio::test]
+    async fn readers_end_at_eof_rather_than_at_their_length() {
+        use futures_lite::io::{BufReader, Cursor};
+
+        let reader = BufReader::new(Cursor::new(b"unannounced".to_vec()));
+        let body = Body::from_reader(reader, 0);
+        assert!(!http_body::Body::is_end_stream(&body));
+        assert_eq!(body.into_bytes().await.unwrap(), "unannounced");
+    }
+
+    #[test]
+    fn debug_summaries() {
+        let json = Body::from_bytes(r#"{"id":42,"ok":1}"#).with_mime(mime::APPLICATION_JSON);
+        assert_eq!(
+            format!("{json:?}"),
+            r#"Body::Once { len: 16, mime: "application/json" }"#
+        );
+        let reader = Body::from_reader(futures_lite::io::empty(), None);
+        assert_eq!(format!("{reader:?}"), "Body::Reader");
+        let reader = Body::from_reader(futures_lite::io::empty(), 7);
+        assert_eq!(format!("{reader:?}"), "Body::Reader { len: 7 }");
+        let full = Body::new(http_body_util::Full::new(Bytes::from_static(b"abc")));
+        assert_eq!(format!("{full:?}"), "Body::HttpBody { len: 3 }");
+        assert_eq!(format!("{:?}", Body::frozen()), "Body::Freeze");
+    }
+
+    #[tokio::test]
+    async fn http_body_frames_keep_trailers() {
+        let mut trailers = http::HeaderMap::new();
+        trailers.insert("grpc-status", http::HeaderValue::from_static("0"));
+        let source = || {
+            let frames = vec![
+                Ok::<_, core::convert::Infallible>(Frame::data(Bytes::from_static(b"payload"))),
+                Ok(Frame::trailers(trailers.clone())),
+            ];
+            Body::new(http_body_util::StreamBody::new(stream::iter(frames)))
+        };
+
+        let collected = BodyExt::collect(source()).await.unwrap();
+        assert_eq!(collected.trailers(), Some(&trailers));
+        assert_eq!(collected.to_bytes(), "payload");
+
+        let collected = BodyExt::collect(source().limit(64)).await.unwrap();
+        assert_eq!(collected.trailers(), Some(&trailers));
+    }
+
+    #[tokio::test]
+    async fn streams_skip_empty_chunks_and_keep_trailers() {
+        let source = || {
+            let mut checksum = HeaderMap::new();
+            checksum.insert("x-checksum", http::HeaderValue::from_static("abc"));
+            let frames = vec![
+                Ok::<_, core::convert::Infallible>(Frame::data(Bytes::new())),
+                Ok(Frame::data(Bytes::from_static(b"one"))),
+                Ok(Frame::trailers(checksum)),
+                Ok(Frame::data(Bytes::new())),
+                Ok(Frame::data(Bytes::from_static(b"two"))),
+                Ok(Frame::trailers(grpc_status("0"))),
+            ];
+            Body::new(http_body_util::StreamBody::new(stream::iter(frames)))
+        };
+
+        let mut body = source();
+        let mut chunks = vec![];
+        while let Some(chunk) = body.try_next().await.unwrap() {
+            chunks.push(chunk);
+        }
+        assert_eq!(chunks, ["one", "two"]);
+        let trailers = body.take_trailers().unwrap();
+        assert_eq!(trailers["x-checksum"], "abc");
+        assert_eq!(trailers["grpc-status"], "0");
+        assert!(body.take_trailers().is_none());
+
+        let chunks: Vec<Bytes> = source().into_data_stream().try_collect().await.unwrap();
+        assert_eq!(chunks, ["one", "two"]);
+        assert_eq!(source().into_bytes().await.unwrap(), "onetwo");
+
+        let mut body = source();
+        assert_eq!(body.as_bytes().await.unwrap(), b"onetwo");
+        assert_eq!(body.take_trailers().unwrap().len(), 2);
+
+        // Buffered bodies forwarded as they are still send their trailers.
+        let mut body = source();
+        body.as_bytes().await.unwrap();
+        assert_eq!(body.len(), Some(6));
+        assert!(!http_body::Body::is_end_stream(&body));
+        let collected = BodyExt::collect(body).await.unwrap();
+        assert_eq!(collected.trailers().unwrap()["grpc-status"], "0");
+        assert_eq!(collected.to_bytes(), "onetwo");
+
+        // Trailers taken while streaming are not lost when the rest is buffered.
+        let mut body = source();
+        assert_eq!(body.next().await.unwrap().unwrap(), "one");
+        assert_eq!(body.next().await.unwrap().unwrap(), "two");
+        let (data, trailers) = body.into_bytes_with_trailers().await.unwrap();
+        assert!(data.is_empty());
+        assert_eq!(trailers.unwrap()["x-checksum"], "abc");
+    }
+
+    #[tokio::test]
+    async fn drain_streams_up_to_the_cap() {
+        let chunked = || {
+            let chunks = ["ab", "", "cde", "f"].map(|chunk| Ok::<_, Error>(Bytes::from(chunk)));
+            Body::from_stream(stream::iter(chunks))
+        };
+        assert_eq!(chunked().drain().await.unwrap(), 6);
+        assert_eq!(chunked().drain_up_to(6).await.unwrap(), 6);
+        assert!(matches!(
+            Body::frozen().drain().await,
+            Err(Error::BodyFrozen)
+        ));
+
+        // Streams of unknown length are read until they pass the cap.
+        assert!(matches!(
+            chunked().drain_up_to(4).await,
+            Err(Error::LimitExceeded(4))
+        ));
+
+        // Known lengths fail before anything is read.
+        let body = Body::from_reader(futures_lite::io::Cursor::new(b"too long"), 8);
+        assert!(matches!(
+            body.drain_up_to(7).await,
+            Err(Error::LimitExceeded(7))
+        ));
+        let body = Body::from_bytes("exact");
+        assert_eq!(body.drain_up_to(5).await.unwrap(), 5);
+    }
+
+    #[tokio::test]
+    async fn text_is_validated_once() {
+        let text = "caf\u{e9} ".repeat(1 << 20);
+        let mut body = Body::from_bytes(text.clone());
+        assert_eq!(body.as_str().await.unwrap(), text);
+        assert!(matches!(body.inner, BodyInner::Text(_)));
+        assert_eq!(body.as_str().await.unwrap(), text);
+        assert_eq!(body.as_bytes().await.unwrap(), text.as_bytes());
+        assert_eq!(body.len(), Some(text.len()));
+        assert_eq!(body.into_string().await.unwrap(), text);
+
+        // Bodies created from text start validated.
+        let mut body = Body::from_text("hello");
+        assert!(matches!(body.inner, BodyInner::Text(_)));
+        assert_eq!(body.as_bytes().await.unwrap(), b"hello");
+        assert_eq!(body.as_str().await.unwrap(), "hello");
+        assert_eq!(body.try_clone().unwrap().peek(4).await.unwrap(), "hell");
+        assert_eq!(body.next().await.unwrap().unwrap(), "hello");
+        assert!(body.next().await.is_none());
+
+        let chunks = ["he", "llo"].map(|chunk| Ok::<_, Error>(Bytes::from(chunk)));
+        let mut body = Body::from_stream(stream::iter(chunks)).with_mime(mime::TEXT_PLAIN);
+        assert_eq!(body.as_bytes().await.unwrap(), b"hello");
+        assert_eq!(body.as_str().await.unwrap(), "hello");
+        assert_eq!(body.as_bytes().await.unwrap(), b"hello");
+        assert_eq!(body.mime(), Some(&mime::TEXT_PLAIN));
+
+        // Invalid data fails every time and stays readable as bytes.
+        let mut body = Body::from_bytes(&b"ok \xff"[..]);
+        assert!(matches!(body.as_str().await, Err(Error::Utf8(_))));
+        assert!(matches!(body.as_str().await, Err(Error::Utf8(_))));
+        assert_eq!(body.as_bytes().await.unwrap(), b"ok \xff");
+        assert!(matches!(body.into_string().await, Err(Error::Utf8(_))));
+    }
+
+    fn grpc_status(status: &'static str) -> HeaderMap {
+        let mut trailers = HeaderMap::new();
+        trailers.insert("grpc-status", http::HeaderValue::from_static(status));
+        trailers
+    }
+
+    #[tokio::test]
+    async fn trailers_follow_the_last_data_frame() {
+        let chunks = stream::iter(vec!["a", "b", "c"]).map(Ok::<_, core::convert::Infallible>);
+        let mut body = Body::from_stream_with_trailers(chunks, async { grpc_status("0") });
+
+        let mut frames = Vec::new();
+        while let Some(frame) = BodyExt::frame(&mut body).await {
+            let frame = frame.unwrap();
+            frames.push(match frame.into_data() {
+                Ok(data) => data,
+                Err(frame) => {
+                    assert_eq!(frame.into_trailers().unwrap(), grpc_status("0"));
+                    Bytes::from_static(b"<trailers>")
+                }
+            });
+        }
+        assert_eq!(frames, ["a", "b", "c", "<trailers>"]);
+        assert!(http_body::Body::is_end_stream(&body));
+    }
+
+    #[tokio::test]
+    async fn trailers_are_collected() {
+        let body = Body::from_bytes("payload").with_trailers(grpc_status("0"));
+        let collected = BodyExt::collect(body).await.unwrap();
+        assert_eq!(collected.trailers(), Some(&grpc_status("0")));
+        assert_eq!(collected.to_bytes(), "payload");
+
+        // Outer trailers replace inner ones of the same name.
+        let mut extra = grpc_status("13");
+        extra.insert("grpc-message", http::HeaderValue::from_static("boom"));
+        let body = Body::from_bytes("payload")
+            .with_trailers(grpc_status("0"))
+            .with_trailers(extra.clone());
+        let (data, trailers) = body.into_bytes_with_trailers().await.unwrap();
+        assert_eq!((data.as_ref(), trailers), (&b"payload"[..], Some(extra)));
+
+        let (_, trailers) = Body::from_bytes("plain")
+            .into_bytes_with_trailers()
+            .await
+            .unwrap();
+        assert_eq!(trailers, None);
+    }
+
+    #[tokio::test]
+    async fn response_trailers() {
+        use crate::{Response, ResponseExt};
+
+        let mut response = Response::new(Body::from_text("done"));
+        assert_eq!(response.trailers().await.unwrap(), None);
+        response.set_trailers(grpc_status("0"));
+        assert_eq!(response.body().mime(), Some(&mime::TEXT_PLAIN_UTF_8));
+        assert_eq!(response.trailers().await.unwrap(), Some(grpc_status("0")));
+
+        let (data, trailers) = response
+            .into_body()
+            .into_bytes_with_trailers()
+            .await
+            .unwrap();
+        assert_eq!(data, "done");
+        assert_eq!(trailers, Some(grpc_status("0")));
+    }
+
     #[cfg(all(feature = "fs", feature = "std"))]
     #[tokio::test]
     async fn file_body_with_mime() {
@@ -1409,4 +3094,57 @@ mod tests {
 
         let _ = std::fs::remove_file(file_path);
     }
+
+    #[tokio::test]
+    async fn unsync_sources() {
+        use core::cell::Cell;
+
+        let count = Cell::new(0u8);
+        let chunks = stream::poll_fn(move |_| {
+            count.set(count.get() + 1);
+            Poll::Ready((count.get() <= 2).then(|| Ok::<_, Error>(Bytes::from("ab"))))
+        });
+        let body = Body::from_stream_unsync(chunks);
+        assert_eq!(body.into_string().await.unwrap(), "abab");
+
+        // A reader holding a `Cell`, which is `Send` but not `Sync`.
+        struct Reader {
+            inner: futures_lite::io::Cursor<&'static [u8]>,
+            reads: Cell<usize>,
+        }
+
+        impl futures_lite::AsyncRead for Reader {
+            fn poll_read(
+                mut self: Pin<&mut Self>,
+                cx: &mut Context<'_>,
+                buf: &mut [u8],
+            ) -> Poll<futures_lite::io::Result<usize>> {
+                self.reads.set(self.reads.get() + 1);
+                Pin::new(&mut self.inner).poll_read(cx, buf)
+            }
+        }
+
+        impl AsyncBufRead for Reader {
+            fn poll_fill_buf(
+                self: Pin<&mut Self>,
+                cx: &mut Context<'_>,
+            ) -> Poll<futures_lite::io::Result<&[u8]>> {
+                let this = self.get_mut();
+                this.reads.set(this.reads.get() + 1);
+                Pin::new(&mut this.inner).poll_fill_buf(cx)
+            }
+
+            fn consume(mut self: Pin<&mut Self>, amt: usize) {
+                Pin::new(&mut self.inner).consume(amt)
+            }
+        }
+
+        let reader = Reader {
+            inner: futures_lite::io::Cursor::new(b"read me"),
+            reads: Cell::new(0),
+        };
+        let body = Body::from_reader_unsync(reader, 7);
+        assert_eq!(body.len(), Some(7));
+        assert_eq!(body.into_string().await.unwrap(), "read me");
+    }
 }
@@ -0,0 +1,337 @@
+use alloc::vec::Vec;
+use bytes::Bytes;
+use core::fmt;
+use core::marker::PhantomData;
+use core::ops::Range;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+use futures_lite::{Stream, StreamExt};
+use mime::Mime;
+use serde::{de::DeserializeOwned, Serialize};
+
+use super::{Body, Error};
+
+const DEFAULT_MAX_LINE_LEN: usize = 1 << 20;
+
+fn ndjson_mime() -> Mime {
+    "application/x-ndjson"
+        .parse()
+        .expect("application/x-ndjson is a valid MIME type")
+}
+
+/// A stream of the values of a newline-delimited JSON body, created by
+/// [`Body::into_ndjson`].
+///
+/// Each line holds one value. Lines are split as data arrives, so a value may span any
+/// number of chunks; only the bytes of the current line are buffered, up to
+/// [`max_line_len`](Self::max_line_len). Blank lines are skipped and a trailing `\r` is
+/// ignored.
+///
+/// A line that is not valid JSON or doesn't match `T` yields
+/// [`BodyError::JsonError`](crate::BodyError::JsonError), after which the stream moves
+/// on to the next line, unless [`fail_fast`](Self::fail_fast) is set. Errors of the
+/// underlying body and lines that are too long always end the stream.
+pub struct NdjsonStream<T> {
+    body: Body,
+    // Consumed lines are only dropped from the front once they make up half of the
+    // buffer, so that splitting a chunk of many lines does not shift it for each one.
+    buf: Vec<u8>,
+    // Start of the first line not returned yet.
+    start: usize,
+    // Bytes before this offset hold no newline.
+    scanned: usize,
+    max_line_len: usize,
+    fail_fast: bool,
+    eof: bool,
+    done: bool,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Unpin for NdjsonStream<T> {}
+
+impl<T> fmt::Debug for NdjsonStream<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NdjsonStream")
+            .field("buffered", &(self.buf.len() - self.start))
+            .field("fail_fast", &self.fail_fast)
+            .field("done", &self.done)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T> NdjsonStream<T> {
+    /// Sets whether the stream ends after the first line that fails to deserialize,
+    /// instead of skipping it. Defaults to `false`.
+    pub fn fail_fast(mut self, fail_fast: bool) -> Self {
+        self.fail_fast = fail_fast;
+        self
+    }
+
+    /// Sets the maximum length of a line, in bytes. Defaults to 1 MiB.
+    ///
+    /// A longer line yields [`BodyError::LimitExceeded`](crate::BodyError::LimitExceeded)
+    /// and ends the stream, so that a body without newlines cannot exhaust memory.
+    pub fn max_line_len(mut self, max_line_len: usize) -> Self {
+        self.max_line_len = max_line_len;
+        self
+    }
+
+    fn extend(&mut self, chunk: &[u8]) {
+        if self.start > 0 && self.start >= self.buf.len() / 2 {
+            self.buf.drain(..self.start);
+            self.scanned -= self.start;
+            self.start = 0;
+        }
+        self.buf.extend_from_slice(chunk);
+    }
+
+    // Returns the range of the next complete line in the buffer, without its newline,
+    // or of the rest of the buffer at the end of the body.
+    fn next_line(&mut self) -> Option<Range<usize>> {
+        let from = self.scanned.max(self.start);
+        if let Some(offset) = self.buf[from..].iter().position(|&b| b == b'\n') {
+            let end = from + offset;
+            let start = core::mem::replace(&mut self.start, end + 1);
+            self.scanned = self.start;
+            return Some(start..end);
+        }
+        self.scanned = self.buf.len();
+        if self.eof && self.start < self.buf.len() {
+            let start = core::mem::replace(&mut self.start, self.buf.len());
+            return Some(start..self.buf.len());
+        }
+        None
+    }
+
+    fn fail(&mut self, error: Error) -> Poll<Option<Result<T, Error>>> {
+        self.done = true;
+        self.buf = Vec::new();
+        self.start = 0;
+        self.scanned = 0;
+        Poll::Ready(Some(Err(error)))
+    }
+}
+
+impl<T: DeserializeOwned> Stream for NdjsonStream<T> {
+    type Item = Result<T, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            if self.done {
+                return Poll::Ready(None);
+            }
+
+            while let Some(range) = self.next_line() {
+                let max = self.max_line_len;
+                if range.len() > max {
+                    return self.fail(Error::LimitExceeded(max));
+                }
+                let line = &self.buf[range];
+                if line.trim_ascii().is_empty() {
+                    continue;
+                }
+                let result = serde_json::from_slice(line).map_err(Error::from);
+                if self.fail_fast {
+                    if let Err(error) = result {
+                        return self.fail(error);
+                    }
+                }
+                return Poll::Ready(Some(result));
+            }
+
+            if self.eof {
+                self.done = true;
+                return Poll::Ready(None);
+            }
+            let max = self.max_line_len;
+            if self.buf.len() - self.start > max {
+                return self.fail(Error::LimitExceeded(max));
+            }
+
+            match Pin::new(&mut self.body).poll_next(cx) {
+                Poll::Ready(Some(Ok(chunk))) => self.extend(&chunk),
+                Poll::Ready(Some(Err(error))) => return self.fail(error),
+                Poll::Ready(None) => self.eof = true,
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+impl Body {
+    /// Creates a newline-delimited JSON body from a stream of values.
+    ///
+    /// Each value is serialized on its own line, followed by `\n`. The MIME type is set
+    /// to `application/x-ndjson`.
+    ///
+    /// # Errors
+    ///
+    /// The body fails with [`BodyError::JsonError`](crate::BodyError::JsonError) if a
+    /// value cannot be serialized, and forwards the errors of the stream.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use futures_lite::stream;
+    /// use http_kit::Body;
+    ///
+    /// # async fn example() -> Result<(), http_kit::BodyError> {
+    /// let values = stream::iter([1, 2, 3].map(Ok::<_, core::convert::Infallible>));
+    /// let body = Body::from_ndjson(values);
+    /// assert_eq!(body.mime().unwrap().as_ref(), "application/x-ndjson");
+    /// assert_eq!(body.into_string().await?, "1\n2\n3\n");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_ndjson<S, T, E>(stream: S) -> Self
+    where
+        S: Stream<Item = Result<T, E>> + Send + Sync + 'static,
+        T: Serialize,
+        E: Into<Error>,
+    {
+        let lines = stream.map(|item| {
+            let value = item.map_err(Into::into)?;
+            let mut line = serde_json::to_vec(&value)?;
+            line.push(b'\n');
+            Ok::<_, Error>(Bytes::from(line))
+        });
+        Body::from_stream(lines).with_mime(ndjson_mime())
+    }
+
+    /// Converts a newline-delimited JSON body into a stream of values, see
+    /// [`NdjsonStream`].
+    ///
+    /// Unlike [`Body::into_json_stream`], a malformed line doesn't end the stream.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use futures_lite::StreamExt;
+    /// use http_kit::Body;
+    ///
+    /// # async fn example() {
+    /// let mut values = Body::from("1\nnope\n\n3\n").into_ndjson::<u32>();
+    /// assert_eq!(values.next().await.unwrap().unwrap(), 1);
+    /// assert!(values.next().await.unwrap().is_err());
+    /// assert_eq!(values.next().await.unwrap().unwrap(), 3);
+    /// assert!(values.next().await.is_none());
+    /// # }
+    /// ```
+    pub fn into_ndjson<T: DeserializeOwned>(self) -> NdjsonStream<T> {
+        NdjsonStream {
+            body: self,
+            buf: Vec::new(),
+            start: 0,
+            scanned: 0,
+            max_line_len: DEFAULT_MAX_LINE_LEN,
+            fail_fast: false,
+            eof: false,
+            done: false,
+            _marker: PhantomData,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::{string::String, vec};
+    use futures_lite::stream;
+    use serde::Deserialize;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Item {
+        id: u32,
+    }
+
+    fn chunked(chunks: Vec<&'static str>) -> Body {
+        Body::from_stream(stream::iter(
+            chunks.into_iter().map(Ok::<_, core::convert::Infallible>),
+        ))
+    }
+
+    #[tokio::test]
+    async fn items_split_across_chunks() {
+        let body = chunked(vec![
+            "{\"id\":",
+            "1}\n{\"i",
+            "d\":2}\r",
+            "\n\n",
+            "{\"id\":3}",
+        ]);
+        let items: Vec<Item> = body.into_ndjson().try_collect().await.unwrap();
+        assert_eq!(items, [Item { id: 1 }, Item { id: 2 }, Item { id: 3 }]);
+    }
+
+    #[tokio::test]
+    async fn malformed_lines() {
+        let lines = "{\"id\":1}\n{\"id\":\n{\"id\":3}\n";
+        let results: Vec<Result<Item, Error>> = chunked(vec![lines]).into_ndjson().collect().await;
+        assert_eq!(results.len(), 3);
+        assert!(matches!(results[1], Err(Error::JsonError(_))));
+        assert_eq!(results[2].as_ref().unwrap(), &Item { id: 3 });
+
+        let results: Vec<Result<Item, Error>> = chunked(vec![lines])
+            .into_ndjson()
+            .fail_fast(true)
+            .collect()
+            .await;
+        assert_eq!(results.len(), 2);
+        assert!(results[1].is_err());
+    }
+
+    #[tokio::test]
+    async fn long_lines_end_the_stream() {
+        let body = chunked(vec!["{\"id\":1}\n{\"id\":", "22}\n{\"id\":3}\n"]);
+        let results: Vec<Result<Item, Error>> = body.into_ndjson().max_line_len(8).collect().await;
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].as_ref().unwrap(), &Item { id: 1 });
+        assert!(matches!(results[1], Err(Error::LimitExceeded(8))));
+
+        // A line without newline fails as soon as it is too long.
+        let endless = stream::repeat(Ok::<_, core::convert::Infallible>("[1,")).take(100);
+        let mut values = Body::from_stream(endless)
+            .into_ndjson::<Vec<u32>>()
+            .max_line_len(30);
+        assert!(matches!(
+            values.next().await,
+            Some(Err(Error::LimitExceeded(30)))
+        ));
+        assert!(values.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn many_lines_in_one_chunk() {
+        let lines: String = (0..20_000)
+            .map(|id| alloc::format!("{{\"id\":{id}}}\n"))
+            .collect();
+        let body = Body::from(lines);
+        let items: Vec<Item> = body.into_ndjson().try_collect().await.unwrap();
+        assert_eq!(items.len(), 20_000);
+        assert_eq!(items[19_999], Item { id: 19_999 });
+    }
+
+    #[tokio::test]
+    async fn empty_trailing_line() {
+        let body = chunked(vec!["{\"id\":1}\n", "  \n"]);
+        let items: Vec<Item> = body.into_ndjson().try_collect().await.unwrap();
+        assert_eq!(items, [Item { id: 1 }]);
+    }
+
+    #[tokio::test]
+    async fn round_trip() {
+        let items = stream::iter(vec![Ok::<_, Error>(Item { id: 1 }), Ok(Item { id: 2 })]);
+        let body = Body::from_ndjson(items);
+        assert_eq!(body.mime(), Some(&ndjson_mime()));
+        let text = body.into_string().await.unwrap();
+        assert_eq!(String::from(text.as_str()), "{\"id\":1}\n{\"id\":2}\n");
+
+        let items = stream::iter(vec![Ok::<_, Error>(Item { id: 7 })]);
+        let decoded: Vec<Item> = Body::from_ndjson(items)
+            .into_ndjson()
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(decoded, [Item { id: 7 }]);
+    }
+}
@@ -0,0 +1,196 @@
+use core::{
+    fmt,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use bytes::{Buf, Bytes};
+use futures_lite::{io, ready, AsyncBufRead, AsyncRead, Stream};
+
+use super::{Body, BodyInner, BoxBufReader, Error};
+
+/// The data of a [`Body`] as an async reader, returned by [`Body::into_reader`].
+///
+/// Bodies created from a reader are read through it directly. Other bodies are read
+/// chunk by chunk, and [`poll_fill_buf`](AsyncBufRead::poll_fill_buf) hands out the
+/// rest of the current chunk without copying it. Trailers are skipped, and read errors
+/// are converted to [`io::Error`]s.
+///
+/// # Examples
+///
+/// ```rust
+/// use futures_lite::AsyncReadExt;
+/// use http_kit::{Body, BodyReader};
+///
+/// struct Upload {
+///     reader: BodyReader,
+/// }
+///
+/// # async fn example() -> std::io::Result<()> {
+/// let mut upload = Upload {
+///     reader: Body::from_bytes("payload").into_reader(),
+/// };
+/// let mut data = String::new();
+/// upload.reader.read_to_string(&mut data).await?;
+/// assert_eq!(data, "payload");
+/// # Ok(())
+/// # }
+/// ```
+pub struct BodyReader {
+    inner: Inner,
+}
+
+enum Inner {
+    Reader(BoxBufReader),
+    Chunks {
+        body: Body,
+        // The rest of the chunk being read.
+        chunk: Bytes,
+        done: bool,
+    },
+}
+
+impl fmt::Debug for BodyReader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("BodyReader");
+        if let Inner::Chunks { chunk, done, .. } = &self.inner {
+            debug.field("buffered", &chunk.len()).field("done", done);
+        }
+        debug.finish_non_exhaustive()
+    }
+}
+
+impl BodyReader {
+    pub(super) fn new(body: Body) -> Self {
+        let inner = match body.inner {
+            BodyInner::Reader { reader, buf, .. } if buf.is_empty() => Inner::Reader(reader),
+            inner => Inner::Chunks {
+                body: Body { inner, ..body },
+                chunk: Bytes::new(),
+                done: false,
+            },
+        };
+        Self { inner }
+    }
+}
+
+fn io_error(error: Error) -> io::Error {
+    match error {
+        #[cfg(feature = "std")]
+        Error::Io(error) => error,
+        error => io::Error::other(error),
+    }
+}
+
+impl AsyncBufRead for BodyReader {
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<&[u8]>> {
+        match &mut self.get_mut().inner {
+            Inner::Reader(reader) => reader.as_mut().poll_fill_buf(cx),
+            Inner::Chunks { body, chunk, done } => {
+                // The body yields no empty chunks, so an empty one means the end.
+                while chunk.is_empty() && !*done {
+                    match ready!(Pin::new(&mut *body).poll_next(cx)) {
+                        Some(Ok(data)) => *chunk = data,
+                        Some(Err(error)) => return Poll::Ready(Err(io_error(error))),
+                        None => *done = true,
+                    }
+                }
+                Poll::Ready(Ok(chunk))
+            }
+        }
+    }
+
+    fn consume(self: Pin<&mut Self>, amt: usize) {
+        match &mut self.get_mut().inner {
+            Inner::Reader(reader) => reader.as_mut().consume(amt),
+            Inner::Chunks { chunk, .. } => chunk.advance(amt.min(chunk.len())),
+        }
+    }
+}
+
+impl AsyncRead for BodyReader {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        if let Inner::Reader(reader) = &mut self.inner {
+            return reader.as_mut().poll_read(cx, buf);
+        }
+        let data = ready!(self.as_mut().poll_fill_buf(cx))?;
+        let len = data.len().min(buf.len());
+        buf[..len].copy_from_slice(&data[..len]);
+        self.consume(len);
+        Poll::Ready(Ok(len))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::{string::String, vec::Vec};
+    use futures_lite::{stream, AsyncBufReadExt, AsyncReadExt, StreamExt};
+
+    fn chunked() -> Body {
+        let chunks = ["first li", "ne\nsec", "", "ond line\nlast"];
+        Body::from_stream(stream::iter(
+            chunks.map(|chunk| Ok::<_, Error>(Bytes::from(chunk))),
+        ))
+    }
+
+    #[tokio::test]
+    async fn lines_span_chunks() {
+        let mut reader = chunked().into_reader();
+        let mut lines = Vec::new();
+        let mut line = String::new();
+        while reader.read_line(&mut line).await.unwrap() > 0 {
+            lines.push(core::mem::take(&mut line));
+        }
+        assert_eq!(lines, ["first line\n", "second line\n", "last"]);
+
+        let text = Body::from_text("one\ntwo");
+        let lines: Vec<String> = text.into_reader().lines().try_collect().await.unwrap();
+        assert_eq!(lines, ["one", "two"]);
+    }
+
+    #[tokio::test]
+    async fn small_reads_cross_chunk_boundaries() {
+        let mut reader = chunked().into_reader();
+        let mut data = Vec::new();
+        let mut buf = [0; 3];
+        loop {
+            let read = reader.read(&mut buf).await.unwrap();
+            if read == 0 {
+                break;
+            }
+            // Reads never span two chunks.
+            assert!(read <= 3);
+            data.extend_from_slice(&buf[..read]);
+        }
+        assert_eq!(data, b"first line\nsecond line\nlast");
+        assert_eq!(reader.read(&mut buf).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn buffers_are_chunks() {
+        let data = Bytes::from_static(b"zero copy");
+        let mut reader = Body::from_bytes(data.clone()).into_reader();
+        let filled = reader.fill_buf().await.unwrap();
+        assert_eq!(filled.as_ptr(), data.as_ptr());
+        assert_eq!(filled.len(), 9);
+        Pin::new(&mut reader).consume(5);
+        assert_eq!(reader.fill_buf().await.unwrap(), b"copy");
+
+        let mut raw = Vec::new();
+        let reader = Body::from_reader(futures_lite::io::Cursor::new(b"raw".to_vec()), 3);
+        reader.into_reader().read_to_end(&mut raw).await.unwrap();
+        assert_eq!(raw, b"raw");
+
+        let error = Body::frozen()
+            .into_reader()
+            .read_to_end(&mut raw)
+            .await
+            .unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::Other);
+    }
+}
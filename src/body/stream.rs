@@ -0,0 +1,282 @@
+use core::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use bytes::Bytes;
+use futures_lite::{ready, Stream};
+use http_body::{Frame, SizeHint};
+
+use super::{Body, Error};
+
+/// The data of a [`Body`] as a stream of chunks, returned by [`Body::into_data_stream`].
+///
+/// Trailers are skipped, and read errors are converted to [`crate::Error`] with the
+/// status of the [`Error`](super::Error). Unlike an
+/// `impl Stream`, the type can be named, for instance in struct fields.
+///
+/// # Examples
+///
+/// ```rust
+/// use futures_lite::StreamExt;
+/// use http_kit::{Body, BodyDataStream};
+///
+/// struct Upload {
+///     data: BodyDataStream,
+/// }
+///
+/// # async fn example() -> http_kit::Result<()> {
+/// let mut upload = Upload {
+///     data: Body::from_bytes("payload").into_data_stream(),
+/// };
+/// while let Some(chunk) = upload.data.next().await {
+///     assert_eq!(chunk?, "payload");
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct BodyDataStream {
+    body: Body,
+}
+
+impl BodyDataStream {
+    pub(super) fn new(body: Body) -> Self {
+        Self { body }
+    }
+}
+
+impl Stream for BodyDataStream {
+    type Item = Result<Bytes, crate::Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            let frame = ready!(http_body::Body::poll_frame(Pin::new(&mut self.body), cx));
+            match frame {
+                Some(Ok(frame)) => match frame.into_data() {
+                    Ok(data) if data.is_empty() => continue,
+                    Ok(data) => return Poll::Ready(Some(Ok(data))),
+                    Err(_trailers) => continue,
+                },
+                Some(Err(error)) => {
+                    let status = crate::HttpError::status(&error);
+                    return Poll::Ready(Some(Err(crate::Error::new(error).set_status(status))));
+                }
+                None => return Poll::Ready(None),
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // Chunks can have any size, so only an empty body bounds their number.
+        match http_body::Body::size_hint(&self.body).upper() {
+            Some(0) => (0, Some(0)),
+            _ => (0, None),
+        }
+    }
+}
+
+pin_project_lite::pin_project! {
+    // Body applying a function to each data chunk of `body`, as it is read.
+    pub(super) struct MapChunks<F> {
+        body: Body,
+        f: F,
+    }
+}
+
+impl<F> MapChunks<F> {
+    pub(super) fn new(body: Body, f: F) -> Self {
+        Self { body, f }
+    }
+}
+
+impl<F> http_body::Body for MapChunks<F>
+where
+    F: FnMut(Bytes) -> Bytes,
+{
+    type Data = Bytes;
+    type Error = Error;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let this = self.project();
+        let frame = ready!(Pin::new(this.body).poll_nested(cx));
+        Poll::Ready(frame.map(|result| result.map(|frame| frame.map_data(this.f))))
+    }
+
+    fn is_end_stream(&self) -> bool {
+        http_body::Body::is_end_stream(&self.body)
+    }
+
+    // The function may change the size of the chunks, so the length is unknown.
+    fn size_hint(&self) -> SizeHint {
+        SizeHint::new()
+    }
+}
+
+pin_project_lite::pin_project! {
+    // Body calling `callback` with the number of data bytes read so far from `body`.
+    pub(super) struct Progress<F> {
+        body: Body,
+        read: u64,
+        callback: F,
+    }
+}
+
+impl<F> Progress<F> {
+    pub(super) fn new(body: Body, callback: F) -> Self {
+        Self {
+            body,
+            read: 0,
+            callback,
+        }
+    }
+}
+
+impl<F> http_body::Body for Progress<F>
+where
+    F: Fn(u64),
+{
+    type Data = Bytes;
+    type Error = Error;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let this = self.project();
+        let frame = ready!(Pin::new(this.body).poll_nested(cx));
+        if let Some(data) = frame
+            .as_ref()
+            .and_then(|frame| frame.as_ref().ok()?.data_ref())
+        {
+            if !data.is_empty() {
+                *this.read += data.len() as u64;
+                (this.callback)(*this.read);
+            }
+        }
+        Poll::Ready(frame)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        http_body::Body::is_end_stream(&self.body)
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        http_body::Body::size_hint(&self.body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use alloc::{string::ToString, vec, vec::Vec};
+    use futures_lite::{stream, StreamExt};
+    use http::HeaderMap;
+
+    fn lines() -> Body {
+        Body::from_stream(stream::iter(vec![
+            Ok::<_, Error>("alpha\n"),
+            Ok("beta\n"),
+            Ok("gamma\n"),
+        ]))
+    }
+
+    async fn chunks(body: Body) -> Vec<Bytes> {
+        body.into_data_stream().map(Result::unwrap).collect().await
+    }
+
+    #[tokio::test]
+    async fn chained_maps_apply_per_chunk() {
+        let prefix = |chunk: Bytes| {
+            let mut prefixed = Vec::from(&b"> "[..]);
+            prefixed.extend_from_slice(&chunk);
+            Bytes::from(prefixed)
+        };
+        let body = lines()
+            .map_chunks(prefix)
+            .map_chunks(|chunk| Bytes::from(chunk.to_ascii_uppercase()));
+        assert_eq!(body.len(), None);
+        assert_eq!(chunks(body).await, ["> ALPHA\n", "> BETA\n", "> GAMMA\n"]);
+
+        // Bodies in memory are mapped as one chunk.
+        let mut calls = 0;
+        let body = Body::from_text("a\nb\n").map_chunks(move |chunk| {
+            calls += 1;
+            assert_eq!(calls, 1);
+            chunk.slice(2..)
+        });
+        assert_eq!(body.mime(), Some(&mime::TEXT_PLAIN_UTF_8));
+        assert_eq!(body.into_bytes().await.unwrap(), "b\n");
+    }
+
+    #[tokio::test]
+    async fn trailers_survive_but_are_not_streamed() {
+        let mut trailers = HeaderMap::new();
+        trailers.insert("x-checksum", "abc".parse().unwrap());
+        let body = lines()
+            .with_trailers(trailers.clone())
+            .map_chunks(|chunk| chunk.slice(..1));
+        let (data, received) = body.into_bytes_with_trailers().await.unwrap();
+        assert_eq!(
+            (data, received),
+            (Bytes::from("abg"), Some(trailers.clone()))
+        );
+
+        let body = lines().with_trailers(trailers);
+        assert_eq!(chunks(body).await, ["alpha\n", "beta\n", "gamma\n"]);
+    }
+
+    #[tokio::test]
+    async fn progress_counts_every_way_of_reading() {
+        use alloc::sync::Arc;
+        use core::sync::atomic::{AtomicU64, Ordering};
+
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let record = |seen: &Arc<std::sync::Mutex<Vec<u64>>>| {
+            let seen = seen.clone();
+            move |read| seen.lock().unwrap().push(read)
+        };
+        let body = lines().with_progress(record(&seen));
+        assert_eq!(body.into_bytes().await.unwrap().len(), 17);
+        assert_eq!(*seen.lock().unwrap(), [6, 11, 17]);
+
+        // In-memory bodies are one chunk, and the length stays known to servers.
+        let read = Arc::new(AtomicU64::new(0));
+        let counter = read.clone();
+        let body = Body::from_text("hello")
+            .with_progress(move |bytes| counter.store(bytes, Ordering::Relaxed));
+        assert_eq!(body.mime(), Some(&mime::TEXT_PLAIN_UTF_8));
+        assert_eq!(body.len(), Some(5));
+        assert_eq!(http_body::Body::size_hint(&body).exact(), Some(5));
+        let collected = http_body_util::BodyExt::collect(body).await.unwrap();
+        assert_eq!(collected.to_bytes(), "hello");
+        assert_eq!(read.load(Ordering::Relaxed), 5);
+
+        // Partly read streams report what was read.
+        seen.lock().unwrap().clear();
+        let mut data = lines().with_progress(record(&seen)).into_data_stream();
+        data.next().await.unwrap().unwrap();
+        drop(data);
+        assert_eq!(*seen.lock().unwrap(), [6]);
+    }
+
+    #[tokio::test]
+    async fn read_errors_become_crate_errors() {
+        let mut data = Body::from_stream(stream::iter([Ok("ok"), Err(Error::LimitExceeded(2))]))
+            .into_data_stream();
+        assert_eq!(data.next().await.unwrap().unwrap(), "ok");
+        let error: crate::Error = data.next().await.unwrap().unwrap_err();
+        assert_eq!(error.to_string(), "body exceeds the limit of 2 bytes");
+        assert_eq!(
+            error.into_boxed_http_error().status(),
+            http::StatusCode::PAYLOAD_TOO_LARGE
+        );
+
+        assert_eq!(Body::empty().into_data_stream().size_hint(), (0, Some(0)));
+    }
+}
@@ -0,0 +1,291 @@
+extern crate std;
+
+use alloc::{
+    collections::VecDeque,
+    string::{String, ToString},
+    sync::Arc,
+};
+use core::{
+    pin::Pin,
+    task::{Context, Poll, Waker},
+};
+use std::{io, sync::Mutex, task::Wake};
+
+use bytes::Bytes;
+use http_body::{Frame, SizeHint};
+
+use super::{Body, Error};
+
+// State shared by the two halves of a tee.
+//
+// Whichever half needs a frame that the other has not read yet polls the source and
+// queues a copy for the other half. A half stops polling while the other one has
+// `high_water_mark` bytes or more queued, so memory is bounded by the slower reader.
+struct Shared {
+    source: Body,
+    queues: [VecDeque<Frame<Bytes>>; 2],
+    buffered: [usize; 2],
+    open: [bool; 2],
+    finished: bool,
+    // Message of a source error, for the half that did not receive the error itself.
+    errors: [Option<String>; 2],
+    high_water_mark: usize,
+}
+
+// Wakes both halves: the source keeps only the last waker it was polled with, so it is
+// polled with this one instead, and neither half misses the data the other waits for.
+#[derive(Default)]
+struct Wakers([Mutex<Option<Waker>>; 2]);
+
+impl Wakers {
+    fn register(&self, side: usize, waker: &Waker) {
+        let mut slot = self.0[side].lock().expect("tee waker poisoned");
+        match &*slot {
+            Some(registered) if registered.will_wake(waker) => {}
+            _ => *slot = Some(waker.clone()),
+        }
+    }
+
+    fn wake_side(&self, side: usize) {
+        let waker = self.0[side].lock().expect("tee waker poisoned").take();
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+impl Wake for Wakers {
+    fn wake(self: Arc<Self>) {
+        self.wake_side(0);
+        self.wake_side(1);
+    }
+}
+
+struct State {
+    shared: Mutex<Shared>,
+    wakers: Arc<Wakers>,
+}
+
+// One of the two bodies returned by `Body::tee`.
+pub(super) struct TeeHalf {
+    state: Arc<State>,
+    side: usize,
+    hint: SizeHint,
+}
+
+pub(super) fn tee(source: Body, high_water_mark: usize) -> (TeeHalf, TeeHalf) {
+    let hint = http_body::Body::size_hint(&source);
+    let state = Arc::new(State {
+        shared: Mutex::new(Shared {
+            source,
+            queues: Default::default(),
+            buffered: [0; 2],
+            open: [true; 2],
+            finished: false,
+            errors: Default::default(),
+            high_water_mark,
+        }),
+        wakers: Arc::default(),
+    });
+    let half = |side| TeeHalf {
+        state: state.clone(),
+        side,
+        hint,
+    };
+    (half(0), half(1))
+}
+
+fn duplicate(frame: &Frame<Bytes>) -> Frame<Bytes> {
+    match (frame.data_ref(), frame.trailers_ref()) {
+        (Some(data), _) => Frame::data(data.clone()),
+        (None, Some(trailers)) => Frame::trailers(trailers.clone()),
+        (None, None) => Frame::data(Bytes::new()),
+    }
+}
+
+impl TeeHalf {
+    fn consumed(&mut self, frame: &Frame<Bytes>) {
+        let Some(data) = frame.data_ref() else {
+            return;
+        };
+        let len = data.len() as u64;
+        let mut hint = SizeHint::new();
+        hint.set_lower(self.hint.lower().saturating_sub(len));
+        if let Some(upper) = self.hint.upper() {
+            hint.set_upper(upper.saturating_sub(len));
+        }
+        self.hint = hint;
+    }
+}
+
+impl http_body::Body for TeeHalf {
+    type Data = Bytes;
+    type Error = Error;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let (side, other) = (self.side, 1 - self.side);
+        let state = self.state.clone();
+        let mut shared = state.shared.lock().expect("tee state poisoned");
+
+        if let Some(frame) = shared.queues[side].pop_front() {
+            if let Some(data) = frame.data_ref() {
+                shared.buffered[side] -= data.len();
+            }
+            drop(shared);
+            state.wakers.wake_side(other);
+            self.consumed(&frame);
+            return Poll::Ready(Some(Ok(frame)));
+        }
+        if shared.finished {
+            let error = shared.errors[side].take();
+            return Poll::Ready(error.map(|message| Err(Error::Io(io::Error::other(message)))));
+        }
+
+        state.wakers.register(side, cx.waker());
+        if shared.open[other] && shared.buffered[other] >= shared.high_water_mark {
+            return Poll::Pending;
+        }
+        let waker = Waker::from(state.wakers.clone());
+        let polled = Pin::new(&mut shared.source).poll_nested(&mut Context::from_waker(&waker));
+        let result = match polled {
+            Poll::Pending => return Poll::Pending,
+            Poll::Ready(Some(Ok(frame))) => {
+                if shared.open[other] {
+                    if let Some(data) = frame.data_ref() {
+                        shared.buffered[other] += data.len();
+                    }
+                    let copy = duplicate(&frame);
+                    shared.queues[other].push_back(copy);
+                }
+                Some(Ok(frame))
+            }
+            Poll::Ready(Some(Err(error))) => {
+                shared.finished = true;
+                if shared.open[other] {
+                    shared.errors[other] = Some(error.to_string());
+                }
+                Some(Err(error))
+            }
+            Poll::Ready(None) => {
+                shared.finished = true;
+                None
+            }
+        };
+        drop(shared);
+        state.wakers.wake_side(other);
+        if let Some(Ok(frame)) = &result {
+            self.consumed(frame);
+        }
+        Poll::Ready(result)
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.hint
+    }
+}
+
+impl Drop for TeeHalf {
+    fn drop(&mut self) {
+        if let Ok(mut shared) = self.state.shared.lock() {
+            shared.open[self.side] = false;
+            shared.queues[self.side].clear();
+            shared.buffered[self.side] = 0;
+        }
+        // The other half may be waiting for this one to catch up.
+        self.state.wakers.wake_side(1 - self.side);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::{format, vec::Vec};
+    use futures_lite::{future, stream, StreamExt};
+    use http::HeaderMap;
+
+    fn chunks(count: usize) -> Body {
+        let chunks: Vec<Result<String, Error>> =
+            (0..count).map(|i| Ok(format!("{i:03},"))).collect();
+        Body::from_stream(stream::iter(chunks))
+    }
+
+    async fn collect(mut body: Body) -> Vec<Bytes> {
+        let mut chunks = Vec::new();
+        while let Some(chunk) = body.next().await {
+            chunks.push(chunk.unwrap());
+        }
+        chunks
+    }
+
+    #[tokio::test]
+    async fn halves_see_the_same_chunks() {
+        let (left, right) = chunks(100).tee_with(16);
+        assert_eq!(left.len(), None);
+        let (left, right) = future::zip(collect(left), collect(right)).await;
+        assert_eq!(left.len(), 100);
+        assert_eq!(left, right);
+        assert_eq!(left[42], "042,");
+
+        let (left, right) = Body::from_bytes("once").tee();
+        assert_eq!(left.into_bytes().await.unwrap(), "once");
+        assert_eq!(right.into_bytes().await.unwrap(), "once");
+    }
+
+    #[tokio::test]
+    async fn fast_half_waits_for_the_slow_one() {
+        let (mut fast, mut slow) = chunks(100).tee_with(8);
+        let mut cx = Context::from_waker(Waker::noop());
+        let mut read = 0;
+        while let Poll::Ready(Some(chunk)) = Pin::new(&mut fast).poll_next(&mut cx) {
+            chunk.unwrap();
+            read += 1;
+        }
+        // Two 4-byte chunks reach the high-water mark.
+        assert_eq!(read, 2);
+
+        assert_eq!(slow.next().await.unwrap().unwrap(), "000,");
+        assert_eq!(fast.next().await.unwrap().unwrap(), "002,");
+        drop(fast);
+        assert_eq!(collect(slow).await.len(), 99);
+    }
+
+    #[tokio::test]
+    async fn dropping_a_half_does_not_stall_the_other() {
+        let (left, right) = chunks(100).tee_with(1);
+        drop(right);
+        assert_eq!(collect(left).await.len(), 100);
+
+        let (mut left, right) = chunks(100).tee_with(1);
+        assert_eq!(left.next().await.unwrap().unwrap(), "000,");
+        drop(right);
+        assert_eq!(collect(left).await.len(), 99);
+    }
+
+    #[tokio::test]
+    async fn errors_and_trailers_reach_both_halves() {
+        let source = Body::from_stream(stream::iter([Ok("data"), Err(Error::LimitExceeded(4))]));
+        let (left, right) = source.tee();
+        assert!(matches!(
+            left.into_bytes().await,
+            Err(Error::LimitExceeded(4))
+        ));
+        let error = right.into_bytes().await.unwrap_err();
+        assert_eq!(error.to_string(), "body exceeds the limit of 4 bytes");
+
+        let mut trailers = HeaderMap::new();
+        trailers.insert("grpc-status", "0".parse().unwrap());
+        let source = chunks(3).with_trailers(trailers.clone());
+        let (left, right) = source.tee();
+        let (left, right) = future::zip(
+            left.into_bytes_with_trailers(),
+            right.into_bytes_with_trailers(),
+        )
+        .await;
+        let expected = (Bytes::from("000,001,002,"), Some(trailers));
+        assert_eq!(left.unwrap(), expected);
+        assert_eq!(right.unwrap(), expected);
+    }
+}
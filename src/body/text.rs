@@ -0,0 +1,169 @@
+use alloc::vec::Vec;
+use bytes::Bytes;
+use bytestr::ByteStr;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+use futures_lite::{ready, Stream};
+
+use super::{Body, Error};
+
+// Incrementally validates a body as UTF-8, carrying incomplete trailing
+// sequences over to the next chunk.
+struct TextStream {
+    body: Body,
+    // Bytes of a multi-byte character split across a chunk boundary (at most 3).
+    carry: Vec<u8>,
+    // Number of bytes already yielded, used to report error offsets.
+    offset: usize,
+    done: bool,
+}
+
+impl TextStream {
+    fn fail(&mut self, offset: usize) -> Poll<Option<Result<ByteStr, Error>>> {
+        self.done = true;
+        Poll::Ready(Some(Err(Error::InvalidUtf8 { offset })))
+    }
+}
+
+impl Stream for TextStream {
+    type Item = Result<ByteStr, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            if self.done {
+                return Poll::Ready(None);
+            }
+
+            let chunk = match ready!(Pin::new(&mut self.body).poll_next(cx)) {
+                Some(Ok(chunk)) => chunk,
+                Some(Err(error)) => {
+                    self.done = true;
+                    return Poll::Ready(Some(Err(error)));
+                }
+                None => {
+                    if self.carry.is_empty() {
+                        self.done = true;
+                        return Poll::Ready(None);
+                    }
+                    let offset = self.offset;
+                    return self.fail(offset);
+                }
+            };
+
+            let mut buf = if self.carry.is_empty() {
+                chunk
+            } else {
+                let mut joined = core::mem::take(&mut self.carry);
+                joined.extend_from_slice(&chunk);
+                Bytes::from(joined)
+            };
+
+            let valid = match core::str::from_utf8(&buf) {
+                Ok(_) => buf.len(),
+                Err(error) if error.error_len().is_none() => error.valid_up_to(),
+                Err(error) => {
+                    let offset = self.offset + error.valid_up_to();
+                    return self.fail(offset);
+                }
+            };
+
+            self.carry.extend_from_slice(&buf[valid..]);
+            buf.truncate(valid);
+            if buf.is_empty() {
+                continue;
+            }
+            self.offset += buf.len();
+            return Poll::Ready(Some(Ok(ByteStr::from_utf8(buf)?)));
+        }
+    }
+}
+
+impl Body {
+    /// Converts the body into a stream of UTF-8 text chunks.
+    ///
+    /// Unlike decoding each raw chunk separately, multi-byte characters split across
+    /// chunk boundaries are carried over to the next chunk, so every yielded item is
+    /// valid UTF-8 and their concatenation equals the whole body.
+    ///
+    /// # Errors
+    ///
+    /// The stream yields [`BodyError::InvalidUtf8`](crate::BodyError::InvalidUtf8) with the
+    /// byte offset of the first invalid byte (or of the truncated character at the end of
+    /// the body) and then terminates. Errors of the underlying body are forwarded as is.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use http_kit::Body;
+    /// use futures_lite::{stream, StreamExt};
+    ///
+    /// # async fn example() -> Result<(), http_kit::BodyError> {
+    /// let chunks = stream::iter(vec![
+    ///     Ok::<_, std::io::Error>(&b"caf\xC3"[..]),
+    ///     Ok(&b"\xA9"[..]),
+    /// ]);
+    /// let mut text = Body::from_stream(chunks).into_text_stream();
+    ///
+    /// assert_eq!(text.next().await.unwrap()?, "caf");
+    /// assert_eq!(text.next().await.unwrap()?, "é");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn into_text_stream(self) -> impl Stream<Item = Result<ByteStr, Error>> + Send {
+        TextStream {
+            body: self,
+            carry: Vec::new(),
+            offset: 0,
+            done: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::{string::String, vec};
+    use futures_lite::{stream, StreamExt};
+
+    fn chunked(chunks: Vec<&'static [u8]>) -> Body {
+        Body::from_stream(stream::iter(
+            chunks.into_iter().map(Ok::<_, core::convert::Infallible>),
+        ))
+    }
+
+    #[tokio::test]
+    async fn carries_split_characters() {
+        // "€" is E2 82 AC, split across three chunks.
+        let body = chunked(vec![b"price: \xE2", b"\x82", b"\xAC 5", b""]);
+        let chunks: Vec<ByteStr> = body.into_text_stream().try_collect().await.unwrap();
+
+        assert_eq!(chunks, vec!["price: ", "€ 5"]);
+        let joined: String = chunks.iter().map(|chunk| chunk.as_str()).collect();
+        assert_eq!(joined, "price: € 5");
+    }
+
+    #[tokio::test]
+    async fn reports_offset_of_invalid_byte() {
+        let body = chunked(vec![b"hello ", b"wor\xFFld"]);
+        let mut stream = body.into_text_stream();
+
+        assert_eq!(stream.next().await.unwrap().unwrap(), "hello ");
+        match stream.next().await.unwrap() {
+            Err(Error::InvalidUtf8 { offset }) => assert_eq!(offset, 9),
+            other => panic!("unexpected item: {other:?}"),
+        }
+        assert!(stream.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn truncated_character_at_end_is_an_error() {
+        let body = chunked(vec![b"ab", b"\xE2\x82"]);
+        let mut stream = body.into_text_stream();
+
+        assert_eq!(stream.next().await.unwrap().unwrap(), "ab");
+        assert!(matches!(
+            stream.next().await.unwrap(),
+            Err(Error::InvalidUtf8 { offset: 2 })
+        ));
+    }
+}
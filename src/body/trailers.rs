@@ -0,0 +1,85 @@
+use alloc::boxed::Box;
+use core::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use bytes::Bytes;
+use futures_lite::ready;
+use http::HeaderMap;
+use http_body::{Frame, SizeHint};
+
+use super::{Body, Error};
+
+pub(super) type BoxTrailers = Pin<Box<dyn Future<Output = HeaderMap> + Send + Sync + 'static>>;
+
+// Body wrapper sending trailers once the data of `body` is complete. Trailers sent by
+// `body` itself are merged into them, the wrapper's values taking precedence.
+pub(super) struct Trailed {
+    body: Body,
+    trailers: Option<BoxTrailers>,
+    received: Option<HeaderMap>,
+    body_done: bool,
+    done: bool,
+}
+
+impl Trailed {
+    pub(super) fn new(body: Body, trailers: BoxTrailers) -> Self {
+        Self {
+            body,
+            trailers: Some(trailers),
+            received: None,
+            body_done: false,
+            done: false,
+        }
+    }
+}
+
+impl http_body::Body for Trailed {
+    type Data = Bytes;
+    type Error = Error;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        while !self.body_done {
+            match ready!(Pin::new(&mut self.body).poll_nested(cx)) {
+                Some(Ok(frame)) => match frame.into_trailers() {
+                    Ok(trailers) => self
+                        .received
+                        .get_or_insert_with(HeaderMap::new)
+                        .extend(trailers),
+                    Err(frame) => return Poll::Ready(Some(Ok(frame))),
+                },
+                Some(Err(error)) => {
+                    self.body_done = true;
+                    self.done = true;
+                    return Poll::Ready(Some(Err(error)));
+                }
+                None => self.body_done = true,
+            }
+        }
+        if self.done {
+            return Poll::Ready(None);
+        }
+        let extra = match self.trailers.as_mut() {
+            Some(trailers) => ready!(trailers.as_mut().poll(cx)),
+            None => HeaderMap::new(),
+        };
+        self.trailers = None;
+        self.done = true;
+        let mut trailers = self.received.take().unwrap_or_default();
+        trailers.extend(extra);
+        Poll::Ready(Some(Ok(Frame::trailers(trailers))))
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.done
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        http_body::Body::size_hint(&self.body)
+    }
+}
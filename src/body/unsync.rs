@@ -0,0 +1,55 @@
+//! Sources that are `Send` but not `Sync`.
+
+use core::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_lite::{io, AsyncBufRead, AsyncRead, Stream};
+use pin_project_lite::pin_project;
+use sync_wrapper::SyncWrapper;
+
+pin_project! {
+    // Makes a `Send` source `Sync`. A body only polls its source through `Pin<&mut>`,
+    // so the source is never shared, and `SyncWrapper` gives no access through `&`.
+    pub(super) struct Unsync<T> {
+        #[pin]
+        inner: SyncWrapper<T>,
+    }
+}
+
+impl<T> Unsync<T> {
+    pub(super) fn new(inner: T) -> Self {
+        Self {
+            inner: SyncWrapper::new(inner),
+        }
+    }
+}
+
+impl<S: Stream> Stream for Unsync<S> {
+    type Item = S::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.project().inner.get_pin_mut().poll_next(cx)
+    }
+}
+
+impl<R: AsyncRead> AsyncRead for Unsync<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        self.project().inner.get_pin_mut().poll_read(cx, buf)
+    }
+}
+
+impl<R: AsyncBufRead> AsyncBufRead for Unsync<R> {
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<&[u8]>> {
+        self.project().inner.get_pin_mut().poll_fill_buf(cx)
+    }
+
+    fn consume(self: Pin<&mut Self>, amt: usize) {
+        self.project().inner.get_pin_mut().consume(amt)
+    }
+}
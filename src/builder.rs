@@ -0,0 +1,339 @@
+//! Fallible builders for [`Request`] and [`Response`].
+//!
+//! Methods, URIs, status codes and header names or values that fail to parse are not
+//! reported right away: the first error is kept and returned by `build()`, so builders
+//! can be chained with values read from configuration without ever panicking.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use http_kit::builder::RequestBuilder;
+//!
+//! let request = RequestBuilder::new()
+//!     .method("POST")
+//!     .uri("/reports")
+//!     .try_header("x-tenant", "acme")
+//!     .text("hello")
+//!     .build()
+//!     .unwrap();
+//! assert_eq!(request.headers()["content-type"], "text/plain; charset=utf-8");
+//!
+//! let error = RequestBuilder::new().uri("not a uri").build();
+//! assert!(error.is_err());
+//! ```
+
+use core::any::Any;
+
+use bytes::Bytes;
+use bytestr::ByteStr;
+use http::{
+    header::{self, HeaderName, HeaderValue},
+    Method, StatusCode, Uri, Version,
+};
+
+use crate::{Body, Request, Response};
+
+// Returns the `Content-Type` to derive from the body, unless `headers` already has one.
+fn content_type(headers: Option<&http::HeaderMap>, body: &Body) -> Option<HeaderValue> {
+    if headers.is_some_and(|headers| headers.contains_key(header::CONTENT_TYPE)) {
+        return None;
+    }
+    HeaderValue::from_str(body.mime()?.as_ref()).ok()
+}
+
+macro_rules! common_setters {
+    () => {
+        /// Sets the HTTP version.
+        #[must_use]
+        pub fn version(mut self, version: Version) -> Self {
+            self.inner = self.inner.version(version);
+            self
+        }
+
+        /// Appends a header, keeping the error for `build()` if the name or the value is
+        /// invalid.
+        #[must_use]
+        pub fn try_header<K, V>(mut self, name: K, value: V) -> Self
+        where
+            HeaderName: TryFrom<K>,
+            <HeaderName as TryFrom<K>>::Error: Into<http::Error>,
+            HeaderValue: TryFrom<V>,
+            <HeaderValue as TryFrom<V>>::Error: Into<http::Error>,
+        {
+            self.inner = self.inner.header(name, value);
+            self
+        }
+
+        /// Inserts an extension, replacing any previous one of the same type.
+        #[must_use]
+        pub fn extension<T>(mut self, value: T) -> Self
+        where
+            T: Clone + Any + Send + Sync + 'static,
+        {
+            self.inner = self.inner.extension(value);
+            self
+        }
+
+        /// Sets the body.
+        ///
+        /// Unless a `Content-Type` header is set, `build()` derives it from the MIME
+        /// type of the body.
+        #[must_use]
+        pub fn body(mut self, body: impl Into<Body>) -> Self {
+            self.body = body.into();
+            self
+        }
+
+        /// Sets a binary body, like [`Body::from_bytes`].
+        #[must_use]
+        pub fn bytes(self, data: impl Into<Bytes>) -> Self {
+            self.body(Body::from_bytes(data))
+        }
+
+        /// Sets a text body, like [`Body::from_text`].
+        #[must_use]
+        pub fn text(self, text: impl Into<ByteStr>) -> Self {
+            self.body(Body::from_text(text))
+        }
+
+        /// Sets a JSON body, like [`Body::from_json`].
+        ///
+        /// # Errors
+        ///
+        /// Returns an error if `value` cannot be serialized.
+        #[cfg(feature = "json")]
+        pub fn json<T: serde::Serialize>(self, value: T) -> Result<Self, serde_json::Error> {
+            Ok(self.body(Body::from_json(value)?))
+        }
+
+        /// Sets an indented JSON body, like [`Body::from_json_pretty`].
+        ///
+        /// # Errors
+        ///
+        /// Returns an error if `value` cannot be serialized.
+        #[cfg(feature = "json")]
+        pub fn json_pretty<T: serde::Serialize>(self, value: T) -> Result<Self, serde_json::Error> {
+            Ok(self.body(Body::from_json_pretty(value)?))
+        }
+
+        /// Sets a newline-delimited JSON body, like [`Body::from_ndjson`].
+        #[cfg(feature = "json")]
+        #[must_use]
+        pub fn ndjson<S, T, E>(self, stream: S) -> Self
+        where
+            S: futures_lite::Stream<Item = Result<T, E>> + Send + Sync + 'static,
+            T: serde::Serialize,
+            E: Into<crate::BodyError>,
+        {
+            self.body(Body::from_ndjson(stream))
+        }
+
+        /// Sets a URL-encoded form body, like [`Body::from_form`].
+        ///
+        /// # Errors
+        ///
+        /// Returns an error if `value` cannot be serialized.
+        #[cfg(feature = "form")]
+        pub fn form<T: serde::Serialize>(
+            self,
+            value: T,
+        ) -> Result<Self, serde_urlencoded::ser::Error> {
+            Ok(self.body(Body::from_form(value)?))
+        }
+    };
+}
+
+/// A builder of [`Request`]s that never panics.
+///
+/// Defaults to a `GET /` request with an empty body.
+#[derive(Debug, Default)]
+pub struct RequestBuilder {
+    inner: http::request::Builder,
+    body: Body,
+}
+
+impl RequestBuilder {
+    /// Creates a builder for a `GET /` request with an empty body.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the method, keeping the error for `build()` if it is invalid.
+    #[must_use]
+    pub fn method<T>(mut self, method: T) -> Self
+    where
+        Method: TryFrom<T>,
+        <Method as TryFrom<T>>::Error: Into<http::Error>,
+    {
+        self.inner = self.inner.method(method);
+        self
+    }
+
+    /// Sets the URI, keeping the error for `build()` if it is invalid.
+    #[must_use]
+    pub fn uri<T>(mut self, uri: T) -> Self
+    where
+        Uri: TryFrom<T>,
+        <Uri as TryFrom<T>>::Error: Into<http::Error>,
+    {
+        self.inner = self.inner.uri(uri);
+        self
+    }
+
+    common_setters!();
+
+    /// Builds the request.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error met while setting the method, the URI or a header.
+    pub fn build(self) -> Result<Request, http::Error> {
+        let mut inner = self.inner;
+        if let Some(value) = content_type(inner.headers_ref(), &self.body) {
+            inner = inner.header(header::CONTENT_TYPE, value);
+        }
+        inner.body(self.body)
+    }
+}
+
+/// A builder of [`Response`]s that never panics.
+///
+/// Defaults to a `200 OK` response with an empty body.
+#[derive(Debug, Default)]
+pub struct ResponseBuilder {
+    inner: http::response::Builder,
+    body: Body,
+}
+
+impl ResponseBuilder {
+    /// Creates a builder for a `200 OK` response with an empty body.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the status code, keeping the error for `build()` if it is invalid.
+    #[must_use]
+    pub fn status<T>(mut self, status: T) -> Self
+    where
+        StatusCode: TryFrom<T>,
+        <StatusCode as TryFrom<T>>::Error: Into<http::Error>,
+    {
+        self.inner = self.inner.status(status);
+        self
+    }
+
+    common_setters!();
+
+    /// Builds the response.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error met while setting the status code or a header.
+    pub fn build(self) -> Result<Response, http::Error> {
+        let mut inner = self.inner;
+        if let Some(value) = content_type(inner.headers_ref(), &self.body) {
+            inner = inner.header(header::CONTENT_TYPE, value);
+        }
+        inner.body(self.body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_parts_are_errors() {
+        assert!(RequestBuilder::new().uri("http://[::1").build().is_err());
+        assert!(RequestBuilder::new().method("GE T").build().is_err());
+        assert!(RequestBuilder::new()
+            .try_header("x-note", "line\nbreak")
+            .build()
+            .is_err());
+        assert!(RequestBuilder::new()
+            .try_header("bad name", "value")
+            .build()
+            .is_err());
+        assert!(ResponseBuilder::new().status(1000u16).build().is_err());
+        assert!(ResponseBuilder::new()
+            .try_header("x-note", &b"\x00"[..])
+            .text("body")
+            .build()
+            .is_err());
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Tenant(&'static str);
+
+    #[test]
+    fn requests_are_assembled() {
+        let request = RequestBuilder::new()
+            .method(Method::PUT)
+            .uri("https://example.com/items/7")
+            .version(Version::HTTP_2)
+            .try_header(header::CONTENT_TYPE, "application/x-ndjson")
+            .extension(Tenant("acme"))
+            .bytes("{}\n")
+            .build()
+            .unwrap();
+        assert_eq!(request.method(), Method::PUT);
+        assert_eq!(request.uri().path(), "/items/7");
+        assert_eq!(request.version(), Version::HTTP_2);
+        // An explicit content type wins over the body's.
+        assert_eq!(
+            request.headers()[header::CONTENT_TYPE],
+            "application/x-ndjson"
+        );
+        assert_eq!(request.extensions().get(), Some(&Tenant("acme")));
+
+        let request = RequestBuilder::new().build().unwrap();
+        assert_eq!(request.method(), Method::GET);
+        assert_eq!(request.uri(), "/");
+        assert!(!request.headers().contains_key(header::CONTENT_TYPE));
+    }
+
+    #[cfg(feature = "json")]
+    #[tokio::test]
+    async fn responses_are_assembled() {
+        let response = ResponseBuilder::new()
+            .status(201)
+            .json(serde_json::json!({ "id": 7 }))
+            .unwrap()
+            .build()
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/json");
+        assert_eq!(
+            response.into_body().into_string().await.unwrap(),
+            r#"{"id":7}"#
+        );
+    }
+
+    #[cfg(feature = "json")]
+    #[tokio::test]
+    async fn ndjson_streams() {
+        let values = futures_lite::stream::iter([1, 2].map(Ok::<_, crate::BodyError>));
+        let response = ResponseBuilder::new().ndjson(values).build().unwrap();
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "application/x-ndjson"
+        );
+        assert_eq!(response.into_body().into_string().await.unwrap(), "1\n2\n");
+    }
+
+    #[cfg(feature = "json")]
+    #[tokio::test]
+    async fn pretty_json() {
+        let request = RequestBuilder::new()
+            .method(Method::POST)
+            .json_pretty(serde_json::json!({ "id": 7 }))
+            .unwrap()
+            .build()
+            .unwrap();
+        assert_eq!(request.headers()[header::CONTENT_TYPE], "application/json");
+        assert_eq!(
+            request.into_body().into_string().await.unwrap(),
+            "{\n  \"id\": 7\n}"
+        );
+    }
+}
@@ -0,0 +1,9 @@
+//! Client-side utilities built on top of a transport [`Endpoint`](crate::Endpoint).
+//!
+//! http-kit does not ship an HTTP client. Instead, the helpers in this module wrap any
+//! endpoint that performs outgoing requests, such as an adapter around a client library
+//! or, in tests, the scripted upstream of the `test-util` feature.
+//!
+//! This module is available with the `std` feature.
+
+pub mod resume;
@@ -0,0 +1,550 @@
+//! Transparent resumption of interrupted downloads.
+//!
+//! [`ResumableDownload`] fetches a resource through a transport [`Endpoint`] and returns
+//! its body as a stream. When the connection breaks mid-body, a follow-up request with
+//! `Range: bytes=<delivered>-` and `If-Range` is issued and its content is stitched onto
+//! the stream, so the consumer sees a single uninterrupted body.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use http_kit::client::resume::{IgnoredRange, ResumableDownload};
+//! use http_kit::{Body, Endpoint, Request, Response};
+//!
+//! struct Transport;
+//!
+//! impl Endpoint for Transport {
+//!     type Error = core::convert::Infallible;
+//!     async fn respond(&mut self, _request: &mut Request) -> Result<Response, Self::Error> {
+//!         Ok(Response::new(Body::from_bytes("file contents")))
+//!     }
+//! }
+//!
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let body = ResumableDownload::new(Transport, "http://example.com/file".parse()?)
+//!     .max_retries(5)
+//!     .on_ignored_range(IgnoredRange::Fail)
+//!     .start()
+//!     .await?;
+//! assert_eq!(body.into_bytes().await?, "file contents");
+//! # Ok(())
+//! # }
+//! ```
+
+extern crate std;
+
+use alloc::boxed::Box;
+use core::{
+    fmt,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use std::{io, sync::Mutex};
+
+use bytes::Bytes;
+use futures_lite::{ready, Stream};
+use http::{header, HeaderMap, HeaderName, HeaderValue, StatusCode, Uri};
+
+use crate::{Body, BodyError, Endpoint, HttpError, Request, Response};
+
+/// What to do when a server answers a range request with the complete resource.
+///
+/// Servers that do not support ranges, or whose resource changed since the first
+/// response (so that `If-Range` no longer matches), answer `200 OK` with the whole body.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum IgnoredRange {
+    /// Re-stream the resource from the start, discarding the bytes the consumer has
+    /// already received.
+    ///
+    /// If the resource changed, the stitched body mixes both versions; choose
+    /// [`IgnoredRange::Fail`] when that is unacceptable.
+    #[default]
+    Discard,
+    /// Fail the body with [`ResumeError::RangeIgnored`].
+    Fail,
+}
+
+/// Error produced while starting or resuming a download.
+///
+/// Errors raised after [`ResumableDownload::start`] returned are reported through the
+/// body stream as [`BodyError::Other`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ResumeError<E> {
+    /// The transport endpoint failed.
+    Endpoint(E),
+    /// The server answered with an unexpected status.
+    Status(StatusCode),
+    /// The server answered a range request with the complete resource and
+    /// [`IgnoredRange::Fail`] is configured.
+    RangeIgnored,
+    /// The `Content-Range` of a partial response does not continue the delivered bytes.
+    UnexpectedRange,
+}
+
+impl<E: fmt::Display> fmt::Display for ResumeError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Endpoint(error) => write!(f, "transport failed: {error}"),
+            Self::Status(status) => write!(f, "unexpected response status {status}"),
+            Self::RangeIgnored => f.write_str("server ignored the range request"),
+            Self::UnexpectedRange => {
+                f.write_str("partial response does not continue the delivered bytes")
+            }
+        }
+    }
+}
+
+impl<E: HttpError> core::error::Error for ResumeError<E> {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            Self::Endpoint(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl<E: HttpError> HttpError for ResumeError<E> {
+    fn status(&self) -> StatusCode {
+        match self {
+            Self::Endpoint(error) => error.status(),
+            _ => StatusCode::BAD_GATEWAY,
+        }
+    }
+}
+
+/// A download that resumes with range requests when its body is interrupted.
+///
+/// A body is considered interrupted when its stream fails with an I/O error caused by a
+/// broken connection (reset, abort, broken pipe, unexpected EOF or timeout), or when it
+/// ends before the length announced by the server. Every resumption counts against
+/// [`max_retries`](Self::max_retries); once they are exhausted the interruption error is
+/// passed on to the consumer.
+///
+/// Resumption requests carry `If-Range` with the strong `ETag` of the first response,
+/// or its `Last-Modified` date when no strong `ETag` is available.
+#[derive(Debug)]
+pub struct ResumableDownload<E> {
+    endpoint: E,
+    uri: Uri,
+    headers: HeaderMap,
+    max_retries: usize,
+    ignored_range: IgnoredRange,
+}
+
+impl<E: Endpoint + 'static> ResumableDownload<E> {
+    /// Creates a download of `uri` through the given transport endpoint.
+    ///
+    /// At most three resumptions are attempted by default.
+    pub fn new(endpoint: E, uri: Uri) -> Self {
+        Self {
+            endpoint,
+            uri,
+            headers: HeaderMap::new(),
+            max_retries: 3,
+            ignored_range: IgnoredRange::default(),
+        }
+    }
+
+    /// Adds a header sent with the initial request and every resumption.
+    #[must_use]
+    pub fn header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.headers.append(name, value);
+        self
+    }
+
+    /// Sets the maximum number of resumption requests.
+    #[must_use]
+    pub fn max_retries(mut self, max_retries: usize) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Sets the behavior when the server ignores a range request.
+    #[must_use]
+    pub fn on_ignored_range(mut self, ignored_range: IgnoredRange) -> Self {
+        self.ignored_range = ignored_range;
+        self
+    }
+
+    /// Sends the initial request and returns the resuming body.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the transport fails or the response status is not successful.
+    pub async fn start(mut self) -> Result<Body, ResumeError<E::Error>> {
+        let mut request = build_request(&self.uri, &self.headers, None);
+        let mut response = self
+            .endpoint
+            .respond(&mut request)
+            .await
+            .map_err(ResumeError::Endpoint)?;
+        if !response.status().is_success() {
+            return Err(ResumeError::Status(response.status()));
+        }
+
+        let mime = response
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok()?.parse().ok());
+        let mut download = Download {
+            uri: self.uri,
+            headers: self.headers,
+            validator: None,
+            total: None,
+            delivered: 0,
+            skip: 0,
+            retries_left: self.max_retries,
+            ignored_range: self.ignored_range,
+            state: State::Done,
+        };
+        download.observe(&response);
+        download.state = State::Streaming {
+            endpoint: self.endpoint,
+            body: take_body(&mut response),
+        };
+
+        let body = Body::from_stream(Resuming(Mutex::new(download)));
+        Ok(match mime {
+            Some(mime) => body.with_mime(mime),
+            None => body,
+        })
+    }
+}
+
+fn build_request(
+    uri: &Uri,
+    headers: &HeaderMap,
+    range: Option<(u64, Option<&HeaderValue>)>,
+) -> Request {
+    let mut request = Request::new(Body::empty());
+    *request.uri_mut() = uri.clone();
+    *request.headers_mut() = headers.clone();
+    if let Some((offset, validator)) = range {
+        let value = alloc::format!("bytes={offset}-");
+        request.headers_mut().insert(
+            header::RANGE,
+            HeaderValue::from_str(&value).expect("range values are valid header values"),
+        );
+        if let Some(validator) = validator {
+            request
+                .headers_mut()
+                .insert(header::IF_RANGE, validator.clone());
+        }
+    }
+    request
+}
+
+fn take_body(response: &mut Response) -> Body {
+    response.body_mut().take().unwrap_or_else(|_| Body::empty())
+}
+
+fn is_interruption(error: &BodyError) -> bool {
+    matches!(
+        error,
+        BodyError::Io(error) if matches!(
+            error.kind(),
+            io::ErrorKind::ConnectionReset
+                | io::ErrorKind::ConnectionAborted
+                | io::ErrorKind::BrokenPipe
+                | io::ErrorKind::UnexpectedEof
+                | io::ErrorKind::Interrupted
+                | io::ErrorKind::TimedOut
+        )
+    )
+}
+
+// Parses `bytes <start>-<end>/<total>` into the start offset and the complete length.
+fn parse_content_range(value: &HeaderValue) -> Option<(u64, Option<u64>)> {
+    let range = value.to_str().ok()?.trim().strip_prefix("bytes ")?;
+    let (span, total) = range.split_once('/')?;
+    let (start, _) = span.split_once('-')?;
+    Some((start.trim().parse().ok()?, total.trim().parse().ok()))
+}
+
+type Pending<E> =
+    Pin<Box<dyn Future<Output = (E, Result<Response, <E as Endpoint>::Error>)> + Send>>;
+
+enum State<E: Endpoint> {
+    Streaming { endpoint: E, body: Body },
+    Resuming(Pending<E>),
+    Done,
+}
+
+struct Download<E: Endpoint> {
+    uri: Uri,
+    headers: HeaderMap,
+    validator: Option<HeaderValue>,
+    // Complete length of the resource, when announced.
+    total: Option<u64>,
+    // Bytes yielded to the consumer.
+    delivered: u64,
+    // Bytes of the current response already yielded before a restart.
+    skip: u64,
+    retries_left: usize,
+    ignored_range: IgnoredRange,
+    state: State<E>,
+}
+
+impl<E: Endpoint + 'static> Download<E> {
+    // Records the validator and length of a complete response.
+    fn observe(&mut self, response: &Response) {
+        let headers = response.headers();
+        let etag = headers
+            .get(header::ETAG)
+            .filter(|etag| !etag.as_bytes().starts_with(b"W/"));
+        self.validator = etag.or(headers.get(header::LAST_MODIFIED)).cloned();
+        self.total = headers
+            .get(header::CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok()?.parse().ok());
+    }
+
+    fn resume(&mut self, mut endpoint: E) {
+        self.retries_left -= 1;
+        let mut request = build_request(
+            &self.uri,
+            &self.headers,
+            Some((self.delivered, self.validator.as_ref())),
+        );
+        self.state = State::Resuming(Box::pin(async move {
+            let result = endpoint.respond(&mut request).await;
+            (endpoint, result)
+        }));
+    }
+
+    fn fail(&mut self, error: ResumeError<E::Error>) -> Poll<Option<Result<Bytes, BodyError>>> {
+        self.state = State::Done;
+        Poll::Ready(Some(Err(BodyError::Other(Box::new(error)))))
+    }
+
+    fn poll_next(&mut self, cx: &mut Context<'_>) -> Poll<Option<Result<Bytes, BodyError>>> {
+        loop {
+            match &mut self.state {
+                State::Streaming { body, .. } => match ready!(Pin::new(body).poll_next(cx)) {
+                    Some(Ok(mut chunk)) => {
+                        let skipped = usize::try_from(self.skip)
+                            .unwrap_or(usize::MAX)
+                            .min(chunk.len());
+                        self.skip -= skipped as u64;
+                        let chunk = chunk.split_off(skipped);
+                        if !chunk.is_empty() {
+                            self.delivered += chunk.len() as u64;
+                            return Poll::Ready(Some(Ok(chunk)));
+                        }
+                    }
+                    Some(Err(error)) if is_interruption(&error) && self.retries_left > 0 => {
+                        let State::Streaming { endpoint, .. } =
+                            core::mem::replace(&mut self.state, State::Done)
+                        else {
+                            unreachable!()
+                        };
+                        self.resume(endpoint);
+                    }
+                    Some(Err(error)) => {
+                        self.state = State::Done;
+                        return Poll::Ready(Some(Err(error)));
+                    }
+                    None if self.total.is_some_and(|total| self.delivered < total) => {
+                        let State::Streaming { endpoint, .. } =
+                            core::mem::replace(&mut self.state, State::Done)
+                        else {
+                            unreachable!()
+                        };
+                        if self.retries_left == 0 {
+                            let error = io::Error::new(
+                                io::ErrorKind::UnexpectedEof,
+                                "body ended before its announced length",
+                            );
+                            return Poll::Ready(Some(Err(error.into())));
+                        }
+                        self.resume(endpoint);
+                    }
+                    None => {
+                        self.state = State::Done;
+                        return Poll::Ready(None);
+                    }
+                },
+                State::Resuming(pending) => {
+                    let (endpoint, result) = ready!(pending.as_mut().poll(cx));
+                    let mut response = match result {
+                        Ok(response) => response,
+                        Err(error) => return self.fail(ResumeError::Endpoint(error)),
+                    };
+                    match response.status() {
+                        StatusCode::PARTIAL_CONTENT => {
+                            let range = response
+                                .headers()
+                                .get(header::CONTENT_RANGE)
+                                .and_then(parse_content_range);
+                            match range {
+                                Some((start, total)) if start == self.delivered => {
+                                    self.total = total.or(self.total);
+                                }
+                                _ => return self.fail(ResumeError::UnexpectedRange),
+                            }
+                        }
+                        StatusCode::OK => match self.ignored_range {
+                            IgnoredRange::Discard => {
+                                self.observe(&response);
+                                self.skip = self.delivered;
+                            }
+                            IgnoredRange::Fail => return self.fail(ResumeError::RangeIgnored),
+                        },
+                        status => return self.fail(ResumeError::Status(status)),
+                    }
+                    self.state = State::Streaming {
+                        endpoint,
+                        body: take_body(&mut response),
+                    };
+                }
+                State::Done => return Poll::Ready(None),
+            }
+        }
+    }
+}
+
+// The pending resumption future is `Send` but not `Sync`, while bodies must be both. The
+// mutex is only ever accessed through `get_mut`, so it never locks.
+struct Resuming<E: Endpoint>(Mutex<Download<E>>);
+
+// Nothing is pinned structurally: the endpoint is only moved, never polled in place.
+impl<E: Endpoint> Unpin for Resuming<E> {}
+
+impl<E: Endpoint + 'static> Stream for Resuming<E> {
+    type Item = Result<Bytes, BodyError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut()
+            .0
+            .get_mut()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .poll_next(cx)
+    }
+}
+
+#[cfg(all(test, feature = "test-util"))]
+mod tests {
+    use super::*;
+    use crate::test::{Reply, UpstreamScript};
+    use alloc::{string::ToString, vec::Vec};
+
+    const URI: &str = "http://upstream.test/file";
+
+    fn content() -> Vec<u8> {
+        (0..100u8).collect()
+    }
+
+    fn full() -> Reply {
+        Reply::ok().body(content()).header(header::ETAG, "\"v1\"")
+    }
+
+    fn rest(from: usize) -> Reply {
+        Reply::status(StatusCode::PARTIAL_CONTENT)
+            .body(content()[from..].to_vec())
+            .header(header::CONTENT_RANGE, alloc::format!("bytes {from}-99/100"))
+            .require_header(header::RANGE, alloc::format!("bytes={from}-"))
+            .require_header(header::IF_RANGE, "\"v1\"")
+    }
+
+    async fn collect(mut body: Body) -> (Vec<u8>, Option<BodyError>) {
+        use futures_lite::StreamExt;
+
+        let mut data = Vec::new();
+        while let Some(chunk) = body.next().await {
+            match chunk {
+                Ok(chunk) => data.extend_from_slice(&chunk),
+                Err(error) => return (data, Some(error)),
+            }
+        }
+        (data, None)
+    }
+
+    #[tokio::test]
+    async fn resumes_with_range_requests() {
+        let upstream = UpstreamScript::new()
+            .route("/file", full().drop_after(30))
+            .route("/file", rest(30).drop_after(40))
+            .route("/file", rest(70));
+
+        let body = ResumableDownload::new(upstream, URI.parse().unwrap())
+            .start()
+            .await
+            .unwrap();
+        let (data, error) = collect(body).await;
+        assert!(error.is_none());
+        assert_eq!(data, content());
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_max_retries() {
+        let upstream = UpstreamScript::new()
+            .route("/file", full().drop_after(10))
+            .route("/file", rest(10).drop_after(10))
+            .route("/file", rest(20).drop_after(10));
+
+        let body = ResumableDownload::new(upstream, URI.parse().unwrap())
+            .max_retries(1)
+            .start()
+            .await
+            .unwrap();
+        let (data, error) = collect(body).await;
+        assert_eq!(data, content()[..20]);
+        assert!(matches!(error, Some(BodyError::Io(_))));
+    }
+
+    #[tokio::test]
+    async fn ignored_range_restarts_without_duplicates() {
+        let upstream = UpstreamScript::new()
+            .route("/file", full().drop_after(45))
+            .route("/file", full());
+
+        let body = ResumableDownload::new(upstream, URI.parse().unwrap())
+            .start()
+            .await
+            .unwrap();
+        let (data, error) = collect(body).await;
+        assert!(error.is_none());
+        assert_eq!(data, content());
+    }
+
+    #[tokio::test]
+    async fn ignored_range_can_fail() {
+        let upstream = UpstreamScript::new()
+            .route("/file", full().drop_after(45))
+            .route("/file", full());
+
+        let body = ResumableDownload::new(upstream, URI.parse().unwrap())
+            .on_ignored_range(IgnoredRange::Fail)
+            .start()
+            .await
+            .unwrap();
+        let (data, error) = collect(body).await;
+        assert_eq!(data, content()[..45]);
+        let error = error.unwrap();
+        assert_eq!(error.to_string(), "server ignored the range request");
+    }
+
+    #[tokio::test]
+    async fn failed_start_reports_status() {
+        let upstream = UpstreamScript::new();
+        let error = ResumableDownload::new(upstream, URI.parse().unwrap())
+            .start()
+            .await
+            .unwrap_err();
+        assert!(matches!(error, ResumeError::Status(StatusCode::NOT_FOUND)));
+    }
+
+    #[test]
+    fn parses_content_range() {
+        let value = HeaderValue::from_static("bytes 30-99/100");
+        assert_eq!(parse_content_range(&value), Some((30, Some(100))));
+        let value = HeaderValue::from_static("bytes 30-99/*");
+        assert_eq!(parse_content_range(&value), Some((30, None)));
+        assert_eq!(
+            parse_content_range(&HeaderValue::from_static("items 1-2/3")),
+            None
+        );
+    }
+}
@@ -0,0 +1,89 @@
+//! Conversions from messages of other HTTP libraries.
+//!
+//! Servers and clients built on `hyper` or `tower` hand out messages whose body is
+//! some other [`http_body::Body`], such as `hyper::body::Incoming`. The functions of
+//! this module wrap that body into a [`Body`], keeping the rest of the message. Going
+//! the other way needs no conversion: [`Body`] implements `http_body::Body`, so a
+//! [`Response`] can be returned to `hyper` as is.
+//!
+//! # Examples
+//!
+//! A `hyper` service calling an [`Endpoint`](crate::Endpoint):
+//!
+//! ```rust,ignore
+//! use http_kit::{convert, Endpoint, Response};
+//!
+//! async fn serve<E: Endpoint>(
+//!     endpoint: &mut E,
+//!     request: http::Request<hyper::body::Incoming>,
+//! ) -> Result<Response, E::Error> {
+//!     let mut request = convert::map_request(request);
+//!     endpoint.respond(&mut request).await
+//! }
+//! ```
+
+use bytes::Bytes;
+
+use crate::{Body, BodyError, Request, Response};
+
+/// Converts a request with any body into a [`Request`], keeping its head as is.
+///
+/// The body is wrapped with [`Body::new`].
+pub fn map_request<B>(request: http::Request<B>) -> Request
+where
+    B: http_body::Body + Send + Sync + 'static,
+    B::Data: Into<Bytes>,
+    B::Error: Into<BodyError>,
+{
+    request.map(Body::new)
+}
+
+/// Converts a response with any body into a [`Response`], keeping its head as is.
+///
+/// The body is wrapped with [`Body::new`].
+pub fn map_response<B>(response: http::Response<B>) -> Response
+where
+    B: http_body::Body + Send + Sync + 'static,
+    B::Data: Into<Bytes>,
+    B::Error: Into<BodyError>,
+{
+    response.map(Body::new)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{RequestExt, ResponseExt};
+    use http::{HeaderValue, StatusCode};
+    use http_body_util::channel::Channel;
+
+    #[tokio::test]
+    async fn channel_bodies_are_wrapped() {
+        let (mut sender, channel) = Channel::<Bytes, BodyError>::new(4);
+        let mut request = http::Request::new(channel);
+        *request.uri_mut() = "/upload?part=1".parse().unwrap();
+        request
+            .headers_mut()
+            .insert("x-trace", HeaderValue::from_static("7"));
+
+        let request = Request::from_http(request);
+        assert_eq!(request.uri(), "/upload?part=1");
+        assert_eq!(request.headers()["x-trace"], "7");
+
+        let sending = async move {
+            sender.send_data(Bytes::from("hello, ")).await.unwrap();
+            sender.send_data(Bytes::from("world")).await.unwrap();
+        };
+        let (_, text) = futures_lite::future::zip(sending, request.into_body().into_string()).await;
+        assert_eq!(text.unwrap(), "hello, world");
+    }
+
+    #[tokio::test]
+    async fn responses_are_wrapped() {
+        let mut response = http::Response::new(http_body_util::Full::new(Bytes::from("ok")));
+        *response.status_mut() = StatusCode::ACCEPTED;
+        let response = Response::from_http(response);
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+        assert_eq!(response.into_body().into_string().await.unwrap(), "ok");
+    }
+}
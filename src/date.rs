@@ -0,0 +1,221 @@
+//! Formatting and parsing of timestamps used in header values.
+
+extern crate std;
+
+use alloc::{format, string::String};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const WEEKDAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+// Seconds since the Unix epoch, negative for earlier times.
+pub(crate) fn unix_timestamp(time: SystemTime) -> i64 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(elapsed) => i64::try_from(elapsed.as_secs()).unwrap_or(i64::MAX),
+        Err(error) => i64::try_from(error.duration().as_secs()).map_or(i64::MIN, |secs| -secs),
+    }
+}
+
+// Formats `time` as an IMF-fixdate (RFC 9110, section 5.6.7), such as
+// `Sun, 06 Nov 1994 08:49:37 GMT`. Times before the epoch are clamped to it.
+pub(crate) fn http_date(time: SystemTime) -> String {
+    let secs = unix_timestamp(time).max(0);
+    let days = secs / 86_400;
+    let seconds = secs % 86_400;
+    let (year, month, day) = civil_from_days(days);
+    format!(
+        "{}, {:02} {} {} {:02}:{:02}:{:02} GMT",
+        WEEKDAYS[(days % 7) as usize],
+        day,
+        MONTHS[month as usize - 1],
+        year,
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+// Parses an HTTP date (RFC 9110, section 5.6.7): an IMF-fixdate, or one of the obsolete
+// RFC 850 and asctime formats that recipients must still accept. Two-digit RFC 850 years
+// below 70 are read as 20xx. Weekday names are not checked against the date.
+pub(crate) fn parse_http_date(value: &str) -> Option<SystemTime> {
+    let value = value.trim();
+    let (day, month, year, time) = if let Some((_, rest)) = value.split_once(", ") {
+        let rest = rest.strip_suffix(" GMT")?;
+        if rest.contains('-') {
+            // Sunday, 06-Nov-94 08:49:37 GMT
+            let (date, time) = rest.split_once(' ')?;
+            let mut parts = date.split('-');
+            let (day, month, year) = (parts.next()?, parts.next()?, parts.next()?);
+            if parts.next().is_some() || year.len() != 2 {
+                return None;
+            }
+            let year: i64 = number(year)?;
+            let year = if year < 70 { 2000 + year } else { 1900 + year };
+            (number(day)?, month, year, time)
+        } else {
+            // Sun, 06 Nov 1994 08:49:37 GMT
+            let mut parts = rest.split(' ');
+            let (day, month, year, time) =
+                (parts.next()?, parts.next()?, parts.next()?, parts.next()?);
+            if parts.next().is_some() || day.len() != 2 || year.len() != 4 {
+                return None;
+            }
+            (number(day)?, month, number(year)?, time)
+        }
+    } else {
+        // Sun Nov  6 08:49:37 1994
+        let mut parts = value.split_ascii_whitespace();
+        let (_, month, day, time, year) = (
+            parts.next()?,
+            parts.next()?,
+            parts.next()?,
+            parts.next()?,
+            parts.next()?,
+        );
+        if parts.next().is_some() || year.len() != 4 {
+            return None;
+        }
+        (number(day)?, month, number(year)?, time)
+    };
+
+    let month = MONTHS.iter().position(|name| *name == month)? as i64 + 1;
+    let mut clock = time.split(':');
+    let (hours, minutes, seconds) = (clock.next()?, clock.next()?, clock.next()?);
+    if clock.next().is_some() || [hours, minutes, seconds].iter().any(|part| part.len() != 2) {
+        return None;
+    }
+    let (hours, minutes, seconds): (i64, i64, i64) =
+        (number(hours)?, number(minutes)?, number(seconds)?);
+    // Leap seconds (60) are accepted and folded into the next minute.
+    if !(1..=days_in_month(year, month)).contains(&day)
+        || hours > 23
+        || minutes > 59
+        || seconds > 60
+    {
+        return None;
+    }
+
+    let secs = days_from_civil(year, month, day) * 86_400 + hours * 3600 + minutes * 60 + seconds;
+    let secs = u64::try_from(secs).ok()?;
+    UNIX_EPOCH.checked_add(core::time::Duration::from_secs(secs))
+}
+
+fn number(digits: &str) -> Option<i64> {
+    if digits.is_empty() || !digits.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+    digits.parse().ok()
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// Converts a proleptic Gregorian (year, month, day) to days since 1970-01-01, the
+// inverse of `civil_from_days`.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let shifted_month = if month > 2 { month - 3 } else { month + 9 };
+    let day_of_year = (153 * shifted_month + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+// Converts days since 1970-01-01 to a proleptic Gregorian (year, month, day).
+// See http://howardhinnant.github.io/date_algorithms.html#civil_from_days.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::time::Duration;
+
+    #[test]
+    fn formats_imf_fixdate() {
+        let at = |secs| UNIX_EPOCH + Duration::from_secs(secs);
+        assert_eq!(http_date(at(0)), "Thu, 01 Jan 1970 00:00:00 GMT");
+        assert_eq!(http_date(at(784_111_777)), "Sun, 06 Nov 1994 08:49:37 GMT");
+        assert_eq!(http_date(at(951_782_400)), "Tue, 29 Feb 2000 00:00:00 GMT");
+        assert_eq!(
+            http_date(at(4_102_444_799)),
+            "Thu, 31 Dec 2099 23:59:59 GMT"
+        );
+    }
+
+    #[test]
+    fn parses_all_three_formats() {
+        let expected = UNIX_EPOCH + Duration::from_secs(784_111_777);
+        for value in [
+            "Sun, 06 Nov 1994 08:49:37 GMT",
+            "Sunday, 06-Nov-94 08:49:37 GMT",
+            "Sun Nov  6 08:49:37 1994",
+        ] {
+            assert_eq!(parse_http_date(value), Some(expected), "{value}");
+        }
+        assert_eq!(
+            parse_http_date("Tue, 29 Feb 2000 00:00:00 GMT"),
+            Some(UNIX_EPOCH + Duration::from_secs(951_782_400))
+        );
+        assert_eq!(
+            parse_http_date("Thursday, 01-Jan-30 00:00:00 GMT"),
+            Some(UNIX_EPOCH + Duration::from_secs(1_893_456_000))
+        );
+
+        for value in [
+            "",
+            "Sun, 06 Nov 1994 08:49:37 UTC",
+            "Sun, 6 Nov 1994 08:49:37 GMT",
+            "Sun, 06 Nox 1994 08:49:37 GMT",
+            "Mon, 29 Feb 1999 00:00:00 GMT",
+            "Sun, 06 Nov 1994 24:00:00 GMT",
+            "Sun, 06 Nov 1994 08:49 GMT",
+            "Sun, 06 Nov +994 08:49:37 GMT",
+            "Wed, 31 Dec 1969 23:59:59 GMT",
+        ] {
+            assert_eq!(parse_http_date(value), None, "{value}");
+        }
+    }
+
+    #[test]
+    fn formatting_round_trips() {
+        for secs in [0, 784_111_777, 951_782_400, 4_102_444_799] {
+            let at = UNIX_EPOCH + Duration::from_secs(secs);
+            assert_eq!(parse_http_date(&http_date(at)), Some(at));
+        }
+    }
+
+    #[test]
+    fn timestamps_before_the_epoch_are_negative() {
+        assert_eq!(unix_timestamp(UNIX_EPOCH - Duration::from_secs(90)), -90);
+        assert_eq!(
+            http_date(UNIX_EPOCH - Duration::from_secs(90)),
+            "Thu, 01 Jan 1970 00:00:00 GMT"
+        );
+    }
+}
@@ -10,6 +10,8 @@
 //! - **Middleware Integration**: Endpoints can be combined with middleware for cross-cutting concerns
 //! - **Type Erasure**: Support for dynamic dispatch through [`AnyEndpoint`]
 //! - **Composition**: Endpoints can be wrapped and combined in various ways
+//! - **Sharing**: [`SharedEndpoint`]s answer through `&self`, so one instance behind an
+//!   `Arc` serves concurrent requests
 //!
 //! # Examples
 //!
@@ -74,6 +76,19 @@
 
 use core::{any::type_name, fmt::Debug, future::Future, ops::DerefMut, pin::Pin};
 
+#[cfg(all(feature = "fs", feature = "std"))]
+pub mod assets;
+#[cfg(all(feature = "json", feature = "std"))]
+pub mod echo;
+mod from_fn;
+pub use from_fn::{endpoint_fn, respond_into, EndpointFn, RespondInto};
+mod shared;
+pub use shared::SharedEndpoint;
+#[cfg(feature = "tower")]
+mod tower;
+#[cfg(feature = "tower")]
+pub use tower::{EndpointService, ServiceEndpoint};
+
 use alloc::boxed::Box;
 
 use crate::{
@@ -404,6 +419,18 @@ impl Endpoint for AnyEndpoint {
     type Error = BoxHttpError;
     /// Processes an HTTP request using the underlying endpoint implementation.
     async fn respond(&mut self, request: &mut Request) -> Result<Response, Self::Error> {
-        self.0.respond_inner(request).await
+        stat!(requests_started);
+        stat!(body_bytes_in, request.body().len().unwrap_or_default());
+        let result = self.0.respond_inner(request).await;
+        stat!(requests_completed);
+        #[cfg(all(feature = "stats", target_has_atomic = "64"))]
+        match &result {
+            Ok(response) => {
+                stat!(body_bytes_out, response.body().len().unwrap_or_default());
+                crate::stats::record_status(response.status());
+            }
+            Err(error) => crate::stats::record_status(error.status()),
+        }
+        result
     }
 }
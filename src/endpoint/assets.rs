@@ -0,0 +1,431 @@
+//! Content-addressed static assets.
+//!
+//! [`HashedAssets`] serves the files of a directory under names that embed a hash of
+//! their content, such as `/assets/app.3f9c2a1b.js`. Because a hashed URL changes
+//! whenever the file does, responses to it can be cached forever; templates obtain the
+//! current URL of a file with [`HashedAssets::asset_url`].
+//!
+//! This module is available with the `fs` feature.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use http_kit::endpoint::assets::HashedAssets;
+//!
+//! # fn example() -> std::io::Result<()> {
+//! let assets = HashedAssets::new("./public");
+//!
+//! // Hand a clone to the templates, serve requests under `/assets/` with the other.
+//! let url = assets.asset_url("app.js")?;
+//! assert!(url.starts_with("/assets/app."));
+//! # Ok(())
+//! # }
+//! ```
+
+extern crate std;
+
+use alloc::{format, string::String, sync::Arc};
+use core::fmt;
+use std::{
+    collections::HashMap,
+    io,
+    path::{Component, Path, PathBuf},
+    sync::Mutex,
+    time::SystemTime,
+};
+
+use http::{header, HeaderValue, Method, StatusCode};
+
+use crate::{Body, Endpoint, HttpError, Request, Response};
+
+const HASH_LEN: usize = 8;
+const IMMUTABLE: &str = "public, max-age=31536000, immutable";
+const NO_CACHE: &str = "no-cache";
+
+/// Error returned by [`HashedAssets`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum AssetError {
+    /// No file matches the path, or its hash is stale.
+    NotFound,
+    /// The method is neither `GET` nor `HEAD`.
+    MethodNotAllowed,
+    /// Reading the file failed.
+    Io(io::Error),
+}
+
+impl fmt::Display for AssetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound => f.write_str("asset not found"),
+            Self::MethodNotAllowed => f.write_str("method not allowed for assets"),
+            Self::Io(error) => write!(f, "failed to read asset: {error}"),
+        }
+    }
+}
+
+impl core::error::Error for AssetError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            Self::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl HttpError for AssetError {
+    fn status(&self) -> StatusCode {
+        match self {
+            Self::NotFound => StatusCode::NOT_FOUND,
+            Self::MethodNotAllowed => StatusCode::METHOD_NOT_ALLOWED,
+            Self::Io(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl From<io::Error> for AssetError {
+    fn from(error: io::Error) -> Self {
+        if error.kind() == io::ErrorKind::NotFound {
+            Self::NotFound
+        } else {
+            Self::Io(error)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Version {
+    modified: Option<SystemTime>,
+    len: u64,
+}
+
+impl Version {
+    fn of(metadata: &std::fs::Metadata) -> Self {
+        Self {
+            modified: metadata.modified().ok(),
+            len: metadata.len(),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Inner {
+    root: PathBuf,
+    prefix: String,
+    hashes: Mutex<HashMap<PathBuf, (Version, String)>>,
+}
+
+/// Endpoint serving a directory under content-hashed file names.
+///
+/// For a file `app.js`, the hashed name is `app.<hash>.js`, where the hash is eight
+/// lowercase hex digits derived from the content. Requests for:
+///
+/// - a hashed name with the current hash are served with
+///   `Cache-Control: public, max-age=31536000, immutable`;
+/// - a hashed name with an outdated hash get `404 Not Found`, so stale pages never
+///   receive content they were not built against;
+/// - the plain name are served with `Cache-Control: no-cache`.
+///
+/// Hashes are computed on first use and cached per file. A cached hash is recomputed
+/// when the modification time or size of its file changes.
+///
+/// `HashedAssets` is a cheap handle: clones share the cache, so one clone can serve
+/// requests while others generate URLs, directly or through request extensions.
+#[derive(Debug, Clone)]
+pub struct HashedAssets {
+    inner: Arc<Inner>,
+}
+
+impl HashedAssets {
+    /// Creates an endpoint serving `root` under the `/assets` URL prefix.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self::with_prefix(root, "/assets")
+    }
+
+    /// Creates an endpoint serving `root` under the given URL prefix.
+    ///
+    /// The prefix is stripped from request paths before they are mapped to files.
+    pub fn with_prefix(root: impl Into<PathBuf>, prefix: impl Into<String>) -> Self {
+        let mut prefix = prefix.into();
+        while prefix.ends_with('/') {
+            prefix.pop();
+        }
+        Self {
+            inner: Arc::new(Inner {
+                root: root.into(),
+                prefix,
+                hashes: Mutex::new(HashMap::new()),
+            }),
+        }
+    }
+
+    /// Returns the hashed URL of the file at `name`, relative to the root.
+    ///
+    /// This reads the file when its hash is not cached yet or the file changed, so it
+    /// blocks; call it while rendering templates rather than on hot async paths.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `name` escapes the root or the file cannot be read.
+    pub fn asset_url(&self, name: &str) -> io::Result<String> {
+        let relative = relative_path(name)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "invalid asset name"))?;
+        let path = self.inner.root.join(&relative);
+        let version = Version::of(&std::fs::metadata(&path)?);
+        let hash = match self.cached(&relative, &version) {
+            Some(hash) => hash,
+            None => self.store(relative, version, &std::fs::read(&path)?),
+        };
+        Ok(format!(
+            "{}/{}",
+            self.inner.prefix,
+            hashed_name(name.trim_start_matches('/'), &hash)
+        ))
+    }
+
+    fn cached(&self, relative: &Path, version: &Version) -> Option<String> {
+        let hashes = self.inner.hashes.lock().expect("asset cache poisoned");
+        hashes
+            .get(relative)
+            .filter(|(cached, _)| cached == version)
+            .map(|(_, hash)| hash.clone())
+    }
+
+    fn store(&self, relative: PathBuf, version: Version, content: &[u8]) -> String {
+        let hash = content_hash(content);
+        self.inner
+            .hashes
+            .lock()
+            .expect("asset cache poisoned")
+            .insert(relative, (version, hash.clone()));
+        hash
+    }
+
+    async fn current_hash(&self, relative: &Path, path: &Path) -> Result<String, AssetError> {
+        let version = Version::of(&async_fs::metadata(path).await?);
+        if let Some(hash) = self.cached(relative, &version) {
+            return Ok(hash);
+        }
+        let content = async_fs::read(path).await?;
+        Ok(self.store(relative.to_path_buf(), version, &content))
+    }
+}
+
+impl Endpoint for HashedAssets {
+    type Error = AssetError;
+
+    async fn respond(&mut self, request: &mut Request) -> Result<Response, Self::Error> {
+        if request.method() != Method::GET && request.method() != Method::HEAD {
+            return Err(AssetError::MethodNotAllowed);
+        }
+        let name = request
+            .uri()
+            .path()
+            .strip_prefix(self.inner.prefix.as_str())
+            .filter(|name| name.starts_with('/'))
+            .ok_or(AssetError::NotFound)?;
+
+        let (relative, requested_hash) = match split_hashed(name) {
+            Some((plain, hash)) => {
+                let relative = relative_path(&plain).ok_or(AssetError::NotFound)?;
+                let metadata = async_fs::metadata(self.inner.root.join(&relative)).await;
+                if metadata.is_ok_and(|metadata| metadata.is_file()) {
+                    (relative, Some(hash))
+                } else {
+                    // A file whose own name merely looks hashed.
+                    (relative_path(name).ok_or(AssetError::NotFound)?, None)
+                }
+            }
+            None => (relative_path(name).ok_or(AssetError::NotFound)?, None),
+        };
+        let path = self.inner.root.join(&relative);
+
+        let cache_control = match requested_hash {
+            Some(hash) => {
+                if self.current_hash(&relative, &path).await? != hash {
+                    return Err(AssetError::NotFound);
+                }
+                IMMUTABLE
+            }
+            None => NO_CACHE,
+        };
+
+        let mut response = Response::new(Body::from_file(&path).await?);
+        response.headers_mut().insert(
+            header::CACHE_CONTROL,
+            HeaderValue::from_static(cache_control),
+        );
+        Ok(response)
+    }
+}
+
+// Maps a URL path to a path below the root, rejecting anything but plain components.
+fn relative_path(name: &str) -> Option<PathBuf> {
+    let mut relative = PathBuf::new();
+    for component in Path::new(name.trim_start_matches('/')).components() {
+        match component {
+            Component::Normal(part) => relative.push(part),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    (!relative.as_os_str().is_empty()).then_some(relative)
+}
+
+// FNV-1a, folded to 32 bits. Content hashes only bust caches, so collision resistance
+// against adversaries is not required.
+fn content_hash(content: &[u8]) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for &byte in content {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    format!(
+        "{:0width$x}",
+        (hash >> 32) as u32 ^ hash as u32,
+        width = HASH_LEN
+    )
+}
+
+fn is_hash(part: &str) -> bool {
+    part.len() == HASH_LEN
+        && part
+            .bytes()
+            .all(|byte| byte.is_ascii_digit() || (b'a'..=b'f').contains(&byte))
+}
+
+// `dir/app.js` becomes `dir/app.<hash>.js`; names without an extension get a suffix.
+fn hashed_name(name: &str, hash: &str) -> String {
+    let (dir, file) = name
+        .rsplit_once('/')
+        .map_or(("", name), |(dir, file)| (dir, file));
+    let file = match file.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => format!("{stem}.{hash}.{extension}"),
+        _ => format!("{file}.{hash}"),
+    };
+    if dir.is_empty() {
+        file
+    } else {
+        format!("{dir}/{file}")
+    }
+}
+
+// Inverse of `hashed_name`: returns the plain name and the hash, if the name has one.
+fn split_hashed(name: &str) -> Option<(String, String)> {
+    let (dir, file) = name
+        .rsplit_once('/')
+        .map_or(("", name), |(dir, file)| (dir, file));
+    let (rest, last) = file.rsplit_once('.')?;
+    let (plain, hash) = match rest.rsplit_once('.') {
+        Some((stem, hash)) if is_hash(hash) && !stem.is_empty() => (format!("{stem}.{last}"), hash),
+        _ if is_hash(last) && !rest.is_empty() => (String::from(rest), last),
+        _ => return None,
+    };
+    Some((format!("{dir}/{plain}"), String::from(hash)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{fs, time::Duration};
+
+    fn temp_root(name: &str) -> PathBuf {
+        let root =
+            std::env::temp_dir().join(format!("http-kit-assets-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("js")).unwrap();
+        fs::write(root.join("js/app.js"), "console.log(1);").unwrap();
+        fs::write(root.join("LICENSE"), "MIT").unwrap();
+        root
+    }
+
+    async fn get(assets: &mut HashedAssets, path: &str) -> Result<Response, AssetError> {
+        let mut request = Request::new(Body::empty());
+        *request.uri_mut() = path.parse().unwrap();
+        assets.respond(&mut request).await
+    }
+
+    #[test]
+    fn hashed_names_round_trip() {
+        assert_eq!(hashed_name("js/app.js", "0123abcd"), "js/app.0123abcd.js");
+        assert_eq!(hashed_name("LICENSE", "0123abcd"), "LICENSE.0123abcd");
+        assert_eq!(hashed_name(".env", "0123abcd"), ".env.0123abcd");
+        assert_eq!(
+            split_hashed("/js/app.min.0123abcd.js"),
+            Some((String::from("/js/app.min.js"), String::from("0123abcd")))
+        );
+        assert_eq!(
+            split_hashed("/LICENSE.0123abcd"),
+            Some((String::from("/LICENSE"), String::from("0123abcd")))
+        );
+        assert_eq!(split_hashed("/js/app.js"), None);
+        assert!(relative_path("/../secret").is_none());
+    }
+
+    #[tokio::test]
+    async fn serves_hashed_and_plain_names() {
+        let root = temp_root("serve");
+        let mut assets = HashedAssets::new(&root);
+
+        let url = assets.asset_url("js/app.js").unwrap();
+        let hash = content_hash(b"console.log(1);");
+        assert_eq!(url, format!("/assets/js/app.{hash}.js"));
+
+        let response = get(&mut assets, &url).await.unwrap();
+        assert_eq!(response.headers()[header::CACHE_CONTROL], IMMUTABLE);
+        assert_eq!(
+            response.into_body().into_bytes().await.unwrap(),
+            "console.log(1);"
+        );
+
+        let response = get(&mut assets, "/assets/js/app.js").await.unwrap();
+        assert_eq!(response.headers()[header::CACHE_CONTROL], NO_CACHE);
+
+        let url = assets.asset_url("LICENSE").unwrap();
+        let response = get(&mut assets, &url).await.unwrap();
+        assert_eq!(response.headers()[header::CACHE_CONTROL], IMMUTABLE);
+
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[tokio::test]
+    async fn stale_or_unknown_assets_are_not_found() {
+        let root = temp_root("stale");
+        let mut assets = HashedAssets::new(&root);
+
+        let error = get(&mut assets, "/assets/js/app.00000000.js")
+            .await
+            .unwrap_err();
+        assert_eq!(error.status(), StatusCode::NOT_FOUND);
+        let error = get(&mut assets, "/assets/missing.js").await.unwrap_err();
+        assert_eq!(error.status(), StatusCode::NOT_FOUND);
+        let error = get(&mut assets, "/assets/../Cargo.toml").await.unwrap_err();
+        assert_eq!(error.status(), StatusCode::NOT_FOUND);
+
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[tokio::test]
+    async fn modification_invalidates_hash() {
+        let root = temp_root("modified");
+        let mut assets = HashedAssets::new(&root);
+        let old_url = assets.asset_url("js/app.js").unwrap();
+
+        let path = root.join("js/app.js");
+        fs::write(&path, "console.log(2);").unwrap();
+        let file = fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(60))
+            .unwrap();
+
+        let new_url = assets.asset_url("js/app.js").unwrap();
+        assert_ne!(old_url, new_url);
+        assert!(get(&mut assets, &old_url).await.is_err());
+        let response = get(&mut assets, &new_url).await.unwrap();
+        assert_eq!(
+            response.into_body().into_bytes().await.unwrap(),
+            "console.log(2);"
+        );
+
+        let _ = fs::remove_dir_all(root);
+    }
+}
@@ -0,0 +1,404 @@
+//! Request introspection for development.
+//!
+//! [`EchoEndpoint`] answers every request with a description of what it received: the
+//! request line, the headers and the body. Mount it in a development or staging
+//! deployment to check what proxies and clients actually send.
+//!
+//! **Do not expose it in production.** Even with sensitive headers redacted, it reflects
+//! request data back to the caller and lets clients delay responses.
+//!
+//! This module is available with the `json` and `std` features.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use http_kit::endpoint::echo::EchoEndpoint;
+//! use http_kit::{Body, Endpoint, Request};
+//!
+//! # async fn example() {
+//! let mut echo = EchoEndpoint::new();
+//! let mut request = Request::new(Body::from_text("ping"));
+//! *request.uri_mut() = "/debug?status=503".parse().unwrap();
+//!
+//! let response = echo.respond(&mut request).await.unwrap();
+//! assert_eq!(response.status(), 503);
+//! let report: serde_json::Value = response.into_body().into_json().await.unwrap();
+//! assert_eq!(report["body"]["data"], "ping");
+//! # }
+//! ```
+
+use alloc::{borrow::Cow, format, string::String, vec::Vec};
+use core::{convert::Infallible, fmt::Write, time::Duration};
+
+use futures_timer::Delay;
+use http::{header, HeaderValue, StatusCode};
+use serde_json::{json, Value};
+
+use crate::{
+    base64, redact, response::text_response, Body, Endpoint, Request, RequestExt, Response,
+};
+
+const DEFAULT_MAX_BODY: usize = 64 << 10;
+const DEFAULT_MAX_DELAY: Duration = Duration::from_secs(10);
+
+/// Endpoint describing the request it received, for debugging deployments.
+///
+/// The description is a JSON document, or plain text when the `Accept` header does not
+/// allow `application/json`:
+///
+/// ```json
+/// {
+///   "method": "POST",
+///   "uri": "/debug?x=1",
+///   "version": "HTTP/1.1",
+///   "headers": [["content-type", "text/plain"], ["authorization", "[REDACTED]"]],
+///   "body": { "encoding": "utf-8", "data": "hello", "truncated": false }
+/// }
+/// ```
+///
+/// Headers keep their order and repetitions. Values of
+/// [sensitive headers](redact::is_sensitive_header) are replaced with
+/// [`REDACTED`](redact::REDACTED) unless the endpoint was created with
+/// [`unredacted`](Self::unredacted). The body is echoed as text when it is valid UTF-8
+/// and fits in [`max_body`](Self::max_body) bytes; otherwise its first `max_body` bytes
+/// are base64-encoded and `truncated` tells whether the rest was dropped.
+///
+/// Two query parameters shape the response, to test client behavior:
+///
+/// - `status=503` sets the response status, which must be between 200 and 599,
+/// - `delay_ms=200` delays the response, up to [`max_delay`](Self::max_delay).
+///
+/// Malformed parameters are answered with `400 Bad Request`.
+#[derive(Debug, Clone)]
+pub struct EchoEndpoint {
+    redact_headers: bool,
+    max_body: usize,
+    max_delay: Duration,
+}
+
+impl Default for EchoEndpoint {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EchoEndpoint {
+    /// Creates an echo endpoint redacting sensitive headers.
+    pub fn new() -> Self {
+        Self {
+            redact_headers: true,
+            max_body: DEFAULT_MAX_BODY,
+            max_delay: DEFAULT_MAX_DELAY,
+        }
+    }
+
+    /// Creates an echo endpoint reporting every header value as received, credentials
+    /// included.
+    pub fn unredacted() -> Self {
+        Self {
+            redact_headers: false,
+            ..Self::new()
+        }
+    }
+
+    /// Sets how many body bytes are echoed, 64 KiB by default.
+    #[must_use]
+    pub fn max_body(mut self, max_body: usize) -> Self {
+        self.max_body = max_body;
+        self
+    }
+
+    /// Sets the longest delay a `delay_ms` parameter may request, 10 seconds by default.
+    /// Longer delays are shortened to it.
+    #[must_use]
+    pub fn max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    fn overrides(&self, request: &Request) -> Result<(StatusCode, Option<Duration>), String> {
+        let mut status = StatusCode::OK;
+        let mut delay = None;
+        for (name, value) in request.query_pairs() {
+            match name.as_ref() {
+                "status" => {
+                    status = value
+                        .parse::<u16>()
+                        .ok()
+                        .filter(|code| (200..=599).contains(code))
+                        .and_then(|code| StatusCode::from_u16(code).ok())
+                        .ok_or_else(|| format!("Invalid status `{value}`"))?;
+                }
+                "delay_ms" => {
+                    let millis = value
+                        .parse::<u64>()
+                        .map_err(|_| format!("Invalid delay `{value}`"))?;
+                    delay = Some(Duration::from_millis(millis).min(self.max_delay));
+                }
+                _ => {}
+            }
+        }
+        Ok((status, delay))
+    }
+
+    fn headers(&self, request: &Request) -> Vec<(String, String)> {
+        request
+            .headers()
+            .iter()
+            .map(|(name, value)| {
+                let value = if self.redact_headers && redact::is_sensitive_header(name) {
+                    Cow::Borrowed(redact::REDACTED)
+                } else {
+                    String::from_utf8_lossy(value.as_bytes())
+                };
+                (String::from(name.as_str()), value.into_owned())
+            })
+            .collect()
+    }
+}
+
+// The echoed body: text, or base64 for binary or truncated data.
+struct Echoed {
+    encoding: &'static str,
+    data: String,
+    truncated: bool,
+}
+
+impl Echoed {
+    async fn read(body: &mut Body, max_body: usize) -> Result<Self, crate::BodyError> {
+        let prefix = body.peek(max_body.saturating_add(1)).await?;
+        let truncated = prefix.len() > max_body;
+        let data = &prefix[..prefix.len().min(max_body)];
+        Ok(match core::str::from_utf8(data) {
+            Ok(text) if !truncated => Self {
+                encoding: "utf-8",
+                data: String::from(text),
+                truncated,
+            },
+            _ => Self {
+                encoding: "base64",
+                data: base64::encode(data),
+                truncated,
+            },
+        })
+    }
+}
+
+fn render_text(request: &Request, headers: &[(String, String)], body: &Echoed) -> String {
+    let mut text = format!(
+        "{} {} {:?}\n",
+        request.method(),
+        request.uri(),
+        request.version()
+    );
+    for (name, value) in headers {
+        let _ = writeln!(text, "{name}: {value}");
+    }
+    text.push('\n');
+    if body.encoding == "base64" {
+        let marker = if body.truncated { ", truncated" } else { "" };
+        let _ = writeln!(text, "[base64{marker}]");
+    }
+    text.push_str(&body.data);
+    text
+}
+
+impl Endpoint for EchoEndpoint {
+    type Error = Infallible;
+    async fn respond(&mut self, request: &mut Request) -> Result<Response, Self::Error> {
+        let (status, delay) = match self.overrides(request) {
+            Ok(overrides) => overrides,
+            Err(message) => return Ok(text_response(StatusCode::BAD_REQUEST, message)),
+        };
+        let body = match Echoed::read(request.body_mut(), self.max_body).await {
+            Ok(body) => body,
+            Err(_) => {
+                return Ok(text_response(
+                    StatusCode::BAD_REQUEST,
+                    String::from("Failed to read request body"),
+                ))
+            }
+        };
+        let headers = self.headers(request);
+        if let Some(delay) = delay {
+            Delay::new(delay).await;
+        }
+
+        let mut response = if request.accepts(&mime::APPLICATION_JSON) {
+            let report = json!({
+                "method": request.method().as_str(),
+                "uri": format!("{}", request.uri()),
+                "version": format!("{:?}", request.version()),
+                "headers": headers
+                    .iter()
+                    .map(|(name, value)| json!([name, value]))
+                    .collect::<Value>(),
+                "body": {
+                    "encoding": body.encoding,
+                    "data": body.data,
+                    "truncated": body.truncated,
+                },
+            });
+            let mut response = Response::new(Body::from(report));
+            response.headers_mut().insert(
+                header::CONTENT_TYPE,
+                HeaderValue::from_static("application/json"),
+            );
+            response
+        } else {
+            text_response(StatusCode::OK, render_text(request, &headers, &body))
+        };
+        *response.status_mut() = status;
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use alloc::vec;
+    use futures_lite::stream;
+
+    async fn echo(endpoint: &mut EchoEndpoint, mut request: Request) -> (StatusCode, Value) {
+        let response = endpoint.respond(&mut request).await.unwrap();
+        let status = response.status();
+        let report = response.into_body().into_json().await.unwrap();
+        (status, report)
+    }
+
+    fn request(uri: &str, body: Body) -> Request {
+        let mut request = Request::new(body);
+        *request.uri_mut() = uri.parse().unwrap();
+        request
+    }
+
+    #[tokio::test]
+    async fn reports_the_request() {
+        let mut request = request("/debug?x=1", Body::from_text("hello"));
+        *request.method_mut() = http::Method::POST;
+        let headers = request.headers_mut();
+        headers.insert(header::CONTENT_TYPE, "text/plain".parse().unwrap());
+        headers.append("x-tag", "a".parse().unwrap());
+        headers.append("x-tag", "b".parse().unwrap());
+
+        let (status, report) = echo(&mut EchoEndpoint::new(), request).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            report,
+            json!({
+                "method": "POST",
+                "uri": "/debug?x=1",
+                "version": "HTTP/1.1",
+                "headers": [["content-type", "text/plain"], ["x-tag", "a"], ["x-tag", "b"]],
+                "body": { "encoding": "utf-8", "data": "hello", "truncated": false },
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn sensitive_headers_are_redacted_by_default() {
+        let build = || {
+            let mut request = request("/", Body::empty());
+            let headers = request.headers_mut();
+            headers.insert(header::AUTHORIZATION, "Bearer t0k3n".parse().unwrap());
+            headers.insert(header::COOKIE, "session=abc".parse().unwrap());
+            headers.insert(header::ACCEPT, "*/*".parse().unwrap());
+            request
+        };
+
+        let (_, report) = echo(&mut EchoEndpoint::new(), build()).await;
+        assert_eq!(
+            report["headers"],
+            json!([
+                ["authorization", "[REDACTED]"],
+                ["cookie", "[REDACTED]"],
+                ["accept", "*/*"]
+            ])
+        );
+
+        let (_, report) = echo(&mut EchoEndpoint::unredacted(), build()).await;
+        assert_eq!(
+            report["headers"][0],
+            json!(["authorization", "Bearer t0k3n"])
+        );
+        assert_eq!(report["headers"][1], json!(["cookie", "session=abc"]));
+    }
+
+    #[tokio::test]
+    async fn status_and_delay_overrides() {
+        let mut endpoint = EchoEndpoint::new().max_delay(Duration::from_millis(50));
+
+        let started = std::time::Instant::now();
+        let (status, _) = echo(
+            &mut endpoint,
+            request("/?status=503&delay_ms=30", Body::empty()),
+        )
+        .await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert!(started.elapsed() >= Duration::from_millis(30));
+
+        // Delays beyond the bound are shortened to it.
+        let started = std::time::Instant::now();
+        echo(&mut endpoint, request("/?delay_ms=3600000", Body::empty())).await;
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        for uri in [
+            "/?status=99",
+            "/?status=600",
+            "/?status=abc",
+            "/?delay_ms=-1",
+        ] {
+            let response = endpoint
+                .respond(&mut request(uri, Body::empty()))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{uri}");
+        }
+    }
+
+    #[tokio::test]
+    async fn binary_and_oversized_bodies_are_base64() {
+        let mut endpoint = EchoEndpoint::new().max_body(4);
+
+        let (_, report) = echo(
+            &mut endpoint,
+            request("/", Body::from_bytes(&b"\xff\x00"[..])),
+        )
+        .await;
+        assert_eq!(
+            report["body"],
+            json!({ "encoding": "base64", "data": "/wA=", "truncated": false })
+        );
+
+        let chunks = stream::iter(vec![Ok::<_, Infallible>("abc"), Ok("defg")]);
+        let (_, report) = echo(&mut endpoint, request("/", Body::from_stream(chunks))).await;
+        assert_eq!(
+            report["body"],
+            json!({ "encoding": "base64", "data": "YWJjZA==", "truncated": true })
+        );
+    }
+
+    #[tokio::test]
+    async fn plain_text_for_clients_rejecting_json() {
+        let mut request = request("/path", Body::from_bytes(&b"\x01\x02\x03"[..]));
+        request
+            .headers_mut()
+            .insert(header::ACCEPT, "text/plain".parse().unwrap());
+
+        let response = EchoEndpoint::new()
+            .max_body(2)
+            .respond(&mut request)
+            .await
+            .unwrap();
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "text/plain; charset=utf-8"
+        );
+        assert_eq!(
+            response.into_body().into_string().await.unwrap(),
+            "GET /path HTTP/1.1\naccept: text/plain\n\n[base64, truncated]\nAQI="
+        );
+    }
+}
@@ -0,0 +1,148 @@
+//! Endpoints built from closures.
+
+use core::{any::type_name, convert::Infallible, fmt, future::Future};
+
+use crate::{Endpoint, HttpError, IntoResponse, Request, Response};
+
+/// An [`Endpoint`] calling a closure, created by [`endpoint_fn`].
+#[derive(Clone)]
+pub struct EndpointFn<F> {
+    f: F,
+}
+
+impl<F> fmt::Debug for EndpointFn<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_fmt(format_args!("EndpointFn[{}]", type_name::<F>()))
+    }
+}
+
+/// Creates an endpoint from a closure.
+///
+/// The closure receives each request and returns the future producing the response.
+/// The future cannot borrow the request, so anything it needs, such as the body, is
+/// taken before it is created. State captured by the closure can be updated on every
+/// call.
+///
+/// # Examples
+///
+/// ```rust
+/// use http_kit::{endpoint::{endpoint_fn, AnyEndpoint}, Body, Response};
+/// use core::convert::Infallible;
+///
+/// let mut hits = 0;
+/// let endpoint = endpoint_fn(move |_request| {
+///     hits += 1;
+///     let body = Body::from_text(format!("hit #{hits}"));
+///     async move { Ok::<_, Infallible>(Response::new(body)) }
+/// });
+/// let endpoint = AnyEndpoint::new(endpoint);
+/// ```
+pub fn endpoint_fn<F, Fut, E>(f: F) -> EndpointFn<F>
+where
+    F: FnMut(&mut Request) -> Fut + Send,
+    Fut: Future<Output = Result<Response, E>> + Send,
+    E: HttpError,
+{
+    EndpointFn { f }
+}
+
+impl<F, Fut, E> Endpoint for EndpointFn<F>
+where
+    F: FnMut(&mut Request) -> Fut + Send,
+    Fut: Future<Output = Result<Response, E>> + Send,
+    E: HttpError,
+{
+    type Error = E;
+    async fn respond(&mut self, request: &mut Request) -> Result<Response, Self::Error> {
+        (self.f)(request).await
+    }
+}
+
+/// An [`Endpoint`] calling a closure whose output converts into a response, created by
+/// [`respond_into`].
+#[derive(Clone)]
+pub struct RespondInto<F> {
+    f: F,
+}
+
+impl<F> fmt::Debug for RespondInto<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_fmt(format_args!("RespondInto[{}]", type_name::<F>()))
+    }
+}
+
+/// Creates an endpoint from a closure returning anything implementing [`IntoResponse`].
+///
+/// The closure is called like that of [`endpoint_fn`]. Its output is converted into the
+/// response, so the endpoint never fails: errors returned in a `Result` are answered with
+/// their status and message.
+///
+/// # Examples
+///
+/// ```rust
+/// use http_kit::{endpoint::respond_into, StatusCode};
+///
+/// let endpoint = respond_into(|request| {
+///     let found = request.uri().path() == "/";
+///     async move {
+///         if found {
+///             (StatusCode::OK, "home")
+///         } else {
+///             (StatusCode::NOT_FOUND, "nothing here")
+///         }
+///     }
+/// });
+/// ```
+pub fn respond_into<F, Fut>(f: F) -> RespondInto<F>
+where
+    F: FnMut(&mut Request) -> Fut + Send,
+    Fut: Future + Send,
+    Fut::Output: IntoResponse,
+{
+    RespondInto { f }
+}
+
+impl<F, Fut> Endpoint for RespondInto<F>
+where
+    F: FnMut(&mut Request) -> Fut + Send,
+    Fut: Future + Send,
+    Fut::Output: IntoResponse,
+{
+    type Error = Infallible;
+    async fn respond(&mut self, request: &mut Request) -> Result<Response, Self::Error> {
+        Ok((self.f)(request).await.into_response())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Body, StatusCode};
+    use alloc::string::{String, ToString};
+
+    http_error!(Teapot, StatusCode::IM_A_TEAPOT, "short and stout");
+
+    #[tokio::test]
+    async fn respond_into_converts_outputs() {
+        let mut endpoint = respond_into(|request: &mut Request| {
+            let path = String::from(request.uri().path());
+            async move {
+                match path.as_str() {
+                    "/tea" => Err(Teapot::new()),
+                    _ => Ok((StatusCode::CREATED, path)),
+                }
+            }
+        });
+
+        let mut request = Request::new(Body::empty());
+        *request.uri_mut() = "/made".parse().unwrap();
+        let response = endpoint.respond(&mut request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let body = response.into_body().into_string().await.unwrap();
+        assert_eq!(body.to_string(), "/made");
+
+        *request.uri_mut() = "/tea".parse().unwrap();
+        let response = endpoint.respond(&mut request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::IM_A_TEAPOT);
+    }
+}
@@ -0,0 +1,119 @@
+//! Endpoints shared by concurrent requests.
+
+use alloc::sync::Arc;
+use core::future::Future;
+
+use crate::{Endpoint, HttpError, Request, Response};
+
+/// An endpoint answering through a shared reference, so that one instance can serve
+/// concurrent requests.
+///
+/// [`Endpoint::respond`] takes `&mut self`, which lets an endpoint update its state
+/// without locks but serializes the requests it serves. A `SharedEndpoint` keeps its
+/// state behind `&self`, using atomics or locks where it changes, and `Arc<E>` is then
+/// an [`Endpoint`]: each connection or task gets a clone of the `Arc`, and all of them
+/// share the same instance. Erasing a clone with
+/// [`AnyEndpoint::new`](super::AnyEndpoint::new) keeps sharing it.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use std::sync::Arc;
+///
+/// use http_kit::endpoint::{AnyEndpoint, SharedEndpoint};
+/// use http_kit::{Body, Endpoint, Request, Response};
+/// use core::convert::Infallible;
+///
+/// struct Counter {
+///     hits: AtomicUsize,
+/// }
+///
+/// impl SharedEndpoint for Counter {
+///     type Error = Infallible;
+///     async fn respond(&self, _request: &mut Request) -> Result<Response, Self::Error> {
+///         let hits = self.hits.fetch_add(1, Ordering::Relaxed) + 1;
+///         Ok(Response::new(Body::from_text(format!("hit #{hits}"))))
+///     }
+/// }
+///
+/// # async fn example() {
+/// let counter = Arc::new(Counter { hits: AtomicUsize::new(0) });
+/// let mut first = AnyEndpoint::new(counter.clone());
+/// let mut second = counter.clone();
+/// first.respond(&mut Request::new(Body::empty())).await.unwrap();
+/// second.respond(&mut Request::new(Body::empty())).await.unwrap();
+/// assert_eq!(counter.hits.load(Ordering::Relaxed), 2);
+/// # }
+/// ```
+pub trait SharedEndpoint: Send + Sync {
+    /// The error type returned by this endpoint.
+    type Error: HttpError;
+
+    /// Processes an HTTP request and generates a response, like
+    /// [`Endpoint::respond`].
+    fn respond(
+        &self,
+        request: &mut Request,
+    ) -> impl Future<Output = Result<Response, Self::Error>> + Send;
+}
+
+impl<E: SharedEndpoint> Endpoint for Arc<E> {
+    type Error = E::Error;
+    async fn respond(&mut self, request: &mut Request) -> Result<Response, Self::Error> {
+        SharedEndpoint::respond(&**self, request).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Body;
+    use alloc::string::ToString;
+    use core::{
+        convert::Infallible,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+    use futures_lite::future;
+
+    // Answers once two requests are in flight at the same time.
+    struct Rendezvous {
+        in_flight: AtomicUsize,
+        served: AtomicUsize,
+    }
+
+    impl SharedEndpoint for Rendezvous {
+        type Error = Infallible;
+        async fn respond(&self, request: &mut Request) -> Result<Response, Self::Error> {
+            self.in_flight.fetch_add(1, Ordering::SeqCst);
+            while self.in_flight.load(Ordering::SeqCst) < 2 {
+                future::yield_now().await;
+            }
+            self.served.fetch_add(1, Ordering::SeqCst);
+            Ok(Response::new(Body::from_text(request.uri().to_string())))
+        }
+    }
+
+    #[tokio::test]
+    async fn concurrent_requests_share_one_instance() {
+        let endpoint = Arc::new(Rendezvous {
+            in_flight: AtomicUsize::new(0),
+            served: AtomicUsize::new(0),
+        });
+        let call = |uri: &'static str| {
+            let mut endpoint = endpoint.clone();
+            tokio::spawn(async move {
+                let mut request = Request::new(Body::empty());
+                *request.uri_mut() = uri.parse().unwrap();
+                let response = Endpoint::respond(&mut endpoint, &mut request)
+                    .await
+                    .unwrap();
+                response.into_body().into_string().await.unwrap()
+            })
+        };
+        let (first, second) = future::zip(call("/a"), call("/b")).await;
+        assert_eq!(first.unwrap(), "/a");
+        assert_eq!(second.unwrap(), "/b");
+        assert_eq!(endpoint.served.load(Ordering::SeqCst), 2);
+    }
+}
@@ -0,0 +1,179 @@
+//! Adapters between endpoints and `tower` services.
+
+use alloc::boxed::Box;
+use core::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use tower_service::Service;
+
+use crate::{Endpoint, HttpError, Request, Response};
+
+/// A `tower` service calling an [`Endpoint`].
+///
+/// Services take `&mut self` only while creating the response future, whereas an
+/// endpoint is borrowed until its response is ready. Each call therefore runs on a
+/// clone of the endpoint, so endpoints sharing state between calls should keep it
+/// behind an `Arc`. The service is always ready.
+///
+/// # Examples
+///
+/// ```rust
+/// use http_kit::{endpoint::{endpoint_fn, EndpointService}, Body, Request, Response};
+/// use tower_service::Service;
+///
+/// # async fn example() {
+/// let hello = endpoint_fn(|_request: &mut Request| async {
+///     Ok::<_, core::convert::Infallible>(Response::new(Body::from_text("hello")))
+/// });
+/// let mut service = EndpointService::new(hello);
+/// let response = service.call(Request::new(Body::empty())).await.unwrap();
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct EndpointService<E> {
+    endpoint: E,
+}
+
+impl<E> EndpointService<E> {
+    /// Wraps `endpoint` into a service.
+    pub const fn new(endpoint: E) -> Self {
+        Self { endpoint }
+    }
+
+    /// Returns the wrapped endpoint.
+    pub fn into_inner(self) -> E {
+        self.endpoint
+    }
+}
+
+impl<E> Service<Request> for EndpointService<E>
+where
+    E: Endpoint + Clone + 'static,
+{
+    type Response = Response;
+    type Error = E::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, E::Error>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, mut request: Request) -> Self::Future {
+        let mut endpoint = self.endpoint.clone();
+        Box::pin(async move { endpoint.respond(&mut request).await })
+    }
+}
+
+/// An [`Endpoint`] calling a `tower` service, so that `tower` middleware stacks can be
+/// used inside [`WithMiddleware`](crate::endpoint::WithMiddleware).
+///
+/// The service takes the request by value: its body and extensions are moved to the
+/// service, and the rest of the request is copied. Once the endpoint has responded,
+/// the request seen by outer middleware therefore has an empty body and no
+/// extensions.
+#[derive(Debug, Clone)]
+pub struct ServiceEndpoint<S> {
+    service: S,
+}
+
+impl<S> ServiceEndpoint<S> {
+    /// Wraps `service` into an endpoint.
+    pub const fn new(service: S) -> Self {
+        Self { service }
+    }
+
+    /// Returns the wrapped service.
+    pub fn into_inner(self) -> S {
+        self.service
+    }
+}
+
+impl<S> Endpoint for ServiceEndpoint<S>
+where
+    S: Service<Request, Response = Response> + Send,
+    S::Error: HttpError,
+    S::Future: Send,
+{
+    type Error = S::Error;
+
+    async fn respond(&mut self, request: &mut Request) -> Result<Response, Self::Error> {
+        futures_lite::future::poll_fn(|cx| self.service.poll_ready(cx)).await?;
+        let mut owned = Request::new(core::mem::take(request.body_mut()));
+        *owned.method_mut() = request.method().clone();
+        *owned.uri_mut() = request.uri().clone();
+        *owned.version_mut() = request.version();
+        *owned.headers_mut() = request.headers().clone();
+        *owned.extensions_mut() = core::mem::take(request.extensions_mut());
+        self.service.call(owned).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        endpoint::WithMiddleware,
+        middleware::{headers::DefaultHeaders, request_id::RequestIdMiddleware},
+        Body, RequestExt,
+    };
+    use alloc::{format, sync::Arc};
+    use core::{
+        convert::Infallible,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+    use http::{HeaderValue, Method};
+
+    // Echoes the method, path, body and request id, counting the calls.
+    #[derive(Clone, Default)]
+    struct Echo {
+        calls: Arc<AtomicUsize>,
+    }
+
+    impl Endpoint for Echo {
+        type Error = Infallible;
+        async fn respond(&mut self, request: &mut Request) -> Result<Response, Self::Error> {
+            self.calls.fetch_add(1, Ordering::Relaxed);
+            let id = request.request_id().map_or("-".into(), |id| id.0.clone());
+            let head = format!("{} {}", request.method(), request.uri().path());
+            let body = request.body_mut().as_str().await.unwrap_or_default();
+            let text = format!("{head} {body} {id}");
+            Ok(Response::new(Body::from_text(text)))
+        }
+    }
+
+    #[tokio::test]
+    async fn round_trip_through_both_adapters() {
+        let echo = Echo::default();
+        let mut service = EndpointService::new(echo.clone());
+        let mut request = Request::new(Body::from_text("ping"));
+        *request.method_mut() = Method::POST;
+        *request.uri_mut() = "/echo".parse().unwrap();
+        let response = service.call(request).await.unwrap();
+        assert_eq!(
+            response.into_body().into_string().await.unwrap(),
+            "POST /echo ping -"
+        );
+
+        let mut endpoint = WithMiddleware::new(
+            ServiceEndpoint::new(service),
+            (
+                DefaultHeaders::new()
+                    .header(http::header::SERVER, HeaderValue::from_static("http-kit")),
+                RequestIdMiddleware::new().generator(|| "req-1".into()),
+            ),
+        );
+        let mut request = Request::new(Body::from_text("pong"));
+        *request.uri_mut() = "/again".parse().unwrap();
+        let response = endpoint.respond(&mut request).await.unwrap();
+        assert_eq!(response.headers()[http::header::SERVER], "http-kit");
+        assert_eq!(
+            response.into_body().into_string().await.unwrap(),
+            "GET /again pong req-1"
+        );
+        assert_eq!(request.uri(), "/again");
+        assert_eq!(echo.calls.load(Ordering::Relaxed), 2);
+    }
+}
@@ -30,7 +30,9 @@ use http::StatusCode;
 /// A concrete error type for HTTP operations.
 ///
 /// Note that this type doesn't implement `HttpError` directly, but also provide `status` method
-/// to get the associated status code.
+/// to get the associated status code. It cannot implement `core::error::Error` without
+/// breaking the conversion from every error type; it converts into [`BoxHttpError`]
+/// instead, keeping its status.
 #[derive(Debug)]
 pub struct Error {
     inner: eyre::Report,
@@ -47,13 +49,88 @@ impl Error {
     }
 
     /// Create a new error from any standard error type.
+    ///
+    /// The status is `500 Internal Server Error`, unless `e` is a [`BoxHttpError`],
+    /// whose status is kept.
     pub fn new(e: impl Into<eyre::Report>) -> Self {
+        let inner = e.into();
+        let status = inner
+            .downcast_ref::<BoxHttpError>()
+            .map_or(StatusCode::INTERNAL_SERVER_ERROR, |error| error.status());
+        Self { inner, status }
+    }
+
+    /// Create a new error from a boxed HTTP error, keeping its status.
+    pub fn from_http_error(error: BoxHttpError) -> Self {
         Self {
-            inner: e.into(),
-            status: StatusCode::INTERNAL_SERVER_ERROR,
+            status: error.status(),
+            inner: eyre::Report::new(error),
         }
     }
 
+    /// Returns the HTTP status code of this error.
+    pub const fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    /// Wraps the error with a message describing what was being done, keeping its status.
+    ///
+    /// The message replaces the error in `Display`; the alternate form (`{:#}`) and
+    /// `Debug` show the whole chain. The original error can still be downcast to.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use http_kit::{Error, StatusCode};
+    ///
+    /// let err = Error::msg("connection refused")
+    ///     .set_status(StatusCode::BAD_GATEWAY)
+    ///     .context("calling the backend");
+    /// assert_eq!(err.to_string(), "calling the backend");
+    /// assert_eq!(format!("{err:#}"), "calling the backend: connection refused");
+    /// assert_eq!(err.status(), StatusCode::BAD_GATEWAY);
+    /// ```
+    pub fn context(self, msg: impl Display + Send + Sync + Debug + 'static) -> Self {
+        Self {
+            inner: self.inner.wrap_err(msg),
+            status: self.status,
+        }
+    }
+
+    /// Returns a reference to the underlying error or context message of type `E`, if
+    /// there is one.
+    pub fn downcast_ref<E>(&self) -> Option<&E>
+    where
+        E: Display + Debug + Send + Sync + 'static,
+    {
+        self.inner.downcast_ref()
+    }
+
+    /// Returns a mutable reference to the underlying error or context message of type
+    /// `E`, if there is one.
+    pub fn downcast_mut<E>(&mut self) -> Option<&mut E>
+    where
+        E: Display + Debug + Send + Sync + 'static,
+    {
+        self.inner.downcast_mut()
+    }
+
+    /// Takes the underlying error or context message of type `E`, or returns `self` if
+    /// there is none.
+    ///
+    /// # Errors
+    ///
+    /// Returns `self` unchanged if it does not hold an `E`.
+    pub fn downcast<E>(self) -> Result<E, Self>
+    where
+        E: Display + Debug + Send + Sync + 'static,
+    {
+        let status = self.status;
+        self.inner
+            .downcast()
+            .map_err(|inner| Self { inner, status })
+    }
+
     /// Consume the error and return the inner `eyre::Report`.
     pub fn into_inner(self) -> eyre::Report {
         self.inner
@@ -94,7 +171,7 @@ impl Error {
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.inner)
+        Display::fmt(&self.inner, f)
     }
 }
 
@@ -147,6 +224,11 @@ pub type Result<T, E = Error> = core::result::Result<T, E>;
 pub trait ResultExt<T> {
     /// Map the error variant to an [`Error`] with the given status code.
     fn status(self, status: StatusCode) -> Result<T, Error>;
+
+    /// Map the error variant to an [`Error`] wrapped with `msg`, see [`Error::context`].
+    fn context<M>(self, msg: M) -> Result<T, Error>
+    where
+        M: Display + Send + Sync + Debug + 'static;
 }
 
 impl<T, E> ResultExt<T> for core::result::Result<T, E>
@@ -156,12 +238,39 @@ where
     fn status(self, status: StatusCode) -> Result<T, Error> {
         self.map_err(|e| Error::new(e).set_status(status))
     }
+
+    fn context<M>(self, msg: M) -> Result<T, Error>
+    where
+        M: Display + Send + Sync + Debug + 'static,
+    {
+        self.map_err(|e| Error::new(e).context(msg))
+    }
+}
+
+impl<T> ResultExt<T> for core::result::Result<T, Error> {
+    fn status(self, status: StatusCode) -> Result<T, Error> {
+        self.map_err(|e| e.set_status(status))
+    }
+
+    fn context<M>(self, msg: M) -> Result<T, Error>
+    where
+        M: Display + Send + Sync + Debug + 'static,
+    {
+        self.map_err(|e| e.context(msg))
+    }
 }
 
 impl<T> ResultExt<T> for core::option::Option<T> {
     fn status(self, status: StatusCode) -> Result<T, Error> {
         self.ok_or_else(|| Error::msg("None value").set_status(status))
     }
+
+    fn context<M>(self, msg: M) -> Result<T, Error>
+    where
+        M: Display + Send + Sync + Debug + 'static,
+    {
+        self.ok_or_else(|| Error::msg(msg))
+    }
 }
 
 /// A boxed HTTP error trait object.
@@ -178,6 +287,12 @@ where
     }
 }
 
+impl From<Error> for BoxHttpError {
+    fn from(error: Error) -> Self {
+        error.into_boxed_http_error()
+    }
+}
+
 impl core::error::Error for BoxHttpError {}
 impl HttpError for BoxHttpError {
     fn status(&self) -> StatusCode {
@@ -190,3 +305,84 @@ impl HttpError for Infallible {
         unreachable!("Infallible can never be instantiated")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::{format, string::ToString};
+
+    http_error!(Gone, StatusCode::GONE, "resource gone");
+
+    #[test]
+    fn context_keeps_the_status_and_chain() {
+        let error = Error::msg("disk full")
+            .set_status(StatusCode::INSUFFICIENT_STORAGE)
+            .context("saving upload")
+            .context("handling request");
+        assert_eq!(error.status(), StatusCode::INSUFFICIENT_STORAGE);
+        assert_eq!(error.to_string(), "handling request");
+        assert_eq!(
+            format!("{error:#}"),
+            "handling request: saving upload: disk full"
+        );
+        let debug = format!("{error:?}");
+        assert!(debug.contains("saving upload"));
+        assert!(debug.contains("disk full"));
+
+        let result: Result<(), Error> = Err(Error::msg("denied").set_status(StatusCode::FORBIDDEN));
+        let error = result.context("reading file").unwrap_err();
+        assert_eq!(error.status(), StatusCode::FORBIDDEN);
+        assert_eq!(format!("{error:#}"), "reading file: denied");
+
+        let error = None::<()>.context("no user").unwrap_err();
+        assert_eq!(error.to_string(), "no user");
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn downcast_after_context() {
+        extern crate std;
+        use std::io;
+
+        let result: Result<(), io::Error> = Err(io::ErrorKind::NotFound.into());
+        let mut error = result
+            .status(StatusCode::NOT_FOUND)
+            .context("opening config")
+            .unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<io::Error>().unwrap().kind(),
+            io::ErrorKind::NotFound
+        );
+        assert!(error.downcast_mut::<io::Error>().is_some());
+        assert!(error.downcast_ref::<fmt::Error>().is_none());
+
+        let error = error.downcast::<fmt::Error>().unwrap_err();
+        assert_eq!(error.status(), StatusCode::NOT_FOUND);
+        let io_error = error.downcast::<io::Error>().unwrap();
+        assert_eq!(io_error.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn status_survives_conversions() {
+        let boxed: BoxHttpError = Box::<Gone>::default();
+        let error = Error::from_http_error(boxed);
+        assert_eq!(error.status(), StatusCode::GONE);
+        assert_eq!(error.to_string(), "resource gone");
+
+        let boxed: BoxHttpError = error.context("looking up").into();
+        assert_eq!(boxed.status(), StatusCode::GONE);
+        assert_eq!(boxed.to_string(), "looking up");
+
+        // `?` on a boxed error goes through `From`, which keeps the status too.
+        let error: Error = boxed.into();
+        assert_eq!(error.status(), StatusCode::GONE);
+        let error = Error::new(Box::<Gone>::default() as BoxHttpError);
+        assert_eq!(error.status(), StatusCode::GONE);
+
+        assert_eq!(
+            Error::new(Gone::new()).status(),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+        assert_eq!(Gone::new().into_error().status(), StatusCode::GONE);
+    }
+}
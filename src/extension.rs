@@ -0,0 +1,205 @@
+//! Storage of values without `Clone` in [`http::Extensions`].
+//!
+//! `http::Extensions::insert` requires `Clone` so that requests and responses can be
+//! cloned with their extensions. Values inserted through [`RequestExt`] and
+//! [`ResponseExt`] are wrapped in a [`Slot`] instead, whose clone is empty: one-shot
+//! values such as channel senders or upgrade handles stay with the original message.
+//!
+//! Lookups fall back to values inserted directly with `extensions_mut().insert`, so both
+//! ways of inserting remain interchangeable for readers.
+//!
+//! [`RequestExt`]: crate::RequestExt
+//! [`ResponseExt`]: crate::ResponseExt
+
+use http::Extensions;
+
+pub(crate) struct Slot<T>(Option<T>);
+
+impl<T> Clone for Slot<T> {
+    fn clone(&self) -> Self {
+        Self(None)
+    }
+}
+
+pub(crate) fn insert<T: Send + Sync + 'static>(extensions: &mut Extensions, value: T) -> Option<T> {
+    let previous = take::<T>(extensions);
+    extensions.insert(Slot(Some(value)));
+    previous
+}
+
+pub(crate) fn get<T: Send + Sync + 'static>(extensions: &Extensions) -> Option<&T> {
+    match extensions.get::<Slot<T>>() {
+        Some(Slot(Some(value))) => Some(value),
+        _ => extensions.get::<T>(),
+    }
+}
+
+pub(crate) fn get_mut<T: Send + Sync + 'static>(extensions: &mut Extensions) -> Option<&mut T> {
+    if extensions.get::<T>().is_some() {
+        return extensions.get_mut::<T>();
+    }
+    extensions
+        .get_mut::<Slot<T>>()
+        .and_then(|slot| slot.0.as_mut())
+}
+
+pub(crate) fn take<T: Send + Sync + 'static>(extensions: &mut Extensions) -> Option<T> {
+    let slot = extensions.remove::<Slot<T>>().and_then(|slot| slot.0);
+    let plain = extensions.remove::<T>();
+    slot.or(plain)
+}
+
+pub(crate) fn get_or_insert_with<T, F>(extensions: &mut Extensions, f: F) -> &mut T
+where
+    T: Send + Sync + 'static,
+    F: FnOnce() -> T,
+{
+    if extensions.get::<T>().is_some() {
+        return extensions.get_mut::<T>().expect("extension is present");
+    }
+    extensions
+        .get_or_insert_with(|| Slot::<T>(None))
+        .0
+        .get_or_insert_with(f)
+}
+
+// Copies the extensions of `from` into `to`, keeping the values `to` already has.
+// `from` is left as it is, since middleware may still read its values, such as the
+// cookie jar, once the endpoint returns.
+pub(crate) fn transfer(from: &Extensions, to: &mut Extensions) {
+    let mut merged = from.clone();
+    merged.extend(core::mem::take(to));
+    *to = merged;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        middleware::MiddlewareError, Body, Endpoint, Middleware, Request, RequestExt, Response,
+        ResponseExt,
+    };
+    use core::convert::Infallible;
+
+    // A one-shot handoff that deliberately does not implement `Clone`.
+    struct Handoff(async_channel::Sender<u32>);
+
+    struct Handler;
+
+    impl Endpoint for Handler {
+        type Error = Infallible;
+        async fn respond(&mut self, request: &mut Request) -> Result<Response, Self::Error> {
+            let Handoff(sender) = request.take_extension::<Handoff>().unwrap();
+            sender.send(7).await.unwrap();
+            assert!(request.take_extension::<Handoff>().is_none());
+            Ok(Response::new(Body::empty()))
+        }
+    }
+
+    struct Inject(Option<Handoff>);
+
+    impl Middleware for Inject {
+        type Error = Infallible;
+        async fn handle<E: Endpoint>(
+            &mut self,
+            request: &mut Request,
+            mut next: E,
+        ) -> Result<Response, MiddlewareError<E::Error, Self::Error>> {
+            request.insert_extension(self.0.take().unwrap());
+            next.respond(request)
+                .await
+                .map_err(MiddlewareError::Endpoint)
+        }
+    }
+
+    #[tokio::test]
+    async fn non_clone_values_are_handed_off() {
+        let (sender, receiver) = async_channel::bounded(1);
+        let mut endpoint =
+            crate::endpoint::WithMiddleware::new(Handler, Inject(Some(Handoff(sender))));
+        let mut request = Request::new(Body::empty());
+        endpoint.respond(&mut request).await.unwrap();
+        assert_eq!(receiver.recv().await.unwrap(), 7);
+    }
+
+    #[test]
+    fn clones_do_not_carry_slots() {
+        let (sender, _receiver) = async_channel::bounded::<u32>(1);
+        let mut response = Response::new(Body::empty());
+        response.insert_extension(Handoff(sender));
+        assert!(response.extension::<Handoff>().is_some());
+
+        let cloned = response.extensions().clone();
+        assert!(crate::extension::get::<Handoff>(&cloned).is_none());
+        assert!(response.take_extension::<Handoff>().is_some());
+    }
+
+    #[test]
+    fn plain_extensions_are_visible() {
+        let mut request = Request::new(Body::empty());
+        request.extensions_mut().insert(5u8);
+        assert_eq!(request.extension::<u8>(), Some(&5));
+        *request.extension_mut::<u8>().unwrap() += 1;
+        assert_eq!(request.insert_extension(9u8), Some(6));
+        assert_eq!(request.extension::<u8>(), Some(&9));
+        assert_eq!(request.take_extension::<u8>(), Some(9));
+        assert!(request.extensions().get::<u8>().is_none());
+    }
+
+    #[test]
+    fn lazily_initialized_cache() {
+        let mut request = Request::new(Body::empty());
+        let mut calls = 0;
+        for _ in 0..3 {
+            let cache = request.extension_or_insert_with(|| {
+                calls += 1;
+                alloc::vec::Vec::<u32>::new()
+            });
+            cache.push(1);
+        }
+        assert_eq!(calls, 1);
+        assert_eq!(
+            request.extension::<alloc::vec::Vec<u32>>().unwrap().len(),
+            3
+        );
+    }
+
+    #[test]
+    fn default_extensions_accumulate() {
+        #[derive(Default)]
+        struct Audit(alloc::vec::Vec<&'static str>);
+
+        let mut request = Request::new(Body::empty());
+        request.extension_or_default::<Audit>().0.push("auth");
+        request.extension_or_default::<Audit>().0.push("quota");
+        assert_eq!(request.extension::<Audit>().unwrap().0, ["auth", "quota"]);
+
+        // Plain values inserted with `extensions_mut` are found, not replaced.
+        let mut response = Response::new(Body::empty());
+        response.extensions_mut().insert(3u16);
+        *response.extension_or_default::<u16>() += 1;
+        assert_eq!(response.extensions().get::<u16>(), Some(&4));
+        assert_eq!(*response.extension_or_default::<u32>(), 0);
+    }
+
+    #[test]
+    fn extensions_are_copied_to_the_response() {
+        let (sender, _receiver) = async_channel::bounded::<u32>(1);
+        let mut request = Request::new(Body::empty());
+        request.insert_extension(Handoff(sender));
+        request.extensions_mut().insert("request");
+        request.extensions_mut().insert(1u8);
+        let mut response = Response::new(Body::empty());
+        response.extensions_mut().insert("response");
+        response.insert_extension(2u16);
+
+        request.transfer_extensions(&mut response);
+        assert_eq!(response.extension::<u8>(), Some(&1));
+        assert_eq!(response.extension::<u16>(), Some(&2));
+        // The response keeps its own value of a shared type.
+        assert_eq!(response.extension::<&str>(), Some(&"response"));
+        // Values without `Clone` stay with the request, which keeps everything.
+        assert!(response.take_extension::<Handoff>().is_none());
+        assert!(request.extension::<Handoff>().is_some());
+        assert_eq!(request.extension::<u8>(), Some(&1));
+    }
+}
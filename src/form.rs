@@ -0,0 +1,353 @@
+//! Decoding of URL-encoded forms with repeated keys.
+//!
+//! `serde_urlencoded`, used by [`Body::into_form`](crate::Body::into_form), maps every
+//! key to a single value. HTML forms repeat keys for multi-selects and checkbox groups
+//! (`tags=a&tags=b`), which the decoder of this module collects into sequences instead:
+//!
+//! - All values of a key, in order, deserialize into a sequence field such as `Vec<T>`.
+//!   A trailing `[]` on a key (`tags[]=a`) is ignored, as PHP-style forms send it.
+//! - Other fields take the last value of their key.
+//! - An empty value deserializes into `None` for `Option` fields.
+//! - A key that is absent is a missing field, so sequence fields that may be empty need
+//!   `#[serde(default)]`.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use http_kit::form;
+//! use serde::Deserialize;
+//!
+//! #[derive(Deserialize)]
+//! struct Filter {
+//!     tags: Vec<String>,
+//!     page: Option<u32>,
+//! }
+//!
+//! let filter: Filter = form::from_bytes(b"tags=http&page=&tags=rust").unwrap();
+//! assert_eq!(filter.tags, ["http", "rust"]);
+//! assert_eq!(filter.page, None);
+//! ```
+
+use alloc::{collections::BTreeMap, string::String, vec, vec::Vec};
+
+use serde::de::{
+    value::{MapDeserializer, SeqDeserializer},
+    DeserializeOwned, Deserializer, Error as _, IntoDeserializer, Unexpected, Visitor,
+};
+
+use crate::percent;
+
+/// Error returned when a form cannot be decoded, the same as that of `serde_urlencoded`.
+pub type Error = serde_urlencoded::de::Error;
+
+/// Maximum number of key-value pairs decoded by [`from_bytes`].
+pub const DEFAULT_MAX_PAIRS: usize = 1000;
+
+/// Deserializes a URL-encoded form, collecting repeated keys into sequences.
+///
+/// Forms with more than [`DEFAULT_MAX_PAIRS`] pairs are rejected.
+///
+/// # Errors
+///
+/// Returns an error if the form has too many pairs or does not match `T`.
+pub fn from_bytes<T: DeserializeOwned>(input: &[u8]) -> Result<T, Error> {
+    from_bytes_with_limit(input, DEFAULT_MAX_PAIRS)
+}
+
+/// Deserializes a URL-encoded form like [`from_bytes`], accepting at most `max_pairs`
+/// key-value pairs.
+///
+/// # Errors
+///
+/// Returns an error if the form has more than `max_pairs` pairs or does not match `T`.
+pub fn from_bytes_with_limit<T: DeserializeOwned>(
+    input: &[u8],
+    max_pairs: usize,
+) -> Result<T, Error> {
+    let mut entries: Vec<(String, Values)> = Vec::new();
+    let mut index = BTreeMap::<String, usize>::new();
+    let pairs = input
+        .split(|&byte| byte == b'&')
+        .filter(|pair| !pair.is_empty());
+    for (count, pair) in pairs.enumerate() {
+        if count == max_pairs {
+            return Err(Error::custom(alloc::format!(
+                "form has more than {max_pairs} fields"
+            )));
+        }
+        let (key, value) = match pair.iter().position(|&byte| byte == b'=') {
+            Some(at) => (&pair[..at], &pair[at + 1..]),
+            None => (pair, &[][..]),
+        };
+        let mut key = decode(key);
+        if key.ends_with("[]") {
+            key.truncate(key.len() - 2);
+        }
+        let value = decode(value);
+        match index.get(&key) {
+            Some(&at) => {
+                let Values(values) = &mut entries[at].1;
+                values.push(value);
+            }
+            None => {
+                index.insert(key.clone(), entries.len());
+                entries.push((key, Values(vec![value])));
+            }
+        }
+    }
+    T::deserialize(MapDeserializer::<_, Error>::new(entries.into_iter()))
+}
+
+fn decode(input: &[u8]) -> String {
+    String::from_utf8_lossy(&percent::decode_lenient(input, true)).into_owned()
+}
+
+// All the values of a key, never empty.
+struct Values(Vec<String>);
+
+impl Values {
+    fn last(mut self) -> Part {
+        Part(self.0.pop().unwrap_or_default())
+    }
+}
+
+impl IntoDeserializer<'_, Error> for Values {
+    type Deserializer = Self;
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+macro_rules! forward_to_last {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+                self.last().$method(visitor)
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for Values {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.last().deserialize_any(visitor)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let mut seq = SeqDeserializer::new(self.0.into_iter().map(Part));
+        let value = visitor.visit_seq(&mut seq)?;
+        seq.end()?;
+        Ok(value)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.0.as_slice() {
+            [value] if value.is_empty() => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.last().deserialize_enum(name, variants, visitor)
+    }
+
+    forward_to_last! {
+        deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64
+        deserialize_i128 deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64
+        deserialize_u128 deserialize_f32 deserialize_f64 deserialize_char deserialize_str
+        deserialize_string deserialize_bytes deserialize_byte_buf deserialize_unit
+        deserialize_identifier deserialize_ignored_any
+    }
+
+    serde::forward_to_deserialize_any! {
+        unit_struct map struct
+    }
+}
+
+// A single value, parsed according to the type it deserializes into.
+struct Part(String);
+
+impl IntoDeserializer<'_, Error> for Part {
+    type Deserializer = Self;
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+macro_rules! parse_part {
+    ($($method:ident => $visit:ident,)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+                match self.0.parse() {
+                    Ok(value) => visitor.$visit(value),
+                    Err(_) => Err(Error::invalid_value(Unexpected::Str(&self.0), &visitor)),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for Part {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_string(self.0)
+    }
+
+    parse_part! {
+        deserialize_bool => visit_bool,
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_i128 => visit_i128,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_u128 => visit_u128,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+        deserialize_char => visit_char,
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        if self.0.is_empty() {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_enum(self.0.into_deserializer())
+    }
+
+    serde::forward_to_deserialize_any! {
+        str string bytes byte_buf unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+    use serde::Deserialize;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    #[serde(rename_all = "lowercase")]
+    enum Color {
+        Red,
+        Blue,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Filter {
+        q: String,
+        #[serde(default)]
+        tags: Vec<String>,
+        colors: Option<Vec<Color>>,
+        page: Option<u32>,
+        exact: bool,
+    }
+
+    #[test]
+    fn repeated_keys_become_sequences() {
+        let filter: Filter =
+            from_bytes(b"tags=a&q=rust+http&tags=b&colors[]=red&exact=true&colors[]=blue").unwrap();
+        assert_eq!(
+            filter,
+            Filter {
+                q: "rust http".into(),
+                tags: vec!["a".into(), "b".into()],
+                colors: Some(vec![Color::Red, Color::Blue]),
+                page: None,
+                exact: true,
+            }
+        );
+
+        // The order of the keys does not matter, only that of the values of a key.
+        let reordered: Filter =
+            from_bytes(b"exact=true&colors[]=red&tags=a&colors[]=blue&tags=b&q=rust%20http")
+                .unwrap();
+        assert_eq!(reordered, filter);
+    }
+
+    #[test]
+    fn optional_and_empty_values() {
+        let filter: Filter = from_bytes(b"q=&page=&exact=false").unwrap();
+        assert_eq!(filter.q, "");
+        assert!(filter.tags.is_empty());
+        assert_eq!(filter.colors, None);
+        assert_eq!(filter.page, None);
+
+        let filter: Filter = from_bytes(b"q=x&page=2&page=3&exact=false&tags=").unwrap();
+        assert_eq!(filter.page, Some(3));
+        assert_eq!(filter.tags, [""]);
+
+        assert!(from_bytes::<Filter>(b"q=x&page=two&exact=false").is_err());
+        assert!(from_bytes::<Filter>(b"q=x&exact=maybe").is_err());
+        assert!(from_bytes::<Filter>(b"q=x").is_err());
+    }
+
+    #[test]
+    fn pair_count_is_limited() {
+        let form = "a=1&".repeat(5);
+        let values: BTreeMap<String, Vec<u32>> = from_bytes_with_limit(form.as_bytes(), 5).unwrap();
+        assert_eq!(values["a"], [1; 5]);
+
+        let error =
+            from_bytes_with_limit::<BTreeMap<String, Vec<u32>>>(form.as_bytes(), 4).unwrap_err();
+        assert_eq!(error.to_string(), "form has more than 4 fields");
+    }
+}
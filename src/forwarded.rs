@@ -0,0 +1,491 @@
+//! The client seen through proxies: `Forwarded` and `X-Forwarded-*` headers.
+//!
+//! Proxies and load balancers record the hops of a request in the `Forwarded` header of
+//! [RFC 7239](https://www.rfc-editor.org/rfc/rfc7239), or in the older
+//! `X-Forwarded-For` and `X-Forwarded-Proto` headers. Each proxy appends the address of
+//! the node that connected to it, so the chain reads from the original client on the
+//! left to the last proxy on the right.
+//!
+//! Anything left of the proxies you run was written by the client and may be forged,
+//! and so is the whole header your proxies do not write.
+//! [`RequestExt::client_addr`](crate::RequestExt::client_addr) therefore reads the
+//! chain of the header named by a [`Source`], walks it from the right, skipping trusted
+//! proxies, and stops at the first other node.
+//!
+//! Malformed elements are skipped by the parsers rather than failing the whole header.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use http_kit::forwarded::Node;
+//! use http_kit::{Body, Request, RequestExt};
+//!
+//! let mut request = Request::new(Body::empty());
+//! let forwarded = r#"for=192.0.2.43, for="[2001:db8:cafe::17]:4711";proto=https"#;
+//! request.headers_mut().insert("forwarded", forwarded.parse().unwrap());
+//!
+//! let elements = request.forwarded();
+//! assert_eq!(elements.len(), 2);
+//! assert_eq!(elements[1].proto.as_deref(), Some("https"));
+//! let Some(Node::Ip { addr, port }) = &elements[1].forwarded_for else { panic!() };
+//! assert_eq!(addr.to_string(), "2001:db8:cafe::17");
+//! assert_eq!(*port, Some(4711));
+//! ```
+
+use alloc::{
+    borrow::ToOwned,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::net::{IpAddr, Ipv6Addr};
+
+use http::{header, HeaderMap};
+
+use crate::headers::{self, is_tchar, parse_quoted};
+
+/// A node of a forwarding chain, as identified in `Forwarded` and `X-Forwarded-For`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Node {
+    /// An IP address, and its port when given and not obfuscated.
+    Ip {
+        /// The address of the node.
+        addr: IpAddr,
+        /// The port of the node.
+        port: Option<u16>,
+    },
+    /// `unknown`: the proxy does not know the node, or does not reveal it.
+    Unknown,
+    /// An obfuscated identifier, such as `_hidden`, hiding the address of the node.
+    Obfuscated(String),
+}
+
+impl Node {
+    /// Parses a node: an IPv4 address, a bracketed IPv6 address, `unknown` or an
+    /// obfuscated identifier, optionally followed by `:` and a port.
+    ///
+    /// IPv6 addresses without brackets, as found in `X-Forwarded-For`, are accepted
+    /// too. Returns `None` if `value` is malformed.
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        if let Ok(addr) = value.parse() {
+            return Some(Self::Ip { addr, port: None });
+        }
+        let (name, port) = match value.strip_prefix('[') {
+            Some(bracketed) => {
+                let (addr, rest) = bracketed.split_once(']')?;
+                let addr: Ipv6Addr = addr.parse().ok()?;
+                (IpAddr::V6(addr).to_string(), parse_port(rest)?)
+            }
+            None => match value.split_once(':') {
+                Some((name, port)) => (name.to_owned(), parse_port_number(port)?),
+                None => (value.to_owned(), None),
+            },
+        };
+        if let Ok(addr) = name.parse() {
+            return Some(Self::Ip { addr, port });
+        }
+        if name.eq_ignore_ascii_case("unknown") {
+            return Some(Self::Unknown);
+        }
+        is_obfuscated(&name).then_some(Self::Obfuscated(name))
+    }
+
+    /// Returns the IP address of the node, if known.
+    pub fn ip(&self) -> Option<IpAddr> {
+        match self {
+            Self::Ip { addr, .. } => Some(*addr),
+            _ => None,
+        }
+    }
+}
+
+// Parses what follows the brackets of an IPv6 node: nothing, or `:` and a port.
+fn parse_port(rest: &str) -> Option<Option<u16>> {
+    match rest.strip_prefix(':') {
+        Some(port) => parse_port_number(port),
+        None => rest.is_empty().then_some(None),
+    }
+}
+
+// Parses a port, yielding `None` for an obfuscated one.
+fn parse_port_number(port: &str) -> Option<Option<u16>> {
+    if is_obfuscated(port) {
+        return Some(None);
+    }
+    if port.is_empty() || !port.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+    Some(Some(port.parse().ok()?))
+}
+
+// `obfnode` and `obfport` of RFC 7239: `_` followed by letters, digits, `.`, `_` or `-`.
+fn is_obfuscated(value: &str) -> bool {
+    value.len() > 1
+        && value.starts_with('_')
+        && value
+            .bytes()
+            .all(|byte| byte.is_ascii_alphanumeric() || b"._-".contains(&byte))
+}
+
+/// The header holding the forwarding chain written by the trusted proxies, see
+/// [`RequestExt::client_addr`](crate::RequestExt::client_addr).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    /// The `for` parameters of the `Forwarded` header of RFC 7239.
+    Forwarded,
+    /// The `X-Forwarded-For` header, as written by nginx or most cloud load balancers.
+    XForwardedFor,
+}
+
+/// One hop of the `Forwarded` header: the parameters set by one proxy.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ForwardedElement {
+    /// `for`: the node that made the request to the proxy.
+    pub forwarded_for: Option<Node>,
+    /// `by`: the interface of the proxy that received the request.
+    pub forwarded_by: Option<Node>,
+    /// `host`: the `Host` header received by the proxy.
+    pub host: Option<String>,
+    /// `proto`: the scheme used to reach the proxy, such as `https`, lowercased.
+    pub proto: Option<String>,
+}
+
+// Parses every `Forwarded` header, in order. Malformed elements are kept as `None`, so
+// that the chain of `client_addr` does not silently skip over them.
+fn elements(headers: &HeaderMap) -> Vec<Option<ForwardedElement>> {
+    let mut elements = Vec::new();
+    for value in headers.get_all(header::FORWARDED) {
+        let Ok(mut rest) = value.to_str() else {
+            elements.push(None);
+            continue;
+        };
+        while !rest.trim().is_empty() {
+            match parse_element(rest) {
+                Some((element, after)) => {
+                    elements.push(Some(element));
+                    rest = after;
+                }
+                None => {
+                    elements.push(None);
+                    rest = skip_element(rest);
+                }
+            }
+        }
+    }
+    elements
+}
+
+// Parses the element at the start of `input`, returning it and the input after its
+// trailing comma.
+fn parse_element(input: &str) -> Option<(ForwardedElement, &str)> {
+    let mut element = ForwardedElement::default();
+    let mut rest = input;
+    loop {
+        let (key, after) = rest.split_once('=')?;
+        let key = key.trim();
+        if key.is_empty() || !key.bytes().all(is_tchar) {
+            return None;
+        }
+        let (value, after) = match after.strip_prefix('"') {
+            Some(quoted) => parse_quoted(quoted)?,
+            None => {
+                let end = after.find([';', ',']).unwrap_or(after.len());
+                let token = after[..end].trim_end();
+                if token.is_empty() || !token.bytes().all(is_tchar) {
+                    return None;
+                }
+                (token.to_owned(), &after[end..])
+            }
+        };
+        // Each parameter may appear once per element.
+        let duplicate = if key.eq_ignore_ascii_case("for") {
+            element
+                .forwarded_for
+                .replace(Node::parse(&value)?)
+                .is_some()
+        } else if key.eq_ignore_ascii_case("by") {
+            element.forwarded_by.replace(Node::parse(&value)?).is_some()
+        } else if key.eq_ignore_ascii_case("host") {
+            element.host.replace(value).is_some()
+        } else if key.eq_ignore_ascii_case("proto") {
+            element.proto.replace(value.to_ascii_lowercase()).is_some()
+        } else {
+            false
+        };
+        if duplicate {
+            return None;
+        }
+        let after = after.trim_start();
+        if let Some(next) = after.strip_prefix(';') {
+            rest = next.trim_start();
+        } else if let Some(next) = after.strip_prefix(',') {
+            return Some((element, next));
+        } else if after.is_empty() {
+            return Some((element, after));
+        } else {
+            return None;
+        }
+    }
+}
+
+// Returns the input after the next comma outside a quoted string.
+fn skip_element(input: &str) -> &str {
+    let mut quoted = false;
+    let mut chars = input.char_indices();
+    while let Some((at, c)) = chars.next() {
+        match c {
+            '"' => quoted = !quoted,
+            '\\' if quoted => {
+                chars.next();
+            }
+            ',' if !quoted => return &input[at + 1..],
+            _ => {}
+        }
+    }
+    ""
+}
+
+// Parses every `X-Forwarded-For` header, in order, keeping malformed entries as `None`.
+fn x_forwarded_for_chain(headers: &HeaderMap) -> Vec<Option<Node>> {
+    headers
+        .get_all(headers::X_FORWARDED_FOR)
+        .iter()
+        .flat_map(|value| match value.to_str() {
+            Ok(value) => value.split(',').map(Node::parse).collect(),
+            Err(_) => alloc::vec![None],
+        })
+        .collect()
+}
+
+pub(crate) fn forwarded(headers: &HeaderMap) -> Vec<ForwardedElement> {
+    elements(headers).into_iter().flatten().collect()
+}
+
+pub(crate) fn x_forwarded_for(headers: &HeaderMap) -> Vec<Node> {
+    x_forwarded_for_chain(headers)
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+// The last proxy appends its scheme, or replaces the header, so the rightmost entry is
+// the one it wrote.
+pub(crate) fn x_forwarded_proto(headers: &HeaderMap) -> Option<&str> {
+    let value = headers
+        .get_all(headers::X_FORWARDED_PROTO)
+        .iter()
+        .next_back()?
+        .to_str()
+        .ok()?;
+    let proto = value.rsplit(',').next()?.trim();
+    (!proto.is_empty()).then_some(proto)
+}
+
+pub(crate) fn client_addr(
+    headers: &HeaderMap,
+    source: Source,
+    trusted_proxies: &[IpAddr],
+) -> Option<IpAddr> {
+    let chain: Vec<Option<IpAddr>> = match source {
+        Source::Forwarded => elements(headers)
+            .into_iter()
+            .map(|element| element?.forwarded_for?.ip())
+            .collect(),
+        Source::XForwardedFor => x_forwarded_for_chain(headers)
+            .into_iter()
+            .map(|node| node?.ip())
+            .collect(),
+    };
+    let trusted = |addr: IpAddr| {
+        trusted_proxies
+            .iter()
+            .any(|proxy| proxy.to_canonical() == addr)
+    };
+    let mut client = None;
+    for addr in chain.into_iter().rev() {
+        // A node that cannot be identified hides everything left of it.
+        let addr = addr?.to_canonical();
+        client = Some(addr);
+        if !trusted(addr) {
+            break;
+        }
+    }
+    client
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Body, Request, RequestExt};
+    use http::HeaderValue;
+
+    fn request(headers: &[(&str, &str)]) -> Request {
+        let mut request = Request::new(Body::empty());
+        for (name, value) in headers {
+            let name = http::HeaderName::from_bytes(name.as_bytes()).unwrap();
+            let value = HeaderValue::from_str(value).unwrap();
+            request.headers_mut().append(name, value);
+        }
+        request
+    }
+
+    fn ip(addr: &str) -> IpAddr {
+        addr.parse().unwrap()
+    }
+
+    fn node(addr: &str, port: Option<u16>) -> Option<Node> {
+        Some(Node::Ip {
+            addr: ip(addr),
+            port,
+        })
+    }
+
+    #[test]
+    fn rfc_7239_examples() {
+        let request = request(&[
+            ("forwarded", r#"for="_gazonk""#),
+            ("forwarded", r#"For="[2001:db8:cafe::17]:4711""#),
+            ("forwarded", "for=192.0.2.60;proto=http;by=203.0.113.43"),
+            ("forwarded", "for=192.0.2.43, for=198.51.100.17"),
+            (
+                "forwarded",
+                r#"for=unknown;host="example.com:8080";by="_p1:_x""#,
+            ),
+        ]);
+        let elements = request.forwarded();
+        let fors: Vec<_> = elements.iter().map(|e| e.forwarded_for.clone()).collect();
+        assert_eq!(
+            fors,
+            [
+                Some(Node::Obfuscated("_gazonk".into())),
+                node("2001:db8:cafe::17", Some(4711)),
+                node("192.0.2.60", None),
+                node("192.0.2.43", None),
+                node("198.51.100.17", None),
+                Some(Node::Unknown),
+            ]
+        );
+        assert_eq!(elements[2].proto.as_deref(), Some("http"));
+        assert_eq!(elements[2].forwarded_by, node("203.0.113.43", None));
+        assert_eq!(elements[5].host.as_deref(), Some("example.com:8080"));
+        assert_eq!(
+            elements[5].forwarded_by,
+            Some(Node::Obfuscated("_p1".into()))
+        );
+    }
+
+    #[test]
+    fn malformed_elements_are_skipped() {
+        let request = request(&[(
+            "forwarded",
+            r#"for=192.0.2.43;proto=https, garbage, for="[::1", for="a,b";by=x, for=1.2.3.4;for=5.6.7.8, for=198.51.100.17;proto=HTTPS"#,
+        )]);
+        let elements = request.forwarded();
+        assert_eq!(elements.len(), 2);
+        assert_eq!(elements[0].forwarded_for, node("192.0.2.43", None));
+        assert_eq!(elements[1].forwarded_for, node("198.51.100.17", None));
+        assert_eq!(elements[1].proto.as_deref(), Some("https"));
+
+        // Unquoted IPv6 addresses and bad ports are malformed.
+        for value in [
+            "for=[::1]:80",
+            r#"for="192.0.2.1:99999""#,
+            "for=_",
+            "for=example.com",
+        ] {
+            assert!(request_forwarded(value).is_empty(), "{value}");
+        }
+    }
+
+    fn request_forwarded(value: &str) -> Vec<ForwardedElement> {
+        request(&[("forwarded", value)]).forwarded()
+    }
+
+    #[test]
+    fn x_forwarded_headers() {
+        let request = request(&[
+            (
+                "x-forwarded-for",
+                "203.0.113.195, 2001:db8:85a3::8a2e:370:7334, [2001:db8::1]:8080",
+            ),
+            (
+                "x-forwarded-for",
+                "198.51.100.1:443 , _hidden, unknown, not an ip,",
+            ),
+            ("x-forwarded-proto", "HTTPS, http"),
+        ]);
+        assert_eq!(
+            request.x_forwarded_for(),
+            [
+                node("203.0.113.195", None).unwrap(),
+                node("2001:db8:85a3::8a2e:370:7334", None).unwrap(),
+                node("2001:db8::1", Some(8080)).unwrap(),
+                node("198.51.100.1", Some(443)).unwrap(),
+                Node::Obfuscated("_hidden".into()),
+                Node::Unknown,
+            ]
+        );
+        assert_eq!(request.x_forwarded_proto(), Some("http"));
+        assert_eq!(Request::new(Body::empty()).x_forwarded_proto(), None);
+    }
+
+    #[test]
+    fn client_addr_skips_trusted_proxies_only() {
+        use Source::{Forwarded, XForwardedFor};
+        let proxies = [ip("10.0.0.1"), ip("10.0.0.2")];
+
+        // The client forged the leftmost entry; the load balancer appended the real
+        // address, then an internal proxy its own.
+        let spoofed = request(&[(
+            "x-forwarded-for",
+            "1.2.3.4, 198.51.100.7, 10.0.0.1, 10.0.0.2",
+        )]);
+        let addr = spoofed.client_addr(XForwardedFor, &proxies);
+        assert_eq!(addr, Some(ip("198.51.100.7")));
+        // Without trusted proxies, the last hop is the client.
+        let addr = spoofed.client_addr(XForwardedFor, &[]);
+        assert_eq!(addr, Some(ip("10.0.0.2")));
+
+        // A forged entry naming a trusted proxy does not reach further left.
+        let forged = request(&[("x-forwarded-for", "1.2.3.4, 10.0.0.1, 203.0.113.9")]);
+        let addr = forged.client_addr(XForwardedFor, &proxies);
+        assert_eq!(addr, Some(ip("203.0.113.9")));
+
+        // Nodes that cannot be identified stop the walk.
+        let hidden = request(&[("x-forwarded-for", "1.2.3.4, _hidden, 10.0.0.1")]);
+        assert_eq!(hidden.client_addr(XForwardedFor, &proxies), None);
+        let malformed = request(&[("x-forwarded-for", "1.2.3.4, 300.1.1.1, 10.0.0.1")]);
+        assert_eq!(malformed.client_addr(XForwardedFor, &proxies), None);
+
+        // Mapped IPv6 addresses match IPv4 proxies.
+        let forwarded = request(&[(
+            "forwarded",
+            r#"for="[2001:db8::7]:51000", for="[::ffff:10.0.0.1]""#,
+        )]);
+        let addr = forwarded.client_addr(Forwarded, &proxies);
+        assert_eq!(addr, Some(ip("2001:db8::7")));
+
+        // Only trusted proxies: the leftmost one made the request.
+        let internal = request(&[("x-forwarded-for", "10.0.0.2, 10.0.0.1")]);
+        let addr = internal.client_addr(XForwardedFor, &proxies);
+        assert_eq!(addr, Some(ip("10.0.0.2")));
+        let empty = Request::new(Body::empty());
+        assert_eq!(empty.client_addr(XForwardedFor, &proxies), None);
+    }
+
+    #[test]
+    fn client_addr_ignores_the_header_proxies_do_not_write() {
+        // A proxy appending `X-Forwarded-For` passes on the `Forwarded` of the client.
+        let both = request(&[
+            ("forwarded", "for=6.6.6.6"),
+            ("x-forwarded-for", "203.0.113.9"),
+        ]);
+        let proxies = [ip("10.0.0.1")];
+        let addr = both.client_addr(Source::XForwardedFor, &proxies);
+        assert_eq!(addr, Some(ip("203.0.113.9")));
+        let addr = both.client_addr(Source::Forwarded, &proxies);
+        assert_eq!(addr, Some(ip("6.6.6.6")));
+        let xff_only = request(&[("x-forwarded-for", "203.0.113.9")]);
+        assert_eq!(xff_only.client_addr(Source::Forwarded, &proxies), None);
+    }
+}
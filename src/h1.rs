@@ -0,0 +1,476 @@
+//! HTTP/1.x connection management and wire helpers.
+//!
+//! Whether a connection can carry another request after the current response depends
+//! on the HTTP version, the `Connection` header on both sides, how the response body is
+//! delimited and whether the server is shutting down. [`ConnectionPolicy`] bundles
+//! these rules so that every HTTP/1 server loop built on http-kit reuses connections
+//! the same way.
+//!
+//! [`PreserveCase`] keeps the original spelling of header names, which the `http` crate
+//! normalizes to lowercase, for peers that depend on it.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use http_kit::h1::{ConnectionPolicy, Framing};
+//! use http_kit::{header, Version};
+//! use http::HeaderMap;
+//!
+//! let mut request_headers = HeaderMap::new();
+//! request_headers.insert(header::CONNECTION, "keep-alive".parse().unwrap());
+//! let mut response_headers = HeaderMap::new();
+//!
+//! let framing = Framing::select(Version::HTTP_10, Some(5));
+//! let decision = ConnectionPolicy::decide(
+//!     Version::HTTP_10,
+//!     &request_headers,
+//!     &response_headers,
+//!     framing,
+//!     false,
+//! );
+//! decision.apply(&mut response_headers);
+//!
+//! assert!(decision.reuse);
+//! assert_eq!(response_headers[header::CONNECTION], "keep-alive");
+//! ```
+
+use alloc::{borrow::Cow, vec::Vec};
+
+use bytes::Bytes;
+use http::{
+    header::{self, InvalidHeaderName},
+    HeaderMap, HeaderName, HeaderValue, Version,
+};
+
+/// How the end of a response body is signalled on the wire.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Framing {
+    /// The body is delimited by a `Content-Length` header, or has no body at all.
+    ContentLength,
+    /// The body uses `Transfer-Encoding: chunked`.
+    Chunked,
+    /// The body ends when the connection is closed.
+    CloseDelimited,
+}
+
+impl Framing {
+    /// Picks the framing for a response body of the given length, if known.
+    ///
+    /// Bodies of unknown length are chunked for HTTP/1.1 peers. HTTP/1.0 has no
+    /// chunked encoding, so such bodies can only be delimited by closing the connection.
+    pub const fn select(version: Version, body_len: Option<u64>) -> Self {
+        if body_len.is_some() {
+            Self::ContentLength
+        } else if supports_chunked(version) {
+            Self::Chunked
+        } else {
+            Self::CloseDelimited
+        }
+    }
+}
+
+/// The outcome of [`ConnectionPolicy::decide`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeepAlive {
+    /// Whether the connection may be read for another request after the response.
+    pub reuse: bool,
+    /// The `Connection` header to write on the response, if the response doesn't
+    /// already convey the decision.
+    pub connection: Option<HeaderValue>,
+}
+
+impl KeepAlive {
+    /// Writes [`connection`](Self::connection) to the response headers, replacing any
+    /// `Connection` header set by the application.
+    pub fn apply(&self, headers: &mut HeaderMap) {
+        if let Some(value) = &self.connection {
+            headers.insert(header::CONNECTION, value.clone());
+        }
+    }
+}
+
+/// Keep-alive rules for HTTP/1.0 and HTTP/1.1 connections.
+///
+/// A connection is reused only if all of the following hold:
+///
+/// - the server is not draining,
+/// - the response body is delimited: close-delimited bodies, and chunked bodies sent to
+///   HTTP/1.0 peers, are terminated by closing the connection,
+/// - neither the request nor the response carries `Connection: close`,
+/// - the request is HTTP/1.1, or an HTTP/1.0 request asked for `Connection: keep-alive`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ConnectionPolicy;
+
+impl ConnectionPolicy {
+    /// Decides whether the connection is kept open after this exchange and which
+    /// `Connection` header the response needs to announce it.
+    ///
+    /// HTTP/1.0 clients are told `keep-alive` when the connection is reused, since
+    /// they would close it otherwise; any client is told `close` when it isn't.
+    pub fn decide(
+        request_version: Version,
+        request_headers: &HeaderMap,
+        response_headers: &HeaderMap,
+        framing: Framing,
+        draining: bool,
+    ) -> KeepAlive {
+        let request = ConnectionTokens::parse(request_headers);
+        let response = ConnectionTokens::parse(response_headers);
+        let persistent_by_default = supports_chunked(request_version);
+
+        let delimited = match framing {
+            Framing::ContentLength => true,
+            Framing::Chunked => persistent_by_default,
+            Framing::CloseDelimited => false,
+        };
+        let reuse = !draining
+            && delimited
+            && !request.close
+            && !response.close
+            && (persistent_by_default || request.keep_alive);
+
+        let connection = if !reuse {
+            (!response.close || response.keep_alive).then_some("close")
+        } else if !persistent_by_default && !response.keep_alive {
+            Some("keep-alive")
+        } else {
+            None
+        };
+
+        KeepAlive {
+            reuse,
+            connection: connection.map(HeaderValue::from_static),
+        }
+    }
+}
+
+const fn supports_chunked(version: Version) -> bool {
+    !matches!(version, Version::HTTP_09 | Version::HTTP_10)
+}
+
+#[derive(Default)]
+struct ConnectionTokens {
+    close: bool,
+    keep_alive: bool,
+}
+
+impl ConnectionTokens {
+    fn parse(headers: &HeaderMap) -> Self {
+        let mut tokens = Self::default();
+        for value in headers.get_all(header::CONNECTION) {
+            let Ok(value) = value.to_str() else {
+                continue;
+            };
+            for token in value.split(',').map(str::trim) {
+                if token.eq_ignore_ascii_case("close") {
+                    tokens.close = true;
+                } else if token.eq_ignore_ascii_case("keep-alive") {
+                    tokens.keep_alive = true;
+                }
+            }
+        }
+        tokens
+    }
+}
+
+/// Spelling of header names without a recorded original.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum HeaderCase {
+    /// Lowercase, as stored by the `http` crate: `content-type`.
+    Lower,
+    /// Each word capitalized: `Content-Type`.
+    #[default]
+    Title,
+}
+
+/// Original spellings of header names, stored in the extensions of a message.
+///
+/// Header names are case-insensitive and the `http` crate stores them lowercase, but
+/// some legacy peers only understand the casing they sent. A parser records each name
+/// with [`record`](Self::record) and inserts the table into the message extensions; a
+/// serializer passes it to [`write_headers`] to emit the recorded spellings again. Names
+/// added later, for example by middleware, are written with the
+/// [fallback casing](Self::with_fallback).
+///
+/// The table is only consulted when writing: header lookups and matching keep their
+/// case-insensitive semantics everywhere.
+///
+/// # Examples
+///
+/// ```rust
+/// use http_kit::h1::{write_headers, PreserveCase};
+/// use http::HeaderMap;
+///
+/// let mut case = PreserveCase::new();
+/// let mut headers = HeaderMap::new();
+/// let name = case.record(b"X-LEGACY-token").unwrap();
+/// headers.insert(name, "1".parse().unwrap());
+/// headers.insert("content-type", "text/plain".parse().unwrap());
+///
+/// let mut head = Vec::new();
+/// write_headers(&headers, Some(&case), &mut head);
+/// assert_eq!(head, b"X-LEGACY-token: 1\r\nContent-Type: text/plain\r\n");
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct PreserveCase {
+    names: HeaderMap<Bytes>,
+    fallback: HeaderCase,
+}
+
+impl PreserveCase {
+    /// Creates an empty table with a [`HeaderCase::Title`] fallback.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the casing of names without a recorded spelling.
+    #[must_use]
+    pub fn with_fallback(mut self, fallback: HeaderCase) -> Self {
+        self.fallback = fallback;
+        self
+    }
+
+    /// Parses a header name as received and records its spelling.
+    ///
+    /// When a name occurs several times, the first spelling wins.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `original` is not a valid header name.
+    pub fn record(&mut self, original: &[u8]) -> Result<HeaderName, InvalidHeaderName> {
+        let name = HeaderName::from_bytes(original)?;
+        if !self.names.contains_key(&name) {
+            self.names
+                .insert(name.clone(), Bytes::copy_from_slice(original));
+        }
+        Ok(name)
+    }
+
+    /// Returns the spelling to write for `name`.
+    pub fn spelling<'a>(&'a self, name: &'a HeaderName) -> Cow<'a, [u8]> {
+        if let Some(original) = self.names.get(name) {
+            return Cow::Borrowed(original);
+        }
+        match self.fallback {
+            HeaderCase::Lower => Cow::Borrowed(name.as_str().as_bytes()),
+            HeaderCase::Title => Cow::Owned(title_case(name.as_str())),
+        }
+    }
+}
+
+fn title_case(name: &str) -> Vec<u8> {
+    let mut upper = true;
+    name.bytes()
+        .map(|byte| {
+            let byte = if upper {
+                byte.to_ascii_uppercase()
+            } else {
+                byte
+            };
+            upper = byte == b'-';
+            byte
+        })
+        .collect()
+}
+
+/// Serializes header fields as `Name: value` lines, each terminated by CRLF.
+///
+/// Names are written lowercase unless a [`PreserveCase`] table is given, which a codec
+/// offering a `preserve_case` option would take from the message extensions.
+pub fn write_headers(headers: &HeaderMap, case: Option<&PreserveCase>, out: &mut Vec<u8>) {
+    for (name, value) in headers {
+        match case {
+            Some(case) => out.extend_from_slice(&case.spelling(name)),
+            None => out.extend_from_slice(name.as_str().as_bytes()),
+        }
+        out.extend_from_slice(b": ");
+        out.extend_from_slice(value.as_bytes());
+        out.extend_from_slice(b"\r\n");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Framing::*;
+    const HTTP_10: Version = Version::HTTP_10;
+    const HTTP_11: Version = Version::HTTP_11;
+
+    fn connection(value: Option<&'static str>) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        if let Some(value) = value {
+            headers.insert(header::CONNECTION, HeaderValue::from_static(value));
+        }
+        headers
+    }
+
+    fn decide(
+        version: Version,
+        request: Option<&'static str>,
+        response: Option<&'static str>,
+        framing: Framing,
+        draining: bool,
+    ) -> (bool, Option<&'static str>) {
+        let decision = ConnectionPolicy::decide(
+            version,
+            &connection(request),
+            &connection(response),
+            framing,
+            draining,
+        );
+        let header = decision
+            .connection
+            .map(|value| match value.to_str().unwrap() {
+                "close" => "close",
+                "keep-alive" => "keep-alive",
+                other => panic!("unexpected Connection header: {other}"),
+            });
+        (decision.reuse, header)
+    }
+
+    #[test]
+    fn http10_closes_unless_keep_alive_is_requested() {
+        assert_eq!(
+            decide(HTTP_10, None, None, ContentLength, false),
+            (false, Some("close"))
+        );
+        assert_eq!(
+            decide(HTTP_10, Some("Keep-Alive"), None, ContentLength, false),
+            (true, Some("keep-alive"))
+        );
+        assert_eq!(
+            decide(
+                HTTP_10,
+                Some("keep-alive"),
+                Some("keep-alive"),
+                ContentLength,
+                false
+            ),
+            (true, None)
+        );
+    }
+
+    #[test]
+    fn http10_cannot_delimit_chunked_bodies() {
+        assert_eq!(
+            decide(HTTP_10, Some("keep-alive"), None, Chunked, false),
+            (false, Some("close"))
+        );
+        assert_eq!(Framing::select(HTTP_10, None), CloseDelimited);
+        assert_eq!(Framing::select(HTTP_11, None), Chunked);
+        assert_eq!(Framing::select(HTTP_10, Some(0)), ContentLength);
+    }
+
+    #[test]
+    fn http11_is_persistent_by_default() {
+        assert_eq!(
+            decide(HTTP_11, None, None, ContentLength, false),
+            (true, None)
+        );
+        assert_eq!(decide(HTTP_11, None, None, Chunked, false), (true, None));
+    }
+
+    #[test]
+    fn close_on_either_side() {
+        assert_eq!(
+            decide(HTTP_11, Some("TE, Close"), None, ContentLength, false),
+            (false, Some("close"))
+        );
+        // The response already announces the decision.
+        assert_eq!(
+            decide(HTTP_11, None, Some("close"), ContentLength, false),
+            (false, None)
+        );
+        assert_eq!(
+            decide(
+                HTTP_10,
+                Some("keep-alive"),
+                Some("close"),
+                ContentLength,
+                false
+            ),
+            (false, None)
+        );
+    }
+
+    #[test]
+    fn close_delimited_body_forces_close() {
+        assert_eq!(
+            decide(HTTP_11, None, None, CloseDelimited, false),
+            (false, Some("close"))
+        );
+        assert_eq!(
+            decide(HTTP_10, Some("keep-alive"), None, CloseDelimited, false),
+            (false, Some("close"))
+        );
+    }
+
+    #[test]
+    fn draining_overrides_keep_alive() {
+        assert_eq!(
+            decide(HTTP_11, None, None, ContentLength, true),
+            (false, Some("close"))
+        );
+        // A contradicting header set by the application is replaced.
+        let decision = ConnectionPolicy::decide(
+            HTTP_10,
+            &connection(Some("keep-alive")),
+            &connection(Some("keep-alive")),
+            ContentLength,
+            true,
+        );
+        let mut headers = connection(Some("keep-alive"));
+        decision.apply(&mut headers);
+        assert!(!decision.reuse);
+        assert_eq!(headers[header::CONNECTION], "close");
+    }
+
+    #[test]
+    fn preserves_recorded_spellings() {
+        use crate::{Body, Request, RequestExt};
+
+        // Parsing: record each name as received and keep the table with the request.
+        let mut case = PreserveCase::new();
+        let mut request = Request::new(Body::empty());
+        for (raw, value) in [
+            (&b"HOST"[..], "example.com"),
+            (b"x-Custom-ID", "7"),
+            (b"X-CUSTOM-id", "8"),
+        ] {
+            let name = case.record(raw).unwrap();
+            request
+                .headers_mut()
+                .append(name, HeaderValue::from_static(value));
+        }
+        request.extensions_mut().insert(case);
+
+        // Matching is unaffected.
+        assert_eq!(request.headers()["host"], "example.com");
+        assert_eq!(request.headers().get_all("x-custom-id").iter().count(), 2);
+
+        // Middleware adds a header afterwards.
+        request.headers_mut().insert(
+            HeaderName::from_static("x-forwarded-proto"),
+            HeaderValue::from_static("https"),
+        );
+
+        let mut out = Vec::new();
+        write_headers(
+            request.headers(),
+            request.extension::<PreserveCase>(),
+            &mut out,
+        );
+        assert_eq!(
+            core::str::from_utf8(&out).unwrap(),
+            "HOST: example.com\r\nx-Custom-ID: 7\r\nx-Custom-ID: 8\r\nX-Forwarded-Proto: https\r\n"
+        );
+
+        let lower = PreserveCase::new().with_fallback(HeaderCase::Lower);
+        let name = HeaderName::from_static("x-forwarded-proto");
+        assert_eq!(&*lower.spelling(&name), b"x-forwarded-proto");
+
+        out.clear();
+        write_headers(request.headers(), None, &mut out);
+        assert!(out.starts_with(b"host: example.com\r\n"));
+    }
+}
@@ -0,0 +1,1048 @@
+//! Well-known extension header names and typed header values.
+//!
+//! The [`http::header`](crate::header) module covers the standard registry, but a handful of
+//! widely used extension headers are missing from it. This module provides strongly-named
+//! constants for every non-standard header that http-kit emits or reads, so applications
+//! interoperating with the bundled middleware can refer to the exact same names.
+//!
+//! It also provides parsed forms of common headers, [`Accept`], [`Authorization`],
+//! [`ContentDisposition`] and [`ETag`] with its [`ETagMatch`] preconditions, which
+//! back the typed accessors of [`RequestExt`](crate::RequestExt) and
+//! [`ResponseExt`](crate::ResponseExt).
+//!
+//! # Examples
+//!
+//! ```rust
+//! use http_kit::{headers, Request, Body};
+//!
+//! let mut request = Request::new(Body::empty());
+//! request
+//!     .headers_mut()
+//!     .insert(headers::X_REQUEST_ID, "7f3c".parse().unwrap());
+//!
+//! assert_eq!(request.headers()[headers::X_REQUEST_ID], "7f3c");
+//! ```
+
+use alloc::{
+    borrow::ToOwned,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt;
+
+use bytestr::ByteStr;
+use http::{header, HeaderMap, HeaderName, HeaderValue};
+use mime::Mime;
+
+use crate::{base64, percent};
+
+macro_rules! extension_headers {
+    ($($(#[$meta:meta])* $name:ident => $value:literal;)*) => {
+        $(
+            $(#[$meta])*
+            pub const $name: HeaderName = HeaderName::from_static($value);
+        )*
+
+        #[cfg(test)]
+        fn all() -> alloc::vec::Vec<HeaderName> {
+            alloc::vec![$($name),*]
+        }
+    };
+}
+
+extension_headers! {
+    /// `X-Request-Id`, a per-request correlation identifier.
+    X_REQUEST_ID => "x-request-id";
+    /// `X-RateLimit-Limit`, the request quota of the current window.
+    X_RATELIMIT_LIMIT => "x-ratelimit-limit";
+    /// `X-RateLimit-Remaining`, the number of requests left in the current window.
+    X_RATELIMIT_REMAINING => "x-ratelimit-remaining";
+    /// `X-RateLimit-Reset`, the number of seconds until the quota is replenished.
+    X_RATELIMIT_RESET => "x-ratelimit-reset";
+    /// `X-Cache`, reports whether a response was served from a cache (`HIT`/`MISS`).
+    X_CACHE => "x-cache";
+    /// `Idempotency-Key`, a client-chosen key making non-idempotent requests safe to retry.
+    IDEMPOTENCY_KEY => "idempotency-key";
+    /// `Server-Timing`, server-side performance metrics for the response.
+    SERVER_TIMING => "server-timing";
+    /// `X-Accel-Buffering`, instructs reverse proxies such as nginx to disable buffering.
+    X_ACCEL_BUFFERING => "x-accel-buffering";
+    /// `Last-Event-ID`, the id of the last Server-Sent Event seen by a reconnecting client.
+    LAST_EVENT_ID => "last-event-id";
+    /// `Deprecation`, signals that the requested resource is deprecated.
+    DEPRECATION => "deprecation";
+    /// `Sunset`, the date after which the requested resource will stop responding.
+    SUNSET => "sunset";
+    /// `X-Forwarded-For`, the chain of client addresses seen by proxies.
+    X_FORWARDED_FOR => "x-forwarded-for";
+    /// `X-Forwarded-Proto`, the scheme used by the client to reach the proxy.
+    X_FORWARDED_PROTO => "x-forwarded-proto";
+    /// `X-Forwarded-Host`, the host requested by the client.
+    X_FORWARDED_HOST => "x-forwarded-host";
+}
+
+// Parses the first `Content-Length` value, ignoring malformed ones.
+pub(crate) fn content_length(headers: &HeaderMap) -> Option<u64> {
+    headers
+        .get(header::CONTENT_LENGTH)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+// Parses the `Content-Type` header, if present and a valid media type.
+pub(crate) fn content_type(headers: &HeaderMap) -> Option<Mime> {
+    headers
+        .get(header::CONTENT_TYPE)?
+        .to_str()
+        .ok()?
+        .parse()
+        .ok()
+}
+
+// Sets `Content-Type` to `mime` unless the header is already present.
+pub(crate) fn set_default_content_type(headers: &mut HeaderMap, mime: Option<&Mime>) {
+    if headers.contains_key(header::CONTENT_TYPE) {
+        return;
+    }
+    // The parser only accepts bytes that header values allow.
+    if let Some(value) = mime.and_then(|mime| HeaderValue::from_str(mime.as_ref()).ok()) {
+        headers.insert(header::CONTENT_TYPE, value);
+    }
+}
+
+// Checks that `Content-Type` is a JSON type, that is with a `json` subtype or a `+json`
+// suffix, and not in a charset other than UTF-8.
+#[cfg(feature = "json")]
+pub(crate) fn expect_json(headers: &HeaderMap) -> crate::Result<()> {
+    let Some(mime) = content_type(headers)
+        .filter(|mime| mime.subtype() == mime::JSON || mime.suffix() == Some(mime::JSON))
+    else {
+        return Err(unsupported_media_type("expected a JSON body".into()));
+    };
+    match mime.get_param(mime::CHARSET) {
+        Some(charset) if !charset.as_str().eq_ignore_ascii_case("utf-8") => Err(
+            unsupported_media_type(alloc::format!("expected UTF-8 JSON, not {charset}")),
+        ),
+        _ => Ok(()),
+    }
+}
+
+// Checks that `Content-Type` is `application/x-www-form-urlencoded`, with any parameters.
+#[cfg(feature = "form")]
+pub(crate) fn expect_form(headers: &HeaderMap) -> crate::Result<()> {
+    let is_form = content_type(headers).is_some_and(|mime| {
+        mime.type_() == mime::APPLICATION && mime.subtype() == mime::WWW_FORM_URLENCODED
+    });
+    if is_form {
+        Ok(())
+    } else {
+        Err(unsupported_media_type("expected a URL-encoded form".into()))
+    }
+}
+
+#[cfg(any(feature = "json", feature = "form"))]
+fn unsupported_media_type(message: String) -> crate::Error {
+    crate::Error::msg(message).set_status(http::StatusCode::UNSUPPORTED_MEDIA_TYPE)
+}
+
+// Appends a header, converting the name and the value first.
+pub(crate) fn try_append<K, V>(
+    headers: &mut HeaderMap,
+    name: K,
+    value: V,
+) -> Result<(), http::Error>
+where
+    HeaderName: TryFrom<K>,
+    <HeaderName as TryFrom<K>>::Error: Into<http::Error>,
+    HeaderValue: TryFrom<V>,
+    <HeaderValue as TryFrom<V>>::Error: Into<http::Error>,
+{
+    let name = HeaderName::try_from(name).map_err(Into::into)?;
+    let value = HeaderValue::try_from(value).map_err(Into::into)?;
+    headers.append(name, value);
+    Ok(())
+}
+
+/// A parsed `Accept` header: media ranges weighted by their q-values.
+///
+/// Malformed media ranges are skipped and malformed q-values count as `1`. A missing
+/// header accepts everything, like `*/*`.
+///
+/// # Examples
+///
+/// ```rust
+/// use http_kit::headers::Accept;
+///
+/// let accept = Accept::parse("text/html;q=0.8, application/json, */*;q=0");
+/// assert_eq!(accept.quality(&mime::APPLICATION_JSON), 1.0);
+/// assert_eq!(accept.quality(&mime::TEXT_HTML), 0.8);
+/// assert!(!accept.accepts(&mime::IMAGE_PNG));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Accept {
+    ranges: Vec<(Mime, f32)>,
+}
+
+impl Accept {
+    /// Parses the value of an `Accept` header.
+    pub fn parse(value: &str) -> Self {
+        let ranges = value
+            .split(',')
+            .filter_map(|range| range.trim().parse::<Mime>().ok())
+            .map(|range| {
+                let quality = range
+                    .get_param("q")
+                    .and_then(|q| q.as_str().parse::<f32>().ok())
+                    .filter(|q| (0.0..=1.0).contains(q))
+                    .unwrap_or(1.0);
+                (range, quality)
+            })
+            .collect();
+        Self { ranges }
+    }
+
+    /// Reads the `Accept` header, combining repeated fields. Without the header, every
+    /// media type is accepted.
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        let mut values = headers
+            .get_all(header::ACCEPT)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .peekable();
+        if values.peek().is_none() {
+            return Self::parse("*/*");
+        }
+        let joined: Vec<&str> = values.collect();
+        Self::parse(&joined.join(","))
+    }
+
+    /// Returns the q-value of `mime`, from the most specific matching media range, or
+    /// `0` when no range matches.
+    ///
+    /// `text/html;level=1` is more specific than `text/html`, which is more specific
+    /// than `text/*`, which is more specific than `*/*`.
+    pub fn quality(&self, mime: &Mime) -> f32 {
+        self.rank(mime).map_or(0.0, |(_, quality)| quality)
+    }
+
+    // Returns the specificity and q-value of the most specific range matching `mime`.
+    fn rank(&self, mime: &Mime) -> Option<((u8, usize), f32)> {
+        self.ranges
+            .iter()
+            .filter_map(|(range, quality)| Some((specificity(range, mime)?, *quality)))
+            .max_by_key(|(specificity, _)| *specificity)
+    }
+
+    /// Returns the best of the `supported` media types, or `None` if none is
+    /// acceptable.
+    ///
+    /// The highest q-value wins. Ties go to the type matched by the most specific range,
+    /// so `text/html, */*` prefers `text/html` over `application/json`, and then to the
+    /// type listed first in `supported`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use http_kit::headers::Accept;
+    ///
+    /// let supported = [mime::APPLICATION_JSON, mime::TEXT_HTML];
+    /// let accept = Accept::parse("text/html, */*;q=0.8");
+    /// assert_eq!(accept.preferred(&supported), Some(&mime::TEXT_HTML));
+    /// assert_eq!(Accept::parse("image/*").preferred(&supported), None);
+    /// ```
+    pub fn preferred<'a>(&self, supported: &'a [Mime]) -> Option<&'a Mime> {
+        let mut best: Option<(&Mime, (u8, usize), f32)> = None;
+        for mime in supported {
+            let Some((specificity, quality)) = self.rank(mime) else {
+                continue;
+            };
+            let better = match best {
+                _ if quality <= 0.0 => false,
+                None => true,
+                Some((_, best_specificity, best_quality)) => {
+                    quality > best_quality
+                        || (quality == best_quality && specificity > best_specificity)
+                }
+            };
+            if better {
+                best = Some((mime, specificity, quality));
+            }
+        }
+        best.map(|(mime, _, _)| mime)
+    }
+
+    /// Returns whether `mime` is acceptable, that is whether its q-value is above `0`.
+    pub fn accepts(&self, mime: &Mime) -> bool {
+        self.quality(mime) > 0.0
+    }
+}
+
+// Ranks how specifically `range` matches `mime`, or `None` if it doesn't.
+fn specificity(range: &Mime, mime: &Mime) -> Option<(u8, usize)> {
+    let rank = if range.type_() == mime::STAR && range.subtype() == mime::STAR {
+        0
+    } else if range.type_() != mime.type_() {
+        return None;
+    } else if range.subtype() == mime::STAR {
+        1
+    } else if range.subtype() == mime.subtype() && range.suffix() == mime.suffix() {
+        2
+    } else {
+        return None;
+    };
+    let mut params = 0;
+    for (name, value) in range.params().filter(|(name, _)| name.as_str() != "q") {
+        if mime.get_param(name) != Some(value) {
+            return None;
+        }
+        params += 1;
+    }
+    Some((rank, params))
+}
+
+/// Credentials of an `Authorization` header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Authorization {
+    /// A bearer token (RFC 6750).
+    Bearer(ByteStr),
+    /// Basic credentials (RFC 7617), decoded from base64.
+    Basic {
+        /// The user id, before the first colon.
+        username: ByteStr,
+        /// The password, which may contain colons.
+        password: ByteStr,
+    },
+    /// Credentials of any other scheme, including the scheme name.
+    Other(ByteStr),
+}
+
+impl Authorization {
+    /// Parses an `Authorization` header value.
+    ///
+    /// Scheme names are case-insensitive. Returns `None` for empty bearer tokens and for
+    /// basic credentials that are not base64-encoded UTF-8 containing a colon.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use http_kit::headers::Authorization;
+    /// use http::HeaderValue;
+    ///
+    /// let value = HeaderValue::from_static("Basic QWxhZGRpbjpvcGVuIHNlc2FtZQ==");
+    /// let Some(Authorization::Basic { username, password }) = Authorization::parse(&value) else {
+    ///     panic!("expected basic credentials");
+    /// };
+    /// assert_eq!(username, "Aladdin");
+    /// assert_eq!(password, "open sesame");
+    /// ```
+    pub fn parse(value: &HeaderValue) -> Option<Self> {
+        let value = value.to_str().ok()?.trim();
+        let (scheme, credentials) = value.split_once(' ').unwrap_or((value, ""));
+        let credentials = credentials.trim();
+        if scheme.eq_ignore_ascii_case("bearer") {
+            if credentials.is_empty() {
+                return None;
+            }
+            Some(Self::Bearer(ByteStr::from(String::from(credentials))))
+        } else if scheme.eq_ignore_ascii_case("basic") {
+            let decoded = String::from_utf8(base64::decode(credentials).ok()?).ok()?;
+            let (username, password) = decoded.split_once(':')?;
+            Some(Self::Basic {
+                username: ByteStr::from(String::from(username)),
+                password: ByteStr::from(String::from(password)),
+            })
+        } else {
+            Some(Self::Other(ByteStr::from(String::from(value))))
+        }
+    }
+}
+
+/// An entity tag (RFC 9110, section 8.8.3), the validator of an `ETag` header.
+///
+/// The derived equality compares tags exactly; preconditions use
+/// [`ETag::strong_eq`] or [`ETag::weak_eq`] instead.
+///
+/// # Examples
+///
+/// ```rust
+/// use http_kit::headers::ETag;
+///
+/// let tag = ETag::parse(r#"W/"v2""#).unwrap();
+/// assert!(tag.is_weak());
+/// assert_eq!(tag.tag(), "v2");
+/// assert_eq!(tag.to_string(), r#"W/"v2""#);
+/// assert!(tag.weak_eq(&ETag::strong("v2").unwrap()));
+/// assert!(!tag.strong_eq(&ETag::strong("v2").unwrap()));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ETag {
+    weak: bool,
+    tag: ByteStr,
+}
+
+impl ETag {
+    /// Creates a strong tag from its opaque value, without quotes.
+    ///
+    /// Returns `None` if `tag` contains quotes, spaces or control characters.
+    pub fn strong(tag: &str) -> Option<Self> {
+        Self::new(false, tag)
+    }
+
+    /// Creates a weak tag from its opaque value, without quotes.
+    ///
+    /// Returns `None` if `tag` contains quotes, spaces or control characters.
+    pub fn weak(tag: &str) -> Option<Self> {
+        Self::new(true, tag)
+    }
+
+    fn new(weak: bool, tag: &str) -> Option<Self> {
+        tag.bytes().all(is_etagc).then(|| Self {
+            weak,
+            tag: ByteStr::from(String::from(tag)),
+        })
+    }
+
+    /// Parses a quoted tag, such as `"xyzzy"` or `W/"xyzzy"`.
+    pub fn parse(value: &str) -> Option<Self> {
+        match parse_etag(value.trim()) {
+            Some((tag, "")) => Some(tag),
+            _ => None,
+        }
+    }
+
+    /// Returns whether the tag is weak.
+    pub fn is_weak(&self) -> bool {
+        self.weak
+    }
+
+    /// Returns the opaque value, without quotes.
+    pub fn tag(&self) -> &str {
+        &self.tag
+    }
+
+    /// Strong comparison: both tags are strong and have the same value.
+    pub fn strong_eq(&self, other: &Self) -> bool {
+        !self.weak && !other.weak && self.tag == other.tag
+    }
+
+    /// Weak comparison: both tags have the same value, whether weak or not.
+    pub fn weak_eq(&self, other: &Self) -> bool {
+        self.tag == other.tag
+    }
+}
+
+impl fmt::Display for ETag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.weak {
+            f.write_str("W/")?;
+        }
+        write!(f, "\"{}\"", self.tag)
+    }
+}
+
+// `etagc` of RFC 9110: visible ASCII except the double quote, and obs-text.
+fn is_etagc(byte: u8) -> bool {
+    byte == 0x21 || (0x23..=0x7e).contains(&byte) || byte >= 0x80
+}
+
+// Parses one tag at the start of `input`, returning it with the rest of the input.
+fn parse_etag(input: &str) -> Option<(ETag, &str)> {
+    let (weak, quoted) = match input.strip_prefix("W/") {
+        Some(rest) => (true, rest),
+        None => (false, input),
+    };
+    let (tag, rest) = quoted.strip_prefix('"')?.split_once('"')?;
+    Some((ETag::new(weak, tag)?, rest))
+}
+
+/// The condition of an `If-Match` or `If-None-Match` header.
+///
+/// # Examples
+///
+/// ```rust
+/// use http_kit::headers::{ETag, ETagMatch};
+///
+/// let condition = ETagMatch::parse(r#""a,b", W/"c""#).unwrap();
+/// assert!(condition.matches_weak(&ETag::strong("c").unwrap()));
+/// assert!(!condition.matches_strong(&ETag::strong("c").unwrap()));
+/// assert!(condition.matches_strong(&ETag::strong("a,b").unwrap()));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ETagMatch {
+    /// `*`, matching any current representation.
+    Any,
+    /// A list of tags, matching a representation that has one of them.
+    Tags(Vec<ETag>),
+}
+
+impl ETagMatch {
+    /// Parses a header value: `*` or a comma-separated list of tags.
+    ///
+    /// Tags may contain commas. Returns `None` if the value is malformed.
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        if value == "*" {
+            return Some(Self::Any);
+        }
+        let mut tags = Vec::new();
+        let mut rest = value;
+        loop {
+            rest = rest.trim_start_matches([' ', '\t', ',']);
+            if rest.is_empty() {
+                break;
+            }
+            let (tag, after) = parse_etag(rest)?;
+            tags.push(tag);
+            rest = after.trim_start_matches([' ', '\t']);
+            if !rest.is_empty() && !rest.starts_with(',') {
+                return None;
+            }
+        }
+        if tags.is_empty() {
+            return None;
+        }
+        Some(Self::Tags(tags))
+    }
+
+    /// Parses every `name` header of `headers` as one list.
+    ///
+    /// Returns `None` if there is no such header or one of them is malformed, so that
+    /// the precondition is ignored rather than misapplied.
+    pub fn from_headers(headers: &HeaderMap, name: &HeaderName) -> Option<Self> {
+        let mut tags = Vec::new();
+        let mut any = false;
+        for value in headers.get_all(name) {
+            match Self::parse(value.to_str().ok()?)? {
+                Self::Any => any = true,
+                Self::Tags(more) => tags.extend(more),
+            }
+        }
+        if any {
+            Some(Self::Any)
+        } else if tags.is_empty() {
+            None
+        } else {
+            Some(Self::Tags(tags))
+        }
+    }
+
+    /// Returns whether `tag` matches with the strong comparison, as `If-Match` requires.
+    pub fn matches_strong(&self, tag: &ETag) -> bool {
+        match self {
+            Self::Any => true,
+            Self::Tags(tags) => tags.iter().any(|candidate| candidate.strong_eq(tag)),
+        }
+    }
+
+    /// Returns whether `tag` matches with the weak comparison, as `If-None-Match`
+    /// requires.
+    pub fn matches_weak(&self, tag: &ETag) -> bool {
+        match self {
+            Self::Any => true,
+            Self::Tags(tags) => tags.iter().any(|candidate| candidate.weak_eq(tag)),
+        }
+    }
+}
+
+/// A `Content-Disposition` header (RFC 6266), telling whether a response is shown or
+/// downloaded, or naming a part of a `multipart/form-data` body (RFC 7578).
+///
+/// Filenames of `attachment` and `inline` dispositions are written twice when they are
+/// not plain ASCII: an ASCII `filename` fallback, where other characters become `_`,
+/// and the exact `filename*` in the UTF-8 encoding of RFC 8187. `form-data` parts don't
+/// use `filename*`; as browsers do, quotes and control characters in their names are
+/// percent-encoded instead. Either way, a name cannot break out of the header.
+///
+/// # Examples
+///
+/// ```rust
+/// use http_kit::headers::ContentDisposition;
+///
+/// let disposition = ContentDisposition::attachment("résumé.pdf");
+/// assert_eq!(
+///     disposition.to_string(),
+///     "attachment; filename=\"r_sum_.pdf\"; filename*=UTF-8''r%C3%A9sum%C3%A9.pdf"
+/// );
+///
+/// let parsed = ContentDisposition::parse(&disposition.to_string()).unwrap();
+/// assert!(parsed.is_attachment());
+/// assert_eq!(parsed.filename(), Some("résumé.pdf"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContentDisposition {
+    disposition: String,
+    name: Option<String>,
+    filename: Option<String>,
+}
+
+impl ContentDisposition {
+    /// Creates an `attachment` disposition, downloaded as `filename`.
+    pub fn attachment(filename: &str) -> Self {
+        Self {
+            disposition: "attachment".to_owned(),
+            name: None,
+            filename: Some(filename.to_owned()),
+        }
+    }
+
+    /// Creates an `inline` disposition, shown by the browser.
+    pub fn inline() -> Self {
+        Self {
+            disposition: "inline".to_owned(),
+            name: None,
+            filename: None,
+        }
+    }
+
+    /// Creates the `form-data` disposition of a multipart field.
+    pub fn form_data(name: &str, filename: Option<&str>) -> Self {
+        Self {
+            disposition: "form-data".to_owned(),
+            name: Some(name.to_owned()),
+            filename: filename.map(ToOwned::to_owned),
+        }
+    }
+
+    /// Parses a header value.
+    ///
+    /// The disposition type is lowercased. `filename*` takes precedence over
+    /// `filename`, and is decoded from UTF-8 or ISO-8859-1. Unknown parameters are
+    /// ignored. Returns `None` if the value is malformed.
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        let end = value.find(';').unwrap_or(value.len());
+        let disposition = value[..end].trim();
+        if disposition.is_empty() || !disposition.bytes().all(is_tchar) {
+            return None;
+        }
+
+        let (mut name, mut filename, mut extended) = (None, None, None);
+        let mut rest = &value[end..];
+        while let Some(param) = rest.strip_prefix(';') {
+            let param = param.trim_start();
+            if param.is_empty() {
+                rest = param;
+                break;
+            }
+            let (key, param) = param.split_once('=')?;
+            let (value, after) = match param.trim_start().strip_prefix('"') {
+                Some(quoted) => parse_quoted(quoted)?,
+                None => {
+                    let param = param.trim_start();
+                    let end = param.find(';').unwrap_or(param.len());
+                    (param[..end].trim_end().to_owned(), &param[end..])
+                }
+            };
+            let key = key.trim();
+            if key.eq_ignore_ascii_case("name") {
+                name = Some(value);
+            } else if key.eq_ignore_ascii_case("filename") {
+                filename = Some(value);
+            } else if key.eq_ignore_ascii_case("filename*") {
+                extended = Some(decode_ext_value(&value)?);
+            }
+            rest = after.trim_start();
+        }
+        if !rest.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            disposition: disposition.to_ascii_lowercase(),
+            name,
+            filename: extended.or(filename),
+        })
+    }
+
+    /// Returns the disposition type, lowercased, such as `attachment`.
+    pub fn disposition(&self) -> &str {
+        &self.disposition
+    }
+
+    /// Returns whether the disposition is `attachment`.
+    pub fn is_attachment(&self) -> bool {
+        self.disposition == "attachment"
+    }
+
+    /// Returns whether the disposition is `inline`.
+    pub fn is_inline(&self) -> bool {
+        self.disposition == "inline"
+    }
+
+    /// Returns whether the disposition is `form-data`.
+    pub fn is_form_data(&self) -> bool {
+        self.disposition == "form-data"
+    }
+
+    /// Returns the name of the form field.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Returns the filename, without any escaping.
+    pub fn filename(&self) -> Option<&str> {
+        self.filename.as_deref()
+    }
+
+    /// Returns the header value.
+    pub fn header_value(&self) -> HeaderValue {
+        HeaderValue::try_from(self.to_string()).expect("control characters are always escaped")
+    }
+}
+
+impl fmt::Display for ContentDisposition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.disposition)?;
+        if let Some(name) = &self.name {
+            write!(f, "; name=\"{}\"", FormEscaped(name))?;
+        }
+        let Some(filename) = &self.filename else {
+            return Ok(());
+        };
+        if self.is_form_data() {
+            return write!(f, "; filename=\"{}\"", FormEscaped(filename));
+        }
+        let fallback: String = filename
+            .chars()
+            .map(|c| match c {
+                ' '..='~' if !matches!(c, '"' | '\\' | '%') => c,
+                _ => '_',
+            })
+            .collect();
+        write!(f, "; filename=\"{fallback}\"")?;
+        if fallback != *filename {
+            f.write_str("; filename*=UTF-8''")?;
+            for &byte in filename.as_bytes() {
+                if byte.is_ascii_alphanumeric() || b"!#$&+-.^_`|~".contains(&byte) {
+                    write!(f, "{}", char::from(byte))?;
+                } else {
+                    write!(f, "%{byte:02X}")?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// Quotes and control characters percent-encoded, as browsers do in form-data names.
+struct FormEscaped<'a>(&'a str);
+
+impl fmt::Display for FormEscaped<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for c in self.0.chars() {
+            if c == '"' || c.is_ascii_control() {
+                write!(f, "%{:02X}", u32::from(c))?;
+            } else {
+                write!(f, "{c}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// `tchar` of RFC 9110: the characters of a token.
+pub(crate) fn is_tchar(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&byte)
+}
+
+// Parses the rest of a quoted string after its opening quote, returning its unescaped
+// content and the input after the closing quote.
+pub(crate) fn parse_quoted(input: &str) -> Option<(String, &str)> {
+    let mut value = String::new();
+    let mut chars = input.char_indices();
+    while let Some((at, c)) = chars.next() {
+        match c {
+            '"' => return Some((value, &input[at + 1..])),
+            '\\' => value.push(chars.next()?.1),
+            _ => value.push(c),
+        }
+    }
+    None
+}
+
+// Decodes an `ext-value` of RFC 8187: `charset'language'percent-encoded`.
+fn decode_ext_value(value: &str) -> Option<String> {
+    let mut parts = value.splitn(3, '\'');
+    let (charset, _language, encoded) = (parts.next()?, parts.next()?, parts.next()?);
+    let bytes = percent::decode(encoded.as_bytes(), false)?;
+    if charset.eq_ignore_ascii_case("utf-8") {
+        String::from_utf8(bytes).ok()
+    } else if charset.eq_ignore_ascii_case("iso-8859-1") {
+        Some(bytes.into_iter().map(char::from).collect())
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extension_headers_are_valid_and_unique() {
+        let mut seen = Vec::new();
+        for name in &all() {
+            let s = name.as_str();
+            assert!(
+                !s.bytes().any(|b| b.is_ascii_uppercase()),
+                "{s} is not lowercase"
+            );
+            assert_eq!(&HeaderName::from_bytes(s.as_bytes()).unwrap(), name);
+            assert!(!seen.contains(&s), "{s} is declared twice");
+            seen.push(s);
+        }
+    }
+
+    #[test]
+    fn accept_prefers_higher_quality() {
+        let accept = Accept::parse("text/html;q=0.8, application/json");
+        assert!(accept.quality(&mime::APPLICATION_JSON) > accept.quality(&mime::TEXT_HTML));
+        assert!(accept.accepts(&mime::TEXT_HTML));
+        assert!(!accept.accepts(&mime::TEXT_PLAIN));
+    }
+
+    #[test]
+    fn accept_uses_most_specific_range() {
+        // RFC 9110, section 12.5.1.
+        let accept = Accept::parse(
+            "text/*;q=0.3, text/plain;q=0.7, text/plain;format=flowed, text/plain;format=fixed;q=0.4, */*;q=0.5",
+        );
+        let quality = |mime: &str| accept.quality(&mime.parse().unwrap());
+        assert_eq!(quality("text/plain;format=flowed"), 1.0);
+        assert_eq!(quality("text/plain"), 0.7);
+        assert_eq!(quality("text/html"), 0.3);
+        assert_eq!(quality("image/jpeg"), 0.5);
+        assert_eq!(quality("text/plain;format=fixed"), 0.4);
+        assert_eq!(quality("text/html;level=3"), 0.3);
+    }
+
+    #[test]
+    fn accept_edge_cases() {
+        let accept = Accept::parse("application/json;q=oops, text/html;q=0, ???");
+        assert_eq!(accept.quality(&mime::APPLICATION_JSON), 1.0);
+        assert!(!accept.accepts(&mime::TEXT_HTML));
+
+        let missing = Accept::from_headers(&HeaderMap::new());
+        assert!(missing.accepts(&mime::IMAGE_PNG));
+
+        let mut headers = HeaderMap::new();
+        headers.append(header::ACCEPT, HeaderValue::from_static("text/html"));
+        headers.append(header::ACCEPT, HeaderValue::from_static("image/*;q=0.5"));
+        let repeated = Accept::from_headers(&headers);
+        assert_eq!(repeated.quality(&mime::IMAGE_PNG), 0.5);
+        assert!(!repeated.accepts(&mime::APPLICATION_JSON));
+    }
+
+    #[test]
+    fn accept_picks_preferred() {
+        let parse = |mimes: &[&str]| -> Vec<Mime> {
+            mimes.iter().map(|mime| mime.parse().unwrap()).collect()
+        };
+
+        // RFC 9110, section 12.5.1.
+        let accept = Accept::parse("audio/*; q=0.2, audio/basic");
+        let supported = parse(&["audio/ogg", "audio/basic"]);
+        assert_eq!(accept.preferred(&supported), Some(&supported[1]));
+
+        let accept = Accept::parse("text/plain; q=0.5, text/html, text/x-dvi; q=0.8, text/x-c");
+        let supported = parse(&["text/plain", "text/x-dvi", "text/x-c", "text/html"]);
+        assert_eq!(accept.preferred(&supported), Some(&supported[2]));
+        assert_eq!(accept.preferred(&supported[..2]), Some(&supported[1]));
+
+        let accept = Accept::parse(
+            "text/*;q=0.3, text/plain;q=0.7, text/plain;format=flowed, text/plain;format=fixed;q=0.4, */*;q=0.5",
+        );
+        let supported = parse(&["text/html", "image/jpeg", "text/plain;format=fixed"]);
+        assert_eq!(accept.preferred(&supported), Some(&supported[1]));
+
+        // Equal q-values go to the more specific match, then to the server's order.
+        let supported = [mime::APPLICATION_JSON, mime::TEXT_HTML];
+        let accept = Accept::parse("*/*, text/html");
+        assert_eq!(accept.preferred(&supported), Some(&mime::TEXT_HTML));
+        let accept = Accept::from_headers(&HeaderMap::new());
+        assert_eq!(accept.preferred(&supported), Some(&mime::APPLICATION_JSON));
+        let accept = Accept::parse("text/html;q=oops, application/json;q=0");
+        assert_eq!(accept.preferred(&supported), Some(&mime::TEXT_HTML));
+        assert_eq!(Accept::parse("image/png").preferred(&supported), None);
+    }
+
+    #[test]
+    fn authorization_schemes() {
+        let parse = |value| Authorization::parse(&HeaderValue::from_static(value));
+        assert_eq!(
+            parse("bearer abc.def"),
+            Some(Authorization::Bearer(ByteStr::from_static("abc.def")))
+        );
+        assert_eq!(
+            parse("Basic dXNlcjpwYTpzcw=="),
+            Some(Authorization::Basic {
+                username: ByteStr::from_static("user"),
+                password: ByteStr::from_static("pa:ss"),
+            })
+        );
+        assert_eq!(
+            parse("Digest username=\"u\""),
+            Some(Authorization::Other(ByteStr::from_static(
+                "Digest username=\"u\""
+            )))
+        );
+        assert_eq!(parse("Bearer "), None);
+        assert_eq!(parse("Basic !!!"), None);
+        assert_eq!(parse("Basic dXNlcg=="), None);
+    }
+
+    #[test]
+    fn content_length_ignores_invalid_values() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(content_length(&headers), None);
+        headers.insert(header::CONTENT_LENGTH, HeaderValue::from_static("42"));
+        assert_eq!(content_length(&headers), Some(42));
+        headers.insert(header::CONTENT_LENGTH, HeaderValue::from_static("-1"));
+        assert_eq!(content_length(&headers), None);
+    }
+
+    #[test]
+    fn etag_comparison() {
+        // The examples of RFC 9110, section 8.8.3.2.
+        let strong = |tag| ETag::strong(tag).unwrap();
+        let weak = |tag| ETag::weak(tag).unwrap();
+        let cases = [
+            (weak("1"), weak("1"), false, true),
+            (weak("1"), weak("2"), false, false),
+            (weak("1"), strong("1"), false, true),
+            (strong("1"), strong("1"), true, true),
+        ];
+        for (left, right, strong_eq, weak_eq) in cases {
+            assert_eq!(left.strong_eq(&right), strong_eq, "{left} {right}");
+            assert_eq!(left.weak_eq(&right), weak_eq, "{left} {right}");
+        }
+
+        assert_eq!(ETag::parse(r#" "xyzzy" "#), Some(strong("xyzzy")));
+        assert_eq!(ETag::parse(r#"W/"""#), Some(weak("")));
+        for invalid in [
+            "xyzzy",
+            r#""xy"zy""#,
+            r#"w/"xyzzy""#,
+            r#""xyzzy"#,
+            r#""a b""#,
+        ] {
+            assert_eq!(ETag::parse(invalid), None, "{invalid}");
+        }
+        assert_eq!(ETag::strong("a\"b"), None);
+    }
+
+    #[test]
+    fn etag_match_lists() {
+        let strong = |tag| ETag::strong(tag).unwrap();
+        let list = ETagMatch::parse(r#""xyzzy", "r2d2xxxx", W/"c3piozzzz""#).unwrap();
+        assert!(list.matches_strong(&strong("r2d2xxxx")));
+        assert!(!list.matches_strong(&strong("c3piozzzz")));
+        assert!(list.matches_weak(&strong("c3piozzzz")));
+        assert!(list.matches_weak(&ETag::weak("xyzzy").unwrap()));
+        assert!(!list.matches_weak(&strong("other")));
+
+        for invalid in ["", ",", r#""a" "b""#, r#""a", b"#, "*, \"a\""] {
+            assert_eq!(ETagMatch::parse(invalid), None, "{invalid}");
+        }
+
+        let mut headers = HeaderMap::new();
+        assert_eq!(ETagMatch::from_headers(&headers, &header::IF_MATCH), None);
+        headers.append(header::IF_MATCH, HeaderValue::from_static(r#""a""#));
+        headers.append(header::IF_MATCH, HeaderValue::from_static(r#""b",,"#));
+        assert_eq!(
+            ETagMatch::from_headers(&headers, &header::IF_MATCH),
+            Some(ETagMatch::Tags(alloc::vec![strong("a"), strong("b")]))
+        );
+        headers.append(header::IF_MATCH, HeaderValue::from_static("bogus"));
+        assert_eq!(ETagMatch::from_headers(&headers, &header::IF_MATCH), None);
+    }
+
+    #[test]
+    fn etag_wildcard() {
+        let any = ETagMatch::parse(" * ").unwrap();
+        assert_eq!(any, ETagMatch::Any);
+        assert!(any.matches_strong(&ETag::weak("w").unwrap()));
+        assert!(any.matches_weak(&ETag::strong("s").unwrap()));
+
+        let mut headers = HeaderMap::new();
+        headers.append(header::IF_NONE_MATCH, HeaderValue::from_static(r#""a""#));
+        headers.append(header::IF_NONE_MATCH, HeaderValue::from_static("*"));
+        assert_eq!(
+            ETagMatch::from_headers(&headers, &header::IF_NONE_MATCH),
+            Some(ETagMatch::Any)
+        );
+    }
+
+    #[test]
+    fn content_disposition_escapes_filenames() {
+        let cases = [
+            ("report.pdf", "attachment; filename=\"report.pdf\""),
+            (
+                "say \"hi\".txt",
+                "attachment; filename=\"say _hi_.txt\"; filename*=UTF-8''say%20%22hi%22.txt",
+            ),
+            (
+                "a\\b.txt",
+                "attachment; filename=\"a_b.txt\"; filename*=UTF-8''a%5Cb.txt",
+            ),
+            (
+                "x\r\nSet-Cookie: a=b",
+                "attachment; filename=\"x__Set-Cookie: a=b\"; filename*=UTF-8''x%0D%0ASet-Cookie%3A%20a%3Db",
+            ),
+            (
+                "\u{1F4C4} 100%.txt",
+                "attachment; filename=\"_ 100_.txt\"; filename*=UTF-8''%F0%9F%93%84%20100%25.txt",
+            ),
+        ];
+        for (filename, expected) in cases {
+            let disposition = ContentDisposition::attachment(filename);
+            assert_eq!(disposition.to_string(), expected);
+            assert_eq!(disposition.header_value(), expected);
+            let parsed = ContentDisposition::parse(expected).unwrap();
+            assert_eq!(parsed, disposition);
+        }
+
+        assert_eq!(ContentDisposition::inline().to_string(), "inline");
+        let field = ContentDisposition::form_data("note\r\n\"x\"", Some("a \"b\".bin"));
+        assert_eq!(
+            field.to_string(),
+            "form-data; name=\"note%0D%0A%22x%22\"; filename=\"a %22b%22.bin\""
+        );
+    }
+
+    #[test]
+    fn content_disposition_parsing() {
+        let parsed =
+            ContentDisposition::parse("Attachment ; FileName = \"a \\\"b\\\".txt\" ; size=12")
+                .unwrap();
+        assert!(parsed.is_attachment());
+        assert_eq!(parsed.disposition(), "attachment");
+        assert_eq!(parsed.filename(), Some("a \"b\".txt"));
+
+        let parsed = ContentDisposition::parse(
+            "attachment; filename*=iso-8859-1'en'caf%E9.txt; filename=cafe.txt",
+        )
+        .unwrap();
+        assert_eq!(parsed.filename(), Some("caf\u{e9}.txt"));
+
+        let parsed = ContentDisposition::parse("form-data; name=field;").unwrap();
+        assert_eq!(parsed.name(), Some("field"));
+        assert_eq!(parsed.filename(), None);
+
+        for invalid in [
+            "",
+            "; filename=a",
+            "attach ment",
+            "attachment; filename",
+            "attachment; filename=\"open",
+            "attachment; filename=\"a\" junk",
+            "attachment; filename*=UTF-8''%FF",
+            "attachment; filename*=koi8-r''abc",
+        ] {
+            assert_eq!(ContentDisposition::parse(invalid), None, "{invalid}");
+        }
+    }
+}
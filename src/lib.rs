@@ -19,23 +19,59 @@
 //!
 //! - `json` - JSON serialization/deserialization via serde_json (enabled by default)
 //! - `form` - Form data handling via serde_urlencoded (enabled by default)
-//! - `fs` - File upload support with MIME type detection
+//! - `csv` - CSV bodies, streamed in both directions
+//! - `encoding` - Decoding of text bodies in legacy charsets via `encoding_rs`
+//! - `fs` - File bodies with MIME type detection; needs `std` as well
 //! - `mime` - MIME type parsing and manipulation
 //! - `http_body` - Implementation of http_body traits
 //! - `std` - Enable standard library support (enabled by default)
+//! - `cookie` - Cookie jars through the `cookie` crate (enabled by default)
+//! - `cookie-signed` - Signed and private cookies in the cookie jar middleware
+//! - `compression` - Response compression middleware with gzip and deflate
+//! - `compression-br` - Brotli support in the compression middleware
+//! - `stats` - Global instrumentation counters in the `stats` module, on targets with
+//!   64-bit atomics
+//! - `log` - A `log` crate sink for the logger middleware
+//! - `tracing` - A `tracing` sink for the logger middleware
+//! - `tower` - Adapters between endpoints and `tower` services
+//! - `test-util` - Scriptable upstream, mock endpoint and test client in the `test` module
 extern crate alloc;
 
+mod base64;
+#[cfg(feature = "std")]
+mod date;
+mod percent;
+#[cfg(feature = "ws")]
+mod sha1;
+
 #[macro_use]
 mod macros;
 
+#[doc(hidden)]
+pub mod __private {
+    pub use alloc::boxed::Box;
+}
+
 pub mod sse;
 
+pub mod convert;
 pub mod error;
+#[cfg(feature = "form")]
+pub mod form;
 pub use error::{BoxHttpError, Error, HttpError, Result, ResultExt};
 mod body;
 
 pub use body::Body;
+pub use body::BodyDataStream;
+pub use body::BodyReader;
+#[cfg(feature = "csv")]
+pub use body::CsvStream;
 pub use body::Error as BodyError;
+#[cfg(all(feature = "fs", feature = "std"))]
+pub use body::FileOptions;
+#[cfg(feature = "json")]
+pub use body::NdjsonStream;
+pub use body::{DataUrlError, DEFAULT_DATA_URL_LIMIT};
 
 pub mod middleware;
 #[doc(inline)]
@@ -46,10 +82,57 @@ pub mod endpoint;
 pub use endpoint::Endpoint;
 
 pub mod utils;
+
+pub mod builder;
+
+pub mod headers;
+
+pub mod forwarded;
+
+pub mod h1;
+
+pub mod limits;
+
+pub mod batch;
+
+pub mod multipart;
+
+pub mod params;
+
+#[cfg(feature = "std")]
+pub mod client;
+
+#[cfg(feature = "std")]
+pub mod presets;
+
+pub mod redact;
+
+pub mod router;
+
+pub mod upgrade;
+
+mod extension;
+mod request;
+pub use request::RequestExt;
+mod response;
+#[cfg(feature = "json")]
+pub use response::Json;
+pub use response::{IntoResponse, ResponseExt};
+
 /// A type alias for HTTP requests with a custom `Body` type.
+///
+/// Being an [`http::Request`], it provides `body`, `body_mut`, `map`, and
+/// `into_parts`/`from_parts` to split it into [`RequestParts`] and its body.
 pub type Request = http::Request<Body>;
 /// A type alias for HTTP responses with a custom `Body` type.
+///
+/// Being an [`http::Response`], it provides `body`, `body_mut`, `map`, and
+/// `into_parts`/`from_parts` to split it into [`ResponseParts`] and its body.
 pub type Response = http::Response<Body>;
+/// The head of a [`Request`]: method, URI, version, headers and extensions.
+pub type RequestParts = http::request::Parts;
+/// The head of a [`Response`]: status, version, headers and extensions.
+pub type ResponseParts = http::response::Parts;
 
 #[cfg(feature = "cookie")]
 pub use cookie;
@@ -57,4 +140,10 @@ pub use cookie;
 #[cfg(feature = "ws")]
 pub mod ws;
 
+#[cfg(feature = "test-util")]
+pub mod test;
+
+#[cfg(all(feature = "stats", target_has_atomic = "64"))]
+pub mod stats;
+
 pub use http::{header, method, uri, version, Extensions, Method, StatusCode, Uri, Version};
@@ -0,0 +1,241 @@
+//! Size limits applied to incoming requests.
+
+use core::fmt;
+
+use http::{HeaderMap, HeaderName, StatusCode};
+
+use crate::HttpError;
+
+/// Limits on the size of incoming requests.
+///
+/// The defaults (1 MiB bodies, 100 header fields, 8 KiB of headers) suit a typical
+/// JSON API; [`Limits::strict`] and [`Limits::relaxed`] provide tighter and looser
+/// presets. Limits are enforced by
+/// [`ValidateRequest`](crate::middleware::validate::ValidateRequest).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Limits {
+    /// Maximum request body size in bytes.
+    pub max_body_size: usize,
+
+    /// Maximum number of header fields.
+    pub max_header_count: usize,
+
+    /// Maximum size in bytes of a single header field (name and value).
+    pub max_header_size: usize,
+
+    /// Maximum total size in bytes of all header fields.
+    pub max_headers_size: usize,
+}
+
+const DEFAULT_MAX_BODY_SIZE: usize = 1 << 20;
+const DEFAULT_MAX_HEADER_COUNT: usize = 100;
+const DEFAULT_MAX_HEADERS_SIZE: usize = 8 << 10;
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+            max_header_count: DEFAULT_MAX_HEADER_COUNT,
+            max_header_size: DEFAULT_MAX_HEADERS_SIZE,
+            max_headers_size: DEFAULT_MAX_HEADERS_SIZE,
+        }
+    }
+}
+
+impl Limits {
+    /// Tight limits for small, internet-facing APIs: 64 KiB bodies, 50 header fields,
+    /// 4 KiB per header field and 8 KiB of headers in total.
+    pub const fn strict() -> Self {
+        Self {
+            max_body_size: 64 << 10,
+            max_header_count: 50,
+            max_header_size: 4 << 10,
+            max_headers_size: 8 << 10,
+        }
+    }
+
+    /// Loose limits for trusted clients or upload-heavy services: 16 MiB bodies, 200
+    /// header fields, 16 KiB per header field and 64 KiB of headers in total.
+    pub const fn relaxed() -> Self {
+        Self {
+            max_body_size: 16 << 20,
+            max_header_count: 200,
+            max_header_size: 16 << 10,
+            max_headers_size: 64 << 10,
+        }
+    }
+
+    /// Checks `headers` against the header limits.
+    ///
+    /// The size of a header field is the length of its name plus the length of its
+    /// value.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first limit that is exceeded.
+    pub fn check_headers(&self, headers: &HeaderMap) -> Result<(), HeaderLimitError> {
+        if headers.len() > self.max_header_count {
+            return Err(HeaderLimitError::TooManyFields {
+                max: self.max_header_count,
+            });
+        }
+        let mut total = 0usize;
+        for (name, value) in headers {
+            let size = name.as_str().len() + value.len();
+            if size > self.max_header_size {
+                return Err(HeaderLimitError::FieldTooLarge {
+                    name: name.clone(),
+                    max: self.max_header_size,
+                });
+            }
+            total += size;
+        }
+        if total > self.max_headers_size {
+            return Err(HeaderLimitError::TooLarge {
+                max: self.max_headers_size,
+            });
+        }
+        Ok(())
+    }
+
+    /// Override the maximum request body size in bytes.
+    ///
+    /// Defaults to 1 MiB.
+    #[must_use]
+    pub const fn with_max_body_size(mut self, max_body_size: usize) -> Self {
+        self.max_body_size = max_body_size;
+        self
+    }
+
+    /// Override the maximum number of header fields.
+    ///
+    /// Defaults to 100.
+    #[must_use]
+    pub const fn with_max_header_count(mut self, max_header_count: usize) -> Self {
+        self.max_header_count = max_header_count;
+        self
+    }
+
+    /// Override the maximum size of a single header field in bytes.
+    ///
+    /// Defaults to 8 KiB.
+    #[must_use]
+    pub const fn with_max_header_size(mut self, max_header_size: usize) -> Self {
+        self.max_header_size = max_header_size;
+        self
+    }
+
+    /// Override the maximum total size of all header fields in bytes.
+    ///
+    /// Defaults to 8 KiB.
+    #[must_use]
+    pub const fn with_max_headers_size(mut self, max_headers_size: usize) -> Self {
+        self.max_headers_size = max_headers_size;
+        self
+    }
+}
+
+/// Error returned when request headers exceed the [`Limits`].
+///
+/// Its status is `431 Request Header Fields Too Large`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum HeaderLimitError {
+    /// More header fields than [`Limits::max_header_count`].
+    TooManyFields {
+        /// The configured limit.
+        max: usize,
+    },
+    /// A header field larger than [`Limits::max_header_size`].
+    FieldTooLarge {
+        /// Name of the offending header.
+        name: HeaderName,
+        /// The configured limit.
+        max: usize,
+    },
+    /// Header fields larger than [`Limits::max_headers_size`] in total.
+    TooLarge {
+        /// The configured limit.
+        max: usize,
+    },
+}
+
+impl fmt::Display for HeaderLimitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooManyFields { max } => {
+                write!(f, "too many header fields (limit: {max})")
+            }
+            Self::FieldTooLarge { name, max } => {
+                write!(f, "header field `{name}` too large (limit: {max} bytes)")
+            }
+            Self::TooLarge { max } => {
+                write!(f, "header fields too large (limit: {max} bytes)")
+            }
+        }
+    }
+}
+
+impl core::error::Error for HeaderLimitError {}
+
+impl HttpError for HeaderLimitError {
+    fn status(&self) -> StatusCode {
+        StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+    use http::HeaderValue;
+
+    #[test]
+    fn names_the_tripped_limit() {
+        let limits = Limits::default()
+            .with_max_header_count(3)
+            .with_max_header_size(32)
+            .with_max_headers_size(48);
+
+        let mut headers = HeaderMap::new();
+        headers.insert("x-a", HeaderValue::from_static("0123456789abcdef0123"));
+        assert_eq!(limits.check_headers(&headers), Ok(()));
+
+        headers.insert(
+            "x-b",
+            HeaderValue::from_static("0123456789abcdef0123456789"),
+        );
+        assert_eq!(
+            limits.check_headers(&headers),
+            Err(HeaderLimitError::TooLarge { max: 48 })
+        );
+
+        headers.insert(
+            "x-c",
+            HeaderValue::from_static("0123456789abcdef0123456789abcdef"),
+        );
+        let error = limits.check_headers(&headers).unwrap_err();
+        assert_eq!(error.status(), StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE);
+        assert_eq!(
+            error.to_string(),
+            "header field `x-c` too large (limit: 32 bytes)"
+        );
+
+        headers.insert("x-d", HeaderValue::from_static(""));
+        assert_eq!(
+            limits.check_headers(&headers),
+            Err(HeaderLimitError::TooManyFields { max: 3 })
+        );
+    }
+
+    #[test]
+    fn presets_are_ordered() {
+        let (strict, default, relaxed) = (Limits::strict(), Limits::default(), Limits::relaxed());
+        assert!(strict.max_body_size < default.max_body_size);
+        assert!(default.max_body_size < relaxed.max_body_size);
+        assert!(strict.max_header_count < relaxed.max_header_count);
+        assert!(strict.max_headers_size <= default.max_headers_size);
+        assert!(default.max_headers_size < relaxed.max_headers_size);
+    }
+}
@@ -1,25 +1,23 @@
-macro_rules! impl_error {
-    ($ty:ident,$message:expr) => {
-        #[doc = concat!("The error type of `", stringify!($ty), "`.")]
-        #[derive(Debug)]
-        pub struct $ty {
-            _priv: (),
-        }
-
-        impl $ty {
-            pub(crate) fn new() -> Self {
-                Self { _priv: () }
-            }
-        }
-
-        impl core::fmt::Display for $ty {
-            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-                f.write_str($message)
-            }
-        }
-
-        impl core::error::Error for $ty {}
+// Bumps a `stats` counter; compiled out without the `stats` feature or on targets
+// without 64-bit atomics.
+macro_rules! stat {
+    ($counter:ident) => {
+        stat!($counter, 1)
     };
+    ($counter:ident if $condition:expr) => {{
+        #[cfg(all(feature = "stats", target_has_atomic = "64"))]
+        if $condition {
+            stat!($counter);
+        }
+        #[cfg(not(all(feature = "stats", target_has_atomic = "64")))]
+        let _ = $condition;
+    }};
+    ($counter:ident, $n:expr) => {{
+        #[cfg(all(feature = "stats", target_has_atomic = "64"))]
+        $crate::stats::COUNTERS
+            .$counter
+            .fetch_add($n as u64, core::sync::atomic::Ordering::Relaxed);
+    }};
 }
 
 /// Defines a zero-sized type that implements [`HttpError`] with a custom formatter.
@@ -40,6 +38,12 @@ macro_rules! http_error_fmt {
             pub const fn new() -> Self {
                 Self { _priv: () }
             }
+
+            /// Converts this error into an [`Error`]($crate::Error) with the same status.
+            #[allow(dead_code)]
+            pub fn into_error(self) -> $crate::Error {
+                $crate::Error::new(self).set_status($status)
+            }
         }
 
         impl ::core::default::Default for $name {
@@ -66,9 +70,9 @@ macro_rules! http_error_fmt {
     };
 }
 
-/// Defines a zero-sized [`HttpError`] type that renders as a static message.
+/// Defines an [`HttpError`] type.
 ///
-/// # Examples
+/// The short form defines a zero-sized type that renders as a static message:
 ///
 /// ```rust
 /// use http_kit::{http_error, StatusCode, HttpError};
@@ -84,6 +88,80 @@ macro_rules! http_error_fmt {
 /// assert_eq!(err.status(), StatusCode::NOT_FOUND);
 /// assert_eq!(err.to_string(), "resource not found");
 /// ```
+///
+/// The struct form defines a type with public fields, which the message can refer to
+/// by name. It accepts two optional clauses after the message:
+///
+/// - `source: Type` adds a private source error, attached with `with_source` and
+///   returned by [`Error::source`](core::error::Error::source);
+/// - `into: Path` implements `From<Name> for Path`, boxing the error, for every target
+///   implementing `From<Box<dyn Error + Send + Sync>>` such as
+///   [`BodyError`](crate::BodyError). The clause can be repeated.
+///
+/// The status can also be separated from the fields by a comma instead of `=>`. The
+/// generated `new` takes the fields in declaration order.
+///
+/// Like any other error type, the types generated by this macro convert into
+/// [`Error`](crate::Error) with `From`, which reports `500 Internal Server Error`. Their
+/// `into_error` method keeps their status instead.
+///
+/// ```rust
+/// use core::time::Duration;
+/// use http_kit::{http_error, BodyError, HttpError, StatusCode};
+///
+/// http_error!(
+///     /// No data arrived within the idle timeout.
+///     pub IdleTimeout { elapsed: Duration } => StatusCode::GATEWAY_TIMEOUT,
+///     "no data for {elapsed:?}",
+///     source: std::io::Error,
+///     into: BodyError,
+/// );
+///
+/// let err = IdleTimeout::new(Duration::from_secs(3))
+///     .with_source(std::io::ErrorKind::TimedOut.into());
+/// assert_eq!(err.elapsed, Duration::from_secs(3));
+/// assert_eq!(err.to_string(), "no data for 3s");
+/// assert_eq!(err.status(), StatusCode::GATEWAY_TIMEOUT);
+/// assert!(std::error::Error::source(&err).is_some());
+///
+/// let body_error: BodyError = err.into();
+/// assert!(matches!(body_error, BodyError::Other(_)));
+/// ```
+///
+/// ```rust
+/// use http_kit::{http_error, StatusCode};
+///
+/// http_error!(
+///     pub UpstreamFailed { url: String },
+///     StatusCode::BAD_GATEWAY,
+///     "upstream {url} failed",
+///     source: std::io::Error,
+/// );
+///
+/// let err = UpstreamFailed::new("http://backend".into())
+///     .with_source(std::io::ErrorKind::ConnectionRefused.into());
+/// assert_eq!(err.to_string(), "upstream http://backend failed");
+///
+/// let err = err.into_error();
+/// assert_eq!(err.to_string(), "upstream http://backend failed");
+/// assert_eq!(err.into_boxed_http_error().status(), StatusCode::BAD_GATEWAY);
+/// ```
+///
+/// Fields must be named, and the status and message are required:
+///
+/// ```rust,compile_fail
+/// http_kit::http_error!(pub Tuple(u32) => http_kit::StatusCode::BAD_REQUEST, "tuple");
+/// ```
+///
+/// ```rust,compile_fail
+/// http_kit::http_error!(pub NoStatus { code: u32 } => "missing status");
+/// ```
+///
+/// Fields can only be rendered if the message mentions existing ones:
+///
+/// ```rust,compile_fail
+/// http_kit::http_error!(pub Typo { code: u32 } => http_kit::StatusCode::BAD_REQUEST, "{cdoe}");
+/// ```
 #[macro_export]
 macro_rules! http_error {
     ($(#[$meta:meta])* $vis:vis $name:ident, $status:expr, $message:expr $(,)?) => {
@@ -94,6 +172,98 @@ macro_rules! http_error {
             |_, f| { f.write_str($message) },
         );
     };
+    (
+        $(#[$meta:meta])*
+        $vis:vis $name:ident { $($(#[$field_meta:meta])* $field:ident : $field_ty:ty),* $(,)? }
+        => $status:expr, $message:literal
+        $(, source: $source:ty)?
+        $(, into: $into:path)*
+        $(,)?
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug)]
+        $vis struct $name {
+            $($(#[$field_meta])* pub $field: $field_ty,)*
+            $(source: ::core::option::Option<$source>,)?
+        }
+
+        impl $name {
+            /// Creates a new instance of this error type.
+            #[allow(clippy::new_without_default)]
+            pub fn new($($field: $field_ty),*) -> Self {
+                Self {
+                    $($field,)*
+                    $(source: ::core::option::Option::None::<$source>,)?
+                }
+            }
+
+            /// Converts this error into an [`Error`]($crate::Error) with the same status.
+            #[allow(dead_code)]
+            pub fn into_error(self) -> $crate::Error {
+                let status = $crate::HttpError::status(&self);
+                $crate::Error::new(self).set_status(status)
+            }
+
+            $(
+                /// Attaches the underlying error.
+                #[must_use]
+                pub fn with_source(mut self, source: $source) -> Self {
+                    self.source = ::core::option::Option::Some(source);
+                    self
+                }
+            )?
+        }
+
+        impl ::core::fmt::Display for $name {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                #[allow(unused_variables)]
+                let Self { $($field,)* .. } = self;
+                write!(f, $message)
+            }
+        }
+
+        impl ::core::error::Error for $name {
+            // Without a `source` clause, the initial `None` is returned as is.
+            #[allow(unused_mut, unused_assignments)]
+            fn source(&self) -> ::core::option::Option<&(dyn ::core::error::Error + 'static)> {
+                let mut source: ::core::option::Option<&(dyn ::core::error::Error + 'static)> =
+                    ::core::option::Option::None;
+                $(
+                    source = self
+                        .source
+                        .as_ref()
+                        .map(|source: &$source| source as &(dyn ::core::error::Error + 'static));
+                )?
+                source
+            }
+        }
+
+        impl $crate::HttpError for $name {
+            fn status(&self) -> $crate::StatusCode {
+                $status
+            }
+        }
+
+        $(
+            impl ::core::convert::From<$name> for $into {
+                fn from(error: $name) -> Self {
+                    let error: $crate::__private::Box<
+                        dyn ::core::error::Error + ::core::marker::Send + ::core::marker::Sync,
+                    > = $crate::__private::Box::new(error);
+                    ::core::convert::From::from(error)
+                }
+            }
+        )*
+    };
+    (
+        $(#[$meta:meta])*
+        $vis:vis $name:ident { $($fields:tt)* }, $status:expr, $message:literal $($rest:tt)*
+    ) => {
+        $crate::http_error!(
+            $(#[$meta])*
+            $vis $name { $($fields)* } => $status, $message $($rest)*
+        );
+    };
 }
 
 #[cfg(test)]
@@ -115,6 +285,75 @@ mod tests {
         |_, f| write!(f, "bad request (400)"),
     );
 
+    http_error!(
+        /// Error with fields, a source and conversions used in tests.
+        pub MacroFieldError { code: u16, reason: &'static str } => StatusCode::BAD_GATEWAY,
+        "upstream answered {code}: {reason}",
+        source: crate::BodyError,
+        into: crate::BodyError,
+    );
+
+    http_error!(
+        /// Error declared with a comma before its status.
+        pub MacroCommaError { id: u32 },
+        StatusCode::NOT_FOUND,
+        "no item {id}",
+        source: crate::BodyError,
+    );
+
+    http_error!(
+        /// Error whose message ignores its field.
+        pub MacroQuietError { detail: u32 } => StatusCode::CONFLICT,
+        "conflict",
+    );
+
+    #[test]
+    fn struct_form_renders_fields_and_source() {
+        use core::error::Error as _;
+
+        let error = MacroFieldError::new(503, "maintenance");
+        assert_eq!(error.code, 503);
+        assert_eq!(error.status(), StatusCode::BAD_GATEWAY);
+        assert_eq!(error.to_string(), "upstream answered 503: maintenance");
+        assert!(error.source().is_none());
+
+        let error = error.with_source(crate::BodyError::LimitExceeded(8));
+        assert_eq!(
+            error.source().unwrap().to_string(),
+            "body exceeds the limit of 8 bytes"
+        );
+
+        let body_error: crate::BodyError = error.into();
+        assert_eq!(body_error.to_string(), "upstream answered 503: maintenance");
+
+        let quiet = MacroQuietError::new(7);
+        assert_eq!(quiet.detail, 7);
+        assert_eq!(quiet.to_string(), "conflict");
+        let error: crate::Error = quiet.into();
+        assert_eq!(error.to_string(), "conflict");
+    }
+
+    #[test]
+    fn into_error_keeps_the_status() {
+        use core::error::Error as _;
+
+        let error = MacroCommaError::new(4).with_source(crate::BodyError::LimitExceeded(1));
+        assert_eq!(error.status(), StatusCode::NOT_FOUND);
+        assert!(error.source().is_some());
+        let error = error.into_error();
+        assert_eq!(error.to_string(), "no item 4");
+        assert_eq!(
+            error.into_boxed_http_error().status(),
+            StatusCode::NOT_FOUND
+        );
+
+        let error = MacroNotFound::new().into_error();
+        assert_eq!(
+            error.into_boxed_http_error().status(),
+            StatusCode::NOT_FOUND
+        );
+    }
+
     #[test]
     fn http_error_macros_create_expected_types() {
         let not_found = MacroNotFound::new();
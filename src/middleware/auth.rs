@@ -0,0 +1,419 @@
+//! HTTP authentication with the `Basic` and `Bearer` schemes.
+
+use alloc::{boxed::Box, format, string::String, sync::Arc};
+use core::{convert::Infallible, fmt, future::Future, pin::Pin};
+
+use bytestr::ByteStr;
+use http::{header, HeaderValue, StatusCode};
+
+use crate::{
+    headers::Authorization, middleware::MiddlewareError, response::text_response, Endpoint,
+    Middleware, Request, RequestExt, Response,
+};
+
+type Verdict<T> = Pin<Box<dyn Future<Output = T> + Send>>;
+type BasicValidator = Arc<dyn Fn(ByteStr, ByteStr) -> Verdict<bool> + Send + Sync>;
+type BearerValidator<P> = Arc<dyn Fn(ByteStr) -> Verdict<Option<P>> + Send + Sync>;
+
+// Outcome of reading the credentials of one scheme from a request.
+enum Credentials {
+    // No `Authorization` header, or one for another scheme.
+    Missing,
+    // An `Authorization` header for the scheme that cannot be decoded.
+    Malformed,
+    Present(Authorization),
+}
+
+fn credentials(request: &Request, scheme: &str) -> Credentials {
+    let Some(value) = request.headers().get(header::AUTHORIZATION) else {
+        return Credentials::Missing;
+    };
+    let Ok(text) = value.to_str() else {
+        return Credentials::Malformed;
+    };
+    let text = text.trim();
+    let name = text.split_once(' ').map_or(text, |(name, _)| name);
+    if !name.eq_ignore_ascii_case(scheme) {
+        return Credentials::Missing;
+    }
+    match Authorization::parse(value) {
+        Some(authorization) => Credentials::Present(authorization),
+        None => Credentials::Malformed,
+    }
+}
+
+fn challenge(scheme: &str, realm: &str) -> HeaderValue {
+    let realm = realm.replace('\\', "\\\\").replace('"', "\\\"");
+    HeaderValue::from_str(&format!("{scheme} realm=\"{realm}\""))
+        .expect("realms are checked when they are set")
+}
+
+fn reject(status: StatusCode, challenge: Option<HeaderValue>) -> Response {
+    let message = if status == StatusCode::BAD_REQUEST {
+        "Malformed Authorization header"
+    } else {
+        "Unauthorized"
+    };
+    let mut response = text_response(status, message);
+    if let Some(challenge) = challenge {
+        response
+            .headers_mut()
+            .insert(header::WWW_AUTHENTICATE, challenge);
+    }
+    response
+}
+
+fn check_realm(realm: &str) {
+    assert!(
+        HeaderValue::from_str(realm).is_ok(),
+        "realm `{realm}` is not a valid header value"
+    );
+}
+
+/// Middleware requiring `Basic` credentials (RFC 7617).
+///
+/// Requests are rejected before reaching the endpoint with:
+///
+/// - `401 Unauthorized` and `WWW-Authenticate: Basic realm="..."` when the
+///   `Authorization` header is missing, uses another scheme, or the validator refuses
+///   the credentials;
+/// - `400 Bad Request` when the credentials are not base64-encoded UTF-8 containing a
+///   colon.
+///
+/// The realm defaults to `Restricted`.
+///
+/// # Examples
+///
+/// ```rust
+/// use http_kit::middleware::BasicAuth;
+///
+/// let auth = BasicAuth::new(|username, password| username == "admin" && password == "hunter2")
+///     .realm("Admin area");
+/// ```
+#[derive(Clone)]
+pub struct BasicAuth {
+    validator: BasicValidator,
+    realm: String,
+}
+
+impl fmt::Debug for BasicAuth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BasicAuth")
+            .field("realm", &self.realm)
+            .finish_non_exhaustive()
+    }
+}
+
+impl BasicAuth {
+    /// Creates the middleware accepting the credentials for which `validator` returns
+    /// `true`.
+    pub fn new(validator: impl Fn(&str, &str) -> bool + Send + Sync + 'static) -> Self {
+        Self::new_async(move |username, password| {
+            let accepted = validator(&username, &password);
+            async move { accepted }
+        })
+    }
+
+    /// Creates the middleware accepting the credentials for which the future returned by
+    /// `validator` resolves to `true`, such as a database lookup.
+    pub fn new_async<F, Fut>(validator: F) -> Self
+    where
+        F: Fn(ByteStr, ByteStr) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = bool> + Send + 'static,
+    {
+        Self {
+            validator: Arc::new(move |username, password| Box::pin(validator(username, password))),
+            realm: String::from("Restricted"),
+        }
+    }
+
+    /// Sets the realm announced in the `WWW-Authenticate` challenge.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `realm` is not a valid header value.
+    #[must_use]
+    pub fn realm(mut self, realm: impl Into<String>) -> Self {
+        self.realm = realm.into();
+        check_realm(&self.realm);
+        self
+    }
+}
+
+impl Middleware for BasicAuth {
+    type Error = Infallible;
+    async fn handle<E: Endpoint>(
+        &mut self,
+        request: &mut Request,
+        mut next: E,
+    ) -> Result<Response, MiddlewareError<E::Error, Self::Error>> {
+        let unauthorized = || {
+            reject(
+                StatusCode::UNAUTHORIZED,
+                Some(challenge("Basic", &self.realm)),
+            )
+        };
+        let (username, password) = match credentials(request, "basic") {
+            Credentials::Present(Authorization::Basic { username, password }) => {
+                (username, password)
+            }
+            Credentials::Missing | Credentials::Present(_) => return Ok(unauthorized()),
+            Credentials::Malformed => return Ok(reject(StatusCode::BAD_REQUEST, None)),
+        };
+        if !(self.validator)(username, password).await {
+            return Ok(unauthorized());
+        }
+        next.respond(request)
+            .await
+            .map_err(MiddlewareError::Endpoint)
+    }
+}
+
+/// Middleware requiring a `Bearer` token (RFC 6750).
+///
+/// Requests are rejected before reaching the endpoint with:
+///
+/// - `401 Unauthorized` and `WWW-Authenticate: Bearer realm="..."` when the
+///   `Authorization` header is missing, uses another scheme, or the validator refuses
+///   the token;
+/// - `400 Bad Request` when the token is empty or the header is not visible ASCII.
+///
+/// The validator may resolve the token to a principal of type `P`, such as a user
+/// record, which is inserted in the request extensions for the endpoint to read.
+/// The realm defaults to `Restricted`.
+///
+/// # Examples
+///
+/// ```rust
+/// use http_kit::middleware::BearerAuth;
+///
+/// #[derive(Debug, Clone)]
+/// struct User {
+///     id: u64,
+/// }
+///
+/// let auth = BearerAuth::with_principal(|token| (token == "s3cr3t").then_some(User { id: 7 }));
+/// // Endpoints read the user with `request.extension::<User>()`.
+/// ```
+pub struct BearerAuth<P = ()> {
+    validator: BearerValidator<P>,
+    realm: String,
+}
+
+impl<P> Clone for BearerAuth<P> {
+    fn clone(&self) -> Self {
+        Self {
+            validator: self.validator.clone(),
+            realm: self.realm.clone(),
+        }
+    }
+}
+
+impl<P> fmt::Debug for BearerAuth<P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BearerAuth")
+            .field("realm", &self.realm)
+            .finish_non_exhaustive()
+    }
+}
+
+impl BearerAuth {
+    /// Creates the middleware accepting the tokens for which `validator` returns `true`.
+    pub fn new(validator: impl Fn(&str) -> bool + Send + Sync + 'static) -> Self {
+        Self::with_principal(move |token| validator(token).then_some(()))
+    }
+
+    /// Creates the middleware accepting the tokens for which the future returned by
+    /// `validator` resolves to `true`.
+    pub fn new_async<F, Fut>(validator: F) -> Self
+    where
+        F: Fn(ByteStr) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = bool> + Send + 'static,
+    {
+        Self::with_principal_async(move |token| {
+            let accepted = validator(token);
+            async move { accepted.await.then_some(()) }
+        })
+    }
+}
+
+impl<P: Send + Sync + 'static> BearerAuth<P> {
+    /// Creates the middleware accepting the tokens that `validator` resolves to a
+    /// principal, which is inserted in the request extensions.
+    pub fn with_principal(validator: impl Fn(&str) -> Option<P> + Send + Sync + 'static) -> Self {
+        Self::with_principal_async(move |token| {
+            let principal = validator(&token);
+            async move { principal }
+        })
+    }
+
+    /// Creates the middleware accepting the tokens that the future returned by
+    /// `validator` resolves to a principal, which is inserted in the request extensions.
+    pub fn with_principal_async<F, Fut>(validator: F) -> Self
+    where
+        F: Fn(ByteStr) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Option<P>> + Send + 'static,
+    {
+        Self {
+            validator: Arc::new(move |token| Box::pin(validator(token))),
+            realm: String::from("Restricted"),
+        }
+    }
+
+    /// Sets the realm announced in the `WWW-Authenticate` challenge.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `realm` is not a valid header value.
+    #[must_use]
+    pub fn realm(mut self, realm: impl Into<String>) -> Self {
+        self.realm = realm.into();
+        check_realm(&self.realm);
+        self
+    }
+}
+
+impl<P: Send + Sync + 'static> Middleware for BearerAuth<P> {
+    type Error = Infallible;
+    async fn handle<E: Endpoint>(
+        &mut self,
+        request: &mut Request,
+        mut next: E,
+    ) -> Result<Response, MiddlewareError<E::Error, Self::Error>> {
+        let unauthorized = || {
+            reject(
+                StatusCode::UNAUTHORIZED,
+                Some(challenge("Bearer", &self.realm)),
+            )
+        };
+        let token = match credentials(request, "bearer") {
+            Credentials::Present(Authorization::Bearer(token)) => token,
+            Credentials::Missing | Credentials::Present(_) => return Ok(unauthorized()),
+            Credentials::Malformed => return Ok(reject(StatusCode::BAD_REQUEST, None)),
+        };
+        let Some(principal) = (self.validator)(token).await else {
+            return Ok(unauthorized());
+        };
+        request.insert_extension(principal);
+        next.respond(request)
+            .await
+            .map_err(MiddlewareError::Endpoint)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{endpoint::WithMiddleware, Body};
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    // Counts the requests reaching it and answers with the authenticated user, if any.
+    struct Protected(Arc<AtomicUsize>);
+
+    impl Endpoint for Protected {
+        type Error = Infallible;
+        async fn respond(&mut self, request: &mut Request) -> Result<Response, Self::Error> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            let user = request.extension::<User>().map_or("", |user| user.0);
+            Ok(Response::new(Body::from_text(user)))
+        }
+    }
+
+    #[derive(Debug)]
+    struct User(&'static str);
+
+    async fn call(middleware: impl Middleware, authorization: Option<&[u8]>) -> (Response, usize) {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let mut endpoint = WithMiddleware::new(Protected(calls.clone()), middleware);
+        let mut request = Request::new(Body::empty());
+        if let Some(authorization) = authorization {
+            request.headers_mut().insert(
+                header::AUTHORIZATION,
+                HeaderValue::from_bytes(authorization).unwrap(),
+            );
+        }
+        let response = endpoint.respond(&mut request).await.unwrap();
+        (response, calls.load(Ordering::SeqCst))
+    }
+
+    fn basic() -> BasicAuth {
+        BasicAuth::new(|username, password| username == "Aladdin" && password == "open sesame")
+            .realm("Cave \"Sesame\"")
+    }
+
+    #[tokio::test]
+    async fn basic_credentials() {
+        let (response, calls) = call(basic(), Some(b"Basic QWxhZGRpbjpvcGVuIHNlc2FtZQ==")).await;
+        assert_eq!((response.status(), calls), (StatusCode::OK, 1));
+
+        // Aladdin:open sesam
+        let (response, calls) = call(basic(), Some(b"basic QWxhZGRpbjpvcGVuIHNlc2Ft")).await;
+        assert_eq!((response.status(), calls), (StatusCode::UNAUTHORIZED, 0));
+        assert_eq!(
+            response.headers()[header::WWW_AUTHENTICATE],
+            r#"Basic realm="Cave \"Sesame\"""#
+        );
+
+        for missing in [None, Some(&b"Bearer QWxhZGRpbjpvcGVuIHNlc2FtZQ=="[..])] {
+            let (response, calls) = call(basic(), missing).await;
+            assert_eq!((response.status(), calls), (StatusCode::UNAUTHORIZED, 0));
+            assert!(response.headers().contains_key(header::WWW_AUTHENTICATE));
+        }
+
+        // Invalid base64, no colon, invalid UTF-8 and a non-ASCII header.
+        for malformed in [
+            &b"Basic QWxh!!!"[..],
+            b"Basic QWxhZGRpbg==",
+            b"Basic /w==",
+            b"Basic \xff",
+        ] {
+            let (response, calls) = call(basic(), Some(malformed)).await;
+            assert_eq!((response.status(), calls), (StatusCode::BAD_REQUEST, 0));
+            assert!(!response.headers().contains_key(header::WWW_AUTHENTICATE));
+        }
+    }
+
+    #[tokio::test]
+    async fn async_validators() {
+        let auth = BasicAuth::new_async(|username, _| async move { username == "Aladdin" });
+        let (response, _) = call(auth, Some(b"Basic QWxhZGRpbjpvcGVuIHNlc2FtZQ==")).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let auth = BearerAuth::new_async(|token| async move { token == "t0k3n" });
+        let (response, calls) = call(auth.clone(), Some(b"Bearer t0k3n")).await;
+        assert_eq!((response.status(), calls), (StatusCode::OK, 1));
+        let (response, calls) = call(auth, Some(b"Bearer other")).await;
+        assert_eq!((response.status(), calls), (StatusCode::UNAUTHORIZED, 0));
+    }
+
+    #[tokio::test]
+    async fn bearer_tokens_resolve_principals() {
+        let auth = || {
+            BearerAuth::with_principal(|token| (token == "s3cr3t").then_some(User("ada")))
+                .realm("api")
+        };
+
+        let (response, calls) = call(auth(), Some(b"Bearer s3cr3t")).await;
+        assert_eq!((response.status(), calls), (StatusCode::OK, 1));
+        assert_eq!(response.into_body().into_string().await.unwrap(), "ada");
+
+        let (response, calls) = call(auth(), Some(b"Bearer wrong")).await;
+        assert_eq!((response.status(), calls), (StatusCode::UNAUTHORIZED, 0));
+        assert_eq!(
+            response.headers()[header::WWW_AUTHENTICATE],
+            r#"Bearer realm="api""#
+        );
+
+        let (response, calls) = call(auth(), None).await;
+        assert_eq!((response.status(), calls), (StatusCode::UNAUTHORIZED, 0));
+
+        let (response, calls) = call(auth(), Some(b"Bearer ")).await;
+        assert_eq!((response.status(), calls), (StatusCode::BAD_REQUEST, 0));
+    }
+
+    #[test]
+    #[should_panic(expected = "is not a valid header value")]
+    fn invalid_realms_panic() {
+        let _ = BasicAuth::new(|_, _| true).realm("line\nbreak");
+    }
+}
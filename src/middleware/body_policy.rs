@@ -0,0 +1,274 @@
+//! Handling of bodies on requests whose method does not expect one.
+//!
+//! Bodies on `GET`, `HEAD` or `DELETE` requests are legal but rarely intended, and some
+//! proxies and caches drop or mishandle them. [`BodyPolicy`] decides per method whether
+//! such bodies are allowed, rejected or discarded before the endpoint runs.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use http_kit::middleware::body_policy::{BodyAction, BodyPolicy};
+//! use http_kit::Method;
+//!
+//! let policy = BodyPolicy::strict().method(Method::OPTIONS, BodyAction::Ignore);
+//! assert_eq!(policy.action(&Method::GET), BodyAction::Reject);
+//! assert_eq!(policy.action(&Method::POST), BodyAction::Allow);
+//! ```
+
+use alloc::{format, string::String, vec::Vec};
+use core::convert::Infallible;
+
+use http::{header, Method, StatusCode};
+
+use crate::{
+    middleware::MiddlewareError, response::text_response, BodyError, Endpoint, Middleware, Request,
+    RequestExt, Response,
+};
+
+const DEFAULT_MAX_DRAIN: usize = 64 << 10;
+
+/// What [`BodyPolicy`] does with a request body.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BodyAction {
+    /// Passes the body to the endpoint.
+    #[default]
+    Allow,
+    /// Answers `400 Bad Request` without calling the endpoint.
+    Reject,
+    /// Drains the body and hands the endpoint a frozen body without framing headers.
+    Ignore,
+}
+
+/// Middleware applying a [`BodyAction`] to request bodies, per method.
+///
+/// A request is considered to have a body when its known length is non-zero, or when it
+/// carries `Transfer-Encoding` or a non-zero `Content-Length`, which covers streaming
+/// bodies of unknown length. Requests without a body always pass.
+///
+/// `TRACE` requests with a body are always rejected, as RFC 9110 forbids them.
+///
+/// Ignored bodies are drained up to [`max_drain`](Self::max_drain) bytes; longer bodies
+/// are answered with `413 Content Too Large`.
+#[derive(Debug, Clone)]
+pub struct BodyPolicy {
+    actions: Vec<(Method, BodyAction)>,
+    max_drain: usize,
+}
+
+impl Default for BodyPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BodyPolicy {
+    /// Creates a policy allowing bodies on every method.
+    pub fn new() -> Self {
+        Self {
+            actions: Vec::new(),
+            max_drain: DEFAULT_MAX_DRAIN,
+        }
+    }
+
+    /// Creates a policy rejecting bodies on `GET` and `HEAD` and ignoring them on
+    /// `DELETE`.
+    pub fn strict() -> Self {
+        Self::new()
+            .method(Method::GET, BodyAction::Reject)
+            .method(Method::HEAD, BodyAction::Reject)
+            .method(Method::DELETE, BodyAction::Ignore)
+    }
+
+    /// Sets the action for bodies on `method`.
+    #[must_use]
+    pub fn method(mut self, method: Method, action: BodyAction) -> Self {
+        self.actions.retain(|(existing, _)| *existing != method);
+        self.actions.push((method, action));
+        self
+    }
+
+    /// Sets how many bytes of an ignored body are drained, 64 KiB by default.
+    #[must_use]
+    pub fn max_drain(mut self, max_drain: usize) -> Self {
+        self.max_drain = max_drain;
+        self
+    }
+
+    /// Returns the action applied to bodies on `method`.
+    pub fn action(&self, method: &Method) -> BodyAction {
+        if method == Method::TRACE {
+            return BodyAction::Reject;
+        }
+        self.actions
+            .iter()
+            .find(|(existing, _)| existing == method)
+            .map_or(BodyAction::Allow, |(_, action)| *action)
+    }
+
+    fn has_body(request: &Request) -> bool {
+        if request.body().len().is_some_and(|len| len > 0) {
+            return true;
+        }
+        let headers = request.headers();
+        headers.contains_key(header::TRANSFER_ENCODING)
+            || headers
+                .get_all(header::CONTENT_LENGTH)
+                .iter()
+                .any(|value| value.as_bytes().iter().any(|&byte| byte != b'0'))
+    }
+
+    fn reject(status: StatusCode, message: &str) -> Response {
+        text_response(status, String::from(message))
+    }
+}
+
+impl Middleware for BodyPolicy {
+    type Error = Infallible;
+    async fn handle<E: Endpoint>(
+        &mut self,
+        request: &mut Request,
+        mut next: E,
+    ) -> Result<Response, MiddlewareError<E::Error, Self::Error>> {
+        if Self::has_body(request) {
+            match self.action(request.method()) {
+                BodyAction::Allow => {}
+                BodyAction::Reject => {
+                    let message = format!("{} requests must not have a body", request.method());
+                    return Ok(Self::reject(StatusCode::BAD_REQUEST, &message));
+                }
+                BodyAction::Ignore => match request.drain_body_up_to(self.max_drain).await {
+                    Ok(_) => {
+                        let headers = request.headers_mut();
+                        headers.remove(header::CONTENT_LENGTH);
+                        headers.remove(header::TRANSFER_ENCODING);
+                    }
+                    Err(BodyError::LimitExceeded(_)) => {
+                        return Ok(Self::reject(
+                            StatusCode::PAYLOAD_TOO_LARGE,
+                            "Request body too large",
+                        ));
+                    }
+                    Err(_) => {
+                        return Ok(Self::reject(
+                            StatusCode::BAD_REQUEST,
+                            "Failed to read request body",
+                        ));
+                    }
+                },
+            }
+        }
+
+        next.respond(request)
+            .await
+            .map_err(MiddlewareError::Endpoint)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{endpoint::WithMiddleware, Body};
+    use alloc::vec;
+    use futures_lite::stream;
+    use http::HeaderValue;
+
+    // Answers with whether the body was still readable.
+    struct Inspect;
+
+    impl Endpoint for Inspect {
+        type Error = Infallible;
+        async fn respond(&mut self, request: &mut Request) -> Result<Response, Self::Error> {
+            let state = if request.body().is_frozen() {
+                "frozen"
+            } else {
+                "readable"
+            };
+            Ok(Response::new(Body::from_text(state)))
+        }
+    }
+
+    async fn call(policy: &BodyPolicy, method: Method, body: Body) -> (StatusCode, String) {
+        let mut endpoint = WithMiddleware::new(Inspect, policy.clone());
+        let mut request = Request::new(body);
+        *request.method_mut() = method;
+        let response = endpoint.respond(&mut request).await.unwrap();
+        let status = response.status();
+        let text = response.into_body().into_string().await.unwrap();
+        (status, String::from(text.as_str()))
+    }
+
+    fn streaming(data: &'static str) -> Body {
+        Body::from_stream(stream::iter(vec![Ok::<_, Infallible>(data)]))
+    }
+
+    #[tokio::test]
+    async fn default_allows_every_method() {
+        let policy = BodyPolicy::new();
+        for method in [Method::GET, Method::HEAD, Method::DELETE, Method::POST] {
+            let (status, text) = call(&policy, method, Body::from_bytes("data")).await;
+            assert_eq!((status, text.as_str()), (StatusCode::OK, "readable"));
+        }
+    }
+
+    #[tokio::test]
+    async fn strict_rejects_get_and_head() {
+        let policy = BodyPolicy::strict();
+        for method in [Method::GET, Method::HEAD] {
+            let (status, text) = call(&policy, method.clone(), Body::from_bytes("data")).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST);
+            assert_eq!(text, format!("{method} requests must not have a body"));
+        }
+        let (status, _) = call(&policy, Method::GET, Body::empty()).await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = call(&policy, Method::PUT, Body::from_bytes("data")).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn strict_ignores_delete_bodies() {
+        let policy = BodyPolicy::strict();
+        let mut endpoint = WithMiddleware::new(Inspect, policy.clone());
+        let mut request = Request::new(Body::from_bytes("data"));
+        *request.method_mut() = Method::DELETE;
+        request
+            .headers_mut()
+            .insert(header::CONTENT_LENGTH, HeaderValue::from_static("4"));
+        let response = endpoint.respond(&mut request).await.unwrap();
+        assert!(!request.headers().contains_key(header::CONTENT_LENGTH));
+        assert_eq!(response.into_body().into_string().await.unwrap(), "frozen");
+
+        let policy = policy.max_drain(2);
+        let (status, _) = call(&policy, Method::DELETE, Body::from_bytes("data")).await;
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[tokio::test]
+    async fn streaming_bodies_are_detected_by_headers() {
+        let policy = BodyPolicy::strict();
+        let (status, _) = call(&policy, Method::GET, streaming("data")).await;
+        assert_eq!(status, StatusCode::OK);
+
+        for (name, value) in [
+            (header::TRANSFER_ENCODING, "chunked"),
+            (header::CONTENT_LENGTH, "4"),
+        ] {
+            let mut endpoint = WithMiddleware::new(Inspect, policy.clone());
+            let mut request = Request::new(streaming("data"));
+            request
+                .headers_mut()
+                .insert(name, HeaderValue::from_static(value));
+            let response = endpoint.respond(&mut request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        }
+    }
+
+    #[tokio::test]
+    async fn trace_always_rejects_bodies() {
+        let policy = BodyPolicy::new().method(Method::TRACE, BodyAction::Allow);
+        assert_eq!(policy.action(&Method::TRACE), BodyAction::Reject);
+        let (status, _) = call(&policy, Method::TRACE, Body::from_bytes("data")).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, _) = call(&policy, Method::TRACE, Body::empty()).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+}
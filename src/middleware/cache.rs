@@ -0,0 +1,610 @@
+//! In-process caching of responses.
+//!
+//! [`Cache`] stores successful `GET` and `HEAD` responses and serves later requests for
+//! the same method and URI without calling the endpoint. Bodies are buffered once when
+//! a response is stored and handed out as cheap copies of the same bytes afterwards.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # #[cfg(feature = "std")]
+//! # {
+//! use core::time::Duration;
+//! use http_kit::{header, middleware::cache::Cache};
+//!
+//! let cache = Cache::new(1024)
+//!     .default_ttl(Duration::from_secs(30))
+//!     .vary(header::ACCEPT_LANGUAGE);
+//! # }
+//! ```
+
+use alloc::{boxed::Box, collections::BTreeMap, vec::Vec};
+use core::{fmt, time::Duration};
+
+use bytes::Bytes;
+use http::{header, HeaderMap, HeaderName, HeaderValue, Method, StatusCode, Version};
+use mime::Mime;
+
+use super::rate_limit::Clock;
+#[cfg(feature = "std")]
+use super::rate_limit::MonotonicClock;
+use crate::{
+    headers, middleware::MiddlewareError, Body, BodyError, Endpoint, Middleware, Request, Response,
+};
+
+// Separates the parts of a cache key; it cannot appear in header values or URIs.
+const SEPARATOR: u8 = b'\n';
+// Stands for a vary header absent from the request, unlike an empty value.
+const ABSENT: u8 = 0;
+
+#[derive(Debug)]
+struct Entry {
+    status: StatusCode,
+    version: Version,
+    headers: HeaderMap,
+    body: Bytes,
+    mime: Option<Mime>,
+    stored_at: Duration,
+    ttl: Duration,
+    // Position in the recency order, larger is more recent.
+    used: u64,
+}
+
+/// Middleware caching successful `GET` and `HEAD` responses in memory.
+///
+/// Responses are keyed by method, URI, the `Host` header when the URI has no authority,
+/// and the values of the request headers named with [`Cache::vary`]. A response is
+/// stored when:
+///
+/// - its status is `2xx`;
+/// - its `Cache-Control` has none of `no-store`, `no-cache` and `private`;
+/// - it has no `Set-Cookie` header;
+/// - its `Vary` header, if any, names only headers given to [`Cache::vary`];
+/// - its body is at most [`max_body_size`](Cache::max_body_size) bytes, 1 MiB by default.
+///
+/// Entries live for the `s-maxage` or `max-age` of the response, or for the
+/// [`default_ttl`](Cache::default_ttl) of one minute. Requests with an `Authorization`
+/// header bypass the cache, unless `Authorization` is a vary header, so that responses
+/// are never shared between users.
+///
+/// Hits are answered with an `Age` header, and every cacheable request gets
+/// `X-Cache: HIT` or `X-Cache: MISS`. Once more than the configured number of entries
+/// are stored, the least recently used one is evicted.
+pub struct Cache {
+    clock: Box<dyn Clock>,
+    capacity: usize,
+    default_ttl: Duration,
+    max_body_size: usize,
+    vary: Vec<HeaderName>,
+    entries: BTreeMap<Vec<u8>, Entry>,
+    // Keys by their `Entry::used` tick, oldest first.
+    recency: BTreeMap<u64, Vec<u8>>,
+    tick: u64,
+}
+
+impl fmt::Debug for Cache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Cache")
+            .field("capacity", &self.capacity)
+            .field("default_ttl", &self.default_ttl)
+            .field("max_body_size", &self.max_body_size)
+            .field("vary", &self.vary)
+            .field("entries", &self.entries.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Cache {
+    /// Creates a cache holding up to `capacity` responses, timed by a
+    /// [`MonotonicClock`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    #[cfg(feature = "std")]
+    pub fn new(capacity: usize) -> Self {
+        Self::with_clock(capacity, MonotonicClock::new())
+    }
+
+    /// Creates a cache like [`Cache::new`], timed by `clock`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn with_clock(capacity: usize, clock: impl Clock + 'static) -> Self {
+        assert!(capacity > 0, "cache capacity must be positive");
+        Self {
+            clock: Box::new(clock),
+            capacity,
+            default_ttl: Duration::from_secs(60),
+            max_body_size: 1024 * 1024,
+            vary: Vec::new(),
+            entries: BTreeMap::new(),
+            recency: BTreeMap::new(),
+            tick: 0,
+        }
+    }
+
+    /// Sets how long responses without `max-age` are kept.
+    #[must_use]
+    pub const fn default_ttl(mut self, ttl: Duration) -> Self {
+        self.default_ttl = ttl;
+        self
+    }
+
+    /// Sets the size of the largest body stored; larger responses are passed through.
+    #[must_use]
+    pub const fn max_body_size(mut self, max_body_size: usize) -> Self {
+        self.max_body_size = max_body_size;
+        self
+    }
+
+    /// Adds the request header `name` to the cache key, so that requests differing in
+    /// its value get separate entries.
+    #[must_use]
+    pub fn vary(mut self, name: HeaderName) -> Self {
+        if !self.vary.contains(&name) {
+            self.vary.push(name);
+        }
+        self
+    }
+
+    /// Returns the number of stored responses, including expired ones not evicted yet.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if no response is stored.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Removes every stored response.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.recency.clear();
+    }
+
+    fn key(&self, request: &Request) -> Vec<u8> {
+        let mut key = Vec::new();
+        key.extend_from_slice(request.method().as_str().as_bytes());
+        key.push(SEPARATOR);
+        let uri = request.uri();
+        key.extend_from_slice(uri.scheme_str().unwrap_or_default().as_bytes());
+        key.push(SEPARATOR);
+        // Requests in origin form only name the host in their `Host` header.
+        let host = uri.authority().map_or_else(
+            || {
+                request
+                    .headers()
+                    .get(header::HOST)
+                    .map_or(&[][..], HeaderValue::as_bytes)
+            },
+            |authority| authority.as_str().as_bytes(),
+        );
+        key.extend_from_slice(host);
+        key.push(SEPARATOR);
+        let target = uri.path_and_query().map_or("/", |target| target.as_str());
+        key.extend_from_slice(target.as_bytes());
+        for name in &self.vary {
+            key.push(SEPARATOR);
+            let mut values = request.headers().get_all(name).iter().peekable();
+            if values.peek().is_none() {
+                key.push(ABSENT);
+            }
+            for (index, value) in values.enumerate() {
+                if index > 0 {
+                    key.push(b',');
+                }
+                key.extend_from_slice(value.as_bytes());
+            }
+        }
+        key
+    }
+
+    fn touch(&mut self, key: &[u8]) {
+        self.tick += 1;
+        if let Some(entry) = self.entries.get_mut(key) {
+            self.recency.remove(&entry.used);
+            entry.used = self.tick;
+            self.recency.insert(self.tick, key.to_vec());
+        }
+    }
+
+    fn remove(&mut self, key: &[u8]) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.recency.remove(&entry.used);
+        }
+    }
+
+    // Serves a fresh entry, dropping it if it expired.
+    fn lookup(&mut self, key: &[u8], now: Duration) -> Option<Response> {
+        let entry = self.entries.get(key)?;
+        let age = now.saturating_sub(entry.stored_at);
+        if age >= entry.ttl {
+            self.remove(key);
+            return None;
+        }
+
+        let mut body = Body::from_bytes(entry.body.clone());
+        if let Some(mime) = &entry.mime {
+            body = body.with_mime(mime.clone());
+        }
+        let mut response = Response::new(body);
+        *response.status_mut() = entry.status;
+        *response.version_mut() = entry.version;
+        *response.headers_mut() = entry.headers.clone();
+        response
+            .headers_mut()
+            .insert(header::AGE, HeaderValue::from(age.as_secs()));
+        self.touch(key);
+        Some(response)
+    }
+
+    // Returns how long `response` may be stored, or `None` if it must not be.
+    fn ttl(&self, response: &Response) -> Option<Duration> {
+        // A response varying on a header missing from the key, or on `*`, could be
+        // served to requests it does not suit.
+        let varies_beyond_key = response
+            .headers()
+            .get_all(header::VARY)
+            .iter()
+            .flat_map(|value| value.as_bytes().split(|&byte| byte == b','))
+            .map(<[u8]>::trim_ascii)
+            .filter(|field| !field.is_empty())
+            .any(|field| {
+                field == b"*"
+                    || HeaderName::from_bytes(field).map_or(true, |name| !self.vary.contains(&name))
+            });
+        if !response.status().is_success()
+            || response.headers().contains_key(header::SET_COOKIE)
+            || varies_beyond_key
+        {
+            return None;
+        }
+
+        let mut max_age = None;
+        let mut shared_max_age = None;
+        for directive in response
+            .headers()
+            .get_all(header::CACHE_CONTROL)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+        {
+            let (name, argument) = directive.split_once('=').unwrap_or((directive, ""));
+            let name = name.trim();
+            let seconds = || argument.trim().trim_matches('"').parse().ok();
+            if ["no-store", "no-cache", "private"]
+                .iter()
+                .any(|bypass| name.eq_ignore_ascii_case(bypass))
+            {
+                return None;
+            } else if name.eq_ignore_ascii_case("max-age") {
+                max_age = seconds();
+            } else if name.eq_ignore_ascii_case("s-maxage") {
+                shared_max_age = seconds();
+            }
+        }
+        let ttl = shared_max_age
+            .or(max_age)
+            .map_or(self.default_ttl, Duration::from_secs);
+        (!ttl.is_zero()).then_some(ttl)
+    }
+
+    // Buffers the body of `response` if it is small enough, leaving it readable.
+    async fn buffer(&self, response: &mut Response) -> Result<Option<Bytes>, BodyError> {
+        let body = response.body_mut();
+        if body.len().is_some_and(|len| len > self.max_body_size) {
+            return Ok(None);
+        }
+        let bytes = body.peek(self.max_body_size.saturating_add(1)).await?;
+        // A short peek read the whole body, which is now held in memory.
+        Ok((bytes.len() <= self.max_body_size).then_some(bytes))
+    }
+
+    fn store(&mut self, key: Vec<u8>, response: &Response, body: Bytes, ttl: Duration) {
+        self.remove(&key);
+        while self.entries.len() >= self.capacity {
+            let Some((_, oldest)) = self.recency.pop_first() else {
+                break;
+            };
+            self.entries.remove(&oldest);
+        }
+        self.tick += 1;
+        self.recency.insert(self.tick, key.clone());
+        self.entries.insert(
+            key,
+            Entry {
+                status: response.status(),
+                version: response.version(),
+                headers: response.headers().clone(),
+                body,
+                mime: response.body().mime().cloned(),
+                stored_at: self.clock.now(),
+                ttl,
+                used: self.tick,
+            },
+        );
+    }
+}
+
+impl Middleware for Cache {
+    type Error = BodyError;
+    async fn handle<E: Endpoint>(
+        &mut self,
+        request: &mut Request,
+        mut next: E,
+    ) -> Result<Response, MiddlewareError<E::Error, Self::Error>> {
+        let cacheable = matches!(*request.method(), Method::GET | Method::HEAD)
+            && (!request.headers().contains_key(header::AUTHORIZATION)
+                || self.vary.contains(&header::AUTHORIZATION));
+        if !cacheable {
+            return next
+                .respond(request)
+                .await
+                .map_err(MiddlewareError::Endpoint);
+        }
+
+        let key = self.key(request);
+        if let Some(mut response) = self.lookup(&key, self.clock.now()) {
+            response
+                .headers_mut()
+                .insert(headers::X_CACHE, HeaderValue::from_static("HIT"));
+            return Ok(response);
+        }
+
+        let mut response = next
+            .respond(request)
+            .await
+            .map_err(MiddlewareError::Endpoint)?;
+        if let Some(ttl) = self.ttl(&response) {
+            let buffered = self
+                .buffer(&mut response)
+                .await
+                .map_err(MiddlewareError::Middleware)?;
+            if let Some(body) = buffered {
+                self.store(key, &response, body, ttl);
+            }
+        }
+        response
+            .headers_mut()
+            .insert(headers::X_CACHE, HeaderValue::from_static("MISS"));
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endpoint::WithMiddleware;
+    use alloc::{format, string::String, sync::Arc, vec};
+    use core::{
+        convert::Infallible,
+        sync::atomic::{AtomicU64, AtomicUsize, Ordering},
+    };
+
+    // Numbers its responses, so cached ones are recognizable. The query string is
+    // copied to `Cache-Control`, and a few paths shape the response.
+    struct Upstream(Arc<AtomicUsize>);
+
+    impl Endpoint for Upstream {
+        type Error = Infallible;
+        async fn respond(&mut self, request: &mut Request) -> Result<Response, Self::Error> {
+            let count = self.0.fetch_add(1, Ordering::SeqCst) + 1;
+            let mut response = Response::new(Body::from_text(format!("response {count}")));
+            if let Some(directives) = request.uri().query() {
+                let directives = directives.replace("%20", " ");
+                response.headers_mut().insert(
+                    header::CACHE_CONTROL,
+                    HeaderValue::from_str(&directives).unwrap(),
+                );
+            }
+            match request.uri().path() {
+                "/missing" => *response.status_mut() = StatusCode::NOT_FOUND,
+                "/large" => *response.body_mut() = Body::from_bytes(vec![b'x'; 64]),
+                "/stream" => {
+                    let chunks = [String::from("stream "), format!("{count}")];
+                    *response.body_mut() = Body::from_stream(futures_lite::stream::iter(
+                        chunks.map(Ok::<_, BodyError>),
+                    ));
+                }
+                "/cookie" => {
+                    response
+                        .headers_mut()
+                        .insert(header::SET_COOKIE, HeaderValue::from_static("id=1"));
+                }
+                "/language" => {
+                    response
+                        .headers_mut()
+                        .insert(header::VARY, HeaderValue::from_static("Accept-Language"));
+                }
+                _ => {}
+            }
+            Ok(response)
+        }
+    }
+
+    struct Harness {
+        millis: Arc<AtomicU64>,
+        endpoint: WithMiddleware<Upstream, Cache>,
+    }
+
+    impl Harness {
+        // A cache of two entries and bodies up to 32 bytes.
+        fn new(configure: impl FnOnce(Cache) -> Cache) -> Self {
+            let millis = Arc::new(AtomicU64::new(0));
+            let clock = {
+                let millis = millis.clone();
+                move || Duration::from_millis(millis.load(Ordering::SeqCst))
+            };
+            let cache = configure(Cache::with_clock(2, clock).max_body_size(32));
+            Self {
+                millis,
+                endpoint: WithMiddleware::new(Upstream(Arc::new(AtomicUsize::new(0))), cache),
+            }
+        }
+
+        fn advance(&self, seconds: u64) {
+            self.millis.fetch_add(seconds * 1000, Ordering::SeqCst);
+        }
+
+        async fn send(&mut self, request: Request) -> Response {
+            let mut request = request;
+            self.endpoint.respond(&mut request).await.unwrap()
+        }
+
+        // Returns the body and the `X-Cache` header of the response to `GET uri`.
+        async fn get(&mut self, uri: &str) -> (String, Option<String>) {
+            let response = self.send(request(Method::GET, uri)).await;
+            let cache = response
+                .headers()
+                .get(headers::X_CACHE)
+                .map(|value| String::from(value.to_str().unwrap()));
+            let body = response.into_body().into_string().await.unwrap();
+            (String::from(body.as_str()), cache)
+        }
+    }
+
+    fn request(method: Method, uri: &str) -> Request {
+        let mut request = Request::new(Body::empty());
+        *request.method_mut() = method;
+        *request.uri_mut() = uri.parse().unwrap();
+        request
+    }
+
+    fn hit(body: &str) -> (String, Option<String>) {
+        (body.into(), Some("HIT".into()))
+    }
+
+    fn miss(body: &str) -> (String, Option<String>) {
+        (body.into(), Some("MISS".into()))
+    }
+
+    #[tokio::test]
+    async fn hits_and_misses() {
+        let mut harness = Harness::new(|cache| cache);
+        assert_eq!(harness.get("/a").await, miss("response 1"));
+        assert_eq!(harness.get("/a").await, hit("response 1"));
+        assert_eq!(harness.get("/stream").await, miss("stream 2"));
+        assert_eq!(harness.get("/stream").await, hit("stream 2"));
+
+        harness.advance(5);
+        let response = harness.send(request(Method::GET, "/a")).await;
+        assert_eq!(response.headers()[header::AGE], "5");
+        assert_eq!(response.body().mime(), Some(&mime::TEXT_PLAIN_UTF_8));
+
+        // HEAD requests have entries of their own.
+        let response = harness.send(request(Method::HEAD, "/a")).await;
+        assert_eq!(response.headers()[headers::X_CACHE], "MISS");
+        let response = harness.send(request(Method::HEAD, "/a")).await;
+        assert_eq!(response.headers()[headers::X_CACHE], "HIT");
+    }
+
+    #[tokio::test]
+    async fn entries_expire() {
+        let mut harness = Harness::new(|cache| cache.default_ttl(Duration::from_secs(10)));
+        let long = "/b?public,%20max-age=100";
+        assert_eq!(harness.get("/a").await, miss("response 1"));
+        assert_eq!(harness.get(long).await, miss("response 2"));
+
+        harness.advance(9);
+        assert_eq!(harness.get("/a").await, hit("response 1"));
+        harness.advance(1);
+        assert_eq!(harness.get("/a").await, miss("response 3"));
+        assert_eq!(harness.get(long).await, hit("response 2"));
+
+        harness.advance(90);
+        assert_eq!(harness.get(long).await, miss("response 4"));
+    }
+
+    #[tokio::test]
+    async fn uncacheable_exchanges_bypass_the_cache() {
+        let mut harness = Harness::new(|cache| cache);
+        for _ in 0..2 {
+            let response = harness.send(request(Method::POST, "/a")).await;
+            assert!(!response.headers().contains_key(headers::X_CACHE));
+
+            let mut authorized = request(Method::GET, "/a");
+            authorized
+                .headers_mut()
+                .insert(header::AUTHORIZATION, HeaderValue::from_static("Bearer t"));
+            let response = harness.send(authorized).await;
+            assert!(!response.headers().contains_key(headers::X_CACHE));
+        }
+
+        for uri in [
+            "/a?no-store",
+            "/a?private,%20max-age=60",
+            "/a?max-age=0",
+            "/missing",
+            "/cookie",
+            "/large",
+        ] {
+            harness.get(uri).await;
+            assert_eq!(harness.get(uri).await.1, Some("MISS".into()), "{uri}");
+        }
+
+        // Bodies too large to store are passed through whole.
+        let (body, _) = harness.get("/large").await;
+        assert_eq!(body.len(), 64);
+    }
+
+    #[tokio::test]
+    async fn vary_headers_split_entries() {
+        let mut harness = Harness::new(|cache| cache.vary(header::ACCEPT_LANGUAGE));
+        let mut send = async |language: &'static str| {
+            let mut request = request(Method::GET, "/");
+            request
+                .headers_mut()
+                .insert(header::ACCEPT_LANGUAGE, HeaderValue::from_static(language));
+            let response = harness.send(request).await;
+            let body = response.into_body().into_string().await.unwrap();
+            String::from(body.as_str())
+        };
+        assert_eq!(send("en").await, "response 1");
+        assert_eq!(send("fr").await, "response 2");
+        assert_eq!(send("en").await, "response 1");
+        assert_eq!(send("fr").await, "response 2");
+
+        // Responses may vary on the headers of the key.
+        let mut harness = Harness::new(|cache| cache.vary(header::ACCEPT_LANGUAGE));
+        assert_eq!(harness.get("/language").await, miss("response 1"));
+        assert_eq!(harness.get("/language").await, hit("response 1"));
+    }
+
+    #[tokio::test]
+    async fn responses_varying_beyond_the_key_are_not_stored() {
+        let mut harness = Harness::new(|cache| cache);
+        assert_eq!(harness.get("/language").await, miss("response 1"));
+        assert_eq!(harness.get("/language").await, miss("response 2"));
+    }
+
+    #[tokio::test]
+    async fn hosts_split_entries() {
+        let mut harness = Harness::new(|cache| cache);
+        let mut send = async |host: &'static str| {
+            let mut request = request(Method::GET, "/");
+            request
+                .headers_mut()
+                .insert(header::HOST, HeaderValue::from_static(host));
+            let response = harness.send(request).await;
+            let body = response.into_body().into_string().await.unwrap();
+            String::from(body.as_str())
+        };
+        assert_eq!(send("a.example").await, "response 1");
+        assert_eq!(send("b.example").await, "response 2");
+        assert_eq!(send("a.example").await, "response 1");
+    }
+
+    #[tokio::test]
+    async fn least_recently_used_entries_are_evicted() {
+        let mut harness = Harness::new(|cache| cache);
+        harness.get("/a").await;
+        harness.get("/b").await;
+        assert_eq!(harness.get("/a").await, hit("response 1"));
+        harness.get("/c").await;
+        assert_eq!(harness.get("/a").await, hit("response 1"));
+        assert_eq!(harness.get("/b").await, miss("response 4"));
+    }
+}
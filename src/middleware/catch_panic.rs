@@ -0,0 +1,98 @@
+//! Conversion of endpoint panics into responses.
+
+extern crate std;
+
+use alloc::string::String;
+use core::{convert::Infallible, panic::AssertUnwindSafe};
+
+use futures_lite::FutureExt;
+use http::StatusCode;
+
+use crate::{
+    middleware::MiddlewareError, response::text_response, Endpoint, Middleware, Request, Response,
+};
+
+/// The message of a caught panic, stored in the response extensions by [`CatchPanic`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PanicMessage(pub String);
+
+/// Middleware turning panics of the wrapped endpoint into `500 Internal Server Error`.
+///
+/// The panic message, when it is a string, is exposed through the [`PanicMessage`]
+/// response extension rather than in the body.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CatchPanic;
+
+impl CatchPanic {
+    /// Creates the middleware.
+    pub const fn new() -> Self {
+        Self
+    }
+}
+
+impl Middleware for CatchPanic {
+    type Error = Infallible;
+    async fn handle<E: Endpoint>(
+        &mut self,
+        request: &mut Request,
+        mut next: E,
+    ) -> Result<Response, MiddlewareError<E::Error, Self::Error>> {
+        match AssertUnwindSafe(next.respond(request)).catch_unwind().await {
+            Ok(result) => result.map_err(MiddlewareError::Endpoint),
+            Err(payload) => {
+                let message = payload
+                    .downcast_ref::<&str>()
+                    .map(|message| String::from(*message))
+                    .or_else(|| payload.downcast_ref::<String>().cloned())
+                    .unwrap_or_default();
+
+                let mut response =
+                    text_response(StatusCode::INTERNAL_SERVER_ERROR, "Internal Server Error");
+                response.extensions_mut().insert(PanicMessage(message));
+                Ok(response)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        endpoint::{endpoint_fn, WithMiddleware},
+        Body,
+    };
+
+    #[tokio::test]
+    async fn panics_become_server_errors() {
+        let panicking = endpoint_fn(|request: &mut Request| {
+            let path = String::from(request.uri().path());
+            async move {
+                if path == "/boom" {
+                    panic!("index out of range");
+                }
+                Ok::<_, Infallible>(Response::new(Body::from_text("fine")))
+            }
+        });
+        let mut endpoint = WithMiddleware::new(panicking, CatchPanic::new());
+
+        let mut request = Request::new(Body::empty());
+        *request.uri_mut() = "/boom".parse().unwrap();
+        let response = endpoint.respond(&mut request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(
+            response.extensions().get::<PanicMessage>(),
+            Some(&PanicMessage(String::from("index out of range")))
+        );
+        assert_eq!(
+            response.into_body().into_string().await.unwrap(),
+            "Internal Server Error"
+        );
+
+        // The endpoint keeps serving after a panic.
+        let mut request = Request::new(Body::empty());
+        *request.uri_mut() = "/".parse().unwrap();
+        let response = endpoint.respond(&mut request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
@@ -0,0 +1,521 @@
+//! Transparent compression of response bodies.
+//!
+//! [`Compression`] picks the best encoding allowed by the request's `Accept-Encoding`
+//! header and compresses the response body as it streams, chunk by chunk, so large and
+//! never-ending bodies such as Server-Sent Events are never buffered.
+//!
+//! gzip and deflate are always available; brotli requires the `compression-br` feature.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use http_kit::middleware::compression::Compression;
+//! use http_kit::{endpoint::WithMiddleware, Body, Endpoint, Request, Response};
+//! use core::convert::Infallible;
+//!
+//! struct Report;
+//!
+//! impl Endpoint for Report {
+//!     type Error = Infallible;
+//!     async fn respond(&mut self, _request: &mut Request) -> Result<Response, Self::Error> {
+//!         Ok(Response::new(Body::from_text("quarterly numbers ".repeat(200))))
+//!     }
+//! }
+//!
+//! # async fn example() {
+//! let mut endpoint = WithMiddleware::new(Report, Compression::new());
+//! let mut request = Request::new(Body::empty());
+//! request
+//!     .headers_mut()
+//!     .insert("accept-encoding", "gzip, deflate".parse().unwrap());
+//! let response = endpoint.respond(&mut request).await.unwrap();
+//! assert_eq!(response.headers()["content-encoding"], "gzip");
+//! # }
+//! ```
+
+extern crate std;
+
+use alloc::vec::Vec;
+use core::{
+    convert::Infallible,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use std::io::{self, Write};
+
+use bytes::Bytes;
+use futures_lite::{ready, Stream};
+use http::{header, HeaderMap, HeaderValue};
+use mime::Mime;
+
+use crate::{
+    middleware::MiddlewareError, Body, BodyError, Endpoint, Middleware, Request, Response,
+};
+
+const DEFAULT_MIN_SIZE: usize = 1024;
+
+/// A content coding supported by [`Compression`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Encoding {
+    /// `gzip` (RFC 1952).
+    Gzip,
+    /// `deflate`, that is the zlib format (RFC 1950).
+    Deflate,
+    /// `br` (RFC 7932).
+    #[cfg(feature = "compression-br")]
+    Brotli,
+}
+
+impl Encoding {
+    // Server preference, used to break ties between equal q-values.
+    const ALL: &'static [Self] = &[
+        #[cfg(feature = "compression-br")]
+        Self::Brotli,
+        Self::Gzip,
+        Self::Deflate,
+    ];
+
+    /// Returns the token of the coding, as used in `Content-Encoding`.
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Gzip => "gzip",
+            Self::Deflate => "deflate",
+            #[cfg(feature = "compression-br")]
+            Self::Brotli => "br",
+        }
+    }
+
+    fn matches(self, coding: &str) -> bool {
+        coding.eq_ignore_ascii_case(self.as_str())
+            || (self == Self::Gzip && coding.eq_ignore_ascii_case("x-gzip"))
+    }
+
+    /// Picks the preferred coding allowed by the `Accept-Encoding` headers, if any.
+    ///
+    /// Each coding takes the q-value of its own entry, or of `*` when it is not listed.
+    /// Codings with a q-value of `0` are refused, and ties are broken in favor of brotli,
+    /// then gzip, then deflate.
+    pub fn negotiate(headers: &HeaderMap) -> Option<Self> {
+        let entries: Vec<(&str, f32)> = headers
+            .get_all(header::ACCEPT_ENCODING)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .filter_map(|entry| {
+                let mut parts = entry.split(';');
+                let coding = parts.next()?.trim();
+                let quality = parts
+                    .filter_map(|param| param.trim().strip_prefix("q="))
+                    .find_map(|q| q.trim().parse::<f32>().ok())
+                    .filter(|q| (0.0..=1.0).contains(q))
+                    .unwrap_or(1.0);
+                (!coding.is_empty()).then_some((coding, quality))
+            })
+            .collect();
+
+        let quality = |encoding: Self| {
+            entries
+                .iter()
+                .find(|(coding, _)| encoding.matches(coding))
+                .or_else(|| entries.iter().find(|(coding, _)| *coding == "*"))
+                .map_or(0.0, |(_, quality)| *quality)
+        };
+
+        let mut best: Option<(Self, f32)> = None;
+        for &encoding in Self::ALL {
+            let quality = quality(encoding);
+            if quality > 0.0 && best.is_none_or(|(_, best)| quality > best) {
+                best = Some((encoding, quality));
+            }
+        }
+        best.map(|(encoding, _)| encoding)
+    }
+
+    fn encoder(self) -> Encoder {
+        match self {
+            Self::Gzip => Encoder::Gzip(flate2::write::GzEncoder::new(
+                Vec::new(),
+                flate2::Compression::default(),
+            )),
+            Self::Deflate => Encoder::Deflate(flate2::write::ZlibEncoder::new(
+                Vec::new(),
+                flate2::Compression::default(),
+            )),
+            #[cfg(feature = "compression-br")]
+            Self::Brotli => Encoder::Brotli(alloc::boxed::Box::new(brotli::CompressorWriter::new(
+                Vec::new(),
+                4096,
+                5,
+                22,
+            ))),
+        }
+    }
+}
+
+/// Returns whether a body of type `mime` is worth compressing.
+///
+/// Images, audio, video, fonts in compressed formats and archives are skipped, except
+/// SVG. Everything else, including unknown binary data, is compressed.
+pub fn is_compressible(mime: &Mime) -> bool {
+    match (mime.type_(), mime.subtype().as_str()) {
+        (mime::IMAGE, _) => mime.subtype() == mime::SVG,
+        (mime::AUDIO | mime::VIDEO, _) => false,
+        (mime::FONT, subtype) => !matches!(subtype, "woff" | "woff2"),
+        (mime::APPLICATION, subtype) => !matches!(
+            subtype,
+            "zip"
+                | "gzip"
+                | "x-gzip"
+                | "zstd"
+                | "x-bzip2"
+                | "x-xz"
+                | "x-7z-compressed"
+                | "x-rar-compressed"
+                | "vnd.rar"
+                | "pdf"
+                | "wasm"
+        ),
+        _ => true,
+    }
+}
+
+/// Middleware compressing response bodies according to `Accept-Encoding`.
+///
+/// Responses are left untouched when:
+///
+/// - the client accepts none of the supported codings;
+/// - the response already has a `Content-Encoding`;
+/// - the body is empty, or its known length is below [`min_size`](Self::min_size);
+/// - the type of the body, read from `Content-Type` or [`Body::mime`], is refused by
+///   the predicate, [`is_compressible`] by default.
+///
+/// Compressed responses get a `Content-Encoding` header and lose their
+/// `Content-Length`, as the compressed length is only known at the end. Every response
+/// eligible for compression is marked `Vary: Accept-Encoding`, whether or not this
+/// client accepted it.
+#[derive(Debug, Clone)]
+pub struct Compression {
+    min_size: usize,
+    predicate: fn(&Mime) -> bool,
+}
+
+impl Default for Compression {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Compression {
+    /// Creates a compression middleware skipping bodies shorter than 1 KiB.
+    pub fn new() -> Self {
+        Self {
+            min_size: DEFAULT_MIN_SIZE,
+            predicate: is_compressible,
+        }
+    }
+
+    /// Sets the length below which bodies of known length are sent as they are.
+    ///
+    /// Streaming bodies, whose length is unknown, are always compressed.
+    #[must_use]
+    pub fn min_size(mut self, min_size: usize) -> Self {
+        self.min_size = min_size;
+        self
+    }
+
+    /// Sets the predicate deciding which media types are compressed.
+    ///
+    /// Bodies without a known type are always compressed.
+    #[must_use]
+    pub fn predicate(mut self, predicate: fn(&Mime) -> bool) -> Self {
+        self.predicate = predicate;
+        self
+    }
+
+    fn eligible(&self, response: &Response) -> bool {
+        if response.headers().contains_key(header::CONTENT_ENCODING) {
+            return false;
+        }
+        let body = response.body();
+        if body
+            .len()
+            .is_some_and(|len| len == 0 || len < self.min_size)
+        {
+            return false;
+        }
+        let declared = response
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<Mime>().ok());
+        match declared.as_ref().or(body.mime()) {
+            Some(mime) => (self.predicate)(mime),
+            None => true,
+        }
+    }
+}
+
+impl Middleware for Compression {
+    type Error = Infallible;
+    async fn handle<E: Endpoint>(
+        &mut self,
+        request: &mut Request,
+        mut next: E,
+    ) -> Result<Response, MiddlewareError<E::Error, Self::Error>> {
+        let encoding = Encoding::negotiate(request.headers());
+        let mut response = next
+            .respond(request)
+            .await
+            .map_err(MiddlewareError::Endpoint)?;
+
+        if !self.eligible(&response) {
+            return Ok(response);
+        }
+        let headers = response.headers_mut();
+        headers.append(header::VARY, HeaderValue::from_static("accept-encoding"));
+        let Some(encoding) = encoding else {
+            return Ok(response);
+        };
+        headers.insert(
+            header::CONTENT_ENCODING,
+            HeaderValue::from_static(encoding.as_str()),
+        );
+        headers.remove(header::CONTENT_LENGTH);
+
+        let body = core::mem::take(response.body_mut());
+        let mime = body.mime().cloned();
+        let mut compressed = Body::from_stream(Compress {
+            body,
+            encoder: Some(encoding.encoder()),
+        });
+        if let Some(mime) = mime {
+            compressed = compressed.with_mime(mime);
+        }
+        *response.body_mut() = compressed;
+        Ok(response)
+    }
+}
+
+enum Encoder {
+    Gzip(flate2::write::GzEncoder<Vec<u8>>),
+    Deflate(flate2::write::ZlibEncoder<Vec<u8>>),
+    #[cfg(feature = "compression-br")]
+    Brotli(alloc::boxed::Box<brotli::CompressorWriter<Vec<u8>>>),
+}
+
+impl Encoder {
+    // Compresses `chunk` and returns everything produced so far. Flushing after every
+    // chunk keeps streams such as Server-Sent Events live.
+    fn write(&mut self, chunk: &[u8]) -> io::Result<Bytes> {
+        let output = match self {
+            Self::Gzip(encoder) => {
+                encoder.write_all(chunk)?;
+                encoder.flush()?;
+                encoder.get_mut()
+            }
+            Self::Deflate(encoder) => {
+                encoder.write_all(chunk)?;
+                encoder.flush()?;
+                encoder.get_mut()
+            }
+            #[cfg(feature = "compression-br")]
+            Self::Brotli(encoder) => {
+                encoder.write_all(chunk)?;
+                encoder.flush()?;
+                encoder.get_mut()
+            }
+        };
+        Ok(Bytes::from(core::mem::take(output)))
+    }
+
+    fn finish(self) -> io::Result<Bytes> {
+        let output = match self {
+            Self::Gzip(encoder) => encoder.finish()?,
+            Self::Deflate(encoder) => encoder.finish()?,
+            #[cfg(feature = "compression-br")]
+            Self::Brotli(encoder) => encoder.into_inner(),
+        };
+        Ok(Bytes::from(output))
+    }
+}
+
+// The compressed form of `body`, one compressed chunk per chunk read.
+struct Compress {
+    body: Body,
+    encoder: Option<Encoder>,
+}
+
+impl Stream for Compress {
+    type Item = Result<Bytes, BodyError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            if self.encoder.is_none() {
+                return Poll::Ready(None);
+            }
+            let output = match ready!(Pin::new(&mut self.body).poll_next(cx)) {
+                Some(Ok(chunk)) => match &mut self.encoder {
+                    Some(encoder) => encoder.write(&chunk),
+                    None => continue,
+                },
+                Some(Err(error)) => {
+                    self.encoder = None;
+                    return Poll::Ready(Some(Err(error)));
+                }
+                None => match self.encoder.take() {
+                    Some(encoder) => encoder.finish(),
+                    None => continue,
+                },
+            };
+            match output {
+                Ok(output) if output.is_empty() => {}
+                Ok(output) => return Poll::Ready(Some(Ok(output))),
+                Err(error) => {
+                    self.encoder = None;
+                    return Poll::Ready(Some(Err(error.into())));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endpoint::WithMiddleware;
+    use alloc::{string::String, vec};
+    use futures_lite::stream;
+    use std::io::Read;
+
+    const TEXT: &str = "the quick brown fox jumps over the lazy dog. ";
+
+    struct Serve(Option<Response>);
+
+    impl Endpoint for Serve {
+        type Error = Infallible;
+        async fn respond(&mut self, _request: &mut Request) -> Result<Response, Self::Error> {
+            Ok(self.0.take().unwrap())
+        }
+    }
+
+    async fn call(compression: Compression, accept: &str, response: Response) -> Response {
+        let mut endpoint = WithMiddleware::new(Serve(Some(response)), compression);
+        let mut request = Request::new(Body::empty());
+        request
+            .headers_mut()
+            .insert(header::ACCEPT_ENCODING, accept.parse().unwrap());
+        endpoint.respond(&mut request).await.unwrap()
+    }
+
+    fn negotiate(accept: &str) -> Option<Encoding> {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::ACCEPT_ENCODING, accept.parse().unwrap());
+        Encoding::negotiate(&headers)
+    }
+
+    #[test]
+    fn negotiation() {
+        assert_eq!(negotiate("br;q=0, gzip"), Some(Encoding::Gzip));
+        assert_eq!(negotiate("deflate, gzip;q=0.5"), Some(Encoding::Deflate));
+        assert_eq!(negotiate("GZIP;q=0.2, *;q=0.1"), Some(Encoding::Gzip));
+        assert_eq!(negotiate("x-gzip"), Some(Encoding::Gzip));
+        assert_eq!(negotiate("identity"), None);
+        assert_eq!(negotiate("*;q=0"), None);
+        assert_eq!(negotiate("gzip;q=0, deflate;q=0"), None);
+        assert_eq!(Encoding::negotiate(&HeaderMap::new()), None);
+        #[cfg(feature = "compression-br")]
+        assert_eq!(negotiate("gzip, br, deflate"), Some(Encoding::Brotli));
+        #[cfg(not(feature = "compression-br"))]
+        assert_eq!(negotiate("gzip, br, deflate"), Some(Encoding::Gzip));
+    }
+
+    #[tokio::test]
+    async fn streaming_bodies_round_trip() {
+        let chunks: Vec<Result<&'static str, Infallible>> = vec![Ok(TEXT); 100];
+        let body = Body::from_stream(stream::iter(chunks)).with_mime(mime::TEXT_PLAIN);
+        let response = call(Compression::new(), "br;q=0, gzip", Response::new(body)).await;
+
+        assert_eq!(response.headers()[header::CONTENT_ENCODING], "gzip");
+        assert_eq!(response.headers()[header::VARY], "accept-encoding");
+        assert_eq!(response.body().mime(), Some(&mime::TEXT_PLAIN));
+        let compressed = response.into_body().into_bytes().await.unwrap();
+        assert!(compressed.len() < TEXT.len() * 100);
+
+        let mut decompressed = String::new();
+        flate2::read::GzDecoder::new(&compressed[..])
+            .read_to_string(&mut decompressed)
+            .unwrap();
+        assert_eq!(decompressed, TEXT.repeat(100));
+
+        let response = call(
+            Compression::new(),
+            "deflate",
+            Response::new(Body::from_text(TEXT.repeat(100))),
+        )
+        .await;
+        let compressed = response.into_body().into_bytes().await.unwrap();
+        let mut decompressed = String::new();
+        flate2::read::ZlibDecoder::new(&compressed[..])
+            .read_to_string(&mut decompressed)
+            .unwrap();
+        assert_eq!(decompressed, TEXT.repeat(100));
+    }
+
+    #[tokio::test]
+    async fn ineligible_responses_are_untouched() {
+        let text = || Body::from_text(TEXT.repeat(100));
+
+        let mut encoded = Response::new(text());
+        encoded
+            .headers_mut()
+            .insert(header::CONTENT_ENCODING, HeaderValue::from_static("zstd"));
+        let png = Response::new(Body::from_bytes(TEXT.repeat(100)).with_mime(mime::IMAGE_PNG));
+        let mut declared_png = Response::new(text());
+        declared_png
+            .headers_mut()
+            .insert(header::CONTENT_TYPE, HeaderValue::from_static("image/png"));
+        let short = Response::new(Body::from_text(TEXT));
+        let empty = Response::new(Body::empty());
+
+        for response in [encoded, png, declared_png, short, empty] {
+            let response = call(Compression::new(), "gzip", response).await;
+            assert_ne!(
+                response.headers().get(header::CONTENT_ENCODING),
+                Some(&HeaderValue::from_static("gzip"))
+            );
+        }
+
+        let mut response = Response::new(text());
+        response
+            .headers_mut()
+            .insert(header::CONTENT_LENGTH, HeaderValue::from(TEXT.len() * 100));
+        let response = call(Compression::new(), "identity", response).await;
+        assert!(!response.headers().contains_key(header::CONTENT_ENCODING));
+        assert!(response.headers().contains_key(header::CONTENT_LENGTH));
+        assert_eq!(response.headers()[header::VARY], "accept-encoding");
+
+        let everything = Compression::new().min_size(0).predicate(|_| true);
+        let response = Response::new(Body::from_bytes("x").with_mime(mime::IMAGE_PNG));
+        let response = call(everything, "gzip", response).await;
+        assert_eq!(response.headers()[header::CONTENT_ENCODING], "gzip");
+    }
+
+    #[cfg(feature = "compression-br")]
+    #[tokio::test]
+    async fn brotli_round_trip() {
+        let response = call(
+            Compression::new(),
+            "gzip;q=0.5, br",
+            Response::new(Body::from_text(TEXT.repeat(100))),
+        )
+        .await;
+        assert_eq!(response.headers()[header::CONTENT_ENCODING], "br");
+        let compressed = response.into_body().into_bytes().await.unwrap();
+        let mut decompressed = String::new();
+        brotli::Decompressor::new(&compressed[..], 4096)
+            .read_to_string(&mut decompressed)
+            .unwrap();
+        assert_eq!(decompressed, TEXT.repeat(100));
+    }
+}
@@ -0,0 +1,350 @@
+//! Cookie jars for endpoints.
+//!
+//! [`CookieJarMiddleware`] parses the `Cookie` headers of each request into a
+//! [`CookieJar`] that endpoints reach through [`RequestExt::cookie_jar_mut`]. Once the
+//! endpoint returns, every cookie added to or removed from the jar is sent back as a
+//! `Set-Cookie` header.
+//!
+//! With the `cookie-signed` feature, the middleware can also sign or encrypt cookies
+//! with a [`Key`]. Endpoints still see plain values: cookies that fail verification are
+//! left out of the jar, and outgoing cookies are protected before they are sent.
+//!
+//! [`RequestExt::cookie_jar_mut`]: crate::RequestExt::cookie_jar_mut
+//!
+//! # Examples
+//!
+//! ```rust
+//! use http_kit::cookie::Cookie;
+//! use http_kit::middleware::cookie_jar::CookieJarMiddleware;
+//! use http_kit::{endpoint::WithMiddleware, Body, Endpoint, Request, RequestExt, Response};
+//! use core::convert::Infallible;
+//!
+//! struct Login;
+//!
+//! impl Endpoint for Login {
+//!     type Error = Infallible;
+//!     async fn respond(&mut self, request: &mut Request) -> Result<Response, Self::Error> {
+//!         if let Some(jar) = request.cookie_jar_mut() {
+//!             jar.add(Cookie::new("session", "abc"));
+//!         }
+//!         Ok(Response::new(Body::empty()))
+//!     }
+//! }
+//!
+//! # async fn example() {
+//! let mut endpoint = WithMiddleware::new(Login, CookieJarMiddleware::new());
+//! let response = endpoint.respond(&mut Request::new(Body::empty())).await.unwrap();
+//! assert_eq!(response.headers()["set-cookie"], "session=abc");
+//! # }
+//! ```
+
+use alloc::string::ToString;
+use core::convert::Infallible;
+
+#[cfg(feature = "cookie-signed")]
+use cookie::Key;
+use cookie::{Cookie, CookieJar};
+use http::{header, HeaderValue};
+
+use crate::{middleware::MiddlewareError, Endpoint, Middleware, Request, RequestExt, Response};
+
+#[derive(Clone)]
+enum Protection {
+    Plain,
+    #[cfg(feature = "cookie-signed")]
+    Signed(Key),
+    #[cfg(feature = "cookie-signed")]
+    Private(Key),
+}
+
+/// Middleware managing a [`CookieJar`] per request.
+///
+/// Requests already carrying a jar, for instance from an outer `CookieJarMiddleware`,
+/// keep it. Malformed cookies are skipped.
+#[derive(Clone)]
+pub struct CookieJarMiddleware {
+    protection: Protection,
+}
+
+impl core::fmt::Debug for CookieJarMiddleware {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let protection = match self.protection {
+            Protection::Plain => "plain",
+            #[cfg(feature = "cookie-signed")]
+            Protection::Signed(_) => "signed",
+            #[cfg(feature = "cookie-signed")]
+            Protection::Private(_) => "private",
+        };
+        f.debug_struct("CookieJarMiddleware")
+            .field("protection", &protection)
+            .finish()
+    }
+}
+
+impl Default for CookieJarMiddleware {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CookieJarMiddleware {
+    /// Creates a middleware exchanging cookies as they are.
+    pub fn new() -> Self {
+        Self {
+            protection: Protection::Plain,
+        }
+    }
+
+    /// Creates a middleware signing cookies with `key`, so clients can read but not
+    /// modify them.
+    ///
+    /// Incoming cookies whose signature does not match are dropped.
+    #[cfg(feature = "cookie-signed")]
+    pub fn signed(key: Key) -> Self {
+        Self {
+            protection: Protection::Signed(key),
+        }
+    }
+
+    /// Creates a middleware encrypting cookies with `key`, so clients can neither read
+    /// nor modify them.
+    ///
+    /// Incoming cookies that cannot be decrypted are dropped.
+    #[cfg(feature = "cookie-signed")]
+    pub fn private(key: Key) -> Self {
+        Self {
+            protection: Protection::Private(key),
+        }
+    }
+
+    fn jar(&self, request: &Request) -> CookieJar {
+        let mut jar = CookieJar::new();
+        let cookies = request
+            .headers()
+            .get_all(header::COOKIE)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(Cookie::split_parse)
+            .filter_map(Result::ok);
+        for cookie in cookies {
+            if let Some(cookie) = self.open(cookie.into_owned()) {
+                jar.add_original(cookie);
+            }
+        }
+        jar
+    }
+
+    fn open(&self, cookie: Cookie<'static>) -> Option<Cookie<'static>> {
+        match &self.protection {
+            Protection::Plain => Some(cookie),
+            #[cfg(feature = "cookie-signed")]
+            Protection::Signed(key) => CookieJar::new().signed(key).verify(cookie),
+            #[cfg(feature = "cookie-signed")]
+            Protection::Private(key) => CookieJar::new().private(key).decrypt(cookie),
+        }
+    }
+
+    // Removal cookies carry no value worth protecting and are sent as they are.
+    fn seal(&self, cookie: &Cookie<'static>) -> Cookie<'static> {
+        #[cfg(feature = "cookie-signed")]
+        {
+            let removal = cookie.max_age() == Some(cookie::time::Duration::ZERO);
+            let mut jar = CookieJar::new();
+            match &self.protection {
+                Protection::Signed(key) if !removal => jar.signed_mut(key).add(cookie.clone()),
+                Protection::Private(key) if !removal => jar.private_mut(key).add(cookie.clone()),
+                _ => return cookie.clone(),
+            }
+            if let Some(sealed) = jar.delta().next() {
+                return sealed.clone();
+            }
+        }
+        cookie.clone()
+    }
+}
+
+impl Middleware for CookieJarMiddleware {
+    type Error = Infallible;
+    async fn handle<E: Endpoint>(
+        &mut self,
+        request: &mut Request,
+        mut next: E,
+    ) -> Result<Response, MiddlewareError<E::Error, Self::Error>> {
+        if request.cookie_jar().is_none() {
+            let jar = self.jar(request);
+            request.insert_extension(jar);
+        }
+
+        let mut response = next
+            .respond(request)
+            .await
+            .map_err(MiddlewareError::Endpoint)?;
+
+        if let Some(jar) = request.cookie_jar() {
+            for cookie in jar.delta() {
+                let sealed = self.seal(cookie);
+                if let Ok(value) = HeaderValue::from_str(&sealed.to_string()) {
+                    response.headers_mut().append(header::SET_COOKIE, value);
+                }
+            }
+        }
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{endpoint::WithMiddleware, Body};
+    use alloc::{string::String, vec::Vec};
+
+    // Counts visits in a cookie and logs out on `/logout`.
+    struct Visits;
+
+    impl Endpoint for Visits {
+        type Error = Infallible;
+        async fn respond(&mut self, request: &mut Request) -> Result<Response, Self::Error> {
+            let logout = request.uri().path() == "/logout";
+            let jar = request.cookie_jar_mut().unwrap();
+            if logout {
+                jar.remove(Cookie::from("visits"));
+                return Ok(Response::new(Body::empty()));
+            }
+            let visits = jar
+                .get("visits")
+                .and_then(|cookie| cookie.value().parse::<u32>().ok())
+                .unwrap_or(0)
+                + 1;
+            jar.add(Cookie::new("visits", visits.to_string()));
+            Ok(Response::new(Body::from_text(visits.to_string())))
+        }
+    }
+
+    async fn visit(
+        middleware: &CookieJarMiddleware,
+        path: &str,
+        cookie: Option<&str>,
+    ) -> (String, Vec<String>) {
+        let mut endpoint = WithMiddleware::new(Visits, middleware.clone());
+        let mut request = Request::new(Body::empty());
+        *request.uri_mut() = path.parse().unwrap();
+        if let Some(cookie) = cookie {
+            request
+                .headers_mut()
+                .insert(header::COOKIE, cookie.parse().unwrap());
+        }
+        let response = endpoint.respond(&mut request).await.unwrap();
+        let set_cookies = response
+            .headers()
+            .get_all(header::SET_COOKIE)
+            .iter()
+            .map(|value| String::from(value.to_str().unwrap()))
+            .collect();
+        let body = response.into_body().into_string().await.unwrap();
+        (String::from(body.as_str()), set_cookies)
+    }
+
+    #[tokio::test]
+    async fn cookies_survive_transferred_extensions() {
+        struct Transfer;
+
+        impl Endpoint for Transfer {
+            type Error = Infallible;
+            async fn respond(&mut self, request: &mut Request) -> Result<Response, Self::Error> {
+                request
+                    .cookie_jar_mut()
+                    .unwrap()
+                    .add(Cookie::new("session", "abc"));
+                let mut response = Response::new(Body::empty());
+                request.transfer_extensions(&mut response);
+                Ok(response)
+            }
+        }
+
+        let mut endpoint = WithMiddleware::new(Transfer, CookieJarMiddleware::new());
+        let response = endpoint
+            .respond(&mut Request::new(Body::empty()))
+            .await
+            .unwrap();
+        assert_eq!(response.headers()[header::SET_COOKIE], "session=abc");
+    }
+
+    #[tokio::test]
+    async fn plain_cookies_round_trip() {
+        let middleware = CookieJarMiddleware::new();
+        let (body, set_cookies) = visit(&middleware, "/", None).await;
+        assert_eq!(
+            (body.as_str(), set_cookies.as_slice()),
+            ("1", &[String::from("visits=1")][..])
+        );
+
+        let (body, set_cookies) = visit(&middleware, "/", Some("theme=dark; visits=1")).await;
+        assert_eq!(body, "2");
+        assert_eq!(set_cookies, ["visits=2"]);
+
+        let (_, set_cookies) = visit(&middleware, "/logout", Some("visits=2")).await;
+        assert_eq!(set_cookies.len(), 1);
+        assert!(set_cookies[0].starts_with("visits=; Max-Age=0"));
+    }
+
+    #[tokio::test]
+    async fn untouched_jars_emit_nothing() {
+        struct Silent;
+
+        impl Endpoint for Silent {
+            type Error = Infallible;
+            async fn respond(&mut self, request: &mut Request) -> Result<Response, Self::Error> {
+                assert_eq!(request.cookie_jar().unwrap().get("a").unwrap().value(), "1");
+                Ok(Response::new(Body::empty()))
+            }
+        }
+
+        let mut endpoint = WithMiddleware::new(Silent, CookieJarMiddleware::new());
+        let mut request = Request::new(Body::empty());
+        request
+            .headers_mut()
+            .insert(header::COOKIE, "a=1; malformed; b=2".parse().unwrap());
+        let response = endpoint.respond(&mut request).await.unwrap();
+        assert!(!response.headers().contains_key(header::SET_COOKIE));
+    }
+
+    #[cfg(feature = "cookie-signed")]
+    #[tokio::test]
+    async fn tampered_signed_cookies_are_rejected() {
+        let middleware = CookieJarMiddleware::signed(Key::generate());
+        let (_, set_cookies) = visit(&middleware, "/", None).await;
+        let signed = set_cookies[0].split(';').next().unwrap();
+        // The value is readable after the signature.
+        assert!(signed.starts_with("visits=") && signed.ends_with('1'));
+        assert_ne!(signed, "visits=1");
+
+        let (body, _) = visit(&middleware, "/", Some(signed)).await;
+        assert_eq!(body, "2");
+
+        let tampered = alloc::format!("{}9", signed.strip_suffix('1').unwrap());
+        let (body, _) = visit(&middleware, "/", Some(&tampered)).await;
+        assert_eq!(body, "1");
+
+        let other = CookieJarMiddleware::signed(Key::generate());
+        let (body, _) = visit(&other, "/", Some(signed)).await;
+        assert_eq!(body, "1");
+    }
+
+    #[cfg(feature = "cookie-signed")]
+    #[tokio::test]
+    async fn private_cookies_are_opaque() {
+        let middleware = CookieJarMiddleware::private(Key::generate());
+        let (_, set_cookies) = visit(&middleware, "/", None).await;
+        let sealed = set_cookies[0].split(';').next().unwrap();
+        assert!(sealed.starts_with("visits="));
+        assert!(!sealed.ends_with("visits=1"));
+
+        let (body, _) = visit(&middleware, "/", Some(sealed)).await;
+        assert_eq!(body, "2");
+        let (body, _) = visit(&middleware, "/", Some("visits=5")).await;
+        assert_eq!(body, "1");
+
+        let (_, set_cookies) = visit(&middleware, "/logout", Some(sealed)).await;
+        assert!(set_cookies[0].starts_with("visits=; Max-Age=0"));
+    }
+}
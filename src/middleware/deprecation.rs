@@ -0,0 +1,294 @@
+//! Announcement of deprecated endpoints.
+//!
+//! [`DeprecationNotice`] stamps the `Deprecation` header (RFC 9745), an optional `Sunset`
+//! date (RFC 8594) and an optional link to migration docs on the responses of matching
+//! requests. Each stamped response is counted in
+//! `StatsSnapshot::deprecated_responses` when the `stats` feature is enabled.
+//!
+//! Endpoints can set the same headers themselves with [`ResponseExt::deprecation`] and
+//! [`ResponseExt::sunset`].
+//!
+//! [`ResponseExt::deprecation`]: crate::ResponseExt::deprecation
+//! [`ResponseExt::sunset`]: crate::ResponseExt::sunset
+//!
+//! # Examples
+//!
+//! ```rust
+//! use std::time::{Duration, SystemTime};
+//! use http_kit::middleware::deprecation::DeprecationNotice;
+//!
+//! let announced = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+//! let notice = DeprecationNotice::prefix("/v1")
+//!     .since(announced)
+//!     .sunset(announced + Duration::from_secs(180 * 24 * 3600))
+//!     .link("https://docs.example.com/migrate-to-v2".parse().unwrap());
+//! ```
+
+extern crate std;
+
+use alloc::{format, string::String, sync::Arc};
+use core::{convert::Infallible, fmt};
+use std::time::SystemTime;
+
+use http::{HeaderValue, Uri};
+
+use crate::{date, headers, middleware::MiddlewareError, Endpoint, Middleware, Request, Response};
+
+type Predicate = Arc<dyn Fn(&Request) -> bool + Send + Sync>;
+
+enum Matcher {
+    Prefix(String),
+    Predicate(Predicate),
+}
+
+impl Matcher {
+    fn matches(&self, request: &Request) -> bool {
+        match self {
+            Self::Prefix(prefix) => {
+                let path = request.uri().path();
+                path.strip_prefix(prefix.trim_end_matches('/'))
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+            }
+            Self::Predicate(predicate) => predicate(request),
+        }
+    }
+}
+
+/// Middleware marking the responses of deprecated endpoints.
+///
+/// Matching responses receive `Deprecation`, and `Sunset` and
+/// `Link: <docs>; rel="deprecation"` when configured. Headers the endpoint already set
+/// are left untouched, so a route can announce more precise dates itself.
+#[derive(Clone)]
+pub struct DeprecationNotice {
+    matcher: Arc<Matcher>,
+    deprecation: HeaderValue,
+    sunset: Option<HeaderValue>,
+    link: Option<HeaderValue>,
+}
+
+impl fmt::Debug for DeprecationNotice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let matcher = match &*self.matcher {
+            Matcher::Prefix(prefix) => prefix.as_str(),
+            Matcher::Predicate(_) => "<predicate>",
+        };
+        f.debug_struct("DeprecationNotice")
+            .field("matcher", &matcher)
+            .field("deprecation", &self.deprecation)
+            .field("sunset", &self.sunset)
+            .field("link", &self.link)
+            .finish()
+    }
+}
+
+impl DeprecationNotice {
+    fn new(matcher: Matcher) -> Self {
+        Self {
+            matcher: Arc::new(matcher),
+            deprecation: deprecation_value(None),
+            sunset: None,
+            link: None,
+        }
+    }
+
+    /// Marks requests whose path is `prefix` or lies below it, segment-wise: `/v1`
+    /// matches `/v1` and `/v1/users` but not `/v10`.
+    pub fn prefix(prefix: impl Into<String>) -> Self {
+        Self::new(Matcher::Prefix(prefix.into()))
+    }
+
+    /// Marks requests for which `predicate` returns `true`.
+    ///
+    /// The predicate sees the request before it is passed to the endpoint.
+    pub fn predicate(predicate: impl Fn(&Request) -> bool + Send + Sync + 'static) -> Self {
+        Self::new(Matcher::Predicate(Arc::new(predicate)))
+    }
+
+    /// Announces when the endpoint was deprecated, instead of `Deprecation: true`.
+    #[must_use]
+    pub fn since(mut self, when: SystemTime) -> Self {
+        self.deprecation = deprecation_value(Some(when));
+        self
+    }
+
+    /// Announces when the endpoint will stop responding.
+    #[must_use]
+    pub fn sunset(mut self, when: SystemTime) -> Self {
+        self.sunset = Some(sunset_value(when));
+        self
+    }
+
+    /// Links to documentation about the deprecation, such as a migration guide.
+    #[must_use]
+    pub fn link(mut self, docs: Uri) -> Self {
+        self.link = Some(
+            HeaderValue::from_str(&format!("<{docs}>; rel=\"deprecation\""))
+                .expect("URIs are valid header values"),
+        );
+        self
+    }
+
+    fn stamp(&self, response: &mut Response) {
+        stat!(deprecated_responses);
+        let headers = response.headers_mut();
+        if !headers.contains_key(headers::DEPRECATION) {
+            headers.insert(headers::DEPRECATION, self.deprecation.clone());
+        }
+        if let Some(sunset) = &self.sunset {
+            if !headers.contains_key(headers::SUNSET) {
+                headers.insert(headers::SUNSET, sunset.clone());
+            }
+        }
+        if let Some(link) = &self.link {
+            let linked = headers.get_all(http::header::LINK).iter().any(|value| {
+                value
+                    .to_str()
+                    .is_ok_and(|value| value.contains("rel=\"deprecation\""))
+            });
+            if !linked {
+                headers.append(http::header::LINK, link.clone());
+            }
+        }
+    }
+}
+
+impl Middleware for DeprecationNotice {
+    type Error = Infallible;
+    async fn handle<E: Endpoint>(
+        &mut self,
+        request: &mut Request,
+        mut next: E,
+    ) -> Result<Response, MiddlewareError<E::Error, Self::Error>> {
+        let deprecated = self.matcher.matches(request);
+        let mut response = next
+            .respond(request)
+            .await
+            .map_err(MiddlewareError::Endpoint)?;
+        if deprecated {
+            self.stamp(&mut response);
+        }
+        Ok(response)
+    }
+}
+
+// `Deprecation` is a structured-field date (`@<unix seconds>`), or `true` when the date
+// is not disclosed.
+pub(crate) fn deprecation_value(when: Option<SystemTime>) -> HeaderValue {
+    match when {
+        Some(when) => HeaderValue::from_str(&format!("@{}", date::unix_timestamp(when)))
+            .expect("timestamps are valid header values"),
+        None => HeaderValue::from_static("true"),
+    }
+}
+
+pub(crate) fn sunset_value(when: SystemTime) -> HeaderValue {
+    HeaderValue::from_str(&date::http_date(when)).expect("HTTP dates are valid header values")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{endpoint::WithMiddleware, Body, ResponseExt};
+    use core::time::Duration;
+    use std::time::UNIX_EPOCH;
+
+    struct Route;
+
+    impl Endpoint for Route {
+        type Error = Infallible;
+        async fn respond(&mut self, request: &mut Request) -> Result<Response, Self::Error> {
+            let mut response = Response::new(Body::empty());
+            if request.uri().path() == "/v1/legacy" {
+                response.sunset(UNIX_EPOCH + Duration::from_secs(86_400));
+            }
+            Ok(response)
+        }
+    }
+
+    async fn call(notice: &DeprecationNotice, path: &str) -> Response {
+        let mut endpoint = WithMiddleware::new(Route, notice.clone());
+        let mut request = Request::new(Body::empty());
+        *request.uri_mut() = path.parse().unwrap();
+        endpoint.respond(&mut request).await.unwrap()
+    }
+
+    #[test]
+    fn header_formats() {
+        let at = UNIX_EPOCH + Duration::from_secs(1_688_169_599);
+        assert_eq!(deprecation_value(Some(at)), "@1688169599");
+        assert_eq!(deprecation_value(None), "true");
+        assert_eq!(
+            deprecation_value(Some(UNIX_EPOCH - Duration::from_secs(5))),
+            "@-5"
+        );
+        assert_eq!(sunset_value(at), "Fri, 30 Jun 2023 23:59:59 GMT");
+
+        let mut response = Response::new(Body::empty());
+        response.deprecation(None);
+        response.sunset(at);
+        assert_eq!(response.headers()[headers::DEPRECATION], "true");
+        response.deprecation(Some(at));
+        assert_eq!(response.headers()[headers::DEPRECATION], "@1688169599");
+        assert_eq!(
+            response.headers()[headers::SUNSET],
+            "Fri, 30 Jun 2023 23:59:59 GMT"
+        );
+    }
+
+    #[tokio::test]
+    async fn prefix_matches_whole_segments() {
+        let notice =
+            DeprecationNotice::prefix("/v1/").link("https://docs.example.com/v2".parse().unwrap());
+
+        for path in ["/v1", "/v1/users", "/v1/users?page=2"] {
+            let response = call(&notice, path).await;
+            assert_eq!(response.headers()[headers::DEPRECATION], "true", "{path}");
+            assert_eq!(
+                response.headers()[http::header::LINK],
+                "<https://docs.example.com/v2>; rel=\"deprecation\""
+            );
+        }
+        for path in ["/v10", "/v2/users", "/"] {
+            let response = call(&notice, path).await;
+            assert!(
+                !response.headers().contains_key(headers::DEPRECATION),
+                "{path}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn predicate_matching() {
+        let notice = DeprecationNotice::predicate(|request| {
+            request
+                .uri()
+                .query()
+                .is_some_and(|query| query.contains("format=xml"))
+        });
+        let response = call(&notice, "/reports?format=xml").await;
+        assert!(response.headers().contains_key(headers::DEPRECATION));
+        let response = call(&notice, "/reports?format=json").await;
+        assert!(!response.headers().contains_key(headers::DEPRECATION));
+    }
+
+    #[tokio::test]
+    async fn endpoint_values_are_kept() {
+        let notice = DeprecationNotice::prefix("/v1")
+            .since(UNIX_EPOCH + Duration::from_secs(10))
+            .sunset(UNIX_EPOCH + Duration::from_secs(3 * 86_400));
+
+        let response = call(&notice, "/v1/legacy").await;
+        assert_eq!(response.headers()[headers::DEPRECATION], "@10");
+        assert_eq!(
+            response.headers()[headers::SUNSET],
+            "Fri, 02 Jan 1970 00:00:00 GMT"
+        );
+
+        let response = call(&notice, "/v1/other").await;
+        assert_eq!(
+            response.headers()[headers::SUNSET],
+            "Sun, 04 Jan 1970 00:00:00 GMT"
+        );
+    }
+}
@@ -0,0 +1,158 @@
+//! Rendering of endpoint errors as responses.
+
+use alloc::{string::ToString, sync::Arc};
+use core::{convert::Infallible, fmt};
+
+use crate::{
+    middleware::MiddlewareError, response::text_response, Endpoint, HttpError, Middleware, Request,
+    Response,
+};
+
+type Render = Arc<dyn Fn(&dyn HttpError) -> Response + Send + Sync>;
+
+/// Middleware answering endpoint errors with a response instead of propagating them.
+///
+/// The response is built by the function given to [`ErrorHandler::new`], or by
+/// [`ErrorHandler::render_plain`] by default.
+///
+/// # Examples
+///
+/// ```rust
+/// use http_kit::{middleware::ErrorHandler, Body, Response};
+///
+/// let handler = ErrorHandler::new(|error| {
+///     let mut response = Response::new(Body::from_text(format!("oops: {error}")));
+///     *response.status_mut() = error.status();
+///     response
+/// });
+/// ```
+#[derive(Clone)]
+pub struct ErrorHandler {
+    render: Render,
+}
+
+impl fmt::Debug for ErrorHandler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ErrorHandler").finish_non_exhaustive()
+    }
+}
+
+impl Default for ErrorHandler {
+    fn default() -> Self {
+        Self::new(Self::render_plain)
+    }
+}
+
+impl ErrorHandler {
+    /// Creates the middleware answering errors with the response built by `render`.
+    pub fn new(render: impl Fn(&dyn HttpError) -> Response + Send + Sync + 'static) -> Self {
+        Self {
+            render: Arc::new(render),
+        }
+    }
+
+    /// Renders `error` as a `text/plain` response with the status of the error.
+    ///
+    /// The body is the `Display` text of the error for client errors. For server errors,
+    /// whose messages may reveal internals, it is the canonical reason of the status,
+    /// such as `Internal Server Error`.
+    pub fn render_plain(error: &dyn HttpError) -> Response {
+        let status = error.status();
+        let message = if status.is_server_error() {
+            status
+                .canonical_reason()
+                .unwrap_or("Server Error")
+                .to_string()
+        } else {
+            error.to_string()
+        };
+        text_response(status, message)
+    }
+}
+
+impl Middleware for ErrorHandler {
+    type Error = Infallible;
+    async fn handle<E: Endpoint>(
+        &mut self,
+        request: &mut Request,
+        mut next: E,
+    ) -> Result<Response, MiddlewareError<E::Error, Self::Error>> {
+        match next.respond(request).await {
+            Ok(response) => Ok(response),
+            Err(error) => Ok((self.render)(&error)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{endpoint::WithMiddleware, Body};
+    use alloc::{boxed::Box, format, string::String};
+    use http::{header, HeaderValue, StatusCode};
+
+    http_error!(Conflict, StatusCode::CONFLICT, "user already exists");
+    http_error!(
+        Database,
+        StatusCode::INTERNAL_SERVER_ERROR,
+        "connection to 10.0.0.7 refused"
+    );
+
+    // Fails with a conflict for `/users` and a database error elsewhere.
+    struct Failing;
+
+    impl Endpoint for Failing {
+        type Error = crate::BoxHttpError;
+        async fn respond(&mut self, request: &mut Request) -> Result<Response, Self::Error> {
+            if request.uri().path() == "/users" {
+                Err(Box::new(Conflict::new()))
+            } else {
+                Err(Box::new(Database::new()))
+            }
+        }
+    }
+
+    async fn call(handler: ErrorHandler, path: &str) -> (StatusCode, Option<HeaderValue>, String) {
+        let mut endpoint = WithMiddleware::new(Failing, handler);
+        let mut request = Request::new(Body::empty());
+        *request.uri_mut() = path.parse().unwrap();
+        let response = endpoint.respond(&mut request).await.unwrap();
+        let content_type = response.headers().get(header::CONTENT_TYPE).cloned();
+        let status = response.status();
+        let body = response.into_body().into_string().await.unwrap();
+        (status, content_type, body.as_str().into())
+    }
+
+    #[tokio::test]
+    async fn default_rendering_hides_server_errors() {
+        let (status, content_type, body) = call(ErrorHandler::default(), "/users").await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(content_type.unwrap(), "text/plain; charset=utf-8");
+        assert_eq!(body, "user already exists");
+
+        let (status, _, body) = call(ErrorHandler::default(), "/orders").await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(body, "Internal Server Error");
+    }
+
+    #[tokio::test]
+    async fn custom_rendering() {
+        let handler = ErrorHandler::new(|error| {
+            let body = format!(
+                r#"{{"status":{},"error":"{error}"}}"#,
+                error.status().as_u16()
+            );
+            let mut response = Response::new(Body::from_text(body));
+            *response.status_mut() = error.status();
+            response.headers_mut().insert(
+                header::CONTENT_TYPE,
+                HeaderValue::from_static("application/json"),
+            );
+            response
+        });
+        let (status, content_type, body) = call(handler, "/users").await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(content_type.unwrap(), "application/json");
+        assert_eq!(body, r#"{"status":409,"error":"user already exists"}"#);
+    }
+}
@@ -0,0 +1,164 @@
+//! Middleware built from closures.
+
+use alloc::boxed::Box;
+use core::{any::type_name, fmt, future::Future, pin::Pin};
+
+use crate::{
+    endpoint::EndpointImpl, error::BoxHttpError, middleware::MiddlewareError, Endpoint, HttpError,
+    Middleware, Request, Response,
+};
+
+/// The rest of the chain, as passed to closures given to [`middleware_fn`].
+///
+/// Errors of the chain are boxed, keeping their status code.
+pub struct Next<'a> {
+    endpoint: &'a mut (dyn EndpointImpl + 'a),
+}
+
+impl fmt::Debug for Next<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_fmt(format_args!("Next[{}]", self.endpoint.name()))
+    }
+}
+
+impl Endpoint for Next<'_> {
+    type Error = BoxHttpError;
+    async fn respond(&mut self, request: &mut Request) -> Result<Response, Self::Error> {
+        self.endpoint.respond_inner(request).await
+    }
+}
+
+/// A [`Middleware`] calling a closure, created by [`middleware_fn`].
+#[derive(Clone)]
+pub struct MiddlewareFn<F> {
+    f: F,
+}
+
+impl<F> fmt::Debug for MiddlewareFn<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_fmt(format_args!("MiddlewareFn[{}]", type_name::<F>()))
+    }
+}
+
+/// Creates a middleware from a closure.
+///
+/// The closure receives each request and the [`Next`] step of the chain, and returns a
+/// boxed future producing the response. Errors returned by the closure are reported as
+/// [`MiddlewareError::Middleware`]. State captured by the closure can be updated on
+/// every call.
+///
+/// # Examples
+///
+/// ```rust
+/// use http_kit::middleware::{middleware_fn, AnyMiddleware};
+/// use http_kit::{BoxHttpError, Endpoint};
+///
+/// let middleware = middleware_fn(|request, mut next| {
+///     Box::pin(async move {
+///         let mut response = next.respond(request).await?;
+///         response
+///             .headers_mut()
+///             .insert("x-powered-by", "http-kit".parse().unwrap());
+///         Ok::<_, BoxHttpError>(response)
+///     })
+/// });
+/// let middleware = AnyMiddleware::new(middleware);
+/// ```
+pub fn middleware_fn<F, Err>(f: F) -> MiddlewareFn<F>
+where
+    F: for<'a> FnMut(
+            &'a mut Request,
+            Next<'a>,
+        ) -> Pin<Box<dyn Future<Output = Result<Response, Err>> + Send + 'a>>
+        + Send,
+    Err: HttpError,
+{
+    MiddlewareFn { f }
+}
+
+impl<F, Err> Middleware for MiddlewareFn<F>
+where
+    F: for<'a> FnMut(
+            &'a mut Request,
+            Next<'a>,
+        ) -> Pin<Box<dyn Future<Output = Result<Response, Err>> + Send + 'a>>
+        + Send,
+    Err: HttpError,
+{
+    type Error = Err;
+    async fn handle<E: Endpoint>(
+        &mut self,
+        request: &mut Request,
+        mut next: E,
+    ) -> Result<Response, MiddlewareError<E::Error, Self::Error>> {
+        let next = Next {
+            endpoint: &mut next,
+        };
+        (self.f)(request, next)
+            .await
+            .map_err(MiddlewareError::Middleware)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        endpoint::{endpoint_fn, AnyEndpoint, WithMiddleware},
+        middleware::AnyMiddleware,
+        Body,
+    };
+    use alloc::{format, string::ToString};
+    use core::convert::Infallible;
+    use http::{HeaderValue, StatusCode};
+
+    #[tokio::test]
+    async fn closures_as_endpoint_and_middleware() {
+        let echo = endpoint_fn(|request: &mut Request| {
+            let body = core::mem::take(request.body_mut());
+            async move { Ok::<_, Infallible>(Response::new(body)) }
+        });
+        assert!(format!("{echo:?}").starts_with("EndpointFn["));
+
+        let mut calls = 0u32;
+        let count = middleware_fn(move |request, mut next| {
+            calls += 1;
+            Box::pin(async move {
+                let mut response = next.respond(request).await?;
+                response
+                    .headers_mut()
+                    .insert("x-call", HeaderValue::from(calls));
+                Ok::<_, BoxHttpError>(response)
+            })
+        });
+        assert!(format!("{count:?}").contains("from_fn"));
+
+        let mut endpoint = WithMiddleware::new(AnyEndpoint::new(echo), AnyMiddleware::new(count));
+        for call in 1..=2 {
+            let mut request = Request::new(Body::from_text("ping"));
+            let response = endpoint.respond(&mut request).await.unwrap();
+            assert_eq!(response.headers()["x-call"], call.to_string().as_str());
+            assert_eq!(response.into_body().into_string().await.unwrap(), "ping");
+        }
+    }
+
+    #[tokio::test]
+    async fn chain_errors_keep_their_status() {
+        let failing = endpoint_fn(|_request: &mut Request| async {
+            Err::<Response, _>(
+                crate::Error::msg("gone")
+                    .set_status(StatusCode::GONE)
+                    .into_boxed_http_error(),
+            )
+        });
+        let passthrough =
+            middleware_fn(|request, mut next| Box::pin(async move { next.respond(request).await }));
+        let mut endpoint = WithMiddleware::new(failing, passthrough);
+        let error = endpoint
+            .respond(&mut Request::new(Body::empty()))
+            .await
+            .unwrap_err();
+        assert!(matches!(error, MiddlewareError::Middleware(_)));
+        assert_eq!(error.status(), StatusCode::GONE);
+    }
+}
@@ -0,0 +1,137 @@
+//! Serving `HEAD` requests with `GET` endpoints.
+
+use core::convert::Infallible;
+
+use http::{header, HeaderValue, Method};
+
+use crate::{middleware::MiddlewareError, Body, Endpoint, Middleware, Request, Response};
+
+/// Request extension telling [`HandleHead`] that the endpoint handles `HEAD` itself.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NativeHead;
+
+/// Middleware answering `HEAD` requests with the headers of the `GET` response.
+///
+/// A `HEAD` request is passed to the endpoint as `GET`, and its method is restored to
+/// `HEAD` afterwards. The response body is then replaced by an empty one. When the length
+/// of the discarded body is known, `Content-Length` is set to it; otherwise the framing
+/// headers are left as the endpoint set them, rather than made up.
+///
+/// Requests carrying the [`NativeHead`] extension are passed through unchanged.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HandleHead;
+
+impl HandleHead {
+    /// Creates the middleware.
+    pub const fn new() -> Self {
+        Self
+    }
+}
+
+impl Middleware for HandleHead {
+    type Error = Infallible;
+    async fn handle<E: Endpoint>(
+        &mut self,
+        request: &mut Request,
+        mut next: E,
+    ) -> Result<Response, MiddlewareError<E::Error, Self::Error>> {
+        if request.method() != Method::HEAD || request.extensions().get::<NativeHead>().is_some() {
+            return next
+                .respond(request)
+                .await
+                .map_err(MiddlewareError::Endpoint);
+        }
+
+        *request.method_mut() = Method::GET;
+        let result = next.respond(request).await;
+        *request.method_mut() = Method::HEAD;
+        let mut response = result.map_err(MiddlewareError::Endpoint)?;
+
+        // The empty body keeps the MIME type, which the `Content-Type` may come from.
+        let mut empty = Body::empty();
+        if let Some(mime) = response.body().mime() {
+            empty = empty.with_mime(mime.clone());
+        }
+        let body = core::mem::replace(response.body_mut(), empty);
+        if let Some(len) = body.len() {
+            response
+                .headers_mut()
+                .insert(header::CONTENT_LENGTH, HeaderValue::from(len));
+        }
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endpoint::WithMiddleware;
+    use alloc::{string::String, vec::Vec};
+    use bytes::Bytes;
+    use http::StatusCode;
+
+    // Serves `GET` only, streaming the body for `/stream`.
+    struct GetOnly;
+
+    impl Endpoint for GetOnly {
+        type Error = Infallible;
+        async fn respond(&mut self, request: &mut Request) -> Result<Response, Self::Error> {
+            if request.method() != Method::GET {
+                let mut response = Response::new(Body::empty());
+                *response.status_mut() = StatusCode::METHOD_NOT_ALLOWED;
+                return Ok(response);
+            }
+            let body = if request.uri().path() == "/stream" {
+                let chunks: Vec<Result<Bytes, Infallible>> = alloc::vec![Ok(Bytes::from("hello"))];
+                Body::from_stream(futures_lite::stream::iter(chunks))
+            } else {
+                Body::from_text("hello, world")
+            };
+            let mut response = Response::new(body);
+            response.headers_mut().insert(
+                "x-method",
+                HeaderValue::from_str(request.method().as_str()).unwrap(),
+            );
+            Ok(response)
+        }
+    }
+
+    async fn head(path: &str, native: bool) -> (Request, Response) {
+        let mut endpoint = WithMiddleware::new(GetOnly, HandleHead::new());
+        let mut request = Request::new(Body::empty());
+        *request.method_mut() = Method::HEAD;
+        *request.uri_mut() = path.parse().unwrap();
+        if native {
+            request.extensions_mut().insert(NativeHead);
+        }
+        let response = endpoint.respond(&mut request).await.unwrap();
+        (request, response)
+    }
+
+    #[tokio::test]
+    async fn head_is_served_by_get() {
+        let (request, response) = head("/", false).await;
+        assert_eq!(request.method(), Method::HEAD);
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["x-method"], "GET");
+        assert_eq!(response.headers()[header::CONTENT_LENGTH], "12");
+        assert_eq!(response.body().len(), Some(0));
+        assert_eq!(response.body().mime(), Some(&mime::TEXT_PLAIN_UTF_8));
+        let body = response.into_body().into_string().await.unwrap();
+        assert_eq!(String::from(body.as_str()), "");
+    }
+
+    #[tokio::test]
+    async fn unknown_lengths_are_not_made_up() {
+        let (_, response) = head("/stream", false).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(!response.headers().contains_key(header::CONTENT_LENGTH));
+        assert_eq!(response.body().len(), Some(0));
+    }
+
+    #[tokio::test]
+    async fn native_head_endpoints_are_left_alone() {
+        let (_, response) = head("/", true).await;
+        assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+    }
+}
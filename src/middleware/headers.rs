@@ -0,0 +1,143 @@
+//! Middleware adding headers to every response.
+
+use core::convert::Infallible;
+
+use http::{header, HeaderMap, HeaderName, HeaderValue};
+
+use crate::{middleware::MiddlewareError, Endpoint, Middleware, Request, Response};
+
+// Inserts every header of `defaults` that the response does not set itself.
+fn fill(defaults: &HeaderMap, response: &mut Response) {
+    for (name, value) in defaults {
+        if !response.headers().contains_key(name) {
+            response.headers_mut().insert(name.clone(), value.clone());
+        }
+    }
+}
+
+/// Middleware adding headers to responses that do not already set them.
+#[derive(Debug, Clone, Default)]
+pub struct DefaultHeaders {
+    headers: HeaderMap,
+}
+
+impl DefaultHeaders {
+    /// Creates the middleware without any header.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a default header.
+    #[must_use]
+    pub fn header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.headers.insert(name, value);
+        self
+    }
+
+    pub(crate) fn apply(&self, response: &mut Response) {
+        fill(&self.headers, response);
+    }
+}
+
+impl Middleware for DefaultHeaders {
+    type Error = Infallible;
+    async fn handle<E: Endpoint>(
+        &mut self,
+        request: &mut Request,
+        mut next: E,
+    ) -> Result<Response, MiddlewareError<E::Error, Self::Error>> {
+        let mut response = next
+            .respond(request)
+            .await
+            .map_err(MiddlewareError::Endpoint)?;
+        self.apply(&mut response);
+        Ok(response)
+    }
+}
+
+/// Middleware adding security-related headers to responses.
+///
+/// Headers already set by the endpoint are left untouched.
+#[derive(Debug, Clone, Default)]
+pub struct SecurityHeaders {
+    headers: HeaderMap,
+}
+
+impl SecurityHeaders {
+    /// Creates the middleware without any header.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates the middleware with a strict set suitable for APIs:
+    ///
+    /// - `Strict-Transport-Security: max-age=63072000; includeSubDomains`
+    /// - `X-Content-Type-Options: nosniff`
+    /// - `X-Frame-Options: DENY`
+    /// - `Referrer-Policy: no-referrer`
+    /// - `Content-Security-Policy: default-src 'none'; frame-ancestors 'none'`
+    /// - `Cross-Origin-Opener-Policy: same-origin`
+    /// - `Cross-Origin-Resource-Policy: same-origin`
+    pub fn strict() -> Self {
+        Self::new()
+            .header(
+                header::STRICT_TRANSPORT_SECURITY,
+                HeaderValue::from_static("max-age=63072000; includeSubDomains"),
+            )
+            .header(
+                header::X_CONTENT_TYPE_OPTIONS,
+                HeaderValue::from_static("nosniff"),
+            )
+            .header(header::X_FRAME_OPTIONS, HeaderValue::from_static("DENY"))
+            .header(
+                header::REFERRER_POLICY,
+                HeaderValue::from_static("no-referrer"),
+            )
+            .header(
+                header::CONTENT_SECURITY_POLICY,
+                HeaderValue::from_static("default-src 'none'; frame-ancestors 'none'"),
+            )
+            .header(
+                HeaderName::from_static("cross-origin-opener-policy"),
+                HeaderValue::from_static("same-origin"),
+            )
+            .header(
+                HeaderName::from_static("cross-origin-resource-policy"),
+                HeaderValue::from_static("same-origin"),
+            )
+    }
+
+    /// Adds or replaces a security header.
+    #[must_use]
+    pub fn header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.headers.insert(name, value);
+        self
+    }
+
+    /// Removes a header from the set.
+    #[must_use]
+    pub fn without(mut self, name: HeaderName) -> Self {
+        self.headers.remove(name);
+        self
+    }
+
+    pub(crate) fn apply(&self, response: &mut Response) {
+        fill(&self.headers, response);
+    }
+}
+
+impl Middleware for SecurityHeaders {
+    type Error = Infallible;
+    async fn handle<E: Endpoint>(
+        &mut self,
+        request: &mut Request,
+        mut next: E,
+    ) -> Result<Response, MiddlewareError<E::Error, Self::Error>> {
+        let mut response = next
+            .respond(request)
+            .await
+            .map_err(MiddlewareError::Endpoint)?;
+        self.apply(&mut response);
+        Ok(response)
+    }
+}
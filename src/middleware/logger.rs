@@ -0,0 +1,403 @@
+//! Access logging through a pluggable sink.
+
+use alloc::{
+    boxed::Box,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{convert::Infallible, fmt, time::Duration};
+
+use http::{HeaderMap, HeaderName, HeaderValue, Method, StatusCode};
+
+use super::rate_limit::Clock;
+#[cfg(feature = "std")]
+use super::rate_limit::MonotonicClock;
+use crate::{
+    middleware::MiddlewareError,
+    redact::{is_sensitive_header, REDACTED},
+    Endpoint, HttpError, Middleware, Request, Response,
+};
+
+/// What [`Logger`] records about one request.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct LogRecord {
+    /// The request method.
+    pub method: Method,
+    /// The request path, without the query string, which may carry secrets.
+    pub path: String,
+    /// The response status, or the status of the endpoint error.
+    pub status: StatusCode,
+    /// The length of the response body, when known without reading it.
+    pub body_size: Option<usize>,
+    /// The time the endpoint took to respond.
+    pub elapsed: Duration,
+    /// The request headers, with sensitive values redacted, if enabled with
+    /// [`Logger::include_headers`].
+    pub headers: Option<HeaderMap>,
+    /// The message of the endpoint error, if the endpoint failed, or of the panic
+    /// caught by [`CatchPanic`](super::catch_panic::CatchPanic) further down the chain.
+    pub error: Option<String>,
+}
+
+impl fmt::Display for LogRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {}", self.method, self.path, self.status.as_u16())?;
+        if let Some(size) = self.body_size {
+            write!(f, " {size}B")?;
+        }
+        write!(f, " {:?}", self.elapsed)?;
+        if let Some(error) = &self.error {
+            write!(f, ": {error}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Destination of the records of a [`Logger`].
+///
+/// Implemented for closures taking a [`LogRecord`] and for `Vec<LogRecord>`, which
+/// collects the records, for tests. With the `log` and `tracing` features,
+/// [`LogCrateSink`] and [`TracingSink`] forward them to those crates.
+pub trait LogSink: Send {
+    /// Records the outcome of one request.
+    fn log(&mut self, record: LogRecord);
+}
+
+impl<F: FnMut(LogRecord) + Send> LogSink for F {
+    fn log(&mut self, record: LogRecord) {
+        self(record);
+    }
+}
+
+impl LogSink for Vec<LogRecord> {
+    fn log(&mut self, record: LogRecord) {
+        self.push(record);
+    }
+}
+
+/// A [`LogSink`] writing records to the `log` crate, under the `http_kit` target.
+///
+/// Server errors are logged at the error level, client errors at the warn level and
+/// anything else at the info level.
+#[cfg(feature = "log")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LogCrateSink;
+
+#[cfg(feature = "log")]
+impl LogSink for LogCrateSink {
+    fn log(&mut self, record: LogRecord) {
+        let level = if record.status.is_server_error() {
+            log::Level::Error
+        } else if record.status.is_client_error() {
+            log::Level::Warn
+        } else {
+            log::Level::Info
+        };
+        log::log!(target: "http_kit", level, "{record}");
+    }
+}
+
+/// A [`LogSink`] emitting records as `tracing` events, with one field per record field.
+///
+/// Levels follow [`LogCrateSink`].
+#[cfg(feature = "tracing")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TracingSink;
+
+#[cfg(feature = "tracing")]
+impl LogSink for TracingSink {
+    fn log(&mut self, record: LogRecord) {
+        macro_rules! emit {
+            ($level:expr) => {
+                tracing::event!(
+                    target: "http_kit",
+                    $level,
+                    method = %record.method,
+                    path = %record.path,
+                    status = record.status.as_u16(),
+                    body_size = record.body_size,
+                    elapsed_ms = record.elapsed.as_secs_f64() * 1000.0,
+                    error = record.error.as_deref(),
+                    "{record}"
+                )
+            };
+        }
+        if record.status.is_server_error() {
+            emit!(tracing::Level::ERROR);
+        } else if record.status.is_client_error() {
+            emit!(tracing::Level::WARN);
+        } else {
+            emit!(tracing::Level::INFO);
+        }
+    }
+}
+
+/// Middleware logging every request to a [`LogSink`].
+///
+/// Each record holds the method, path, status, body size and elapsed time of a request.
+/// Endpoint errors are logged with their [`HttpError::status`] and message, then returned
+/// unchanged.
+///
+/// Request headers are only recorded when enabled with [`Logger::include_headers`].
+/// Values of sensitive headers are then replaced with [`REDACTED`]: by default those
+/// recognized by [`is_sensitive_header`], such as `Authorization` and `Cookie`, and any
+/// header added with [`Logger::redact_header`].
+///
+/// # Examples
+///
+/// ```rust
+/// # #[cfg(feature = "std")]
+/// # {
+/// use http_kit::middleware::{LogRecord, Logger};
+///
+/// let logger = Logger::new(|record: LogRecord| eprintln!("{record}"))
+///     .include_headers()
+///     .redact_header(http::HeaderName::from_static("x-session"));
+/// # }
+/// ```
+pub struct Logger<S> {
+    sink: S,
+    clock: Box<dyn Clock>,
+    include_headers: bool,
+    redacted: Vec<HeaderName>,
+}
+
+impl<S: fmt::Debug> fmt::Debug for Logger<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Logger")
+            .field("sink", &self.sink)
+            .field("include_headers", &self.include_headers)
+            .field("redacted", &self.redacted)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S: LogSink> Logger<S> {
+    /// Creates a logger writing to `sink`, timed by a [`MonotonicClock`].
+    #[cfg(feature = "std")]
+    pub fn new(sink: S) -> Self {
+        Self::with_clock(sink, MonotonicClock::new())
+    }
+
+    /// Creates a logger like [`Logger::new`], timed by `clock`.
+    pub fn with_clock(sink: S, clock: impl Clock + 'static) -> Self {
+        Self {
+            sink,
+            clock: Box::new(clock),
+            include_headers: false,
+            redacted: Vec::new(),
+        }
+    }
+
+    /// Records the request headers, redacting sensitive values.
+    #[must_use]
+    pub fn include_headers(mut self) -> Self {
+        self.include_headers = true;
+        self
+    }
+
+    /// Redacts the values of the header `name` in addition to the default ones.
+    #[must_use]
+    pub fn redact_header(mut self, name: HeaderName) -> Self {
+        self.redacted.push(name);
+        self
+    }
+
+    /// Returns the sink.
+    pub const fn sink(&self) -> &S {
+        &self.sink
+    }
+
+    /// Returns the sink mutably.
+    pub fn sink_mut(&mut self) -> &mut S {
+        &mut self.sink
+    }
+
+    fn headers(&self, request: &Request) -> HeaderMap {
+        let mut headers = request.headers().clone();
+        for (name, value) in headers.iter_mut() {
+            if is_sensitive_header(name) || self.redacted.contains(name) {
+                *value = HeaderValue::from_static(REDACTED);
+            }
+        }
+        headers
+    }
+}
+
+impl<S: LogSink> Middleware for Logger<S> {
+    type Error = Infallible;
+    async fn handle<E: Endpoint>(
+        &mut self,
+        request: &mut Request,
+        mut next: E,
+    ) -> Result<Response, MiddlewareError<E::Error, Self::Error>> {
+        let method = request.method().clone();
+        let path = request.uri().path().to_string();
+        let headers = self.include_headers.then(|| self.headers(request));
+
+        let start = self.clock.now();
+        let result = next.respond(request).await;
+        let elapsed = self.clock.now().saturating_sub(start);
+
+        let (status, body_size, error) = match &result {
+            Ok(response) => (
+                response.status(),
+                response.body().len(),
+                panic_message(response),
+            ),
+            Err(error) => (error.status(), None, Some(error.to_string())),
+        };
+        self.sink.log(LogRecord {
+            method,
+            path,
+            status,
+            body_size,
+            elapsed,
+            headers,
+            error,
+        });
+        result.map_err(MiddlewareError::Endpoint)
+    }
+}
+
+// The message of a panic caught by `CatchPanic` further down the chain.
+#[cfg(feature = "std")]
+fn panic_message(response: &Response) -> Option<String> {
+    use super::catch_panic::PanicMessage;
+
+    let PanicMessage(message) = response.extensions().get::<PanicMessage>()?;
+    Some(alloc::format!("panicked: {message}"))
+}
+
+#[cfg(not(feature = "std"))]
+fn panic_message(_: &Response) -> Option<String> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Body;
+    use alloc::sync::Arc;
+    use core::sync::atomic::{AtomicU64, Ordering};
+    use http::header;
+
+    http_error!(
+        Unavailable,
+        StatusCode::SERVICE_UNAVAILABLE,
+        "upstream is down"
+    );
+
+    // Takes 25 ms on the shared clock, and fails for `/down`.
+    struct Slow(Arc<AtomicU64>);
+
+    impl Endpoint for Slow {
+        type Error = Unavailable;
+        async fn respond(&mut self, request: &mut Request) -> Result<Response, Self::Error> {
+            self.0.fetch_add(25, Ordering::SeqCst);
+            if request.uri().path() == "/down" {
+                return Err(Unavailable::new());
+            }
+            let mut response = Response::new(Body::from_text("created"));
+            *response.status_mut() = StatusCode::CREATED;
+            Ok(response)
+        }
+    }
+
+    fn logger() -> (Logger<Vec<LogRecord>>, Slow) {
+        let millis = Arc::new(AtomicU64::new(0));
+        let clock = {
+            let millis = millis.clone();
+            move || Duration::from_millis(millis.load(Ordering::SeqCst))
+        };
+        (Logger::with_clock(Vec::new(), clock), Slow(millis))
+    }
+
+    fn request(method: Method, uri: &str) -> Request {
+        let mut request = Request::new(Body::empty());
+        *request.method_mut() = method;
+        *request.uri_mut() = uri.parse().unwrap();
+        let headers = request.headers_mut();
+        headers.insert(
+            header::AUTHORIZATION,
+            HeaderValue::from_static("Bearer t0k3n"),
+        );
+        headers.insert(header::COOKIE, HeaderValue::from_static("session=abc"));
+        headers.insert("x-session", HeaderValue::from_static("abc"));
+        headers.insert(header::ACCEPT, HeaderValue::from_static("text/plain"));
+        request
+    }
+
+    #[tokio::test]
+    async fn successful_requests_are_recorded() {
+        let (mut logger, mut endpoint) = logger();
+        let mut request = request(Method::POST, "/users?token=secret");
+        let response = logger.handle(&mut request, &mut endpoint).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+
+        let [record] = logger.sink().as_slice() else {
+            panic!("expected one record");
+        };
+        assert_eq!(record.method, Method::POST);
+        assert_eq!(record.path, "/users");
+        assert_eq!(record.status, StatusCode::CREATED);
+        assert_eq!(record.body_size, Some(7));
+        assert_eq!(record.elapsed, Duration::from_millis(25));
+        assert!(record.headers.is_none());
+        assert!(record.error.is_none());
+        assert_eq!(record.to_string(), "POST /users 201 7B 25ms");
+    }
+
+    #[tokio::test]
+    async fn errors_are_recorded_and_propagated() {
+        let (logger, mut endpoint) = logger();
+        let mut logger = logger
+            .include_headers()
+            .redact_header(HeaderName::from_static("x-session"));
+        let mut request = request(Method::GET, "/down");
+        let error = logger
+            .handle(&mut request, &mut endpoint)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            MiddlewareError::Endpoint(Unavailable { .. })
+        ));
+
+        let record = &logger.sink()[0];
+        assert_eq!(record.status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(record.body_size, None);
+        assert_eq!(record.error.as_deref(), Some("upstream is down"));
+        assert_eq!(record.to_string(), "GET /down 503 25ms: upstream is down");
+
+        let headers = record.headers.as_ref().unwrap();
+        assert_eq!(headers[header::AUTHORIZATION], REDACTED);
+        assert_eq!(headers[header::COOKIE], REDACTED);
+        assert_eq!(headers["x-session"], REDACTED);
+        assert_eq!(headers[header::ACCEPT], "text/plain");
+        // The request itself is left untouched.
+        assert_eq!(request.headers()[header::AUTHORIZATION], "Bearer t0k3n");
+    }
+
+    #[cfg(feature = "std")]
+    #[tokio::test]
+    async fn caught_panics_are_recorded() {
+        use crate::{endpoint::endpoint_fn, middleware::catch_panic::CatchPanic};
+
+        let (mut logger, _) = logger();
+        let panicking = endpoint_fn(|_: &mut Request| async {
+            if true {
+                panic!("lock poisoned");
+            }
+            Ok::<_, Infallible>(Response::new(Body::empty()))
+        });
+        let mut endpoint = crate::endpoint::WithMiddleware::new(panicking, CatchPanic::new());
+        let mut request = request(Method::GET, "/");
+        logger.handle(&mut request, &mut endpoint).await.unwrap();
+
+        let record = &logger.sink()[0];
+        assert_eq!(record.status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(record.error.as_deref(), Some("panicked: lock poisoned"));
+    }
+}
@@ -0,0 +1,342 @@
+//! API versioning through vendored media types.
+//!
+//! [`MediaVersion`] negotiates an API version from media types such as
+//! `application/vnd.myapp.v2+json`. The resolved version is stored in the request
+//! extensions as [`ApiVersion`] so endpoints can branch on it, and the response
+//! `Content-Type` is stamped with the matching vendored type.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use http_kit::{Request, Response, Endpoint, Body};
+//! use http_kit::endpoint::WithMiddleware;
+//! use http_kit::middleware::media_version::{ApiVersion, MediaVersion};
+//! use core::convert::Infallible;
+//!
+//! struct Users;
+//!
+//! impl Endpoint for Users {
+//!     type Error = Infallible;
+//!     async fn respond(&mut self, request: &mut Request) -> Result<Response, Self::Error> {
+//!         let ApiVersion(version) = *request.extensions().get::<ApiVersion>().unwrap();
+//!         let body = if version >= 2 { r#"{"full_name":"Alice"}"# } else { r#"{"name":"Alice"}"# };
+//!         Ok(Response::new(Body::from_bytes(body)))
+//!     }
+//! }
+//!
+//! let endpoint = WithMiddleware::new(Users, MediaVersion::new("vnd.myapp", [1, 2]));
+//! ```
+
+use alloc::{format, string::String, vec::Vec};
+use core::convert::Infallible;
+
+use http::{header, HeaderValue, Method, StatusCode};
+use mime::Mime;
+
+use crate::{
+    middleware::MiddlewareError, response::text_response, Endpoint, Middleware, Request, Response,
+};
+
+/// The API version resolved by [`MediaVersion`], stored in the request extensions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ApiVersion(pub u32);
+
+/// Middleware negotiating an API version from vendored media types.
+///
+/// The `Accept` header is searched for `application/<vendor>.v<N>+json`. Plain
+/// `application/json` (or a wildcard, or no `Accept` header at all) resolves to the
+/// latest supported version. Requests asking only for unsupported versions are rejected
+/// with `406 Not Acceptable`, listing the supported media types in the body.
+///
+/// For `POST`, `PUT` and `PATCH` requests the `Content-Type` is validated the same way,
+/// and a non-JSON or unsupported vendored type is rejected with `415 Unsupported Media Type`.
+///
+/// JSON responses get their `Content-Type` replaced with the vendored type of the
+/// resolved version, and `Vary: Accept` is appended.
+#[derive(Debug, Clone)]
+pub struct MediaVersion {
+    vendor: String,
+    supported: Vec<u32>,
+}
+
+impl MediaVersion {
+    /// Creates a new middleware for the given vendor tree (e.g. `vnd.myapp`) and supported versions.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `supported` is empty.
+    pub fn new(vendor: impl Into<String>, supported: impl IntoIterator<Item = u32>) -> Self {
+        let mut supported: Vec<u32> = supported.into_iter().collect();
+        assert!(
+            !supported.is_empty(),
+            "MediaVersion requires at least one supported version"
+        );
+        supported.sort_unstable();
+        supported.dedup();
+        Self {
+            vendor: vendor.into(),
+            supported,
+        }
+    }
+
+    /// Returns the latest supported version.
+    pub fn latest(&self) -> ApiVersion {
+        ApiVersion(*self.supported.last().expect("supported is never empty"))
+    }
+
+    /// Returns the vendored media type for the given version, e.g. `application/vnd.myapp.v2+json`.
+    pub fn media_type(&self, version: ApiVersion) -> String {
+        format!("application/{}.v{}+json", self.vendor, version.0)
+    }
+
+    fn is_supported(&self, version: u32) -> bool {
+        self.supported.contains(&version)
+    }
+
+    // Classifies a media type: `Some(Some(v))` for a vendored type, `Some(None)` for
+    // a type meaning "latest", `None` for anything unrelated.
+    fn classify(&self, mime: &Mime) -> Option<Option<u32>> {
+        if mime.type_() == mime::STAR {
+            return Some(None);
+        }
+        if mime.type_() != mime::APPLICATION {
+            return None;
+        }
+        if mime.subtype() == mime::STAR || mime.subtype() == mime::JSON {
+            return Some(None);
+        }
+        if mime.suffix() != Some(mime::JSON) {
+            return None;
+        }
+        let version = mime
+            .subtype()
+            .as_str()
+            .strip_prefix(self.vendor.as_str())?
+            .strip_prefix(".v")?
+            .parse()
+            .ok()?;
+        Some(Some(version))
+    }
+
+    fn negotiate(&self, accept: Option<&HeaderValue>) -> Option<ApiVersion> {
+        let Some(accept) = accept.and_then(|value| value.to_str().ok()) else {
+            return Some(self.latest());
+        };
+
+        let mut best: Option<(f32, ApiVersion)> = None;
+        for mime in accept
+            .split(',')
+            .filter_map(|item| item.trim().parse::<Mime>().ok())
+        {
+            let q = mime
+                .get_param("q")
+                .and_then(|q| q.as_str().parse::<f32>().ok())
+                .unwrap_or(1.0);
+            if q <= 0.0 {
+                continue;
+            }
+            let version = match self.classify(&mime) {
+                Some(Some(version)) if self.is_supported(version) => ApiVersion(version),
+                Some(None) => self.latest(),
+                _ => continue,
+            };
+            if best.is_none_or(|(best_q, _)| q > best_q) {
+                best = Some((q, version));
+            }
+        }
+        best.map(|(_, version)| version)
+    }
+
+    fn supported_list(&self) -> String {
+        self.supported
+            .iter()
+            .map(|version| self.media_type(ApiVersion(*version)))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn reject(&self, status: StatusCode) -> Response {
+        text_response(
+            status,
+            format!("Supported media types: {}", self.supported_list()),
+        )
+    }
+
+    fn content_type_acceptable(&self, request: &Request) -> bool {
+        let Some(content_type) = request.headers().get(header::CONTENT_TYPE) else {
+            return true;
+        };
+        let Some(mime) = content_type
+            .to_str()
+            .ok()
+            .and_then(|value| value.parse::<Mime>().ok())
+        else {
+            return false;
+        };
+        match self.classify(&mime) {
+            Some(Some(version)) => self.is_supported(version),
+            Some(None) => mime.type_() == mime::APPLICATION && mime.subtype() == mime::JSON,
+            None => false,
+        }
+    }
+}
+
+impl Middleware for MediaVersion {
+    type Error = Infallible;
+    async fn handle<E: Endpoint>(
+        &mut self,
+        request: &mut Request,
+        mut next: E,
+    ) -> Result<Response, MiddlewareError<E::Error, Self::Error>> {
+        let Some(version) = self.negotiate(request.headers().get(header::ACCEPT)) else {
+            return Ok(self.reject(StatusCode::NOT_ACCEPTABLE));
+        };
+
+        let writes = matches!(
+            *request.method(),
+            Method::POST | Method::PUT | Method::PATCH
+        );
+        if writes && !self.content_type_acceptable(request) {
+            return Ok(self.reject(StatusCode::UNSUPPORTED_MEDIA_TYPE));
+        }
+
+        request.extensions_mut().insert(version);
+        let mut response = next
+            .respond(request)
+            .await
+            .map_err(MiddlewareError::Endpoint)?;
+
+        let is_json = response
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<Mime>().ok())
+            .or_else(|| response.body().mime().cloned())
+            .is_some_and(|mime| {
+                mime.type_() == mime::APPLICATION
+                    && (mime.subtype() == mime::JSON || mime.suffix() == Some(mime::JSON))
+            });
+        if is_json {
+            if let Ok(value) = HeaderValue::from_str(&self.media_type(version)) {
+                response.headers_mut().insert(header::CONTENT_TYPE, value);
+            }
+        }
+        response
+            .headers_mut()
+            .append(header::VARY, HeaderValue::from_static("accept"));
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{endpoint::WithMiddleware, Body};
+    use alloc::string::ToString;
+
+    struct Versioned;
+
+    impl Endpoint for Versioned {
+        type Error = Infallible;
+        async fn respond(&mut self, request: &mut Request) -> Result<Response, Self::Error> {
+            let version = request.extensions().get::<ApiVersion>().unwrap().0;
+            let mut response = Response::new(Body::from_text(version.to_string()));
+            response.headers_mut().insert(
+                header::CONTENT_TYPE,
+                HeaderValue::from_static("application/json"),
+            );
+            Ok(response)
+        }
+    }
+
+    fn endpoint() -> WithMiddleware<Versioned, MediaVersion> {
+        WithMiddleware::new(Versioned, MediaVersion::new("vnd.myapp", [1, 2, 3]))
+    }
+
+    fn build(method: Method, accept: Option<&'static str>) -> Request {
+        let mut request = Request::new(Body::empty());
+        *request.method_mut() = method;
+        if let Some(accept) = accept {
+            request
+                .headers_mut()
+                .insert(header::ACCEPT, HeaderValue::from_static(accept));
+        }
+        request
+    }
+
+    #[tokio::test]
+    async fn selects_requested_version() {
+        let mut request = build(Method::GET, Some("application/vnd.myapp.v2+json"));
+        let mut response = endpoint().respond(&mut request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "application/vnd.myapp.v2+json"
+        );
+        assert_eq!(response.headers()[header::VARY], "accept");
+        assert_eq!(response.body_mut().as_str().await.unwrap(), "2");
+    }
+
+    #[tokio::test]
+    async fn prefers_highest_quality_supported_version() {
+        let mut request = build(
+            Method::GET,
+            Some("application/vnd.myapp.v9+json, application/vnd.myapp.v1+json;q=0.5, application/vnd.myapp.v2+json;q=0.9"),
+        );
+        let mut response = endpoint().respond(&mut request).await.unwrap();
+        assert_eq!(response.body_mut().as_str().await.unwrap(), "2");
+    }
+
+    #[tokio::test]
+    async fn plain_json_and_missing_accept_mean_latest() {
+        for accept in [Some("application/json"), Some("*/*"), None] {
+            let mut request = build(Method::GET, accept);
+            let mut response = endpoint().respond(&mut request).await.unwrap();
+            assert_eq!(response.body_mut().as_str().await.unwrap(), "3");
+            assert_eq!(
+                response.headers()[header::CONTENT_TYPE],
+                "application/vnd.myapp.v3+json"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn unsupported_version_is_not_acceptable() {
+        let mut request = build(Method::GET, Some("application/vnd.myapp.v7+json"));
+        let mut response = endpoint().respond(&mut request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::NOT_ACCEPTABLE);
+        assert_eq!(
+            response.body_mut().as_str().await.unwrap(),
+            "Supported media types: application/vnd.myapp.v1+json, \
+             application/vnd.myapp.v2+json, application/vnd.myapp.v3+json"
+        );
+    }
+
+    #[tokio::test]
+    async fn write_with_mismatched_content_type_is_unsupported() {
+        let mut request = build(Method::POST, Some("application/vnd.myapp.v2+json"));
+        request.headers_mut().insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/vnd.myapp.v8+json"),
+        );
+        let response = endpoint().respond(&mut request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+        let mut request = build(Method::PUT, None);
+        request
+            .headers_mut()
+            .insert(header::CONTENT_TYPE, HeaderValue::from_static("text/xml"));
+        let response = endpoint().respond(&mut request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+        let mut request = build(Method::POST, None);
+        request.headers_mut().insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/vnd.myapp.v1+json"),
+        );
+        let response = endpoint().respond(&mut request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
@@ -0,0 +1,391 @@
+//! Byte counts and durations of requests, recorded once their response is sent.
+//!
+//! [`Metrics`] counts the bytes read from the request body and sent from the response
+//! body with [`Body::with_progress`], and hands a [`MetricsRecord`] to a
+//! [`MetricsSink`] when the response body ends, not when the endpoint returns:
+//! streaming responses are sent long after that. A response body dropped before its
+//! end, for instance because the client went away, is still recorded, as not
+//! completed.
+//!
+//! Request bodies already in memory are counted whole and left as they are, so that
+//! they stay cloneable for middleware further in, such as retries.
+//!
+//! The byte counts are 64-bit atomics, so the module is only available on targets
+//! that have them.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # #[cfg(feature = "std")]
+//! # {
+//! use http_kit::middleware::metrics::{Metrics, MetricsRecord};
+//!
+//! let metrics = Metrics::new(|record: MetricsRecord| {
+//!     eprintln!(
+//!         "{} {} {}: {}B in, {}B out in {:?}",
+//!         record.method,
+//!         record.path,
+//!         record.status,
+//!         record.request_bytes,
+//!         record.response_bytes,
+//!         record.duration,
+//!     );
+//! });
+//! # }
+//! ```
+
+use alloc::{string::String, sync::Arc};
+use core::{
+    convert::Infallible,
+    fmt,
+    pin::Pin,
+    sync::atomic::{AtomicU64, Ordering::Relaxed},
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use bytes::Bytes;
+use futures_lite::ready;
+use http::{Method, StatusCode};
+use http_body::{Frame, SizeHint};
+
+use super::rate_limit::Clock;
+#[cfg(feature = "std")]
+use super::rate_limit::MonotonicClock;
+use crate::{
+    middleware::MiddlewareError, Body, BodyError, Endpoint, HttpError, Middleware, Request,
+    RequestExt, Response,
+};
+
+/// What [`Metrics`] records about one request.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct MetricsRecord {
+    /// The request method.
+    pub method: Method,
+    /// The request path, without the query string.
+    pub path: String,
+    /// The response status, or the status of the endpoint error.
+    pub status: StatusCode,
+    /// The number of bytes read from the request body.
+    pub request_bytes: u64,
+    /// The number of bytes read from the response body.
+    pub response_bytes: u64,
+    /// The time from the request reaching the middleware to the end of the response
+    /// body, or to its drop.
+    pub duration: Duration,
+    /// Whether the response body was read to its end. It is `false` if the body was
+    /// dropped before, and if the endpoint failed.
+    pub completed: bool,
+}
+
+/// Destination of the records of [`Metrics`].
+///
+/// Records are sent from wherever the response body ends or is dropped, so the sink is
+/// shared and takes `&self`. Implemented for closures taking a [`MetricsRecord`].
+pub trait MetricsSink: Send + Sync + 'static {
+    /// Records the outcome of one request.
+    fn record(&self, record: MetricsRecord);
+}
+
+impl<F: Fn(MetricsRecord) + Send + Sync + 'static> MetricsSink for F {
+    fn record(&self, record: MetricsRecord) {
+        self(record);
+    }
+}
+
+/// Middleware recording the byte counts and duration of every request to a
+/// [`MetricsSink`], see the [module documentation](self).
+///
+/// The request body is wrapped to count its bytes, which makes it a streaming body:
+/// place [`Retry`](super::retry::Retry), which replays bodies in memory, outside of
+/// this middleware.
+#[derive(Clone)]
+pub struct Metrics {
+    sink: Arc<dyn MetricsSink>,
+    clock: Arc<dyn Clock>,
+}
+
+impl fmt::Debug for Metrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Metrics").finish_non_exhaustive()
+    }
+}
+
+impl Metrics {
+    /// Creates a middleware recording to `sink`, timed by a [`MonotonicClock`].
+    #[cfg(feature = "std")]
+    pub fn new(sink: impl MetricsSink) -> Self {
+        Self::with_clock(sink, MonotonicClock::new())
+    }
+
+    /// Creates a middleware like [`Metrics::new`], timed by `clock`.
+    pub fn with_clock(sink: impl MetricsSink, clock: impl Clock + 'static) -> Self {
+        Self {
+            sink: Arc::new(sink),
+            clock: Arc::new(clock),
+        }
+    }
+}
+
+// A record waiting for the end of the response body.
+struct Pending {
+    sink: Arc<dyn MetricsSink>,
+    clock: Arc<dyn Clock>,
+    start: Duration,
+    method: Method,
+    path: String,
+    status: StatusCode,
+    request_bytes: Arc<AtomicU64>,
+    response_bytes: Arc<AtomicU64>,
+}
+
+impl Pending {
+    fn send(self, completed: bool) {
+        self.sink.record(MetricsRecord {
+            method: self.method,
+            path: self.path,
+            status: self.status,
+            request_bytes: self.request_bytes.load(Relaxed),
+            response_bytes: self.response_bytes.load(Relaxed),
+            duration: self.clock.now().saturating_sub(self.start),
+            completed,
+        });
+    }
+}
+
+// Response body sending its record when it ends or is dropped.
+struct Tracked {
+    body: Body,
+    pending: Option<Pending>,
+}
+
+impl Tracked {
+    fn finish(&mut self, completed: bool) {
+        if let Some(pending) = self.pending.take() {
+            pending.send(completed);
+        }
+    }
+}
+
+impl Drop for Tracked {
+    fn drop(&mut self) {
+        let completed = http_body::Body::is_end_stream(&self.body);
+        self.finish(completed);
+    }
+}
+
+impl http_body::Body for Tracked {
+    type Data = Bytes;
+    type Error = BodyError;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let this = self.get_mut();
+        let frame = ready!(http_body::Body::poll_frame(Pin::new(&mut this.body), cx));
+        if frame.is_none() || http_body::Body::is_end_stream(&this.body) {
+            this.finish(true);
+        }
+        Poll::Ready(frame)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        http_body::Body::is_end_stream(&self.body)
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        http_body::Body::size_hint(&self.body)
+    }
+}
+
+// Wraps `body` to store its progress in `counter`.
+fn counted(body: Body, counter: &Arc<AtomicU64>) -> Body {
+    let counter = counter.clone();
+    body.with_progress(move |read| counter.store(read, Relaxed))
+}
+
+impl Middleware for Metrics {
+    type Error = Infallible;
+    async fn handle<E: Endpoint>(
+        &mut self,
+        request: &mut Request,
+        mut next: E,
+    ) -> Result<Response, MiddlewareError<E::Error, Self::Error>> {
+        let start = self.clock.now();
+        let request_bytes = Arc::new(AtomicU64::new(0));
+        if request.body().try_clone().is_some() {
+            let length = request.body().len().unwrap_or_default();
+            request_bytes.store(length as u64, Relaxed);
+        } else if let Ok(body) = request.take_body() {
+            *request.body_mut() = counted(body, &request_bytes);
+        }
+        let mut pending = Pending {
+            sink: self.sink.clone(),
+            clock: self.clock.clone(),
+            start,
+            method: request.method().clone(),
+            path: request.uri().path().into(),
+            status: StatusCode::OK,
+            request_bytes,
+            response_bytes: Arc::new(AtomicU64::new(0)),
+        };
+
+        match next.respond(request).await {
+            Ok(response) => {
+                pending.status = response.status();
+                Ok(response.map(|body| {
+                    let mime = body.mime().cloned();
+                    let length = body.len();
+                    let body = counted(body, &pending.response_bytes);
+                    let tracked = Body::with_length(
+                        Tracked {
+                            body,
+                            pending: Some(pending),
+                        },
+                        length,
+                    );
+                    match mime {
+                        Some(mime) => tracked.with_mime(mime),
+                        None => tracked,
+                    }
+                }))
+            }
+            Err(error) => {
+                pending.status = error.status();
+                pending.send(false);
+                Err(MiddlewareError::Endpoint(error))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use crate::{BoxHttpError, Error};
+    use alloc::{vec, vec::Vec};
+    use futures_lite::{stream, StreamExt};
+    use std::sync::Mutex;
+
+    type Records = Arc<Mutex<Vec<MetricsRecord>>>;
+
+    // Reads the request body, then streams a response of three chunks.
+    struct Upload;
+
+    impl Endpoint for Upload {
+        type Error = BoxHttpError;
+        async fn respond(&mut self, request: &mut Request) -> Result<Response, Self::Error> {
+            let body = request.body_mut().as_bytes().await.unwrap();
+            if body == b"fail" {
+                let error = Error::msg("rejected").set_status(StatusCode::UNPROCESSABLE_ENTITY);
+                return Err(error.into_boxed_http_error());
+            }
+            let chunks = vec!["first ", "second ", "third"];
+            let chunks = chunks.into_iter().map(Ok::<_, BodyError>);
+            let mut response = Response::new(Body::from_stream(stream::iter(chunks)));
+            *response.status_mut() = StatusCode::CREATED;
+            Ok(response)
+        }
+    }
+
+    // A middleware whose clock advances by a millisecond each time it is read.
+    fn metrics() -> (Metrics, Records) {
+        let records = Records::default();
+        let sink = records.clone();
+        let ticks = AtomicU64::new(0);
+        let clock = move || Duration::from_millis(ticks.fetch_add(1, Relaxed));
+        let metrics = Metrics::with_clock(move |record| sink.lock().unwrap().push(record), clock);
+        (metrics, records)
+    }
+
+    async fn call(metrics: &mut Metrics, body: &'static str) -> Result<Response, StatusCode> {
+        let mut request = Request::post(http::Uri::from_static("/upload?token=secret"));
+        let chunks = stream::iter([Ok::<_, BodyError>(body)]);
+        *request.body_mut() = Body::from_stream(chunks);
+        metrics
+            .handle(&mut request, Upload)
+            .await
+            .map_err(|error| error.status())
+    }
+
+    #[tokio::test]
+    async fn records_once_the_response_body_ends() {
+        let (mut metrics, records) = metrics();
+        let response = call(&mut metrics, "payload").await.unwrap();
+        // The endpoint returned, but the response is still to be sent.
+        assert!(records.lock().unwrap().is_empty());
+
+        let body = response.into_body().into_bytes().await.unwrap();
+        assert_eq!(body, "first second third");
+        let records = records.lock().unwrap();
+        let [record] = &records[..] else {
+            panic!("expected one record, got {records:?}")
+        };
+        assert_eq!(record.method, Method::POST);
+        assert_eq!(record.path, "/upload");
+        assert_eq!(record.status, StatusCode::CREATED);
+        assert_eq!(record.request_bytes, 7);
+        assert_eq!(record.response_bytes, 18);
+        assert_eq!(record.duration, Duration::from_millis(1));
+        assert!(record.completed);
+    }
+
+    #[tokio::test]
+    async fn bodies_in_memory_stay_cloneable() {
+        // Checks that the request body can still be copied, as `Retry` does.
+        struct Cloneable;
+
+        impl Endpoint for Cloneable {
+            type Error = Infallible;
+            async fn respond(&mut self, request: &mut Request) -> Result<Response, Self::Error> {
+                assert_eq!(request.body().len(), Some(7));
+                assert!(request.body().try_clone().is_some());
+                Ok(Response::new(Body::from_bytes("done")))
+            }
+        }
+
+        let (mut metrics, records) = metrics();
+        let mut request = Request::post(http::Uri::from_static("/upload"));
+        *request.body_mut() = Body::from_bytes("payload");
+        let response = metrics.handle(&mut request, Cloneable).await.unwrap();
+        assert_eq!(response.body().len(), Some(4));
+        response.into_body().into_bytes().await.unwrap();
+
+        let records = records.lock().unwrap();
+        assert_eq!(records[0].request_bytes, 7);
+        assert_eq!(records[0].response_bytes, 4);
+    }
+
+    #[tokio::test]
+    async fn dropped_bodies_are_recorded_as_incomplete() {
+        let (mut metrics, records) = metrics();
+        let response = call(&mut metrics, "payload").await.unwrap();
+        let mut data = response.into_body().into_data_stream();
+        assert_eq!(data.next().await.unwrap().unwrap(), "first ");
+        assert!(records.lock().unwrap().is_empty());
+        drop(data);
+
+        let records = records.lock().unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].response_bytes, 6);
+        assert!(!records[0].completed);
+    }
+
+    #[tokio::test]
+    async fn endpoint_errors_are_recorded_right_away() {
+        let (mut metrics, records) = metrics();
+        let status = call(&mut metrics, "fail").await.unwrap_err();
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+        let records = records.lock().unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(records[0].request_bytes, 4);
+        assert_eq!(records[0].response_bytes, 0);
+        assert!(!records[0].completed);
+    }
+}
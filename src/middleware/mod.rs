@@ -6,7 +6,7 @@
 //!
 //! - Request/response logging
 //! - Authentication and authorization
-//! - Request timeouts
+//! - Request timeouts and retries
 //! - Response compression
 //! - Custom headers
 //! - Request/response transformation
@@ -33,7 +33,8 @@
 //! ```
 //!
 //! The middleware can then be composed with endpoints using [`WithMiddleware`].
-//! Multiple middleware can be chained together using tuples like `(Middleware1, Middleware2)`.
+//! Multiple middleware can be chained together using tuples like `(Middleware1, Middleware2)`,
+//! or assembled at runtime in a [`MiddlewareStack`].
 use crate::{endpoint::EndpointImpl, error::BoxHttpError, Endpoint, HttpError, Request, Response};
 use alloc::boxed::Box;
 use core::{
@@ -45,6 +46,48 @@ use core::{
     pin::Pin,
 };
 use http::StatusCode;
+
+mod auth;
+pub use auth::{BasicAuth, BearerAuth};
+pub mod body_policy;
+pub mod cache;
+#[cfg(feature = "std")]
+pub mod catch_panic;
+#[cfg(feature = "compression")]
+pub mod compression;
+#[cfg(feature = "cookie")]
+pub mod cookie_jar;
+#[cfg(feature = "std")]
+pub mod deprecation;
+mod error_handler;
+pub use error_handler::ErrorHandler;
+mod from_fn;
+pub use from_fn::{middleware_fn, MiddlewareFn, Next};
+mod head;
+pub use head::{HandleHead, NativeHead};
+pub mod headers;
+mod logger;
+#[cfg(feature = "log")]
+pub use logger::LogCrateSink;
+#[cfg(feature = "tracing")]
+pub use logger::TracingSink;
+pub use logger::{LogRecord, LogSink, Logger};
+pub mod media_version;
+#[cfg(target_has_atomic = "64")]
+pub mod metrics;
+mod normalize;
+pub use normalize::NormalizeHeaders;
+pub mod rate_limit;
+pub mod request_id;
+#[cfg(feature = "std")]
+pub mod retry;
+pub mod sniff;
+mod stack;
+pub use stack::MiddlewareStack;
+#[cfg(feature = "std")]
+pub mod timeout;
+pub mod validate;
+
 /// Trait for implementing middleware that can process HTTP requests and responses.
 ///
 /// Middleware sits between the initial request and the final endpoint, allowing you to
@@ -243,7 +286,9 @@ pub(crate) trait MiddlewareImpl: Send {
 impl<'a> Endpoint for &mut (dyn EndpointImpl + 'a) {
     type Error = BoxHttpError;
     async fn respond(&mut self, request: &mut Request) -> Result<Response, Self::Error> {
-        self.respond_inner(request).await
+        // Dispatch through the trait object: `self.respond_inner` would resolve to the
+        // blanket `EndpointImpl` impl of `&mut dyn EndpointImpl` and recurse forever.
+        (**self).respond_inner(request).await
     }
 }
 
@@ -336,6 +381,84 @@ where
     }
 }
 
+// Error type of a middleware tuple: the first element's error, then the error of the
+// remaining elements, nested for larger tuples.
+macro_rules! tuple_error {
+    ($only:ident) => {
+        <$only as Middleware>::Error
+    };
+    ($first:ident, $($rest:ident),+) => {
+        MiddlewareTupleError<<$first as Middleware>::Error, tuple_error!($($rest),+)>
+    };
+}
+
+// Wraps `$endpoint` in the given middleware, the first one being the outermost.
+macro_rules! nest_endpoint {
+    ($endpoint:expr;) => {
+        $endpoint
+    };
+    ($endpoint:expr; $first:ident $(, $rest:ident)*) => {
+        crate::endpoint::WithMiddleware::new(nest_endpoint!($endpoint; $($rest),*), $first)
+    };
+}
+
+// Converts the error of nested `WithMiddleware` layers into the tuple error.
+macro_rules! flatten_error {
+    ($error:expr; $only:ident) => {
+        $error
+    };
+    ($error:expr; $first:ident, $($rest:ident),+) => {
+        nest_error($error, |inner| flatten_error!(inner; $($rest),+))
+    };
+}
+
+fn nest_error<I, N, R: HttpError, A: HttpError>(
+    error: MiddlewareError<I, A>,
+    inner: impl FnOnce(I) -> MiddlewareError<N, R>,
+) -> MiddlewareError<N, MiddlewareTupleError<A, R>> {
+    match error {
+        MiddlewareError::Middleware(error) => {
+            MiddlewareError::Middleware(MiddlewareTupleError::First(error))
+        }
+        MiddlewareError::Endpoint(error) => match inner(error) {
+            MiddlewareError::Endpoint(error) => MiddlewareError::Endpoint(error),
+            MiddlewareError::Middleware(error) => {
+                MiddlewareError::Middleware(MiddlewareTupleError::Second(error))
+            }
+        },
+    }
+}
+
+macro_rules! impl_middleware_tuple {
+    ($first:ident $first_var:ident, $($rest:ident $rest_var:ident),+) => {
+        /// Chains middleware, the first element being the outermost.
+        ///
+        /// Errors of the first element are reported as [`MiddlewareTupleError::First`], and
+        /// errors of the following ones as [`MiddlewareTupleError::Second`], nested for
+        /// tuples of more than two elements.
+        impl<$first: Middleware, $($rest: Middleware),+> Middleware for ($first, $($rest),+) {
+            type Error = tuple_error!($first, $($rest),+);
+            async fn handle<E: Endpoint>(
+                &mut self,
+                request: &mut Request,
+                mut next: E,
+            ) -> Result<Response, MiddlewareError<E::Error, Self::Error>> {
+                let ($first_var, $($rest_var),+) = self;
+                let inner = nest_endpoint!(&mut next; $($rest_var),+);
+                $first_var
+                    .handle(request, inner)
+                    .await
+                    .map_err(|error| flatten_error!(error; $first_var, $($rest_var),+))
+            }
+        }
+    };
+}
+
+impl_middleware_tuple!(A a, B b);
+impl_middleware_tuple!(A a, B b, C c);
+impl_middleware_tuple!(A a, B b, C c, D d);
+impl_middleware_tuple!(A a, B b, C c, D d, F f);
+
 /// Type-erased middleware that can hold any middleware implementation behind a trait object.
 ///
 /// `AnyMiddleware` provides dynamic dispatch for middleware, allowing you to store
@@ -433,6 +556,25 @@ impl Middleware for AnyMiddleware {
     }
 }
 
+/// Optional middleware: `Some` applies the wrapped middleware, `None` passes the request
+/// through to the next handler.
+impl<M: Middleware> Middleware for Option<M> {
+    type Error = M::Error;
+    async fn handle<E: Endpoint>(
+        &mut self,
+        request: &mut Request,
+        mut next: E,
+    ) -> Result<Response, MiddlewareError<E::Error, Self::Error>> {
+        match self {
+            Some(middleware) => middleware.handle(request, next).await,
+            None => next
+                .respond(request)
+                .await
+                .map_err(MiddlewareError::Endpoint),
+        }
+    }
+}
+
 /// No-op middleware implementation for the unit type.
 ///
 /// This implementation allows `()` to be used as a middleware that does nothing
@@ -452,3 +594,188 @@ impl Middleware for () {
             .map_err(MiddlewareError::<_, Self::Error>::Endpoint)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use crate::{endpoint::WithMiddleware, Body, RequestExt};
+    use alloc::{string::String, sync::Arc, vec::Vec};
+    use core::fmt;
+    use std::sync::Mutex;
+
+    type Log = Arc<Mutex<Vec<&'static str>>>;
+
+    #[derive(Debug)]
+    struct Denied;
+
+    impl fmt::Display for Denied {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("denied")
+        }
+    }
+
+    impl core::error::Error for Denied {}
+
+    impl HttpError for Denied {
+        fn status(&self) -> StatusCode {
+            StatusCode::UNAUTHORIZED
+        }
+    }
+
+    // Logs its name, then fails with `Denied` if `deny` is set.
+    struct Named {
+        name: &'static str,
+        log: Log,
+        deny: bool,
+    }
+
+    impl Middleware for Named {
+        type Error = Denied;
+        async fn handle<E: Endpoint>(
+            &mut self,
+            request: &mut Request,
+            mut next: E,
+        ) -> Result<Response, MiddlewareError<E::Error, Self::Error>> {
+            self.log.lock().unwrap().push(self.name);
+            if self.deny {
+                return Err(MiddlewareError::Middleware(Denied));
+            }
+            next.respond(request)
+                .await
+                .map_err(MiddlewareError::Endpoint)
+        }
+    }
+
+    struct Ok200(Log);
+
+    impl Endpoint for Ok200 {
+        type Error = Infallible;
+        async fn respond(&mut self, _request: &mut Request) -> Result<Response, Self::Error> {
+            self.0.lock().unwrap().push("endpoint");
+            Ok(Response::new(Body::empty()))
+        }
+    }
+
+    fn named(name: &'static str, log: &Log, deny: bool) -> Named {
+        Named {
+            name,
+            log: log.clone(),
+            deny,
+        }
+    }
+
+    // Takes the request body, as a middleware inspecting it would.
+    struct Consume;
+
+    impl Middleware for Consume {
+        type Error = Infallible;
+        async fn handle<E: Endpoint>(
+            &mut self,
+            request: &mut Request,
+            mut next: E,
+        ) -> Result<Response, MiddlewareError<E::Error, Self::Error>> {
+            let body = request.take_body().unwrap();
+            assert_eq!(body.into_bytes().await.unwrap(), "secret");
+            next.respond(request)
+                .await
+                .map_err(MiddlewareError::Endpoint)
+        }
+    }
+
+    // Tries to put a body back in place of the consumed one, then does so explicitly.
+    struct Restore;
+
+    impl Endpoint for Restore {
+        type Error = Infallible;
+        async fn respond(&mut self, request: &mut Request) -> Result<Response, Self::Error> {
+            let refused = request.try_replace_body(Body::from_text("forged"));
+            assert!(matches!(refused, Err(crate::BodyError::BodyFrozen)));
+            assert!(request.body().is_frozen());
+            assert!(request.body_mut().take().is_err());
+
+            request.set_body(Body::from_text("restored"));
+            let previous = request.try_replace_body(Body::empty()).unwrap();
+            Ok(Response::new(previous))
+        }
+    }
+
+    #[tokio::test]
+    async fn consumed_bodies_stay_frozen() {
+        let mut endpoint = WithMiddleware::new(Restore, Consume);
+        let mut request = Request::new(Body::from_text("secret"));
+        let response = endpoint.respond(&mut request).await.unwrap();
+        assert_eq!(
+            response.into_body().into_string().await.unwrap(),
+            "restored"
+        );
+        assert_eq!(request.body().len(), Some(0));
+    }
+
+    #[tokio::test]
+    async fn tuples_run_outermost_first() {
+        let log = Log::default();
+        let chain = (
+            named("logging", &log, false),
+            named("auth", &log, false),
+            named("timing", &log, false),
+        );
+        let mut endpoint = WithMiddleware::new(Ok200(log.clone()), chain);
+        endpoint
+            .respond(&mut Request::new(Body::empty()))
+            .await
+            .unwrap();
+        assert_eq!(
+            *log.lock().unwrap(),
+            ["logging", "auth", "timing", "endpoint"]
+        );
+
+        let five = ((), (), (), (), named("last", &log, false));
+        let mut endpoint = WithMiddleware::new(Ok200(log.clone()), five);
+        assert!(endpoint
+            .respond(&mut Request::new(Body::empty()))
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn tuple_errors_name_the_failing_element() {
+        let log = Log::default();
+        let chain = (
+            named("logging", &log, false),
+            named("auth", &log, true),
+            named("timing", &log, false),
+        );
+        let mut endpoint = WithMiddleware::new(Ok200(log.clone()), chain);
+        let error = endpoint
+            .respond(&mut Request::new(Body::empty()))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            MiddlewareError::Middleware(MiddlewareTupleError::Second(MiddlewareTupleError::First(
+                Denied
+            )))
+        ));
+        assert_eq!(error.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            String::from(
+                "Middleware error: Second middleware error: First middleware error: denied"
+            ),
+            alloc::format!("{error}")
+        );
+        assert_eq!(*log.lock().unwrap(), ["logging", "auth"]);
+
+        let mut endpoint =
+            WithMiddleware::new(Ok200(log.clone()), (named("logging", &log, true), ()));
+        let error = endpoint
+            .respond(&mut Request::new(Body::empty()))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            MiddlewareError::Middleware(MiddlewareTupleError::First(Denied))
+        ));
+    }
+}
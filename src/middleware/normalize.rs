@@ -0,0 +1,130 @@
+//! Normalization of the framing headers of responses.
+
+use core::convert::Infallible;
+
+use http::{header, Method};
+
+use crate::{middleware::MiddlewareError, Endpoint, Middleware, Request, Response, ResponseExt};
+
+/// Middleware making `Content-Length` and `Transfer-Encoding` agree with the response body.
+///
+/// Middleware replacing bodies, such as compression or error handlers, easily leave a
+/// stale `Content-Length` behind. This middleware runs
+/// [`ResponseExt::sync_content_length`] on every response, with two exceptions:
+///
+/// - An empty body answering a `HEAD` request keeps its `Content-Length`, which
+///   describes the body a `GET` request would have received.
+/// - A successful response to `CONNECT` switches to tunnel mode and has neither header.
+///
+/// Place it outermost, so that it sees the final body.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NormalizeHeaders;
+
+impl NormalizeHeaders {
+    /// Creates the middleware.
+    pub const fn new() -> Self {
+        Self
+    }
+}
+
+impl Middleware for NormalizeHeaders {
+    type Error = Infallible;
+    async fn handle<E: Endpoint>(
+        &mut self,
+        request: &mut Request,
+        mut next: E,
+    ) -> Result<Response, MiddlewareError<E::Error, Self::Error>> {
+        let mut response = next
+            .respond(request)
+            .await
+            .map_err(MiddlewareError::Endpoint)?;
+
+        let method = request.method();
+        if *method == Method::CONNECT && response.status().is_success() {
+            let headers = response.headers_mut();
+            headers.remove(header::CONTENT_LENGTH);
+            headers.remove(header::TRANSFER_ENCODING);
+        } else if !(*method == Method::HEAD && response.body().len() == Some(0)) {
+            response.sync_content_length();
+        }
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{endpoint::WithMiddleware, middleware::ErrorHandler, Body};
+    use http::{HeaderValue, StatusCode};
+
+    http_error!(Teapot, StatusCode::IM_A_TEAPOT, "short and stout");
+
+    // Answers with a fixed, wrongly framed body, or fails for `/error`.
+    struct Framed;
+
+    impl Endpoint for Framed {
+        type Error = Teapot;
+        async fn respond(&mut self, request: &mut Request) -> Result<Response, Self::Error> {
+            if request.uri().path() == "/error" {
+                return Err(Teapot::new());
+            }
+            let mut response = Response::new(Body::from_text("hello"));
+            response
+                .headers_mut()
+                .insert(header::CONTENT_LENGTH, HeaderValue::from_static("512"));
+            Ok(response)
+        }
+    }
+
+    fn request(method: Method, path: &str) -> Request {
+        let mut request = Request::new(Body::empty());
+        *request.method_mut() = method;
+        *request.uri_mut() = path.parse().unwrap();
+        request
+    }
+
+    #[tokio::test]
+    async fn stale_lengths_are_fixed() {
+        let mut endpoint = WithMiddleware::new(Framed, NormalizeHeaders::new());
+        let response = endpoint
+            .respond(&mut request(Method::GET, "/"))
+            .await
+            .unwrap();
+        assert_eq!(response.headers()[header::CONTENT_LENGTH], "5");
+    }
+
+    #[tokio::test]
+    async fn bodies_replaced_by_middleware() {
+        let mut endpoint =
+            WithMiddleware::new(Framed, (NormalizeHeaders::new(), ErrorHandler::default()));
+        let response = endpoint
+            .respond(&mut request(Method::GET, "/error"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::IM_A_TEAPOT);
+        assert_eq!(response.headers()[header::CONTENT_LENGTH], "15");
+    }
+
+    #[tokio::test]
+    async fn head_and_connect() {
+        let mut endpoint = WithMiddleware::new(
+            Framed,
+            (
+                NormalizeHeaders::new(),
+                crate::middleware::HandleHead::new(),
+            ),
+        );
+        let response = endpoint
+            .respond(&mut request(Method::HEAD, "/"))
+            .await
+            .unwrap();
+        assert_eq!(response.headers()[header::CONTENT_LENGTH], "5");
+
+        let mut endpoint = WithMiddleware::new(Framed, NormalizeHeaders::new());
+        let response = endpoint
+            .respond(&mut request(Method::CONNECT, "example.com:443"))
+            .await
+            .unwrap();
+        assert!(!response.headers().contains_key(header::CONTENT_LENGTH));
+    }
+}
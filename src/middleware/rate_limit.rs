@@ -0,0 +1,469 @@
+//! Per-key request rate limiting.
+//!
+//! [`RateLimit`] keeps a token bucket for each key extracted from the requests. Every
+//! request takes a token; tokens come back one per refill interval, up to the bucket
+//! capacity. Requests finding their bucket empty are answered with
+//! `429 Too Many Requests` and a `Retry-After` header, without reaching the endpoint.
+//!
+//! All responses carry `X-RateLimit-Limit`, `X-RateLimit-Remaining` and
+//! `X-RateLimit-Reset`, the number of seconds until the bucket is full again.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # #[cfg(feature = "std")]
+//! # {
+//! use core::time::Duration;
+//! use http_kit::{headers, middleware::rate_limit::RateLimit};
+//!
+//! // Bursts of 20 requests per client, then one request every 100 ms.
+//! let limit = RateLimit::new(20, Duration::from_millis(100)).key(|request| {
+//!     let client = request.headers().get(headers::X_FORWARDED_FOR)?;
+//!     Some(client.to_str().ok()?.into())
+//! });
+//! # }
+//! ```
+
+#[cfg(feature = "std")]
+extern crate std;
+
+use alloc::{boxed::Box, collections::BTreeMap, format};
+use core::{convert::Infallible, fmt, time::Duration};
+
+use bytestr::ByteStr;
+use http::{header, HeaderMap, HeaderValue, StatusCode};
+
+use crate::{
+    headers, middleware::MiddlewareError, response::text_response, Endpoint, Middleware, Request,
+    Response,
+};
+
+/// A monotonic time source for [`RateLimit`].
+///
+/// Implemented for closures returning the time elapsed since an arbitrary, fixed origin,
+/// which lets tests drive the limiter deterministically.
+pub trait Clock: Send + Sync {
+    /// Returns the time elapsed since the origin of the clock. It must never decrease.
+    fn now(&self) -> Duration;
+}
+
+impl<F: Fn() -> Duration + Send + Sync> Clock for F {
+    fn now(&self) -> Duration {
+        self()
+    }
+}
+
+/// The [`Clock`] backed by [`std::time::Instant`], counting from its creation.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy)]
+pub struct MonotonicClock {
+    origin: std::time::Instant,
+}
+
+#[cfg(feature = "std")]
+impl MonotonicClock {
+    /// Creates a clock starting at zero now.
+    pub fn new() -> Self {
+        Self {
+            origin: std::time::Instant::now(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for MonotonicClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "std")]
+impl Clock for MonotonicClock {
+    fn now(&self) -> Duration {
+        self.origin.elapsed()
+    }
+}
+
+type KeyExtractor = Box<dyn Fn(&Request) -> Option<ByteStr> + Send + Sync>;
+
+// Outcome of taking a token from a bucket.
+struct Decision {
+    allowed: bool,
+    remaining: u32,
+    // Time until the bucket is full again.
+    reset: Duration,
+    // Time until the next token, when the request was refused.
+    retry_after: Duration,
+}
+
+/// Middleware limiting the request rate of each key with a token bucket.
+///
+/// Requests are keyed by the extractor set with [`RateLimit::key`]; by default every
+/// request shares one global bucket. Requests for which the extractor returns `None` are
+/// not limited.
+///
+/// At most [`max_keys`](RateLimit::max_keys) buckets are tracked, 10 000 by default.
+/// When a new key arrives at the limit, full buckets are forgotten first, since they hold
+/// no state; failing that, the bucket that will be full soonest is dropped.
+///
+/// The buckets live in the middleware itself, so clones of an endpoint wrapping it do
+/// not share limits.
+pub struct RateLimit {
+    capacity: u32,
+    interval: Duration,
+    clock: Box<dyn Clock>,
+    key: KeyExtractor,
+    max_keys: usize,
+    // The instant each bucket will be full again, which determines its token count.
+    buckets: BTreeMap<ByteStr, Duration>,
+}
+
+impl fmt::Debug for RateLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RateLimit")
+            .field("capacity", &self.capacity)
+            .field("interval", &self.interval)
+            .field("max_keys", &self.max_keys)
+            .field("tracked_keys", &self.buckets.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl RateLimit {
+    /// Creates a limiter allowing bursts of `capacity` requests, refilled with one token
+    /// every `interval`, timed by a [`MonotonicClock`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` or `interval` is zero, or if refilling the whole bucket,
+    /// `capacity` times `interval`, overflows a [`Duration`].
+    #[cfg(feature = "std")]
+    pub fn new(capacity: u32, interval: Duration) -> Self {
+        Self::with_clock(capacity, interval, MonotonicClock::new())
+    }
+
+    /// Creates a limiter like [`RateLimit::new`], timed by `clock`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` or `interval` is zero, or if refilling the whole bucket,
+    /// `capacity` times `interval`, overflows a [`Duration`].
+    pub fn with_clock(capacity: u32, interval: Duration, clock: impl Clock + 'static) -> Self {
+        assert!(capacity > 0, "rate limit capacity must be positive");
+        assert!(!interval.is_zero(), "rate limit interval must be positive");
+        assert!(
+            interval.checked_mul(capacity).is_some(),
+            "rate limit window must fit in a Duration"
+        );
+        Self {
+            capacity,
+            interval,
+            clock: Box::new(clock),
+            key: Box::new(|_| Some(ByteStr::new())),
+            max_keys: 10_000,
+            buckets: BTreeMap::new(),
+        }
+    }
+
+    /// Keys the buckets with `key`, such as a client address, an API key header or an
+    /// authenticated principal found in the extensions.
+    ///
+    /// Requests for which `key` returns `None` are not limited.
+    #[must_use]
+    pub fn key(
+        mut self,
+        key: impl Fn(&Request) -> Option<ByteStr> + Send + Sync + 'static,
+    ) -> Self {
+        self.key = Box::new(key);
+        self
+    }
+
+    /// Sets the maximum number of buckets tracked at once.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_keys` is zero.
+    #[must_use]
+    pub fn max_keys(mut self, max_keys: usize) -> Self {
+        assert!(max_keys > 0, "rate limit must track at least one key");
+        self.max_keys = max_keys;
+        self
+    }
+
+    // Time for the bucket to refill entirely from empty.
+    fn window(&self) -> Duration {
+        self.interval * self.capacity
+    }
+
+    fn take(&mut self, key: ByteStr) -> Decision {
+        let now = self.clock.now();
+        let window = self.window();
+        if !self.buckets.contains_key(&key) && self.buckets.len() >= self.max_keys {
+            self.evict(now);
+        }
+
+        let full_at = self.buckets.get(&key).copied().unwrap_or(now).max(now);
+        let next = full_at.saturating_add(self.interval);
+        let deficit = next - now;
+        if deficit > window {
+            return Decision {
+                allowed: false,
+                remaining: 0,
+                reset: full_at - now,
+                retry_after: deficit - window,
+            };
+        }
+        self.buckets.insert(key, next);
+        let remaining = (window - deficit).as_nanos() / self.interval.as_nanos();
+        Decision {
+            allowed: true,
+            remaining: u32::try_from(remaining).unwrap_or(u32::MAX),
+            reset: deficit,
+            retry_after: Duration::ZERO,
+        }
+    }
+
+    fn evict(&mut self, now: Duration) {
+        self.buckets.retain(|_, full_at| *full_at > now);
+        if self.buckets.len() >= self.max_keys {
+            let soonest = self
+                .buckets
+                .iter()
+                .min_by_key(|(_, full_at)| **full_at)
+                .map(|(key, _)| key.clone());
+            if let Some(key) = soonest {
+                self.buckets.remove(&key);
+            }
+        }
+    }
+
+    fn stamp(&self, headers: &mut HeaderMap, decision: &Decision) {
+        headers.insert(headers::X_RATELIMIT_LIMIT, HeaderValue::from(self.capacity));
+        headers.insert(
+            headers::X_RATELIMIT_REMAINING,
+            HeaderValue::from(decision.remaining),
+        );
+        headers.insert(
+            headers::X_RATELIMIT_RESET,
+            HeaderValue::from(seconds(decision.reset)),
+        );
+    }
+}
+
+// Whole seconds, rounded up so clients never retry too early.
+fn seconds(duration: Duration) -> u64 {
+    duration.as_secs() + u64::from(duration.subsec_nanos() > 0)
+}
+
+impl Middleware for RateLimit {
+    type Error = Infallible;
+    async fn handle<E: Endpoint>(
+        &mut self,
+        request: &mut Request,
+        mut next: E,
+    ) -> Result<Response, MiddlewareError<E::Error, Self::Error>> {
+        let Some(key) = (self.key)(request) else {
+            return next
+                .respond(request)
+                .await
+                .map_err(MiddlewareError::Endpoint);
+        };
+        let decision = self.take(key);
+        if !decision.allowed {
+            let retry_after = seconds(decision.retry_after);
+            let mut response = text_response(
+                StatusCode::TOO_MANY_REQUESTS,
+                format!("Too Many Requests, retry in {retry_after}s"),
+            );
+            let headers = response.headers_mut();
+            headers.insert(header::RETRY_AFTER, HeaderValue::from(retry_after));
+            self.stamp(headers, &decision);
+            return Ok(response);
+        }
+
+        let mut response = next
+            .respond(request)
+            .await
+            .map_err(MiddlewareError::Endpoint)?;
+        self.stamp(response.headers_mut(), &decision);
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Body;
+    use alloc::sync::Arc;
+    use core::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+    // A clock advanced by hand, in milliseconds.
+    #[derive(Clone, Default)]
+    struct ManualClock(Arc<AtomicU64>);
+
+    impl ManualClock {
+        fn advance(&self, millis: u64) {
+            self.0.fetch_add(millis, Ordering::SeqCst);
+        }
+    }
+
+    impl Clock for ManualClock {
+        fn now(&self) -> Duration {
+            Duration::from_millis(self.0.load(Ordering::SeqCst))
+        }
+    }
+
+    struct Counter(Arc<AtomicUsize>);
+
+    impl Endpoint for Counter {
+        type Error = Infallible;
+        async fn respond(&mut self, _request: &mut Request) -> Result<Response, Self::Error> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            Ok(Response::new(Body::empty()))
+        }
+    }
+
+    struct Harness {
+        clock: ManualClock,
+        calls: Arc<AtomicUsize>,
+        counter: Counter,
+        limit: RateLimit,
+    }
+
+    impl Harness {
+        // Bursts of 3 requests, then one every second, keyed by the `x-client` header.
+        fn new(configure: impl FnOnce(RateLimit) -> RateLimit) -> Self {
+            let clock = ManualClock::default();
+            let limit =
+                RateLimit::with_clock(3, Duration::from_secs(1), clock.clone()).key(|request| {
+                    let client = request.headers().get("x-client")?.to_str().ok()?;
+                    Some(ByteStr::from(client))
+                });
+            let calls = Arc::new(AtomicUsize::new(0));
+            Self {
+                clock,
+                counter: Counter(calls.clone()),
+                calls,
+                limit: configure(limit),
+            }
+        }
+
+        async fn call(&mut self, client: Option<&'static str>) -> Response {
+            let mut request = Request::new(Body::empty());
+            if let Some(client) = client {
+                request
+                    .headers_mut()
+                    .insert("x-client", HeaderValue::from_static(client));
+            }
+            self.limit
+                .handle(&mut request, &mut self.counter)
+                .await
+                .unwrap()
+        }
+
+        fn calls(&self) -> usize {
+            self.calls.load(Ordering::SeqCst)
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "rate limit window must fit in a Duration")]
+    fn overflowing_windows_are_rejected() {
+        RateLimit::with_clock(2, Duration::MAX, ManualClock::default());
+    }
+
+    #[tokio::test]
+    async fn bursts_are_limited_to_the_capacity() {
+        let mut harness = Harness::new(|limit| limit);
+        for remaining in ["2", "1", "0"] {
+            let response = harness.call(Some("a")).await;
+            assert_eq!(response.status(), StatusCode::OK);
+            assert_eq!(response.headers()[headers::X_RATELIMIT_LIMIT], "3");
+            assert_eq!(
+                response.headers()[headers::X_RATELIMIT_REMAINING],
+                remaining
+            );
+        }
+        assert_eq!(harness.calls(), 3);
+
+        let response = harness.call(Some("a")).await;
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "1");
+        assert_eq!(response.headers()[headers::X_RATELIMIT_REMAINING], "0");
+        assert_eq!(response.headers()[headers::X_RATELIMIT_RESET], "3");
+        assert_eq!(harness.calls(), 3);
+
+        harness.clock.advance(400);
+        let response = harness.call(Some("a")).await;
+        assert_eq!(response.headers()[header::RETRY_AFTER], "1");
+        assert_eq!(harness.calls(), 3);
+    }
+
+    #[tokio::test]
+    async fn tokens_refill_over_time() {
+        let mut harness = Harness::new(|limit| limit);
+        for _ in 0..3 {
+            harness.call(Some("a")).await;
+        }
+
+        harness.clock.advance(1000);
+        let response = harness.call(Some("a")).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[headers::X_RATELIMIT_REMAINING], "0");
+        let response = harness.call(Some("a")).await;
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+
+        // Refilling stops at the capacity.
+        harness.clock.advance(60_000);
+        for _ in 0..3 {
+            assert_eq!(harness.call(Some("a")).await.status(), StatusCode::OK);
+        }
+        assert_eq!(
+            harness.call(Some("a")).await.status(),
+            StatusCode::TOO_MANY_REQUESTS
+        );
+        assert_eq!(harness.calls(), 7);
+    }
+
+    #[tokio::test]
+    async fn keys_are_isolated() {
+        let mut harness = Harness::new(|limit| limit);
+        for _ in 0..3 {
+            harness.call(Some("a")).await;
+        }
+        assert_eq!(
+            harness.call(Some("a")).await.status(),
+            StatusCode::TOO_MANY_REQUESTS
+        );
+        let response = harness.call(Some("b")).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[headers::X_RATELIMIT_REMAINING], "2");
+
+        // Requests without a key are not limited.
+        for _ in 0..10 {
+            let response = harness.call(None).await;
+            assert_eq!(response.status(), StatusCode::OK);
+            assert!(!response.headers().contains_key(headers::X_RATELIMIT_LIMIT));
+        }
+    }
+
+    #[tokio::test]
+    async fn tracked_keys_are_bounded() {
+        let mut harness = Harness::new(|limit| limit.max_keys(2));
+        harness.call(Some("a")).await;
+        harness.clock.advance(100);
+        harness.call(Some("b")).await;
+        harness.call(Some("b")).await;
+
+        // `a` will be full first, so it makes room for `c`.
+        harness.call(Some("c")).await;
+        assert_eq!(harness.limit.buckets.len(), 2);
+        assert!(!harness.limit.buckets.contains_key("a"));
+
+        // Full buckets are dropped before any other.
+        harness.clock.advance(1_500);
+        harness.call(Some("d")).await;
+        let buckets = &harness.limit.buckets;
+        assert!(buckets.contains_key("b") && buckets.contains_key("d"));
+    }
+}
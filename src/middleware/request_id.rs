@@ -0,0 +1,318 @@
+//! Per-request correlation identifiers.
+//!
+//! [`RequestIdMiddleware`] gives every request a [`RequestId`]: the one the client sent
+//! in `X-Request-Id` (or a configured header), or a freshly generated one. The id is
+//! stored in the request extensions, where [`RequestExt::request_id`] reads it, and is
+//! echoed on the response so both sides can correlate their logs.
+//!
+//! [`RequestExt::request_id`]: crate::RequestExt::request_id
+//!
+//! # Examples
+//!
+//! ```rust
+//! use core::convert::Infallible;
+//! use http_kit::middleware::request_id::RequestIdMiddleware;
+//! use http_kit::{endpoint::{endpoint_fn, WithMiddleware}, headers, Body, Endpoint, Request, RequestExt, Response};
+//!
+//! let echo = endpoint_fn(|request: &mut Request| {
+//!     let id = request.request_id().unwrap().clone();
+//!     async move { Ok::<_, Infallible>(Response::new(Body::from_text(id.as_str().to_owned()))) }
+//! });
+//! let mut endpoint = WithMiddleware::new(echo, RequestIdMiddleware::new());
+//!
+//! # futures_lite::future::block_on(async {
+//! let mut request = Request::new(Body::empty());
+//! request.headers_mut().insert(headers::X_REQUEST_ID, "7f3c".parse().unwrap());
+//! let response = endpoint.respond(&mut request).await.unwrap();
+//! assert_eq!(response.headers()[headers::X_REQUEST_ID], "7f3c");
+//! assert_eq!(response.into_body().into_string().await.unwrap(), "7f3c");
+//! # });
+//! ```
+
+use alloc::{format, sync::Arc};
+use core::{convert::Infallible, fmt};
+
+use bytestr::ByteStr;
+use http::{HeaderName, HeaderValue};
+
+use crate::{headers, middleware::MiddlewareError, Endpoint, Middleware, Request, Response};
+
+/// The correlation id of a request, stored in its extensions by [`RequestIdMiddleware`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RequestId(pub ByteStr);
+
+impl RequestId {
+    /// Returns the id as a string slice.
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+}
+
+impl fmt::Display for RequestId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+type Generator = Arc<dyn Fn() -> ByteStr + Send + Sync>;
+
+/// Middleware assigning a [`RequestId`] to every request.
+///
+/// The id is, in order of preference:
+///
+/// 1. the [`RequestId`] extension already present, so nested stacks agree on one id;
+/// 2. the value of the request header, `X-Request-Id` by default, if it is non-empty
+///    visible ASCII;
+/// 3. a new id from the generator, which is also set on the request header so that
+///    requests forwarded upstream carry it.
+///
+/// The response header is then set to the id, replacing any value the endpoint set.
+///
+/// The default generator produces UUIDv4-formatted ids built from a process-wide counter,
+/// so they never repeat within a process. With `std` the upper half is drawn from the
+/// per-process random keys of `RandomState`, making ids from different processes
+/// unlikely to collide as well.
+#[derive(Clone)]
+pub struct RequestIdMiddleware {
+    header: HeaderName,
+    generator: Generator,
+}
+
+impl fmt::Debug for RequestIdMiddleware {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RequestIdMiddleware")
+            .field("header", &self.header)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for RequestIdMiddleware {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RequestIdMiddleware {
+    /// Creates the middleware reading and writing `X-Request-Id`, with the default
+    /// generator.
+    pub fn new() -> Self {
+        Self {
+            header: headers::X_REQUEST_ID,
+            generator: Arc::new(generate),
+        }
+    }
+
+    /// Reads and writes the id in `header` instead of `X-Request-Id`.
+    #[must_use]
+    pub fn header(mut self, header: HeaderName) -> Self {
+        self.header = header;
+        self
+    }
+
+    /// Generates missing ids with `generator`, such as a ULID or UUID constructor.
+    ///
+    /// The generator may be called concurrently and should not return the same id twice.
+    /// Ids that are not valid header values are stored in the extensions but not sent.
+    #[must_use]
+    pub fn generator(mut self, generator: impl Fn() -> ByteStr + Send + Sync + 'static) -> Self {
+        self.generator = Arc::new(generator);
+        self
+    }
+
+    fn resolve(&self, request: &mut Request) -> RequestId {
+        if let Some(id) = request.extensions().get::<RequestId>() {
+            return id.clone();
+        }
+        let incoming = request
+            .headers()
+            .get(&self.header)
+            .and_then(|value| value.to_str().ok())
+            .filter(|value| !value.is_empty());
+        let id = match incoming {
+            Some(value) => RequestId(ByteStr::from(value)),
+            None => {
+                let id = RequestId((self.generator)());
+                if let Ok(value) = HeaderValue::from_str(id.as_str()) {
+                    request.headers_mut().insert(self.header.clone(), value);
+                }
+                id
+            }
+        };
+        request.extensions_mut().insert(id.clone());
+        id
+    }
+}
+
+impl Middleware for RequestIdMiddleware {
+    type Error = Infallible;
+    async fn handle<E: Endpoint>(
+        &mut self,
+        request: &mut Request,
+        mut next: E,
+    ) -> Result<Response, MiddlewareError<E::Error, Self::Error>> {
+        let id = self.resolve(request);
+        let mut response = next
+            .respond(request)
+            .await
+            .map_err(MiddlewareError::Endpoint)?;
+        if let Ok(value) = HeaderValue::from_str(id.as_str()) {
+            response.headers_mut().insert(self.header.clone(), value);
+        }
+        Ok(response)
+    }
+}
+
+// A UUIDv4-formatted id whose low 62 bits are a bijective scramble of a process-wide
+// counter, so no two calls in a process return the same id until the counter wraps.
+fn generate() -> ByteStr {
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    const MASK: u64 = (1 << 62) - 1;
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    let n = COUNTER.fetch_add(1, Ordering::Relaxed) as u64;
+    // Xor and multiplication by an odd constant are both bijective modulo 2^62.
+    let low = ((n ^ 0x2545_F491_4F6C_DD1D).wrapping_mul(0x9E37_79B9_7F4A_7C15) & MASK)
+        | 0x8000_0000_0000_0000;
+    let high = (process_key() & 0xFFFF_FFFF_FFFF_0FFF) | 0x4000;
+    ByteStr::from(format!(
+        "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+        high >> 32,
+        (high >> 16) & 0xFFFF,
+        high & 0xFFFF,
+        low >> 48,
+        low & 0xFFFF_FFFF_FFFF,
+    ))
+}
+
+#[cfg(feature = "std")]
+fn process_key() -> u64 {
+    extern crate std;
+    use core::hash::BuildHasher;
+    use std::{collections::hash_map::RandomState, sync::OnceLock};
+
+    static KEY: OnceLock<u64> = OnceLock::new();
+    *KEY.get_or_init(|| RandomState::new().hash_one(0u8))
+}
+
+#[cfg(not(feature = "std"))]
+const fn process_key() -> u64 {
+    0x6A09_E667_F3BC_C908
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        endpoint::{endpoint_fn, WithMiddleware},
+        Body, RequestExt,
+    };
+    use alloc::{
+        collections::BTreeSet,
+        string::{String, ToString},
+        vec::Vec,
+    };
+
+    // Answers with the id seen by the endpoint and the forwarded request header.
+    fn echo() -> impl Endpoint {
+        endpoint_fn(|request: &mut Request| {
+            let id = request.request_id().map(RequestId::to_string);
+            let header = request
+                .headers()
+                .get(headers::X_REQUEST_ID)
+                .map(|value| String::from(value.to_str().unwrap()));
+            assert_eq!(id, header);
+            async move { Ok::<_, Infallible>(Response::new(Body::from_text(id.unwrap()))) }
+        })
+    }
+
+    async fn call(middleware: RequestIdMiddleware, request: &mut Request) -> (String, String) {
+        let mut endpoint = WithMiddleware::new(echo(), middleware);
+        let response = endpoint.respond(request).await.unwrap();
+        let header = String::from(response.headers()[headers::X_REQUEST_ID].to_str().unwrap());
+        let body = response.into_body().into_string().await.unwrap();
+        (header, String::from(body.as_str()))
+    }
+
+    #[tokio::test]
+    async fn incoming_header_is_propagated_verbatim() {
+        let mut request = Request::new(Body::empty());
+        request.headers_mut().insert(
+            headers::X_REQUEST_ID,
+            HeaderValue::from_static("req-01HZY Ab/9"),
+        );
+        let (header, seen) = call(RequestIdMiddleware::new(), &mut request).await;
+        assert_eq!(header, "req-01HZY Ab/9");
+        assert_eq!(seen, "req-01HZY Ab/9");
+    }
+
+    #[tokio::test]
+    async fn missing_ids_are_generated() {
+        let mut ids = BTreeSet::new();
+        for _ in 0..64 {
+            let mut request = Request::new(Body::empty());
+            let (header, seen) = call(RequestIdMiddleware::new(), &mut request).await;
+            assert_eq!(header, seen);
+            assert_eq!(header.len(), 36);
+            assert_eq!(header.as_bytes()[14], b'4');
+            assert!(ids.insert(header));
+        }
+
+        let mut request = Request::new(Body::empty());
+        request
+            .headers_mut()
+            .insert(headers::X_REQUEST_ID, HeaderValue::from_static(""));
+        let middleware = RequestIdMiddleware::new().generator(|| ByteStr::from("generated"));
+        assert_eq!(call(middleware, &mut request).await.0, "generated");
+    }
+
+    #[test]
+    fn default_ids_are_unique_across_threads() {
+        extern crate std;
+
+        let threads: Vec<_> = (0..4)
+            .map(|_| std::thread::spawn(|| (0..1000).map(|_| generate()).collect::<Vec<_>>()))
+            .collect();
+        let mut ids = BTreeSet::new();
+        for thread in threads {
+            for id in thread.join().unwrap() {
+                assert!(ids.insert(String::from(id.as_str())));
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn existing_extensions_are_kept() {
+        let mut request = Request::new(Body::empty());
+        request
+            .extensions_mut()
+            .insert(RequestId(ByteStr::from("outer")));
+        request
+            .headers_mut()
+            .insert(headers::X_REQUEST_ID, HeaderValue::from_static("outer"));
+        let inner = RequestIdMiddleware::new().generator(|| ByteStr::from("inner"));
+        assert_eq!(
+            call(inner, &mut request).await,
+            ("outer".into(), "outer".into())
+        );
+    }
+
+    #[tokio::test]
+    async fn header_name_is_configurable() {
+        let trace = HeaderName::from_static("x-trace-id");
+        let mut endpoint = WithMiddleware::new(
+            endpoint_fn(|request: &mut Request| {
+                let id = request.request_id().unwrap().to_string();
+                async move { Ok::<_, Infallible>(Response::new(Body::from_text(id))) }
+            }),
+            RequestIdMiddleware::new().header(trace.clone()),
+        );
+        let mut request = Request::new(Body::empty());
+        request
+            .headers_mut()
+            .insert(trace.clone(), HeaderValue::from_static("abc"));
+        let response = endpoint.respond(&mut request).await.unwrap();
+        assert_eq!(response.headers()[&trace], "abc");
+        assert!(!response.headers().contains_key(headers::X_REQUEST_ID));
+    }
+}
@@ -0,0 +1,455 @@
+//! Retries of transient failures, for clients used as endpoints.
+//!
+//! [`Retry`] sends a request again when the endpoint answers with a transient failure,
+//! by default a `502`, `503` or `504` response or an error with a `5xx` status. It waits
+//! between attempts as long as the `Retry-After` header of the failed response asks, or
+//! else as long as its [`Backoff`] says.
+//!
+//! Only requests that can be sent twice are retried: their method must be idempotent,
+//! or they must carry an `Idempotency-Key` header, and their body must be in memory so
+//! that it can be restored before each attempt. A streaming body is consumed by the
+//! first attempt and cannot be replayed, so such requests are attempted once; buffer
+//! the body first, for instance with [`Body::as_bytes`](crate::Body::as_bytes), to make
+//! them retryable.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use core::time::Duration;
+//! use http_kit::middleware::retry::{Exponential, Retry};
+//!
+//! // Up to 4 attempts, waiting 100 ms, 200 ms then 400 ms between them.
+//! let retry = Retry::new(4).backoff(Exponential::new(
+//!     Duration::from_millis(100),
+//!     Duration::from_secs(1),
+//! ));
+//! ```
+
+extern crate std;
+
+use alloc::boxed::Box;
+use core::{fmt, time::Duration};
+use std::time::SystemTime;
+
+use futures_timer::Delay;
+use http::{header, StatusCode};
+
+use crate::{
+    headers, middleware::MiddlewareError, Body, Endpoint, HttpError, Middleware, Request,
+    RequestExt, Response,
+};
+
+/// The delays between the attempts of [`Retry`].
+///
+/// Implemented for closures mapping the retry number to a delay.
+pub trait Backoff: Send {
+    /// Returns the delay before the `retry`-th retry, counting from 1.
+    fn delay(&mut self, retry: u32) -> Duration;
+}
+
+impl<F: FnMut(u32) -> Duration + Send> Backoff for F {
+    fn delay(&mut self, retry: u32) -> Duration {
+        self(retry)
+    }
+}
+
+/// A [`Backoff`] waiting the same delay before every retry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fixed {
+    delay: Duration,
+}
+
+impl Fixed {
+    /// Creates a backoff waiting `delay` before every retry.
+    pub const fn new(delay: Duration) -> Self {
+        Self { delay }
+    }
+}
+
+impl Backoff for Fixed {
+    fn delay(&mut self, _retry: u32) -> Duration {
+        self.delay
+    }
+}
+
+/// A [`Backoff`] doubling the delay before every retry, up to a cap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Exponential {
+    initial: Duration,
+    max: Duration,
+}
+
+impl Exponential {
+    /// Creates a backoff waiting `initial` before the first retry, then twice as long
+    /// before each of the next ones, but never longer than `max`.
+    pub const fn new(initial: Duration, max: Duration) -> Self {
+        Self { initial, max }
+    }
+}
+
+impl Backoff for Exponential {
+    fn delay(&mut self, retry: u32) -> Duration {
+        let factor = 2u32.saturating_pow(retry.saturating_sub(1));
+        self.initial.saturating_mul(factor).min(self.max)
+    }
+}
+
+/// The outcome of an attempt, judged by the predicate of [`Retry::when`].
+#[derive(Debug, Clone, Copy)]
+pub enum Outcome<'a> {
+    /// The endpoint answered with a response.
+    Response(&'a Response),
+    /// The endpoint failed with an error.
+    Error(&'a dyn HttpError),
+}
+
+impl Outcome<'_> {
+    /// Returns the status of the response or of the error.
+    pub fn status(&self) -> StatusCode {
+        match self {
+            Self::Response(response) => response.status(),
+            Self::Error(error) => error.status(),
+        }
+    }
+}
+
+/// Returns whether `outcome` is a transient failure: a `502 Bad Gateway`,
+/// `503 Service Unavailable` or `504 Gateway Timeout` response, or an error with a
+/// `5xx` status.
+///
+/// This is the default predicate of [`Retry`]. Client errors are never transient.
+pub fn is_transient(outcome: Outcome<'_>) -> bool {
+    match outcome {
+        Outcome::Response(response) => matches!(
+            response.status(),
+            StatusCode::BAD_GATEWAY | StatusCode::SERVICE_UNAVAILABLE | StatusCode::GATEWAY_TIMEOUT
+        ),
+        Outcome::Error(error) => error.status().is_server_error(),
+    }
+}
+
+type Predicate = Box<dyn Fn(Outcome<'_>) -> bool + Send + Sync>;
+
+/// Middleware retrying requests that failed transiently, see the
+/// [module documentation](self).
+///
+/// The response or error of the last attempt is returned. A response asking with
+/// `Retry-After` to wait longer than [`max_retry_after`](Retry::max_retry_after) is
+/// returned right away.
+pub struct Retry {
+    max_attempts: u32,
+    backoff: Box<dyn Backoff>,
+    when: Predicate,
+    max_retry_after: Duration,
+}
+
+impl fmt::Debug for Retry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Retry")
+            .field("max_attempts", &self.max_attempts)
+            .field("max_retry_after", &self.max_retry_after)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Retry {
+    /// Creates a middleware making up to `max_attempts` attempts, the first one
+    /// included.
+    ///
+    /// It retries the outcomes accepted by [`is_transient`], waits 100 ms before the
+    /// first retry and twice as long before each of the next ones, up to 10 seconds,
+    /// and follows `Retry-After` headers of up to a minute.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_attempts` is zero.
+    pub fn new(max_attempts: u32) -> Self {
+        assert!(max_attempts > 0, "a request needs at least one attempt");
+        Self {
+            max_attempts,
+            backoff: Box::new(Exponential::new(
+                Duration::from_millis(100),
+                Duration::from_secs(10),
+            )),
+            when: Box::new(is_transient),
+            max_retry_after: Duration::from_secs(60),
+        }
+    }
+
+    /// Sets the delays between attempts, used when the failed response has no
+    /// `Retry-After` header.
+    #[must_use]
+    pub fn backoff(mut self, backoff: impl Backoff + 'static) -> Self {
+        self.backoff = Box::new(backoff);
+        self
+    }
+
+    /// Retries the outcomes for which `when` returns `true`, instead of those accepted
+    /// by [`is_transient`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use http_kit::middleware::retry::{is_transient, Outcome, Retry};
+    /// use http_kit::StatusCode;
+    ///
+    /// // Also retry when rate limited.
+    /// let retry = Retry::new(3).when(|outcome| {
+    ///     outcome.status() == StatusCode::TOO_MANY_REQUESTS || is_transient(outcome)
+    /// });
+    /// ```
+    #[must_use]
+    pub fn when<F>(mut self, when: F) -> Self
+    where
+        F: Fn(Outcome<'_>) -> bool + Send + Sync + 'static,
+    {
+        self.when = Box::new(when);
+        self
+    }
+
+    /// Sets the longest `Retry-After` delay to wait for, a minute by default.
+    ///
+    /// Responses asking to wait longer are returned without retrying.
+    #[must_use]
+    pub const fn max_retry_after(mut self, max: Duration) -> Self {
+        self.max_retry_after = max;
+        self
+    }
+}
+
+// Returns a copy of the body to restore before each retry, if the request may be sent
+// more than once.
+fn replayable(request: &Request) -> Option<Body> {
+    if !request.is_idempotent() && !request.headers().contains_key(headers::IDEMPOTENCY_KEY) {
+        return None;
+    }
+    request.body().try_clone()
+}
+
+// Parses `Retry-After`, either a number of seconds or an HTTP date.
+fn retry_after(response: &Response) -> Option<Duration> {
+    let value = response
+        .headers()
+        .get(header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim();
+    if let Ok(seconds) = value.parse() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = crate::date::parse_http_date(value)?;
+    Some(date.duration_since(SystemTime::now()).unwrap_or_default())
+}
+
+impl Middleware for Retry {
+    type Error = core::convert::Infallible;
+    async fn handle<E: Endpoint>(
+        &mut self,
+        request: &mut Request,
+        mut next: E,
+    ) -> Result<Response, MiddlewareError<E::Error, Self::Error>> {
+        let replay = if self.max_attempts > 1 {
+            replayable(request)
+        } else {
+            None
+        };
+        let mut attempt = 1;
+        loop {
+            let result = next.respond(request).await;
+            let body = match &replay {
+                Some(replay) if attempt < self.max_attempts => replay.try_clone(),
+                _ => None,
+            };
+            let outcome = match &result {
+                Ok(response) => Outcome::Response(response),
+                Err(error) => Outcome::Error(error),
+            };
+            let Some(body) = body.filter(|_| (self.when)(outcome)) else {
+                return result.map_err(MiddlewareError::Endpoint);
+            };
+            let delay = match result.as_ref().ok().and_then(retry_after) {
+                Some(delay) if delay > self.max_retry_after => {
+                    return result.map_err(MiddlewareError::Endpoint)
+                }
+                Some(delay) => delay,
+                None => self.backoff.delay(attempt),
+            };
+            drop(result);
+            Delay::new(delay).await;
+            *request.body_mut() = body;
+            attempt += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BoxHttpError, Error};
+    use alloc::{string::String, vec, vec::Vec};
+    use futures_lite::stream;
+    use http::{HeaderValue, Method};
+
+    // Serves the scripted outcomes in order, repeating the last one, and records the
+    // request bodies.
+    struct Script {
+        outcomes: Vec<Result<StatusCode, StatusCode>>,
+        retry_after: Option<&'static str>,
+        bodies: Vec<String>,
+    }
+
+    impl Script {
+        fn new(outcomes: Vec<Result<StatusCode, StatusCode>>) -> Self {
+            Self {
+                outcomes,
+                retry_after: None,
+                bodies: Vec::new(),
+            }
+        }
+
+        fn failing(failures: usize, status: StatusCode) -> Self {
+            let mut outcomes = vec![Ok(status); failures];
+            outcomes.push(Ok(StatusCode::OK));
+            Self::new(outcomes)
+        }
+    }
+
+    impl Endpoint for Script {
+        type Error = BoxHttpError;
+        async fn respond(&mut self, request: &mut Request) -> Result<Response, Self::Error> {
+            let body = request.take_body().unwrap().into_string().await.unwrap();
+            let outcome = self.outcomes[self.bodies.len().min(self.outcomes.len() - 1)];
+            self.bodies.push(String::from(&*body));
+            match outcome {
+                Ok(status) => {
+                    let mut response = Response::new(Body::empty());
+                    *response.status_mut() = status;
+                    if let Some(retry_after) = self.retry_after {
+                        let value = HeaderValue::from_static(retry_after);
+                        response.headers_mut().insert(header::RETRY_AFTER, value);
+                    }
+                    Ok(response)
+                }
+                Err(status) => Err(Error::msg("upstream failed")
+                    .set_status(status)
+                    .into_boxed_http_error()),
+            }
+        }
+    }
+
+    fn retry(max_attempts: u32) -> Retry {
+        Retry::new(max_attempts).backoff(Fixed::new(Duration::ZERO))
+    }
+
+    fn build(method: Method, body: Body) -> Request {
+        let mut request = Request::new(body);
+        *request.method_mut() = method;
+        request
+    }
+
+    async fn call(
+        retry: &mut Retry,
+        script: &mut Script,
+        mut request: Request,
+    ) -> Result<StatusCode, StatusCode> {
+        match retry.handle(&mut request, script).await {
+            Ok(response) => Ok(response.status()),
+            Err(error) => Err(error.status()),
+        }
+    }
+
+    #[tokio::test]
+    async fn transient_failures_are_retried_with_the_body() {
+        let mut script = Script::failing(2, StatusCode::SERVICE_UNAVAILABLE);
+        let request = build(Method::PUT, Body::from_text("payload"));
+        let status = call(&mut retry(3), &mut script, request).await;
+        assert_eq!(status, Ok(StatusCode::OK));
+        assert_eq!(script.bodies, ["payload"; 3]);
+
+        // The last failure is returned once the attempts are exhausted.
+        let mut script = Script::failing(5, StatusCode::BAD_GATEWAY);
+        let request = build(Method::GET, Body::empty());
+        let status = call(&mut retry(2), &mut script, request).await;
+        assert_eq!(status, Ok(StatusCode::BAD_GATEWAY));
+        assert_eq!(script.bodies.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn only_transient_failures_are_retried() {
+        let statuses = [
+            (Err(StatusCode::INTERNAL_SERVER_ERROR), 3),
+            (Ok(StatusCode::GATEWAY_TIMEOUT), 3),
+            (Ok(StatusCode::INTERNAL_SERVER_ERROR), 1),
+            (Ok(StatusCode::NOT_FOUND), 1),
+            (Err(StatusCode::BAD_REQUEST), 1),
+        ];
+        for (outcome, attempts) in statuses {
+            let mut script = Script::new(vec![outcome]);
+            let request = build(Method::GET, Body::empty());
+            let status = call(&mut retry(3), &mut script, request).await;
+            assert_eq!(status, outcome);
+            assert_eq!(script.bodies.len(), attempts, "{outcome:?}");
+        }
+
+        let mut retry = retry(3).when(|outcome| outcome.status() == StatusCode::CONFLICT);
+        let mut script = Script::failing(1, StatusCode::CONFLICT);
+        let status = call(&mut retry, &mut script, build(Method::GET, Body::empty())).await;
+        assert_eq!(status, Ok(StatusCode::OK));
+    }
+
+    #[tokio::test]
+    async fn requests_that_cannot_be_replayed_are_sent_once() {
+        let chunks = ["stre", "amed"].map(|chunk| Ok::<_, crate::BodyError>(chunk.as_bytes()));
+        let streaming = Body::from_stream(stream::iter(chunks));
+        let mut script = Script::failing(1, StatusCode::SERVICE_UNAVAILABLE);
+        let status = call(&mut retry(3), &mut script, build(Method::PUT, streaming)).await;
+        assert_eq!(status, Ok(StatusCode::SERVICE_UNAVAILABLE));
+        assert_eq!(script.bodies, ["streamed"]);
+
+        let mut script = Script::failing(1, StatusCode::SERVICE_UNAVAILABLE);
+        let post = build(Method::POST, Body::from_text("order"));
+        let status = call(&mut retry(3), &mut script, post).await;
+        assert_eq!(status, Ok(StatusCode::SERVICE_UNAVAILABLE));
+        assert_eq!(script.bodies.len(), 1);
+
+        // An idempotency key makes a POST safe to send again.
+        let mut script = Script::failing(1, StatusCode::SERVICE_UNAVAILABLE);
+        let mut post = build(Method::POST, Body::from_text("order"));
+        let key = HeaderValue::from_static("8e03978e");
+        post.headers_mut().insert(headers::IDEMPOTENCY_KEY, key);
+        let status = call(&mut retry(3), &mut script, post).await;
+        assert_eq!(status, Ok(StatusCode::OK));
+        assert_eq!(script.bodies, ["order", "order"]);
+    }
+
+    #[tokio::test]
+    async fn retry_after_overrides_the_backoff() {
+        // The backoff alone would outlast the test.
+        let mut retry = Retry::new(2).backoff(Fixed::new(Duration::from_secs(3600)));
+        let mut script = Script::failing(1, StatusCode::SERVICE_UNAVAILABLE);
+        script.retry_after = Some("0");
+        let status = call(&mut retry, &mut script, build(Method::GET, Body::empty())).await;
+        assert_eq!(status, Ok(StatusCode::OK));
+        assert_eq!(script.bodies.len(), 2);
+
+        let mut script = Script::failing(1, StatusCode::SERVICE_UNAVAILABLE);
+        script.retry_after = Some("Wed, 21 Oct 2015 07:28:00 GMT");
+        let status = call(&mut retry, &mut script, build(Method::GET, Body::empty())).await;
+        assert_eq!(status, Ok(StatusCode::OK));
+
+        let mut script = Script::failing(1, StatusCode::SERVICE_UNAVAILABLE);
+        script.retry_after = Some("120");
+        let status = call(&mut retry, &mut script, build(Method::GET, Body::empty())).await;
+        assert_eq!(status, Ok(StatusCode::SERVICE_UNAVAILABLE));
+        assert_eq!(script.bodies.len(), 1);
+    }
+
+    #[test]
+    fn exponential_delays_are_capped() {
+        let mut backoff = Exponential::new(Duration::from_millis(100), Duration::from_secs(1));
+        let delays: Vec<_> = (1..=6).map(|retry| backoff.delay(retry)).collect();
+        let millis = [100, 200, 400, 800, 1000, 1000].map(Duration::from_millis);
+        assert_eq!(delays, millis);
+        assert_eq!(backoff.delay(u32::MAX), Duration::from_secs(1));
+    }
+}
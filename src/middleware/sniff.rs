@@ -0,0 +1,251 @@
+//! Rejection of uploads whose content does not match the declared type.
+//!
+//! [`UploadTypeGuard`] peeks the first bytes of request bodies, sniffs their media type
+//! with [`utils::sniff`](crate::utils::sniff) and rejects requests whose declared
+//! `Content-Type` hides dangerous content, such as an "image" that is actually a ZIP
+//! archive or an executable.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use http_kit::{Request, Response, Endpoint, Body};
+//! use http_kit::endpoint::WithMiddleware;
+//! use http_kit::middleware::sniff::UploadTypeGuard;
+//! use core::convert::Infallible;
+//!
+//! struct Upload;
+//!
+//! impl Endpoint for Upload {
+//!     type Error = Infallible;
+//!     async fn respond(&mut self, _request: &mut Request) -> Result<Response, Self::Error> {
+//!         Ok(Response::new(Body::empty()))
+//!     }
+//! }
+//!
+//! let endpoint = WithMiddleware::new(Upload, UploadTypeGuard::new());
+//! ```
+
+use alloc::{format, string::String, vec::Vec};
+use core::convert::Infallible;
+
+use http::{header, StatusCode};
+use mime::Mime;
+
+use crate::{
+    middleware::MiddlewareError,
+    response::text_response,
+    utils::sniff::{self, SNIFF_LEN},
+    Endpoint, Middleware, Request, Response,
+};
+
+/// Middleware rejecting request bodies whose magic bytes contradict the declared type.
+///
+/// The declared type is read from the `Content-Type` header, falling back to
+/// [`Body::mime`]. Bodies are rejected with `415 Unsupported Media Type` when:
+///
+/// - the sniffed type is [dangerous](sniff::is_dangerous), is not compatible with the
+///   declared type according to [`sniff::matches`] and the pair was not explicitly
+///   allowed, or
+/// - the content is a native executable, unless executables are allowed.
+///
+/// Harmless content under another type, such as text declared as an image, bodies
+/// without a recognized signature and requests without a declared type (other than
+/// executables) are passed through. The peeked bytes are put back, so the endpoint
+/// receives the complete body, with its length and trailers.
+#[derive(Debug, Clone)]
+pub struct UploadTypeGuard {
+    peek_len: usize,
+    reject_executables: bool,
+    allowed: Vec<(Mime, Mime)>,
+}
+
+impl Default for UploadTypeGuard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl UploadTypeGuard {
+    /// Creates a guard peeking [`SNIFF_LEN`] bytes and rejecting executables.
+    pub fn new() -> Self {
+        Self {
+            peek_len: SNIFF_LEN,
+            reject_executables: true,
+            allowed: Vec::new(),
+        }
+    }
+
+    /// Sets how many bytes of each body are peeked.
+    #[must_use]
+    pub fn peek_len(mut self, peek_len: usize) -> Self {
+        self.peek_len = peek_len;
+        self
+    }
+
+    /// Sets whether executables are accepted when they match the declared type.
+    #[must_use]
+    pub fn allow_executables(mut self, allow: bool) -> Self {
+        self.reject_executables = !allow;
+        self
+    }
+
+    /// Accepts content sniffed as `sniffed` when `declared` is the declared type.
+    ///
+    /// Only the essence (type and subtype) of both types is compared.
+    #[must_use]
+    pub fn allow(mut self, declared: Mime, sniffed: Mime) -> Self {
+        self.allowed.push((declared, sniffed));
+        self
+    }
+
+    fn is_allowed(&self, declared: &Mime, sniffed: &Mime) -> bool {
+        sniff::matches(declared, sniffed)
+            || self
+                .allowed
+                .iter()
+                .any(|(allowed_declared, allowed_sniffed)| {
+                    allowed_declared.essence_str() == declared.essence_str()
+                        && allowed_sniffed.essence_str() == sniffed.essence_str()
+                })
+    }
+
+    fn reject(message: String) -> Response {
+        text_response(StatusCode::UNSUPPORTED_MEDIA_TYPE, message)
+    }
+}
+
+impl Middleware for UploadTypeGuard {
+    type Error = Infallible;
+    async fn handle<E: Endpoint>(
+        &mut self,
+        request: &mut Request,
+        mut next: E,
+    ) -> Result<Response, MiddlewareError<E::Error, Self::Error>> {
+        let declared = request
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<Mime>().ok())
+            .or_else(|| request.body().mime().cloned());
+
+        // A body that cannot be peeked is left for the endpoint to report.
+        let sniffed = match request.body_mut().peek(self.peek_len).await {
+            Ok(prefix) => sniff::sniff(&prefix),
+            Err(_) => None,
+        };
+
+        if let Some(sniffed) = sniffed {
+            if self.reject_executables && sniff::is_executable(&sniffed) {
+                return Ok(Self::reject(String::from(
+                    "Executable uploads are not allowed",
+                )));
+            }
+            if let Some(declared) = declared {
+                if sniff::is_dangerous(&sniffed) && !self.is_allowed(&declared, &sniffed) {
+                    return Ok(Self::reject(format!(
+                        "Declared content type {} does not match the content ({})",
+                        declared.essence_str(),
+                        sniffed.essence_str()
+                    )));
+                }
+            }
+        }
+
+        next.respond(request)
+            .await
+            .map_err(MiddlewareError::Endpoint)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{endpoint::WithMiddleware, Body};
+    use bytes::Bytes;
+    use http::HeaderValue;
+
+    struct Store;
+
+    impl Endpoint for Store {
+        type Error = Infallible;
+        async fn respond(&mut self, request: &mut Request) -> Result<Response, Self::Error> {
+            let bytes = request
+                .body_mut()
+                .take()
+                .unwrap()
+                .into_bytes()
+                .await
+                .unwrap();
+            Ok(Response::new(Body::from_bytes(bytes)))
+        }
+    }
+
+    fn upload(content_type: &'static str, data: &'static [u8]) -> Request {
+        let mut request = Request::new(Body::from_bytes(data));
+        request
+            .headers_mut()
+            .insert(header::CONTENT_TYPE, HeaderValue::from_static(content_type));
+        request
+    }
+
+    async fn send(guard: UploadTypeGuard, mut request: Request) -> Response {
+        let mut endpoint = WithMiddleware::new(Store, guard);
+        endpoint.respond(&mut request).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn rejects_mismatched_upload() {
+        let request = upload("image/png", b"PK\x03\x04\x14\0\x06\0");
+        let response = send(UploadTypeGuard::new(), request).await;
+        assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+        let request = upload("image/png", b"PK\x03\x04\x14\0\x06\0");
+        let guard =
+            UploadTypeGuard::new().allow(mime::IMAGE_PNG, "application/zip".parse().unwrap());
+        assert_eq!(send(guard, request).await.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn rejects_executables() {
+        let request = upload("application/octet-stream", b"\x7FELF\x02\x01\x01\0");
+        let response = send(UploadTypeGuard::new(), request).await;
+        assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    }
+
+    #[tokio::test]
+    async fn passes_matching_and_unknown_content() {
+        let png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+        let response = send(UploadTypeGuard::new().peek_len(4), upload("image/png", png)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.into_body().into_bytes().await.unwrap(),
+            Bytes::from_static(png)
+        );
+
+        let request = upload("application/octet-stream", b"\x01\x02\x03");
+        let response = send(UploadTypeGuard::new(), request).await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn passes_generic_declarations_and_harmless_content() {
+        let form = b"--boundary\r\nContent-Disposition: form-data; name=\"a\"\r\n\r\n1\r\n";
+        let request = upload("multipart/form-data; boundary=boundary", form);
+        let response = send(UploadTypeGuard::new(), request).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        for data in [&b"plain text"[..], b"\x89PNG\r\n\x1a\n", b"PK\x03\x04"] {
+            let request = upload("application/octet-stream", data);
+            let response = send(UploadTypeGuard::new(), request).await;
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+
+        let request = upload("image/png", b"not a picture");
+        let response = send(UploadTypeGuard::new(), request).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let request = upload("text/plain", b"%PDF-1.7\n");
+        let response = send(UploadTypeGuard::new(), request).await;
+        assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    }
+}
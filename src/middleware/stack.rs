@@ -0,0 +1,270 @@
+//! A runtime-assembled chain of middleware.
+
+use alloc::{boxed::Box, vec::Vec};
+use core::{future::Future, pin::Pin};
+
+use crate::{
+    endpoint::EndpointImpl,
+    error::BoxHttpError,
+    middleware::{AnyMiddleware, MiddlewareError},
+    Endpoint, Middleware, Request, Response,
+};
+
+/// An ordered list of middleware applied as one.
+///
+/// Middleware run in registration order around the endpoint: the first registered one
+/// sees the request first and the response last. Any of them can answer without calling
+/// the rest of the chain, in which case the later middleware and the endpoint are
+/// skipped.
+///
+/// Unlike nested [`WithMiddleware`](crate::endpoint::WithMiddleware), the stack can be
+/// assembled at runtime, for instance from configuration. Errors of the middleware and
+/// of the endpoint are returned as [`BoxHttpError`] and keep their status code.
+///
+/// # Examples
+///
+/// ```rust
+/// # #[cfg(feature = "std")]
+/// # {
+/// use core::time::Duration;
+/// use http_kit::middleware::{body_policy::BodyPolicy, timeout::Timeout, MiddlewareStack};
+///
+/// let mut stack = MiddlewareStack::new().with(BodyPolicy::strict());
+/// stack.push(Timeout::new(Duration::from_secs(30)));
+/// assert_eq!(stack.len(), 2);
+/// # }
+/// ```
+#[derive(Debug, Default)]
+pub struct MiddlewareStack {
+    middlewares: Vec<AnyMiddleware>,
+}
+
+impl MiddlewareStack {
+    /// Creates an empty stack, which passes requests straight to the endpoint.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends `middleware`, which runs inside the ones already registered.
+    pub fn push(&mut self, middleware: impl Middleware + 'static) {
+        self.middlewares.push(AnyMiddleware::new(middleware));
+    }
+
+    /// Appends `middleware` and returns the stack, for chained construction.
+    #[must_use]
+    pub fn with(mut self, middleware: impl Middleware + 'static) -> Self {
+        self.push(middleware);
+        self
+    }
+
+    /// Returns the number of middleware in the stack.
+    pub fn len(&self) -> usize {
+        self.middlewares.len()
+    }
+
+    /// Returns whether the stack has no middleware.
+    pub fn is_empty(&self) -> bool {
+        self.middlewares.is_empty()
+    }
+}
+
+// The part of the chain that still has to run: the remaining middleware, then the
+// endpoint. Each middleware receives the chain after it as its `next` endpoint.
+struct Chain<'a> {
+    middlewares: &'a mut [AnyMiddleware],
+    endpoint: &'a mut dyn EndpointImpl,
+}
+
+impl EndpointImpl for Chain<'_> {
+    fn respond_inner<'this, 'req, 'fut>(
+        &'this mut self,
+        request: &'req mut Request,
+    ) -> Pin<Box<dyn 'fut + Send + Future<Output = Result<Response, BoxHttpError>>>>
+    where
+        'this: 'fut,
+        'req: 'fut,
+    {
+        Box::pin(async move {
+            match self.middlewares.split_first_mut() {
+                Some((first, rest)) => {
+                    let mut next = Chain {
+                        middlewares: rest,
+                        endpoint: &mut *self.endpoint,
+                    };
+                    first.0.handle_inner(request, &mut next).await
+                }
+                None => self.endpoint.respond_inner(request).await,
+            }
+        })
+    }
+}
+
+impl Middleware for MiddlewareStack {
+    type Error = BoxHttpError;
+    async fn handle<E: Endpoint>(
+        &mut self,
+        request: &mut Request,
+        mut next: E,
+    ) -> Result<Response, MiddlewareError<E::Error, Self::Error>> {
+        let mut chain = Chain {
+            middlewares: &mut self.middlewares,
+            endpoint: &mut next,
+        };
+        chain
+            .respond_inner(request)
+            .await
+            .map_err(MiddlewareError::Middleware)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use crate::{endpoint::WithMiddleware, Body, HttpError};
+    use alloc::{format, string::String, sync::Arc, vec};
+    use core::fmt;
+    use http::StatusCode;
+    use std::sync::Mutex;
+
+    type Log = Arc<Mutex<Vec<String>>>;
+
+    // Records its name before and after the rest of the chain, or answers on its own
+    // when `block` is set.
+    struct Step {
+        name: &'static str,
+        log: Log,
+        block: bool,
+    }
+
+    impl Middleware for Step {
+        type Error = BoxHttpError;
+        async fn handle<E: Endpoint>(
+            &mut self,
+            request: &mut Request,
+            mut next: E,
+        ) -> Result<Response, MiddlewareError<E::Error, Self::Error>> {
+            self.log.lock().unwrap().push(format!("{} pre", self.name));
+            if self.block {
+                let mut response = Response::new(Body::empty());
+                *response.status_mut() = StatusCode::FORBIDDEN;
+                return Ok(response);
+            }
+            let response = next
+                .respond(request)
+                .await
+                .map_err(MiddlewareError::Endpoint)?;
+            self.log.lock().unwrap().push(format!("{} post", self.name));
+            Ok(response)
+        }
+    }
+
+    struct Handler(Log);
+
+    impl Endpoint for Handler {
+        type Error = BoxHttpError;
+        async fn respond(&mut self, _request: &mut Request) -> Result<Response, Self::Error> {
+            self.0.lock().unwrap().push(String::from("endpoint"));
+            Ok(Response::new(Body::empty()))
+        }
+    }
+
+    fn step(name: &'static str, log: &Log) -> Step {
+        Step {
+            name,
+            log: log.clone(),
+            block: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn runs_in_registration_order() {
+        let log = Log::default();
+        let mut stack = MiddlewareStack::new().with(step("a", &log));
+        stack.push(step("b", &log));
+        stack.push(step("c", &log));
+
+        let mut endpoint = WithMiddleware::new(Handler(log.clone()), stack);
+        let response = endpoint
+            .respond(&mut Request::new(Body::empty()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            *log.lock().unwrap(),
+            ["a pre", "b pre", "c pre", "endpoint", "c post", "b post", "a post"]
+        );
+    }
+
+    #[tokio::test]
+    async fn short_circuit_skips_the_rest() {
+        let log = Log::default();
+        let stack = MiddlewareStack::new()
+            .with(step("a", &log))
+            .with(Step {
+                block: true,
+                ..step("b", &log)
+            })
+            .with(step("c", &log));
+
+        let mut endpoint = WithMiddleware::new(Handler(log.clone()), stack);
+        let response = endpoint
+            .respond(&mut Request::new(Body::empty()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert_eq!(*log.lock().unwrap(), ["a pre", "b pre", "a post"]);
+    }
+
+    #[tokio::test]
+    async fn errors_keep_their_status() {
+        #[derive(Debug)]
+        struct Teapot;
+
+        impl fmt::Display for Teapot {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("short and stout")
+            }
+        }
+
+        impl core::error::Error for Teapot {}
+
+        impl HttpError for Teapot {
+            fn status(&self) -> StatusCode {
+                StatusCode::IM_A_TEAPOT
+            }
+        }
+
+        struct Fail;
+
+        impl Middleware for Fail {
+            type Error = Teapot;
+            async fn handle<E: Endpoint>(
+                &mut self,
+                _request: &mut Request,
+                _next: E,
+            ) -> Result<Response, MiddlewareError<E::Error, Self::Error>> {
+                Err(MiddlewareError::Middleware(Teapot))
+            }
+        }
+
+        let log = Log::default();
+        let stack = MiddlewareStack::new().with(step("a", &log)).with(Fail);
+        let mut endpoint = WithMiddleware::new(Handler(log.clone()), stack);
+        let error = endpoint
+            .respond(&mut Request::new(Body::empty()))
+            .await
+            .unwrap_err();
+        assert_eq!(error.status(), StatusCode::IM_A_TEAPOT);
+        assert_eq!(*log.lock().unwrap(), ["a pre"]);
+
+        // An empty stack passes the request straight through.
+        let mut endpoint = WithMiddleware::new(Handler(log.clone()), MiddlewareStack::new());
+        assert!(endpoint
+            .respond(&mut Request::new(Body::empty()))
+            .await
+            .is_ok());
+        assert_eq!(vec!["a pre", "endpoint"], *log.lock().unwrap());
+    }
+}
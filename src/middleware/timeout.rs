@@ -0,0 +1,186 @@
+//! Deadlines for endpoint execution.
+
+use alloc::sync::Arc;
+use core::{fmt, time::Duration};
+
+use futures_lite::FutureExt;
+use futures_timer::Delay;
+use http::StatusCode;
+
+use crate::{middleware::MiddlewareError, Endpoint, HttpError, Middleware, Request, Response};
+
+type Respond = Arc<dyn Fn() -> Response + Send + Sync>;
+
+/// Error returned by [`Timeout`] when the endpoint does not respond in time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeoutError {
+    status: StatusCode,
+}
+
+impl fmt::Display for TimeoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("request timed out")
+    }
+}
+
+impl core::error::Error for TimeoutError {}
+
+impl HttpError for TimeoutError {
+    fn status(&self) -> StatusCode {
+        self.status
+    }
+}
+
+/// Middleware failing requests whose endpoint takes longer than a deadline.
+///
+/// On expiry the endpoint future is dropped, and never polled again, and a
+/// [`TimeoutError`] is returned with status `504 Gateway Timeout` unless configured
+/// otherwise. With [`with_response`](Self::with_response), a response is returned
+/// instead. The request body may have been partly read by then.
+#[derive(Clone)]
+pub struct Timeout {
+    duration: Duration,
+    status: StatusCode,
+    response: Option<Respond>,
+}
+
+impl fmt::Debug for Timeout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Timeout")
+            .field("duration", &self.duration)
+            .field("status", &self.status)
+            .field("response", &self.response.is_some())
+            .finish()
+    }
+}
+
+impl Timeout {
+    /// Creates a timeout middleware with the given deadline.
+    pub const fn new(duration: Duration) -> Self {
+        Self {
+            duration,
+            status: StatusCode::GATEWAY_TIMEOUT,
+            response: None,
+        }
+    }
+
+    /// Sets the status code of the [`TimeoutError`].
+    #[must_use]
+    pub const fn with_status(mut self, status: StatusCode) -> Self {
+        self.status = status;
+        self
+    }
+
+    /// Answers timed out requests with the response built by `response` instead of
+    /// failing with a [`TimeoutError`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use core::time::Duration;
+    /// use http_kit::{header, middleware::timeout::Timeout, Body, Response, StatusCode};
+    ///
+    /// let retry_after = 30;
+    /// let timeout = Timeout::new(Duration::from_secs(10)).with_response(move || {
+    ///     let mut response = Response::new(Body::from_text("try again later"));
+    ///     *response.status_mut() = StatusCode::SERVICE_UNAVAILABLE;
+    ///     response.headers_mut().insert(header::RETRY_AFTER, retry_after.into());
+    ///     response
+    /// });
+    /// ```
+    #[must_use]
+    pub fn with_response(
+        mut self,
+        response: impl Fn() -> Response + Send + Sync + 'static,
+    ) -> Self {
+        self.response = Some(Arc::new(response));
+        self
+    }
+}
+
+impl Middleware for Timeout {
+    type Error = TimeoutError;
+    async fn handle<E: Endpoint>(
+        &mut self,
+        request: &mut Request,
+        mut next: E,
+    ) -> Result<Response, MiddlewareError<E::Error, Self::Error>> {
+        let (status, response) = (self.status, &self.response);
+        let respond = async {
+            next.respond(request)
+                .await
+                .map_err(MiddlewareError::Endpoint)
+        };
+        let expire = async {
+            Delay::new(self.duration).await;
+            match response {
+                Some(response) => Ok(response()),
+                None => Err(MiddlewareError::Middleware(TimeoutError { status })),
+            }
+        };
+        respond.or(expire).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{endpoint::WithMiddleware, Body};
+    use core::convert::Infallible;
+
+    // Sleeps for the number of milliseconds in the path before answering.
+    struct Slow;
+
+    impl Endpoint for Slow {
+        type Error = Infallible;
+        async fn respond(&mut self, request: &mut Request) -> Result<Response, Self::Error> {
+            let millis = request.uri().path()[1..].parse().unwrap();
+            Delay::new(Duration::from_millis(millis)).await;
+            Ok(Response::new(Body::from_text("done")))
+        }
+    }
+
+    async fn call(
+        timeout: Timeout,
+        millis: u64,
+    ) -> Result<Response, MiddlewareError<Infallible, TimeoutError>> {
+        let mut endpoint = WithMiddleware::new(Slow, timeout);
+        let mut request = Request::new(Body::empty());
+        *request.uri_mut() = alloc::format!("/{millis}").parse().unwrap();
+        endpoint.respond(&mut request).await
+    }
+
+    #[tokio::test]
+    async fn fast_endpoints_pass_through() {
+        let response = call(Timeout::new(Duration::from_secs(5)), 1).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.into_body().into_string().await.unwrap(), "done");
+    }
+
+    #[tokio::test]
+    async fn slow_endpoints_time_out() {
+        let timeout = Timeout::new(Duration::from_millis(20));
+        let error = call(timeout.clone(), 5_000).await.unwrap_err();
+        assert!(matches!(error, MiddlewareError::Middleware(_)));
+        assert_eq!(error.status(), StatusCode::GATEWAY_TIMEOUT);
+
+        let error = call(
+            timeout.clone().with_status(StatusCode::REQUEST_TIMEOUT),
+            5_000,
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(error.status(), StatusCode::REQUEST_TIMEOUT);
+
+        // The response may depend on configuration captured by the closure.
+        let message = alloc::string::String::from("busy");
+        let timeout = timeout.with_response(move || {
+            let mut response = Response::new(Body::from_text(message.clone()));
+            *response.status_mut() = StatusCode::SERVICE_UNAVAILABLE;
+            response
+        });
+        let response = call(timeout, 5_000).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.into_body().into_string().await.unwrap(), "busy");
+    }
+}
@@ -0,0 +1,163 @@
+//! Validation of request framing and size limits.
+
+use alloc::string::{String, ToString};
+use core::convert::Infallible;
+
+use http::{header, StatusCode};
+
+use crate::{
+    limits::Limits, middleware::MiddlewareError, response::text_response, Endpoint, HttpError,
+    Middleware, Request, Response,
+};
+
+// Status and message of a rejected request.
+type Rejection = (StatusCode, String);
+
+/// Middleware rejecting malformed or oversized requests before they reach the endpoint.
+///
+/// Requests are rejected with:
+///
+/// - `431 Request Header Fields Too Large` when the header count, a single header field
+///   or the total header size exceeds the [`Limits`], with a body naming the limit (see
+///   [`HeaderLimitError`](crate::limits::HeaderLimitError));
+/// - `400 Bad Request` when both `Content-Length` and `Transfer-Encoding` are present or
+///   `Content-Length` is malformed or ambiguous;
+/// - `413 Content Too Large` when the body exceeds [`Limits::max_body_size`].
+///
+/// Bodies of unknown length are checked by peeking up to the limit, so at most
+/// `max_body_size + 1` bytes are buffered before the endpoint runs.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ValidateRequest {
+    limits: Limits,
+}
+
+impl ValidateRequest {
+    /// Creates the middleware enforcing the given limits.
+    pub const fn new(limits: Limits) -> Self {
+        Self { limits }
+    }
+
+    /// Returns the enforced limits.
+    pub const fn limits(&self) -> &Limits {
+        &self.limits
+    }
+
+    fn reject((status, message): Rejection) -> Response {
+        text_response(status, message)
+    }
+
+    fn content_length(request: &Request) -> Result<Option<usize>, Rejection> {
+        let bad_request = |message| Err((StatusCode::BAD_REQUEST, String::from(message)));
+        let headers = request.headers();
+
+        let mut length = None;
+        for value in headers.get_all(header::CONTENT_LENGTH) {
+            let Some(parsed) = value
+                .to_str()
+                .ok()
+                .and_then(|value| value.trim().parse().ok())
+            else {
+                return bad_request("Malformed Content-Length");
+            };
+            if length.is_some_and(|length| length != parsed) {
+                return bad_request("Conflicting Content-Length values");
+            }
+            length = Some(parsed);
+        }
+        if length.is_some() && headers.contains_key(header::TRANSFER_ENCODING) {
+            return bad_request("Both Content-Length and Transfer-Encoding are present");
+        }
+        Ok(length)
+    }
+}
+
+impl Middleware for ValidateRequest {
+    type Error = Infallible;
+    async fn handle<E: Endpoint>(
+        &mut self,
+        request: &mut Request,
+        mut next: E,
+    ) -> Result<Response, MiddlewareError<E::Error, Self::Error>> {
+        let declared = match self
+            .limits
+            .check_headers(request.headers())
+            .map_err(|error| (error.status(), error.to_string()))
+            .and_then(|()| Self::content_length(request))
+        {
+            Ok(declared) => declared,
+            Err(rejection) => return Ok(Self::reject(rejection)),
+        };
+
+        let max = self.limits.max_body_size;
+        let too_large = match declared.or(request.body().len()) {
+            Some(length) => length > max,
+            // A body that cannot be peeked is left for the endpoint to report.
+            None => request
+                .body_mut()
+                .peek(max.saturating_add(1))
+                .await
+                .is_ok_and(|prefix| prefix.len() > max),
+        };
+        if too_large {
+            return Ok(Self::reject((
+                StatusCode::PAYLOAD_TOO_LARGE,
+                String::from("Request body too large"),
+            )));
+        }
+
+        next.respond(request)
+            .await
+            .map_err(MiddlewareError::Endpoint)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{endpoint::WithMiddleware, Body};
+    use http::HeaderValue;
+
+    struct Ok200;
+
+    impl Endpoint for Ok200 {
+        type Error = Infallible;
+        async fn respond(&mut self, _request: &mut Request) -> Result<Response, Self::Error> {
+            Ok(Response::new(Body::empty()))
+        }
+    }
+
+    #[tokio::test]
+    async fn oversized_headers_are_rejected_with_431() {
+        let limits = Limits::default().with_max_header_size(16);
+        let mut endpoint = WithMiddleware::new(Ok200, ValidateRequest::new(limits));
+
+        let mut request = Request::new(Body::empty());
+        request
+            .headers_mut()
+            .insert("x-token", HeaderValue::from_static("0123456789abcdef"));
+        let response = endpoint.respond(&mut request).await.unwrap();
+        assert_eq!(
+            response.status(),
+            StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE
+        );
+        assert_eq!(
+            response.into_body().into_string().await.unwrap(),
+            "header field `x-token` too large (limit: 16 bytes)"
+        );
+    }
+
+    #[tokio::test]
+    async fn ambiguous_framing_is_rejected_with_400() {
+        let mut endpoint = WithMiddleware::new(Ok200, ValidateRequest::default());
+
+        let mut request = Request::new(Body::empty());
+        let headers = request.headers_mut();
+        headers.insert(header::CONTENT_LENGTH, HeaderValue::from_static("0"));
+        headers.insert(
+            header::TRANSFER_ENCODING,
+            HeaderValue::from_static("chunked"),
+        );
+        let response = endpoint.respond(&mut request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+}
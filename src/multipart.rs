@@ -0,0 +1,347 @@
+//! Building `multipart/form-data` bodies.
+//!
+//! [`MultipartBuilder`] assembles the fields of an HTML form, including file uploads,
+//! into a [`Body`] for outgoing requests. In-memory fields are serialized up front,
+//! while streaming parts such as files are read only as the body is sent.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use http_kit::multipart::MultipartBuilder;
+//! use http_kit::{header, Body, Request, RequestExt};
+//!
+//! let form = MultipartBuilder::new()
+//!     .text("title", "Quarterly report")
+//!     .bytes("attachment", "report.csv", mime::TEXT_CSV, "q,revenue\n3,42\n");
+//!
+//! let mut request = Request::new(Body::empty());
+//! request.multipart(form);
+//!
+//! let content_type = request.headers()[header::CONTENT_TYPE].to_str().unwrap();
+//! assert!(content_type.starts_with("multipart/form-data; boundary="));
+//! ```
+
+#[cfg(feature = "std")]
+extern crate std;
+
+use alloc::{format, string::String, vec::Vec};
+
+use bytes::{Bytes, BytesMut};
+use bytestr::ByteStr;
+use futures_lite::{stream, StreamExt};
+use mime::Mime;
+
+use crate::{headers::ContentDisposition, Body};
+
+/// Builder of a `multipart/form-data` body.
+///
+/// Fields are emitted in the order they are added. The boundary is chosen when the body
+/// is [built](Self::build): it never occurs in the in-memory fields, and is random
+/// enough that a streamed part containing it is practically impossible.
+#[derive(Debug, Default)]
+pub struct MultipartBuilder {
+    parts: Vec<Part>,
+}
+
+#[derive(Debug)]
+struct Part {
+    // Part headers, including the blank line that ends them.
+    head: String,
+    data: PartData,
+}
+
+#[derive(Debug)]
+enum PartData {
+    Bytes(Bytes),
+    Stream(Body),
+}
+
+impl MultipartBuilder {
+    /// Creates a builder without fields.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a text field.
+    #[must_use]
+    pub fn text(mut self, name: &str, value: impl Into<ByteStr>) -> Self {
+        let value: ByteStr = value.into();
+        self.parts.push(Part {
+            head: head(name, None, None),
+            data: PartData::Bytes(value.into()),
+        });
+        self
+    }
+
+    /// Appends a file field whose contents are already in memory.
+    #[must_use]
+    pub fn bytes(mut self, name: &str, filename: &str, mime: Mime, data: impl Into<Bytes>) -> Self {
+        self.parts.push(Part {
+            head: head(name, Some(filename), Some(&mime)),
+            data: PartData::Bytes(data.into()),
+        });
+        self
+    }
+
+    /// Appends a field streamed from `body`.
+    ///
+    /// The part is labelled with the MIME type of the body, if any, and is only read
+    /// when the multipart body is sent.
+    #[must_use]
+    pub fn part(mut self, name: &str, filename: Option<&str>, body: Body) -> Self {
+        self.parts.push(Part {
+            head: head(name, filename, body.mime()),
+            data: PartData::Stream(body),
+        });
+        self
+    }
+
+    /// Appends a file field streamed from the file at `path`.
+    ///
+    /// The file name is sent without its directories and the MIME type is guessed from
+    /// the extension, like [`Body::from_file`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be opened.
+    #[cfg(all(feature = "fs", feature = "std"))]
+    pub async fn file(
+        self,
+        name: &str,
+        path: impl AsRef<std::path::Path>,
+    ) -> Result<Self, std::io::Error> {
+        let path = path.as_ref();
+        let body = Body::from_file(path).await?;
+        let filename = path.file_name().map(|name| name.to_string_lossy());
+        Ok(self.part(name, filename.as_deref(), body))
+    }
+
+    /// Serializes the fields into a body typed `multipart/form-data; boundary=...`.
+    ///
+    /// The body has a known length when all fields are in memory, and is streamed
+    /// otherwise.
+    pub fn build(self) -> Body {
+        let boundary = loop {
+            let boundary = next_boundary();
+            let delimiter = format!("--{boundary}");
+            let collides = self.parts.iter().any(|part| match &part.data {
+                PartData::Bytes(data) => contains(data, delimiter.as_bytes()),
+                PartData::Stream(_) => false,
+            });
+            if !collides {
+                break boundary;
+            }
+        };
+        let mime: Mime = format!("multipart/form-data; boundary={boundary}")
+            .parse()
+            .expect("boundaries are valid MIME parameters");
+
+        // Runs of in-memory data are merged into single segments between streamed parts.
+        let mut segments = Vec::new();
+        let mut pending = BytesMut::new();
+        for part in self.parts {
+            pending.extend_from_slice(format!("--{boundary}\r\n").as_bytes());
+            pending.extend_from_slice(part.head.as_bytes());
+            match part.data {
+                PartData::Bytes(data) => pending.extend_from_slice(&data),
+                PartData::Stream(body) => {
+                    segments.push(Body::from_bytes(pending.split().freeze()));
+                    segments.push(body);
+                }
+            }
+            pending.extend_from_slice(b"\r\n");
+        }
+        pending.extend_from_slice(format!("--{boundary}--\r\n").as_bytes());
+
+        let body = if segments.is_empty() {
+            Body::from_bytes(pending.freeze())
+        } else {
+            segments.push(Body::from_bytes(pending.freeze()));
+            Body::from_stream(stream::iter(segments).flatten())
+        };
+        body.with_mime(mime)
+    }
+}
+
+fn head(name: &str, filename: Option<&str>, mime: Option<&Mime>) -> String {
+    let disposition = ContentDisposition::form_data(name, filename);
+    let mut head = format!("content-disposition: {disposition}\r\n");
+    if let Some(mime) = mime {
+        head.push_str(&format!("content-type: {mime}\r\n"));
+    }
+    head.push_str("\r\n");
+    head
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack
+        .windows(needle.len())
+        .any(|window| window == needle)
+}
+
+// Streamed parts cannot be checked for the boundary in advance, so with `std` the
+// boundary is derived from the per-process random keys of `RandomState`.
+#[cfg(feature = "std")]
+fn next_boundary() -> String {
+    use core::hash::BuildHasher;
+    use std::collections::hash_map::RandomState;
+
+    let high = RandomState::new().hash_one(0u8);
+    let low = RandomState::new().hash_one(1u8);
+    format!("form-{high:016x}{low:016x}")
+}
+
+#[cfg(not(feature = "std"))]
+fn next_boundary() -> String {
+    use core::sync::atomic::{AtomicU64, Ordering};
+
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let n = COUNTER.fetch_add(1, Ordering::Relaxed);
+    let scrambled = (n ^ 0x2545_F491_4F6C_DD1D).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    format!("form-{scrambled:016x}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::{string::ToString, vec};
+
+    // A minimal `multipart/form-data` reader returning the headers and data of each part.
+    fn parse(body: &[u8], mime: &Mime) -> Vec<(String, Bytes)> {
+        let boundary = mime.get_param(mime::BOUNDARY).unwrap().as_str();
+        let delimiter = format!("\r\n--{boundary}");
+        let body = Bytes::copy_from_slice(body);
+        let text = body.as_ref();
+
+        let first = format!("--{boundary}\r\n");
+        assert!(text.starts_with(first.as_bytes()));
+        let mut rest = &text[first.len()..];
+        let mut parts = Vec::new();
+        loop {
+            let end = rest
+                .windows(delimiter.len())
+                .position(|window| window == delimiter.as_bytes())
+                .expect("delimiter after part");
+            let part = &rest[..end];
+            let split = part
+                .windows(4)
+                .position(|window| window == b"\r\n\r\n")
+                .unwrap();
+            let head = core::str::from_utf8(&part[..split]).unwrap().to_string();
+            parts.push((head, body.slice_ref(&part[split + 4..])));
+            rest = &rest[end + delimiter.len()..];
+            if rest == b"--\r\n" {
+                return parts;
+            }
+            rest = rest
+                .strip_prefix(b"\r\n")
+                .expect("line break after delimiter");
+        }
+    }
+
+    #[tokio::test]
+    async fn in_memory_fields_round_trip() {
+        let body = MultipartBuilder::new()
+            .text("title", "hello")
+            .bytes(
+                "upload",
+                "a \"b\".bin",
+                mime::APPLICATION_OCTET_STREAM,
+                &b"\x00\r\n--\xFF"[..],
+            )
+            .build();
+        let mime = body.mime().unwrap().clone();
+        assert_eq!(mime.essence_str(), "multipart/form-data");
+        let len = body.len().unwrap();
+
+        let data = body.into_bytes().await.unwrap();
+        assert_eq!(data.len(), len);
+        let parts = parse(&data, &mime);
+        assert_eq!(
+            parts,
+            vec![
+                (
+                    "content-disposition: form-data; name=\"title\"".to_string(),
+                    Bytes::from_static(b"hello")
+                ),
+                (
+                    "content-disposition: form-data; name=\"upload\"; filename=\"a %22b%22.bin\"\r\n\
+                     content-type: application/octet-stream"
+                        .to_string(),
+                    Bytes::from_static(b"\x00\r\n--\xFF")
+                ),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn streamed_parts_are_not_buffered() {
+        let chunks = stream::iter(vec![
+            Ok::<_, core::convert::Infallible>("line 1\n"),
+            Ok("line 2\n"),
+        ]);
+        let body = MultipartBuilder::new()
+            .part(
+                "log",
+                Some("app.log"),
+                Body::from_stream(chunks).with_mime(mime::TEXT_PLAIN),
+            )
+            .text("level", "info")
+            .build();
+        assert_eq!(body.len(), None);
+
+        let mime = body.mime().unwrap().clone();
+        let parts = parse(&body.into_bytes().await.unwrap(), &mime);
+        assert_eq!(parts.len(), 2);
+        assert!(parts[0].0.ends_with("content-type: text/plain"));
+        let (line, _) = parts[0].0.split_once("\r\n").unwrap();
+        let disposition =
+            ContentDisposition::parse(line.strip_prefix("content-disposition: ").unwrap()).unwrap();
+        assert!(disposition.is_form_data());
+        assert_eq!(disposition.name(), Some("log"));
+        assert_eq!(disposition.filename(), Some("app.log"));
+        assert_eq!(parts[0].1, "line 1\nline 2\n");
+        assert_eq!(parts[1].1, "info");
+    }
+
+    #[tokio::test]
+    async fn empty_form() {
+        let body = MultipartBuilder::new().build();
+        let mime = body.mime().unwrap().clone();
+        let boundary = mime.get_param(mime::BOUNDARY).unwrap();
+        assert_eq!(
+            body.into_bytes().await.unwrap(),
+            format!("--{boundary}--\r\n")
+        );
+    }
+
+    #[test]
+    fn boundaries_differ() {
+        assert_ne!(next_boundary(), next_boundary());
+    }
+
+    #[cfg(all(feature = "fs", feature = "std"))]
+    #[tokio::test]
+    async fn file_parts_are_named_after_the_file() {
+        let path = std::env::temp_dir().join(format!("http-kit-form-{}.json", std::process::id()));
+        std::fs::write(&path, "{\"ok\":true}").unwrap();
+        let body = MultipartBuilder::new()
+            .file("config", &path)
+            .await
+            .unwrap()
+            .build();
+        std::fs::remove_file(&path).ok();
+
+        let mime = body.mime().unwrap().clone();
+        let parts = parse(&body.into_bytes().await.unwrap(), &mime);
+        let expected = format!(
+            "content-disposition: form-data; name=\"config\"; filename=\"{}\"\r\n\
+             content-type: application/json",
+            path.file_name().unwrap().to_str().unwrap()
+        );
+        assert_eq!(
+            parts,
+            vec![(expected, Bytes::from_static(b"{\"ok\":true}"))]
+        );
+    }
+}
@@ -0,0 +1,157 @@
+//! Path parameters matched by a router.
+//!
+//! Routers store the parameters they capture in a [`PathParams`] request extension, so
+//! that endpoints and middleware can read them with
+//! [`RequestExt::param`](crate::RequestExt::param) whichever router matched the route.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use http_kit::{params::PathParams, Body, Request, RequestExt};
+//!
+//! let mut params = PathParams::new();
+//! params.insert("id", "42");
+//!
+//! let mut request = Request::new(Body::empty());
+//! request.extensions_mut().insert(params);
+//!
+//! assert_eq!(request.param("id"), Some("42"));
+//! let id: u32 = request.extension::<PathParams>().unwrap().get_parsed("id")?;
+//! assert_eq!(id, 42);
+//! # Ok::<(), http_kit::Error>(())
+//! ```
+
+use alloc::{format, string::String, vec::Vec};
+use core::{fmt::Display, str::FromStr};
+
+use http::StatusCode;
+
+/// Named parameters captured from the request path, in the order they were matched.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PathParams {
+    params: Vec<(String, String)>,
+}
+
+impl PathParams {
+    /// Creates an empty set of parameters.
+    pub const fn new() -> Self {
+        Self { params: Vec::new() }
+    }
+
+    /// Sets the parameter `name`, returning its previous value.
+    pub fn insert(&mut self, name: impl Into<String>, value: impl Into<String>) -> Option<String> {
+        let (name, value) = (name.into(), value.into());
+        match self
+            .params
+            .iter_mut()
+            .find(|(existing, _)| *existing == name)
+        {
+            Some((_, existing)) => Some(core::mem::replace(existing, value)),
+            None => {
+                self.params.push((name, value));
+                None
+            }
+        }
+    }
+
+    /// Returns the value of the parameter `name`.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(existing, _)| existing == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Parses the value of the parameter `name`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error with status `400 Bad Request` if the value does not parse as
+    /// `T`, and `500 Internal Server Error` if there is no such parameter, which means
+    /// the route does not capture it.
+    pub fn get_parsed<T>(&self, name: &str) -> crate::Result<T>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        let Some(value) = self.get(name) else {
+            return Err(
+                crate::Error::msg(format!("missing path parameter `{name}`"))
+                    .set_status(StatusCode::INTERNAL_SERVER_ERROR),
+            );
+        };
+        value.parse().map_err(|error| {
+            crate::Error::msg(format!("invalid path parameter `{name}`: {error}"))
+                .set_status(StatusCode::BAD_REQUEST)
+        })
+    }
+
+    /// Returns an iterator over the names and values of the parameters.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.params
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+
+    /// Returns the number of parameters.
+    pub fn len(&self) -> usize {
+        self.params.len()
+    }
+
+    /// Returns whether there are no parameters.
+    pub fn is_empty(&self) -> bool {
+        self.params.is_empty()
+    }
+}
+
+impl<N: Into<String>, V: Into<String>> FromIterator<(N, V)> for PathParams {
+    fn from_iter<I: IntoIterator<Item = (N, V)>>(iter: I) -> Self {
+        let mut params = Self::new();
+        for (name, value) in iter {
+            params.insert(name, value);
+        }
+        params
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    fn status(error: crate::Error) -> StatusCode {
+        error.into_boxed_http_error().status()
+    }
+
+    #[test]
+    fn parameters_are_replaced_in_place() {
+        let mut params: PathParams = [("user", "alice"), ("post", "7")].into_iter().collect();
+        assert_eq!(params.insert("user", "bob"), Some("alice".to_string()));
+        assert_eq!(params.get("user"), Some("bob"));
+        assert_eq!(params.get("missing"), None);
+        assert_eq!(params.len(), 2);
+        assert_eq!(
+            params.iter().collect::<Vec<_>>(),
+            [("user", "bob"), ("post", "7")]
+        );
+        assert!(PathParams::new().is_empty());
+    }
+
+    #[test]
+    fn parse_failures_are_client_errors() {
+        let params: PathParams = [("id", "abc")].into_iter().collect();
+
+        let error = params.get_parsed::<u32>("id").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "invalid path parameter `id`: invalid digit found in string"
+        );
+        assert_eq!(status(error), StatusCode::BAD_REQUEST);
+
+        let error = params.get_parsed::<u32>("page").unwrap_err();
+        assert_eq!(error.to_string(), "missing path parameter `page`");
+        assert_eq!(status(error), StatusCode::INTERNAL_SERVER_ERROR);
+
+        assert_eq!(params.get_parsed::<String>("id").unwrap(), "abc");
+    }
+}
@@ -0,0 +1,267 @@
+//! Percent-encoding and decoding shared by the crate internals, and exposed in
+//! [`utils`](crate::utils).
+
+use alloc::{borrow::Cow, string::String, vec::Vec};
+use core::fmt;
+
+const HEX: &[u8; 16] = b"0123456789ABCDEF";
+
+fn hex(byte: u8) -> Option<u8> {
+    match byte {
+        b'0'..=b'9' => Some(byte - b'0'),
+        b'a'..=b'f' => Some(byte - b'a' + 10),
+        b'A'..=b'F' => Some(byte - b'A' + 10),
+        _ => None,
+    }
+}
+
+/// Decodes `%XX` escapes, optionally treating `+` as a space (form encoding).
+///
+/// Returns `None` on a truncated or non-hexadecimal escape.
+pub(crate) fn decode(input: &[u8], plus_as_space: bool) -> Option<Vec<u8>> {
+    let mut output = Vec::with_capacity(input.len());
+    let mut iter = input.iter();
+    while let Some(&byte) = iter.next() {
+        match byte {
+            b'%' => {
+                let high = iter.next().copied().and_then(hex)?;
+                let low = iter.next().copied().and_then(hex)?;
+                output.push(high << 4 | low);
+            }
+            b'+' if plus_as_space => output.push(b' '),
+            _ => output.push(byte),
+        }
+    }
+    Some(output)
+}
+
+/// Decodes like [`decode`], but keeps malformed escapes as they are, the way form parsers
+/// do. Borrows the input when there is nothing to decode.
+pub(crate) fn decode_lenient(input: &[u8], plus_as_space: bool) -> Cow<'_, [u8]> {
+    if !input
+        .iter()
+        .any(|&byte| byte == b'%' || (plus_as_space && byte == b'+'))
+    {
+        return Cow::Borrowed(input);
+    }
+    let mut output = Vec::with_capacity(input.len());
+    let mut index = 0;
+    while let Some(&byte) = input.get(index) {
+        index += 1;
+        match byte {
+            b'%' => {
+                let escape = input
+                    .get(index..index + 2)
+                    .and_then(|digits| Some(hex(digits[0])? << 4 | hex(digits[1])?));
+                match escape {
+                    Some(decoded) => {
+                        output.push(decoded);
+                        index += 2;
+                    }
+                    None => output.push(b'%'),
+                }
+            }
+            b'+' if plus_as_space => output.push(b' '),
+            _ => output.push(byte),
+        }
+    }
+    Cow::Owned(output)
+}
+
+fn push_escape(output: &mut String, byte: u8) {
+    output.push('%');
+    output.push(char::from(HEX[usize::from(byte >> 4)]));
+    output.push(char::from(HEX[usize::from(byte & 0xf)]));
+}
+
+/// Appends `input` to `output` encoded like HTML forms do: alphanumerics and `*-._` are
+/// kept, spaces become `+` and every other byte becomes a `%XX` escape.
+pub(crate) fn encode_form_component(input: &str, output: &mut String) {
+    for &byte in input.as_bytes() {
+        match byte {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'*' | b'-' | b'.' | b'_' => {
+                output.push(char::from(byte));
+            }
+            b' ' => output.push('+'),
+            _ => push_escape(output, byte),
+        }
+    }
+}
+
+// Escapes the bytes of `input` for which `keep` is false, borrowing the input when all
+// of them are kept.
+fn encode_with(input: &str, keep: fn(u8) -> bool) -> Cow<'_, str> {
+    let Some(first) = input.bytes().position(|byte| !keep(byte)) else {
+        return Cow::Borrowed(input);
+    };
+    let mut output = String::with_capacity(input.len() + 16);
+    output.push_str(&input[..first]);
+    for &byte in &input.as_bytes()[first..] {
+        if keep(byte) {
+            output.push(char::from(byte));
+        } else {
+            push_escape(&mut output, byte);
+        }
+    }
+    Cow::Owned(output)
+}
+
+// `unreserved` of RFC 3986.
+fn is_unreserved(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~')
+}
+
+// `pchar` of RFC 3986: what a path segment may hold verbatim.
+fn is_path_segment_char(byte: u8) -> bool {
+    is_unreserved(byte) || b"!$&'()*+,;=:@".contains(&byte)
+}
+
+// A query component may hold `pchar`, `/` and `?`, except the `&`, `=` and `+` that
+// separate or mean something to form decoders.
+fn is_query_component_char(byte: u8) -> bool {
+    is_unreserved(byte) || b"!$'()*,;:@/?".contains(&byte)
+}
+
+/// Percent-encodes `input` as a single URI path segment.
+///
+/// Everything outside the `pchar` set of RFC 3986 is escaped, including `/`, `?`, `#`,
+/// `%`, spaces and non-ASCII characters, whose UTF-8 bytes are escaped one by one.
+/// Sub-delimiters such as `+`, `;` and `=`, as well as `:` and `@`, are kept. The
+/// input is borrowed when nothing needs escaping.
+///
+/// # Examples
+///
+/// ```rust
+/// use http_kit::utils::percent_encode_path_segment;
+///
+/// assert_eq!(percent_encode_path_segment("a/b?c"), "a%2Fb%3Fc");
+/// assert_eq!(percent_encode_path_segment("café"), "caf%C3%A9");
+/// assert_eq!(percent_encode_path_segment("v1.2~rc"), "v1.2~rc");
+/// ```
+pub fn percent_encode_path_segment(input: &str) -> Cow<'_, str> {
+    encode_with(input, is_path_segment_char)
+}
+
+/// Percent-encodes `input` as the name or value of a query parameter.
+///
+/// On top of what [`percent_encode_path_segment`] escapes, `&` and `=` are escaped so
+/// that the component cannot split the query, and `+` is escaped because form decoders
+/// read it as a space. Spaces become `%20`, which every decoder reads as a space; `/`
+/// and `?` are kept. The input is borrowed when nothing needs escaping.
+///
+/// # Examples
+///
+/// ```rust
+/// use http_kit::utils::percent_encode_query_component;
+///
+/// let query = format!("q={}", percent_encode_query_component("c++ & rust = fun"));
+/// assert_eq!(query, "q=c%2B%2B%20%26%20rust%20%3D%20fun");
+/// assert_eq!(percent_encode_query_component("/docs?v=2"), "/docs?v%3D2");
+/// ```
+pub fn percent_encode_query_component(input: &str) -> Cow<'_, str> {
+    encode_with(input, is_query_component_char)
+}
+
+/// Decodes the `%XX` escapes of `input`.
+///
+/// A `+` is kept as is: only form decoders read it as a space. The input is borrowed
+/// when it holds no escape.
+///
+/// # Errors
+///
+/// Returns [`DecodeError::InvalidEscape`] if a `%` is not followed by two hexadecimal
+/// digits, and [`DecodeError::InvalidUtf8`] if the decoded bytes are not UTF-8.
+///
+/// # Examples
+///
+/// ```rust
+/// use http_kit::utils::percent_decode;
+///
+/// assert_eq!(percent_decode("caf%C3%A9+cr%C3%A8me").unwrap(), "café+crème");
+/// assert!(percent_decode("100%").is_err());
+/// ```
+pub fn percent_decode(input: &str) -> Result<Cow<'_, str>, DecodeError> {
+    if !input.contains('%') {
+        return Ok(Cow::Borrowed(input));
+    }
+    let bytes = decode(input.as_bytes(), false).ok_or(DecodeError::InvalidEscape)?;
+    String::from_utf8(bytes)
+        .map(Cow::Owned)
+        .map_err(|_| DecodeError::InvalidUtf8)
+}
+
+/// Error of [`percent_decode`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DecodeError {
+    /// A `%` is not followed by two hexadecimal digits.
+    InvalidEscape,
+    /// The decoded bytes are not valid UTF-8.
+    InvalidUtf8,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidEscape => f.write_str("malformed percent-encoded sequence"),
+            Self::InvalidUtf8 => f.write_str("percent-decoded text is not valid UTF-8"),
+        }
+    }
+}
+
+impl core::error::Error for DecodeError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn path_segments() {
+        assert_eq!(
+            percent_encode_path_segment("reports/2024?draft#1"),
+            "reports%2F2024%3Fdraft%231"
+        );
+        assert_eq!(percent_encode_path_segment("100% sure"), "100%25%20sure");
+        assert_eq!(percent_encode_path_segment("日本"), "%E6%97%A5%E6%9C%AC");
+        let kept = percent_encode_path_segment("user@host:a+b;v=1");
+        assert_eq!(kept, "user@host:a+b;v=1");
+        assert!(matches!(kept, Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn query_components_and_spaces() {
+        assert_eq!(percent_encode_query_component("a b"), "a%20b");
+        assert_eq!(percent_encode_query_component("a+b"), "a%2Bb");
+        assert_eq!(percent_encode_query_component("k=v&x"), "k%3Dv%26x");
+        assert!(matches!(
+            percent_encode_query_component("/path?x:y"),
+            Cow::Borrowed(_)
+        ));
+
+        // `+` only means a space to form decoders.
+        assert_eq!(percent_decode("a+b%20c").unwrap(), "a+b c");
+        assert_eq!(&*decode_lenient(b"a+b%20c", true), b"a b c");
+    }
+
+    #[test]
+    fn decoding() {
+        let decoded = percent_decode("plain-text").unwrap();
+        assert!(matches!(decoded, Cow::Borrowed(_)));
+        assert_eq!(percent_decode("%e2%9c%93 ok").unwrap(), "\u{2713} ok");
+        assert_eq!(percent_decode("%4"), Err(DecodeError::InvalidEscape));
+        assert_eq!(percent_decode("%zz"), Err(DecodeError::InvalidEscape));
+        assert_eq!(percent_decode("%C3%28"), Err(DecodeError::InvalidUtf8));
+    }
+
+    #[test]
+    fn round_trips_do_not_double_encode() {
+        for encoded in ["caf%C3%A9", "a%2Fb", "100%25", "%E6%97%A5+x"] {
+            let decoded = percent_decode(encoded).unwrap();
+            assert_eq!(percent_encode_path_segment(&decoded), encoded);
+        }
+        for text in ["a b&c=d+e", "ünïcödé/?", "%41"] {
+            let encoded = percent_encode_query_component(text);
+            assert_eq!(percent_decode(&encoded).unwrap(), text);
+        }
+    }
+}
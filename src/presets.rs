@@ -0,0 +1,248 @@
+//! Ready-made middleware compositions.
+//!
+//! [`hardened`] assembles request validation, size limits, a timeout, panic recovery
+//! and security headers into a single middleware, so new services get safe defaults
+//! without wiring each piece by hand.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use core::time::Duration;
+//! use http_kit::endpoint::WithMiddleware;
+//! use http_kit::middleware::timeout::Timeout;
+//! use http_kit::presets::{hardened, HardenedConfig};
+//! use http_kit::{Body, Endpoint, Request, Response};
+//!
+//! struct Api;
+//!
+//! impl Endpoint for Api {
+//!     type Error = core::convert::Infallible;
+//!     async fn respond(&mut self, _request: &mut Request) -> Result<Response, Self::Error> {
+//!         Ok(Response::new(Body::from_bytes("{}")))
+//!     }
+//! }
+//!
+//! let mut config = HardenedConfig::default();
+//! config.timeout = Some(Timeout::new(Duration::from_secs(5)));
+//! config.default_headers = None;
+//! let endpoint = WithMiddleware::new(Api, hardened(config));
+//! ```
+
+use core::{convert::Infallible, time::Duration};
+
+use http::{header, HeaderValue};
+
+use crate::{
+    endpoint::WithMiddleware,
+    limits::Limits,
+    middleware::{
+        catch_panic::CatchPanic,
+        headers::{DefaultHeaders, SecurityHeaders},
+        timeout::Timeout,
+        validate::ValidateRequest,
+        ErrorHandler, MiddlewareError,
+    },
+    Endpoint, HttpError, Middleware, Request, Response,
+};
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Components of the [`hardened`] preset.
+///
+/// Every component can be replaced, or removed by setting it to `None`. The defaults
+/// are safe for a typical JSON API.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct HardenedConfig {
+    /// Framing checks and [`Limits`] (1 MiB bodies, 8 KiB headers by default).
+    pub validate: Option<ValidateRequest>,
+    /// Deadline for the endpoint, 30 seconds by default.
+    pub timeout: Option<Timeout>,
+    /// Conversion of panics into `500 Internal Server Error`.
+    pub catch_panic: Option<CatchPanic>,
+    /// Security headers, [`SecurityHeaders::strict`] by default.
+    pub security_headers: Option<SecurityHeaders>,
+    /// Other default headers, `Cache-Control: no-store` by default.
+    pub default_headers: Option<DefaultHeaders>,
+}
+
+impl Default for HardenedConfig {
+    fn default() -> Self {
+        Self {
+            validate: Some(ValidateRequest::new(Limits::default())),
+            timeout: Some(Timeout::new(DEFAULT_TIMEOUT)),
+            catch_panic: Some(CatchPanic::new()),
+            security_headers: Some(SecurityHeaders::strict()),
+            default_headers: Some(
+                DefaultHeaders::new()
+                    .header(header::CACHE_CONTROL, HeaderValue::from_static("no-store")),
+            ),
+        }
+    }
+}
+
+/// Middleware created by [`hardened`].
+#[derive(Debug, Clone)]
+pub struct Hardened {
+    config: HardenedConfig,
+}
+
+/// Composes the components of `config` into one middleware.
+///
+/// From the outside in, a request passes through:
+///
+/// 1. default headers and security headers, added to every response including the
+///    error responses produced below;
+/// 2. error rendering: errors of the inner layers and of the endpoint become responses
+///    with their status code, hiding the message of `5xx` errors;
+/// 3. panic recovery;
+/// 4. the timeout, so slow request bodies count against the deadline too;
+/// 5. request validation and limits;
+/// 6. the endpoint.
+pub fn hardened(config: HardenedConfig) -> Hardened {
+    Hardened { config }
+}
+
+fn error_response(error: &impl HttpError) -> Response {
+    ErrorHandler::render_plain(error)
+}
+
+impl Middleware for Hardened {
+    type Error = Infallible;
+    async fn handle<E: Endpoint>(
+        &mut self,
+        request: &mut Request,
+        next: E,
+    ) -> Result<Response, MiddlewareError<E::Error, Self::Error>> {
+        let config = &mut self.config;
+        let mut chain = WithMiddleware::new(
+            WithMiddleware::new(
+                WithMiddleware::new(next, &mut config.validate),
+                &mut config.timeout,
+            ),
+            &mut config.catch_panic,
+        );
+        let mut response = match chain.respond(request).await {
+            Ok(response) => response,
+            Err(error) => error_response(&error),
+        };
+
+        if let Some(security_headers) = &config.security_headers {
+            security_headers.apply(&mut response);
+        }
+        if let Some(default_headers) = &config.default_headers {
+            default_headers.apply(&mut response);
+        }
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Body;
+    use alloc::vec;
+    use futures_lite::stream;
+    use futures_timer::Delay;
+    use http::StatusCode;
+
+    struct Api;
+
+    impl Endpoint for Api {
+        type Error = crate::middleware::timeout::TimeoutError;
+        async fn respond(&mut self, request: &mut Request) -> Result<Response, Self::Error> {
+            match request.uri().path() {
+                "/slow" => Delay::new(Duration::from_secs(5)).await,
+                "/panic" => panic!("endpoint exploded"),
+                _ => {}
+            }
+            let body = request
+                .body_mut()
+                .take()
+                .unwrap()
+                .into_bytes()
+                .await
+                .unwrap();
+            Ok(Response::new(Body::from_bytes(body)))
+        }
+    }
+
+    async fn send(path: &str, body: Body) -> Response {
+        let config = HardenedConfig {
+            timeout: Some(Timeout::new(Duration::from_millis(50))),
+            validate: Some(ValidateRequest::new(
+                Limits::default().with_max_body_size(16),
+            )),
+            ..HardenedConfig::default()
+        };
+
+        let mut endpoint = WithMiddleware::new(Api, hardened(config));
+        let mut request = Request::new(body);
+        *request.uri_mut() = path.parse().unwrap();
+        endpoint.respond(&mut request).await.unwrap()
+    }
+
+    fn assert_secured(response: &Response) {
+        assert_eq!(
+            response.headers()[header::X_CONTENT_TYPE_OPTIONS],
+            "nosniff"
+        );
+        assert_eq!(response.headers()[header::X_FRAME_OPTIONS], "DENY");
+        assert_eq!(response.headers()[header::CACHE_CONTROL], "no-store");
+    }
+
+    #[tokio::test]
+    async fn passes_small_requests() {
+        let response = send("/", Body::from_bytes("{}")).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_secured(&response);
+        assert_eq!(response.into_body().into_bytes().await.unwrap(), "{}");
+    }
+
+    #[tokio::test]
+    async fn oversized_body_is_rejected() {
+        let response = send("/", Body::from_bytes("x".repeat(17))).await;
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        assert_secured(&response);
+
+        let chunks = vec![Ok::<_, Infallible>("0123456789"); 3];
+        let response = send("/", Body::from_stream(stream::iter(chunks))).await;
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        assert_secured(&response);
+    }
+
+    #[tokio::test]
+    async fn slow_endpoint_times_out() {
+        let response = send("/slow", Body::empty()).await;
+        assert_eq!(response.status(), StatusCode::GATEWAY_TIMEOUT);
+        assert_secured(&response);
+    }
+
+    #[tokio::test]
+    async fn panic_becomes_server_error() {
+        let response = send("/panic", Body::empty()).await;
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_secured(&response);
+        let message = response
+            .extensions()
+            .get::<crate::middleware::catch_panic::PanicMessage>()
+            .unwrap();
+        assert_eq!(message.0, "endpoint exploded");
+    }
+
+    #[tokio::test]
+    async fn components_can_be_removed() {
+        let config = HardenedConfig {
+            security_headers: None,
+            validate: None,
+            ..HardenedConfig::default()
+        };
+
+        let mut endpoint = WithMiddleware::new(Api, hardened(config));
+        let mut request = Request::new(Body::from_bytes("x".repeat(2 << 20)));
+        let response = endpoint.respond(&mut request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(!response.headers().contains_key(header::X_FRAME_OPTIONS));
+        assert_eq!(response.headers()[header::CACHE_CONTROL], "no-store");
+    }
+}
@@ -0,0 +1,501 @@
+//! Redaction of sensitive data in captured bodies.
+//!
+//! [`Redactor`] turns a request or response body into a string that is safe to log:
+//! bodies of non-textual content types are never captured, oversized bodies are replaced
+//! by a marker, and sensitive JSON fields or form keys have their values replaced with
+//! [`REDACTED`]. Header values that carry credentials are recognized by
+//! [`is_sensitive_header`], and [`DebugRequest`] and [`DebugResponse`] print messages
+//! without them.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # #[cfg(feature = "json")]
+//! # {
+//! use http_kit::redact::Redactor;
+//!
+//! let redactor = Redactor::new().field("ssn");
+//! let captured = redactor.redact(
+//!     Some(&mime::APPLICATION_JSON),
+//!     br#"{"user":{"name":"alice","password":"hunter2","ssn":"123"}}"#,
+//! );
+//! assert_eq!(
+//!     captured,
+//!     r#"{"user":{"name":"alice","password":"[REDACTED]","ssn":"[REDACTED]"}}"#
+//! );
+//! # }
+//! ```
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt;
+use http::{header, HeaderMap, HeaderName};
+use mime::Mime;
+
+use crate::{percent, Body, BodyError, Request, Response};
+
+/// Replacement for the value of a sensitive field.
+pub const REDACTED: &str = "[REDACTED]";
+/// Replacement for a body larger than the capture limit.
+pub const TRUNCATED: &str = "[TRUNCATED]";
+/// Replacement for a body whose content type is never captured.
+pub const OMITTED: &str = "[OMITTED]";
+/// Replacement for a JSON body that could not be parsed, and therefore not redacted.
+pub const UNPARSEABLE: &str = "[UNPARSEABLE]";
+
+const DEFAULT_MAX_BYTES: usize = 4096;
+
+const DEFAULT_FIELDS: &[&str] = &[
+    "password",
+    "passwd",
+    "secret",
+    "token",
+    "access_token",
+    "refresh_token",
+    "api_key",
+    "card_number",
+    "cvv",
+];
+
+const SENSITIVE_HEADERS: &[HeaderName] = &[
+    header::AUTHORIZATION,
+    header::PROXY_AUTHORIZATION,
+    header::COOKIE,
+    header::SET_COOKIE,
+];
+
+/// Returns whether values of the header `name` carry credentials and must not be logged.
+///
+/// Covers `Authorization`, `Proxy-Authorization`, `Cookie`, `Set-Cookie` and any header
+/// whose name contains `token`, `secret` or `api-key`, such as `X-Api-Key`.
+pub fn is_sensitive_header(name: &HeaderName) -> bool {
+    SENSITIVE_HEADERS.contains(name)
+        || ["token", "secret", "api-key"]
+            .iter()
+            .any(|word| name.as_str().contains(word))
+}
+
+/// Configuration describing which parts of a body may be logged.
+///
+/// By default only JSON, URL-encoded form and `text/*` bodies are captured, at most
+/// 4 KiB are retained, and common credential fields (`password`, `token`, `card_number`,
+/// ...) are redacted. Field names are matched case-insensitively at any depth.
+#[derive(Debug, Clone)]
+pub struct Redactor {
+    max_bytes: usize,
+    fields: Vec<String>,
+    content_types: Vec<Mime>,
+}
+
+impl Default for Redactor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Redactor {
+    /// Creates a redactor with the default configuration.
+    pub fn new() -> Self {
+        Self {
+            max_bytes: DEFAULT_MAX_BYTES,
+            fields: DEFAULT_FIELDS
+                .iter()
+                .map(|field| field.to_string())
+                .collect(),
+            content_types: Vec::new(),
+        }
+    }
+
+    /// Sets the maximum number of bytes captured. Larger bodies are replaced by [`TRUNCATED`].
+    #[must_use]
+    pub fn max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// Adds a field name (JSON key or form key) whose value is redacted.
+    #[must_use]
+    pub fn field(mut self, name: impl Into<String>) -> Self {
+        let mut name = name.into();
+        name.make_ascii_lowercase();
+        self.fields.push(name);
+        self
+    }
+
+    /// Removes all field names, including the defaults.
+    #[must_use]
+    pub fn clear_fields(mut self) -> Self {
+        self.fields.clear();
+        self
+    }
+
+    /// Allows capturing bodies of an additional content type.
+    ///
+    /// The comparison ignores parameters; `text/*`-style wildcards are supported.
+    #[must_use]
+    pub fn allow_content_type(mut self, mime: Mime) -> Self {
+        self.content_types.push(mime);
+        self
+    }
+
+    fn is_sensitive(&self, name: &str) -> bool {
+        self.fields
+            .iter()
+            .any(|field| field.eq_ignore_ascii_case(name))
+    }
+
+    /// Returns whether bodies of the given content type may be captured.
+    pub fn is_capturable(&self, mime: Option<&Mime>) -> bool {
+        let Some(mime) = mime else {
+            return false;
+        };
+        is_json(mime)
+            || is_form(mime)
+            || mime.type_() == mime::TEXT
+            || self.content_types.iter().any(|allowed| {
+                allowed.type_() == mime.type_()
+                    && (allowed.subtype() == mime::STAR || allowed.subtype() == mime.subtype())
+            })
+    }
+
+    /// Renders `data` for logging, applying the content type, size and field rules.
+    pub fn redact(&self, mime: Option<&Mime>, data: &[u8]) -> String {
+        if !self.is_capturable(mime) {
+            return OMITTED.to_string();
+        }
+        if data.len() > self.max_bytes {
+            return TRUNCATED.to_string();
+        }
+        match mime {
+            Some(mime) if is_json(mime) => self.redact_json(data),
+            Some(mime) if is_form(mime) => self.redact_form(data),
+            _ => String::from_utf8_lossy(data).into_owned(),
+        }
+    }
+
+    /// Captures the body for logging without consuming it.
+    ///
+    /// At most `max_bytes + 1` bytes are read with [`Body::peek`], so the next consumer
+    /// still observes the complete body. The content type is taken from `mime` when
+    /// given, otherwise from [`Body::mime`].
+    pub async fn capture(&self, mime: Option<&Mime>, body: &mut Body) -> Result<String, BodyError> {
+        let mime = mime.or(body.mime()).cloned();
+        if !self.is_capturable(mime.as_ref()) {
+            return Ok(OMITTED.to_string());
+        }
+        if body.len().is_some_and(|len| len > self.max_bytes) {
+            return Ok(TRUNCATED.to_string());
+        }
+
+        let prefix = body.peek(self.max_bytes.saturating_add(1)).await?;
+        if prefix.len() > self.max_bytes {
+            Ok(TRUNCATED.to_string())
+        } else {
+            Ok(self.redact(mime.as_ref(), &prefix))
+        }
+    }
+
+    #[cfg(feature = "json")]
+    fn redact_json(&self, data: &[u8]) -> String {
+        fn walk(redactor: &Redactor, value: &mut serde_json::Value) {
+            match value {
+                serde_json::Value::Object(map) => {
+                    for (key, value) in map.iter_mut() {
+                        if redactor.is_sensitive(key) {
+                            *value = serde_json::Value::String(REDACTED.to_string());
+                        } else {
+                            walk(redactor, value);
+                        }
+                    }
+                }
+                serde_json::Value::Array(items) => {
+                    items.iter_mut().for_each(|item| walk(redactor, item))
+                }
+                _ => {}
+            }
+        }
+
+        match serde_json::from_slice::<serde_json::Value>(data) {
+            Ok(mut value) => {
+                walk(self, &mut value);
+                value.to_string()
+            }
+            Err(_) => UNPARSEABLE.to_string(),
+        }
+    }
+
+    #[cfg(not(feature = "json"))]
+    fn redact_json(&self, _data: &[u8]) -> String {
+        UNPARSEABLE.to_string()
+    }
+
+    fn redact_form(&self, data: &[u8]) -> String {
+        let mut output = String::with_capacity(data.len());
+        for (i, pair) in data.split(|byte| *byte == b'&').enumerate() {
+            if i > 0 {
+                output.push('&');
+            }
+            let (key, _) = match pair.iter().position(|byte| *byte == b'=') {
+                Some(index) => (&pair[..index], &pair[index + 1..]),
+                None => (pair, &pair[pair.len()..]),
+            };
+            let decoded = percent::decode(key, true).unwrap_or_else(|| key.to_vec());
+            if self.is_sensitive(&String::from_utf8_lossy(&decoded)) {
+                output.push_str(&String::from_utf8_lossy(key));
+                output.push('=');
+                output.push_str(REDACTED);
+            } else {
+                output.push_str(&String::from_utf8_lossy(pair));
+            }
+        }
+        output
+    }
+}
+
+/// A [`Debug`](fmt::Debug) summary of a request, created by
+/// [`RequestExt::debug`](crate::RequestExt::debug) or
+/// [`RequestExt::debug_full`](crate::RequestExt::debug_full).
+///
+/// Prints the method, URI, version, the number of headers, the headers and a summary of
+/// the body. Unless created by `debug_full`, the values of
+/// [sensitive headers](is_sensitive_header) are replaced with [`REDACTED`].
+///
+/// # Examples
+///
+/// ```rust
+/// use http_kit::{Body, Request, RequestExt};
+///
+/// let mut request = Request::new(Body::from_text("hi"));
+/// request.headers_mut().insert("authorization", "Bearer abc".parse().unwrap());
+/// let printed = format!("{:?}", request.debug());
+/// assert!(printed.contains(r#""authorization": "[REDACTED]""#));
+/// assert!(!printed.contains("abc"));
+/// ```
+#[derive(Clone, Copy)]
+pub struct DebugRequest<'a> {
+    request: &'a Request,
+    full: bool,
+}
+
+impl<'a> DebugRequest<'a> {
+    pub(crate) const fn new(request: &'a Request, full: bool) -> Self {
+        Self { request, full }
+    }
+}
+
+impl fmt::Debug for DebugRequest<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let request = self.request;
+        f.debug_struct("Request")
+            .field("method", request.method())
+            .field("uri", request.uri())
+            .field("version", &request.version())
+            .field("header_count", &request.headers().len())
+            .field("headers", &DebugHeaders::new(request.headers(), self.full))
+            .field("body", request.body())
+            .finish()
+    }
+}
+
+/// A [`Debug`](fmt::Debug) summary of a response, created by
+/// [`ResponseExt::debug`](crate::ResponseExt::debug) or
+/// [`ResponseExt::debug_full`](crate::ResponseExt::debug_full).
+///
+/// Prints the status, version, the number of headers, the headers and a summary of the
+/// body, redacting [sensitive headers](is_sensitive_header) like [`DebugRequest`].
+#[derive(Clone, Copy)]
+pub struct DebugResponse<'a> {
+    response: &'a Response,
+    full: bool,
+}
+
+impl<'a> DebugResponse<'a> {
+    pub(crate) const fn new(response: &'a Response, full: bool) -> Self {
+        Self { response, full }
+    }
+}
+
+impl fmt::Debug for DebugResponse<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let response = self.response;
+        f.debug_struct("Response")
+            .field("status", &response.status())
+            .field("version", &response.version())
+            .field("header_count", &response.headers().len())
+            .field("headers", &DebugHeaders::new(response.headers(), self.full))
+            .field("body", response.body())
+            .finish()
+    }
+}
+
+struct DebugHeaders<'a> {
+    headers: &'a HeaderMap,
+    full: bool,
+}
+
+impl<'a> DebugHeaders<'a> {
+    const fn new(headers: &'a HeaderMap, full: bool) -> Self {
+        Self { headers, full }
+    }
+}
+
+impl fmt::Debug for DebugHeaders<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut map = f.debug_map();
+        for (name, value) in self.headers {
+            if !self.full && is_sensitive_header(name) {
+                map.entry(&name.as_str(), &REDACTED);
+            } else {
+                map.entry(&name.as_str(), value);
+            }
+        }
+        map.finish()
+    }
+}
+
+fn is_json(mime: &Mime) -> bool {
+    mime.type_() == mime::APPLICATION
+        && (mime.subtype() == mime::JSON || mime.suffix() == Some(mime::JSON))
+}
+
+fn is_form(mime: &Mime) -> bool {
+    mime.type_() == mime::APPLICATION && mime.subtype() == mime::WWW_FORM_URLENCODED
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{RequestExt, ResponseExt};
+    use alloc::vec;
+    use bytes::Bytes;
+    use futures_lite::stream;
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn redacts_nested_fields_and_arrays() {
+        let redactor = Redactor::new().field("SSN");
+        let data = br#"{"users":[{"name":"a","password":"x"},{"name":"b","profile":{"ssn":"1","Card_Number":"4111"}}],"token":{"nested":"object"}}"#;
+        let captured = redactor.redact(Some(&mime::APPLICATION_JSON), data);
+
+        let value: serde_json::Value = serde_json::from_str(&captured).unwrap();
+        assert_eq!(value["users"][0]["name"], "a");
+        assert_eq!(value["users"][0]["password"], REDACTED);
+        assert_eq!(value["users"][1]["profile"]["ssn"], REDACTED);
+        assert_eq!(value["users"][1]["profile"]["Card_Number"], REDACTED);
+        assert_eq!(value["token"], REDACTED);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn unparseable_json_is_not_logged() {
+        let captured = Redactor::new().redact(Some(&mime::APPLICATION_JSON), b"{\"password\":");
+        assert_eq!(captured, UNPARSEABLE);
+    }
+
+    #[test]
+    fn redacts_form_keys() {
+        let captured = Redactor::new().redact(
+            Some(&mime::APPLICATION_WWW_FORM_URLENCODED),
+            b"user=alice&pass%77ord=hunter2&remember",
+        );
+        assert_eq!(captured, "user=alice&pass%77ord=[REDACTED]&remember");
+    }
+
+    #[test]
+    fn omits_binary_content_types() {
+        let redactor = Redactor::new();
+        assert_eq!(redactor.redact(Some(&mime::IMAGE_PNG), b"\x89PNG"), OMITTED);
+        assert_eq!(redactor.redact(None, b"data"), OMITTED);
+        assert_eq!(
+            redactor
+                .allow_content_type(mime::IMAGE_STAR)
+                .redact(Some(&mime::IMAGE_PNG), b"png"),
+            "png"
+        );
+    }
+
+    #[tokio::test]
+    async fn oversized_stream_is_truncated_and_restored() {
+        let chunks = vec![Bytes::from_static(b"0123456789"); 5];
+        let mut body = Body::from_stream(stream::iter(chunks.into_iter().map(Ok::<_, BodyError>)))
+            .with_mime(mime::TEXT_PLAIN);
+
+        let captured = Redactor::new()
+            .max_bytes(16)
+            .capture(None, &mut body)
+            .await
+            .unwrap();
+        assert_eq!(captured, TRUNCATED);
+        assert_eq!(body.mime(), Some(&mime::TEXT_PLAIN));
+        assert_eq!(body.into_bytes().await.unwrap().len(), 50);
+    }
+
+    #[test]
+    fn sensitive_headers() {
+        for name in [
+            "authorization",
+            "cookie",
+            "set-cookie",
+            "x-api-key",
+            "x-csrf-token",
+        ] {
+            assert!(
+                is_sensitive_header(&HeaderName::from_static(name)),
+                "{name}"
+            );
+        }
+        for name in ["accept", "content-type", "x-request-id"] {
+            assert!(
+                !is_sensitive_header(&HeaderName::from_static(name)),
+                "{name}"
+            );
+        }
+    }
+
+    #[test]
+    fn debug_summaries_redact_credentials() {
+        let mut request = Request::new(Body::from_text("hello"));
+        *request.uri_mut() = "/login".parse().unwrap();
+        let headers = request.headers_mut();
+        headers.insert(header::ACCEPT, "*/*".parse().unwrap());
+        headers.insert(header::AUTHORIZATION, "Bearer s3cr3t".parse().unwrap());
+        headers.insert(header::COOKIE, "session=abc".parse().unwrap());
+
+        let printed = alloc::format!("{:?}", request.debug());
+        assert_eq!(
+            printed,
+            "Request { method: GET, uri: /login, version: HTTP/1.1, header_count: 3, \
+             headers: {\"accept\": \"*/*\", \"authorization\": \"[REDACTED]\", \
+             \"cookie\": \"[REDACTED]\"}, \
+             body: Body::Once { len: 5, mime: \"text/plain; charset=utf-8\" } }"
+        );
+        let printed = alloc::format!("{:?}", request.debug_full());
+        assert!(printed.contains("\"Bearer s3cr3t\""));
+        assert!(printed.contains("\"session=abc\""));
+
+        let mut response = Response::new(Body::empty());
+        response
+            .headers_mut()
+            .insert(header::SET_COOKIE, "id=1".parse().unwrap());
+        let printed = alloc::format!("{:?}", response.debug());
+        assert_eq!(
+            printed,
+            "Response { status: 200, version: HTTP/1.1, header_count: 1, \
+             headers: {\"set-cookie\": \"[REDACTED]\"}, body: Body::Once { len: 0 } }"
+        );
+    }
+
+    #[tokio::test]
+    async fn known_length_over_limit_is_not_read() {
+        let mut body = Body::from_text("a".repeat(32));
+        let captured = Redactor::new()
+            .max_bytes(8)
+            .capture(None, &mut body)
+            .await
+            .unwrap();
+        assert_eq!(captured, TRUNCATED);
+        assert_eq!(body.len(), Some(32));
+    }
+}
@@ -0,0 +1,1633 @@
+//! Extension methods for [`Request`].
+
+#[cfg(feature = "std")]
+extern crate std;
+
+use alloc::{
+    borrow::Cow,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use core::{future::Future, net::IpAddr};
+
+use http::header::{HeaderName, HeaderValue};
+use http::{Method, Uri};
+use mime::Mime;
+
+use crate::{
+    extension,
+    forwarded::{self, ForwardedElement, Node, Source},
+    headers::{self, Accept, Authorization, ContentDisposition, ETagMatch},
+    middleware::request_id::RequestId,
+    multipart::MultipartBuilder,
+    params::PathParams,
+    percent,
+    redact::DebugRequest,
+    upgrade::OnUpgrade,
+    Body, BodyError, Request, Response,
+};
+
+/// Extension trait adding convenience methods to [`Request`].
+///
+/// `Request` is an alias of [`http::Request`], so these methods are provided by a trait;
+/// import it with `use http_kit::RequestExt`.
+pub trait RequestExt {
+    /// Takes the [`OnUpgrade`] handle placed by the server adapter, if any.
+    ///
+    /// Returns `None` when the transport does not support upgrades or the handle was
+    /// already taken.
+    fn on_upgrade(&mut self) -> Option<OnUpgrade>;
+
+    /// Inserts an extension, returning the previous value of the same type.
+    ///
+    /// Unlike `extensions_mut().insert`, the value does not need to implement `Clone`,
+    /// which suits one-shot values such as channel senders or transactions. Such values
+    /// are not copied when the extensions are cloned.
+    fn insert_extension<T: Send + Sync + 'static>(&mut self, value: T) -> Option<T>;
+
+    /// Returns a reference to an extension inserted with either
+    /// [`RequestExt::insert_extension`] or `extensions_mut().insert`.
+    fn extension<T: Send + Sync + 'static>(&self) -> Option<&T>;
+
+    /// Returns a mutable reference to an extension.
+    fn extension_mut<T: Send + Sync + 'static>(&mut self) -> Option<&mut T>;
+
+    /// Removes an extension and returns it, handing its ownership to the caller.
+    fn take_extension<T: Send + Sync + 'static>(&mut self) -> Option<T>;
+
+    /// Returns the extension of type `T`, inserting the value returned by `f` first if
+    /// there is none.
+    ///
+    /// Useful for lazily initialized per-message caches.
+    fn extension_or_insert_with<T, F>(&mut self, f: F) -> &mut T
+    where
+        T: Send + Sync + 'static,
+        F: FnOnce() -> T;
+
+    /// Returns the extension of type `T`, inserting `T::default()` first if there is
+    /// none, such as a list that several middleware append to.
+    fn extension_or_default<T: Default + Send + Sync + 'static>(&mut self) -> &mut T;
+
+    /// Copies the extensions of the request onto `response`, so that request-scoped
+    /// values such as the request id or timings reach response processing.
+    ///
+    /// The extensions the response already has are kept, and win over request
+    /// extensions of the same type. The request keeps its extensions, which middleware
+    /// such as the cookie jar one still reads once the endpoint returns. Values
+    /// inserted with [`RequestExt::insert_extension`] need not be `Clone`, so they are
+    /// not copied: move them with [`RequestExt::take_extension`] instead.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use http_kit::{Body, Request, RequestExt, Response, ResponseExt};
+    ///
+    /// #[derive(Debug, Clone, PartialEq)]
+    /// struct Tenant(&'static str);
+    ///
+    /// let mut request = Request::new(Body::empty());
+    /// request.extensions_mut().insert(Tenant("acme"));
+    /// let mut response = Response::new(Body::empty());
+    /// request.transfer_extensions(&mut response);
+    /// assert_eq!(response.extension::<Tenant>(), Some(&Tenant("acme")));
+    /// assert_eq!(request.extension::<Tenant>(), Some(&Tenant("acme")));
+    /// ```
+    fn transfer_extensions(&self, response: &mut Response);
+
+    /// Replaces the body with the form built by `form` and sets the matching
+    /// `Content-Type: multipart/form-data; boundary=...` header.
+    fn multipart(&mut self, form: MultipartBuilder);
+
+    /// Deserializes the query string into `T`.
+    ///
+    /// A request without a query string is treated like an empty one, so types whose
+    /// fields are all optional still deserialize. Decoding follows
+    /// [`Body::into_form`](crate::Body::into_form).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error with status `400 Bad Request` if the query string does not match
+    /// `T`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use http_kit::{Body, Request, RequestExt};
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Page {
+    ///     q: String,
+    ///     limit: Option<u32>,
+    /// }
+    ///
+    /// let mut request = Request::new(Body::empty());
+    /// *request.uri_mut() = "/search?q=rust+http&limit=20".parse().unwrap();
+    ///
+    /// let page: Page = request.query().unwrap();
+    /// assert_eq!(page.q, "rust http");
+    /// assert_eq!(page.limit, Some(20));
+    /// ```
+    #[cfg(feature = "form")]
+    fn query<'a, T: serde::Deserialize<'a>>(&'a self) -> crate::Result<T>;
+
+    /// Returns the path of the request URI, `/` if it has none.
+    fn path(&self) -> &str;
+
+    /// Returns the host the request is addressed to, without the port.
+    ///
+    /// The authority of the request URI is preferred, as in proxy requests and HTTP/2;
+    /// otherwise the `Host` header is used.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use http_kit::{Body, Request, RequestExt};
+    ///
+    /// let mut request = Request::new(Body::empty());
+    /// request.headers_mut().insert("host", "example.com:8080".parse().unwrap());
+    /// assert_eq!(request.host(), Some("example.com"));
+    ///
+    /// *request.uri_mut() = "https://api.example.com/v1".parse().unwrap();
+    /// assert_eq!(request.host(), Some("api.example.com"));
+    /// ```
+    fn host(&self) -> Option<&str>;
+
+    /// Returns the scheme of the request URI, such as `https`.
+    ///
+    /// Servers usually receive origin-form URIs, which have no scheme.
+    fn scheme(&self) -> Option<&str>;
+
+    /// Parses the `Forwarded` headers into their elements, from the client to the last
+    /// proxy, skipping malformed ones.
+    ///
+    /// See the [`forwarded`](crate::forwarded) module.
+    fn forwarded(&self) -> Vec<ForwardedElement>;
+
+    /// Parses the `X-Forwarded-For` headers into their nodes, from the client to the
+    /// last proxy, skipping malformed ones.
+    fn x_forwarded_for(&self) -> Vec<Node>;
+
+    /// Returns the scheme the client used according to `X-Forwarded-Proto`, the
+    /// rightmost one if several were listed: those left of it may come from the
+    /// client.
+    fn x_forwarded_proto(&self) -> Option<&str>;
+
+    /// Returns the address of the client, found by walking the chain of the `source`
+    /// header from the right and skipping `trusted_proxies`.
+    ///
+    /// `source` must be the header the trusted proxies write. The other one is
+    /// ignored, since it reaches the server as the client sent it.
+    ///
+    /// The first address that is not a trusted proxy is the client: everything left of
+    /// it was sent by that client and cannot be trusted. Returns `None` if there is no
+    /// chain, or if the walk reaches a node that is unknown, obfuscated or malformed
+    /// before the client. IPv4-mapped IPv6 addresses are compared and returned as IPv4.
+    ///
+    /// The headers are only meaningful if the connection itself comes from a trusted
+    /// proxy, which the server must check against the peer address of the socket.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use core::net::IpAddr;
+    /// use http_kit::forwarded::Source;
+    /// use http_kit::{headers, Body, Request, RequestExt};
+    ///
+    /// let load_balancer: IpAddr = "10.0.0.1".parse().unwrap();
+    /// let mut request = Request::new(Body::empty());
+    /// // The client sent a forged entry, then the load balancer appended its address.
+    /// let chain = "6.6.6.6, 203.0.113.9".parse().unwrap();
+    /// request.headers_mut().insert(headers::X_FORWARDED_FOR, chain);
+    ///
+    /// let client = request.client_addr(Source::XForwardedFor, &[load_balancer]);
+    /// assert_eq!(client, Some("203.0.113.9".parse().unwrap()));
+    /// ```
+    fn client_addr(&self, source: Source, trusted_proxies: &[IpAddr]) -> Option<IpAddr>;
+
+    /// Returns the path parameter `name` from the [`PathParams`] extension, if any.
+    ///
+    /// Use [`PathParams::get_parsed`] to parse the value.
+    fn param(&self, name: &str) -> Option<&str>;
+
+    /// Returns the [`RequestId`] assigned by
+    /// [`RequestIdMiddleware`](crate::middleware::request_id::RequestIdMiddleware), if any.
+    fn request_id(&self) -> Option<&RequestId>;
+
+    /// Returns the percent-decoded `(name, value)` pairs of the query string, in order.
+    ///
+    /// Decoding matches [`RequestExt::query`]: `+` is a space, malformed escapes are kept
+    /// as they are and invalid UTF-8 is replaced. Empty pairs are skipped, and a pair
+    /// without `=` has an empty value.
+    fn query_pairs(&self) -> impl Iterator<Item = (Cow<'_, str>, Cow<'_, str>)>;
+
+    /// Appends the pair `key=value` to the query string of the URI and returns the
+    /// request.
+    ///
+    /// Both are encoded like HTML forms do, so `&`, `=`, spaces and non-ASCII characters
+    /// are safe, and [`RequestExt::query_pairs`] gives them back. Existing pairs are kept.
+    /// URIs without a path, such as the `host:port` target of `CONNECT`, are left as is.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use http_kit::{Body, Request, RequestExt};
+    ///
+    /// let mut request = Request::new(Body::empty());
+    /// *request.uri_mut() = "https://example.com/search?page=2".parse().unwrap();
+    ///
+    /// let request = request.query_param("q", "fish & chips");
+    /// assert_eq!(
+    ///     request.uri(),
+    ///     "https://example.com/search?page=2&q=fish+%26+chips"
+    /// );
+    /// ```
+    fn query_param(self, key: &str, value: &str) -> Self
+    where
+        Self: Sized;
+
+    /// Replaces the query string of the URI with `params` serialized as a URL-encoded
+    /// form, and returns the request.
+    ///
+    /// The query string is removed if `params` serializes to nothing. URIs without a
+    /// path are left as is, like with [`RequestExt::query_param`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `params` cannot be serialized as a form, for instance if it is
+    /// not a struct, a map or a sequence of pairs.
+    #[cfg(feature = "form")]
+    fn set_query<T: serde::Serialize>(
+        self,
+        params: &T,
+    ) -> Result<Self, serde_urlencoded::ser::Error>
+    where
+        Self: Sized;
+
+    /// Returns the value of the `Content-Length` header, or `None` if it is missing or
+    /// malformed.
+    fn content_length(&self) -> Option<u64>;
+
+    /// Appends a header and returns the request.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the name or the value is invalid, for instance when the value
+    /// contains a line break.
+    fn try_header<K, V>(self, name: K, value: V) -> Result<Self, http::Error>
+    where
+        Self: Sized,
+        HeaderName: TryFrom<K>,
+        <HeaderName as TryFrom<K>>::Error: Into<http::Error>,
+        HeaderValue: TryFrom<V>,
+        <HeaderValue as TryFrom<V>>::Error: Into<http::Error>;
+
+    /// Parses `mime`, sets it as the `Content-Type` header and the MIME type of the body,
+    /// and returns the request.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `mime` is not a valid media type. Every valid media type is a
+    /// valid header value, including parameters with non-ASCII bytes.
+    fn try_mime(self, mime: &str) -> Result<Self, mime::FromStrError>
+    where
+        Self: Sized;
+
+    /// Replaces the body, setting `Content-Type` from the MIME type of `body`.
+    ///
+    /// Unlike `*request.body_mut() = body`, the message then describes its body. A
+    /// `Content-Type` header that is already set wins over the MIME type of the body
+    /// and is kept as is. See [`RequestExt::sync_content_type`].
+    ///
+    /// Like [`Body::unfreeze`], this installs `body` even if the current body is frozen
+    /// because it was consumed; [`RequestExt::try_replace_body`] refuses to.
+    fn set_body(&mut self, body: Body);
+
+    /// Replaces the body like [`RequestExt::set_body`] and returns the previous one,
+    /// unless the body is frozen, see [`Body::try_replace`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BodyError::BodyFrozen`], leaving the request unchanged, if the body was
+    /// consumed.
+    fn try_replace_body(&mut self, body: Body) -> Result<Body, BodyError>;
+
+    /// Sets the `Content-Type` header from the MIME type of the body, unless the header
+    /// is already set or the body has no MIME type.
+    fn sync_content_type(&mut self);
+
+    /// Takes the body, leaving a frozen body in its place, see [`Body::take`].
+    ///
+    /// The MIME type of the returned body is that of the `Content-Type` header when it
+    /// holds a valid media type, so that code handed the bare body still knows its
+    /// format. As with [`RequestExt::set_body`], the header wins over the MIME type
+    /// the body had.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BodyError::BodyFrozen`] if the body was already taken.
+    fn take_body(&mut self) -> Result<Body, BodyError>;
+
+    /// Takes the body and reads and discards the rest of it, so that the connection can
+    /// serve the next request, see [`Body::drain`].
+    ///
+    /// Call it when answering without reading the body, for instance after rejecting
+    /// the credentials. A body that was already taken or read is left alone and counts
+    /// as drained. Use [`RequestExt::drain_body_up_to`] to bound the work an endless
+    /// body can cause.
+    ///
+    /// # Errors
+    ///
+    /// Returns the read errors of the body.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use http_kit::{Body, Request, RequestExt};
+    ///
+    /// # async fn example() -> Result<(), http_kit::BodyError> {
+    /// let mut request = Request::new(Body::from_bytes("unwanted"));
+    /// assert_eq!(request.drain_body().await?, 8);
+    /// // Draining twice is harmless.
+    /// assert_eq!(request.drain_body().await?, 0);
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn drain_body(&mut self) -> impl Future<Output = Result<u64, BodyError>> + Send;
+
+    /// Takes the body and reads and discards the rest of it, like
+    /// [`RequestExt::drain_body`], but reads at most `max_bytes`, see
+    /// [`Body::drain_up_to`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BodyError::LimitExceeded`] if the body is longer than `max_bytes`, in
+    /// which case the connection should be closed rather than reused, and the read
+    /// errors of the body.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use http_kit::{Body, BodyError, Request, RequestExt};
+    ///
+    /// # async fn example() -> Result<(), BodyError> {
+    /// let mut request = Request::new(Body::from_bytes("unwanted"));
+    /// let error = request.drain_body_up_to(4).await.unwrap_err();
+    /// assert!(matches!(error, BodyError::LimitExceeded(4)));
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn drain_body_up_to(
+        &mut self,
+        max_bytes: usize,
+    ) -> impl Future<Output = Result<u64, BodyError>> + Send;
+
+    /// Converts a request with any body, such as `hyper::body::Incoming`, into a
+    /// [`Request`](crate::Request), see [`convert::map_request`](crate::convert::map_request).
+    fn from_http<B>(request: http::Request<B>) -> Self
+    where
+        Self: Sized,
+        B: http_body::Body + Send + Sync + 'static,
+        B::Data: Into<bytes::Bytes>,
+        B::Error: Into<BodyError>;
+
+    /// Creates a `GET` request to `uri`, with an empty body.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use http_kit::{Method, Request, RequestExt, Uri};
+    ///
+    /// let request = Request::get(Uri::from_static("/items"));
+    /// assert_eq!(request.method(), Method::GET);
+    /// ```
+    fn get(uri: Uri) -> Self
+    where
+        Self: Sized;
+
+    /// Creates a `POST` request to `uri`, with an empty body.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use http_kit::{Method, Request, RequestExt, Uri};
+    ///
+    /// let request = Request::post(Uri::from_static("/items"));
+    /// assert_eq!(request.method(), Method::POST);
+    /// ```
+    fn post(uri: Uri) -> Self
+    where
+        Self: Sized;
+
+    /// Creates a `PUT` request to `uri`, with an empty body.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use http_kit::{Method, Request, RequestExt, Uri};
+    ///
+    /// let request = Request::put(Uri::from_static("/items/7"));
+    /// assert_eq!(request.method(), Method::PUT);
+    /// ```
+    fn put(uri: Uri) -> Self
+    where
+        Self: Sized;
+
+    /// Creates a `DELETE` request to `uri`, with an empty body.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use http_kit::{Method, Request, RequestExt, Uri};
+    ///
+    /// let request = Request::delete(Uri::from_static("/items/7"));
+    /// assert_eq!(request.method(), Method::DELETE);
+    /// ```
+    fn delete(uri: Uri) -> Self
+    where
+        Self: Sized;
+
+    /// Creates a `HEAD` request to `uri`, with an empty body.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use http_kit::{Method, Request, RequestExt, Uri};
+    ///
+    /// let request = Request::head(Uri::from_static("/items"));
+    /// assert_eq!(request.method(), Method::HEAD);
+    /// ```
+    fn head(uri: Uri) -> Self
+    where
+        Self: Sized;
+
+    /// Creates a `PATCH` request to `uri`, with an empty body.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use http_kit::{Method, Request, RequestExt, Uri};
+    ///
+    /// let request = Request::patch(Uri::from_static("/items/7"));
+    /// assert_eq!(request.method(), Method::PATCH);
+    /// ```
+    fn patch(uri: Uri) -> Self
+    where
+        Self: Sized;
+
+    /// Creates a `OPTIONS` request to `uri`, with an empty body.
+    ///
+    /// Use `*` to ask about the server as a whole.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use http_kit::{Method, Request, RequestExt, Uri};
+    ///
+    /// let request = Request::options(Uri::from_static("*"));
+    /// assert_eq!(request.method(), Method::OPTIONS);
+    /// ```
+    fn options(uri: Uri) -> Self
+    where
+        Self: Sized;
+
+    /// Creates a `TRACE` request to `uri`, with an empty body.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use http_kit::{Method, Request, RequestExt, Uri};
+    ///
+    /// let request = Request::trace(Uri::from_static("/"));
+    /// assert_eq!(request.method(), Method::TRACE);
+    /// ```
+    fn trace(uri: Uri) -> Self
+    where
+        Self: Sized;
+
+    /// Creates a `CONNECT` request to `uri`, with an empty body.
+    ///
+    /// The URI of a `CONNECT` request is the authority of the tunnel target.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use http_kit::{Method, Request, RequestExt, Uri};
+    ///
+    /// let request = Request::connect(Uri::from_static("example.com:443"));
+    /// assert_eq!(request.method(), Method::CONNECT);
+    /// ```
+    fn connect(uri: Uri) -> Self
+    where
+        Self: Sized;
+
+    /// Returns whether the request method is `method`.
+    fn method_is(&self, method: &Method) -> bool;
+
+    /// Returns whether the request method is safe, that is read-only, as defined by
+    /// [RFC 9110](https://www.rfc-editor.org/rfc/rfc9110#section-9.2.1): `GET`, `HEAD`,
+    /// `OPTIONS` and `TRACE`.
+    ///
+    /// Safe requests can be prefetched, cached or retried without changing the state
+    /// of the server.
+    fn is_safe(&self) -> bool;
+
+    /// Returns whether the request method is idempotent, as defined by
+    /// [RFC 9110](https://www.rfc-editor.org/rfc/rfc9110#section-9.2.2): the safe
+    /// methods, `PUT` and `DELETE`.
+    ///
+    /// Sending an idempotent request twice has the same effect as sending it once, so
+    /// it can be retried after a connection failure.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use http_kit::{Request, RequestExt, Uri};
+    ///
+    /// let put = Request::put(Uri::from_static("/items/7"));
+    /// assert!(put.is_idempotent() && !put.is_safe());
+    /// let post = Request::post(Uri::from_static("/items"));
+    /// assert!(!post.is_idempotent());
+    /// ```
+    fn is_idempotent(&self) -> bool;
+
+    /// Returns whether the `Accept` header allows `mime`, honoring wildcards and
+    /// q-values. Requests without an `Accept` header accept everything.
+    ///
+    /// See [`Accept`] to compare several media types.
+    fn accepts(&self, mime: &Mime) -> bool;
+
+    /// Returns the best of the `supported` media types according to the `Accept`
+    /// header, see [`Accept::preferred`].
+    fn preferred_mime(&self, supported: &[Mime]) -> Option<Mime>;
+
+    /// Parses the `Authorization` header, if present and well-formed.
+    fn authorization(&self) -> Option<Authorization>;
+
+    /// Parses the `Content-Disposition` header, if present and well-formed.
+    fn content_disposition(&self) -> Option<ContentDisposition>;
+
+    /// Buffers the body and returns an independent copy of the request.
+    ///
+    /// The method, URI, version, headers, cloneable extensions and body are copied; the
+    /// body of `self` stays readable. Extensions inserted with
+    /// [`RequestExt::insert_extension`] are not cloneable and stay with `self` only.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the body cannot be buffered, see [`Body::as_bytes`](crate::Body::as_bytes).
+    fn clone_with_buffered_body(
+        &mut self,
+    ) -> impl Future<Output = Result<Request, BodyError>> + Send;
+
+    /// Deserializes the JSON body after checking its `Content-Type`, see
+    /// [`Body::into_json`](crate::Body::into_json).
+    ///
+    /// Any JSON media type is accepted: `application/json`, other types with a `json`
+    /// subtype and structured syntax suffixes such as `application/problem+json` or
+    /// `application/vnd.api+json`. Parameters are ignored, except a `charset` other than
+    /// UTF-8, which JSON over HTTP cannot use.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error with status `415 Unsupported Media Type` if the `Content-Type`
+    /// is missing, not JSON or in another charset, and the status of the [`BodyError`]
+    /// if the body cannot be read or decoded.
+    #[cfg(feature = "json")]
+    #[allow(clippy::wrong_self_convention)]
+    fn into_json<T>(&mut self) -> impl Future<Output = crate::Result<T>> + Send
+    where
+        T: serde::de::DeserializeOwned;
+
+    /// Deserializes the URL-encoded form in the body after checking its
+    /// `Content-Type`, see [`Body::into_form`](crate::Body::into_form).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error with status `415 Unsupported Media Type` if the `Content-Type`
+    /// is not `application/x-www-form-urlencoded`, with any parameters, and the status
+    /// of the [`BodyError`] if the body cannot be read or decoded.
+    #[cfg(feature = "form")]
+    #[allow(clippy::wrong_self_convention)]
+    fn into_form<T>(&mut self) -> impl Future<Output = crate::Result<T>> + Send
+    where
+        T: serde::de::DeserializeOwned;
+
+    /// Deserializes the URL-encoded form in the body, collecting repeated keys into
+    /// sequences, see [`Body::into_form_extended`](crate::Body::into_form_extended).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error with status `415 Unsupported Media Type` if the `Content-Type`
+    /// is not `application/x-www-form-urlencoded`, and the status of the
+    /// [`BodyError`] if the body cannot be read or decoded.
+    #[cfg(feature = "form")]
+    #[allow(clippy::wrong_self_convention)]
+    fn into_form_extended<T>(&mut self) -> impl Future<Output = crate::Result<T>> + Send
+    where
+        T: serde::de::DeserializeOwned;
+
+    /// Returns whether the request asks to upgrade the connection to a WebSocket.
+    ///
+    /// The request must be a `GET` with `Connection: Upgrade`, `Upgrade: websocket`
+    /// (in any case), `Sec-WebSocket-Version: 13` and a valid `Sec-WebSocket-Key`.
+    #[cfg(feature = "ws")]
+    fn is_websocket_upgrade(&self) -> bool;
+
+    /// Creates a WebSocket upgrade request to `uri`, with a fresh `Sec-WebSocket-Key`.
+    ///
+    /// Keep the key to check the answer with
+    /// [`ResponseExt::verify_websocket_accept`](crate::ResponseExt::verify_websocket_accept).
+    ///
+    /// Requires the `std` feature, the source of unpredictable keys.
+    #[cfg(all(feature = "ws", feature = "std"))]
+    fn websocket(uri: http::Uri) -> Self
+    where
+        Self: Sized;
+
+    /// Parses the `If-None-Match` headers, if present and well-formed.
+    fn if_none_match(&self) -> Option<ETagMatch>;
+
+    /// Parses the `If-Match` headers, if present and well-formed.
+    fn if_match(&self) -> Option<ETagMatch>;
+
+    /// Parses the `If-Modified-Since` header, if present and a valid HTTP date.
+    #[cfg(feature = "std")]
+    fn if_modified_since(&self) -> Option<std::time::SystemTime>;
+
+    /// Prepares the request to subscribe to Server-Sent Events.
+    ///
+    /// Sets `Accept: text/event-stream` and `Cache-Control: no-cache`, and, when
+    /// resuming a stream, `Last-Event-ID` to the id reported by
+    /// [`SseStream::last_event_id`](crate::sse::SseStream::last_event_id). An id that is
+    /// not a valid header value is left out.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use http_kit::{headers, Body, Request, RequestExt};
+    ///
+    /// let mut request = Request::new(Body::empty());
+    /// request.sse(Some("42"));
+    /// assert_eq!(request.headers()["accept"], "text/event-stream");
+    /// assert_eq!(request.headers()[headers::LAST_EVENT_ID], "42");
+    /// ```
+    fn sse(&mut self, last_event_id: Option<&str>);
+
+    /// Returns the cookie jar placed by
+    /// [`CookieJarMiddleware`](crate::middleware::cookie_jar::CookieJarMiddleware).
+    #[cfg(feature = "cookie")]
+    fn cookie_jar(&self) -> Option<&cookie::CookieJar>;
+
+    /// Returns the cookie jar placed by
+    /// [`CookieJarMiddleware`](crate::middleware::cookie_jar::CookieJarMiddleware), for
+    /// adding or removing cookies. Changes are sent back with the response.
+    #[cfg(feature = "cookie")]
+    fn cookie_jar_mut(&mut self) -> Option<&mut cookie::CookieJar>;
+
+    /// Returns a [`Debug`](core::fmt::Debug) summary of the request that is safe to log.
+    ///
+    /// The derived `Debug` of [`http::Request`] prints every header value, credentials
+    /// included. The summary replaces the values of
+    /// [sensitive headers](crate::redact::is_sensitive_header), see
+    /// [`DebugRequest`].
+    fn debug(&self) -> DebugRequest<'_>;
+
+    /// Returns a [`Debug`](core::fmt::Debug) summary of the request that prints every
+    /// header value, for local debugging.
+    fn debug_full(&self) -> DebugRequest<'_>;
+}
+
+impl RequestExt for Request {
+    fn on_upgrade(&mut self) -> Option<OnUpgrade> {
+        self.take_extension::<OnUpgrade>()
+    }
+
+    fn insert_extension<T: Send + Sync + 'static>(&mut self, value: T) -> Option<T> {
+        extension::insert(self.extensions_mut(), value)
+    }
+
+    fn extension<T: Send + Sync + 'static>(&self) -> Option<&T> {
+        extension::get(self.extensions())
+    }
+
+    fn extension_mut<T: Send + Sync + 'static>(&mut self) -> Option<&mut T> {
+        extension::get_mut(self.extensions_mut())
+    }
+
+    fn take_extension<T: Send + Sync + 'static>(&mut self) -> Option<T> {
+        extension::take(self.extensions_mut())
+    }
+
+    fn extension_or_insert_with<T, F>(&mut self, f: F) -> &mut T
+    where
+        T: Send + Sync + 'static,
+        F: FnOnce() -> T,
+    {
+        extension::get_or_insert_with(self.extensions_mut(), f)
+    }
+
+    fn extension_or_default<T: Default + Send + Sync + 'static>(&mut self) -> &mut T {
+        extension::get_or_insert_with(self.extensions_mut(), T::default)
+    }
+
+    fn transfer_extensions(&self, response: &mut Response) {
+        extension::transfer(self.extensions(), response.extensions_mut());
+    }
+
+    fn multipart(&mut self, form: MultipartBuilder) {
+        let body = form.build();
+        if let Some(value) = body
+            .mime()
+            .and_then(|mime| http::HeaderValue::from_str(mime.as_ref()).ok())
+        {
+            self.headers_mut().insert(http::header::CONTENT_TYPE, value);
+        }
+        *self.body_mut() = body;
+    }
+
+    #[cfg(feature = "form")]
+    fn query<'a, T: serde::Deserialize<'a>>(&'a self) -> crate::Result<T> {
+        use crate::ResultExt;
+
+        serde_urlencoded::from_str(self.uri().query().unwrap_or_default())
+            .status(http::StatusCode::BAD_REQUEST)
+    }
+
+    fn path(&self) -> &str {
+        self.uri().path()
+    }
+
+    fn host(&self) -> Option<&str> {
+        if let Some(host) = self.uri().host() {
+            return Some(host);
+        }
+        let host = self.headers().get(http::header::HOST)?.to_str().ok()?;
+        // Strip the port, keeping the brackets of an IPv6 literal as `Uri::host` does.
+        let host = match host.find(']') {
+            Some(end) if host.starts_with('[') => &host[..=end],
+            _ => host.split(':').next().unwrap_or(host),
+        };
+        (!host.is_empty()).then_some(host)
+    }
+
+    fn scheme(&self) -> Option<&str> {
+        self.uri().scheme_str()
+    }
+
+    fn forwarded(&self) -> Vec<ForwardedElement> {
+        forwarded::forwarded(self.headers())
+    }
+
+    fn x_forwarded_for(&self) -> Vec<Node> {
+        forwarded::x_forwarded_for(self.headers())
+    }
+
+    fn x_forwarded_proto(&self) -> Option<&str> {
+        forwarded::x_forwarded_proto(self.headers())
+    }
+
+    fn client_addr(&self, source: Source, trusted_proxies: &[IpAddr]) -> Option<IpAddr> {
+        forwarded::client_addr(self.headers(), source, trusted_proxies)
+    }
+
+    fn param(&self, name: &str) -> Option<&str> {
+        self.extensions().get::<PathParams>()?.get(name)
+    }
+
+    fn request_id(&self) -> Option<&RequestId> {
+        self.extensions().get()
+    }
+
+    fn query_pairs(&self) -> impl Iterator<Item = (Cow<'_, str>, Cow<'_, str>)> {
+        self.uri()
+            .query()
+            .unwrap_or_default()
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+                (decode_component(name), decode_component(value))
+            })
+    }
+
+    fn query_param(mut self, key: &str, value: &str) -> Self {
+        let mut query = String::from(self.uri().query().unwrap_or_default());
+        if !query.is_empty() {
+            query.push('&');
+        }
+        percent::encode_form_component(key, &mut query);
+        query.push('=');
+        percent::encode_form_component(value, &mut query);
+        set_query_string(self.uri_mut(), &query);
+        self
+    }
+
+    #[cfg(feature = "form")]
+    fn set_query<T: serde::Serialize>(
+        mut self,
+        params: &T,
+    ) -> Result<Self, serde_urlencoded::ser::Error> {
+        let query = serde_urlencoded::to_string(params)?;
+        set_query_string(self.uri_mut(), &query);
+        Ok(self)
+    }
+
+    fn content_length(&self) -> Option<u64> {
+        headers::content_length(self.headers())
+    }
+
+    fn try_header<K, V>(mut self, name: K, value: V) -> Result<Self, http::Error>
+    where
+        HeaderName: TryFrom<K>,
+        <HeaderName as TryFrom<K>>::Error: Into<http::Error>,
+        HeaderValue: TryFrom<V>,
+        <HeaderValue as TryFrom<V>>::Error: Into<http::Error>,
+    {
+        crate::headers::try_append(self.headers_mut(), name, value)?;
+        Ok(self)
+    }
+
+    fn try_mime(mut self, mime: &str) -> Result<Self, mime::FromStrError> {
+        let mime: Mime = mime.parse()?;
+        // The parser only accepts bytes that header values allow.
+        if let Ok(value) = HeaderValue::from_str(mime.as_ref()) {
+            self.headers_mut().insert(http::header::CONTENT_TYPE, value);
+        }
+        let body = core::mem::take(self.body_mut());
+        *self.body_mut() = body.with_mime(mime);
+        Ok(self)
+    }
+
+    fn set_body(&mut self, body: Body) {
+        self.body_mut().unfreeze(body);
+        self.sync_content_type();
+    }
+
+    fn try_replace_body(&mut self, body: Body) -> Result<Body, BodyError> {
+        let previous = self.body_mut().try_replace(body)?;
+        self.sync_content_type();
+        Ok(previous)
+    }
+
+    fn sync_content_type(&mut self) {
+        let mime = self.body().mime().cloned();
+        crate::headers::set_default_content_type(self.headers_mut(), mime.as_ref());
+    }
+
+    fn take_body(&mut self) -> Result<Body, BodyError> {
+        let body = self.body_mut().take()?;
+        Ok(match crate::headers::content_type(self.headers()) {
+            Some(mime) => body.with_mime(mime),
+            None => body,
+        })
+    }
+
+    async fn drain_body(&mut self) -> Result<u64, BodyError> {
+        match self.take_body() {
+            Ok(body) => body.drain().await,
+            Err(_) => Ok(0),
+        }
+    }
+
+    async fn drain_body_up_to(&mut self, max_bytes: usize) -> Result<u64, BodyError> {
+        match self.take_body() {
+            Ok(body) => body.drain_up_to(max_bytes).await,
+            Err(_) => Ok(0),
+        }
+    }
+
+    fn from_http<B>(request: http::Request<B>) -> Self
+    where
+        B: http_body::Body + Send + Sync + 'static,
+        B::Data: Into<bytes::Bytes>,
+        B::Error: Into<BodyError>,
+    {
+        crate::convert::map_request(request)
+    }
+
+    fn get(uri: Uri) -> Self {
+        with_method(Method::GET, uri)
+    }
+
+    fn post(uri: Uri) -> Self {
+        with_method(Method::POST, uri)
+    }
+
+    fn put(uri: Uri) -> Self {
+        with_method(Method::PUT, uri)
+    }
+
+    fn delete(uri: Uri) -> Self {
+        with_method(Method::DELETE, uri)
+    }
+
+    fn head(uri: Uri) -> Self {
+        with_method(Method::HEAD, uri)
+    }
+
+    fn patch(uri: Uri) -> Self {
+        with_method(Method::PATCH, uri)
+    }
+
+    fn options(uri: Uri) -> Self {
+        with_method(Method::OPTIONS, uri)
+    }
+
+    fn trace(uri: Uri) -> Self {
+        with_method(Method::TRACE, uri)
+    }
+
+    fn connect(uri: Uri) -> Self {
+        with_method(Method::CONNECT, uri)
+    }
+
+    fn method_is(&self, method: &Method) -> bool {
+        self.method() == method
+    }
+
+    fn is_safe(&self) -> bool {
+        self.method().is_safe()
+    }
+
+    fn is_idempotent(&self) -> bool {
+        self.method().is_idempotent()
+    }
+
+    fn accepts(&self, mime: &Mime) -> bool {
+        Accept::from_headers(self.headers()).accepts(mime)
+    }
+
+    fn preferred_mime(&self, supported: &[Mime]) -> Option<Mime> {
+        Accept::from_headers(self.headers())
+            .preferred(supported)
+            .cloned()
+    }
+
+    fn authorization(&self) -> Option<Authorization> {
+        Authorization::parse(self.headers().get(http::header::AUTHORIZATION)?)
+    }
+
+    fn content_disposition(&self) -> Option<ContentDisposition> {
+        let value = self.headers().get(http::header::CONTENT_DISPOSITION)?;
+        ContentDisposition::parse(value.to_str().ok()?)
+    }
+
+    async fn clone_with_buffered_body(&mut self) -> Result<Request, BodyError> {
+        self.body_mut().as_bytes().await?;
+        let body = self
+            .body()
+            .try_clone()
+            .expect("buffered bodies are cheap to clone");
+        let mut request = Request::new(body);
+        *request.method_mut() = self.method().clone();
+        *request.uri_mut() = self.uri().clone();
+        *request.version_mut() = self.version();
+        *request.headers_mut() = self.headers().clone();
+        *request.extensions_mut() = self.extensions().clone();
+        Ok(request)
+    }
+
+    #[cfg(feature = "json")]
+    async fn into_json<T>(&mut self) -> crate::Result<T>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        headers::expect_json(self.headers())?;
+        self.body_mut()
+            .into_json()
+            .await
+            .map_err(BodyError::into_http_error)
+    }
+
+    #[cfg(feature = "form")]
+    async fn into_form<T>(&mut self) -> crate::Result<T>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        headers::expect_form(self.headers())?;
+        self.body_mut()
+            .into_form()
+            .await
+            .map_err(BodyError::into_http_error)
+    }
+
+    #[cfg(feature = "form")]
+    async fn into_form_extended<T>(&mut self) -> crate::Result<T>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        headers::expect_form(self.headers())?;
+        self.body_mut()
+            .into_form_extended()
+            .await
+            .map_err(BodyError::into_http_error)
+    }
+
+    #[cfg(feature = "ws")]
+    fn is_websocket_upgrade(&self) -> bool {
+        crate::ws::check_request(self).is_ok()
+    }
+
+    #[cfg(all(feature = "ws", feature = "std"))]
+    fn websocket(uri: http::Uri) -> Self {
+        crate::ws::request(uri)
+    }
+
+    fn if_none_match(&self) -> Option<ETagMatch> {
+        ETagMatch::from_headers(self.headers(), &http::header::IF_NONE_MATCH)
+    }
+
+    fn if_match(&self) -> Option<ETagMatch> {
+        ETagMatch::from_headers(self.headers(), &http::header::IF_MATCH)
+    }
+
+    #[cfg(feature = "std")]
+    fn if_modified_since(&self) -> Option<std::time::SystemTime> {
+        let value = self.headers().get(http::header::IF_MODIFIED_SINCE)?;
+        crate::date::parse_http_date(value.to_str().ok()?)
+    }
+
+    fn sse(&mut self, last_event_id: Option<&str>) {
+        use http::{header, HeaderValue};
+
+        let fields = self.headers_mut();
+        fields.insert(
+            header::ACCEPT,
+            HeaderValue::from_static("text/event-stream"),
+        );
+        fields.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+        match last_event_id.map(HeaderValue::from_str) {
+            Some(Ok(id)) => {
+                fields.insert(headers::LAST_EVENT_ID, id);
+            }
+            _ => {
+                fields.remove(headers::LAST_EVENT_ID);
+            }
+        }
+    }
+
+    #[cfg(feature = "cookie")]
+    fn cookie_jar(&self) -> Option<&cookie::CookieJar> {
+        self.extension()
+    }
+
+    #[cfg(feature = "cookie")]
+    fn cookie_jar_mut(&mut self) -> Option<&mut cookie::CookieJar> {
+        self.extension_mut()
+    }
+
+    fn debug(&self) -> DebugRequest<'_> {
+        DebugRequest::new(self, false)
+    }
+
+    fn debug_full(&self) -> DebugRequest<'_> {
+        DebugRequest::new(self, true)
+    }
+}
+
+// Replaces the query string of `uri`, removing it when `query` is empty. The query must
+// already be encoded.
+fn set_query_string(uri: &mut http::Uri, query: &str) {
+    if uri.path_and_query().is_none() {
+        return;
+    }
+    let path = uri.path();
+    let path_and_query = if query.is_empty() {
+        path.to_string()
+    } else {
+        alloc::format!("{path}?{query}")
+    };
+    let mut parts = uri.clone().into_parts();
+    parts.path_and_query = path_and_query.parse().ok();
+    if let Ok(rebuilt) = http::Uri::from_parts(parts) {
+        *uri = rebuilt;
+    }
+}
+
+fn decode_component(component: &str) -> Cow<'_, str> {
+    match percent::decode_lenient(component.as_bytes(), true) {
+        Cow::Borrowed(_) => Cow::Borrowed(component),
+        Cow::Owned(bytes) => match String::from_utf8(bytes) {
+            Ok(decoded) => Cow::Owned(decoded),
+            Err(error) => Cow::Owned(String::from_utf8_lossy(error.as_bytes()).to_string()),
+        },
+    }
+}
+
+fn with_method(method: Method, uri: Uri) -> Request {
+    let mut request = Request::new(Body::empty());
+    *request.method_mut() = method;
+    *request.uri_mut() = uri;
+    request
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    fn request(uri: &str) -> Request {
+        let mut request = Request::new(Body::empty());
+        *request.uri_mut() = uri.parse().unwrap();
+        request
+    }
+
+    #[tokio::test]
+    async fn drain_body_skips_taken_bodies() {
+        let chunks = ["un", "read"].map(|chunk| Ok::<_, BodyError>(bytes::Bytes::from(chunk)));
+        let mut request = Request::new(Body::from_stream(futures_lite::stream::iter(chunks)));
+        assert_eq!(request.drain_body().await.unwrap(), 6);
+        assert!(request.body().is_frozen());
+        assert_eq!(request.drain_body().await.unwrap(), 0);
+
+        let mut request = Request::new(Body::from_bytes("read"));
+        request.take_body().unwrap();
+        assert_eq!(request.drain_body_up_to(0).await.unwrap(), 0);
+
+        let mut request = Request::new(Body::from_bytes("too long"));
+        let error = request.drain_body_up_to(4).await.unwrap_err();
+        assert!(matches!(error, BodyError::LimitExceeded(4)));
+        assert!(request.body().is_frozen());
+    }
+
+    #[test]
+    fn method_properties_follow_rfc_9110() {
+        let uri = || Uri::from_static("/");
+        let table = [
+            (Request::get(uri()), Method::GET, true, true),
+            (Request::head(uri()), Method::HEAD, true, true),
+            (Request::options(uri()), Method::OPTIONS, true, true),
+            (Request::trace(uri()), Method::TRACE, true, true),
+            (Request::put(uri()), Method::PUT, false, true),
+            (Request::delete(uri()), Method::DELETE, false, true),
+            (Request::post(uri()), Method::POST, false, false),
+            (Request::patch(uri()), Method::PATCH, false, false),
+            (
+                Request::connect(Uri::from_static("example.com:443")),
+                Method::CONNECT,
+                false,
+                false,
+            ),
+        ];
+        for (request, method, safe, idempotent) in table {
+            assert!(request.method_is(&method), "{method}");
+            assert_eq!(request.is_safe(), safe, "{method}");
+            assert_eq!(request.is_idempotent(), idempotent, "{method}");
+            assert_eq!(request.body().is_empty(), Some(true));
+        }
+
+        let mut custom = request("/");
+        *custom.method_mut() = Method::from_bytes(b"PURGE").unwrap();
+        assert!(!custom.method_is(&Method::GET));
+        assert!(!custom.is_safe() && !custom.is_idempotent());
+    }
+
+    #[test]
+    fn query_pairs_are_decoded() {
+        let search = request("/?a+b=c%20d&&flag&sum=1%2B1&price=%E2%82%AC5&bad=%zz%4&raw=%FF");
+        let pairs: Vec<_> = search.query_pairs().collect();
+        assert_eq!(
+            pairs,
+            [
+                ("a b", "c d"),
+                ("flag", ""),
+                ("sum", "1+1"),
+                ("price", "€5"),
+                ("bad", "%zz%4"),
+                ("raw", "\u{FFFD}"),
+            ]
+            .map(|(name, value)| (Cow::Borrowed(name), Cow::Borrowed(value)))
+        );
+        assert!(matches!(pairs[1].0, Cow::Borrowed(_)));
+        assert_eq!(request("/").query_pairs().count(), 0);
+    }
+
+    #[tokio::test]
+    async fn parts_round_trip() {
+        let mut original = request("https://example.com/upload?v=1");
+        *original.method_mut() = http::Method::PUT;
+        original
+            .headers_mut()
+            .insert(http::header::ETAG, HeaderValue::from_static("\"v1\""));
+        original.extensions_mut().insert(RequestId("req-1".into()));
+        *original.body_mut() = Body::from_text("payload");
+
+        let (parts, body): (crate::RequestParts, Body) = original.into_parts();
+        let rebuilt = Request::from_parts(parts, body.with_mime(mime::TEXT_CSV));
+        assert_eq!(rebuilt.method(), http::Method::PUT);
+        assert_eq!(rebuilt.uri(), "https://example.com/upload?v=1");
+        assert_eq!(rebuilt.headers()[http::header::ETAG], "\"v1\"");
+        assert_eq!(rebuilt.request_id().unwrap().as_str(), "req-1");
+        assert_eq!(rebuilt.body().mime(), Some(&mime::TEXT_CSV));
+
+        let mapped = rebuilt.map(|body| Body::from_bytes(body.len().unwrap().to_string()));
+        assert_eq!(mapped.request_id().unwrap().as_str(), "req-1");
+        assert_eq!(mapped.into_body().into_string().await.unwrap(), "7");
+    }
+
+    #[test]
+    fn query_params_are_appended() {
+        let appended = request("https://example.com:8443/search?page=2")
+            .query_param("q", "a&b=c d")
+            .query_param("city", "Zürich");
+        assert_eq!(
+            appended.uri(),
+            "https://example.com:8443/search?page=2&q=a%26b%3Dc+d&city=Z%C3%BCrich"
+        );
+        let pairs: Vec<(String, String)> = appended
+            .query_pairs()
+            .map(|(name, value)| (name.into_owned(), value.into_owned()))
+            .collect();
+        assert_eq!(
+            pairs,
+            [("page", "2"), ("q", "a&b=c d"), ("city", "Zürich")]
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+        );
+
+        assert_eq!(request("/").query_param("k", "").uri(), "/?k=");
+        let connect = request("example.com:443").query_param("k", "v");
+        assert_eq!(connect.uri(), "example.com:443");
+    }
+
+    #[cfg(feature = "form")]
+    #[test]
+    fn queries_are_replaced() {
+        #[derive(serde::Serialize)]
+        struct Search<'a> {
+            q: &'a str,
+            tag: Option<&'a str>,
+        }
+
+        let search = Search {
+            q: "東京 & more",
+            tag: None,
+        };
+        let replaced = request("http://example.com/search?old=1")
+            .set_query(&search)
+            .unwrap();
+        assert_eq!(
+            replaced.uri(),
+            "http://example.com/search?q=%E6%9D%B1%E4%BA%AC+%26+more"
+        );
+        let empty: [(&str, &str); 0] = [];
+        assert_eq!(
+            replaced.set_query(&empty).unwrap().uri(),
+            "http://example.com/search"
+        );
+        assert!(request("/").set_query(&"text").is_err());
+    }
+
+    #[cfg(feature = "json")]
+    #[tokio::test]
+    async fn json_media_types() {
+        let decode = |content_type: Option<&'static str>| async move {
+            let mut message = Request::new(Body::from_bytes("{\"id\":7}"));
+            if let Some(value) = content_type {
+                message
+                    .headers_mut()
+                    .insert(http::header::CONTENT_TYPE, HeaderValue::from_static(value));
+            }
+            message
+                .into_json::<serde_json::Value>()
+                .await
+                .map(|value| value["id"].as_u64())
+                .map_err(|error| (error.status(), error.to_string()))
+        };
+
+        for accepted in [
+            "application/json",
+            "application/json; charset=utf-8",
+            "application/json; charset=\"UTF-8\"",
+            "application/problem+json",
+            "application/vnd.api+json; ext=bulk",
+            "text/json",
+        ] {
+            assert_eq!(decode(Some(accepted)).await, Ok(Some(7)), "{accepted}");
+        }
+
+        let unsupported = http::StatusCode::UNSUPPORTED_MEDIA_TYPE;
+        for rejected in [
+            None,
+            Some("text/plain"),
+            Some("application/jsonp"),
+            Some("application/json-seq"),
+            Some("application/x-www-form-urlencoded"),
+            Some("json"),
+        ] {
+            let (status, message) = decode(rejected).await.unwrap_err();
+            assert_eq!(status, unsupported, "{rejected:?}");
+            assert_eq!(message, "expected a JSON body");
+        }
+        assert_eq!(
+            decode(Some("application/json; charset=utf-16")).await,
+            Err((unsupported, "expected UTF-8 JSON, not utf-16".into()))
+        );
+
+        let mut message = Request::new(Body::from_bytes("{"));
+        message.headers_mut().insert(
+            http::header::CONTENT_TYPE,
+            HeaderValue::from_static("application/json"),
+        );
+        let error = message.into_json::<serde_json::Value>().await.unwrap_err();
+        assert_eq!(error.status(), http::StatusCode::BAD_REQUEST);
+    }
+
+    #[cfg(feature = "form")]
+    #[tokio::test]
+    async fn forms_check_the_content_type() {
+        #[derive(Debug, serde::Deserialize)]
+        struct Login {
+            user: String,
+        }
+
+        let form = |content_type: &'static str| {
+            let mut message = Request::new(Body::from_bytes("user=ann"));
+            message.headers_mut().insert(
+                http::header::CONTENT_TYPE,
+                HeaderValue::from_static(content_type),
+            );
+            message
+        };
+        let login: Login = form("application/x-www-form-urlencoded; charset=utf-8")
+            .into_form()
+            .await
+            .unwrap();
+        assert_eq!(login.user, "ann");
+
+        for rejected in [
+            "text/plain",
+            "multipart/form-data; boundary=x",
+            "application/json",
+        ] {
+            let error = form(rejected).into_form::<Login>().await.unwrap_err();
+            assert_eq!(error.status(), http::StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        }
+    }
+
+    #[cfg(feature = "form")]
+    #[tokio::test]
+    async fn extended_forms_check_the_content_type() {
+        #[derive(Debug, serde::Deserialize)]
+        struct Survey {
+            pets: Vec<String>,
+        }
+
+        let mut request = Request::new(Body::from_bytes("pets=cat&pets=dog"));
+        let error = request.into_form_extended::<Survey>().await.unwrap_err();
+        assert_eq!(
+            error.into_boxed_http_error().status(),
+            http::StatusCode::UNSUPPORTED_MEDIA_TYPE
+        );
+
+        request.headers_mut().insert(
+            http::header::CONTENT_TYPE,
+            HeaderValue::from_static("application/x-www-form-urlencoded; charset=utf-8"),
+        );
+        let survey: Survey = request.into_form_extended().await.unwrap();
+        assert_eq!(survey.pets, ["cat", "dog"]);
+
+        let mut request = Request::new(Body::from_bytes("pets=cat&pets"));
+        request.headers_mut().insert(
+            http::header::CONTENT_TYPE,
+            HeaderValue::from_static("application/x-www-form-urlencoded"),
+        );
+        let error = request.into_form_extended::<(u32,)>().await.unwrap_err();
+        assert_eq!(
+            error.into_boxed_http_error().status(),
+            http::StatusCode::BAD_REQUEST
+        );
+    }
+
+    #[cfg(feature = "form")]
+    #[tokio::test]
+    async fn query_pairs_match_form_decoding() {
+        let query = "a+b=c%20d&&flag&sum=1%2B1&price=%E2%82%AC5&bad=%zz%4&raw=%FF";
+        let from_query: Vec<(String, String)> = request(&alloc::format!("/?{query}"))
+            .query_pairs()
+            .map(|(name, value)| (name.into_owned(), value.into_owned()))
+            .collect();
+        let from_body: Vec<(String, String)> = Body::from_bytes(query).into_form().await.unwrap();
+        assert_eq!(from_query, from_body);
+    }
+
+    #[test]
+    fn uri_parts() {
+        let mut proxied = request("http://[::1]:8080/a/b?c");
+        proxied.headers_mut().insert(
+            http::header::HOST,
+            HeaderValue::from_static("ignored.example"),
+        );
+        assert_eq!(proxied.host(), Some("[::1]"));
+        assert_eq!(proxied.scheme(), Some("http"));
+        assert_eq!(proxied.path(), "/a/b");
+
+        let mut origin = request("/a?b");
+        assert_eq!((origin.host(), origin.scheme()), (None, None));
+        for (host, expected) in [
+            ("example.com", Some("example.com")),
+            ("example.com:8080", Some("example.com")),
+            ("[2001:db8::1]:443", Some("[2001:db8::1]")),
+            (":80", None),
+        ] {
+            origin
+                .headers_mut()
+                .insert(http::header::HOST, HeaderValue::from_static(host));
+            assert_eq!(origin.host(), expected, "{host}");
+        }
+    }
+
+    #[test]
+    fn path_params() {
+        let mut search = request("/users/7");
+        assert_eq!(search.param("id"), None);
+        search
+            .extensions_mut()
+            .insert(PathParams::from_iter([("id", "7")]));
+        assert_eq!(search.param("id"), Some("7"));
+        assert_eq!(search.param("name"), None);
+    }
+
+    #[test]
+    fn typed_headers() {
+        let mut request = request("/");
+        assert_eq!(request.content_length(), None);
+        assert!(request.accepts(&mime::TEXT_HTML));
+        assert!(request.authorization().is_none());
+
+        let headers = request.headers_mut();
+        headers.insert(http::header::CONTENT_LENGTH, "12".parse().unwrap());
+        headers.insert(
+            http::header::ACCEPT,
+            "text/html;q=0.8, application/json".parse().unwrap(),
+        );
+        headers.insert(http::header::AUTHORIZATION, "Bearer t0k3n".parse().unwrap());
+        assert_eq!(request.content_length(), Some(12));
+        assert!(request.accepts(&mime::APPLICATION_JSON));
+        assert!(!request.accepts(&mime::IMAGE_PNG));
+        assert_eq!(
+            request.authorization(),
+            Some(Authorization::Bearer("t0k3n".into()))
+        );
+        assert_eq!(request.content_disposition(), None);
+        request.headers_mut().insert(
+            http::header::CONTENT_DISPOSITION,
+            "form-data; name=\"file\"; filename=\"a.txt\""
+                .parse()
+                .unwrap(),
+        );
+        assert_eq!(
+            request.content_disposition(),
+            Some(ContentDisposition::form_data("file", Some("a.txt")))
+        );
+
+        assert_eq!(request.if_match(), None);
+        let headers = request.headers_mut();
+        headers.insert(http::header::IF_MATCH, "*".parse().unwrap());
+        headers.insert(
+            http::header::IF_NONE_MATCH,
+            "W/\"a\", \"b\"".parse().unwrap(),
+        );
+        assert_eq!(request.if_match(), Some(ETagMatch::Any));
+        let tags = ["a", "b"].map(|tag| headers::ETag::strong(tag).unwrap());
+        let if_none_match = request.if_none_match().unwrap();
+        assert!(tags.iter().all(|tag| if_none_match.matches_weak(tag)));
+        assert!(!if_none_match.matches_strong(&tags[0]));
+    }
+
+    #[test]
+    fn sse_subscription_headers() {
+        let mut subscription = request("/events");
+        subscription.sse(Some("17"));
+        let fields = subscription.headers();
+        assert_eq!(fields[http::header::ACCEPT], "text/event-stream");
+        assert_eq!(fields[http::header::CACHE_CONTROL], "no-cache");
+        assert_eq!(fields[headers::LAST_EVENT_ID], "17");
+
+        subscription.sse(None);
+        assert!(!subscription.headers().contains_key(headers::LAST_EVENT_ID));
+        subscription.sse(Some("bad\nid"));
+        assert!(!subscription.headers().contains_key(headers::LAST_EVENT_ID));
+    }
+
+    #[test]
+    fn fallible_setters() {
+        let tagged = request("/")
+            .try_header("x-tenant", "acme")
+            .unwrap()
+            .try_mime("application/json")
+            .unwrap();
+        assert_eq!(tagged.headers()["x-tenant"], "acme");
+        assert_eq!(
+            tagged.headers()[http::header::CONTENT_TYPE],
+            "application/json"
+        );
+        assert_eq!(tagged.body().mime(), Some(&mime::APPLICATION_JSON));
+
+        assert!(request("/").try_header("x-note", "a\r\nb").is_err());
+        assert!(request("/").try_header("bad name", "value").is_err());
+        assert!(request("/").try_mime("application").is_err());
+        assert!(request("/").try_mime("text/plain; charset").is_err());
+    }
+
+    #[tokio::test]
+    async fn body_content_types() {
+        let mut message = request("/");
+        message.set_body(Body::from_bytes("raw"));
+        assert_eq!(
+            message.headers()[http::header::CONTENT_TYPE],
+            "application/octet-stream"
+        );
+
+        // A conflicting header set beforehand is kept, and describes the taken body.
+        let mut message = request("/");
+        message.headers_mut().insert(
+            http::header::CONTENT_TYPE,
+            HeaderValue::from_static("application/json"),
+        );
+        message.set_body(Body::from_text("{}"));
+        assert_eq!(
+            message.headers()[http::header::CONTENT_TYPE],
+            "application/json"
+        );
+        let body = message.take_body().unwrap();
+        assert_eq!(body.mime(), Some(&mime::APPLICATION_JSON));
+        assert!(message.body().is_frozen());
+
+        // Without a valid header, the body keeps its own MIME type.
+        let mut message = request("/");
+        message.headers_mut().insert(
+            http::header::CONTENT_TYPE,
+            HeaderValue::from_static("not a type"),
+        );
+        *message.body_mut() = Body::from_text("hi");
+        let body = message.take_body().unwrap();
+        assert_eq!(body.mime(), Some(&mime::TEXT_PLAIN_UTF_8));
+        assert_eq!(body.into_string().await.unwrap(), "hi");
+
+        let mut message = request("/");
+        *message.body_mut() = Body::from_text("hi");
+        message.sync_content_type();
+        assert_eq!(
+            message.headers()[http::header::CONTENT_TYPE],
+            "text/plain; charset=utf-8"
+        );
+    }
+
+    #[tokio::test]
+    async fn buffered_clones_are_independent() {
+        let chunks = futures_lite::stream::iter([Ok::<_, BodyError>("a"), Ok("b")]);
+        let mut original = request("/upload?x=1");
+        *original.method_mut() = http::Method::POST;
+        *original.body_mut() = crate::Body::from_stream(chunks);
+        original
+            .headers_mut()
+            .insert(http::header::CONTENT_TYPE, "text/plain".parse().unwrap());
+        original.extensions_mut().insert(7u32);
+
+        let mut copy = original.clone_with_buffered_body().await.unwrap();
+        copy.headers_mut().clear();
+        assert_eq!(copy.method(), http::Method::POST);
+        assert_eq!(copy.uri(), "/upload?x=1");
+        assert_eq!(copy.extensions().get(), Some(&7u32));
+        assert_eq!(original.headers()[http::header::CONTENT_TYPE], "text/plain");
+        assert_eq!(copy.into_body().into_string().await.unwrap(), "ab");
+        assert_eq!(original.into_body().into_string().await.unwrap(), "ab");
+    }
+
+    #[cfg(feature = "form")]
+    #[test]
+    fn typed_query() {
+        use crate::HttpError;
+        use http::StatusCode;
+
+        #[derive(Debug, PartialEq, serde::Deserialize)]
+        struct Filter {
+            tag: Option<String>,
+            page: Option<u32>,
+        }
+
+        assert_eq!(
+            request("/items").query::<Filter>().unwrap(),
+            Filter {
+                tag: None,
+                page: None
+            }
+        );
+        assert_eq!(
+            request("/items?tag=a%26b&page=2")
+                .query::<Filter>()
+                .unwrap(),
+            Filter {
+                tag: Some("a&b".into()),
+                page: Some(2)
+            }
+        );
+        let error = request("/items?page=two").query::<Filter>().unwrap_err();
+        assert_eq!(
+            error.into_boxed_http_error().status(),
+            StatusCode::BAD_REQUEST
+        );
+    }
+}
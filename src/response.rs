@@ -0,0 +1,1567 @@
+//! Extension methods for [`Response`].
+
+#[cfg(feature = "std")]
+extern crate std;
+
+use core::future::Future;
+
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use bytes::Bytes;
+use http::{
+    header::{self, HeaderName, HeaderValue},
+    status::InvalidStatusCode,
+    HeaderMap, Method, StatusCode,
+};
+use mime::Mime;
+
+use crate::{
+    extension,
+    headers::{ContentDisposition, ETag, ETagMatch},
+    redact::DebugResponse,
+    upgrade::UpgradeMarker,
+    Body, BodyError, HttpError, Request, RequestExt, Response,
+};
+
+/// Extension trait adding convenience methods to [`Response`].
+///
+/// `Response` is an alias of [`http::Response`], so these methods are provided by a trait;
+/// import it with `use http_kit::ResponseExt`.
+pub trait ResponseExt {
+    /// Marks the response as a protocol upgrade.
+    ///
+    /// Server adapters fulfill the pending [`OnUpgrade`](crate::upgrade::OnUpgrade) once
+    /// the response head has been flushed.
+    fn mark_upgrade(&mut self);
+
+    /// Returns whether the response was marked with [`ResponseExt::mark_upgrade`].
+    fn is_upgrade(&self) -> bool;
+
+    /// Inserts an extension, returning the previous value of the same type.
+    ///
+    /// Unlike `extensions_mut().insert`, the value does not need to implement `Clone`,
+    /// which suits one-shot values such as channel senders or transactions. Such values
+    /// are not copied when the extensions are cloned.
+    fn insert_extension<T: Send + Sync + 'static>(&mut self, value: T) -> Option<T>;
+
+    /// Returns a reference to an extension inserted with either
+    /// [`ResponseExt::insert_extension`] or `extensions_mut().insert`.
+    fn extension<T: Send + Sync + 'static>(&self) -> Option<&T>;
+
+    /// Returns a mutable reference to an extension.
+    fn extension_mut<T: Send + Sync + 'static>(&mut self) -> Option<&mut T>;
+
+    /// Removes an extension and returns it, handing its ownership to the caller.
+    fn take_extension<T: Send + Sync + 'static>(&mut self) -> Option<T>;
+
+    /// Returns the extension of type `T`, inserting the value returned by `f` first if
+    /// there is none.
+    ///
+    /// Useful for lazily initialized per-message caches.
+    fn extension_or_insert_with<T, F>(&mut self, f: F) -> &mut T
+    where
+        T: Send + Sync + 'static,
+        F: FnOnce() -> T;
+
+    /// Returns the extension of type `T`, inserting `T::default()` first if there is
+    /// none, such as a list that several middleware append to.
+    fn extension_or_default<T: Default + Send + Sync + 'static>(&mut self) -> &mut T;
+
+    /// Sets the `Deprecation` header (RFC 9745).
+    ///
+    /// The header carries the date the resource was deprecated as `@<unix seconds>`, or
+    /// `true` when `when` is `None`.
+    #[cfg(feature = "std")]
+    fn deprecation(&mut self, when: Option<std::time::SystemTime>);
+
+    /// Sets the `Sunset` header (RFC 8594) to the HTTP date after which the resource
+    /// will stop responding.
+    #[cfg(feature = "std")]
+    fn sunset(&mut self, when: std::time::SystemTime);
+
+    /// Returns the value of the `Content-Length` header, or `None` if it is missing or
+    /// malformed.
+    fn content_length(&self) -> Option<u64>;
+
+    /// Makes the framing headers agree with the body.
+    ///
+    /// When the length of the body is known, `Content-Length` is set to it and
+    /// `Transfer-Encoding` is removed; when it is unknown, `Content-Length` is removed.
+    /// Responses with a `1xx`, `204 No Content` or `304 Not Modified` status have no
+    /// body, so both headers are removed from them.
+    fn sync_content_length(&mut self);
+
+    /// Appends a header and returns the response.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the name or the value is invalid, for instance when the value
+    /// contains a line break.
+    fn try_header<K, V>(self, name: K, value: V) -> Result<Self, http::Error>
+    where
+        Self: Sized,
+        HeaderName: TryFrom<K>,
+        <HeaderName as TryFrom<K>>::Error: Into<http::Error>,
+        HeaderValue: TryFrom<V>,
+        <HeaderValue as TryFrom<V>>::Error: Into<http::Error>;
+
+    /// Parses `mime`, sets it as the `Content-Type` header and the MIME type of the body,
+    /// and returns the response.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `mime` is not a valid media type. Every valid media type is a
+    /// valid header value, including parameters with non-ASCII bytes.
+    fn try_mime(self, mime: &str) -> Result<Self, mime::FromStrError>
+    where
+        Self: Sized;
+
+    /// Replaces the body, setting `Content-Type` from the MIME type of `body`.
+    ///
+    /// Unlike `*response.body_mut() = body`, the message then describes its body. A
+    /// `Content-Type` header that is already set wins over the MIME type of the body
+    /// and is kept as is. See [`ResponseExt::sync_content_type`].
+    ///
+    /// Like [`Body::unfreeze`], this installs `body` even if the current body is frozen
+    /// because it was consumed; [`ResponseExt::try_replace_body`] refuses to.
+    fn set_body(&mut self, body: Body);
+
+    /// Replaces the body like [`ResponseExt::set_body`] and returns the previous one,
+    /// unless the body is frozen, see [`Body::try_replace`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BodyError::BodyFrozen`], leaving the response unchanged, if the body was
+    /// consumed.
+    fn try_replace_body(&mut self, body: Body) -> Result<Body, BodyError>;
+
+    /// Sets the `Content-Type` header from the MIME type of the body, unless the header
+    /// is already set or the body has no MIME type.
+    fn sync_content_type(&mut self);
+
+    /// Takes the body, leaving a frozen body in its place, see [`Body::take`].
+    ///
+    /// The MIME type of the returned body is that of the `Content-Type` header when it
+    /// holds a valid media type, so that code handed the bare body still knows its
+    /// format. As with [`ResponseExt::set_body`], the header wins over the MIME type
+    /// the body had.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BodyError::BodyFrozen`] if the body was already taken.
+    fn take_body(&mut self) -> Result<Body, BodyError>;
+
+    /// Converts a response with any body, such as `hyper::body::Incoming`, into a
+    /// [`Response`](crate::Response), see [`convert::map_response`](crate::convert::map_response).
+    fn from_http<B>(response: http::Response<B>) -> Self
+    where
+        Self: Sized,
+        B: http_body::Body + Send + Sync + 'static,
+        B::Data: Into<bytes::Bytes>,
+        B::Error: Into<BodyError>;
+
+    /// Creates a response with the status code `status`.
+    ///
+    /// Like [`IntoResponse`], the `Content-Type` header is set from the MIME type of the
+    /// body: `text/plain; charset=utf-8` for strings and `application/octet-stream` for
+    /// bytes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `status` is not between 100 and 999.
+    fn try_new(status: u16, body: impl Into<Body>) -> Result<Self, InvalidStatusCode>
+    where
+        Self: Sized;
+
+    /// Creates a `text/plain` response with the status of `error` and its `Display` text.
+    ///
+    /// Like [`ErrorHandler::render_plain`](crate::middleware::ErrorHandler::render_plain),
+    /// server errors only get the canonical reason of their status, so that their text
+    /// does not leak internal details.
+    fn from_error(error: &dyn HttpError) -> Self
+    where
+        Self: Sized;
+
+    /// Creates a `200 OK` response with `body`.
+    ///
+    /// Like [`ResponseExt::try_new`], the `Content-Type` header is set from the MIME
+    /// type of the body. The other constructors named after a status, such as
+    /// [`ResponseExt::created`] or [`ResponseExt::not_found`], work the same way.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use http_kit::{Response, ResponseExt, StatusCode};
+    ///
+    /// let response = Response::ok("hello");
+    /// assert_eq!(response.status(), StatusCode::OK);
+    /// assert_eq!(response.headers()["content-type"], "text/plain; charset=utf-8");
+    ///
+    /// let response = Response::not_found().with_status(StatusCode::GONE);
+    /// assert_eq!(response.status(), StatusCode::GONE);
+    /// ```
+    fn ok(body: impl Into<Body>) -> Self
+    where
+        Self: Sized;
+
+    /// Creates a `201 Created` response with `body`.
+    fn created(body: impl Into<Body>) -> Self
+    where
+        Self: Sized;
+
+    /// Creates an empty `202 Accepted` response.
+    fn accepted() -> Self
+    where
+        Self: Sized;
+
+    /// Creates a `204 No Content` response, which has no body and no content headers.
+    fn no_content() -> Self
+    where
+        Self: Sized;
+
+    /// Creates a `304 Not Modified` response, which has no body and no content headers.
+    ///
+    /// See [`ResponseExt::not_modified_if`] to answer conditional requests.
+    fn not_modified() -> Self
+    where
+        Self: Sized;
+
+    /// Creates a `400 Bad Request` response with `body`.
+    fn bad_request(body: impl Into<Body>) -> Self
+    where
+        Self: Sized;
+
+    /// Creates an empty `401 Unauthorized` response.
+    fn unauthorized() -> Self
+    where
+        Self: Sized;
+
+    /// Creates an empty `403 Forbidden` response.
+    fn forbidden() -> Self
+    where
+        Self: Sized;
+
+    /// Creates an empty `404 Not Found` response.
+    fn not_found() -> Self
+    where
+        Self: Sized;
+
+    /// Creates a `422 Unprocessable Entity` response with `body`.
+    fn unprocessable(body: impl Into<Body>) -> Self
+    where
+        Self: Sized;
+
+    /// Creates a `500 Internal Server Error` response with `body`.
+    fn internal_error(body: impl Into<Body>) -> Self
+    where
+        Self: Sized;
+
+    /// Sets the status code and returns the response, for use in a chain.
+    ///
+    /// Setting `204 No Content` or `304 Not Modified` removes the content headers, since
+    /// such responses have no content. Their body must already be empty: debug builds
+    /// panic if it is known not to be.
+    fn with_status(self, status: StatusCode) -> Self
+    where
+        Self: Sized;
+
+    /// Creates a CSV download streaming `records`, see [`Body::from_csv`].
+    ///
+    /// Besides `Content-Type: text/csv; charset=utf-8`, `Content-Disposition` is set to
+    /// `attachment` with `filename`, see [`ResponseExt::attachment`].
+    #[cfg(feature = "csv")]
+    fn csv<S, T, E>(records: S, filename: &str) -> Self
+    where
+        Self: Sized,
+        S: futures_lite::Stream<Item = Result<T, E>> + Send + Sync + 'static,
+        T: serde::Serialize,
+        E: Into<BodyError>;
+
+    /// Sends `trailers` after the body, see [`Body::with_trailers`](crate::Body::with_trailers).
+    fn set_trailers(&mut self, trailers: HeaderMap);
+
+    /// Buffers the body and returns a copy of its trailers, see [`Body::trailers`](crate::Body::trailers).
+    fn trailers(&mut self) -> impl Future<Output = Result<Option<HeaderMap>, BodyError>> + Send;
+
+    /// Consumes the response and decodes its body as text in the charset of its
+    /// `Content-Type`, UTF-8 if it names none.
+    ///
+    /// See [`Body::into_string_with_charset`] for how charsets and byte order marks are
+    /// handled.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BodyError::UnknownCharset`] if the charset is unknown and
+    /// [`BodyError::MalformedText`] or [`BodyError::Utf8`] if the body is not valid in
+    /// it, besides the errors of reading the body.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use http_kit::{header, Body, Response, ResponseExt};
+    ///
+    /// # async fn example() -> Result<(), http_kit::BodyError> {
+    /// let mut response = Response::new(Body::from_bytes(&b"r\xe9sum\xe9"[..]));
+    /// response.headers_mut().insert(
+    ///     header::CONTENT_TYPE,
+    ///     "text/html; charset=iso-8859-1".parse().unwrap(),
+    /// );
+    /// assert_eq!(response.text().await?, "r\u{e9}sum\u{e9}");
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "encoding")]
+    fn text(self) -> impl Future<Output = Result<bytestr::ByteStr, BodyError>> + Send
+    where
+        Self: Sized;
+
+    /// Consumes the response and decodes its body like [`ResponseExt::text`], but
+    /// replaces malformed sequences with `U+FFFD` and decodes bodies in an unknown
+    /// charset as UTF-8.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error only if the body cannot be read.
+    #[cfg(feature = "encoding")]
+    fn text_lossy(self) -> impl Future<Output = Result<bytestr::ByteStr, BodyError>> + Send
+    where
+        Self: Sized;
+
+    /// Deserializes the JSON body after checking its `Content-Type`, see
+    /// [`RequestExt::into_json`](crate::RequestExt::into_json) for the accepted media
+    /// types.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error with status `415 Unsupported Media Type` if the `Content-Type`
+    /// is missing, not JSON or in another charset than UTF-8, and the status of the
+    /// [`BodyError`] if the body cannot be read or decoded.
+    #[cfg(feature = "json")]
+    #[allow(clippy::wrong_self_convention)]
+    fn into_json<T>(&mut self) -> impl Future<Output = crate::Result<T>> + Send
+    where
+        T: serde::de::DeserializeOwned;
+
+    /// Deserializes the URL-encoded form in the body after checking its
+    /// `Content-Type`, see [`Body::into_form`](crate::Body::into_form).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error with status `415 Unsupported Media Type` if the `Content-Type`
+    /// is not `application/x-www-form-urlencoded`, with any parameters, and the status
+    /// of the [`BodyError`] if the body cannot be read or decoded.
+    #[cfg(feature = "form")]
+    #[allow(clippy::wrong_self_convention)]
+    fn into_form<T>(&mut self) -> impl Future<Output = crate::Result<T>> + Send
+    where
+        T: serde::de::DeserializeOwned;
+
+    /// Sets `Content-Disposition` to `attachment` with `filename` and returns the
+    /// response, so that browsers download it.
+    ///
+    /// Filenames that are not plain ASCII are sent with both an ASCII fallback and
+    /// their exact UTF-8 form, see [`ContentDisposition`].
+    fn attachment(self, filename: &str) -> Self
+    where
+        Self: Sized;
+
+    /// Sets the `ETag` header and returns the response.
+    fn etag(self, tag: ETag) -> Self
+    where
+        Self: Sized;
+
+    /// Sets the `Last-Modified` header to the HTTP date of `when`.
+    #[cfg(feature = "std")]
+    fn set_last_modified(&mut self, when: std::time::SystemTime);
+
+    /// Parses the `Last-Modified` header, if present and a valid HTTP date.
+    #[cfg(feature = "std")]
+    fn last_modified(&self) -> Option<std::time::SystemTime>;
+
+    /// Turns the response into `304 Not Modified` when the preconditions of `request`
+    /// show that the client already has it.
+    ///
+    /// Only successful responses to `GET` and `HEAD` requests are considered. The
+    /// `ETag` of the response is compared with `If-None-Match` using the weak
+    /// comparison; without `If-None-Match`, its `Last-Modified` date is compared with
+    /// `If-Modified-Since` (with the `std` feature). The 304 response has no body and
+    /// keeps the validators and caching headers, but none of the content headers.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use http::StatusCode;
+    /// use http_kit::{headers::ETag, Body, Request, Response, ResponseExt};
+    ///
+    /// let mut request = Request::new(Body::empty());
+    /// request.headers_mut().insert("if-none-match", r#"W/"v1""#.parse().unwrap());
+    ///
+    /// let response = Response::new(Body::from_text("hello"))
+    ///     .etag(ETag::strong("v1").unwrap())
+    ///     .not_modified_if(&request);
+    /// assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+    /// assert_eq!(response.headers()["etag"], r#""v1""#);
+    /// ```
+    fn not_modified_if(self, request: &Request) -> Self
+    where
+        Self: Sized;
+
+    /// Answers a WebSocket upgrade request with `101 Switching Protocols`.
+    ///
+    /// The response carries `Sec-WebSocket-Accept`, the subprotocol selected from
+    /// `config`, if any, and is marked as an upgrade (see
+    /// [`ResponseExt::mark_upgrade`]).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `request` is not a valid upgrade request, see
+    /// [`RequestExt::is_websocket_upgrade`](crate::RequestExt::is_websocket_upgrade).
+    #[cfg(feature = "ws")]
+    fn websocket_accept(
+        request: &Request,
+        config: &crate::ws::WebSocketConfig,
+    ) -> Result<Self, crate::ws::WsHandshakeError>
+    where
+        Self: Sized;
+
+    /// Checks that the response accepts a WebSocket upgrade requested with the
+    /// `Sec-WebSocket-Key` `key`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the status is not `101 Switching Protocols`, or if the
+    /// upgrade headers or the `Sec-WebSocket-Accept` value are wrong.
+    #[cfg(feature = "ws")]
+    fn verify_websocket_accept(&self, key: &[u8]) -> Result<(), crate::ws::WsHandshakeError>;
+
+    /// Builds the representation of the `choices` that `request` prefers.
+    ///
+    /// The media type is picked with [`RequestExt::preferred_mime`], and only the
+    /// matching body is built. The response carries that type in `Content-Type` and
+    /// `Vary: Accept`. When no choice is acceptable, the response is
+    /// `406 Not Acceptable`, listing the available types.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the body builder that was called.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use http_kit::{Body, BodyError, Request, Response, ResponseExt};
+    ///
+    /// let mut request = Request::new(Body::empty());
+    /// request.headers_mut().insert("accept", "text/html".parse().unwrap());
+    ///
+    /// type Render = Box<dyn FnOnce() -> Result<Body, BodyError>>;
+    /// let choices: [(mime::Mime, Render); 2] = [
+    ///     (mime::APPLICATION_JSON, Box::new(|| Ok(Body::from_bytes(r#"{"id":7}"#)))),
+    ///     (mime::TEXT_HTML, Box::new(|| Ok(Body::from_text("<p>7</p>")))),
+    /// ];
+    /// let response = Response::negotiated(&request, choices).unwrap();
+    /// assert_eq!(response.headers()["content-type"], "text/html");
+    /// assert_eq!(response.headers()["vary"], "accept");
+    /// ```
+    fn negotiated<F, E>(
+        request: &Request,
+        choices: impl IntoIterator<Item = (Mime, F)>,
+    ) -> Result<Self, E>
+    where
+        Self: Sized,
+        F: FnOnce() -> Result<Body, E>;
+
+    /// Returns a [`Debug`](core::fmt::Debug) summary of the response that is safe to
+    /// log, with the values of [sensitive headers](crate::redact::is_sensitive_header)
+    /// such as `Set-Cookie` replaced, see [`DebugResponse`].
+    fn debug(&self) -> DebugResponse<'_>;
+
+    /// Returns a [`Debug`](core::fmt::Debug) summary of the response that prints every
+    /// header value, for local debugging.
+    fn debug_full(&self) -> DebugResponse<'_>;
+
+    /// Panics unless the status of the response is `status`. Returns the response, so
+    /// that assertions can be chained.
+    #[cfg(feature = "test-util")]
+    fn assert_status(&self, status: StatusCode) -> &Self;
+
+    /// Panics unless the response has a header `name` whose first value is `expected`.
+    #[cfg(feature = "test-util")]
+    fn assert_header(&self, name: &str, expected: &str) -> &Self;
+
+    /// Buffers the body and panics unless it deserializes from JSON to `expected`.
+    ///
+    /// The body stays readable afterwards. The `Content-Type` is not checked.
+    #[cfg(all(feature = "test-util", feature = "json"))]
+    fn assert_body_json<T>(&mut self, expected: T) -> impl Future<Output = ()> + Send
+    where
+        T: serde::de::DeserializeOwned + PartialEq + core::fmt::Debug + Send;
+}
+
+impl ResponseExt for Response {
+    fn mark_upgrade(&mut self) {
+        self.extensions_mut().insert(UpgradeMarker);
+    }
+
+    fn is_upgrade(&self) -> bool {
+        self.extensions().get::<UpgradeMarker>().is_some()
+    }
+
+    fn insert_extension<T: Send + Sync + 'static>(&mut self, value: T) -> Option<T> {
+        extension::insert(self.extensions_mut(), value)
+    }
+
+    fn extension<T: Send + Sync + 'static>(&self) -> Option<&T> {
+        extension::get(self.extensions())
+    }
+
+    fn extension_mut<T: Send + Sync + 'static>(&mut self) -> Option<&mut T> {
+        extension::get_mut(self.extensions_mut())
+    }
+
+    fn take_extension<T: Send + Sync + 'static>(&mut self) -> Option<T> {
+        extension::take(self.extensions_mut())
+    }
+
+    fn extension_or_insert_with<T, F>(&mut self, f: F) -> &mut T
+    where
+        T: Send + Sync + 'static,
+        F: FnOnce() -> T,
+    {
+        extension::get_or_insert_with(self.extensions_mut(), f)
+    }
+
+    fn extension_or_default<T: Default + Send + Sync + 'static>(&mut self) -> &mut T {
+        extension::get_or_insert_with(self.extensions_mut(), T::default)
+    }
+
+    #[cfg(feature = "std")]
+    fn deprecation(&mut self, when: Option<std::time::SystemTime>) {
+        self.headers_mut().insert(
+            crate::headers::DEPRECATION,
+            crate::middleware::deprecation::deprecation_value(when),
+        );
+    }
+
+    #[cfg(feature = "std")]
+    fn sunset(&mut self, when: std::time::SystemTime) {
+        self.headers_mut().insert(
+            crate::headers::SUNSET,
+            crate::middleware::deprecation::sunset_value(when),
+        );
+    }
+
+    fn content_length(&self) -> Option<u64> {
+        crate::headers::content_length(self.headers())
+    }
+
+    fn sync_content_length(&mut self) {
+        let status = self.status();
+        let len = self.body().len();
+        let headers = self.headers_mut();
+        if status.is_informational()
+            || status == StatusCode::NO_CONTENT
+            || status == StatusCode::NOT_MODIFIED
+        {
+            headers.remove(header::CONTENT_LENGTH);
+            headers.remove(header::TRANSFER_ENCODING);
+            return;
+        }
+        match len {
+            Some(len) => {
+                let len = HeaderValue::from(len);
+                if headers.get_all(header::CONTENT_LENGTH).iter().ne([&len]) {
+                    headers.insert(header::CONTENT_LENGTH, len);
+                }
+                headers.remove(header::TRANSFER_ENCODING);
+            }
+            None => {
+                headers.remove(header::CONTENT_LENGTH);
+            }
+        }
+    }
+
+    fn try_header<K, V>(mut self, name: K, value: V) -> Result<Self, http::Error>
+    where
+        HeaderName: TryFrom<K>,
+        <HeaderName as TryFrom<K>>::Error: Into<http::Error>,
+        HeaderValue: TryFrom<V>,
+        <HeaderValue as TryFrom<V>>::Error: Into<http::Error>,
+    {
+        crate::headers::try_append(self.headers_mut(), name, value)?;
+        Ok(self)
+    }
+
+    fn try_mime(mut self, mime: &str) -> Result<Self, mime::FromStrError> {
+        let mime: Mime = mime.parse()?;
+        // The parser only accepts bytes that header values allow.
+        if let Ok(value) = HeaderValue::from_str(mime.as_ref()) {
+            self.headers_mut().insert(http::header::CONTENT_TYPE, value);
+        }
+        let body = core::mem::take(self.body_mut());
+        *self.body_mut() = body.with_mime(mime);
+        Ok(self)
+    }
+
+    fn set_body(&mut self, body: Body) {
+        self.body_mut().unfreeze(body);
+        self.sync_content_type();
+    }
+
+    fn try_replace_body(&mut self, body: Body) -> Result<Body, BodyError> {
+        let previous = self.body_mut().try_replace(body)?;
+        self.sync_content_type();
+        Ok(previous)
+    }
+
+    fn sync_content_type(&mut self) {
+        let mime = self.body().mime().cloned();
+        crate::headers::set_default_content_type(self.headers_mut(), mime.as_ref());
+    }
+
+    fn take_body(&mut self) -> Result<Body, BodyError> {
+        let body = self.body_mut().take()?;
+        Ok(match crate::headers::content_type(self.headers()) {
+            Some(mime) => body.with_mime(mime),
+            None => body,
+        })
+    }
+
+    fn from_http<B>(response: http::Response<B>) -> Self
+    where
+        B: http_body::Body + Send + Sync + 'static,
+        B::Data: Into<bytes::Bytes>,
+        B::Error: Into<BodyError>,
+    {
+        crate::convert::map_response(response)
+    }
+
+    fn try_new(status: u16, body: impl Into<Body>) -> Result<Self, InvalidStatusCode> {
+        let status = StatusCode::from_u16(status)?;
+        let mut response = body.into().into_response();
+        *response.status_mut() = status;
+        Ok(response)
+    }
+
+    fn from_error(error: &dyn HttpError) -> Self {
+        crate::middleware::ErrorHandler::render_plain(error)
+    }
+
+    fn ok(body: impl Into<Body>) -> Self {
+        body.into().into_response().with_status(StatusCode::OK)
+    }
+
+    fn created(body: impl Into<Body>) -> Self {
+        body.into().into_response().with_status(StatusCode::CREATED)
+    }
+
+    fn bad_request(body: impl Into<Body>) -> Self {
+        body.into()
+            .into_response()
+            .with_status(StatusCode::BAD_REQUEST)
+    }
+
+    fn unprocessable(body: impl Into<Body>) -> Self {
+        body.into()
+            .into_response()
+            .with_status(StatusCode::UNPROCESSABLE_ENTITY)
+    }
+
+    fn internal_error(body: impl Into<Body>) -> Self {
+        body.into()
+            .into_response()
+            .with_status(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+
+    fn accepted() -> Self {
+        Response::new(Body::empty()).with_status(StatusCode::ACCEPTED)
+    }
+
+    fn unauthorized() -> Self {
+        Response::new(Body::empty()).with_status(StatusCode::UNAUTHORIZED)
+    }
+
+    fn forbidden() -> Self {
+        Response::new(Body::empty()).with_status(StatusCode::FORBIDDEN)
+    }
+
+    fn not_found() -> Self {
+        Response::new(Body::empty()).with_status(StatusCode::NOT_FOUND)
+    }
+
+    fn no_content() -> Self {
+        Response::new(Body::empty()).with_status(StatusCode::NO_CONTENT)
+    }
+
+    fn not_modified() -> Self {
+        Response::new(Body::empty()).with_status(StatusCode::NOT_MODIFIED)
+    }
+
+    fn with_status(mut self, status: StatusCode) -> Self {
+        if status == StatusCode::NO_CONTENT || status == StatusCode::NOT_MODIFIED {
+            debug_assert!(
+                self.body().is_empty() != Some(false),
+                "a {status} response cannot have a body"
+            );
+            for name in CONTENT_HEADERS {
+                self.headers_mut().remove(name);
+            }
+        }
+        *self.status_mut() = status;
+        self
+    }
+
+    #[cfg(feature = "csv")]
+    fn csv<S, T, E>(records: S, filename: &str) -> Self
+    where
+        S: futures_lite::Stream<Item = Result<T, E>> + Send + Sync + 'static,
+        T: serde::Serialize,
+        E: Into<BodyError>,
+    {
+        Body::from_csv(records).into_response().attachment(filename)
+    }
+
+    #[cfg(feature = "encoding")]
+    async fn text(self) -> Result<bytestr::ByteStr, BodyError> {
+        let mime = crate::headers::content_type(self.headers());
+        let charset = mime.as_ref().and_then(|mime| mime.get_param(mime::CHARSET));
+        let charset = charset.as_ref().map_or("utf-8", |charset| charset.as_str());
+        self.into_body().into_string_with_charset(charset).await
+    }
+
+    #[cfg(feature = "encoding")]
+    async fn text_lossy(self) -> Result<bytestr::ByteStr, BodyError> {
+        let mime = crate::headers::content_type(self.headers());
+        let charset = mime.as_ref().and_then(|mime| mime.get_param(mime::CHARSET));
+        let bytes = self.into_body().into_bytes().await?;
+        Ok(crate::body::decode_lossy(
+            bytes,
+            charset.map(|charset| charset.as_str()),
+        ))
+    }
+
+    #[cfg(feature = "json")]
+    async fn into_json<T>(&mut self) -> crate::Result<T>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        crate::headers::expect_json(self.headers())?;
+        self.body_mut()
+            .into_json()
+            .await
+            .map_err(BodyError::into_http_error)
+    }
+
+    #[cfg(feature = "form")]
+    async fn into_form<T>(&mut self) -> crate::Result<T>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        crate::headers::expect_form(self.headers())?;
+        self.body_mut()
+            .into_form()
+            .await
+            .map_err(BodyError::into_http_error)
+    }
+
+    fn attachment(mut self, filename: &str) -> Self {
+        let disposition = ContentDisposition::attachment(filename);
+        self.headers_mut()
+            .insert(header::CONTENT_DISPOSITION, disposition.header_value());
+        self
+    }
+
+    fn etag(mut self, tag: ETag) -> Self {
+        if let Ok(value) = HeaderValue::from_str(&tag.to_string()) {
+            self.headers_mut().insert(header::ETAG, value);
+        }
+        self
+    }
+
+    #[cfg(feature = "std")]
+    fn set_last_modified(&mut self, when: std::time::SystemTime) {
+        if let Ok(value) = HeaderValue::from_str(&crate::date::http_date(when)) {
+            self.headers_mut().insert(header::LAST_MODIFIED, value);
+        }
+    }
+
+    #[cfg(feature = "std")]
+    fn last_modified(&self) -> Option<std::time::SystemTime> {
+        let value = self.headers().get(header::LAST_MODIFIED)?;
+        crate::date::parse_http_date(value.to_str().ok()?)
+    }
+
+    fn not_modified_if(mut self, request: &Request) -> Self {
+        let safe = request.method() == Method::GET || request.method() == Method::HEAD;
+        if !safe || !self.status().is_success() || !is_not_modified(&self, request) {
+            return self;
+        }
+        *self.status_mut() = StatusCode::NOT_MODIFIED;
+        *self.body_mut() = Body::empty();
+        for name in CONTENT_HEADERS {
+            self.headers_mut().remove(name);
+        }
+        self
+    }
+
+    #[cfg(feature = "ws")]
+    fn websocket_accept(
+        request: &Request,
+        config: &crate::ws::WebSocketConfig,
+    ) -> Result<Self, crate::ws::WsHandshakeError> {
+        crate::ws::accept(request, config)
+    }
+
+    #[cfg(feature = "ws")]
+    fn verify_websocket_accept(&self, key: &[u8]) -> Result<(), crate::ws::WsHandshakeError> {
+        crate::ws::verify(self, key)
+    }
+
+    fn negotiated<F, E>(
+        request: &Request,
+        choices: impl IntoIterator<Item = (Mime, F)>,
+    ) -> Result<Self, E>
+    where
+        F: FnOnce() -> Result<Body, E>,
+    {
+        let (mimes, mut builders): (Vec<Mime>, Vec<Option<F>>) = choices
+            .into_iter()
+            .map(|(mime, builder)| (mime, Some(builder)))
+            .unzip();
+        let preferred = request
+            .preferred_mime(&mimes)
+            .and_then(|mime| mimes.iter().position(|candidate| *candidate == mime));
+
+        let mut response = match preferred {
+            Some(index) => {
+                let build = builders[index].take().expect("each choice is built once");
+                let mime = mimes[index].clone();
+                let mut response = Response::new(build()?.with_mime(mime.clone()));
+                if let Ok(value) = HeaderValue::from_str(mime.as_ref()) {
+                    response.headers_mut().insert(header::CONTENT_TYPE, value);
+                }
+                response
+            }
+            None => {
+                let available: Vec<&str> = mimes.iter().map(AsRef::as_ref).collect();
+                let message = format!("Available media types: {}", available.join(", "));
+                text_response(StatusCode::NOT_ACCEPTABLE, message)
+            }
+        };
+        response
+            .headers_mut()
+            .append(header::VARY, HeaderValue::from_static("accept"));
+        Ok(response)
+    }
+
+    fn set_trailers(&mut self, trailers: HeaderMap) {
+        let body = core::mem::take(self.body_mut());
+        *self.body_mut() = body.with_trailers(trailers);
+    }
+
+    fn trailers(&mut self) -> impl Future<Output = Result<Option<HeaderMap>, BodyError>> + Send {
+        self.body_mut().trailers()
+    }
+
+    fn debug(&self) -> DebugResponse<'_> {
+        DebugResponse::new(self, false)
+    }
+
+    fn debug_full(&self) -> DebugResponse<'_> {
+        DebugResponse::new(self, true)
+    }
+
+    #[cfg(feature = "test-util")]
+    #[track_caller]
+    fn assert_status(&self, status: StatusCode) -> &Self {
+        assert!(
+            self.status() == status,
+            "expected status {status}, got {}: {:?}",
+            self.status(),
+            self.debug_full()
+        );
+        self
+    }
+
+    #[cfg(feature = "test-util")]
+    #[track_caller]
+    fn assert_header(&self, name: &str, expected: &str) -> &Self {
+        match self.headers().get(name) {
+            Some(value) => assert!(
+                value == expected,
+                "expected header `{name}` to be {expected:?}, got {value:?}"
+            ),
+            None => panic!("expected header `{name}` to be {expected:?}, but it is missing"),
+        }
+        self
+    }
+
+    #[cfg(all(feature = "test-util", feature = "json"))]
+    async fn assert_body_json<T>(&mut self, expected: T)
+    where
+        T: serde::de::DeserializeOwned + PartialEq + core::fmt::Debug + Send,
+    {
+        let actual: T = match self.body_mut().into_json().await {
+            Ok(actual) => actual,
+            Err(error) => panic!("expected a JSON body matching {expected:?}: {error}"),
+        };
+        assert_eq!(actual, expected, "unexpected JSON body");
+    }
+}
+
+/// Conversion of a value into a [`Response`], so that handlers can return whatever
+/// describes their answer best.
+///
+/// Bodies get the `Content-Type` of their MIME type: `text/plain; charset=utf-8` for
+/// strings and `application/octet-stream` for bytes. Errors of a `Result` are rendered
+/// with [`ResponseExt::from_error`].
+///
+/// # Examples
+///
+/// ```rust
+/// use http_kit::{IntoResponse, StatusCode};
+///
+/// let response = (StatusCode::CREATED, "saved").into_response();
+/// assert_eq!(response.status(), StatusCode::CREATED);
+/// assert_eq!(response.headers()["content-type"], "text/plain; charset=utf-8");
+/// ```
+pub trait IntoResponse {
+    /// Converts `self` into a response.
+    fn into_response(self) -> Response;
+}
+
+impl IntoResponse for Response {
+    fn into_response(self) -> Response {
+        self
+    }
+}
+
+impl IntoResponse for Body {
+    fn into_response(self) -> Response {
+        let mut response = Response::new(self);
+        response.sync_content_type();
+        response
+    }
+}
+
+/// An empty response with the status.
+impl IntoResponse for StatusCode {
+    fn into_response(self) -> Response {
+        let mut response = Response::new(Body::empty());
+        *response.status_mut() = self;
+        response
+    }
+}
+
+impl<T: Into<Body>> IntoResponse for (StatusCode, T) {
+    fn into_response(self) -> Response {
+        let mut response = self.1.into().into_response();
+        *response.status_mut() = self.0;
+        response
+    }
+}
+
+// A `text/plain` response with `status`, the shape of the rejections and error responses
+// built by the crate.
+pub(crate) fn text_response(status: StatusCode, text: impl Into<bytestr::ByteStr>) -> Response {
+    (status, Body::from_text(text)).into_response()
+}
+
+macro_rules! into_response_via_body {
+    ($($ty:ty),*) => {
+        $(
+            impl IntoResponse for $ty {
+                fn into_response(self) -> Response {
+                    Body::from(self).into_response()
+                }
+            }
+        )*
+    };
+}
+
+into_response_via_body!(
+    &'static str,
+    String,
+    bytestr::ByteStr,
+    &'static [u8],
+    Vec<u8>,
+    Bytes
+);
+
+impl<T: IntoResponse, E: HttpError> IntoResponse for Result<T, E> {
+    fn into_response(self) -> Response {
+        match self {
+            Ok(value) => value.into_response(),
+            Err(error) => Response::from_error(&error),
+        }
+    }
+}
+
+/// A value answered as JSON, with `Content-Type: application/json`.
+///
+/// A value that fails to serialize is answered with `500 Internal Server Error`.
+///
+/// # Examples
+///
+/// ```rust
+/// use http_kit::{IntoResponse, Json};
+///
+/// let response = Json(serde_json::json!({ "id": 1 })).into_response();
+/// assert_eq!(response.headers()["content-type"], "application/json");
+/// ```
+#[cfg(feature = "json")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Json<T>(pub T);
+
+#[cfg(feature = "json")]
+impl<T: serde::Serialize> IntoResponse for Json<T> {
+    fn into_response(self) -> Response {
+        match Body::from_json(&self.0) {
+            Ok(body) => body.into_response(),
+            Err(error) => Response::from_error(&*crate::Error::new(error).into_boxed_http_error()),
+        }
+    }
+}
+
+// Headers describing the content, which a 304 response has none of.
+const CONTENT_HEADERS: [HeaderName; 6] = [
+    header::CONTENT_TYPE,
+    header::CONTENT_LENGTH,
+    header::CONTENT_ENCODING,
+    header::CONTENT_LANGUAGE,
+    header::CONTENT_RANGE,
+    header::TRANSFER_ENCODING,
+];
+
+// Evaluates `If-None-Match`, or `If-Modified-Since` in its absence (RFC 9110,
+// section 13.2.2).
+fn is_not_modified(response: &Response, request: &Request) -> bool {
+    if let Some(condition) = request.if_none_match() {
+        let etag = response
+            .headers()
+            .get(header::ETAG)
+            .and_then(|value| value.to_str().ok())
+            .and_then(ETag::parse);
+        return match (condition, etag) {
+            (ETagMatch::Any, _) => true,
+            (condition, Some(etag)) => condition.matches_weak(&etag),
+            (_, None) => false,
+        };
+    }
+    #[cfg(feature = "std")]
+    if let (Some(since), Some(modified)) = (request.if_modified_since(), response.last_modified()) {
+        return modified <= since;
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_constructors() {
+        let cases = [
+            (Response::ok("done"), StatusCode::OK, true),
+            (Response::created("/items/7"), StatusCode::CREATED, true),
+            (Response::accepted(), StatusCode::ACCEPTED, false),
+            (
+                Response::bad_request("no name"),
+                StatusCode::BAD_REQUEST,
+                true,
+            ),
+            (Response::unauthorized(), StatusCode::UNAUTHORIZED, false),
+            (Response::forbidden(), StatusCode::FORBIDDEN, false),
+            (Response::not_found(), StatusCode::NOT_FOUND, false),
+            (
+                Response::unprocessable("bad email"),
+                StatusCode::UNPROCESSABLE_ENTITY,
+                true,
+            ),
+            (
+                Response::internal_error("oops"),
+                StatusCode::INTERNAL_SERVER_ERROR,
+                true,
+            ),
+        ];
+        for (response, status, typed) in cases {
+            assert_eq!(response.status(), status);
+            let content_type = response.headers().get(header::CONTENT_TYPE);
+            assert_eq!(content_type.is_some(), typed, "{status}");
+        }
+
+        for (mut response, status) in [
+            (Response::no_content(), StatusCode::NO_CONTENT),
+            (Response::not_modified(), StatusCode::NOT_MODIFIED),
+        ] {
+            assert_eq!(response.status(), status);
+            assert_eq!(response.body().len(), Some(0));
+            response.sync_content_length();
+            assert!(response.headers().is_empty(), "{status}");
+        }
+
+        // Emptied responses lose their stale content headers.
+        let mut response = Response::ok("deleted");
+        response.sync_content_length();
+        *response.body_mut() = Body::empty();
+        let response = response.with_status(StatusCode::NO_CONTENT);
+        assert!(!response.headers().contains_key(header::CONTENT_LENGTH));
+        assert!(!response.headers().contains_key(header::CONTENT_TYPE));
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "a 204 No Content response cannot have a body")]
+    fn no_content_with_a_body_is_a_bug() {
+        let _ = Response::ok("content").with_status(StatusCode::NO_CONTENT);
+    }
+
+    #[test]
+    fn fallible_constructors() {
+        let response = Response::try_new(404, "missing")
+            .unwrap()
+            .try_header("x-trace", "1")
+            .unwrap()
+            .try_mime("text/plain; charset=utf-8")
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(response.headers()["x-trace"], "1");
+        assert_eq!(
+            response.headers()[http::header::CONTENT_TYPE],
+            "text/plain; charset=utf-8"
+        );
+        assert_eq!(response.body().mime(), Some(&mime::TEXT_PLAIN_UTF_8));
+
+        let response = Response::try_new(200, alloc::vec![1, 2, 3]).unwrap();
+        assert_eq!(
+            response.headers()[http::header::CONTENT_TYPE],
+            "application/octet-stream"
+        );
+        let response = Response::try_new(204, Body::empty()).unwrap();
+        assert!(!response.headers().contains_key(http::header::CONTENT_TYPE));
+
+        assert!(Response::try_new(1000, Body::empty()).is_err());
+        assert!(Response::try_new(42, Body::empty()).is_err());
+        let ok = || Response::try_new(200, Body::empty()).unwrap();
+        assert!(ok().try_header("x-trace", "a\nb").is_err());
+        assert!(ok().try_header("", "value").is_err());
+        assert!(ok().try_mime("not a mime").is_err());
+    }
+
+    type Render = fn() -> Result<Body, &'static str>;
+
+    fn negotiate(accept: Option<&str>) -> Result<Response, &'static str> {
+        let mut request = Request::new(Body::empty());
+        if let Some(accept) = accept {
+            request
+                .headers_mut()
+                .insert(header::ACCEPT, accept.parse().unwrap());
+        }
+        let choices: [(Mime, Render); 3] = [
+            (mime::APPLICATION_JSON, || Ok(Body::from_bytes("{}"))),
+            (mime::TEXT_HTML, || Ok(Body::from_text("<p></p>"))),
+            (mime::IMAGE_PNG, || Err("no renderer")),
+        ];
+        Response::negotiated(&request, choices)
+    }
+
+    #[tokio::test]
+    async fn negotiation() {
+        // Without `Accept`, anything goes and the first choice wins.
+        let response = negotiate(None).unwrap();
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/json");
+        assert_eq!(response.headers()[header::VARY], "accept");
+
+        let response = negotiate(Some("text/*;q=0.3, text/html;q=0.7, */*;q=0.5")).unwrap();
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "text/html");
+        assert_eq!(response.body().mime(), Some(&mime::TEXT_HTML));
+        assert_eq!(response.into_body().into_string().await.unwrap(), "<p></p>");
+
+        // Only the chosen body is built.
+        assert_eq!(negotiate(Some("image/*")).unwrap_err(), "no renderer");
+
+        let response = negotiate(Some("text/csv, application/json;q=0")).unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_ACCEPTABLE);
+        assert_eq!(response.headers()[header::VARY], "accept");
+        assert_eq!(
+            response.into_body().into_string().await.unwrap(),
+            "Available media types: application/json, text/html, image/png"
+        );
+    }
+
+    fn conditional(headers: &[(HeaderName, &'static str)]) -> Request {
+        let mut request = Request::new(Body::empty());
+        for (name, value) in headers {
+            request
+                .headers_mut()
+                .append(name, HeaderValue::from_static(value));
+        }
+        request
+    }
+
+    fn tagged(tag: ETag) -> Response {
+        let mut response = Response::new(Body::from_text("hello")).etag(tag);
+        let headers = response.headers_mut();
+        headers.insert(header::CONTENT_LENGTH, HeaderValue::from_static("5"));
+        headers.insert(
+            header::CACHE_CONTROL,
+            HeaderValue::from_static("max-age=60"),
+        );
+        response
+    }
+
+    #[tokio::test]
+    async fn not_modified_uses_weak_comparison() {
+        let request = conditional(&[(header::IF_NONE_MATCH, r#""v0", W/"v1""#)]);
+        let response = tagged(ETag::strong("v1").unwrap()).not_modified_if(&request);
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(response.headers()[header::ETAG], r#""v1""#);
+        assert_eq!(response.headers()[header::CACHE_CONTROL], "max-age=60");
+        assert!(!response.headers().contains_key(header::CONTENT_TYPE));
+        assert!(!response.headers().contains_key(header::CONTENT_LENGTH));
+        assert!(response.into_body().into_bytes().await.unwrap().is_empty());
+
+        let response = tagged(ETag::weak("v1").unwrap()).not_modified_if(&request);
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+
+        let response = tagged(ETag::strong("v2").unwrap()).not_modified_if(&request);
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_LENGTH], "5");
+
+        // Unsafe methods and unsuccessful responses are left alone.
+        let mut post = conditional(&[(header::IF_NONE_MATCH, r#""v1""#)]);
+        *post.method_mut() = Method::POST;
+        let response = tagged(ETag::strong("v1").unwrap()).not_modified_if(&post);
+        assert_eq!(response.status(), StatusCode::OK);
+        let mut missing = tagged(ETag::strong("v1").unwrap());
+        *missing.status_mut() = StatusCode::NOT_FOUND;
+        let response = missing.not_modified_if(&request);
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn not_modified_wildcard() {
+        let request = conditional(&[(header::IF_NONE_MATCH, "*")]);
+        let response = Response::new(Body::empty()).not_modified_if(&request);
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        let response = tagged(ETag::weak("any").unwrap()).not_modified_if(&request);
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+
+        let request = conditional(&[]);
+        let response = tagged(ETag::strong("v1").unwrap()).not_modified_if(&request);
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn not_modified_since() {
+        use std::time::{Duration, UNIX_EPOCH};
+
+        let modified = UNIX_EPOCH + Duration::from_secs(784_111_777);
+        let response = || {
+            let mut response = Response::new(Body::from_text("hello"));
+            response.set_last_modified(modified);
+            response
+        };
+        assert_eq!(
+            response().headers()[header::LAST_MODIFIED],
+            "Sun, 06 Nov 1994 08:49:37 GMT"
+        );
+        assert_eq!(response().last_modified(), Some(modified));
+
+        let request = conditional(&[(header::IF_MODIFIED_SINCE, "Sun, 06 Nov 1994 08:49:37 GMT")]);
+        assert_eq!(request.if_modified_since(), Some(modified));
+        let not_modified = response().not_modified_if(&request);
+        assert_eq!(not_modified.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(
+            not_modified.headers()[header::LAST_MODIFIED],
+            "Sun, 06 Nov 1994 08:49:37 GMT"
+        );
+
+        let earlier = conditional(&[(header::IF_MODIFIED_SINCE, "Sunday, 06-Nov-94 08:49:36 GMT")]);
+        assert_eq!(
+            response().not_modified_if(&earlier).status(),
+            StatusCode::OK
+        );
+
+        // If-None-Match takes precedence over If-Modified-Since.
+        let both = conditional(&[
+            (header::IF_NONE_MATCH, r#""other""#),
+            (header::IF_MODIFIED_SINCE, "Sun, 06 Nov 1994 08:49:37 GMT"),
+        ]);
+        let response = response().etag(ETag::strong("v1").unwrap());
+        assert_eq!(response.not_modified_if(&both).status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn parts_round_trip() {
+        #[derive(Clone, Debug, PartialEq)]
+        struct Route(&'static str);
+
+        let mut original = Response::new(Body::from_text("created"));
+        *original.status_mut() = StatusCode::CREATED;
+        original
+            .headers_mut()
+            .insert(header::LOCATION, HeaderValue::from_static("/items/7"));
+        original.extensions_mut().insert(Route("/items"));
+
+        let (parts, body): (crate::ResponseParts, Body) = original.into_parts();
+        assert_eq!(parts.status, StatusCode::CREATED);
+        let rebuilt = Response::from_parts(parts, body);
+        assert_eq!(rebuilt.headers()[header::LOCATION], "/items/7");
+        assert_eq!(rebuilt.extension::<Route>(), Some(&Route("/items")));
+        assert_eq!(rebuilt.body().mime(), Some(&mime::TEXT_PLAIN_UTF_8));
+
+        let mapped = rebuilt.map(|_| Body::empty());
+        assert_eq!(mapped.status(), StatusCode::CREATED);
+        assert_eq!(mapped.body().len(), Some(0));
+    }
+
+    #[tokio::test]
+    async fn body_content_types() {
+        let mut response = Response::new(Body::empty());
+        response.set_body(Body::from_text("hi"));
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "text/plain; charset=utf-8"
+        );
+
+        // An explicit header wins in both directions.
+        response.headers_mut().insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("text/markdown"),
+        );
+        response.set_body(Body::from_bytes("# hi"));
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "text/markdown");
+        let body = response.take_body().unwrap();
+        assert_eq!(body.mime().unwrap().as_ref(), "text/markdown");
+        assert_eq!(body.into_string().await.unwrap(), "# hi");
+        assert!(matches!(response.take_body(), Err(BodyError::BodyFrozen)));
+
+        let mut response = Response::new(Body::from_bytes("raw"));
+        assert!(!response.headers().contains_key(header::CONTENT_TYPE));
+        response.sync_content_type();
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "application/octet-stream"
+        );
+        *response.body_mut() = Body::empty();
+        response.headers_mut().remove(header::CONTENT_TYPE);
+        response.sync_content_type();
+        assert!(!response.headers().contains_key(header::CONTENT_TYPE));
+    }
+
+    #[test]
+    fn sync_content_length_per_status() {
+        let framed = |status: StatusCode, body: Body| {
+            let mut response = Response::new(body);
+            *response.status_mut() = status;
+            let headers = response.headers_mut();
+            headers.insert(header::CONTENT_LENGTH, HeaderValue::from_static("99"));
+            headers.insert(
+                header::TRANSFER_ENCODING,
+                HeaderValue::from_static("chunked"),
+            );
+            response.sync_content_length();
+            response
+        };
+
+        for status in [
+            StatusCode::OK,
+            StatusCode::NOT_FOUND,
+            StatusCode::BAD_GATEWAY,
+        ] {
+            let response = framed(status, Body::from_text("hello"));
+            assert_eq!(response.headers()[header::CONTENT_LENGTH], "5");
+            assert!(!response.headers().contains_key(header::TRANSFER_ENCODING));
+        }
+
+        for status in [
+            StatusCode::CONTINUE,
+            StatusCode::SWITCHING_PROTOCOLS,
+            StatusCode::NO_CONTENT,
+            StatusCode::NOT_MODIFIED,
+        ] {
+            let response = framed(status, Body::from_text("hello"));
+            assert!(!response.headers().contains_key(header::CONTENT_LENGTH));
+            assert!(!response.headers().contains_key(header::TRANSFER_ENCODING));
+        }
+
+        let chunks: Vec<Result<bytes::Bytes, core::convert::Infallible>> = Vec::new();
+        let streaming = Body::from_stream(futures_lite::stream::iter(chunks));
+        let response = framed(StatusCode::OK, streaming);
+        assert!(!response.headers().contains_key(header::CONTENT_LENGTH));
+        assert_eq!(response.headers()[header::TRANSFER_ENCODING], "chunked");
+
+        // Nothing is added when the headers were absent and the body is empty.
+        let mut response = Response::new(Body::empty());
+        *response.status_mut() = StatusCode::NO_CONTENT;
+        response.sync_content_length();
+        assert!(response.headers().is_empty());
+    }
+
+    http_error!(Locked, StatusCode::LOCKED, "resource is locked");
+    http_error!(
+        Crashed,
+        StatusCode::INTERNAL_SERVER_ERROR,
+        "connection to db-7 refused"
+    );
+
+    async fn text(response: Response) -> String {
+        response
+            .into_body()
+            .into_string()
+            .await
+            .unwrap()
+            .to_string()
+    }
+
+    #[tokio::test]
+    async fn into_response_impls() {
+        let response = StatusCode::NO_CONTENT.into_response();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert_eq!(response.body().len(), Some(0));
+        assert!(!response.headers().contains_key(header::CONTENT_TYPE));
+
+        let response = "hello".into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "text/plain; charset=utf-8"
+        );
+        assert_eq!(text(response).await, "hello");
+
+        let response = String::from("owned").into_response();
+        assert_eq!(text(response).await, "owned");
+
+        let response = bytestr::ByteStr::from_static("shared").into_response();
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "text/plain; charset=utf-8"
+        );
+
+        for response in [
+            Bytes::from_static(b"\x00\x01").into_response(),
+            alloc::vec![1, 2, 3].into_response(),
+            b"\x00".as_slice().into_response(),
+        ] {
+            assert_eq!(
+                response.headers()[header::CONTENT_TYPE],
+                "application/octet-stream"
+            );
+        }
+
+        let response = (StatusCode::ACCEPTED, "queued").into_response();
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+        assert_eq!(text(response).await, "queued");
+
+        let response = Response::new(Body::empty()).into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn results_render_their_errors() {
+        let ok: Result<_, Locked> = Ok((StatusCode::CREATED, "made"));
+        let response = ok.into_response();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        assert_eq!(text(response).await, "made");
+
+        let err: Result<&'static str, Locked> = Err(Locked::new());
+        let response = err.into_response();
+        assert_eq!(response.status(), StatusCode::LOCKED);
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "text/plain; charset=utf-8"
+        );
+        assert_eq!(text(response).await, "resource is locked");
+
+        let err: Result<&'static str, Crashed> = Err(Crashed::new());
+        let response = err.into_response();
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(text(response).await, "Internal Server Error");
+    }
+
+    #[cfg(feature = "json")]
+    #[tokio::test]
+    async fn json_responses() {
+        let response = Json(serde_json::json!({ "id": 7 })).into_response();
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/json");
+        assert_eq!(text(response).await, r#"{"id":7}"#);
+
+        // Maps with non-string keys cannot be serialized.
+        let map = alloc::collections::BTreeMap::from([((1, 2), 3)]);
+        let response = Json(map).into_response();
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[cfg(feature = "encoding")]
+    #[tokio::test]
+    async fn text_follows_the_charset() {
+        let typed = |content_type: &'static str, body: &'static [u8]| {
+            let mut response = Response::new(Body::from_bytes(body));
+            response
+                .headers_mut()
+                .insert(header::CONTENT_TYPE, HeaderValue::from_static(content_type));
+            response
+        };
+
+        let latin1 = typed("text/html; charset=ISO-8859-1", b"<p>d\xe9j\xe0 vu</p>");
+        assert_eq!(latin1.text().await.unwrap(), "<p>d\u{e9}j\u{e0} vu</p>");
+
+        let bom = typed("text/plain", b"\xef\xbb\xbfhello");
+        assert_eq!(bom.text().await.unwrap(), "hello");
+        let untyped = Response::new(Body::from_bytes(&b"\xef\xbb\xbfhi"[..]));
+        assert_eq!(untyped.text().await.unwrap(), "hi");
+
+        let error = typed("text/plain; charset=x-klingon", b"abc")
+            .text()
+            .await
+            .unwrap_err();
+        assert!(matches!(&error, BodyError::UnknownCharset(label) if label == "x-klingon"));
+        let lossy = typed("text/plain; charset=x-klingon", b"abc\xff")
+            .text_lossy()
+            .await
+            .unwrap();
+        assert_eq!(lossy, "abc\u{fffd}");
+
+        // Invalid UTF-8 is an error unless lossy decoding is asked for.
+        let invalid = typed("text/plain; charset=utf-8", b"caf\xe9");
+        assert!(matches!(invalid.text().await, Err(BodyError::Utf8(_))));
+        let invalid = typed("text/plain; charset=utf-8", b"caf\xe9");
+        assert_eq!(invalid.text_lossy().await.unwrap(), "caf\u{fffd}");
+    }
+
+    #[cfg(feature = "json")]
+    #[tokio::test]
+    async fn json_bodies_check_the_content_type() {
+        let mut response = Response::new(Body::from_bytes("{\"title\":\"Gone\"}"));
+        let error = response.into_json::<serde_json::Value>().await.unwrap_err();
+        assert_eq!(error.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+        response.headers_mut().insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/problem+json"),
+        );
+        let problem: serde_json::Value = response.into_json().await.unwrap();
+        assert_eq!(problem["title"], "Gone");
+    }
+
+    #[cfg(feature = "csv")]
+    #[tokio::test]
+    async fn csv_downloads() {
+        let rows = futures_lite::stream::iter([Ok::<_, BodyError>(("a", 1)), Ok(("b,c", 2))]);
+        let response = Response::csv(rows, "r\u{e9}port \"q1\".csv");
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "text/csv; charset=utf-8"
+        );
+        assert_eq!(
+            response.headers()[header::CONTENT_DISPOSITION],
+            "attachment; filename=\"r_port _q1_.csv\"; filename*=UTF-8''r%C3%A9port%20%22q1%22.csv"
+        );
+        assert_eq!(text(response).await, "a,1\r\n\"b,c\",2\r\n");
+    }
+}
@@ -0,0 +1,403 @@
+//! Path-based dispatch of requests to endpoints.
+//!
+//! A [`Router`] maps path patterns to endpoints. Patterns are made of `/`-separated
+//! segments, each one of:
+//!
+//! - a literal, such as `users`, matching itself;
+//! - a parameter, such as `:id`, matching any non-empty segment;
+//! - a trailing wildcard, such as `*path`, matching the rest of the path, possibly empty.
+//!
+//! When several patterns match, the most specific one wins: literals before parameters
+//! before wildcards, compared segment by segment, so `/users/new` takes precedence over
+//! `/users/:id`. The captured values are percent-decoded and stored in the
+//! [`PathParams`] request extension, where [`RequestExt::param`](crate::RequestExt::param)
+//! reads them. Requests matching no pattern get `404 Not Found`.
+//!
+//! Dispatch on the method is done by the endpoint; [`MethodRouter`], built with [`get`],
+//! [`post`] and the like, answers other methods with `405 Method Not Allowed`.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use core::convert::Infallible;
+//! use http_kit::router::{get, Router};
+//! use http_kit::{endpoint::endpoint_fn, Body, Endpoint, Request, RequestExt, Response};
+//!
+//! let show = endpoint_fn(|request: &mut Request| {
+//!     let body = Body::from_text(format!("user {}", request.param("id").unwrap()));
+//!     async move { Ok::<_, Infallible>(Response::new(body)) }
+//! });
+//! let mut router = Router::new().at("/users/:id", get(show));
+//!
+//! # futures_lite::future::block_on(async {
+//! let mut request = Request::new(Body::empty());
+//! *request.uri_mut() = "/users/42".parse().unwrap();
+//! let response = router.respond(&mut request).await.unwrap();
+//! assert_eq!(response.into_body().into_string().await.unwrap(), "user 42");
+//! # });
+//! ```
+
+mod method;
+
+pub use method::{delete, get, patch, post, put, MethodRouter};
+
+use alloc::{
+    borrow::ToOwned,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use http::StatusCode;
+
+use crate::{
+    endpoint::AnyEndpoint, error::BoxHttpError, params::PathParams, percent,
+    response::text_response, Endpoint, Request, Response,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Literal(String),
+    Param(String),
+    Wildcard(String),
+}
+
+impl Segment {
+    // Lower ranks are more specific.
+    const fn rank(&self) -> u8 {
+        match self {
+            Self::Literal(_) => 0,
+            Self::Param(_) => 1,
+            Self::Wildcard(_) => 2,
+        }
+    }
+
+    // Whether both segments match the same paths, whatever their names.
+    fn overlaps(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Literal(a), Self::Literal(b)) => a == b,
+            _ => self.rank() == other.rank(),
+        }
+    }
+}
+
+fn parse(pattern: &str) -> Vec<Segment> {
+    let name = |name: &str| {
+        assert!(!name.is_empty(), "unnamed parameter in route `{pattern}`");
+        name.to_owned()
+    };
+    let segments: Vec<Segment> = pattern
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| {
+            if let Some(param) = segment.strip_prefix(':') {
+                Segment::Param(name(param))
+            } else if let Some(wildcard) = segment.strip_prefix('*') {
+                Segment::Wildcard(name(wildcard))
+            } else {
+                Segment::Literal(segment.to_owned())
+            }
+        })
+        .collect();
+    let wildcard = segments
+        .iter()
+        .position(|segment| matches!(segment, Segment::Wildcard(_)));
+    if let Some(position) = wildcard {
+        assert!(
+            position == segments.len() - 1,
+            "wildcard before the end of route `{pattern}`"
+        );
+    }
+    segments
+}
+
+// Matches `path` against `pattern`, reporting each captured value to `capture`.
+fn matches<'a, 'p>(
+    pattern: &'a [Segment],
+    path: &'p str,
+    mut capture: impl FnMut(&'a str, &'p str),
+) -> bool {
+    let mut rest = path;
+    for segment in pattern {
+        rest = rest.trim_start_matches('/');
+        let (part, tail) = rest.split_once('/').unwrap_or((rest, ""));
+        match segment {
+            Segment::Literal(literal) if part == literal => {}
+            Segment::Param(name) if !part.is_empty() => capture(name, part),
+            Segment::Wildcard(name) => {
+                capture(name, rest);
+                return true;
+            }
+            _ => return false,
+        }
+        rest = tail;
+    }
+    rest.trim_start_matches('/').is_empty()
+}
+
+#[derive(Debug)]
+struct Route {
+    pattern: Vec<Segment>,
+    endpoint: AnyEndpoint,
+}
+
+/// An [`Endpoint`] dispatching requests to the endpoint of the matching path pattern.
+///
+/// See the [module documentation](self) for the pattern syntax.
+#[derive(Debug, Default)]
+pub struct Router {
+    routes: Vec<Route>,
+}
+
+impl Router {
+    /// Creates a router without routes.
+    pub const fn new() -> Self {
+        Self { routes: Vec::new() }
+    }
+
+    /// Routes requests whose path matches `pattern` to `endpoint`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `pattern` has an unnamed parameter or wildcard, a wildcard before its
+    /// last segment, or matches the same paths as a pattern already routed.
+    #[must_use]
+    pub fn at(mut self, pattern: &str, endpoint: impl Endpoint + 'static) -> Self {
+        self.push(parse(pattern), AnyEndpoint::new(endpoint));
+        self
+    }
+
+    /// Routes the paths of `router` under `prefix`.
+    ///
+    /// The prefix may contain parameters, which are captured along with those of the
+    /// nested routes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `prefix` contains a wildcard, or under the conditions of
+    /// [`Router::at`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use http_kit::router::{get, Router};
+    /// # use http_kit::{endpoint::endpoint_fn, Body, Response};
+    /// # let list = endpoint_fn(|_| async { Ok::<_, core::convert::Infallible>(Response::new(Body::empty())) });
+    ///
+    /// let api = Router::new().at("/posts", get(list));
+    /// let router = Router::new().nest("/users/:user", api);
+    /// ```
+    #[must_use]
+    pub fn nest(mut self, prefix: &str, router: Router) -> Self {
+        let prefix = parse(prefix);
+        assert!(
+            !prefix
+                .iter()
+                .any(|segment| matches!(segment, Segment::Wildcard(_))),
+            "wildcard in a nesting prefix"
+        );
+        for route in router.routes {
+            let mut pattern = prefix.clone();
+            pattern.extend(route.pattern);
+            self.push(pattern, route.endpoint);
+        }
+        self
+    }
+
+    fn push(&mut self, pattern: Vec<Segment>, endpoint: AnyEndpoint) {
+        let conflict = self.routes.iter().any(|route| {
+            route.pattern.len() == pattern.len()
+                && route
+                    .pattern
+                    .iter()
+                    .zip(&pattern)
+                    .all(|(a, b)| a.overlaps(b))
+        });
+        assert!(
+            !conflict,
+            "route `{}` overlaps an existing route",
+            display(&pattern)
+        );
+        self.routes.push(Route { pattern, endpoint });
+    }
+
+    // Returns the index of the most specific route matching `path`.
+    fn find(&self, path: &str) -> Option<usize> {
+        self.routes
+            .iter()
+            .enumerate()
+            .filter(|(_, route)| matches(&route.pattern, path, |_, _| {}))
+            .min_by(|(_, a), (_, b)| {
+                let ranks = a.pattern.iter().map(Segment::rank);
+                ranks.cmp(b.pattern.iter().map(Segment::rank))
+            })
+            .map(|(index, _)| index)
+    }
+}
+
+fn display(pattern: &[Segment]) -> String {
+    if pattern.is_empty() {
+        return "/".to_string();
+    }
+    let mut display = String::new();
+    for segment in pattern {
+        display.push('/');
+        match segment {
+            Segment::Literal(literal) => display.push_str(literal),
+            Segment::Param(name) => {
+                display.push(':');
+                display.push_str(name);
+            }
+            Segment::Wildcard(name) => {
+                display.push('*');
+                display.push_str(name);
+            }
+        }
+    }
+    display
+}
+
+fn decode(value: &str) -> String {
+    String::from_utf8_lossy(&percent::decode_lenient(value.as_bytes(), false)).into_owned()
+}
+
+impl Endpoint for Router {
+    type Error = BoxHttpError;
+
+    async fn respond(&mut self, request: &mut Request) -> Result<Response, Self::Error> {
+        let path = request.uri().path();
+        let Some(index) = self.find(path) else {
+            return Ok(text_response(StatusCode::NOT_FOUND, "Not Found"));
+        };
+        let route = &mut self.routes[index];
+
+        let mut captured = Vec::new();
+        matches(&route.pattern, path, |name, value| {
+            captured.push((name, decode(value)));
+        });
+        if !captured.is_empty() {
+            let params = request.extensions_mut().get_or_insert_with(PathParams::new);
+            for (name, value) in captured {
+                params.insert(name, value);
+            }
+        }
+        route.endpoint.respond(request).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{endpoint::endpoint_fn, Body, RequestExt};
+    use alloc::format;
+    use core::convert::Infallible;
+
+    // Endpoint answering with its name and the path parameters it received.
+    fn named(name: &'static str) -> impl Endpoint + 'static {
+        endpoint_fn(move |request: &mut Request| {
+            let mut body = String::from(name);
+            if let Some(params) = request.extension::<PathParams>() {
+                for (param, value) in params.iter() {
+                    body.push_str(&format!(" {param}={value}"));
+                }
+            }
+            async move { Ok::<_, Infallible>(Response::new(Body::from_text(body))) }
+        })
+    }
+
+    async fn call(router: &mut Router, path: &str) -> (StatusCode, String) {
+        let mut request = Request::new(Body::empty());
+        *request.uri_mut() = path.parse().unwrap();
+        let response = router.respond(&mut request).await.unwrap();
+        let status = response.status();
+        (
+            status,
+            response
+                .into_body()
+                .into_string()
+                .await
+                .unwrap()
+                .to_string(),
+        )
+    }
+
+    #[tokio::test]
+    async fn most_specific_route_wins() {
+        let mut router = Router::new()
+            .at("/users/:id", named("show"))
+            .at("/users/new", named("new"))
+            .at("/users/:id/*rest", named("nested"))
+            .at("/*path", named("fallback"))
+            .at("/", named("root"));
+
+        for (path, expected) in [
+            ("/", "root"),
+            ("/users/new", "new"),
+            ("/users/new/", "new"),
+            ("/users/42", "show id=42"),
+            ("/users/caf%C3%A9", "show id=café"),
+            ("/users/42/posts/7", "nested id=42 rest=posts/7"),
+            ("/users/42/", "show id=42"),
+            ("/users", "fallback path=users"),
+            ("/static/css/site.css", "fallback path=static/css/site.css"),
+        ] {
+            assert_eq!(
+                call(&mut router, path).await,
+                (StatusCode::OK, expected.into())
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn unmatched_paths_are_not_found() {
+        let mut router = Router::new()
+            .at("/users/:id", named("show"))
+            .at("/static/*path", named("static"));
+        assert_eq!(call(&mut router, "/users").await.0, StatusCode::NOT_FOUND);
+        assert_eq!(
+            call(&mut router, "/users/1/2").await.0,
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(call(&mut router, "/").await.0, StatusCode::NOT_FOUND);
+        assert_eq!(
+            call(&mut router, "/static").await,
+            (StatusCode::OK, "static path=".into())
+        );
+    }
+
+    #[tokio::test]
+    async fn nested_routers_capture_prefix_parameters() {
+        let posts = Router::new()
+            .at("/posts", named("list"))
+            .at("/posts/:post", named("post"));
+        let mut router = Router::new()
+            .at("/health", named("health"))
+            .nest("/users/:user", posts);
+
+        assert_eq!(
+            call(&mut router, "/users/ada/posts/3").await.1,
+            "post user=ada post=3"
+        );
+        assert_eq!(
+            call(&mut router, "/users/ada/posts").await.1,
+            "list user=ada"
+        );
+        assert_eq!(
+            call(&mut router, "/users/ada").await.0,
+            StatusCode::NOT_FOUND
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "route `/users/:name` overlaps an existing route")]
+    fn overlapping_parameters_panic() {
+        let _ = Router::new()
+            .at("/users/:id", named("a"))
+            .at("/users/:name", named("b"));
+    }
+
+    #[test]
+    #[should_panic(expected = "wildcard before the end of route `/*path/edit`")]
+    fn inner_wildcards_panic() {
+        let _ = Router::new().at("/*path/edit", named("a"));
+    }
+}
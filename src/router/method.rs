@@ -0,0 +1,199 @@
+//! Dispatch on the request method.
+
+use alloc::{string::String, vec::Vec};
+
+use http::{header, HeaderValue, Method, StatusCode};
+
+use crate::{
+    endpoint::AnyEndpoint, error::BoxHttpError, response::text_response, Endpoint, Request,
+    Response,
+};
+
+/// An [`Endpoint`] dispatching requests to an endpoint per method.
+///
+/// `HEAD` requests are served by the `GET` endpoint unless one is set for `HEAD`.
+/// Other methods get `405 Method Not Allowed` with an `Allow` header listing the
+/// supported ones.
+///
+/// # Examples
+///
+/// ```rust
+/// use http_kit::router::get;
+/// # use http_kit::{endpoint::endpoint_fn, Body, Response};
+/// # let endpoint = || endpoint_fn(|_| async { Ok::<_, core::convert::Infallible>(Response::new(Body::empty())) });
+/// # let (list, create) = (endpoint(), endpoint());
+///
+/// let users = get(list).post(create);
+/// ```
+#[derive(Debug, Default)]
+pub struct MethodRouter {
+    endpoints: Vec<(Method, AnyEndpoint)>,
+}
+
+macro_rules! method_routes {
+    ($($name:ident => $method:ident,)*) => {
+        impl MethodRouter {
+            $(
+                #[doc = concat!("Routes `", stringify!($method), "` requests to `endpoint`.")]
+                ///
+                /// # Panics
+                ///
+                /// Panics if the method is already routed.
+                #[must_use]
+                pub fn $name(self, endpoint: impl Endpoint + 'static) -> Self {
+                    self.on(Method::$method, endpoint)
+                }
+            )*
+        }
+
+        $(
+            #[doc = concat!("Creates a [`MethodRouter`] routing `", stringify!($method), "` requests to `endpoint`.")]
+            pub fn $name(endpoint: impl Endpoint + 'static) -> MethodRouter {
+                MethodRouter::new().on(Method::$method, endpoint)
+            }
+        )*
+    };
+}
+
+method_routes! {
+    get => GET,
+    post => POST,
+    put => PUT,
+    delete => DELETE,
+    patch => PATCH,
+}
+
+impl MethodRouter {
+    /// Creates a router without methods, answering every request with `405`.
+    pub const fn new() -> Self {
+        Self {
+            endpoints: Vec::new(),
+        }
+    }
+
+    /// Routes requests with `method` to `endpoint`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the method is already routed.
+    #[must_use]
+    pub fn on(mut self, method: Method, endpoint: impl Endpoint + 'static) -> Self {
+        assert!(
+            self.position(&method).is_none(),
+            "method {method} is already routed"
+        );
+        self.endpoints.push((method, AnyEndpoint::new(endpoint)));
+        self
+    }
+
+    fn position(&self, method: &Method) -> Option<usize> {
+        self.endpoints
+            .iter()
+            .position(|(existing, _)| existing == method)
+    }
+
+    // Value of the `Allow` header, in the order the methods were routed.
+    fn allow(&self) -> HeaderValue {
+        let mut allow = String::new();
+        let implicit_head = self.position(&Method::HEAD).is_none();
+        for (method, _) in &self.endpoints {
+            if !allow.is_empty() {
+                allow.push_str(", ");
+            }
+            allow.push_str(method.as_str());
+            if *method == Method::GET && implicit_head {
+                allow.push_str(", HEAD");
+            }
+        }
+        HeaderValue::try_from(allow).expect("method names are valid header values")
+    }
+}
+
+impl Endpoint for MethodRouter {
+    type Error = BoxHttpError;
+
+    async fn respond(&mut self, request: &mut Request) -> Result<Response, Self::Error> {
+        let method = request.method();
+        let position = self.position(method).or_else(|| {
+            (*method == Method::HEAD)
+                .then(|| self.position(&Method::GET))
+                .flatten()
+        });
+        match position {
+            Some(index) => self.endpoints[index].1.respond(request).await,
+            None => {
+                let mut response =
+                    text_response(StatusCode::METHOD_NOT_ALLOWED, "Method Not Allowed");
+                response.headers_mut().insert(header::ALLOW, self.allow());
+                Ok(response)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{endpoint::endpoint_fn, router::Router, Body};
+    use core::convert::Infallible;
+
+    fn named(name: &'static str) -> impl Endpoint + 'static {
+        endpoint_fn(move |_: &mut Request| async move {
+            Ok::<_, Infallible>(Response::new(Body::from_text(name)))
+        })
+    }
+
+    async fn call(router: &mut Router, method: Method, path: &str) -> Response {
+        let mut request = Request::new(Body::empty());
+        *request.method_mut() = method;
+        *request.uri_mut() = path.parse().unwrap();
+        router.respond(&mut request).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn methods_are_dispatched() {
+        let mut router = Router::new()
+            .at("/users", get(named("list")).post(named("create")))
+            .at("/users/:id", get(named("show")).delete(named("remove")));
+
+        let response = call(&mut router, Method::POST, "/users").await;
+        assert_eq!(response.into_body().into_string().await.unwrap(), "create");
+        let response = call(&mut router, Method::HEAD, "/users/1").await;
+        assert_eq!(response.into_body().into_string().await.unwrap(), "show");
+
+        let response = call(&mut router, Method::PUT, "/users/1").await;
+        assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(response.headers()[header::ALLOW], "GET, HEAD, DELETE");
+
+        let response = call(&mut router, Method::DELETE, "/posts").await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn explicit_head_is_listed_once() {
+        let methods = MethodRouter::new()
+            .on(Method::HEAD, named("head"))
+            .get(named("get"))
+            .on(Method::OPTIONS, named("options"));
+        let mut router = Router::new().at("/", methods);
+
+        let response = call(&mut router, Method::HEAD, "/").await;
+        assert_eq!(response.into_body().into_string().await.unwrap(), "head");
+        let response = call(&mut router, Method::POST, "/").await;
+        assert_eq!(response.headers()[header::ALLOW], "HEAD, GET, OPTIONS");
+
+        let response = call(
+            &mut Router::new().at("/", MethodRouter::new()),
+            Method::GET,
+            "/",
+        )
+        .await;
+        assert_eq!(response.headers()[header::ALLOW], "");
+    }
+
+    #[test]
+    #[should_panic(expected = "method GET is already routed")]
+    fn duplicate_methods_panic() {
+        let _ = get(named("a")).get(named("b"));
+    }
+}
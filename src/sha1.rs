@@ -0,0 +1,127 @@
+//! Minimal SHA-1 (RFC 3174) used by the WebSocket handshake.
+//!
+//! SHA-1 is broken as a collision-resistant hash; RFC 6455 only uses it to prove that
+//! the server understood the handshake, so it must not be used for anything else.
+
+const INITIAL_STATE: [u32; 5] = [
+    0x6745_2301,
+    0xEFCD_AB89,
+    0x98BA_DCFE,
+    0x1032_5476,
+    0xC3D2_E1F0,
+];
+
+/// Returns the SHA-1 digest of the concatenation of `parts`.
+pub(crate) fn digest(parts: &[&[u8]]) -> [u8; 20] {
+    let mut state = INITIAL_STATE;
+    let mut block = [0u8; 64];
+    let mut filled = 0;
+    let mut length: u64 = 0;
+
+    for part in parts {
+        length += part.len() as u64;
+        for &byte in *part {
+            block[filled] = byte;
+            filled += 1;
+            if filled == 64 {
+                compress(&mut state, &block);
+                filled = 0;
+            }
+        }
+    }
+
+    // Padding: a one bit, zeros, then the message length in bits.
+    block[filled] = 0x80;
+    block[filled + 1..].fill(0);
+    if filled >= 56 {
+        compress(&mut state, &block);
+        block.fill(0);
+    }
+    block[56..].copy_from_slice(&(length * 8).to_be_bytes());
+    compress(&mut state, &block);
+
+    let mut output = [0u8; 20];
+    for (chunk, word) in output.chunks_exact_mut(4).zip(state) {
+        chunk.copy_from_slice(&word.to_be_bytes());
+    }
+    output
+}
+
+fn compress(state: &mut [u32; 5], block: &[u8; 64]) {
+    let mut schedule = [0u32; 80];
+    for (word, bytes) in schedule.iter_mut().zip(block.chunks_exact(4)) {
+        *word = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    }
+    for i in 16..80 {
+        schedule[i] = (schedule[i - 3] ^ schedule[i - 8] ^ schedule[i - 14] ^ schedule[i - 16])
+            .rotate_left(1);
+    }
+
+    let [mut a, mut b, mut c, mut d, mut e] = *state;
+    for (i, word) in schedule.iter().enumerate() {
+        let (f, k) = match i {
+            0..=19 => ((b & c) | (!b & d), 0x5A82_7999),
+            20..=39 => (b ^ c ^ d, 0x6ED9_EBA1),
+            40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1B_BCDC),
+            _ => (b ^ c ^ d, 0xCA62_C1D6),
+        };
+        let temp = a
+            .rotate_left(5)
+            .wrapping_add(f)
+            .wrapping_add(e)
+            .wrapping_add(k)
+            .wrapping_add(*word);
+        e = d;
+        d = c;
+        c = b.rotate_left(30);
+        b = a;
+        a = temp;
+    }
+    for (word, value) in state.iter_mut().zip([a, b, c, d, e]) {
+        *word = word.wrapping_add(value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::{format, string::String, vec};
+
+    fn hex(digest: [u8; 20]) -> String {
+        digest.iter().map(|byte| format!("{byte:02x}")).collect()
+    }
+
+    #[test]
+    fn fips_180_vectors() {
+        let vectors: [(&[u8], &str); 4] = [
+            (b"", "da39a3ee5e6b4b0d3255bfef95601890afd80709"),
+            (b"abc", "a9993e364706816aba3e25717850c26c9cd0d89d"),
+            (
+                b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq",
+                "84983e441c3bd26ebaae4aa1f95129e5e54670f1",
+            ),
+            (
+                b"The quick brown fox jumps over the lazy dog",
+                "2fd4e1c67a2d28fced849ee1bb76e7391b93eb12",
+            ),
+        ];
+        for (input, expected) in vectors {
+            assert_eq!(hex(digest(&[input])), expected);
+        }
+
+        let million = vec![b'a'; 1_000_000];
+        assert_eq!(
+            hex(digest(&[&million])),
+            "34aa973cd4c4daa4f61eeb2bdbad27316534016f"
+        );
+    }
+
+    #[test]
+    fn parts_are_concatenated() {
+        let whole = digest(&[b"The quick brown fox jumps over the lazy dog"]);
+        assert_eq!(
+            digest(&[b"The quick brown ", b"", b"fox jumps over the lazy dog"]),
+            whole
+        );
+    }
+}
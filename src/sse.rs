@@ -22,24 +22,35 @@ use alloc::vec::Vec;
 use bytes::Bytes;
 use core::error::Error as StdError;
 use core::fmt;
+#[cfg(feature = "json")]
+use core::marker::PhantomData;
 use core::pin::Pin;
 use core::task::{Context, Poll};
-use futures_lite::{Stream, StreamExt};
+use futures_lite::Stream;
 use http_body::Frame;
-use http_body_util::StreamBody;
 use pin_project_lite::pin_project;
 #[cfg(feature = "json")]
-use serde::Serialize;
+use serde::{de::DeserializeOwned, Serialize};
 #[cfg(feature = "json")]
 use serde_json::{self, to_string};
 
-use crate::Body;
+use crate::{Body, BodyError};
+
+#[cfg(feature = "std")]
+mod event_source;
+#[cfg(feature = "std")]
+pub use event_source::{EventSource, EventSourceError, DEFAULT_RETRY};
 
 /// Represents a Server-Sent Event that can be sent to clients.
-#[derive(Debug)]
+///
+/// Every field is optional: an event may carry only an `id` or an `event` type, and
+/// [`Event::comment`] builds a comment, which clients ignore but which keeps idle
+/// connections open. `Event::default()` is an event without any field.
+#[derive(Debug, Default)]
 pub struct Event {
+    comment: Option<String>,
     event: Option<String>,
-    data: String,
+    data: Option<String>,
     id: Option<String>,
     retry: Option<u64>,
 }
@@ -75,6 +86,25 @@ impl Event {
         Ok(Self::from_data(to_string(data)?))
     }
 
+    /// Creates a new SSE event with JSON data, like [`Event::new`], which already
+    /// returns serialization errors rather than panicking.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(feature = "json")]
+    /// # {
+    /// use http_kit::sse::Event;
+    ///
+    /// let event = Event::try_new(&[1, 2, 3]).unwrap();
+    /// assert_eq!(event.data::<Vec<u8>>().unwrap(), [1, 2, 3]);
+    /// # }
+    /// ```
+    #[cfg(feature = "json")]
+    pub fn try_new<T: Serialize>(data: &T) -> Result<Self, serde_json::Error> {
+        Self::new(data)
+    }
+
     /// Creates a new SSE event from string data.
     ///
     /// # Examples
@@ -86,10 +116,37 @@ impl Event {
     /// ```
     pub fn from_data<T: Into<String>>(data: T) -> Self {
         Self {
-            event: None,
-            data: data.into(),
-            id: None,
-            retry: None,
+            data: Some(data.into()),
+            ..Self::default()
+        }
+    }
+
+    /// Creates a comment, encoded as lines starting with a colon.
+    ///
+    /// Comments are ignored by clients; [`SseStream`] only yields them with
+    /// [`include_comments`](SseStream::include_comments).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use http_kit::sse::Event;
+    ///
+    /// let comment = Event::comment("keep-alive");
+    /// assert_eq!(comment.encode(), ": keep-alive\n\n");
+    /// ```
+    pub fn comment<T: Into<String>>(text: T) -> Self {
+        Self {
+            comment: Some(text.into()),
+            ..Self::default()
+        }
+    }
+
+    /// Returns the comment text if set.
+    pub const fn comment_text(&self) -> Option<&str> {
+        if let Some(comment) = self.comment.as_ref() {
+            Some(comment.as_str())
+        } else {
+            None
         }
     }
 
@@ -116,9 +173,18 @@ impl Event {
         self.retry
     }
 
-    /// Returns the raw text data of the event.
+    /// Returns the raw text data of the event, empty if it has no `data` field.
     pub const fn text_data(&self) -> &str {
-        self.data.as_str()
+        if let Some(data) = self.data.as_ref() {
+            data.as_str()
+        } else {
+            ""
+        }
+    }
+
+    /// Returns whether the event has a `data` field, possibly empty.
+    pub const fn has_data(&self) -> bool {
+        self.data.is_some()
     }
 
     /// Deserializes the event data as JSON.
@@ -181,21 +247,37 @@ impl Event {
     /// Encodes the event as an SSE-formatted string.
     ///
     /// The output follows the SSE specification format:
+    /// - `: <comment>` (optional)
     /// - `event: <type>` (optional)
-    /// - `data: <data>`
+    /// - `data: <data>` (optional)
     /// - `id: <id>` (optional)
     /// - `retry: <milliseconds>` (optional)
     /// - Empty line to end the event
+    ///
+    /// Comments and data spanning several lines are written as one field per line, so
+    /// that a parser joins the data back with `\n`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use http_kit::sse::Event;
+    ///
+    /// let event = Event::from_data("first\nsecond").with_id("1");
+    /// assert_eq!(event.encode(), "data: first\ndata: second\nid: 1\n\n");
+    /// ```
     pub fn encode(&self) -> String {
         let mut encoded = String::new();
+        if let Some(comment) = self.comment_text() {
+            push_lines(&mut encoded, ":", comment);
+        }
         if let Some(event) = self.event() {
             encoded.push_str("event: ");
             encoded.push_str(event);
             encoded.push('\n');
         }
-        encoded.push_str("data: ");
-        encoded.push_str(&self.data);
-        encoded.push('\n');
+        if let Some(data) = &self.data {
+            push_lines(&mut encoded, "data:", data);
+        }
 
         if let Some(id) = self.id() {
             encoded.push_str("id: ");
@@ -213,16 +295,185 @@ impl Event {
     }
 }
 
-pub(crate) fn into_body<S, E>(stream: S) -> impl http_body::Body<Data = Bytes, Error = E> + Send
+// Writes `value` as one `<field> <line>` line per line it contains.
+fn push_lines(encoded: &mut String, field: &str, value: &str) {
+    let mut rest = value;
+    loop {
+        let end = rest.find(['\r', '\n']).unwrap_or(rest.len());
+        encoded.push_str(field);
+        encoded.push(' ');
+        encoded.push_str(&rest[..end]);
+        encoded.push('\n');
+        if end == rest.len() {
+            break;
+        }
+        rest = rest[end..].strip_prefix("\r\n").unwrap_or(&rest[end + 1..]);
+    }
+}
+
+pin_project! {
+    /// An `http_body::Body` sending Server-Sent Events.
+    ///
+    /// Each event is encoded into exactly one data frame, so adapters that forward
+    /// frames keep event boundaries intact. With [`keep_alive`](Self::keep_alive), a
+    /// `: keep-alive` comment frame is sent whenever the stream stays quiet for the
+    /// configured duration, which stops proxies from closing idle connections.
+    ///
+    /// [`Body::from_sse`] wraps an `SseBody` without keep-alive; convert a configured
+    /// one with `Body::from`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(feature = "std")]
+    /// # {
+    /// use core::time::Duration;
+    /// use futures_lite::stream;
+    /// use http_kit::{sse::{Event, SseBody}, Body};
+    ///
+    /// let events = stream::iter(vec![Ok::<_, std::io::Error>(Event::from_data("tick"))]);
+    /// let body = Body::from(SseBody::new(events).keep_alive(Duration::from_secs(15)));
+    /// assert_eq!(body.mime(), Some(&mime::TEXT_EVENT_STREAM));
+    /// # }
+    /// ```
+    pub struct SseBody<S> {
+        #[pin]
+        stream: S,
+        keep_alive: Option<KeepAlive>,
+        done: bool,
+    }
+}
+
+impl<S> fmt::Debug for SseBody<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SseBody")
+            .field("keep_alive", &self.keep_alive.is_some())
+            .field("done", &self.done)
+            .finish_non_exhaustive()
+    }
+}
+
+// Comment frame sent when the stream has been idle for the keep-alive interval.
+const KEEP_ALIVE: &[u8] = b": keep-alive\n\n";
+
+#[cfg(feature = "std")]
+struct KeepAlive {
+    interval: core::time::Duration,
+    timer: futures_timer::Delay,
+}
+
+#[cfg(feature = "std")]
+impl KeepAlive {
+    fn reset(&mut self) {
+        self.timer.reset(self.interval);
+    }
+
+    fn poll_expired(&mut self, cx: &mut Context<'_>) -> bool {
+        use core::future::Future;
+
+        if Pin::new(&mut self.timer).poll(cx).is_ready() {
+            self.reset();
+            // Register the new deadline with the waker.
+            let _ = Pin::new(&mut self.timer).poll(cx);
+            true
+        } else {
+            false
+        }
+    }
+}
+
+// Without a timer, keep-alive cannot be configured and the type is never constructed.
+#[cfg(not(feature = "std"))]
+enum KeepAlive {}
+
+#[cfg(not(feature = "std"))]
+impl KeepAlive {
+    fn reset(&mut self) {
+        match *self {}
+    }
+
+    fn poll_expired(&mut self, _cx: &mut Context<'_>) -> bool {
+        match *self {}
+    }
+}
+
+impl<S> SseBody<S> {
+    /// Creates a body sending the events of `stream`.
+    pub fn new(stream: S) -> Self {
+        Self {
+            stream,
+            keep_alive: None,
+            done: false,
+        }
+    }
+
+    /// Sends a `: keep-alive` comment whenever no event was produced for `interval`.
+    #[cfg(feature = "std")]
+    #[must_use]
+    pub fn keep_alive(mut self, interval: core::time::Duration) -> Self {
+        self.keep_alive = Some(KeepAlive {
+            interval,
+            timer: futures_timer::Delay::new(interval),
+        });
+        self
+    }
+}
+
+impl<S, E> http_body::Body for SseBody<S>
+where
+    S: Stream<Item = Result<Event, E>>,
+{
+    type Data = Bytes;
+    type Error = E;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let this = self.project();
+        if *this.done {
+            return Poll::Ready(None);
+        }
+        match this.stream.poll_next(cx) {
+            Poll::Ready(Some(Ok(event))) => {
+                stat!(sse_events);
+                if let Some(keep_alive) = this.keep_alive {
+                    keep_alive.reset();
+                }
+                Poll::Ready(Some(Ok(Frame::data(Bytes::from(event.encode())))))
+            }
+            Poll::Ready(Some(Err(error))) => Poll::Ready(Some(Err(error))),
+            Poll::Ready(None) => {
+                *this.done = true;
+                Poll::Ready(None)
+            }
+            Poll::Pending => {
+                let expired = this
+                    .keep_alive
+                    .as_mut()
+                    .is_some_and(|keep_alive| keep_alive.poll_expired(cx));
+                if expired {
+                    Poll::Ready(Some(Ok(Frame::data(Bytes::from_static(KEEP_ALIVE)))))
+                } else {
+                    Poll::Pending
+                }
+            }
+        }
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.done
+    }
+}
+
+impl<S, E> From<SseBody<S>> for Body
 where
-    S: Stream<Item = Result<Event, E>> + Send,
-    E: Send,
+    S: Stream<Item = Result<Event, E>> + Send + Sync + 'static,
+    E: Into<BodyError> + Send + Sync + 'static,
 {
-    StreamBody::new(
-        stream.map(|result| {
-            result.map(|event| Frame::data(Bytes::from(event.encode().into_bytes())))
-        }),
-    )
+    fn from(body: SseBody<S>) -> Self {
+        Body::new(body).with_mime(mime::TEXT_EVENT_STREAM)
+    }
 }
 
 pin_project! {
@@ -233,8 +484,77 @@ pin_project! {
     pub struct SseStream{
         #[pin]
         body:Body,
-        buffer: Vec<u8>,
+        lines: LineBuffer,
         partial_event: PartialEvent,
+        reconnect: Reconnect,
+        max_event_size: usize,
+        include_comments: bool,
+        done: bool,
+    }
+}
+
+/// Default limit on the size of a single event parsed by [`SseStream`], 1 MiB.
+pub const DEFAULT_MAX_EVENT_SIZE: usize = 1 << 20;
+
+// State a client needs to resume the stream after a disconnection.
+#[derive(Default, Debug)]
+struct Reconnect {
+    last_event_id: Option<String>,
+    retry: Option<u64>,
+}
+
+// Received bytes, split into lines as they complete.
+//
+// Consumed lines are only dropped from the front of `buf` once they make up half of
+// it, and the search for a line terminator resumes where the previous one stopped, so
+// an event arriving in many small chunks is scanned once.
+#[derive(Default, Debug)]
+struct LineBuffer {
+    buf: Vec<u8>,
+    // Start of the first line not returned yet.
+    start: usize,
+    // Bytes before this offset hold no line terminator.
+    scanned: usize,
+    // The last line ended with a CR, so a LF right after it belongs to the same CRLF.
+    skip_lf: bool,
+}
+
+impl LineBuffer {
+    // Length of the line being received.
+    fn pending(&self) -> usize {
+        self.buf.len() - self.start
+    }
+
+    fn extend(&mut self, chunk: &[u8]) {
+        if self.start > 0 && self.start >= self.buf.len() / 2 {
+            self.buf.drain(..self.start);
+            self.scanned -= self.start;
+            self.start = 0;
+        }
+        self.buf.extend_from_slice(chunk);
+    }
+
+    // Returns the next complete line, without its terminator: CRLF, CR or LF.
+    fn next_line(&mut self) -> Option<&[u8]> {
+        if self.skip_lf && self.start < self.buf.len() {
+            self.skip_lf = false;
+            if self.buf[self.start] == b'\n' {
+                self.start += 1;
+            }
+        }
+        let from = self.scanned.max(self.start);
+        let Some(offset) = self.buf[from..]
+            .iter()
+            .position(|&byte| byte == b'\n' || byte == b'\r')
+        else {
+            self.scanned = self.buf.len();
+            return None;
+        };
+        let end = from + offset;
+        self.skip_lf = self.buf[end] == b'\r';
+        let start = core::mem::replace(&mut self.start, end + 1);
+        self.scanned = self.start;
+        Some(&self.buf[start..end])
     }
 }
 
@@ -244,6 +564,21 @@ struct PartialEvent {
     event: Option<String>,
     data: Vec<String>,
     retry: Option<u64>,
+    // Only collected when the stream includes comments.
+    comments: Vec<String>,
+    // Bytes of the lines received since the last blank line.
+    size: usize,
+}
+
+impl PartialEvent {
+    // Whether the block received so far dispatches an event.
+    fn has_fields(&self) -> bool {
+        self.id.is_some()
+            || self.event.is_some()
+            || self.retry.is_some()
+            || !self.data.is_empty()
+            || !self.comments.is_empty()
+    }
 }
 
 impl SseStream {
@@ -251,24 +586,199 @@ impl SseStream {
     ///
     /// This function wraps the provided body in an SSE stream parser that can
     /// asynchronously parse Server-Sent Events from the body data.
+    ///
+    /// Events are limited to [`DEFAULT_MAX_EVENT_SIZE`] bytes, see
+    /// [`SseStream::with_max_event_size`].
     pub fn new(body: Body) -> Self {
+        Self::with_max_event_size(body, DEFAULT_MAX_EVENT_SIZE)
+    }
+
+    /// Creates a new SSE stream from an HTTP body, accepting events of at most `limit`
+    /// bytes.
+    ///
+    /// The size of an event counts every line of its block, comments included. Once an
+    /// event grows beyond `limit`, the stream yields [`ParseError::EventTooLarge`] and
+    /// ends, so that a server that never finishes an event cannot exhaust memory.
+    pub fn with_max_event_size(body: Body, limit: usize) -> Self {
         Self {
             body,
-            buffer: Vec::new(),
+            lines: LineBuffer::default(),
             partial_event: PartialEvent::default(),
+            reconnect: Reconnect::default(),
+            max_event_size: limit,
+            include_comments: false,
+            done: false,
+        }
+    }
+
+    /// Sets whether comments are yielded as events, which they are not by default.
+    ///
+    /// The comments of a block are joined with `\n` into the
+    /// [`comment_text`](Event::comment_text) of its event, so a block made only of
+    /// comments yields an event built by [`Event::comment`].
+    pub fn include_comments(mut self, include: bool) -> Self {
+        self.include_comments = include;
+        self
+    }
+
+    /// Returns the id of the last event received, to send as `Last-Event-ID` when
+    /// reconnecting.
+    ///
+    /// The id is updated whenever an event is dispatched with an `id` field, and persists
+    /// across events without one. An empty `id` field resets it to `None`.
+    pub fn last_event_id(&self) -> Option<&str> {
+        self.reconnect.last_event_id.as_deref()
+    }
+
+    /// Returns the latest reconnection delay in milliseconds announced by a `retry`
+    /// field.
+    pub const fn retry(&self) -> Option<u64> {
+        self.reconnect.retry
+    }
+
+    /// Converts the stream into a stream of events whose data is deserialized from
+    /// JSON, see [`JsonEventStream`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use futures_lite::StreamExt;
+    /// use http_kit::{sse::SseStream, Body};
+    ///
+    /// # async fn example() {
+    /// let body = Body::from(": ping\n\nevent: tick\ndata: 1\n\ndata: {\"n\":2}\nid: 7\n\n");
+    /// let mut events = SseStream::new(body)
+    ///     .json_events::<serde_json::Value>()
+    ///     .only_event("message");
+    /// let (meta, value) = events.next().await.unwrap().unwrap();
+    /// assert_eq!(meta.id.as_deref(), Some("7"));
+    /// assert_eq!(value["n"], 2);
+    /// assert!(events.next().await.is_none());
+    /// # }
+    /// ```
+    #[cfg(feature = "json")]
+    pub fn json_events<T: DeserializeOwned>(self) -> JsonEventStream<T> {
+        JsonEventStream {
+            stream: self,
+            only_event: None,
+            _marker: PhantomData,
+        }
+    }
+}
+
+/// The fields of an event other than its data, yielded by [`JsonEventStream`].
+#[cfg(feature = "json")]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EventMeta {
+    /// The `id` field of the event.
+    pub id: Option<String>,
+    /// The `event` field of the event, its type.
+    pub event: Option<String>,
+    /// The `retry` field of the event, in milliseconds.
+    pub retry: Option<u64>,
+}
+
+/// A stream of events whose data is JSON, created by [`SseStream::json_events`].
+///
+/// Each item pairs the [`EventMeta`] of an event with its data deserialized as `T`.
+/// Comments, keep-alive frames and events without a `data` field are skipped. Data that
+/// fails to deserialize yields [`ParseError::Json`], which keeps the raw data, and the
+/// stream moves on to the next event. The errors of the underlying [`SseStream`] are
+/// forwarded.
+#[cfg(feature = "json")]
+pub struct JsonEventStream<T> {
+    stream: SseStream,
+    only_event: Option<String>,
+    _marker: PhantomData<fn() -> T>,
+}
+
+#[cfg(feature = "json")]
+impl<T> Unpin for JsonEventStream<T> {}
+
+#[cfg(feature = "json")]
+impl<T> fmt::Debug for JsonEventStream<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JsonEventStream")
+            .field("only_event", &self.only_event)
+            .field("last_event_id", &self.stream.last_event_id())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "json")]
+impl<T> JsonEventStream<T> {
+    /// Only yields events of the type `event`, skipping the others before their data is
+    /// deserialized.
+    ///
+    /// Events without an `event` field have the type `message`, as in browsers.
+    pub fn only_event(mut self, event: impl Into<String>) -> Self {
+        self.only_event = Some(event.into());
+        self
+    }
+
+    /// Returns the underlying stream, for its [`last_event_id`](SseStream::last_event_id)
+    /// and [`retry`](SseStream::retry).
+    pub const fn get_ref(&self) -> &SseStream {
+        &self.stream
+    }
+}
+
+#[cfg(feature = "json")]
+impl<T: DeserializeOwned> Stream for JsonEventStream<T> {
+    type Item = Result<(EventMeta, T), ParseError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            let event = match futures_lite::ready!(Pin::new(&mut self.stream).poll_next(cx)) {
+                Some(Ok(event)) => event,
+                Some(Err(error)) => return Poll::Ready(Some(Err(error))),
+                None => return Poll::Ready(None),
+            };
+            if !event.has_data() {
+                continue;
+            }
+            if let Some(only) = &self.only_event {
+                if event.event().unwrap_or("message") != only {
+                    continue;
+                }
+            }
+            let Event {
+                event,
+                data,
+                id,
+                retry,
+                ..
+            } = event;
+            let data = data.unwrap_or_default();
+            let meta = EventMeta { id, event, retry };
+            return Poll::Ready(Some(match serde_json::from_str(&data) {
+                Ok(value) => Ok((meta, value)),
+                Err(error) => Err(ParseError::Json { data, error }),
+            }));
         }
     }
 }
 
 /// Errors that can occur while parsing Server-Sent Events.
-#[derive(Debug, Clone)]
+#[derive(Debug)]
+#[non_exhaustive]
 pub enum ParseError {
     /// The underlying body stream encountered an error
-    BodyError(String),
+    BodyError(BodyError),
     /// Invalid UTF-8 encoding in the SSE data
     InvalidUtf8,
     /// Invalid retry value (not a valid number)
     InvalidRetryValue,
+    /// An event is larger than the limit of the stream, in bytes.
+    EventTooLarge(usize),
+    /// The data of an event read by [`JsonEventStream`] could not be deserialized.
+    #[cfg(feature = "json")]
+    Json {
+        /// The raw data of the event.
+        data: String,
+        /// The deserialization error.
+        error: serde_json::Error,
+    },
 }
 
 impl fmt::Display for ParseError {
@@ -277,35 +787,71 @@ impl fmt::Display for ParseError {
             ParseError::BodyError(e) => write!(f, "Body stream error: {}", e),
             ParseError::InvalidUtf8 => write!(f, "Invalid UTF-8 in SSE data"),
             ParseError::InvalidRetryValue => write!(f, "Invalid retry value in SSE event"),
+            ParseError::EventTooLarge(limit) => {
+                write!(f, "SSE event exceeds the limit of {limit} bytes")
+            }
+            #[cfg(feature = "json")]
+            ParseError::Json { error, .. } => write!(f, "Invalid JSON in SSE event: {error}"),
         }
     }
 }
 
-impl StdError for ParseError {}
+impl StdError for ParseError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            ParseError::BodyError(e) => Some(e),
+            #[cfg(feature = "json")]
+            ParseError::Json { error, .. } => Some(error),
+            ParseError::InvalidUtf8
+            | ParseError::InvalidRetryValue
+            | ParseError::EventTooLarge(_) => None,
+        }
+    }
+}
+
+impl From<BodyError> for ParseError {
+    fn from(error: BodyError) -> Self {
+        ParseError::BodyError(error)
+    }
+}
 
 impl Stream for SseStream {
     type Item = Result<Event, ParseError>;
 
     fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
         let mut this = self.project();
+        if *this.done {
+            return Poll::Ready(None);
+        }
 
         loop {
-            // Try to parse an event from the buffer
-            if let Some(event) = parse_event_from_buffer(this.buffer, this.partial_event) {
+            // Try to parse an event from the buffered lines
+            if let Some(event) = parse_event(
+                this.lines,
+                this.partial_event,
+                this.reconnect,
+                *this.include_comments,
+            ) {
                 return Poll::Ready(Some(Ok(event)));
             }
 
+            if this.partial_event.size + this.lines.pending() > *this.max_event_size {
+                *this.done = true;
+                return Poll::Ready(Some(Err(ParseError::EventTooLarge(*this.max_event_size))));
+            }
+
             // If no complete event, read more data from the body
             match this.body.as_mut().poll_next(cx) {
                 Poll::Ready(Some(Ok(frame))) => {
-                    this.buffer.extend_from_slice(&frame);
+                    this.lines.extend(&frame);
                 }
                 Poll::Ready(Some(Err(e))) => {
-                    return Poll::Ready(Some(Err(ParseError::BodyError(e.to_string()))));
+                    return Poll::Ready(Some(Err(ParseError::BodyError(e))));
                 }
                 Poll::Ready(None) => {
                     // Stream ended, check if we have a partial event to emit
                     if !this.partial_event.data.is_empty() {
+                        this.reconnect.dispatch(this.partial_event);
                         return Poll::Ready(Some(Ok(finalize_event(this.partial_event))));
                     }
                     return Poll::Ready(None);
@@ -316,76 +862,81 @@ impl Stream for SseStream {
     }
 }
 
-fn parse_event_from_buffer(
-    buffer: &mut Vec<u8>,
+impl Reconnect {
+    // Records the id of an event being dispatched, even one without data.
+    fn dispatch(&mut self, partial_event: &PartialEvent) {
+        if let Some(id) = &partial_event.id {
+            self.last_event_id = (!id.is_empty()).then(|| id.clone());
+        }
+    }
+}
+
+fn parse_event(
+    lines: &mut LineBuffer,
     partial_event: &mut PartialEvent,
+    reconnect: &mut Reconnect,
+    include_comments: bool,
 ) -> Option<Event> {
-    while let Some(line) = read_line(buffer) {
+    while let Some(line) = lines.next_line() {
         if line.is_empty() {
-            if !partial_event.data.is_empty() {
+            partial_event.size = 0;
+            reconnect.dispatch(partial_event);
+            if partial_event.has_fields() {
                 return Some(finalize_event(partial_event));
             }
             continue;
         }
-
-        let line = String::from_utf8_lossy(&line);
-
-        if line.starts_with(':') {
-            continue;
-        } else if let Some(data) = line.strip_prefix("data: ") {
-            partial_event.data.push(data.to_string());
-        } else if let Some(data) = line.strip_prefix("data:") {
-            partial_event.data.push(data.to_string());
-        } else if let Some(event_type) = line.strip_prefix("event: ") {
-            partial_event.event = Some(event_type.to_string());
-        } else if let Some(event_type) = line.strip_prefix("event:") {
-            partial_event.event = Some(event_type.to_string());
-        } else if let Some(id) = line.strip_prefix("id: ") {
-            partial_event.id = Some(id.to_string());
-        } else if let Some(id) = line.strip_prefix("id:") {
-            partial_event.id = Some(id.to_string());
-        } else if let Some(retry_str) = line.strip_prefix("retry: ") {
-            if let Ok(retry) = retry_str.parse::<u64>() {
-                partial_event.retry = Some(retry);
-            }
-        } else if let Some(retry_str) = line.strip_prefix("retry:") {
-            if let Ok(retry) = retry_str.parse::<u64>() {
-                partial_event.retry = Some(retry);
+        partial_event.size += line.len() + 1;
+        let line = String::from_utf8_lossy(line);
+        if let Some(comment) = line.strip_prefix(':') {
+            if include_comments {
+                let comment = comment.strip_prefix(' ').unwrap_or(comment);
+                partial_event.comments.push(comment.to_string());
             }
+            continue;
         }
+        process_field(&line, partial_event, reconnect);
     }
 
     None
 }
 
-fn read_line(buffer: &mut Vec<u8>) -> Option<Vec<u8>> {
-    if buffer.is_empty() {
-        return None;
-    }
-
-    let newline_idx = buffer.iter().position(|b| *b == b'\n' || *b == b'\r')?;
-
-    let line = buffer.drain(..newline_idx).collect::<Vec<u8>>();
-    // Remove the newline character we stopped at.
-    if !buffer.is_empty() {
-        let newline = buffer.remove(0);
-        if newline == b'\r' && !buffer.is_empty() && buffer[0] == b'\n' {
-            buffer.remove(0);
+// Applies a non-empty line other than a comment to the event being built, following the WHATWG
+// "process the field" steps.
+fn process_field(line: &str, partial_event: &mut PartialEvent, reconnect: &mut Reconnect) {
+    // A line without a colon is a field name with an empty value. Exactly one space
+    // after the colon is dropped; further ones belong to the value.
+    let (field, value) = match line.split_once(':') {
+        Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+        None => (line, ""),
+    };
+    match field {
+        "data" => partial_event.data.push(value.to_string()),
+        "event" => partial_event.event = Some(value.to_string()),
+        "id" if !value.contains('\0') => partial_event.id = Some(value.to_string()),
+        "retry" if !value.is_empty() && value.bytes().all(|byte| byte.is_ascii_digit()) => {
+            if let Ok(retry) = value.parse::<u64>() {
+                partial_event.retry = Some(retry);
+                reconnect.retry = Some(retry);
+            }
         }
+        _ => {}
     }
-
-    Some(line)
 }
 
 fn finalize_event(partial_event: &mut PartialEvent) -> Event {
-    let event = Event {
-        id: partial_event.id.take(),
+    let join = |lines: &mut Vec<String>| {
+        let joined = (!lines.is_empty()).then(|| lines.join("\n"));
+        lines.clear();
+        joined
+    };
+    Event {
+        comment: join(&mut partial_event.comments),
         event: partial_event.event.take(),
-        data: partial_event.data.join("\n"),
+        data: join(&mut partial_event.data),
+        id: partial_event.id.take(),
         retry: partial_event.retry.take(),
-    };
-    partial_event.data.clear();
-    event
+    }
 }
 
 #[cfg(test)]
@@ -521,6 +1072,67 @@ mod tests {
         assert_eq!(decoded, data);
     }
 
+    #[cfg(feature = "json")]
+    #[tokio::test]
+    async fn json_events_filter_by_type() {
+        #[derive(serde::Deserialize, PartialEq, Debug)]
+        struct Tick {
+            n: u32,
+        }
+
+        let body = Body::from(
+            ": keep-alive\n\n\
+             event: tick\ndata: {\"n\":1}\nid: 1\n\n\
+             event: status\ndata: {\"up\":true}\n\n\
+             event: tick\nid: 2\n\n\
+             event: tick\ndata: {\"n\":3}\nretry: 500\n\n",
+        );
+        let events: Vec<_> = SseStream::new(body)
+            .include_comments(true)
+            .json_events::<Tick>()
+            .only_event("tick")
+            .try_collect()
+            .await
+            .unwrap();
+        let ticks: Vec<_> = events.iter().map(|(_, tick)| tick.n).collect();
+        assert_eq!(ticks, [1, 3]);
+        assert_eq!(
+            events[0].0,
+            EventMeta {
+                id: Some("1".into()),
+                event: Some("tick".into()),
+                retry: None,
+            }
+        );
+        assert_eq!(events[1].0.retry, Some(500));
+
+        // Unnamed events are of type `message`.
+        let body = Body::from("data: {\"n\":4}\n\nevent: other\ndata: {\"n\":5}\n\n");
+        let mut events = SseStream::new(body)
+            .json_events::<Tick>()
+            .only_event("message");
+        assert_eq!(events.next().await.unwrap().unwrap().1, Tick { n: 4 });
+        assert!(events.next().await.is_none());
+    }
+
+    #[cfg(feature = "json")]
+    #[tokio::test]
+    async fn malformed_json_events_do_not_end_the_stream() {
+        let body = Body::from("data: {\"n\":1}\n\ndata: {\"n\":\nid: bad\n\ndata: {\"n\":3}\n\n");
+        let mut events = SseStream::new(body).json_events::<serde_json::Value>();
+        assert_eq!(events.next().await.unwrap().unwrap().1["n"], 1);
+        match events.next().await.unwrap() {
+            Err(ParseError::Json { data, error }) => {
+                assert_eq!(data, "{\"n\":");
+                assert!(error.is_eof());
+            }
+            other => panic!("unexpected item: {other:?}"),
+        }
+        assert_eq!(events.get_ref().last_event_id(), Some("bad"));
+        assert_eq!(events.next().await.unwrap().unwrap().1["n"], 3);
+        assert!(events.next().await.is_none());
+    }
+
     #[tokio::test]
     async fn test_stream_chunked_data() {
         // Simulate data coming in chunks
@@ -597,8 +1209,13 @@ mod tests {
         let mut stream = SseStream::new(body);
 
         let event = stream.next().await.unwrap().unwrap();
-        assert_eq!(event.text_data(), "test");
+        assert!(!event.has_data());
         assert_eq!(event.id(), Some("1"));
+
+        let event = stream.next().await.unwrap().unwrap();
+        assert_eq!(event.text_data(), "test");
+        assert_eq!(event.id(), None);
+        assert_eq!(stream.last_event_id(), Some("1"));
     }
 
     #[tokio::test]
@@ -636,8 +1253,96 @@ mod tests {
         let mut stream = SseStream::new(body);
 
         let event = stream.next().await.unwrap().unwrap();
-        // Should handle \r\n properly
-        assert!(event.text_data().contains("Windows"));
+        assert_eq!(event.text_data(), "Windows\nline endings");
+        assert!(stream.next().await.is_none());
+    }
+
+    async fn collect_chunks(chunks: &[&'static str]) -> Vec<Event> {
+        let chunks =
+            futures_lite::stream::iter(chunks.to_vec()).map(Ok::<_, core::convert::Infallible>);
+        SseStream::new(Body::from_stream(chunks))
+            .map(Result::unwrap)
+            .collect()
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_line_terminators() {
+        // Bare CR, CRLF and LF can be mixed, and a CRLF may be split across chunks.
+        let events = collect_chunks(&[
+            "event: a\rdata: 1\r\r",
+            "data: 2\r",
+            "\ndata: 3\r\n\r",
+            "\ndata: 4\n\r",
+        ])
+        .await;
+        let data: Vec<&str> = events.iter().map(Event::text_data).collect();
+        assert_eq!(data, ["1", "2\n3", "4"]);
+        assert_eq!(events[0].event(), Some("a"));
+        assert_eq!(events[1].event(), None);
+    }
+
+    // Streams a single event of `size` bytes in 1 KiB chunks.
+    fn large_event(size: usize) -> Body {
+        let chunk = Bytes::from("x".repeat(1024));
+        let chunks = core::iter::once(Bytes::from("data: "))
+            .chain(core::iter::repeat_n(chunk, size / 1024))
+            .chain(core::iter::once(Bytes::from("\n\n")))
+            .map(Ok::<_, core::convert::Infallible>);
+        Body::from_stream(futures_lite::stream::iter(chunks))
+    }
+
+    #[tokio::test]
+    async fn test_large_event_in_small_chunks() {
+        let size = 5 << 20;
+        let mut stream = SseStream::with_max_event_size(large_event(size), 6 << 20);
+        let event = stream.next().await.unwrap().unwrap();
+        assert_eq!(event.text_data().len(), size);
+        assert!(stream.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_event_size_limit() {
+        let mut stream = SseStream::new(large_event(5 << 20));
+        let error = stream.next().await.unwrap().unwrap_err();
+        assert!(matches!(
+            error,
+            ParseError::EventTooLarge(DEFAULT_MAX_EVENT_SIZE)
+        ));
+        assert!(stream.next().await.is_none());
+
+        // The limit applies to each event, not to the whole stream.
+        let events = "data: 0123456789\n\n".repeat(10);
+        let mut stream = SseStream::with_max_event_size(Body::from(Bytes::from(events)), 20);
+        let mut count = 0;
+        while let Some(event) = stream.next().await {
+            assert_eq!(event.unwrap().text_data(), "0123456789");
+            count += 1;
+        }
+        assert_eq!(count, 10);
+    }
+
+    #[tokio::test]
+    async fn test_field_values() {
+        let events = collect_chunks(&[
+            "data:  leading space\n\n",
+            "data\ndata\n\n",
+            "id: a\0b\ndata: nul\n\n",
+            "retry: 12a\nretry: +5\ndata: retry\n\n",
+            "event: typed\n\ndata: fresh\n\n",
+        ])
+        .await;
+        assert_eq!(events[0].text_data(), " leading space");
+        // A field name without a colon has an empty value.
+        assert_eq!(events[1].text_data(), "\n");
+        // Ids containing NUL are ignored.
+        assert_eq!(events[2].id(), None);
+        assert_eq!(events[3].retry(), None);
+        // A block with only an event type dispatches, without data.
+        assert_eq!(events[4].event(), Some("typed"));
+        assert!(!events[4].has_data());
+        assert_eq!(events[5].text_data(), "fresh");
+        assert_eq!(events[5].event(), None);
     }
 
     #[tokio::test]
@@ -718,8 +1423,11 @@ mod tests {
         let error = ParseError::InvalidRetryValue;
         assert_eq!(format!("{}", error), "Invalid retry value in SSE event");
 
-        let error = ParseError::BodyError("test error".to_string());
-        assert_eq!(format!("{}", error), "Body stream error: test error");
+        let error = ParseError::BodyError(BodyError::LimitExceeded(8));
+        assert_eq!(
+            format!("{}", error),
+            "Body stream error: body exceeds the limit of 8 bytes"
+        );
     }
 
     #[cfg(feature = "json")]
@@ -772,11 +1480,19 @@ mod tests {
         assert_eq!(event.event(), Some("test"));
     }
 
-    #[test]
-    fn test_parse_error_clone() {
-        let error = ParseError::InvalidUtf8;
-        let cloned = error.clone();
-        assert_eq!(format!("{}", cloned), "Invalid UTF-8 in SSE data");
+    #[tokio::test]
+    async fn test_parse_error_keeps_body_error() {
+        let chunks =
+            futures_lite::stream::iter([Ok("data: one\n\n"), Err(BodyError::LimitExceeded(8))]);
+        let mut stream = SseStream::new(Body::from_stream(chunks));
+        assert_eq!(stream.next().await.unwrap().unwrap().text_data(), "one");
+
+        let error = stream.next().await.unwrap().unwrap_err();
+        assert!(matches!(
+            error,
+            ParseError::BodyError(BodyError::LimitExceeded(8))
+        ));
+        assert!(StdError::source(&error).is_some());
     }
 
     #[test]
@@ -807,4 +1523,148 @@ mod tests {
         let event = stream.next().await.unwrap().unwrap();
         assert_eq!(event.text_data(), "real event");
     }
+
+    #[tokio::test]
+    async fn test_sse_body_sends_one_frame_per_event() {
+        use http_body_util::BodyExt;
+
+        let events = futures_lite::stream::iter(vec![
+            Ok::<_, core::convert::Infallible>(Event::from_data("a\nb").with_id("1")),
+            Ok(Event::from_data("c")),
+        ]);
+        let mut body = SseBody::new(events);
+        let mut frames = Vec::new();
+        while let Some(frame) = body.frame().await {
+            frames.push(frame.unwrap().into_data().unwrap());
+        }
+        assert_eq!(frames, ["data: a\ndata: b\nid: 1\n\n", "data: c\n\n"]);
+        assert!(http_body::Body::is_end_stream(&body));
+    }
+
+    #[cfg(feature = "std")]
+    #[tokio::test]
+    async fn test_sse_body_keep_alive_on_stalled_stream() {
+        use core::time::Duration;
+        use futures_lite::FutureExt;
+        use futures_timer::Delay;
+        use http_body_util::BodyExt;
+
+        // One event, then an upstream that never produces anything again.
+        let events = futures_lite::stream::once(Ok::<_, core::convert::Infallible>(
+            Event::from_data("first"),
+        ))
+        .chain(futures_lite::stream::pending());
+        let mut body = SseBody::new(events).keep_alive(Duration::from_millis(20));
+
+        let mut frames = Vec::new();
+        for _ in 0..3 {
+            let timeout = async {
+                Delay::new(Duration::from_secs(5)).await;
+                None
+            };
+            let frame = body
+                .frame()
+                .or(timeout)
+                .await
+                .expect("keep-alive frame before the timeout");
+            frames.push(frame.unwrap().into_data().unwrap());
+        }
+        assert_eq!(
+            frames,
+            ["data: first\n\n", ": keep-alive\n\n", ": keep-alive\n\n"]
+        );
+
+        // Keep-alive comments are skipped by the parser.
+        let mut stream = SseStream::new(Body::from(Bytes::from(frames.concat())));
+        assert_eq!(stream.next().await.unwrap().unwrap().text_data(), "first");
+        assert!(stream.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_last_event_id_tracking() {
+        let data = b"id: 1\ndata: a\n\ndata: b\n\nid: 2\nretry: 3000\ndata: c\n\nid:\ndata: d\n\nid: 5\n\n";
+        let mut stream = SseStream::new(Body::from(Bytes::from(&data[..])));
+        assert_eq!(stream.last_event_id(), None);
+        assert_eq!(stream.retry(), None);
+
+        stream.next().await.unwrap().unwrap();
+        assert_eq!(stream.last_event_id(), Some("1"));
+
+        // Events without an id keep the previous one.
+        let event = stream.next().await.unwrap().unwrap();
+        assert_eq!(event.text_data(), "b");
+        assert_eq!(stream.last_event_id(), Some("1"));
+
+        stream.next().await.unwrap().unwrap();
+        assert_eq!(stream.last_event_id(), Some("2"));
+        assert_eq!(stream.retry(), Some(3000));
+
+        // An empty id field resets the id.
+        let event = stream.next().await.unwrap().unwrap();
+        assert_eq!(event.text_data(), "d");
+        assert_eq!(stream.last_event_id(), None);
+
+        // A block carrying only an id dispatches an event without data.
+        let event = stream.next().await.unwrap().unwrap();
+        assert_eq!(event.id(), Some("5"));
+        assert!(!event.has_data());
+        assert_eq!(stream.last_event_id(), Some("5"));
+        assert_eq!(stream.retry(), Some(3000));
+        assert!(stream.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_multi_line_encode_round_trip() {
+        let sent = [
+            Event::from_data("first\nsecond\r\nthird\rfourth")
+                .with_event("lines")
+                .with_id("1"),
+            Event::from_data("trailing\n"),
+            Event::from_data(""),
+            Event::default().with_id("2"),
+            Event::comment("one\ntwo").with_event("noted"),
+        ];
+        assert_eq!(
+            sent[0].encode(),
+            "event: lines\ndata: first\ndata: second\ndata: third\ndata: fourth\nid: 1\n\n"
+        );
+        assert_eq!(sent[1].encode(), "data: trailing\ndata: \n\n");
+        assert_eq!(sent[3].encode(), "id: 2\n\n");
+        assert_eq!(sent[4].encode(), ": one\n: two\nevent: noted\n\n");
+
+        let encoded: String = sent.iter().map(Event::encode).collect();
+        let mut stream = SseStream::new(Body::from(encoded)).include_comments(true);
+        let mut received = Vec::new();
+        while let Some(event) = stream.next().await {
+            received.push(event.unwrap());
+        }
+        assert_eq!(received.len(), sent.len());
+        assert_eq!(received[0].text_data(), "first\nsecond\nthird\nfourth");
+        assert_eq!(received[0].event(), Some("lines"));
+        assert_eq!(received[1].text_data(), "trailing\n");
+        assert!(received[2].has_data());
+        assert_eq!(received[2].text_data(), "");
+        assert_eq!(received[3].id(), Some("2"));
+        assert!(!received[3].has_data());
+        assert_eq!(received[4].comment_text(), Some("one\ntwo"));
+        assert_eq!(received[4].event(), Some("noted"));
+    }
+
+    #[tokio::test]
+    async fn test_include_comments() {
+        let data = ": keep-alive\n\n:raw\ndata: a\n\n";
+        let mut stream = SseStream::new(Body::from(data));
+        let event = stream.next().await.unwrap().unwrap();
+        assert_eq!(event.comment_text(), None);
+        assert_eq!(event.text_data(), "a");
+        assert!(stream.next().await.is_none());
+
+        let mut stream = SseStream::new(Body::from(data)).include_comments(true);
+        let comment = stream.next().await.unwrap().unwrap();
+        assert_eq!(comment.comment_text(), Some("keep-alive"));
+        assert!(!comment.has_data());
+        let event = stream.next().await.unwrap().unwrap();
+        assert_eq!(event.comment_text(), Some("raw"));
+        assert_eq!(event.text_data(), "a");
+    }
 }
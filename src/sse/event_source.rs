@@ -0,0 +1,450 @@
+//! A client for event streams that reconnects like a browser's `EventSource`.
+
+use alloc::{boxed::Box, string::String};
+use core::{
+    fmt,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use futures_lite::{ready, Stream};
+use futures_timer::Delay;
+use http::{header, HeaderMap, HeaderValue, Method, StatusCode, Uri, Version};
+
+use super::{Event, ParseError, SseStream};
+use crate::{headers::LAST_EVENT_ID, Body, HttpError, Request, Response};
+
+/// The reconnection delay used until the server sends a `retry` field, 3 seconds.
+pub const DEFAULT_RETRY: Duration = Duration::from_millis(3000);
+
+/// Error yielded by an [`EventSource`].
+///
+/// [`Connect`](Self::Connect) and [`Stream`](Self::Stream) errors are followed by a
+/// reconnection; the others end the event source.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum EventSourceError<E> {
+    /// The connector failed.
+    Connect(E),
+    /// The server answered with a status other than `2xx`.
+    Status(StatusCode),
+    /// The response is not an event stream, with the `Content-Type` it has, if any.
+    ContentType(Option<HeaderValue>),
+    /// The event stream broke or could not be parsed.
+    Stream(ParseError),
+}
+
+impl<E: fmt::Display> fmt::Display for EventSourceError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Connect(error) => write!(f, "connection failed: {error}"),
+            Self::Status(status) => write!(f, "unexpected response status {status}"),
+            Self::ContentType(Some(value)) => {
+                write!(f, "response is not an event stream: {value:?}")
+            }
+            Self::ContentType(None) => f.write_str("response has no content type"),
+            Self::Stream(error) => write!(f, "event stream failed: {error}"),
+        }
+    }
+}
+
+impl<E: HttpError> core::error::Error for EventSourceError<E> {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            Self::Connect(error) => Some(error),
+            Self::Stream(error) => Some(error),
+            Self::Status(_) | Self::ContentType(_) => None,
+        }
+    }
+}
+
+impl<E: HttpError> HttpError for EventSourceError<E> {
+    fn status(&self) -> StatusCode {
+        match self {
+            Self::Connect(error) => error.status(),
+            _ => StatusCode::BAD_GATEWAY,
+        }
+    }
+}
+
+type Jitter = Box<dyn FnMut(Duration) -> Duration + Send>;
+
+enum State<F> {
+    Idle,
+    Connecting(Pin<Box<F>>),
+    Streaming(Box<SseStream>),
+    Waiting(Delay),
+    Done,
+}
+
+/// A stream of the events of a server, reconnecting whenever the connection ends.
+///
+/// Requests are sent through a connector, a closure turning a [`Request`] into a
+/// response future, so any HTTP client can be used. Every request is a copy of the
+/// base request, without its body or extensions, with `Accept: text/event-stream`
+/// and `Cache-Control: no-cache` unless they are set, and with the id of the last event
+/// received as `Last-Event-ID`. A `Last-Event-ID` in the base request resumes from
+/// that event.
+///
+/// Following the `EventSource` specification:
+///
+/// - when the response body ends, or fails, the connector is called again after the
+///   reconnection delay, [`DEFAULT_RETRY`] until a `retry` field changes it,
+/// - connector errors are yielded and followed by a reconnection as well,
+/// - a `204 No Content` response ends the stream, and
+/// - a status other than `2xx`, or a response that is not `text/event-stream`, is
+///   yielded as an error and ends the stream.
+///
+/// # Examples
+///
+/// ```rust
+/// use futures_lite::StreamExt;
+/// use http_kit::sse::EventSource;
+/// use http_kit::{Body, Request, Response, StatusCode};
+///
+/// # async fn example() {
+/// let connect = |request: Request| async move {
+///     let last = request.headers().get("last-event-id").cloned();
+///     let mut response = if last.is_none() {
+///         Response::new(Body::from("retry: 1\nid: 1\ndata: hello\n\n"))
+///     } else {
+///         Response::new(Body::empty())
+///     };
+///     if last.is_some() {
+///         *response.status_mut() = StatusCode::NO_CONTENT;
+///     }
+///     response
+///         .headers_mut()
+///         .insert("content-type", "text/event-stream".parse().unwrap());
+///     Ok::<_, core::convert::Infallible>(response)
+/// };
+/// let mut events = EventSource::new(connect, Request::new(Body::empty()));
+/// let event = events.next().await.unwrap().unwrap();
+/// assert_eq!(event.text_data(), "hello");
+/// assert!(events.next().await.is_none());
+/// # }
+/// ```
+pub struct EventSource<C, F> {
+    connector: C,
+    method: Method,
+    uri: Uri,
+    version: Version,
+    headers: HeaderMap,
+    last_event_id: Option<String>,
+    retry: Duration,
+    jitter: Option<Jitter>,
+    state: State<F>,
+}
+
+impl<C, F> fmt::Debug for EventSource<C, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EventSource")
+            .field("uri", &self.uri)
+            .field("last_event_id", &self.last_event_id)
+            .field("retry", &self.retry)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<C, F> Unpin for EventSource<C, F> {}
+
+impl<C, F, E> EventSource<C, F>
+where
+    C: FnMut(Request) -> F,
+    F: Future<Output = Result<Response, E>>,
+{
+    /// Creates an event source sending copies of `request` through `connector`.
+    ///
+    /// Nothing is sent until the stream is polled.
+    pub fn new(connector: C, request: Request) -> Self {
+        let (parts, _) = request.into_parts();
+        let last_event_id = parts
+            .headers
+            .get(LAST_EVENT_ID)
+            .and_then(|value| value.to_str().ok())
+            .filter(|id| !id.is_empty())
+            .map(String::from);
+        Self {
+            connector,
+            method: parts.method,
+            uri: parts.uri,
+            version: parts.version,
+            headers: parts.headers,
+            last_event_id,
+            retry: DEFAULT_RETRY,
+            jitter: None,
+            state: State::Idle,
+        }
+    }
+
+    /// Sets the reconnection delay used until the server sends a `retry` field.
+    /// Defaults to [`DEFAULT_RETRY`].
+    #[must_use]
+    pub fn retry(mut self, retry: Duration) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Sets a function adjusting each reconnection delay, for instance to add random
+    /// jitter so that clients disconnected together don't reconnect together.
+    ///
+    /// It receives the current delay and returns the one to wait.
+    #[must_use]
+    pub fn jitter(mut self, jitter: impl FnMut(Duration) -> Duration + Send + 'static) -> Self {
+        self.jitter = Some(Box::new(jitter));
+        self
+    }
+
+    /// Returns the id of the last event received, sent as `Last-Event-ID` when
+    /// reconnecting.
+    pub fn last_event_id(&self) -> Option<&str> {
+        self.last_event_id.as_deref()
+    }
+
+    /// Returns the current reconnection delay.
+    pub const fn current_retry(&self) -> Duration {
+        self.retry
+    }
+
+    fn request(&self) -> Request {
+        let mut request = Request::new(Body::empty());
+        *request.method_mut() = self.method.clone();
+        *request.uri_mut() = self.uri.clone();
+        *request.version_mut() = self.version;
+        let headers = request.headers_mut();
+        *headers = self.headers.clone();
+        headers
+            .entry(header::ACCEPT)
+            .or_insert(HeaderValue::from_static("text/event-stream"));
+        headers
+            .entry(header::CACHE_CONTROL)
+            .or_insert(HeaderValue::from_static("no-cache"));
+        headers.remove(LAST_EVENT_ID);
+        if let Some(value) = self
+            .last_event_id
+            .as_deref()
+            .and_then(|id| HeaderValue::from_str(id).ok())
+        {
+            headers.insert(LAST_EVENT_ID, value);
+        }
+        request
+    }
+
+    fn reconnect(&mut self) {
+        let delay = match &mut self.jitter {
+            Some(jitter) => jitter(self.retry),
+            None => self.retry,
+        };
+        self.state = State::Waiting(Delay::new(delay));
+    }
+
+    fn record(&mut self, event: &Event) {
+        if let Some(id) = event.id() {
+            self.last_event_id = (!id.is_empty()).then(|| String::from(id));
+        }
+        if let Some(retry) = event.retry() {
+            self.retry = Duration::from_millis(retry);
+        }
+    }
+}
+
+fn is_event_stream(headers: &HeaderMap) -> bool {
+    crate::headers::content_type(headers)
+        .is_some_and(|mime| mime.essence_str() == "text/event-stream")
+}
+
+impl<C, F, E> Stream for EventSource<C, F>
+where
+    C: FnMut(Request) -> F,
+    F: Future<Output = Result<Response, E>>,
+{
+    type Item = Result<Event, EventSourceError<E>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            match &mut this.state {
+                State::Idle => {
+                    let request = this.request();
+                    this.state = State::Connecting(Box::pin((this.connector)(request)));
+                }
+                State::Connecting(future) => match ready!(future.as_mut().poll(cx)) {
+                    Ok(response) => {
+                        let status = response.status();
+                        if status == StatusCode::NO_CONTENT {
+                            this.state = State::Done;
+                            return Poll::Ready(None);
+                        }
+                        if !status.is_success() {
+                            this.state = State::Done;
+                            return Poll::Ready(Some(Err(EventSourceError::Status(status))));
+                        }
+                        if !is_event_stream(response.headers()) {
+                            this.state = State::Done;
+                            let value = response.headers().get(header::CONTENT_TYPE).cloned();
+                            return Poll::Ready(Some(Err(EventSourceError::ContentType(value))));
+                        }
+                        this.state =
+                            State::Streaming(Box::new(SseStream::new(response.into_body())));
+                    }
+                    Err(error) => {
+                        this.reconnect();
+                        return Poll::Ready(Some(Err(EventSourceError::Connect(error))));
+                    }
+                },
+                State::Streaming(stream) => match ready!(Pin::new(&mut **stream).poll_next(cx)) {
+                    Some(Ok(event)) => {
+                        this.record(&event);
+                        return Poll::Ready(Some(Ok(event)));
+                    }
+                    Some(Err(error)) => {
+                        this.reconnect();
+                        return Poll::Ready(Some(Err(EventSourceError::Stream(error))));
+                    }
+                    None => this.reconnect(),
+                },
+                State::Waiting(delay) => {
+                    ready!(Pin::new(delay).poll(cx));
+                    this.state = State::Idle;
+                }
+                State::Done => return Poll::Ready(None),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use alloc::{string::ToString, sync::Arc, vec, vec::Vec};
+    use core::convert::Infallible;
+    use futures_lite::StreamExt;
+    use std::sync::Mutex;
+
+    type Seen = Arc<Mutex<Vec<Option<String>>>>;
+
+    // Answers with the scripted responses in order, recording the `Last-Event-ID` of
+    // each request.
+    fn scripted(
+        script: Vec<(StatusCode, &'static str)>,
+    ) -> (
+        impl FnMut(Request) -> core::future::Ready<Result<Response, Infallible>>,
+        Seen,
+    ) {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let record = seen.clone();
+        let mut script = script.into_iter();
+        let connector = move |request: Request| {
+            let id = request
+                .headers()
+                .get(LAST_EVENT_ID)
+                .map(|value| value.to_str().unwrap().to_string());
+            record.lock().unwrap().push(id);
+            let (status, body) = script.next().expect("no more responses");
+            let mut response = Response::new(Body::from(body));
+            *response.status_mut() = status;
+            response.headers_mut().insert(
+                header::CONTENT_TYPE,
+                HeaderValue::from_static("text/event-stream"),
+            );
+            core::future::ready(Ok(response))
+        };
+        (connector, seen)
+    }
+
+    #[tokio::test]
+    async fn ids_carry_over_reconnects() {
+        let (connector, seen) = scripted(vec![
+            (StatusCode::OK, "retry: 1\nid: 1\ndata: a\n\ndata: b\n\n"),
+            (StatusCode::OK, "data: c\nid: 2\n\n"),
+            (StatusCode::OK, "id\ndata: d\n\n"),
+            (StatusCode::NO_CONTENT, ""),
+        ]);
+        let mut request = Request::new(Body::empty());
+        *request.uri_mut() = "http://example.com/events".parse().unwrap();
+        request
+            .headers_mut()
+            .insert(LAST_EVENT_ID, HeaderValue::from_static("0"));
+        let mut jittered = 0;
+        let events = EventSource::new(connector, request).jitter(move |retry| {
+            assert_eq!(retry, Duration::from_millis(1));
+            jittered += 1;
+            retry * jittered
+        });
+        let data: Vec<String> = events
+            .map(|event| event.unwrap().text_data().to_string())
+            .collect()
+            .await;
+        assert_eq!(data, ["a", "b", "c", "d"]);
+        assert_eq!(
+            *seen.lock().unwrap(),
+            [
+                Some("0".to_string()),
+                Some("1".to_string()),
+                Some("2".to_string()),
+                // An empty id resets the last event id.
+                None,
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn failures_end_the_stream() {
+        let (connector, seen) = scripted(vec![
+            (StatusCode::OK, "data: a\n\n"),
+            (StatusCode::SERVICE_UNAVAILABLE, ""),
+        ]);
+        let mut events =
+            EventSource::new(connector, Request::new(Body::empty())).retry(Duration::ZERO);
+        assert_eq!(events.next().await.unwrap().unwrap().text_data(), "a");
+        let error = events.next().await.unwrap().unwrap_err();
+        assert!(matches!(
+            error,
+            EventSourceError::Status(StatusCode::SERVICE_UNAVAILABLE)
+        ));
+        assert_eq!(error.status(), StatusCode::BAD_GATEWAY);
+        assert!(events.next().await.is_none());
+        assert_eq!(seen.lock().unwrap().len(), 2);
+
+        let connector = |_request: Request| async {
+            let mut response = Response::new(Body::from("data: a\n\n"));
+            response
+                .headers_mut()
+                .insert(header::CONTENT_TYPE, HeaderValue::from_static("text/plain"));
+            Ok::<_, Infallible>(response)
+        };
+        let mut events = EventSource::new(connector, Request::new(Body::empty()));
+        let error = events.next().await.unwrap().unwrap_err();
+        assert!(matches!(error, EventSourceError::ContentType(Some(_))));
+        assert!(events.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn connector_errors_are_retried() {
+        let mut calls = 0;
+        let connector = move |_request: Request| {
+            calls += 1;
+            let result = match calls {
+                1 => Err(crate::BodyError::BodyFrozen),
+                _ => {
+                    let mut response = Response::new(Body::empty());
+                    *response.status_mut() = StatusCode::NO_CONTENT;
+                    Ok(response)
+                }
+            };
+            core::future::ready(result)
+        };
+        let mut events =
+            EventSource::new(connector, Request::new(Body::empty())).retry(Duration::ZERO);
+        assert!(matches!(
+            events.next().await,
+            Some(Err(EventSourceError::Connect(_)))
+        ));
+        assert!(events.next().await.is_none());
+        assert_eq!(events.current_retry(), Duration::ZERO);
+    }
+}
@@ -0,0 +1,239 @@
+//! Crate-level instrumentation counters.
+//!
+//! With the `stats` feature enabled, http-kit keeps a handful of global counters updated
+//! with a single relaxed atomic operation at its choke points: [`AnyEndpoint`] request
+//! handling, body buffering and streaming, SSE event encoding and deprecation notices.
+//! Without the feature the updates are compiled out entirely.
+//!
+//! [`AnyEndpoint`]: crate::endpoint::AnyEndpoint
+//!
+//! # Examples
+//!
+//! ```rust
+//! use http_kit::stats::Stats;
+//!
+//! let snapshot = Stats::snapshot();
+//! println!("{} requests completed", snapshot.requests_completed);
+//! Stats::reset();
+//! ```
+
+use core::sync::atomic::{AtomicU64, Ordering::Relaxed};
+
+use http::StatusCode;
+
+pub(crate) struct Counters {
+    pub(crate) requests_started: AtomicU64,
+    pub(crate) requests_completed: AtomicU64,
+    pub(crate) bodies_buffered: AtomicU64,
+    pub(crate) bodies_streamed: AtomicU64,
+    pub(crate) body_bytes_in: AtomicU64,
+    pub(crate) body_bytes_out: AtomicU64,
+    pub(crate) sse_events: AtomicU64,
+    pub(crate) client_errors: AtomicU64,
+    pub(crate) server_errors: AtomicU64,
+    pub(crate) deprecated_responses: AtomicU64,
+}
+
+pub(crate) static COUNTERS: Counters = Counters {
+    requests_started: AtomicU64::new(0),
+    requests_completed: AtomicU64::new(0),
+    bodies_buffered: AtomicU64::new(0),
+    bodies_streamed: AtomicU64::new(0),
+    body_bytes_in: AtomicU64::new(0),
+    body_bytes_out: AtomicU64::new(0),
+    sse_events: AtomicU64::new(0),
+    client_errors: AtomicU64::new(0),
+    server_errors: AtomicU64::new(0),
+    deprecated_responses: AtomicU64::new(0),
+};
+
+// Records the status class of a completed request.
+pub(crate) fn record_status(status: StatusCode) {
+    if status.is_client_error() {
+        COUNTERS.client_errors.fetch_add(1, Relaxed);
+    } else if status.is_server_error() {
+        COUNTERS.server_errors.fetch_add(1, Relaxed);
+    }
+}
+
+/// A point-in-time copy of the counters.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct StatsSnapshot {
+    /// Requests passed to an [`AnyEndpoint`](crate::endpoint::AnyEndpoint).
+    pub requests_started: u64,
+    /// Requests for which an [`AnyEndpoint`](crate::endpoint::AnyEndpoint) returned,
+    /// successfully or not.
+    pub requests_completed: u64,
+    /// Bodies read into memory at once (`into_bytes` and the conversions built on it).
+    pub bodies_buffered: u64,
+    /// Streaming bodies consumed chunk by chunk until their end.
+    pub bodies_streamed: u64,
+    /// Bytes of request bodies of known length passed to an
+    /// [`AnyEndpoint`](crate::endpoint::AnyEndpoint).
+    pub body_bytes_in: u64,
+    /// Bytes of response bodies of known length returned by an
+    /// [`AnyEndpoint`](crate::endpoint::AnyEndpoint).
+    pub body_bytes_out: u64,
+    /// Server-sent events encoded into bodies.
+    pub sse_events: u64,
+    /// Requests completed with a `4xx` status.
+    pub client_errors: u64,
+    /// Requests completed with a `5xx` status.
+    pub server_errors: u64,
+    /// Responses stamped by the `DeprecationNotice` middleware.
+    pub deprecated_responses: u64,
+}
+
+impl serde::Serialize for StatsSnapshot {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("StatsSnapshot", 10)?;
+        state.serialize_field("requests_started", &self.requests_started)?;
+        state.serialize_field("requests_completed", &self.requests_completed)?;
+        state.serialize_field("bodies_buffered", &self.bodies_buffered)?;
+        state.serialize_field("bodies_streamed", &self.bodies_streamed)?;
+        state.serialize_field("body_bytes_in", &self.body_bytes_in)?;
+        state.serialize_field("body_bytes_out", &self.body_bytes_out)?;
+        state.serialize_field("sse_events", &self.sse_events)?;
+        state.serialize_field("client_errors", &self.client_errors)?;
+        state.serialize_field("server_errors", &self.server_errors)?;
+        state.serialize_field("deprecated_responses", &self.deprecated_responses)?;
+        state.end()
+    }
+}
+
+/// Access to the global counters.
+#[derive(Debug, Clone, Copy)]
+pub struct Stats;
+
+impl Stats {
+    /// Reads the current value of every counter.
+    ///
+    /// Counters are read individually, so a snapshot taken while requests are in flight
+    /// may be slightly inconsistent across fields.
+    pub fn snapshot() -> StatsSnapshot {
+        StatsSnapshot {
+            requests_started: COUNTERS.requests_started.load(Relaxed),
+            requests_completed: COUNTERS.requests_completed.load(Relaxed),
+            bodies_buffered: COUNTERS.bodies_buffered.load(Relaxed),
+            bodies_streamed: COUNTERS.bodies_streamed.load(Relaxed),
+            body_bytes_in: COUNTERS.body_bytes_in.load(Relaxed),
+            body_bytes_out: COUNTERS.body_bytes_out.load(Relaxed),
+            sse_events: COUNTERS.sse_events.load(Relaxed),
+            client_errors: COUNTERS.client_errors.load(Relaxed),
+            server_errors: COUNTERS.server_errors.load(Relaxed),
+            deprecated_responses: COUNTERS.deprecated_responses.load(Relaxed),
+        }
+    }
+
+    /// Resets every counter to zero.
+    pub fn reset() {
+        for counter in [
+            &COUNTERS.requests_started,
+            &COUNTERS.requests_completed,
+            &COUNTERS.bodies_buffered,
+            &COUNTERS.bodies_streamed,
+            &COUNTERS.body_bytes_in,
+            &COUNTERS.body_bytes_out,
+            &COUNTERS.sse_events,
+            &COUNTERS.client_errors,
+            &COUNTERS.server_errors,
+            &COUNTERS.deprecated_responses,
+        ] {
+            counter.store(0, Relaxed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{endpoint::AnyEndpoint, sse::Event, Body, Endpoint, Request, Response};
+    use alloc::vec;
+    use core::convert::Infallible;
+    use futures_lite::{stream, StreamExt};
+
+    extern crate std;
+
+    // Both tests observe the global counters, so they must not interleave.
+    static SERIAL: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+    struct Echo;
+
+    impl Endpoint for Echo {
+        type Error = Infallible;
+        async fn respond(&mut self, request: &mut Request) -> Result<Response, Self::Error> {
+            let body = request
+                .body_mut()
+                .take()
+                .unwrap()
+                .into_bytes()
+                .await
+                .unwrap();
+            let mut response = Response::new(Body::from_bytes(body));
+            if request.uri().path() == "/missing" {
+                *response.status_mut() = StatusCode::NOT_FOUND;
+            }
+            Ok(response)
+        }
+    }
+
+    // Other tests update the counters concurrently, so only lower bounds are asserted.
+    #[test]
+    fn counters_follow_workload() {
+        let _guard = SERIAL.lock().unwrap();
+        let before = Stats::snapshot();
+
+        futures_lite::future::block_on(async {
+            let mut endpoint = AnyEndpoint::new(Echo);
+            for path in ["/", "/missing"] {
+                let mut request = Request::new(Body::from_bytes("hello"));
+                *request.uri_mut() = path.parse().unwrap();
+                endpoint.respond(&mut request).await.unwrap();
+            }
+
+            let mut streamed = Body::from_stream(stream::iter(vec![Ok::<_, Infallible>("a")]));
+            while streamed.next().await.is_some() {}
+
+            let events = stream::iter(vec![
+                Ok::<_, Infallible>(Event::from_data("one")),
+                Ok(Event::from_data("two")),
+            ]);
+            Body::from_sse(events).into_bytes().await.unwrap();
+        });
+
+        let after = Stats::snapshot();
+        assert!(after.requests_started >= before.requests_started + 2);
+        assert!(after.requests_completed >= before.requests_completed + 2);
+        assert!(after.client_errors > before.client_errors);
+        assert!(after.bodies_buffered >= before.bodies_buffered + 3);
+        assert!(after.bodies_streamed > before.bodies_streamed);
+        assert!(after.body_bytes_in >= before.body_bytes_in + 10);
+        assert!(after.body_bytes_out >= before.body_bytes_out + 10);
+        assert!(after.sse_events >= before.sse_events + 2);
+    }
+
+    #[test]
+    fn reset_clears_counters() {
+        let _guard = SERIAL.lock().unwrap();
+        COUNTERS.requests_started.fetch_add(1_000_000, Relaxed);
+        assert!(Stats::snapshot().requests_started >= 1_000_000);
+
+        Stats::reset();
+        assert!(Stats::snapshot().requests_started < 1_000_000);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn snapshot_serializes() {
+        let value = serde_json::to_value(StatsSnapshot {
+            sse_events: 3,
+            ..StatsSnapshot::default()
+        })
+        .unwrap();
+        assert_eq!(value["sse_events"], 3);
+        assert_eq!(value["requests_started"], 0);
+    }
+}
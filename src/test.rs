@@ -0,0 +1,619 @@
+//! Scriptable upstream, mock endpoint and in-process client for tests.
+//!
+//! [`TestClient`] sends requests built in a line or two straight to an [`Endpoint`],
+//! and [`MockEndpoint`] stands in for the inner endpoint of middleware under test,
+//! recording the requests it receives. The `test-util` feature also adds assertions
+//! such as [`ResponseExt::assert_status`](crate::ResponseExt::assert_status).
+//!
+//! [`UpstreamScript`] describes how a fake upstream answers requests: per path, a
+//! sequence of [`Reply`] values that can delay the response, drip the body slowly,
+//! drop the connection mid-body, redirect or require headers. The script can be used
+//! in-process, as it implements [`Endpoint`], or served over a real TCP socket with
+//! [`UpstreamScript::serve`] for socket-level tests.
+//!
+//! This module is available with the `test-util` feature.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use http_kit::test::{Reply, UpstreamScript};
+//! use http_kit::{Body, Endpoint, Request, StatusCode};
+//!
+//! # async fn example() {
+//! let mut upstream = UpstreamScript::new()
+//!     .route("/flaky", Reply::status(StatusCode::SERVICE_UNAVAILABLE))
+//!     .route("/flaky", Reply::ok().body("recovered"));
+//!
+//! let mut request = Request::new(Body::empty());
+//! *request.uri_mut() = "/flaky".parse().unwrap();
+//! assert_eq!(upstream.respond(&mut request).await.unwrap().status(), 503);
+//! assert_eq!(upstream.respond(&mut request).await.unwrap().status(), 200);
+//! # }
+//! ```
+
+extern crate std;
+
+use alloc::{collections::VecDeque, format, string::String, sync::Arc, vec::Vec};
+use core::{
+    convert::Infallible,
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+use std::{
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{Shutdown, SocketAddr, TcpListener, TcpStream},
+    sync::Mutex,
+    thread::{self, JoinHandle},
+};
+
+use bytes::Bytes;
+use futures_lite::stream;
+use futures_timer::Delay;
+use http::{header, HeaderMap, HeaderName, HeaderValue, Method, StatusCode};
+
+use crate::{Body, Endpoint, Request, Response};
+
+mod client;
+mod mock;
+pub use client::{TestClient, TestRequest};
+pub use mock::{MockEndpoint, RecordedRequest};
+
+/// A scripted answer of the upstream.
+///
+/// Header values that fail to convert panic, as this type is meant for tests.
+#[derive(Debug, Clone)]
+pub struct Reply {
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Bytes,
+    delay: Option<Duration>,
+    drip: Option<(usize, Duration)>,
+    drop_after: Option<usize>,
+    required: Vec<(HeaderName, HeaderValue)>,
+}
+
+impl Reply {
+    /// Creates a reply with the given status and an empty body.
+    pub fn status(status: StatusCode) -> Self {
+        Self {
+            status,
+            headers: HeaderMap::new(),
+            body: Bytes::new(),
+            delay: None,
+            drip: None,
+            drop_after: None,
+            required: Vec::new(),
+        }
+    }
+
+    /// Creates a `200 OK` reply with an empty body.
+    pub fn ok() -> Self {
+        Self::status(StatusCode::OK)
+    }
+
+    /// Waits before sending the response head.
+    #[must_use]
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = Some(delay);
+        self
+    }
+
+    /// Adds a response header.
+    #[must_use]
+    pub fn header<V>(mut self, name: HeaderName, value: V) -> Self
+    where
+        V: TryInto<HeaderValue>,
+        V::Error: core::fmt::Debug,
+    {
+        self.headers
+            .append(name, value.try_into().expect("invalid header value"));
+        self
+    }
+
+    /// Sets the response body.
+    #[must_use]
+    pub fn body(mut self, body: impl Into<Bytes>) -> Self {
+        self.body = body.into();
+        self
+    }
+
+    /// Sends the body in `chunks` pieces, pausing between them.
+    #[must_use]
+    pub fn drip(mut self, chunks: usize, pause: Duration) -> Self {
+        self.drip = Some((chunks.max(1), pause));
+        self
+    }
+
+    /// Drops the connection after `bytes` bytes of the body have been sent.
+    ///
+    /// The `Content-Length` still advertises the complete body, so clients observe a
+    /// truncated response. In-process, the body stream fails with an I/O error instead.
+    #[must_use]
+    pub fn drop_after(mut self, bytes: usize) -> Self {
+        self.drop_after = Some(bytes);
+        self
+    }
+
+    /// Redirects to `location`, using `302 Found` unless a redirection status is set.
+    #[must_use]
+    pub fn redirect_to<V>(mut self, location: V) -> Self
+    where
+        V: TryInto<HeaderValue>,
+        V::Error: core::fmt::Debug,
+    {
+        if !self.status.is_redirection() {
+            self.status = StatusCode::FOUND;
+        }
+        self.headers.insert(
+            header::LOCATION,
+            location.try_into().expect("invalid header value"),
+        );
+        self
+    }
+
+    /// Answers `400 Bad Request` instead unless the request carries this header value.
+    #[must_use]
+    pub fn require_header<V>(mut self, name: HeaderName, value: V) -> Self
+    where
+        V: TryInto<HeaderValue>,
+        V::Error: core::fmt::Debug,
+    {
+        self.required
+            .push((name, value.try_into().expect("invalid header value")));
+        self
+    }
+
+    // Body pieces as they are sent, truncated when the connection is dropped.
+    fn chunks(&self) -> VecDeque<Bytes> {
+        let count = self.drip.map_or(1, |(chunks, _)| chunks);
+        let size = self.body.len().div_ceil(count).max(1);
+        let limit = self.drop_after.unwrap_or(usize::MAX).min(self.body.len());
+
+        let mut chunks = VecDeque::new();
+        let mut start = 0;
+        while start < limit {
+            let end = (start + size).min(limit);
+            chunks.push_back(self.body.slice(start..end));
+            start = end;
+        }
+        chunks
+    }
+
+    fn pause(&self) -> Option<Duration> {
+        self.drip.map(|(_, pause)| pause)
+    }
+
+    fn into_response(self) -> Response {
+        let mut response = Response::new(Body::empty());
+        *response.status_mut() = self.status;
+        *response.headers_mut() = self.headers.clone();
+        response
+            .headers_mut()
+            .insert(header::CONTENT_LENGTH, self.body.len().into());
+
+        if self.drip.is_none() && self.drop_after.is_none() {
+            *response.body_mut() = Body::from_bytes(self.body);
+            return response;
+        }
+
+        let state = (self.chunks(), self.pause(), self.drop_after.is_some(), true);
+        let body = stream::unfold(state, |(mut chunks, pause, dropped, first)| async move {
+            match chunks.pop_front() {
+                Some(chunk) => {
+                    if let (Some(pause), false) = (pause, first) {
+                        Delay::new(pause).await;
+                    }
+                    Some((Ok(chunk), (chunks, pause, dropped, false)))
+                }
+                None if dropped => {
+                    let error = io::Error::new(
+                        io::ErrorKind::ConnectionReset,
+                        "upstream dropped the connection",
+                    );
+                    Some((Err(error), (chunks, pause, false, false)))
+                }
+                None => None,
+            }
+        });
+        *response.body_mut() = Body::from_stream(body);
+        response
+    }
+}
+
+#[derive(Debug)]
+struct Route {
+    method: Option<Method>,
+    path: String,
+    replies: Vec<Reply>,
+    served: usize,
+}
+
+/// A scripted fake upstream.
+///
+/// Replies registered for the same method and path are served in order; the last one
+/// repeats once the sequence is exhausted. `OPTIONS` requests to a scripted path without
+/// an explicit `OPTIONS` reply are answered with `204 No Content` and an `Allow` header.
+/// Other unscripted requests get `404 Not Found`.
+#[derive(Debug, Default)]
+pub struct UpstreamScript {
+    routes: Vec<Route>,
+    requests: usize,
+}
+
+impl UpstreamScript {
+    /// Creates an empty script.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a reply for requests to `path` with any method.
+    #[must_use]
+    pub fn route(self, path: impl Into<String>, reply: Reply) -> Self {
+        self.push(None, path.into(), reply)
+    }
+
+    /// Appends a reply for requests to `path` with the given method.
+    #[must_use]
+    pub fn route_method(self, method: Method, path: impl Into<String>, reply: Reply) -> Self {
+        self.push(Some(method), path.into(), reply)
+    }
+
+    fn push(mut self, method: Option<Method>, path: String, reply: Reply) -> Self {
+        match self
+            .routes
+            .iter_mut()
+            .find(|route| route.method == method && route.path == path)
+        {
+            Some(route) => route.replies.push(reply),
+            None => self.routes.push(Route {
+                method,
+                path,
+                replies: alloc::vec![reply],
+                served: 0,
+            }),
+        }
+        self
+    }
+
+    /// Returns the number of requests answered so far.
+    pub fn requests(&self) -> usize {
+        self.requests
+    }
+
+    fn next(&mut self, method: &Method, path: &str, headers: &HeaderMap) -> Reply {
+        self.requests += 1;
+        let route = self
+            .routes
+            .iter_mut()
+            .filter(|route| route.path == path)
+            .find(|route| route.method.as_ref().is_none_or(|m| m == method));
+
+        let Some(route) = route else {
+            if method == Method::OPTIONS && self.routes.iter().any(|route| route.path == path) {
+                return Reply::status(StatusCode::NO_CONTENT).header(
+                    header::ALLOW,
+                    "GET, HEAD, POST, PUT, PATCH, DELETE, OPTIONS",
+                );
+            }
+            return Reply::status(StatusCode::NOT_FOUND);
+        };
+
+        let reply = route.replies[route.served.min(route.replies.len() - 1)].clone();
+        route.served += 1;
+
+        for (name, value) in &reply.required {
+            if headers.get(name) != Some(value) {
+                return Reply::status(StatusCode::BAD_REQUEST)
+                    .body(format!("missing required header `{name}`"));
+            }
+        }
+        reply
+    }
+
+    /// Serves the script over TCP on an ephemeral localhost port.
+    ///
+    /// Each connection carries a single request and is closed after the response. The
+    /// server stops when the returned [`UpstreamServer`] is dropped.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if binding the listener fails.
+    pub fn serve(self) -> io::Result<UpstreamServer> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let addr = listener.local_addr()?;
+        let script = Arc::new(Mutex::new(self));
+        let shutdown = Arc::new(AtomicBool::new(false));
+
+        let handle = thread::spawn({
+            let script = script.clone();
+            let shutdown = shutdown.clone();
+            move || {
+                for stream in listener.incoming() {
+                    if shutdown.load(Ordering::Acquire) {
+                        break;
+                    }
+                    if let Ok(stream) = stream {
+                        let script = script.clone();
+                        thread::spawn(move || {
+                            let _ = serve_connection(stream, &script);
+                        });
+                    }
+                }
+            }
+        });
+
+        Ok(UpstreamServer {
+            addr,
+            script,
+            shutdown,
+            handle: Some(handle),
+        })
+    }
+}
+
+impl Endpoint for UpstreamScript {
+    type Error = Infallible;
+
+    async fn respond(&mut self, request: &mut Request) -> Result<Response, Self::Error> {
+        let reply = self.next(request.method(), request.uri().path(), request.headers());
+        if let Some(delay) = reply.delay {
+            Delay::new(delay).await;
+        }
+        Ok(reply.into_response())
+    }
+}
+
+fn serve_connection(stream: TcpStream, script: &Mutex<UpstreamScript>) -> io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let mut parts = line.split_whitespace();
+    let method: Method = parts
+        .next()
+        .and_then(|method| method.parse().ok())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "malformed request line"))?;
+    let path = parts.next().unwrap_or("/");
+    let path = String::from(path.split('?').next().unwrap_or(path));
+
+    let mut headers = HeaderMap::new();
+    loop {
+        line.clear();
+        reader.read_line(&mut line)?;
+        let Some((name, value)) = line.trim_end().split_once(':') else {
+            break;
+        };
+        if let (Ok(name), Ok(value)) = (
+            HeaderName::from_bytes(name.trim().as_bytes()),
+            HeaderValue::from_str(value.trim()),
+        ) {
+            headers.append(name, value);
+        }
+    }
+    let length = headers
+        .get(header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok()?.parse().ok())
+        .unwrap_or(0);
+    io::copy(&mut reader.by_ref().take(length), &mut io::sink())?;
+
+    let reply = script
+        .lock()
+        .expect("script lock poisoned")
+        .next(&method, &path, &headers);
+    if let Some(delay) = reply.delay {
+        thread::sleep(delay);
+    }
+
+    let mut stream = stream;
+    let mut head = format!(
+        "HTTP/1.1 {} {}\r\ncontent-length: {}\r\nconnection: close\r\n",
+        reply.status.as_u16(),
+        reply.status.canonical_reason().unwrap_or(""),
+        reply.body.len()
+    );
+    for (name, value) in &reply.headers {
+        head.push_str(name.as_str());
+        head.push_str(": ");
+        head.push_str(&String::from_utf8_lossy(value.as_bytes()));
+        head.push_str("\r\n");
+    }
+    head.push_str("\r\n");
+    stream.write_all(head.as_bytes())?;
+    stream.flush()?;
+
+    for (i, chunk) in reply.chunks().into_iter().enumerate() {
+        if let (Some(pause), true) = (reply.pause(), i > 0) {
+            thread::sleep(pause);
+        }
+        stream.write_all(&chunk)?;
+        stream.flush()?;
+    }
+    stream.shutdown(Shutdown::Both)
+}
+
+/// A running TCP upstream created by [`UpstreamScript::serve`].
+#[derive(Debug)]
+pub struct UpstreamServer {
+    addr: SocketAddr,
+    script: Arc<Mutex<UpstreamScript>>,
+    shutdown: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl UpstreamServer {
+    /// Returns the address the server listens on.
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Returns the `http://` base URL of the server.
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Returns the number of requests answered so far.
+    pub fn requests(&self) -> usize {
+        self.script.lock().expect("script lock poisoned").requests()
+    }
+}
+
+impl Drop for UpstreamServer {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::Release);
+        // Wake the accept loop so it observes the shutdown flag.
+        let _ = TcpStream::connect(self.addr);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BodyError;
+    use std::time::Instant;
+
+    fn get(server: &UpstreamServer, path: &str) -> TcpStream {
+        let mut stream = TcpStream::connect(server.addr()).unwrap();
+        write!(stream, "GET {path} HTTP/1.1\r\nhost: upstream\r\n\r\n").unwrap();
+        stream
+    }
+
+    fn split_response(raw: &[u8]) -> (String, &[u8]) {
+        let end = raw.windows(4).position(|w| w == b"\r\n\r\n").unwrap();
+        (
+            String::from_utf8_lossy(&raw[..end]).into_owned(),
+            &raw[end + 4..],
+        )
+    }
+
+    #[test]
+    fn drops_connection_mid_body() {
+        let server = UpstreamScript::new()
+            .route(
+                "/download",
+                Reply::ok().body(alloc::vec![b'x'; 100]).drop_after(40),
+            )
+            .serve()
+            .unwrap();
+
+        let mut raw = Vec::new();
+        get(&server, "/download").read_to_end(&mut raw).unwrap();
+        let (head, body) = split_response(&raw);
+        assert!(head.starts_with("HTTP/1.1 200 OK"));
+        assert!(head.contains("content-length: 100"));
+        assert_eq!(body.len(), 40);
+        assert_eq!(server.requests(), 1);
+    }
+
+    #[tokio::test]
+    async fn dropped_body_fails_in_process() {
+        let mut upstream =
+            UpstreamScript::new().route("/download", Reply::ok().body("abcdef").drop_after(3));
+        let mut request = Request::new(Body::empty());
+        *request.uri_mut() = "/download".parse().unwrap();
+
+        let response = upstream.respond(&mut request).await.unwrap();
+        assert_eq!(response.headers()[header::CONTENT_LENGTH], "6");
+        let error = response.into_body().into_bytes().await.unwrap_err();
+        assert!(matches!(error, BodyError::Io(_)));
+    }
+
+    #[test]
+    fn slow_drip_trips_idle_timeout() {
+        let pause = Duration::from_millis(300);
+        let server = UpstreamScript::new()
+            .route("/slow", Reply::ok().body("abcdef").drip(3, pause))
+            .serve()
+            .unwrap();
+
+        // An idle timeout shorter than the pause gives up after the first chunk.
+        let mut stream = get(&server, "/slow");
+        stream
+            .set_read_timeout(Some(Duration::from_millis(50)))
+            .unwrap();
+        let mut raw = Vec::new();
+        let error = stream.read_to_end(&mut raw).unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+        ));
+        assert_eq!(split_response(&raw).1, b"ab");
+
+        // A longer idle timeout receives the whole body, spread over the pauses.
+        let started = Instant::now();
+        let mut stream = get(&server, "/slow");
+        stream.set_read_timeout(Some(pause * 4)).unwrap();
+        let mut raw = Vec::new();
+        stream.read_to_end(&mut raw).unwrap();
+        assert_eq!(split_response(&raw).1, b"abcdef");
+        assert!(started.elapsed() >= pause * 2);
+    }
+
+    #[tokio::test]
+    async fn follows_redirect_chain() {
+        let mut upstream = UpstreamScript::new()
+            .route("/start", Reply::ok().redirect_to("/middle"))
+            .route(
+                "/middle",
+                Reply::status(StatusCode::PERMANENT_REDIRECT).redirect_to("/end"),
+            )
+            .route(
+                "/end",
+                Reply::ok()
+                    .require_header(header::AUTHORIZATION, "Bearer token")
+                    .body("arrived"),
+            );
+
+        let mut path = String::from("/start");
+        let mut statuses = Vec::new();
+        let response = loop {
+            let mut request = Request::new(Body::empty());
+            *request.uri_mut() = path.parse().unwrap();
+            request
+                .headers_mut()
+                .insert(header::AUTHORIZATION, "Bearer token".parse().unwrap());
+            let response = upstream.respond(&mut request).await.unwrap();
+            statuses.push(response.status());
+            match response.headers().get(header::LOCATION) {
+                Some(location) => path = String::from(location.to_str().unwrap()),
+                None => break response,
+            }
+        };
+
+        assert_eq!(
+            statuses,
+            [
+                StatusCode::FOUND,
+                StatusCode::PERMANENT_REDIRECT,
+                StatusCode::OK
+            ]
+        );
+        assert_eq!(response.into_body().into_bytes().await.unwrap(), "arrived");
+        assert_eq!(upstream.requests(), 3);
+    }
+
+    #[tokio::test]
+    async fn answers_options_and_missing_headers() {
+        let mut upstream = UpstreamScript::new().route_method(
+            Method::POST,
+            "/items",
+            Reply::status(StatusCode::CREATED).require_header(header::CONTENT_TYPE, "text/plain"),
+        );
+
+        let mut request = Request::new(Body::empty());
+        *request.uri_mut() = "/items".parse().unwrap();
+        *request.method_mut() = Method::OPTIONS;
+        let response = upstream.respond(&mut request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert!(response.headers().contains_key(header::ALLOW));
+
+        *request.method_mut() = Method::POST;
+        let response = upstream.respond(&mut request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        *request.method_mut() = Method::GET;
+        let response = upstream.respond(&mut request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+}
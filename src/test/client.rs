@@ -0,0 +1,274 @@
+//! An in-process client driving an endpoint.
+
+use core::fmt;
+
+use http::{header::HeaderName, HeaderValue, Method, Uri};
+
+use crate::{Body, Endpoint, Request, RequestExt, Response};
+
+/// A client sending requests straight to an [`Endpoint`], without a network.
+///
+/// Requests are built with [`TestRequest`]; invalid URIs and header values panic, as
+/// this type is meant for tests.
+///
+/// # Examples
+///
+/// ```rust
+/// use http_kit::endpoint::endpoint_fn;
+/// use http_kit::test::TestClient;
+/// use http_kit::{Request, Response, ResponseExt, StatusCode};
+///
+/// # async fn example() {
+/// let hello = endpoint_fn(|request: &mut Request| {
+///     let name = request.headers()["x-name"].to_str().unwrap().to_owned();
+///     async move { Ok::<_, core::convert::Infallible>(Response::new(name.into())) }
+/// });
+/// let mut client = TestClient::new(hello);
+/// let response = client.get("/hello").header("x-name", "ada").send().await.unwrap();
+/// response.assert_status(StatusCode::OK);
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct TestClient<E> {
+    endpoint: E,
+}
+
+impl<E: Endpoint> TestClient<E> {
+    /// Wraps `endpoint` into a client.
+    pub const fn new(endpoint: E) -> Self {
+        Self { endpoint }
+    }
+
+    /// Returns the wrapped endpoint.
+    pub fn into_inner(self) -> E {
+        self.endpoint
+    }
+
+    /// Starts a request with the given method to `uri`, such as `/items?page=2`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `uri` is not a valid URI.
+    pub fn request(&mut self, method: Method, uri: &str) -> TestRequest<'_, E> {
+        let uri: Uri = uri
+            .parse()
+            .unwrap_or_else(|error| panic!("invalid URI `{uri}`: {error}"));
+        let mut request = Request::new(Body::empty());
+        *request.method_mut() = method;
+        *request.uri_mut() = uri;
+        TestRequest {
+            endpoint: &mut self.endpoint,
+            request,
+        }
+    }
+
+    /// Starts a `GET` request to `uri`.
+    pub fn get(&mut self, uri: &str) -> TestRequest<'_, E> {
+        self.request(Method::GET, uri)
+    }
+
+    /// Starts a `POST` request to `uri`.
+    pub fn post(&mut self, uri: &str) -> TestRequest<'_, E> {
+        self.request(Method::POST, uri)
+    }
+
+    /// Starts a `PUT` request to `uri`.
+    pub fn put(&mut self, uri: &str) -> TestRequest<'_, E> {
+        self.request(Method::PUT, uri)
+    }
+
+    /// Starts a `PATCH` request to `uri`.
+    pub fn patch(&mut self, uri: &str) -> TestRequest<'_, E> {
+        self.request(Method::PATCH, uri)
+    }
+
+    /// Starts a `DELETE` request to `uri`.
+    pub fn delete(&mut self, uri: &str) -> TestRequest<'_, E> {
+        self.request(Method::DELETE, uri)
+    }
+}
+
+/// A request being built by a [`TestClient`].
+pub struct TestRequest<'a, E> {
+    endpoint: &'a mut E,
+    request: Request,
+}
+
+impl<E> fmt::Debug for TestRequest<'_, E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TestRequest")
+            .field("request", &self.request.debug_full())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<E: Endpoint> TestRequest<'_, E> {
+    /// Appends a request header.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the name or the value is invalid.
+    #[must_use]
+    pub fn header<K, V>(mut self, name: K, value: V) -> Self
+    where
+        K: TryInto<HeaderName>,
+        K::Error: fmt::Debug,
+        V: TryInto<HeaderValue>,
+        V::Error: fmt::Debug,
+    {
+        self.request.headers_mut().append(
+            name.try_into().expect("invalid header name"),
+            value.try_into().expect("invalid header value"),
+        );
+        self
+    }
+
+    /// Sets the request body, and the `Content-Type` from its MIME type unless already
+    /// set.
+    #[must_use]
+    pub fn body(mut self, body: impl Into<Body>) -> Self {
+        self.request.set_body(body.into());
+        self
+    }
+
+    /// Sets a JSON request body.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `value` cannot be serialized.
+    #[cfg(feature = "json")]
+    #[must_use]
+    pub fn json<T: serde::Serialize>(self, value: &T) -> Self {
+        let body = Body::from_json(value).expect("value cannot be serialized to JSON");
+        self.body(body)
+    }
+
+    /// Inserts a request extension, as a server adapter or an outer middleware would.
+    #[must_use]
+    pub fn extension<T: Send + Sync + 'static>(mut self, value: T) -> Self {
+        self.request.insert_extension(value);
+        self
+    }
+
+    /// Sends the request to the endpoint and returns its response.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the endpoint.
+    pub async fn send(self) -> Result<Response, E::Error> {
+        let Self {
+            endpoint,
+            mut request,
+        } = self;
+        endpoint.respond(&mut request).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        endpoint::WithMiddleware, middleware::headers::DefaultHeaders, test::MockEndpoint,
+        ResponseExt,
+    };
+    use http::{header, StatusCode};
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Tenant(&'static str);
+
+    #[tokio::test]
+    async fn builds_requests() {
+        let mock = MockEndpoint::from_fn(|request| {
+            let mut response = Response::new(Body::empty());
+            *response.status_mut() = StatusCode::ACCEPTED;
+            let value = request.headers[header::CONTENT_TYPE].clone();
+            response.headers_mut().insert("x-seen", value);
+            response
+        });
+        let endpoint = WithMiddleware::new(
+            mock.clone(),
+            DefaultHeaders::new().header(header::SERVER, HeaderValue::from_static("mock")),
+        );
+        let mut client = TestClient::new(endpoint);
+
+        let response = client
+            .patch("/items/7")
+            .header("x-trace", "1")
+            .header("x-trace", "2")
+            .body("patched")
+            .extension(Tenant("acme"))
+            .send()
+            .await
+            .unwrap();
+        response
+            .assert_status(StatusCode::ACCEPTED)
+            .assert_header("x-seen", "text/plain; charset=utf-8")
+            .assert_header("server", "mock");
+
+        let request = mock.last_request().unwrap();
+        assert_eq!(request.method, Method::PATCH);
+        assert_eq!(request.uri, "/items/7");
+        let traces: alloc::vec::Vec<_> = request.headers.get_all("x-trace").iter().collect();
+        assert_eq!(traces, ["1", "2"]);
+        assert_eq!(request.text(), "patched");
+    }
+
+    #[cfg(feature = "json")]
+    #[tokio::test]
+    async fn json_round_trip() {
+        use crate::test::Reply;
+
+        let mock = MockEndpoint::new().reply(
+            Reply::ok()
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(r#"{"id":7,"tags":["a"]}"#),
+        );
+        let mut client = TestClient::new(mock.clone());
+        let mut response = client
+            .post("/items")
+            .json(&serde_json::json!({ "name": "pen" }))
+            .send()
+            .await
+            .unwrap();
+        response
+            .assert_body_json(serde_json::json!({ "id": 7, "tags": ["a"] }))
+            .await;
+        // The body stays readable after the assertion.
+        assert_eq!(
+            response.body_mut().as_str().await.unwrap(),
+            r#"{"id":7,"tags":["a"]}"#
+        );
+
+        let request = mock.last_request().unwrap();
+        assert_eq!(request.headers[header::CONTENT_TYPE], "application/json");
+        assert_eq!(request.text(), r#"{"name":"pen"}"#);
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "expected status 404 Not Found, got 200 OK")]
+    async fn status_mismatches_panic() {
+        let response = TestClient::new(MockEndpoint::new())
+            .get("/")
+            .send()
+            .await
+            .unwrap();
+        response.assert_status(StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "expected header `x-missing`")]
+    async fn missing_headers_panic() {
+        let response = TestClient::new(MockEndpoint::new())
+            .get("/")
+            .send()
+            .await
+            .unwrap();
+        response.assert_header("x-missing", "1");
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid URI")]
+    fn invalid_uris_panic() {
+        let _ = TestClient::new(MockEndpoint::new()).get("http://[::1");
+    }
+}
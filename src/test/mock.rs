@@ -0,0 +1,308 @@
+//! Mock endpoints recording the requests they receive.
+
+extern crate std;
+
+use alloc::{borrow::Cow, boxed::Box, string::String, sync::Arc, vec::Vec};
+use core::fmt;
+use std::sync::Mutex;
+
+use bytes::Bytes;
+use futures_timer::Delay;
+use http::{HeaderMap, Method, Uri, Version};
+
+use super::Reply;
+use crate::{BodyError, Endpoint, Request, Response};
+
+/// A request received by a [`MockEndpoint`], with its body buffered.
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    /// The method of the request.
+    pub method: Method,
+    /// The URI of the request.
+    pub uri: Uri,
+    /// The HTTP version of the request.
+    pub version: Version,
+    /// The headers of the request.
+    pub headers: HeaderMap,
+    /// The whole body of the request.
+    pub body: Bytes,
+}
+
+impl RecordedRequest {
+    /// Returns the body as text, replacing invalid UTF-8.
+    pub fn text(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(&self.body)
+    }
+}
+
+type Respond = Box<dyn FnMut(&RecordedRequest) -> Response + Send>;
+
+enum Responder {
+    Replies { replies: Vec<Reply>, served: usize },
+    Fn(Respond),
+}
+
+struct State {
+    responder: Responder,
+    requests: Vec<RecordedRequest>,
+}
+
+/// An endpoint answering with canned replies or a closure, and recording every request
+/// it receives.
+///
+/// Clones share the replies and the recorded requests, so a test can keep a clone to
+/// inspect what reached the endpoint after moving it into a middleware stack. Request
+/// bodies are buffered before answering and stay readable afterwards; a body that fails
+/// to buffer fails the call with its error.
+///
+/// # Examples
+///
+/// ```rust
+/// use http_kit::endpoint::WithMiddleware;
+/// use http_kit::middleware::request_id::RequestIdMiddleware;
+/// use http_kit::test::{MockEndpoint, Reply, TestClient};
+/// use http_kit::StatusCode;
+///
+/// # async fn example() {
+/// let mock = MockEndpoint::new().reply(Reply::status(StatusCode::CREATED));
+/// let endpoint = WithMiddleware::new(mock.clone(), RequestIdMiddleware::new());
+/// let mut client = TestClient::new(endpoint);
+///
+/// let response = client.post("/items").body("pen").send().await.unwrap();
+/// assert_eq!(response.status(), StatusCode::CREATED);
+///
+/// let request = mock.last_request().unwrap();
+/// assert_eq!(request.text(), "pen");
+/// assert!(request.headers.contains_key("x-request-id"));
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct MockEndpoint {
+    state: Arc<Mutex<State>>,
+}
+
+impl fmt::Debug for MockEndpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MockEndpoint")
+            .field("calls", &self.calls())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for MockEndpoint {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MockEndpoint {
+    /// Creates a mock answering `200 OK` with an empty body until replies are added.
+    pub fn new() -> Self {
+        Self::with(Responder::Replies {
+            replies: Vec::new(),
+            served: 0,
+        })
+    }
+
+    /// Creates a mock answering with `respond`, called with each recorded request.
+    ///
+    /// The closure runs while the mock is locked, so it must not call the methods of
+    /// the mock itself.
+    pub fn from_fn<F>(respond: F) -> Self
+    where
+        F: FnMut(&RecordedRequest) -> Response + Send + 'static,
+    {
+        Self::with(Responder::Fn(Box::new(respond)))
+    }
+
+    fn with(responder: Responder) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(State {
+                responder,
+                requests: Vec::new(),
+            })),
+        }
+    }
+
+    /// Appends a canned reply, replacing the closure of [`MockEndpoint::from_fn`].
+    ///
+    /// Replies are served in order, and the last one repeats once the sequence is
+    /// exhausted. Their delays are honored, but they are served whatever the path and
+    /// required headers.
+    #[must_use]
+    pub fn reply(self, reply: Reply) -> Self {
+        {
+            let mut state = self.lock();
+            match &mut state.responder {
+                Responder::Replies { replies, .. } => replies.push(reply),
+                Responder::Fn(_) => {
+                    state.responder = Responder::Replies {
+                        replies: alloc::vec![reply],
+                        served: 0,
+                    }
+                }
+            }
+        }
+        self
+    }
+
+    /// Returns the requests received so far, oldest first.
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.lock().requests.clone()
+    }
+
+    /// Returns the last request received, if any.
+    pub fn last_request(&self) -> Option<RecordedRequest> {
+        self.lock().requests.last().cloned()
+    }
+
+    /// Returns the number of requests received so far.
+    pub fn calls(&self) -> usize {
+        self.lock().requests.len()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().expect("mock lock poisoned")
+    }
+}
+
+impl Endpoint for MockEndpoint {
+    type Error = BodyError;
+
+    async fn respond(&mut self, request: &mut Request) -> Result<Response, Self::Error> {
+        let body = Bytes::copy_from_slice(request.body_mut().as_bytes().await?);
+        let recorded = RecordedRequest {
+            method: request.method().clone(),
+            uri: request.uri().clone(),
+            version: request.version(),
+            headers: request.headers().clone(),
+            body,
+        };
+
+        let reply = {
+            let mut state = self.lock();
+            let State {
+                responder,
+                requests,
+            } = &mut *state;
+            requests.push(recorded);
+            let recorded = requests.last().expect("the request was just recorded");
+            match responder {
+                Responder::Fn(respond) => return Ok(respond(recorded)),
+                Responder::Replies { replies, served } => {
+                    let reply = replies.get((*served).min(replies.len().saturating_sub(1)));
+                    *served += 1;
+                    reply.cloned().unwrap_or_else(Reply::ok)
+                }
+            }
+        };
+        if let Some(delay) = reply.delay {
+            Delay::new(delay).await;
+        }
+        Ok(reply.into_response())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test::TestClient, Body, RequestExt};
+    use futures_lite::stream;
+    use http::{header, StatusCode};
+
+    #[tokio::test]
+    async fn streamed_bodies_are_recorded_whole() {
+        let mock = MockEndpoint::new();
+        let mut client = TestClient::new(mock.clone());
+        let chunks = ["str", "eam", "ed"].map(|chunk| Ok::<_, BodyError>(Bytes::from(chunk)));
+        let response = client
+            .put("/upload?part=2")
+            .header(header::CONTENT_TYPE, "text/plain")
+            .body(Body::from_stream(stream::iter(chunks)))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let requests = mock.requests();
+        assert_eq!(requests.len(), 1);
+        let request = &requests[0];
+        assert_eq!(request.method, Method::PUT);
+        assert_eq!(request.uri, "/upload?part=2");
+        assert_eq!(request.headers[header::CONTENT_TYPE], "text/plain");
+        assert_eq!(request.body, "streamed");
+    }
+
+    #[tokio::test]
+    async fn bodies_stay_readable_after_recording() {
+        let mut mock = MockEndpoint::new();
+        let mut request = Request::new(Body::from_text("kept"));
+        mock.respond(&mut request).await.unwrap();
+        assert_eq!(request.body_mut().as_str().await.unwrap(), "kept");
+        assert_eq!(mock.last_request().unwrap().body, "kept");
+    }
+
+    #[tokio::test]
+    async fn replies_are_served_in_order() {
+        let mock = MockEndpoint::new()
+            .reply(Reply::status(StatusCode::SERVICE_UNAVAILABLE))
+            .reply(Reply::ok().body("up"));
+        let mut client = TestClient::new(mock.clone());
+        let mut statuses = Vec::new();
+        for _ in 0..3 {
+            statuses.push(client.get("/health").send().await.unwrap().status());
+        }
+        assert_eq!(
+            statuses,
+            [
+                StatusCode::SERVICE_UNAVAILABLE,
+                StatusCode::OK,
+                StatusCode::OK
+            ]
+        );
+        assert_eq!(mock.calls(), 3);
+
+        let response = TestClient::new(MockEndpoint::new())
+            .delete("/")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn closures_see_the_recorded_request() {
+        let mock = MockEndpoint::from_fn(|request| {
+            let mut response = Response::new(Body::from_text(request.text().to_uppercase()));
+            if request.method == Method::POST {
+                *response.status_mut() = StatusCode::CREATED;
+            }
+            response
+        });
+        let mut client = TestClient::new(mock.clone());
+        let response = client.post("/echo").body("hey").send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        assert_eq!(response.into_body().into_string().await.unwrap(), "HEY");
+
+        // Canned replies take over from the closure.
+        let mock = mock.reply(Reply::status(StatusCode::GONE));
+        let response = TestClient::new(mock.clone())
+            .get("/echo")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::GONE);
+        assert_eq!(mock.calls(), 2);
+        assert_eq!(mock.last_request().unwrap().method, Method::GET);
+    }
+
+    #[tokio::test]
+    async fn failing_bodies_fail_the_call() {
+        let mock = MockEndpoint::new();
+        let mut request = Request::new(Body::frozen());
+        let error = mock.clone().respond(&mut request).await.unwrap_err();
+        assert!(matches!(error, BodyError::BodyFrozen));
+        assert_eq!(mock.calls(), 0);
+        assert!(request.take_body().is_err());
+    }
+}
@@ -0,0 +1,263 @@
+//! Protocol upgrade plumbing.
+//!
+//! Protocols such as WebSocket or h2c take over the underlying connection once the
+//! server has answered with `101 Switching Protocols`. This module defines the handoff
+//! between server adapters and endpoints:
+//!
+//! 1. When the transport supports upgrades, the adapter calls [`pending`] and places the
+//!    returned [`OnUpgrade`] into the request extensions, keeping the [`UpgradeFulfiller`].
+//! 2. The endpoint takes the handle with [`RequestExt::on_upgrade`](crate::RequestExt::on_upgrade),
+//!    responds with `101` and calls [`ResponseExt::mark_upgrade`](crate::ResponseExt::mark_upgrade).
+//! 3. After flushing the response head, the adapter sees
+//!    [`ResponseExt::is_upgrade`](crate::ResponseExt::is_upgrade) and hands the raw
+//!    connection to [`UpgradeFulfiller::fulfill`], which resolves the [`OnUpgrade`] future.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use http_kit::{upgrade, Body, Request, RequestExt};
+//!
+//! let (on_upgrade, _fulfiller) = upgrade::pending();
+//! let mut request = Request::new(Body::empty());
+//! request.extensions_mut().insert(on_upgrade);
+//!
+//! assert!(request.on_upgrade().is_some());
+//! assert!(request.on_upgrade().is_none());
+//! ```
+
+use alloc::boxed::Box;
+use core::{
+    fmt,
+    future::{Future, IntoFuture},
+    pin::Pin,
+    task::{Context, Poll},
+};
+use futures_lite::{io, AsyncRead, AsyncWrite};
+
+trait Io: AsyncRead + AsyncWrite + Send + Unpin {}
+
+impl<T: AsyncRead + AsyncWrite + Send + Unpin> Io for T {}
+
+/// The raw connection handed over after a successful upgrade.
+pub struct Upgraded {
+    io: Box<dyn Io>,
+}
+
+impl Upgraded {
+    /// Wraps a duplex connection.
+    pub fn new(io: impl AsyncRead + AsyncWrite + Send + Unpin + 'static) -> Self {
+        Self { io: Box::new(io) }
+    }
+}
+
+impl fmt::Debug for Upgraded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Upgraded").finish_non_exhaustive()
+    }
+}
+
+impl AsyncRead for Upgraded {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.io).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for Upgraded {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.io).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.io).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.io).poll_close(cx)
+    }
+}
+
+http_error!(
+    /// Error returned when an upgrade is never fulfilled.
+    ///
+    /// This happens when the adapter drops the [`UpgradeFulfiller`], for example because
+    /// the connection closed before the `101` response was flushed.
+    pub UpgradeError,
+    http::StatusCode::INTERNAL_SERVER_ERROR,
+    "connection upgrade was canceled"
+);
+
+/// A handle resolving to the upgraded connection.
+///
+/// Server adapters insert it into the request extensions; await it (it implements
+/// [`IntoFuture`]) after responding with a response marked as an upgrade.
+#[derive(Clone)]
+pub struct OnUpgrade {
+    rx: async_channel::Receiver<Upgraded>,
+}
+
+impl fmt::Debug for OnUpgrade {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OnUpgrade").finish_non_exhaustive()
+    }
+}
+
+impl IntoFuture for OnUpgrade {
+    type Output = Result<Upgraded, UpgradeError>;
+    type IntoFuture = Pin<Box<dyn Future<Output = Self::Output> + Send>>;
+
+    fn into_future(self) -> Self::IntoFuture {
+        Box::pin(async move { self.rx.recv().await.map_err(|_| UpgradeError::new()) })
+    }
+}
+
+/// The adapter side of a pending upgrade, created by [`pending`].
+pub struct UpgradeFulfiller {
+    tx: async_channel::Sender<Upgraded>,
+}
+
+impl fmt::Debug for UpgradeFulfiller {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UpgradeFulfiller").finish_non_exhaustive()
+    }
+}
+
+impl UpgradeFulfiller {
+    /// Hands the raw connection to the waiting [`OnUpgrade`].
+    ///
+    /// # Errors
+    ///
+    /// Returns the connection back if every [`OnUpgrade`] handle has been dropped.
+    pub fn fulfill(
+        self,
+        io: impl AsyncRead + AsyncWrite + Send + Unpin + 'static,
+    ) -> Result<(), Upgraded> {
+        self.tx
+            .try_send(Upgraded::new(io))
+            .map_err(|error| error.into_inner())
+    }
+}
+
+/// Creates a pending upgrade, returning the endpoint handle and the adapter side.
+pub fn pending() -> (OnUpgrade, UpgradeFulfiller) {
+    let (tx, rx) = async_channel::bounded(1);
+    (OnUpgrade { rx }, UpgradeFulfiller { tx })
+}
+
+/// Marker inserted into response extensions by
+/// [`ResponseExt::mark_upgrade`](crate::ResponseExt::mark_upgrade).
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct UpgradeMarker;
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use crate::{Body, Endpoint, Request, RequestExt, Response, ResponseExt, StatusCode};
+    use alloc::{sync::Arc, vec::Vec};
+    use futures_lite::{AsyncReadExt, AsyncWriteExt};
+    use std::sync::Mutex;
+
+    // In-memory connection: reads from a fixed buffer, records everything written.
+    struct Duplex {
+        input: io::Cursor<Vec<u8>>,
+        output: Arc<Mutex<Vec<u8>>>,
+    }
+
+    impl AsyncRead for Duplex {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut [u8],
+        ) -> Poll<io::Result<usize>> {
+            Pin::new(&mut self.input).poll_read(cx, buf)
+        }
+    }
+
+    impl AsyncWrite for Duplex {
+        fn poll_write(
+            self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            self.output.lock().unwrap().extend_from_slice(buf);
+            Poll::Ready(Ok(buf.len()))
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    #[derive(Default)]
+    struct EchoUpgrade {
+        task: Option<tokio::task::JoinHandle<()>>,
+    }
+
+    impl Endpoint for EchoUpgrade {
+        type Error = core::convert::Infallible;
+
+        async fn respond(&mut self, request: &mut Request) -> Result<Response, Self::Error> {
+            let on_upgrade = request.on_upgrade().expect("adapter supports upgrades");
+            self.task = Some(tokio::spawn(async move {
+                let mut io = on_upgrade.await.unwrap();
+                let mut buf = [0; 5];
+                io.read_exact(&mut buf).await.unwrap();
+                io.write_all(&buf).await.unwrap();
+                io.flush().await.unwrap();
+            }));
+
+            let mut response = Response::new(Body::empty());
+            *response.status_mut() = StatusCode::SWITCHING_PROTOCOLS;
+            response.mark_upgrade();
+            Ok(response)
+        }
+    }
+
+    #[tokio::test]
+    async fn adapter_fulfills_upgrade() {
+        let (on_upgrade, fulfiller) = pending();
+        let mut request = Request::new(Body::empty());
+        request.extensions_mut().insert(on_upgrade);
+
+        let mut endpoint = EchoUpgrade::default();
+        let response = endpoint.respond(&mut request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SWITCHING_PROTOCOLS);
+        assert!(response.is_upgrade());
+
+        let output = Arc::new(Mutex::new(Vec::new()));
+        fulfiller
+            .fulfill(Duplex {
+                input: io::Cursor::new(b"hello".to_vec()),
+                output: output.clone(),
+            })
+            .unwrap();
+        endpoint.task.take().unwrap().await.unwrap();
+
+        assert_eq!(&*output.lock().unwrap(), b"hello");
+    }
+
+    #[tokio::test]
+    async fn dropped_fulfiller_cancels() {
+        let (on_upgrade, fulfiller) = pending();
+        drop(fulfiller);
+        assert_eq!(on_upgrade.await.unwrap_err(), UpgradeError::new());
+    }
+
+    #[test]
+    fn unmarked_response_is_not_upgrade() {
+        assert!(!Response::new(Body::empty()).is_upgrade());
+    }
+}
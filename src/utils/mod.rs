@@ -9,6 +9,8 @@
 //! - [`Bytes`] - Efficient byte buffer for HTTP body data
 //! - [`ByteStr`] - UTF-8 validated byte string for text content
 //! - All items from `futures_lite` - Async utilities and traits
+//! - [`percent_encode_path_segment`], [`percent_encode_query_component`] and
+//!   [`percent_decode`] - Percent-encoding of URI components
 //!
 //! # Examples
 //!
@@ -41,6 +43,12 @@
 //! # }
 //! ```
 
+pub mod sniff;
+
+pub use crate::percent::{
+    percent_decode, percent_encode_path_segment, percent_encode_query_component, DecodeError,
+};
+
 /// Efficient, reference-counted byte buffer for HTTP body data.
 ///
 /// `Bytes` is a cheaply cloneable and sliceable chunk of contiguous memory.
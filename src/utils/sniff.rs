@@ -0,0 +1,235 @@
+//! Content sniffing from magic bytes.
+//!
+//! [`sniff`] inspects the first bytes of a payload and reports the media type implied by
+//! its signature. It is meant for detecting mismatches between a declared `Content-Type`
+//! and the actual content (see [`UploadTypeGuard`](crate::middleware::sniff::UploadTypeGuard)),
+//! not as a replacement for the declared type.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use http_kit::utils::sniff::sniff;
+//!
+//! let mime = sniff(b"%PDF-1.7\n").unwrap();
+//! assert_eq!(mime, mime::APPLICATION_PDF);
+//! assert!(sniff(&[0x01, 0x02, 0x03]).is_none());
+//! ```
+
+use mime::Mime;
+
+/// Number of bytes [`sniff`] needs to recognize every supported signature.
+pub const SNIFF_LEN: usize = 512;
+
+const ZIP: &str = "application/zip";
+const GZIP: &str = "application/gzip";
+const WASM: &str = "application/wasm";
+const ELF: &str = "application/x-elf";
+const PE: &str = "application/vnd.microsoft.portable-executable";
+
+const SIGNATURES: &[(&[u8], &str)] = &[
+    (b"\x89PNG\r\n\x1a\n", "image/png"),
+    (b"\xFF\xD8\xFF", "image/jpeg"),
+    (b"GIF87a", "image/gif"),
+    (b"GIF89a", "image/gif"),
+    (b"%PDF-", "application/pdf"),
+    (b"PK\x03\x04", ZIP),
+    (b"PK\x05\x06", ZIP),
+    (b"PK\x07\x08", ZIP),
+    (b"\x1F\x8B\x08", GZIP),
+    (b"\0asm", WASM),
+    (b"\x7FELF", ELF),
+    (b"\xEF\xBB\xBF", "text/plain; charset=utf-8"),
+    (b"\xFE\xFF", "text/plain; charset=utf-16be"),
+    (b"\xFF\xFE", "text/plain; charset=utf-16le"),
+];
+
+fn parse(mime: &str) -> Mime {
+    mime.parse().expect("signature media types are valid")
+}
+
+// A DOS header is only reported as a PE executable when the `PE\0\0` header it points to
+// is either out of reach of the prefix or actually present.
+fn is_pe(prefix: &[u8]) -> bool {
+    if !prefix.starts_with(b"MZ") {
+        return false;
+    }
+    let Some(offset) = prefix.get(0x3C..0x40) else {
+        return true;
+    };
+    let offset = u32::from_le_bytes([offset[0], offset[1], offset[2], offset[3]]) as usize;
+    match prefix.get(offset..offset.saturating_add(4)) {
+        Some(header) => header == b"PE\0\0",
+        None => true,
+    }
+}
+
+// Bytes that never appear in text (WHATWG MIME sniffing "binary data bytes").
+fn is_binary_byte(byte: u8) -> bool {
+    matches!(byte, 0x00..=0x08 | 0x0B | 0x0E..=0x1A | 0x1C..=0x1F)
+}
+
+fn looks_like_text(prefix: &[u8]) -> bool {
+    if prefix.iter().copied().any(is_binary_byte) {
+        return false;
+    }
+    match core::str::from_utf8(prefix) {
+        Ok(_) => true,
+        // A multi-byte character may be cut off at the end of the prefix.
+        Err(error) => error.error_len().is_none(),
+    }
+}
+
+/// Detects the media type of a payload from its first bytes.
+///
+/// Recognizes PNG, JPEG, GIF, PDF, ZIP (including Office and OpenDocument files), gzip,
+/// WebAssembly, ELF and PE executables, and text with a UTF-8 or UTF-16 byte order mark.
+/// Prefixes without a signature that are valid UTF-8 free of control characters are
+/// reported as `text/plain`. Returns `None` for empty or unrecognized prefixes.
+///
+/// Pass at least [`SNIFF_LEN`] bytes when available.
+pub fn sniff(prefix: &[u8]) -> Option<Mime> {
+    if prefix.is_empty() {
+        return None;
+    }
+    if let Some((_, mime)) = SIGNATURES
+        .iter()
+        .find(|(signature, _)| prefix.starts_with(signature))
+    {
+        return Some(parse(mime));
+    }
+    if is_pe(prefix) {
+        return Some(parse(PE));
+    }
+    looks_like_text(prefix).then_some(mime::TEXT_PLAIN)
+}
+
+/// Returns whether `mime` denotes a native executable (ELF or PE) as reported by [`sniff`].
+pub fn is_executable(mime: &Mime) -> bool {
+    let essence = mime.essence_str();
+    essence == ELF || essence == PE
+}
+
+/// Returns whether `mime` as reported by [`sniff`] is content that can be run or
+/// unpacked: native executables, WebAssembly, archives and PDF documents.
+///
+/// Uploads are most often rejected for carrying such content under an innocuous type.
+pub fn is_dangerous(mime: &Mime) -> bool {
+    is_executable(mime) || matches!(mime.essence_str(), ZIP | GZIP | WASM | "application/pdf")
+}
+
+fn is_textual(mime: &Mime) -> bool {
+    mime.type_() == mime::TEXT
+        || mime.suffix() == Some(mime::JSON)
+        || mime.suffix() == Some(mime::XML)
+        || (mime.type_() == mime::APPLICATION
+            && matches!(
+                mime.subtype().as_str(),
+                "json" | "xml" | "javascript" | "ecmascript" | "x-www-form-urlencoded" | "x-ndjson"
+            ))
+}
+
+/// Returns whether content sniffed as `sniffed` is consistent with the `declared` type.
+///
+/// Besides identical essences, this accepts well-known aliases and containers: ZIP
+/// content for Office, OpenDocument, JAR and EPUB types, and plain text for any textual
+/// type such as JSON, XML or form data. `application/octet-stream` and `multipart/*`
+/// declare no particular content, so anything matches them.
+pub fn matches(declared: &Mime, sniffed: &Mime) -> bool {
+    let declared_essence = declared.essence_str();
+    if declared_essence == sniffed.essence_str()
+        || declared_essence == "application/octet-stream"
+        || declared.type_() == mime::MULTIPART
+    {
+        return true;
+    }
+    match sniffed.essence_str() {
+        ZIP => {
+            let subtype = declared.subtype().as_str();
+            declared.type_() == mime::APPLICATION
+                && (subtype.starts_with("vnd.openxmlformats-officedocument.")
+                    || subtype.starts_with("vnd.oasis.opendocument.")
+                    || matches!(subtype, "java-archive" | "x-zip-compressed")
+                    || declared.suffix().is_some_and(|suffix| suffix == "zip"))
+        }
+        GZIP => declared_essence == "application/x-gzip",
+        "image/jpeg" => matches!(declared_essence, "image/jpg" | "image/pjpeg"),
+        "text/plain" => is_textual(declared),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn recognizes_signatures() {
+        let fixtures: &[(&[u8], &str)] = &[
+            (b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR", "image/png"),
+            (b"\xFF\xD8\xFF\xE0\0\x10JFIF", "image/jpeg"),
+            (b"GIF89a\x01\0\x01\0", "image/gif"),
+            (b"%PDF-1.4\n%\xE2\xE3", "application/pdf"),
+            (b"PK\x03\x04\x14\0\x06\0", "application/zip"),
+            (b"\x1F\x8B\x08\0\0\0\0\0", "application/gzip"),
+            (b"\0asm\x01\0\0\0", "application/wasm"),
+            (b"\x7FELF\x02\x01\x01", "application/x-elf"),
+            (
+                b"MZ\x90\0\x03\0",
+                "application/vnd.microsoft.portable-executable",
+            ),
+            (b"\xEF\xBB\xBFhello", "text/plain"),
+            (b"\xFF\xFEh\0i\0", "text/plain"),
+            (b"{\"name\":\"caf\xC3", "text/plain"),
+        ];
+        for (prefix, expected) in fixtures {
+            let sniffed = sniff(prefix).unwrap();
+            assert_eq!(sniffed.essence_str(), *expected, "prefix {prefix:?}");
+        }
+        assert_eq!(
+            sniff(b"\xFE\xFF\0h").unwrap().get_param("charset").unwrap(),
+            "utf-16be"
+        );
+    }
+
+    #[test]
+    fn unknown_and_binary_prefixes() {
+        assert!(sniff(b"").is_none());
+        assert!(sniff(b"\x01\x02\x03\x04").is_none());
+        assert!(sniff(b"text\0with nul").is_none());
+    }
+
+    #[test]
+    fn dos_header_requires_pe_signature_when_visible() {
+        let mut pe = Vec::from(&b"MZ"[..]);
+        pe.resize(0x80, 0);
+        pe[0x3C] = 0x40;
+        pe[0x40..0x44].copy_from_slice(b"PE\0\0");
+        assert!(is_executable(&sniff(&pe).unwrap()));
+
+        pe[0x40..0x44].copy_from_slice(b"MZMZ");
+        assert!(sniff(&pe).is_none());
+    }
+
+    #[test]
+    fn compatible_declarations() {
+        let zip = sniff(b"PK\x03\x04").unwrap();
+        let docx = "application/vnd.openxmlformats-officedocument.wordprocessingml.document"
+            .parse()
+            .unwrap();
+        assert!(matches(&docx, &zip));
+        assert!(matches(&mime::APPLICATION_JSON, &mime::TEXT_PLAIN));
+        assert!(matches(
+            &mime::IMAGE_PNG,
+            &sniff(b"\x89PNG\r\n\x1a\n").unwrap()
+        ));
+        assert!(!matches(&mime::IMAGE_PNG, &zip));
+        assert!(!matches(&mime::TEXT_HTML, &mime::APPLICATION_PDF));
+
+        assert!(matches(&mime::APPLICATION_OCTET_STREAM, &mime::IMAGE_PNG));
+        assert!(matches(&mime::APPLICATION_OCTET_STREAM, &mime::TEXT_PLAIN));
+        assert!(matches(&mime::MULTIPART_FORM_DATA, &mime::TEXT_PLAIN));
+        assert!(is_dangerous(&zip));
+        assert!(!is_dangerous(&mime::TEXT_PLAIN));
+    }
+}
@@ -1,4 +1,41 @@
-//! WebSocket message and configuration types.
+//! WebSocket messages and configuration, the HTTP side of the handshake, and framing
+//! over the upgraded connection with [`WebSocketStream`].
+//!
+//! Servers check upgrade requests with
+//! [`RequestExt::is_websocket_upgrade`](crate::RequestExt::is_websocket_upgrade) and
+//! answer them with [`ResponseExt::websocket_accept`](crate::ResponseExt::websocket_accept);
+//! clients build them with [`RequestExt::websocket`](crate::RequestExt::websocket) and
+//! check the answer with
+//! [`ResponseExt::verify_websocket_accept`](crate::ResponseExt::verify_websocket_accept).
+//!
+//! # Examples
+//!
+//! ```rust
+//! # #[cfg(feature = "std")]
+//! # {
+//! use http_kit::ws::WebSocketConfig;
+//! use http_kit::{header, Request, RequestExt, Response, ResponseExt, StatusCode};
+//!
+//! let request = Request::websocket("/chat".parse().unwrap());
+//! assert!(request.is_websocket_upgrade());
+//!
+//! let config = WebSocketConfig::default();
+//! let response = Response::websocket_accept(&request, &config).unwrap();
+//! assert_eq!(response.status(), StatusCode::SWITCHING_PROTOCOLS);
+//!
+//! let key = request.headers()[header::SEC_WEBSOCKET_KEY].as_bytes();
+//! assert!(response.verify_websocket_accept(key).is_ok());
+//! # }
+//! ```
+
+mod handshake;
+mod stream;
+
+#[cfg(feature = "std")]
+pub(crate) use handshake::request;
+pub(crate) use handshake::{accept, check_request, verify};
+pub use handshake::{accept_key, WsHandshakeError};
+pub use stream::{Role, WebSocketStream, WsError};
 
 use alloc::{borrow::ToOwned, string::String, vec::Vec};
 use bytes::Bytes;
@@ -17,8 +54,134 @@ pub enum WebSocketMessage {
     /// Pong control frame.
     Pong(Bytes),
 
-    /// Close control frame.
-    Close,
+    /// Close control frame, with the status code and reason of the closure if any.
+    Close(Option<CloseFrame>),
+}
+
+/// Status code and reason of a [`WebSocketMessage::Close`] frame.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CloseFrame {
+    /// Why the connection is being closed.
+    pub code: CloseCode,
+    /// Human-readable explanation, at most 123 bytes on the wire.
+    pub reason: ByteStr,
+}
+
+/// Status code of a close frame, from the IANA WebSocket Close Code Number Registry
+/// (RFC 6455, section 7.4).
+///
+/// # Examples
+///
+/// ```rust
+/// use http_kit::ws::CloseCode;
+///
+/// assert_eq!(CloseCode::from_u16(1001), CloseCode::Away);
+/// assert_eq!(CloseCode::Away.into_u16(), 1001);
+/// assert_eq!(CloseCode::from_u16(4000), CloseCode::Other(4000));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CloseCode {
+    /// 1000, the purpose of the connection has been fulfilled.
+    Normal,
+    /// 1001, the endpoint is going away, such as a server shutting down.
+    Away,
+    /// 1002, the peer violated the protocol.
+    Protocol,
+    /// 1003, the endpoint received a type of data it cannot accept.
+    Unsupported,
+    /// 1005, reserved for closures without a status code; never sent.
+    NoStatus,
+    /// 1006, reserved for connections lost without a close frame; never sent.
+    Abnormal,
+    /// 1007, a message had data inconsistent with its type, such as invalid UTF-8.
+    InvalidData,
+    /// 1008, a message violated the endpoint's policy.
+    Policy,
+    /// 1009, a message was too big to process.
+    TooBig,
+    /// 1010, the server did not negotiate an extension the client requires.
+    MandatoryExtension,
+    /// 1011, the server met an unexpected condition.
+    InternalError,
+    /// 1012, the server is restarting.
+    ServiceRestart,
+    /// 1013, the server is overloaded; the client should try again later.
+    TryAgainLater,
+    /// 1014, a gateway received an invalid response from upstream.
+    BadGateway,
+    /// 1015, reserved for TLS handshake failures; never sent.
+    TlsHandshake,
+    /// Any other code, such as the library and application codes 3000 to 4999.
+    Other(u16),
+}
+
+macro_rules! close_codes {
+    ($($code:literal => $variant:ident,)*) => {
+        impl CloseCode {
+            /// Returns the close code with the number `code`.
+            pub const fn from_u16(code: u16) -> Self {
+                match code {
+                    $($code => Self::$variant,)*
+                    other => Self::Other(other),
+                }
+            }
+
+            /// Returns the number of the close code.
+            pub const fn into_u16(self) -> u16 {
+                match self {
+                    $(Self::$variant => $code,)*
+                    Self::Other(code) => code,
+                }
+            }
+        }
+
+        #[cfg(test)]
+        const REGISTERED: &[(u16, CloseCode)] = &[$(($code, CloseCode::$variant)),*];
+    };
+}
+
+close_codes! {
+    1000 => Normal,
+    1001 => Away,
+    1002 => Protocol,
+    1003 => Unsupported,
+    1005 => NoStatus,
+    1006 => Abnormal,
+    1007 => InvalidData,
+    1008 => Policy,
+    1009 => TooBig,
+    1010 => MandatoryExtension,
+    1011 => InternalError,
+    1012 => ServiceRestart,
+    1013 => TryAgainLater,
+    1014 => BadGateway,
+    1015 => TlsHandshake,
+}
+
+impl CloseCode {
+    /// Returns whether the code may appear in a close frame.
+    ///
+    /// The reserved codes 1005, 1006 and 1015, the unassigned codes below 3000 and the
+    /// codes outside 1000 to 4999 may not.
+    pub const fn is_allowed(self) -> bool {
+        match self {
+            Self::NoStatus | Self::Abnormal | Self::TlsHandshake => false,
+            Self::Other(code) => matches!(code, 3000..=4999),
+            _ => true,
+        }
+    }
+}
+
+impl From<u16> for CloseCode {
+    fn from(code: u16) -> Self {
+        Self::from_u16(code)
+    }
+}
+
+impl From<CloseCode> for u16 {
+    fn from(code: CloseCode) -> Self {
+        code.into_u16()
+    }
 }
 
 /// Configuration applied when establishing a websocket connection.
@@ -32,6 +195,15 @@ pub struct WebSocketConfig {
     /// Maximum incoming websocket frame size in bytes.
     /// `None` means no limit.
     pub max_frame_size: Option<usize>,
+
+    /// Subprotocols the server supports, most preferred first.
+    ///
+    /// The handshake selects the first one the client offers in
+    /// `Sec-WebSocket-Protocol`, if any.
+    pub protocols: Vec<ByteStr>,
+
+    /// Whether a [`WebSocketStream`] answers pings with pongs by itself.
+    pub auto_pong: bool,
 }
 
 const DEFAULT_MAX_MESSAGE_SIZE: Option<usize> = Some(64 << 20);
@@ -42,6 +214,8 @@ impl Default for WebSocketConfig {
         Self {
             max_message_size: DEFAULT_MAX_MESSAGE_SIZE,
             max_frame_size: DEFAULT_MAX_FRAME_SIZE,
+            protocols: Vec::new(),
+            auto_pong: true,
         }
     }
 }
@@ -68,6 +242,27 @@ impl WebSocketConfig {
         self.max_frame_size = max_frame_size;
         self
     }
+
+    /// Sets the subprotocols the server supports, most preferred first.
+    #[must_use]
+    pub fn with_protocols<P: Into<ByteStr>>(
+        mut self,
+        protocols: impl IntoIterator<Item = P>,
+    ) -> Self {
+        self.protocols = protocols.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Sets whether a [`WebSocketStream`] answers pings with pongs by itself.
+    ///
+    /// Pings are still yielded by the stream either way.
+    ///
+    /// Defaults to `true`.
+    #[must_use]
+    pub const fn with_auto_pong(mut self, auto_pong: bool) -> Self {
+        self.auto_pong = auto_pong;
+        self
+    }
 }
 
 impl WebSocketMessage {
@@ -89,9 +284,18 @@ impl WebSocketMessage {
         Self::Pong(value.into())
     }
 
-    /// Construct a close message.
+    /// Construct a close message without a status code.
     pub fn close() -> Self {
-        Self::Close
+        Self::Close(None)
+    }
+
+    /// Construct a close message with a status code and a reason.
+    #[must_use]
+    pub fn close_with(code: CloseCode, reason: impl Into<ByteStr>) -> Self {
+        Self::Close(Some(CloseFrame {
+            code,
+            reason: reason.into(),
+        }))
     }
 
     /// Construct a binary message.
@@ -196,3 +400,60 @@ impl From<&[u8]> for WebSocketMessage {
         Self::Binary(value.to_vec().into())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn close_codes_round_trip() {
+        for &(number, code) in REGISTERED {
+            assert_eq!(CloseCode::from_u16(number), code);
+            assert_eq!(code.into_u16(), number);
+            assert_eq!(u16::from(CloseCode::from(number)), number);
+        }
+        for number in [0, 999, 1004, 1016, 2999, 3000, 4999, 5000, u16::MAX] {
+            assert_eq!(CloseCode::from_u16(number), CloseCode::Other(number));
+            assert_eq!(CloseCode::Other(number).into_u16(), number);
+        }
+    }
+
+    #[test]
+    fn allowed_close_codes() {
+        let allowed: Vec<u16> = REGISTERED
+            .iter()
+            .filter(|(_, code)| code.is_allowed())
+            .map(|(number, _)| *number)
+            .collect();
+        assert_eq!(
+            allowed,
+            [1000, 1001, 1002, 1003, 1007, 1008, 1009, 1010, 1011, 1012, 1013, 1014]
+        );
+        for (number, is_allowed) in [
+            (999, false),
+            (1004, false),
+            (2999, false),
+            (3000, true),
+            (4999, true),
+            (5000, false),
+        ] {
+            assert_eq!(
+                CloseCode::from_u16(number).is_allowed(),
+                is_allowed,
+                "{number}"
+            );
+        }
+    }
+
+    #[test]
+    fn close_messages() {
+        assert_eq!(WebSocketMessage::close(), WebSocketMessage::Close(None));
+        let WebSocketMessage::Close(Some(frame)) =
+            WebSocketMessage::close_with(CloseCode::Away, "restarting")
+        else {
+            panic!("expected a close frame");
+        };
+        assert_eq!(frame.code, CloseCode::Away);
+        assert_eq!(frame.reason, "restarting");
+    }
+}
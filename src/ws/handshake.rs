@@ -0,0 +1,344 @@
+//! The HTTP side of the WebSocket opening handshake (RFC 6455, section 4).
+
+#[cfg(feature = "std")]
+extern crate std;
+
+#[cfg(feature = "std")]
+use alloc::string::String;
+use core::fmt;
+
+use bytestr::ByteStr;
+use http::{header, HeaderMap, HeaderValue, Method, StatusCode};
+
+use super::WebSocketConfig;
+use crate::{base64, sha1, Body, HttpError, Request, Response, ResponseExt};
+
+// Appended to the client key before hashing, see RFC 6455, section 1.3.
+const GUID: &[u8] = b"258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+const VERSION: &str = "13";
+
+/// Computes the `Sec-WebSocket-Accept` value answering the `Sec-WebSocket-Key` `key`.
+///
+/// # Examples
+///
+/// ```rust
+/// // The example of RFC 6455, section 1.3.
+/// let accept = http_kit::ws::accept_key(b"dGhlIHNhbXBsZSBub25jZQ==");
+/// assert_eq!(accept, "s3pPLMBiTxaQ9kYGzzhZRbK+xOo=");
+/// ```
+pub fn accept_key(key: &[u8]) -> ByteStr {
+    ByteStr::from(base64::encode(sha1::digest(&[key, GUID])))
+}
+
+/// Reasons a WebSocket handshake cannot complete.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum WsHandshakeError {
+    /// The request is not a `GET` asking to upgrade the connection to `websocket`.
+    NotUpgrade,
+    /// `Sec-WebSocket-Version` is missing or is not 13.
+    UnsupportedVersion,
+    /// `Sec-WebSocket-Key` is missing or is not 16 base64-encoded bytes.
+    InvalidKey,
+    /// The server answered with another status than `101 Switching Protocols`.
+    UnexpectedStatus(StatusCode),
+    /// The server did not confirm the upgrade, or its `Sec-WebSocket-Accept` does not
+    /// match the key.
+    InvalidAccept,
+}
+
+impl fmt::Display for WsHandshakeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotUpgrade => f.write_str("request is not a websocket upgrade"),
+            Self::UnsupportedVersion => f.write_str("unsupported websocket version"),
+            Self::InvalidKey => f.write_str("missing or invalid Sec-WebSocket-Key"),
+            Self::UnexpectedStatus(status) => {
+                write!(f, "websocket upgrade refused with status {status}")
+            }
+            Self::InvalidAccept => f.write_str("invalid websocket handshake response"),
+        }
+    }
+}
+
+impl core::error::Error for WsHandshakeError {}
+
+impl HttpError for WsHandshakeError {
+    /// `426 Upgrade Required` for unsupported versions, `400 Bad Request` for other
+    /// invalid requests, and `502 Bad Gateway` for invalid server responses.
+    fn status(&self) -> StatusCode {
+        match self {
+            Self::NotUpgrade | Self::InvalidKey => StatusCode::BAD_REQUEST,
+            Self::UnsupportedVersion => StatusCode::UPGRADE_REQUIRED,
+            Self::UnexpectedStatus(_) | Self::InvalidAccept => StatusCode::BAD_GATEWAY,
+        }
+    }
+}
+
+// Returns whether one of the comma-separated `name` headers is `token`, ignoring case.
+fn has_token(headers: &HeaderMap, name: header::HeaderName, token: &str) -> bool {
+    headers
+        .get_all(name)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|item| item.trim().eq_ignore_ascii_case(token))
+}
+
+// Checks an upgrade request and returns its key.
+pub(crate) fn check_request(request: &Request) -> Result<&HeaderValue, WsHandshakeError> {
+    let headers = request.headers();
+    if request.method() != Method::GET
+        || !has_token(headers, header::CONNECTION, "upgrade")
+        || !has_token(headers, header::UPGRADE, "websocket")
+    {
+        return Err(WsHandshakeError::NotUpgrade);
+    }
+    if headers
+        .get(header::SEC_WEBSOCKET_VERSION)
+        .is_none_or(|version| version.as_bytes().trim_ascii() != VERSION.as_bytes())
+    {
+        return Err(WsHandshakeError::UnsupportedVersion);
+    }
+    let key = headers
+        .get(header::SEC_WEBSOCKET_KEY)
+        .ok_or(WsHandshakeError::InvalidKey)?;
+    match base64::decode(key.as_bytes()) {
+        Ok(nonce) if nonce.len() == 16 => Ok(key),
+        _ => Err(WsHandshakeError::InvalidKey),
+    }
+}
+
+// Builds the `101 Switching Protocols` answer to a valid upgrade request.
+pub(crate) fn accept(
+    request: &Request,
+    config: &WebSocketConfig,
+) -> Result<Response, WsHandshakeError> {
+    let key = check_request(request)?;
+    let mut response = Response::new(Body::empty());
+    *response.status_mut() = StatusCode::SWITCHING_PROTOCOLS;
+    let headers = response.headers_mut();
+    headers.insert(header::UPGRADE, HeaderValue::from_static("websocket"));
+    headers.insert(header::CONNECTION, HeaderValue::from_static("Upgrade"));
+    let accept = accept_key(key.as_bytes());
+    if let Ok(value) = HeaderValue::from_str(&accept) {
+        headers.insert(header::SEC_WEBSOCKET_ACCEPT, value);
+    }
+    if let Some(protocol) = select_protocol(request.headers(), config) {
+        if let Ok(value) = HeaderValue::from_str(protocol) {
+            headers.insert(header::SEC_WEBSOCKET_PROTOCOL, value);
+        }
+    }
+    response.mark_upgrade();
+    Ok(response)
+}
+
+// Picks the first protocol of the server's list that the client offered.
+fn select_protocol<'a>(headers: &HeaderMap, config: &'a WebSocketConfig) -> Option<&'a str> {
+    config
+        .protocols
+        .iter()
+        .map(|protocol| &**protocol)
+        .find(|protocol| has_token(headers, header::SEC_WEBSOCKET_PROTOCOL, protocol))
+}
+
+// Builds a client upgrade request with a fresh key.
+#[cfg(feature = "std")]
+pub(crate) fn request(uri: http::Uri) -> Request {
+    let mut request = Request::new(Body::empty());
+    *request.uri_mut() = uri;
+    let headers = request.headers_mut();
+    headers.insert(header::CONNECTION, HeaderValue::from_static("Upgrade"));
+    headers.insert(header::UPGRADE, HeaderValue::from_static("websocket"));
+    headers.insert(
+        header::SEC_WEBSOCKET_VERSION,
+        HeaderValue::from_static(VERSION),
+    );
+    if let Ok(key) = HeaderValue::from_str(&new_key()) {
+        headers.insert(header::SEC_WEBSOCKET_KEY, key);
+    }
+    request
+}
+
+// Checks the server's answer to an upgrade request sent with `key`.
+pub(crate) fn verify(response: &Response, key: &[u8]) -> Result<(), WsHandshakeError> {
+    if response.status() != StatusCode::SWITCHING_PROTOCOLS {
+        return Err(WsHandshakeError::UnexpectedStatus(response.status()));
+    }
+    let headers = response.headers();
+    let accepted = headers
+        .get(header::SEC_WEBSOCKET_ACCEPT)
+        .is_some_and(|accept| accept.as_bytes() == accept_key(key).as_bytes());
+    if !accepted
+        || !has_token(headers, header::CONNECTION, "upgrade")
+        || !has_token(headers, header::UPGRADE, "websocket")
+    {
+        return Err(WsHandshakeError::InvalidAccept);
+    }
+    Ok(())
+}
+
+// A `Sec-WebSocket-Key`: 16 bytes that only need to differ between handshakes, taken
+// from the per-process random keys of `RandomState`.
+#[cfg(feature = "std")]
+fn new_key() -> String {
+    base64::encode(nonce())
+}
+
+// Sixteen unpredictable bytes, for handshake keys and frame masks.
+#[cfg(feature = "std")]
+pub(super) fn nonce() -> [u8; 16] {
+    use core::hash::BuildHasher;
+    use std::collections::hash_map::RandomState;
+
+    let high = RandomState::new().hash_one(0u8);
+    let low = RandomState::new().hash_one(1u8);
+    let mut nonce = [0u8; 16];
+    nonce[..8].copy_from_slice(&high.to_be_bytes());
+    nonce[8..].copy_from_slice(&low.to_be_bytes());
+    nonce
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RequestExt;
+
+    // The opening handshake of RFC 6455, section 1.2.
+    fn rfc_request() -> Request {
+        let mut request = Request::new(Body::empty());
+        *request.uri_mut() = "/chat".parse().unwrap();
+        let headers = request.headers_mut();
+        headers.insert(header::HOST, HeaderValue::from_static("server.example.com"));
+        headers.insert(header::UPGRADE, HeaderValue::from_static("websocket"));
+        headers.insert(header::CONNECTION, HeaderValue::from_static("Upgrade"));
+        headers.insert(
+            header::SEC_WEBSOCKET_KEY,
+            HeaderValue::from_static("dGhlIHNhbXBsZSBub25jZQ=="),
+        );
+        headers.insert(
+            header::SEC_WEBSOCKET_PROTOCOL,
+            HeaderValue::from_static("chat, superchat"),
+        );
+        headers.insert(
+            header::SEC_WEBSOCKET_VERSION,
+            HeaderValue::from_static("13"),
+        );
+        request
+    }
+
+    #[test]
+    fn rfc_6455_handshake() {
+        let request = rfc_request();
+        assert!(request.is_websocket_upgrade());
+
+        let config = WebSocketConfig::default().with_protocols(["superchat", "chat"]);
+        let response = Response::websocket_accept(&request, &config).unwrap();
+        assert_eq!(response.status(), StatusCode::SWITCHING_PROTOCOLS);
+        assert!(response.is_upgrade());
+        let headers = response.headers();
+        assert_eq!(
+            headers[header::SEC_WEBSOCKET_ACCEPT],
+            "s3pPLMBiTxaQ9kYGzzhZRbK+xOo="
+        );
+        assert_eq!(headers[header::SEC_WEBSOCKET_PROTOCOL], "superchat");
+        assert_eq!(headers[header::UPGRADE], "websocket");
+        assert!(response
+            .verify_websocket_accept(b"dGhlIHNhbXBsZSBub25jZQ==")
+            .is_ok());
+        assert_eq!(
+            response.verify_websocket_accept(b"AQIDBAUGBwgJCgsMDQ4PEA=="),
+            Err(WsHandshakeError::InvalidAccept)
+        );
+
+        // Without a common subprotocol, none is selected.
+        let config = WebSocketConfig::default().with_protocols(["mqtt"]);
+        let response = Response::websocket_accept(&request, &config).unwrap();
+        assert!(!response
+            .headers()
+            .contains_key(header::SEC_WEBSOCKET_PROTOCOL));
+    }
+
+    #[test]
+    fn upgrade_headers_are_case_insensitive_lists() {
+        let mut request = rfc_request();
+        let headers = request.headers_mut();
+        headers.insert(header::UPGRADE, HeaderValue::from_static("WebSocket"));
+        headers.insert(
+            header::CONNECTION,
+            HeaderValue::from_static("keep-alive, UPGRADE"),
+        );
+        assert!(request.is_websocket_upgrade());
+    }
+
+    #[test]
+    fn invalid_upgrades_are_rejected() {
+        let config = WebSocketConfig::default();
+        let reject = |edit: fn(&mut Request)| {
+            let mut request = rfc_request();
+            edit(&mut request);
+            assert!(!request.is_websocket_upgrade());
+            Response::websocket_accept(&request, &config).unwrap_err()
+        };
+
+        let error = reject(|request| *request.method_mut() = Method::POST);
+        assert_eq!(error, WsHandshakeError::NotUpgrade);
+        let error = reject(|request| {
+            request.headers_mut().remove(header::CONNECTION);
+        });
+        assert_eq!(error.status(), StatusCode::BAD_REQUEST);
+        let error = reject(|request| {
+            request
+                .headers_mut()
+                .insert(header::UPGRADE, HeaderValue::from_static("h2c"));
+        });
+        assert_eq!(error, WsHandshakeError::NotUpgrade);
+        let error = reject(|request| {
+            request
+                .headers_mut()
+                .insert(header::SEC_WEBSOCKET_VERSION, HeaderValue::from_static("8"));
+        });
+        assert_eq!(error.status(), StatusCode::UPGRADE_REQUIRED);
+        let error = reject(|request| {
+            request.headers_mut().insert(
+                header::SEC_WEBSOCKET_KEY,
+                HeaderValue::from_static("c2hvcnQ="),
+            );
+        });
+        assert_eq!(error, WsHandshakeError::InvalidKey);
+        let error = reject(|request| {
+            request.headers_mut().remove(header::SEC_WEBSOCKET_KEY);
+        });
+        assert_eq!(error, WsHandshakeError::InvalidKey);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn client_requests_round_trip() {
+        let request = Request::websocket("ws://example.com/feed".parse().unwrap());
+        assert!(request.is_websocket_upgrade());
+        assert_eq!(request.uri().path(), "/feed");
+
+        let key = request.headers()[header::SEC_WEBSOCKET_KEY].clone();
+        assert_eq!(base64::decode(key.as_bytes()).unwrap().len(), 16);
+        let other = Request::websocket("/feed".parse().unwrap());
+        assert_ne!(other.headers()[header::SEC_WEBSOCKET_KEY], key);
+
+        let response = Response::websocket_accept(&request, &WebSocketConfig::default()).unwrap();
+        assert!(response.verify_websocket_accept(key.as_bytes()).is_ok());
+
+        let refused = Response::new(Body::empty());
+        assert_eq!(
+            refused.verify_websocket_accept(key.as_bytes()),
+            Err(WsHandshakeError::UnexpectedStatus(StatusCode::OK))
+        );
+        let mut partial =
+            Response::websocket_accept(&request, &WebSocketConfig::default()).unwrap();
+        partial.headers_mut().remove(header::UPGRADE);
+        assert_eq!(
+            partial.verify_websocket_accept(key.as_bytes()),
+            Err(WsHandshakeError::InvalidAccept)
+        );
+    }
+}
@@ -0,0 +1,836 @@
+//! RFC 6455 framing over a duplex transport.
+
+use core::{
+    fmt,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use bytes::{Buf, BufMut, BytesMut};
+use bytestr::ByteStr;
+use futures_lite::{future, io, ready, AsyncRead, AsyncWrite, Stream};
+
+use super::{CloseCode, CloseFrame, WebSocketConfig, WebSocketMessage};
+
+const CONTINUATION: u8 = 0x0;
+const TEXT: u8 = 0x1;
+const BINARY: u8 = 0x2;
+const CLOSE: u8 = 0x8;
+const PING: u8 = 0x9;
+const PONG: u8 = 0xA;
+
+const MAX_CONTROL_PAYLOAD: usize = 125;
+const READ_CHUNK: usize = 8 << 10;
+
+/// The end of the connection a [`WebSocketStream`] speaks for.
+///
+/// Clients mask the frames they send and servers do not; each side rejects frames
+/// masked the wrong way.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Role {
+    /// The end that sent the upgrade request.
+    ///
+    /// Requires the `std` feature, the source of the unpredictable masking keys that
+    /// RFC 6455 requires.
+    #[cfg(feature = "std")]
+    Client,
+    /// The end that accepted the upgrade request.
+    Server,
+}
+
+/// Errors reading or writing WebSocket frames.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum WsError {
+    /// The transport failed.
+    Io(io::Error),
+    /// The peer violated RFC 6455, or a message cannot be sent without violating it.
+    Protocol(&'static str),
+    /// An incoming frame exceeds [`WebSocketConfig::max_frame_size`], given here.
+    FrameTooLarge(usize),
+    /// An incoming message exceeds [`WebSocketConfig::max_message_size`], given here.
+    MessageTooLarge(usize),
+    /// A text message or a close reason is not valid UTF-8.
+    InvalidUtf8,
+    /// A close frame has already been sent, so no other message can follow.
+    Closed,
+}
+
+impl WsError {
+    /// Returns the close code telling the peer why the connection failed.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use http_kit::ws::{CloseCode, WsError};
+    ///
+    /// assert_eq!(WsError::MessageTooLarge(1024).close_code(), CloseCode::TooBig);
+    /// ```
+    pub const fn close_code(&self) -> CloseCode {
+        match self {
+            Self::Io(_) | Self::Closed => CloseCode::Abnormal,
+            Self::Protocol(_) => CloseCode::Protocol,
+            Self::FrameTooLarge(_) | Self::MessageTooLarge(_) => CloseCode::TooBig,
+            Self::InvalidUtf8 => CloseCode::InvalidData,
+        }
+    }
+}
+
+impl fmt::Display for WsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "websocket transport error: {error}"),
+            Self::Protocol(reason) => write!(f, "websocket protocol error: {reason}"),
+            Self::FrameTooLarge(limit) => {
+                write!(f, "websocket frame exceeds the limit of {limit} bytes")
+            }
+            Self::MessageTooLarge(limit) => {
+                write!(f, "websocket message exceeds the limit of {limit} bytes")
+            }
+            Self::InvalidUtf8 => f.write_str("websocket text is not valid UTF-8"),
+            Self::Closed => f.write_str("websocket connection is closing"),
+        }
+    }
+}
+
+impl core::error::Error for WsError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            Self::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for WsError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+struct Frame {
+    fin: bool,
+    opcode: u8,
+    payload: BytesMut,
+}
+
+/// A WebSocket connection over an upgraded transport, such as
+/// [`Upgraded`](crate::upgrade::Upgraded).
+///
+/// Incoming messages are read by polling the [`Stream`]; fragmented messages are
+/// reassembled, and control frames interleaved with their fragments are yielded as
+/// they arrive. Outgoing messages are written with [`WebSocketStream::send`], each as a
+/// single frame.
+///
+/// The stream replies to close frames, and to pings unless
+/// [`WebSocketConfig::auto_pong`] is disabled. It ends after yielding the peer's close
+/// frame, or when the transport reaches end-of-file between frames.
+///
+/// # Examples
+///
+/// ```rust
+/// use futures_lite::StreamExt;
+/// use http_kit::ws::{Role, WebSocketConfig, WebSocketMessage, WebSocketStream, WsError};
+///
+/// async fn echo(upgraded: http_kit::upgrade::Upgraded) -> Result<(), WsError> {
+///     let mut socket = WebSocketStream::new(upgraded, Role::Server, WebSocketConfig::default());
+///     while let Some(message) = socket.next().await {
+///         match message? {
+///             message @ (WebSocketMessage::Text(_) | WebSocketMessage::Binary(_)) => {
+///                 socket.send(message).await?;
+///             }
+///             _ => {}
+///         }
+///     }
+///     Ok(())
+/// }
+/// ```
+pub struct WebSocketStream<T> {
+    transport: T,
+    role: Role,
+    config: WebSocketConfig,
+    read_buf: BytesMut,
+    // Opcode and data of a fragmented message being received.
+    message: Option<(u8, BytesMut)>,
+    write_buf: BytesMut,
+    needs_flush: bool,
+    close_sent: bool,
+    done: bool,
+}
+
+impl<T> fmt::Debug for WebSocketStream<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WebSocketStream")
+            .field("role", &self.role)
+            .field("config", &self.config)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T> WebSocketStream<T> {
+    /// Wraps a transport on which the opening handshake has completed.
+    pub fn new(transport: T, role: Role, config: WebSocketConfig) -> Self {
+        Self {
+            transport,
+            role,
+            config,
+            read_buf: BytesMut::new(),
+            message: None,
+            write_buf: BytesMut::new(),
+            needs_flush: false,
+            close_sent: false,
+            done: false,
+        }
+    }
+
+    /// Returns the end of the connection the stream speaks for.
+    pub const fn role(&self) -> Role {
+        self.role
+    }
+
+    /// Returns the configuration of the stream.
+    pub const fn config(&self) -> &WebSocketConfig {
+        &self.config
+    }
+
+    /// Returns a reference to the transport.
+    pub const fn get_ref(&self) -> &T {
+        &self.transport
+    }
+
+    /// Returns a mutable reference to the transport.
+    ///
+    /// Reading or writing through it corrupts the framing.
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.transport
+    }
+
+    /// Returns the transport, dropping any data read or queued but not yet processed.
+    pub fn into_inner(self) -> T {
+        self.transport
+    }
+
+    fn mask(&self) -> Option<[u8; 4]> {
+        match self.role {
+            #[cfg(feature = "std")]
+            Role::Client => {
+                let nonce = super::handshake::nonce();
+                Some([nonce[0], nonce[1], nonce[2], nonce[3]])
+            }
+            Role::Server => None,
+        }
+    }
+
+    fn queue(&mut self, opcode: u8, payload: &[u8]) {
+        let mask = self.mask();
+        encode_frame(&mut self.write_buf, true, opcode, payload, mask);
+    }
+
+    fn fail(&mut self, error: WsError) -> WsError {
+        self.done = true;
+        self.write_buf.clear();
+        error
+    }
+
+    // Processes the complete frames of `read_buf` until a message is complete.
+    fn decode(&mut self) -> Result<Option<WebSocketMessage>, WsError> {
+        while let Some(frame) = self.next_frame()? {
+            if let Some(message) = self.handle(frame)? {
+                return Ok(Some(message));
+            }
+        }
+        Ok(None)
+    }
+
+    // Parses the next complete frame of `read_buf`, if any.
+    fn next_frame(&mut self) -> Result<Option<Frame>, WsError> {
+        let buf = &self.read_buf[..];
+        if buf.len() < 2 {
+            return Ok(None);
+        }
+        let fin = buf[0] & 0x80 != 0;
+        if buf[0] & 0x70 != 0 {
+            return Err(WsError::Protocol("reserved bits set without an extension"));
+        }
+        let opcode = buf[0] & 0x0F;
+        let masked = buf[1] & 0x80 != 0;
+        let (len, mut header_len) = match buf[1] & 0x7F {
+            126 if buf.len() < 4 => return Ok(None),
+            126 => (u64::from(u16::from_be_bytes([buf[2], buf[3]])), 4),
+            127 if buf.len() < 10 => return Ok(None),
+            127 => {
+                let mut len = [0u8; 8];
+                len.copy_from_slice(&buf[2..10]);
+                (u64::from_be_bytes(len), 10)
+            }
+            len => (u64::from(len), 2),
+        };
+
+        match opcode {
+            CONTINUATION | TEXT | BINARY => {}
+            CLOSE | PING | PONG if !fin => {
+                return Err(WsError::Protocol("fragmented control frame"));
+            }
+            CLOSE | PING | PONG if len > MAX_CONTROL_PAYLOAD as u64 => {
+                return Err(WsError::Protocol("control frame longer than 125 bytes"));
+            }
+            CLOSE | PING | PONG => {}
+            _ => return Err(WsError::Protocol("unknown opcode")),
+        }
+        match (self.role, masked) {
+            (Role::Server, false) => return Err(WsError::Protocol("unmasked client frame")),
+            #[cfg(feature = "std")]
+            (Role::Client, true) => return Err(WsError::Protocol("masked server frame")),
+            _ => {}
+        }
+        if len >> 63 != 0 {
+            return Err(WsError::Protocol("frame length with the high bit set"));
+        }
+        if let Some(limit) = self.config.max_frame_size {
+            if len > limit as u64 {
+                return Err(WsError::FrameTooLarge(limit));
+            }
+        }
+        let len = usize::try_from(len).map_err(|_| WsError::FrameTooLarge(usize::MAX))?;
+        if let (Some(limit), CONTINUATION | TEXT | BINARY) = (self.config.max_message_size, opcode)
+        {
+            let received = self.message.as_ref().map_or(0, |(_, data)| data.len());
+            if received.saturating_add(len) > limit {
+                return Err(WsError::MessageTooLarge(limit));
+            }
+        }
+
+        let mut mask = None;
+        if masked {
+            if buf.len() < header_len + 4 {
+                return Ok(None);
+            }
+            mask = Some([
+                buf[header_len],
+                buf[header_len + 1],
+                buf[header_len + 2],
+                buf[header_len + 3],
+            ]);
+            header_len += 4;
+        }
+        let arrived = buf.len() - header_len;
+        if arrived < len {
+            // The declared length is not trusted with an allocation: the buffer at most
+            // doubles ahead of the data that has arrived.
+            let missing = len - arrived;
+            self.read_buf
+                .reserve(missing.min(buf.len().max(READ_CHUNK)));
+            return Ok(None);
+        }
+
+        self.read_buf.advance(header_len);
+        let mut payload = self.read_buf.split_to(len);
+        if let Some(mask) = mask {
+            apply_mask(&mut payload, mask);
+        }
+        Ok(Some(Frame {
+            fin,
+            opcode,
+            payload,
+        }))
+    }
+
+    // Processes the frame, returning a message once one is complete.
+    fn handle(&mut self, frame: Frame) -> Result<Option<WebSocketMessage>, WsError> {
+        match frame.opcode {
+            TEXT | BINARY if self.message.is_some() => Err(WsError::Protocol(
+                "new message before the previous one finished",
+            )),
+            TEXT | BINARY if frame.fin => data_message(frame.opcode, frame.payload).map(Some),
+            TEXT | BINARY => {
+                self.message = Some((frame.opcode, frame.payload));
+                Ok(None)
+            }
+            CONTINUATION => {
+                let Some((_, data)) = &mut self.message else {
+                    return Err(WsError::Protocol("continuation without a message"));
+                };
+                data.unsplit(frame.payload);
+                match self.message.take() {
+                    Some((opcode, data)) if frame.fin => data_message(opcode, data).map(Some),
+                    message => {
+                        self.message = message;
+                        Ok(None)
+                    }
+                }
+            }
+            PING => {
+                if self.config.auto_pong && !self.close_sent {
+                    self.queue(PONG, &frame.payload);
+                }
+                Ok(Some(WebSocketMessage::Ping(frame.payload.freeze())))
+            }
+            PONG => Ok(Some(WebSocketMessage::Pong(frame.payload.freeze()))),
+            _ => {
+                let close = close_frame(&frame.payload)?;
+                if !self.close_sent {
+                    // Echo the status code, as RFC 6455, section 5.5.1 suggests.
+                    self.queue(CLOSE, &frame.payload[..frame.payload.len().min(2)]);
+                    self.close_sent = true;
+                }
+                self.done = true;
+                Ok(Some(WebSocketMessage::Close(close)))
+            }
+        }
+    }
+}
+
+impl<T: AsyncRead + AsyncWrite + Unpin> WebSocketStream<T> {
+    /// Sends a message as a single frame, after any pending pong or close reply.
+    ///
+    /// Sending a [`WebSocketMessage::Close`] starts the closing handshake; the stream
+    /// then yields the remaining messages until the peer's close frame.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`WsError::Closed`] if a close frame has already been sent,
+    /// [`WsError::Protocol`] for control payloads over 125 bytes or close frames with a
+    /// reserved code, and [`WsError::Io`] if the transport fails.
+    pub async fn send(&mut self, message: WebSocketMessage) -> Result<(), WsError> {
+        if self.close_sent {
+            return Err(WsError::Closed);
+        }
+        match message {
+            WebSocketMessage::Text(text) => self.queue(TEXT, text.as_bytes()),
+            WebSocketMessage::Binary(data) => self.queue(BINARY, &data),
+            WebSocketMessage::Ping(data) | WebSocketMessage::Pong(data)
+                if data.len() > MAX_CONTROL_PAYLOAD =>
+            {
+                return Err(WsError::Protocol("control frame longer than 125 bytes"));
+            }
+            WebSocketMessage::Ping(data) => self.queue(PING, &data),
+            WebSocketMessage::Pong(data) => self.queue(PONG, &data),
+            WebSocketMessage::Close(None) => {
+                self.queue(CLOSE, &[]);
+                self.close_sent = true;
+            }
+            WebSocketMessage::Close(Some(frame)) => {
+                if !frame.code.is_allowed() {
+                    return Err(WsError::Protocol("reserved close code"));
+                }
+                if frame.reason.len() > MAX_CONTROL_PAYLOAD - 2 {
+                    return Err(WsError::Protocol("control frame longer than 125 bytes"));
+                }
+                let mut payload = BytesMut::with_capacity(2 + frame.reason.len());
+                payload.put_u16(frame.code.into_u16());
+                payload.put_slice(frame.reason.as_bytes());
+                self.queue(CLOSE, &payload);
+                self.close_sent = true;
+            }
+        }
+        future::poll_fn(|cx| self.poll_write_pending(cx)).await?;
+        Ok(())
+    }
+
+    fn poll_write_pending(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        while !self.write_buf.is_empty() {
+            let written = ready!(Pin::new(&mut self.transport).poll_write(cx, &self.write_buf))?;
+            if written == 0 {
+                return Poll::Ready(Err(io::ErrorKind::WriteZero.into()));
+            }
+            self.write_buf.advance(written);
+            self.needs_flush = true;
+        }
+        if self.needs_flush {
+            ready!(Pin::new(&mut self.transport).poll_flush(cx))?;
+            self.needs_flush = false;
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_fill(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<usize>> {
+        let len = self.read_buf.len();
+        self.read_buf.resize(len + READ_CHUNK, 0);
+        let result = Pin::new(&mut self.transport).poll_read(cx, &mut self.read_buf[len..]);
+        let read = match result {
+            Poll::Ready(Ok(read)) => read,
+            _ => 0,
+        };
+        self.read_buf.truncate(len + read);
+        result
+    }
+}
+
+impl<T: AsyncRead + AsyncWrite + Unpin> Stream for WebSocketStream<T> {
+    type Item = Result<WebSocketMessage, WsError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            // Pongs and close replies are written while the stream is read.
+            match this.poll_write_pending(cx) {
+                Poll::Ready(Err(error)) => return Poll::Ready(Some(Err(this.fail(error.into())))),
+                Poll::Pending if this.done => return Poll::Pending,
+                _ => {}
+            }
+            if this.done {
+                return Poll::Ready(None);
+            }
+
+            match this.decode() {
+                Ok(Some(message)) => {
+                    // Start writing a pong or a close reply queued by the frame.
+                    if let Poll::Ready(Err(error)) = this.poll_write_pending(cx) {
+                        return Poll::Ready(Some(Err(this.fail(error.into()))));
+                    }
+                    return Poll::Ready(Some(Ok(message)));
+                }
+                Ok(None) => {}
+                Err(error) => return Poll::Ready(Some(Err(this.fail(error)))),
+            }
+
+            match ready!(this.poll_fill(cx)) {
+                Ok(0) if this.read_buf.is_empty() && this.message.is_none() => this.done = true,
+                Ok(0) => {
+                    let error = io::Error::from(io::ErrorKind::UnexpectedEof);
+                    return Poll::Ready(Some(Err(this.fail(error.into()))));
+                }
+                Ok(_) => {}
+                Err(error) => return Poll::Ready(Some(Err(this.fail(error.into())))),
+            }
+        }
+    }
+}
+
+fn data_message(opcode: u8, data: BytesMut) -> Result<WebSocketMessage, WsError> {
+    if opcode == TEXT {
+        let text = ByteStr::from_utf8(data.freeze()).map_err(|_| WsError::InvalidUtf8)?;
+        Ok(WebSocketMessage::Text(text))
+    } else {
+        Ok(WebSocketMessage::Binary(data.freeze()))
+    }
+}
+
+fn close_frame(payload: &[u8]) -> Result<Option<CloseFrame>, WsError> {
+    match payload {
+        [] => Ok(None),
+        [_] => Err(WsError::Protocol("close frame with a one-byte payload")),
+        [high, low, reason @ ..] => {
+            let code = CloseCode::from_u16(u16::from_be_bytes([*high, *low]));
+            if !code.is_allowed() {
+                return Err(WsError::Protocol("reserved close code"));
+            }
+            let reason = ByteStr::from_utf8(reason.to_vec()).map_err(|_| WsError::InvalidUtf8)?;
+            Ok(Some(CloseFrame { code, reason }))
+        }
+    }
+}
+
+fn apply_mask(payload: &mut [u8], mask: [u8; 4]) {
+    for (byte, key) in payload.iter_mut().zip(mask.iter().cycle()) {
+        *byte ^= key;
+    }
+}
+
+fn encode_frame(buf: &mut BytesMut, fin: bool, opcode: u8, payload: &[u8], mask: Option<[u8; 4]>) {
+    let mask_bit = if mask.is_some() { 0x80 } else { 0 };
+    buf.reserve(14 + payload.len());
+    buf.put_u8(if fin { 0x80 } else { 0 } | opcode);
+    match payload.len() {
+        len @ 0..=125 => buf.put_u8(mask_bit | len as u8),
+        len @ 126..=0xFFFF => {
+            buf.put_u8(mask_bit | 126);
+            buf.put_u16(len as u16);
+        }
+        len => {
+            buf.put_u8(mask_bit | 127);
+            buf.put_u64(len as u64);
+        }
+    }
+    let start = buf.len();
+    if let Some(mask) = mask {
+        buf.put_slice(&mask);
+        buf.put_slice(payload);
+        apply_mask(&mut buf[start + 4..], mask);
+    } else {
+        buf.put_slice(payload);
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use alloc::{boxed::Box, vec, vec::Vec};
+    use bytes::Bytes;
+    use futures_lite::StreamExt;
+
+    // One end of an in-memory duplex pipe.
+    struct Pipe {
+        tx: async_channel::Sender<Vec<u8>>,
+        rx: Pin<Box<async_channel::Receiver<Vec<u8>>>>,
+        pending: Bytes,
+    }
+
+    fn duplex() -> (Pipe, Pipe) {
+        let (a_tx, a_rx) = async_channel::unbounded();
+        let (b_tx, b_rx) = async_channel::unbounded();
+        let pipe = |tx, rx| Pipe {
+            tx,
+            rx: Box::pin(rx),
+            pending: Bytes::new(),
+        };
+        (pipe(a_tx, b_rx), pipe(b_tx, a_rx))
+    }
+
+    impl AsyncRead for Pipe {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut [u8],
+        ) -> Poll<io::Result<usize>> {
+            while self.pending.is_empty() {
+                match ready!(self.rx.as_mut().poll_next(cx)) {
+                    Some(chunk) => self.pending = chunk.into(),
+                    None => return Poll::Ready(Ok(0)),
+                }
+            }
+            let len = buf.len().min(self.pending.len());
+            buf[..len].copy_from_slice(&self.pending.split_to(len));
+            Poll::Ready(Ok(len))
+        }
+    }
+
+    impl AsyncWrite for Pipe {
+        fn poll_write(
+            self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            let sent = self.tx.try_send(buf.to_vec());
+            Poll::Ready(
+                sent.map(|()| buf.len())
+                    .map_err(|_| io::ErrorKind::BrokenPipe.into()),
+            )
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            self.tx.close();
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    fn connect(config: WebSocketConfig) -> (WebSocketStream<Pipe>, WebSocketStream<Pipe>) {
+        let (client, server) = duplex();
+        (
+            WebSocketStream::new(client, Role::Client, config.clone()),
+            WebSocketStream::new(server, Role::Server, config),
+        )
+    }
+
+    // Final flag, opcode and payload of a raw frame.
+    type RawFrame<'a> = (bool, u8, &'a [u8]);
+
+    // Writes raw frames from the client, masked with a fixed key.
+    fn write_raw(client: &WebSocketStream<Pipe>, frames: &[RawFrame<'_>]) {
+        let mut buf = BytesMut::new();
+        for &(fin, opcode, payload) in frames {
+            encode_frame(&mut buf, fin, opcode, payload, Some([1, 2, 3, 4]));
+        }
+        client.get_ref().tx.try_send(buf.to_vec()).unwrap();
+    }
+
+    async fn next(socket: &mut WebSocketStream<Pipe>) -> WebSocketMessage {
+        socket.next().await.unwrap().unwrap()
+    }
+
+    #[tokio::test]
+    async fn messages_round_trip() {
+        let (mut client, mut server) = connect(WebSocketConfig::default());
+        let large = vec![7u8; 70_000];
+        let messages = [
+            WebSocketMessage::text("hello"),
+            WebSocketMessage::text(""),
+            WebSocketMessage::binary(vec![0u8, 159, 146, 150]),
+            WebSocketMessage::binary(vec![1u8; 300]),
+            WebSocketMessage::binary(large),
+        ];
+        for message in messages {
+            client.send(message.clone()).await.unwrap();
+            let received = next(&mut server).await;
+            assert_eq!(received, message);
+            server.send(received).await.unwrap();
+            assert_eq!(next(&mut client).await, message);
+        }
+    }
+
+    #[tokio::test]
+    async fn fragments_are_reassembled_around_control_frames() {
+        let (mut client, mut server) = connect(WebSocketConfig::default());
+        write_raw(
+            &client,
+            &[
+                (false, TEXT, "frag".as_bytes()),
+                (false, CONTINUATION, "men".as_bytes()),
+                (true, PING, b"are you there?"),
+                (true, CONTINUATION, "ted ✓".as_bytes()),
+            ],
+        );
+        assert_eq!(
+            next(&mut server).await,
+            WebSocketMessage::ping("are you there?")
+        );
+        assert_eq!(
+            next(&mut server).await,
+            WebSocketMessage::text("fragmented ✓")
+        );
+        // The ping was answered automatically.
+        assert_eq!(
+            next(&mut client).await,
+            WebSocketMessage::pong("are you there?")
+        );
+
+        write_raw(&client, &[(true, CONTINUATION, b"orphan")]);
+        assert!(matches!(
+            server.next().await.unwrap(),
+            Err(WsError::Protocol(_))
+        ));
+        assert!(server.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn pongs_can_be_left_to_the_application() {
+        let (mut client, mut server) = connect(WebSocketConfig::default().with_auto_pong(false));
+        client.send(WebSocketMessage::ping("1")).await.unwrap();
+        client.send(WebSocketMessage::text("after")).await.unwrap();
+        assert_eq!(next(&mut server).await, WebSocketMessage::ping("1"));
+        assert_eq!(next(&mut server).await, WebSocketMessage::text("after"));
+        server.send(WebSocketMessage::text("reply")).await.unwrap();
+        assert_eq!(next(&mut client).await, WebSocketMessage::text("reply"));
+    }
+
+    #[test]
+    fn declared_lengths_are_not_reserved_up_front() {
+        let config = WebSocketConfig::default()
+            .with_max_frame_size(None)
+            .with_max_message_size(None);
+        let (_client, mut server) = connect(config);
+        server.read_buf.extend_from_slice(&[0x82, 0xFF]);
+        server
+            .read_buf
+            .extend_from_slice(&(1u64 << 62).to_be_bytes());
+        server.read_buf.extend_from_slice(&[1, 2, 3, 4, 0xAA]);
+        assert!(server.next_frame().unwrap().is_none());
+        assert!(server.read_buf.capacity() <= 2 * READ_CHUNK);
+    }
+
+    #[tokio::test]
+    async fn oversized_messages_fail() {
+        let config = WebSocketConfig::default()
+            .with_max_frame_size(Some(16))
+            .with_max_message_size(Some(24));
+        let (mut client, mut server) = connect(config.clone());
+        client
+            .send(WebSocketMessage::binary(vec![0u8; 17]))
+            .await
+            .unwrap();
+        let error = server.next().await.unwrap().unwrap_err();
+        assert!(matches!(error, WsError::FrameTooLarge(16)));
+        assert_eq!(error.close_code(), CloseCode::TooBig);
+
+        let (client, mut server) = connect(config);
+        write_raw(
+            &client,
+            &[(false, BINARY, &[0; 16]), (true, CONTINUATION, &[0; 16])],
+        );
+        assert!(matches!(
+            server.next().await.unwrap(),
+            Err(WsError::MessageTooLarge(24))
+        ));
+    }
+
+    #[tokio::test]
+    async fn invalid_frames_fail() {
+        let cases: [(&[RawFrame<'_>], &str); 4] = [
+            (&[(true, 0x3, b"")], "unknown opcode"),
+            (&[(false, PING, b"")], "fragmented control frame"),
+            (
+                &[(true, PONG, &[0; 126])],
+                "control frame longer than 125 bytes",
+            ),
+            (
+                &[(false, TEXT, b"a"), (true, BINARY, b"b")],
+                "new message before the previous one finished",
+            ),
+        ];
+        for (frames, reason) in cases {
+            let (client, mut server) = connect(WebSocketConfig::default());
+            write_raw(&client, frames);
+            assert!(
+                matches!(server.next().await.unwrap(), Err(WsError::Protocol(r)) if r == reason),
+                "{reason}"
+            );
+        }
+
+        let (client, mut server) = connect(WebSocketConfig::default());
+        write_raw(&client, &[(true, TEXT, b"\xff")]);
+        assert!(matches!(
+            server.next().await.unwrap(),
+            Err(WsError::InvalidUtf8)
+        ));
+
+        // Servers do not mask their frames, so a client receiving one rejects it.
+        let (mut client, server) = connect(WebSocketConfig::default());
+        let mut buf = BytesMut::new();
+        encode_frame(&mut buf, true, TEXT, b"hi", Some([9; 4]));
+        server.get_ref().tx.try_send(buf.to_vec()).unwrap();
+        assert!(matches!(
+            client.next().await.unwrap(),
+            Err(WsError::Protocol("masked server frame"))
+        ));
+    }
+
+    #[tokio::test]
+    async fn closing_handshake() {
+        let (mut client, mut server) = connect(WebSocketConfig::default());
+        client
+            .send(WebSocketMessage::close_with(CloseCode::Away, "bye"))
+            .await
+            .unwrap();
+        assert!(matches!(
+            client.send(WebSocketMessage::text("late")).await,
+            Err(WsError::Closed)
+        ));
+
+        assert_eq!(
+            next(&mut server).await,
+            WebSocketMessage::close_with(CloseCode::Away, "bye")
+        );
+        assert!(server.next().await.is_none());
+        // The server echoed the status code.
+        assert_eq!(
+            next(&mut client).await,
+            WebSocketMessage::close_with(CloseCode::Away, "")
+        );
+        assert!(client.next().await.is_none());
+
+        let (mut client, _server) = connect(WebSocketConfig::default());
+        let invalid = WebSocketMessage::close_with(CloseCode::NoStatus, "");
+        assert!(matches!(
+            client.send(invalid).await,
+            Err(WsError::Protocol("reserved close code"))
+        ));
+    }
+
+    #[tokio::test]
+    async fn end_of_file_mid_frame_fails() {
+        let (client, mut server) = connect(WebSocketConfig::default());
+        client.get_ref().tx.try_send(vec![0x81, 0x85, 1]).unwrap();
+        drop(client);
+        let error = server.next().await.unwrap().unwrap_err();
+        assert!(matches!(error, WsError::Io(ref e) if e.kind() == io::ErrorKind::UnexpectedEof));
+
+        let (client, mut server) = connect(WebSocketConfig::default());
+        drop(client);
+        assert!(server.next().await.is_none());
+    }
+}